@@ -5,7 +5,7 @@
 //! notifying them when they may make progress.
 
 use std::ops::{Deref, DerefMut};
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{self, Ordering};
 use std::task::{Context, Waker};
 
 pub use crate::slab::Index;
@@ -41,6 +41,10 @@ pub struct AsyncStdWakerSet {
     inner: Mutex<Inner>,
 }
 
+impl locker::Init for AsyncStdWakerSet {
+    const INIT: Self = Self::new();
+}
+
 impl AsyncStdWakerSet {
     /// Creates a new `AsyncStdWakerSet`.
     #[inline]
@@ -66,14 +70,27 @@ impl AsyncStdWakerSet {
         let mut inner = &mut *self.lock();
         let mut notified = false;
 
+        let limit = match n {
+            Notify::Batch(max) => max,
+            _ => usize::MAX,
+        };
+
+        // The number of entries that were notified, but haven't removed themselves yet.
+        let mut in_flight = inner.entries.len() - inner.notifiable;
+
+        if in_flight >= limit {
+            return false;
+        }
+
         for (_, opt_waker) in inner.entries.iter_mut() {
             // If there is no waker in this entry, that means it was already woken.
             if let Some(w) = opt_waker.take() {
                 w.wake();
                 inner.notifiable -= 1;
+                in_flight += 1;
                 notified = true;
 
-                if n == Notify::One {
+                if n == Notify::One || in_flight >= limit {
                     break;
                 }
             }
@@ -97,8 +114,14 @@ impl AsyncStdWakerSet {
 impl crate::WakerSet for AsyncStdWakerSet {
     type Index = Index;
 
+    /// Checks if there are no entries, without locking the set.
+    #[inline]
     fn is_empty(&self) -> bool {
-        self.inner.lock().entries.is_empty()
+        // Pairs with the fence in `insert()`.
+        atomic::fence(Ordering::SeqCst);
+
+        // Both flags are clear exactly when there are no entries.
+        self.inner.raw().inner().tag(Ordering::Relaxed) == 0
     }
 
     /// Inserts a waker for a blocked operation and returns a key associated with it.
@@ -109,6 +132,10 @@ impl crate::WakerSet for AsyncStdWakerSet {
 
         let key = inner.entries.insert(Some(w));
         inner.notifiable += 1;
+        drop(inner);
+
+        // Make the new entry visible to `is_empty()` before the blocked operation retries.
+        atomic::fence(Ordering::SeqCst);
         key
     }
 
@@ -172,6 +199,20 @@ impl crate::WakerSet for AsyncStdWakerSet {
             false
         }
     }
+
+    /// Notifies blocked operations until up to `max` of them have been notified.
+    ///
+    /// Returns `true` if at least one operation was notified.
+    #[inline]
+    fn notify_batch(&self, max: usize) -> bool {
+        if max <= 1 {
+            self.notify_any()
+        } else if self.flag() & NOTIFIABLE != 0 {
+            self.notify(Notify::Batch(max))
+        } else {
+            false
+        }
+    }
 }
 
 /// A guard holding a `AsyncStdWakerSet` locked.
@@ -223,6 +264,66 @@ enum Notify {
     Any,
     /// Notify one additional entry.
     One,
+    /// Notify entries until the given number of entries are notified.
+    Batch(usize),
     /// Notify all entries.
     All,
 }
+
+#[test]
+fn notify_batch() {
+    use crate::conformance::Count;
+    use crate::WakerSet;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    let count = Arc::new(Count(AtomicUsize::new(0)));
+    let waker = Waker::from(count.clone());
+    let mut cx = Context::from_waker(&waker);
+    let woken = || count.0.load(Ordering::SeqCst);
+
+    let set = AsyncStdWakerSet::new();
+    let keys: Vec<_> = (0..5).map(|_| set.insert(&mut cx)).collect();
+
+    assert!(set.notify_batch(3));
+    assert_eq!(woken(), 3);
+
+    // the woken entries haven't removed themselves yet, so they count against the batch
+    assert!(!set.notify_batch(3));
+    assert_eq!(woken(), 3);
+
+    set.remove(keys[0]);
+    assert!(set.notify_batch(3));
+    assert_eq!(woken(), 4);
+}
+
+#[test]
+fn is_empty_without_locking() {
+    use crate::WakerSet;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+
+    let set = AsyncStdWakerSet::new();
+    assert!(set.is_empty());
+
+    let key = set.insert(&mut cx);
+    assert!(set.notify_any());
+
+    // a notified entry that hasn't removed itself yet still counts
+    let _lock = set.inner.lock();
+    assert!(!set.is_empty());
+    drop(_lock);
+
+    set.remove(key);
+    let _lock = set.inner.lock();
+    assert!(set.is_empty());
+}
@@ -0,0 +1,215 @@
+//! Cancelling lock operations
+//!
+//! The `*_cancellable` lock methods take any `Future + Unpin` as a cancellation signal,
+//! and resolve to `Err(Cancelled)` if it completes before the lock is acquired.
+//! [`CancelToken`] is a minimal signal that can be shared between many operations.
+
+use crate::notify::{OnceFlag, WaitForSet};
+use crate::WakerSet;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The error returned when a lock operation was cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the lock operation was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// A token that cancels all of the operations waiting on it once it is cancelled
+pub struct CancelToken<W> {
+    flag: OnceFlag<W>,
+}
+
+impl<W: locker::Init> locker::Init for CancelToken<W> {
+    const INIT: Self = Self::from_waker_set(locker::Init::INIT);
+}
+
+impl<W: locker::Init> Default for CancelToken<W> {
+    #[inline]
+    fn default() -> Self {
+        locker::Init::INIT
+    }
+}
+
+impl<W> CancelToken<W> {
+    /// Create a new token that isn't cancelled, which blocks tasks in `waker_set`
+    #[inline]
+    pub const fn from_waker_set(waker_set: W) -> Self {
+        Self {
+            flag: OnceFlag::from_waker_set(waker_set),
+        }
+    }
+
+    /// Check if the token was cancelled
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.is_set()
+    }
+}
+
+impl<W: WakerSet> CancelToken<W> {
+    /// Cancel all operations waiting on this token, and all future operations
+    ///
+    /// Returns false if the token was already cancelled
+    #[inline]
+    pub fn cancel(&self) -> bool {
+        self.flag.set()
+    }
+
+    /// A future that completes once this token is cancelled
+    #[inline]
+    pub fn cancelled(&self) -> WaitForSet<'_, W> {
+        self.flag.wait()
+    }
+}
+
+/// A future that resolves to `Err(Cancelled)` if `cancel` completes before `future`
+///
+/// It is cancelled as soon as `cancel` completes, `cancel` is polled before `future`, so this
+/// wins if both are ready. After that `future` isn't polled again, and it is deregistered from
+/// the lock's `WakerSet` once the `Cancellable` is dropped.
+pub struct Cancellable<F, C> {
+    future: F,
+    cancel: C,
+}
+
+impl<F, C> Cancellable<F, C> {
+    #[inline]
+    pub fn new(future: F, cancel: C) -> Self {
+        Self { future, cancel }
+    }
+}
+
+impl<F: Future, C: Future + Unpin> Future for Cancellable<F, C> {
+    type Output = Result<F::Output, Cancelled>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        // SAFETY: `future` is structurally pinned, it is never moved out of `Cancellable`
+        let this = unsafe { self.get_unchecked_mut() };
+
+        if Pin::new(&mut this.cancel).poll(ctx).is_ready() {
+            return Poll::Ready(Err(Cancelled));
+        }
+
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        future.poll(ctx).map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CancelToken, Cancelled};
+    use crate::async_std::AsyncStdWakerSet;
+    use crate::conformance::Count;
+
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+
+    type Mutex<T> = crate::mutex::Mutex<locker::mutex::spin::SpinLock, AsyncStdWakerSet, T>;
+    type RwLock<T> = crate::rwlock::RwLock<locker::rwlock::spin::SpinLock, AsyncStdWakerSet, T>;
+
+    #[test]
+    fn cancel_token() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let token = CancelToken::<AsyncStdWakerSet>::default();
+        assert!(!token.is_cancelled());
+
+        let mut cancelled = token.cancelled();
+        assert!(Pin::new(&mut cancelled).poll(&mut cx).is_pending());
+
+        assert!(token.cancel());
+        assert!(!token.cancel());
+        assert!(token.is_cancelled());
+        assert_eq!(count.0.load(Ordering::SeqCst), 1);
+
+        assert!(Pin::new(&mut cancelled).poll(&mut cx).is_ready());
+        assert!(Pin::new(&mut token.cancelled()).poll(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn lock_cancellable() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+        let woken = || count.0.load(Ordering::SeqCst);
+
+        let mutex = Mutex::new(0);
+        let token = CancelToken::<AsyncStdWakerSet>::default();
+
+        // the lock is free, so the token doesn't matter
+        let mut lock = Box::pin(mutex.lock_cancellable(token.cancelled()));
+        let guard = match lock.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(guard)) => guard,
+            _ => panic!("could not acquire a free lock"),
+        };
+        drop(lock);
+
+        let mut lock = Box::pin(mutex.lock_cancellable(token.cancelled()));
+        assert!(lock.as_mut().poll(&mut cx).is_pending());
+
+        token.cancel();
+        assert_eq!(woken(), 1);
+        assert!(matches!(
+            lock.as_mut().poll(&mut cx),
+            Poll::Ready(Err(Cancelled))
+        ));
+
+        // the cancelled operation is no longer registered, so it isn't woken on unlock
+        drop(guard);
+        assert_eq!(woken(), 1);
+
+        // a cancelled token cancels new operations that would wait
+        let _guard = mutex.try_lock().unwrap();
+        let mut lock = Box::pin(mutex.lock_cancellable(token.cancelled()));
+        assert!(matches!(
+            lock.as_mut().poll(&mut cx),
+            Poll::Ready(Err(Cancelled))
+        ));
+    }
+
+    #[test]
+    fn rwlock_cancellable() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+        let woken = || count.0.load(Ordering::SeqCst);
+
+        let rwlock = RwLock::new(0);
+        let token = CancelToken::<AsyncStdWakerSet>::default();
+
+        let guard = rwlock.try_write().unwrap();
+        let mut read = Box::pin(rwlock.read_cancellable(token.cancelled()));
+        let mut write = Box::pin(rwlock.write_cancellable(token.cancelled()));
+        assert!(read.as_mut().poll(&mut cx).is_pending());
+        assert!(write.as_mut().poll(&mut cx).is_pending());
+
+        // one wake up for each cancelled operation
+        token.cancel();
+        assert_eq!(woken(), 2);
+        assert!(matches!(
+            read.as_mut().poll(&mut cx),
+            Poll::Ready(Err(Cancelled))
+        ));
+        assert!(matches!(
+            write.as_mut().poll(&mut cx),
+            Poll::Ready(Err(Cancelled))
+        ));
+
+        drop(guard);
+        assert_eq!(woken(), 2);
+    }
+}
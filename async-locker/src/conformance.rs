@@ -0,0 +1,422 @@
+//! A conformance suite for [`WakerSet`] implementations
+//!
+//! The async locks rely on a few guarantees from their [`WakerSet`]:
+//!
+//! * `notify_any` and `notify_all` wake up blocked operations that are still in the set
+//! * removed operations are never woken up
+//! * cancelling an operation that was already notified passes the notification on to
+//!   another blocked operation, otherwise that wakeup is lost
+//!
+//! The checks in this module test these guarantees directly, and by running contended
+//! async mutexes and rwlocks on [`MockExecutor`], a deterministic executor that only polls
+//! tasks after they were woken. So a lost wakeup shows up as a panic instead of a hang,
+//! and a waker set can be certified without depending on a runtime.
+//!
+//! [`wakerset_conformance!`](crate::wakerset_conformance) generates a test for every check
+//!
+//! ```
+//! async_locker::wakerset_conformance!(async_std, async_locker::async_std::AsyncStdWakerSet);
+//! ```
+
+use crate::WakerSet;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+type Mutex<W, T> = crate::mutex::Mutex<locker::mutex::spin::SpinLock, W, T>;
+type RwLock<W, T> = crate::rwlock::RwLock<locker::rwlock::spin::SpinLock, W, T>;
+
+/// Generate a test module named `$name`, which runs every check in
+/// [`conformance`](crate::conformance) against the waker set `$waker_set`
+#[macro_export]
+macro_rules! wakerset_conformance {
+    ($name:ident, $waker_set:ty) => {
+        mod $name {
+            #[test]
+            fn notify() {
+                $crate::conformance::notify::<$waker_set>()
+            }
+
+            #[test]
+            fn remove() {
+                $crate::conformance::remove::<$waker_set>()
+            }
+
+            #[test]
+            fn cancel() {
+                $crate::conformance::cancel::<$waker_set>()
+            }
+
+            #[test]
+            fn notify_batch() {
+                $crate::conformance::notify_batch::<$waker_set>()
+            }
+
+            #[test]
+            fn mutex() {
+                $crate::conformance::mutex::<$waker_set>()
+            }
+
+            #[test]
+            fn mutex_cancel() {
+                $crate::conformance::mutex_cancel::<$waker_set>()
+            }
+
+            #[test]
+            fn rwlock() {
+                $crate::conformance::rwlock::<$waker_set>()
+            }
+        }
+    };
+}
+
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Flag {
+    fn new() -> (Arc<Self>, Waker) {
+        let flag = Arc::new(Self(AtomicBool::new(false)));
+        let waker = Waker::from(flag.clone());
+        (flag, waker)
+    }
+
+    fn take(&self) -> bool {
+        self.0.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// Counts how many times it was woken, for the unit tests of this crate
+#[cfg(test)]
+pub(crate) struct Count(pub(crate) AtomicUsize);
+
+#[cfg(test)]
+impl Wake for Count {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+struct Task<'a> {
+    future: Option<Pin<Box<dyn Future<Output = ()> + 'a>>>,
+    flag: Arc<Flag>,
+    waker: Waker,
+}
+
+/// A deterministic single-threaded executor
+///
+/// Tasks are only polled after they are woken, and they are always polled in the
+/// order that they were spawned in, so every run of a test does the same thing.
+#[derive(Default)]
+pub struct MockExecutor<'a> {
+    tasks: Vec<Task<'a>>,
+}
+
+impl<'a> MockExecutor<'a> {
+    /// Create a new executor without any tasks
+    pub fn new() -> Self {
+        Self { tasks: Vec::new() }
+    }
+
+    /// Add a task, which is woken so that it is polled on the next run
+    pub fn spawn(&mut self, future: impl Future<Output = ()> + 'a) -> usize {
+        let (flag, waker) = Flag::new();
+        waker.wake_by_ref();
+
+        self.tasks.push(Task {
+            future: Some(Box::pin(future)),
+            flag,
+            waker,
+        });
+
+        self.tasks.len() - 1
+    }
+
+    /// Check if the task has been woken since it was last polled
+    pub fn is_woken(&self, task: usize) -> bool {
+        self.tasks[task].flag.0.load(Ordering::SeqCst)
+    }
+
+    /// Check if the task ran to completion or was cancelled
+    pub fn is_done(&self, task: usize) -> bool {
+        self.tasks[task].future.is_none()
+    }
+
+    /// Poll the task once, even if it wasn't woken, and return true if it completed
+    pub fn poll(&mut self, task: usize) -> bool {
+        let task = &mut self.tasks[task];
+        task.flag.take();
+
+        let future = match task.future.as_mut() {
+            Some(future) => future,
+            None => return true,
+        };
+
+        let mut cx = Context::from_waker(&task.waker);
+
+        if future.as_mut().poll(&mut cx).is_ready() {
+            task.future = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Drop the task without running it to completion
+    pub fn cancel(&mut self, task: usize) {
+        self.tasks[task].future = None;
+    }
+
+    /// Poll the woken tasks until all tasks are done
+    ///
+    /// # Panic
+    ///
+    /// Panics if there are unfinished tasks, but none of them were woken,
+    /// because they will never make progress
+    pub fn run(&mut self) {
+        loop {
+            let mut polled = false;
+
+            for task in 0..self.tasks.len() {
+                if !self.is_done(task) && self.is_woken(task) {
+                    polled = true;
+                    self.poll(task);
+                }
+            }
+
+            if self.tasks.iter().all(|task| task.future.is_none()) {
+                return;
+            }
+
+            assert!(polled, "lost wakeup: no pending task was woken");
+        }
+    }
+}
+
+/// A future that yields to the executor once
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// `notify_any` and `notify_all` wake up the operations in the set
+pub fn notify<W: WakerSet + locker::Init>() {
+    let set = W::INIT;
+    let (flags, wakers): (Vec<_>, Vec<_>) = (0..3).map(|_| Flag::new()).unzip();
+
+    assert!(set.is_empty());
+    assert!(!set.notify_any());
+    assert!(!set.notify_all());
+
+    let key = set.insert(&mut Context::from_waker(&wakers[0]));
+    assert!(!set.is_empty());
+    assert!(set.notify_any());
+    assert!(flags[0].take());
+    set.remove(key);
+    assert!(set.is_empty());
+
+    let keys: Vec<_> = wakers
+        .iter()
+        .map(|waker| set.insert(&mut Context::from_waker(waker)))
+        .collect();
+
+    assert!(set.notify_all());
+    assert!(flags.iter().all(|flag| flag.take()));
+
+    // every operation was already notified
+    assert!(!set.notify_all());
+
+    for key in keys {
+        set.remove(key);
+    }
+
+    assert!(set.is_empty());
+}
+
+/// Removed operations are not woken up
+pub fn remove<W: WakerSet + locker::Init>() {
+    let set = W::INIT;
+    let (first, first_waker) = Flag::new();
+    let (second, second_waker) = Flag::new();
+
+    let first_key = set.insert(&mut Context::from_waker(&first_waker));
+    let second_key = set.insert(&mut Context::from_waker(&second_waker));
+    set.remove(first_key);
+
+    assert!(set.notify_all());
+    assert!(!first.take());
+    assert!(second.take());
+
+    set.remove(second_key);
+    assert!(set.is_empty());
+    assert!(!set.notify_any());
+}
+
+/// Cancelling a notified operation passes the notification on to another operation
+pub fn cancel<W: WakerSet + locker::Init>() {
+    let set = W::INIT;
+    let (first, first_waker) = Flag::new();
+    let (second, second_waker) = Flag::new();
+
+    let first_key = set.insert(&mut Context::from_waker(&first_waker));
+    let second_key = set.insert(&mut Context::from_waker(&second_waker));
+
+    assert!(set.notify_any());
+    let (notified, (waiting, waiting_key)) = match (first.take(), second.take()) {
+        (true, false) => (first_key, (&second, second_key)),
+        (false, true) => (second_key, (&first, first_key)),
+        _ => panic!("`notify_any` must wake up exactly one operation"),
+    };
+
+    assert!(set.cancel(notified));
+    assert!(waiting.take());
+
+    // the remaining operation wasn't notified yet when it is cancelled,
+    // so there is nothing to pass on
+    let (third, third_waker) = Flag::new();
+    let third_key = set.insert(&mut Context::from_waker(&third_waker));
+    set.remove(waiting_key);
+
+    assert!(!set.cancel(third_key));
+    assert!(!third.take());
+    assert!(set.is_empty());
+}
+
+/// `notify_batch` wakes up at least one operation, and no more than `max`
+pub fn notify_batch<W: WakerSet + locker::Init>() {
+    let set = W::INIT;
+    let (flags, wakers): (Vec<_>, Vec<_>) = (0..4).map(|_| Flag::new()).unzip();
+
+    let keys: Vec<_> = wakers
+        .iter()
+        .map(|waker| set.insert(&mut Context::from_waker(waker)))
+        .collect();
+
+    assert!(set.notify_batch(2));
+    let woken = flags.iter().filter(|flag| flag.take()).count();
+    assert!((1..=2).contains(&woken), "woke up {} operations", woken);
+
+    for key in keys {
+        set.remove(key);
+    }
+
+    assert!(set.is_empty());
+}
+
+/// Contended tasks all acquire an async mutex
+pub fn mutex<W: WakerSet + locker::Init>() {
+    let mutex = Mutex::<W, _>::new(0);
+    let mut executor = MockExecutor::new();
+
+    for _ in 0..4 {
+        executor.spawn(async {
+            for _ in 0..3 {
+                let mut guard = mutex.lock().await;
+                // hold the lock across a suspension point, so the other tasks block
+                YieldNow(false).await;
+                *guard += 1;
+            }
+        });
+    }
+
+    executor.run();
+    drop(executor);
+
+    assert_eq!(mutex.into_inner(), 12);
+}
+
+/// Dropping a notified lock future passes the notification on to another task
+pub fn mutex_cancel<W: WakerSet + locker::Init>() {
+    let mutex = Mutex::<W, _>::new(0);
+    let mut executor = MockExecutor::new();
+
+    let holder = executor.spawn(async {
+        let _guard = mutex.lock().await;
+        YieldNow(false).await;
+    });
+
+    let waiters = [
+        executor.spawn(async { *mutex.lock().await += 1 }),
+        executor.spawn(async { *mutex.lock().await += 1 }),
+    ];
+
+    // the holder acquires the lock and yields, so both waiters block
+    assert!(!executor.poll(holder));
+    for &waiter in &waiters {
+        assert!(!executor.poll(waiter));
+        assert!(!executor.is_woken(waiter));
+    }
+
+    assert!(executor.poll(holder));
+
+    let (notified, waiting) = match (executor.is_woken(waiters[0]), executor.is_woken(waiters[1])) {
+        (true, false) => (waiters[0], waiters[1]),
+        (false, true) => (waiters[1], waiters[0]),
+        _ => panic!("unlocking the mutex must wake up exactly one task"),
+    };
+
+    executor.cancel(notified);
+    assert!(executor.is_woken(waiting));
+
+    executor.run();
+    drop(executor);
+
+    assert_eq!(mutex.into_inner(), 1);
+}
+
+/// Contended readers and writers all acquire an async rwlock
+pub fn rwlock<W: WakerSet + locker::Init>() {
+    let rwlock = RwLock::<W, _>::new(0);
+    let reads = AtomicUsize::new(0);
+    let mut executor = MockExecutor::new();
+
+    for i in 0..6 {
+        let rwlock = &rwlock;
+        let reads = &reads;
+
+        executor.spawn(async move {
+            for _ in 0..3 {
+                if i % 2 == 0 {
+                    let mut guard = rwlock.write().await;
+                    YieldNow(false).await;
+                    *guard += 1;
+                } else {
+                    let guard = rwlock.read().await;
+                    YieldNow(false).await;
+                    assert!(*guard <= 9);
+                    reads.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+    }
+
+    executor.run();
+    drop(executor);
+
+    assert_eq!(reads.into_inner(), 9);
+    assert_eq!(rwlock.into_inner(), 9);
+}
+
+#[cfg(test)]
+crate::wakerset_conformance!(async_std, crate::async_std::AsyncStdWakerSet);
+
+#[cfg(test)]
+crate::wakerset_conformance!(local_async_std, crate::local_async_std::AsyncStdWakerSet);
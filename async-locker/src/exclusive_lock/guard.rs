@@ -1,9 +1,10 @@
 use super::raw::RawExclusiveGuard;
 use crate::WakerSet;
 use locker::exclusive_lock::{
-    RawExclusiveLock, RawExclusiveLockDowngrade, SplittableExclusiveLock,
+    RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair, SplittableExclusiveLock,
 };
 use locker::RawLockInfo;
+use std::future::Future;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
@@ -82,10 +83,6 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized, T: ?Sized, St>
         (self.raw, self.value)
     }
 
-    pub async fn bump(&self) {
-        self.raw.bump().await
-    }
-
     pub fn map<F: FnOnce(&mut T) -> &mut U, U: ?Sized>(
         self,
         f: F,
@@ -106,6 +103,70 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized, T: ?Sized, St>
     }
 }
 
+impl<'a, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized, T: ?Sized, St>
+    ExclusiveGuard<'a, L, W, T, St>
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+{
+    /// Temporarily yield the lock to a waiting operation, if there are any
+    ///
+    /// If the returned future is dropped before the lock is re-acquired, the lock is not
+    /// re-acquired and the guard is consumed.
+    pub async fn bump(self) -> Self {
+        let (raw, value) = self.into_raw_parts();
+        let value = SendPtr(value);
+        let raw = raw.bump().await;
+        unsafe { Self::from_raw_parts(raw, value.0) }
+    }
+
+    /// Temporarily unlock the lock while running the future returned by `f`,
+    /// and re-acquire it once that future completes
+    ///
+    /// If the returned future is dropped before it completes, the lock is not
+    /// re-acquired and the guard is consumed.
+    pub async fn unlocked<F: Future>(self, f: impl FnOnce() -> F) -> (Self, F::Output) {
+        let (raw, value) = self.into_raw_parts();
+        let value = SendPtr(value);
+        let (raw, output) = raw.unlocked(f).await;
+        (unsafe { Self::from_raw_parts(raw, value.0) }, output)
+    }
+}
+
+impl<'a, L: RawExclusiveLockFair + RawLockInfo, W: WakerSet + ?Sized, T: ?Sized, St>
+    ExclusiveGuard<'a, L, W, T, St>
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+{
+    /// Temporarily yield the lock to a waiting operation using a fair unlocking protocol,
+    /// if there are any waiting operations
+    ///
+    /// See [`ExclusiveGuard::bump`] for cancellation semantics
+    pub async fn bump_fair(self) -> Self {
+        let (raw, value) = self.into_raw_parts();
+        let value = SendPtr(value);
+        let raw = raw.bump_fair().await;
+        unsafe { Self::from_raw_parts(raw, value.0) }
+    }
+
+    /// Temporarily unlock the lock using a fair unlocking protocol while running the future
+    /// returned by `f`, and re-acquire it once that future completes
+    ///
+    /// See [`ExclusiveGuard::unlocked`] for cancellation semantics
+    pub async fn unlocked_fair<F: Future>(self, f: impl FnOnce() -> F) -> (Self, F::Output) {
+        let (raw, value) = self.into_raw_parts();
+        let value = SendPtr(value);
+        let (raw, output) = raw.unlocked_fair(f).await;
+        (unsafe { Self::from_raw_parts(raw, value.0) }, output)
+    }
+}
+
+/// The value pointer of a guard, held across an `await` while the lock is released
+///
+/// This keeps futures that hold it `Send` if the guard would be `Send`
+struct SendPtr<T: ?Sized>(*mut T);
+
+unsafe impl<T: ?Sized + Send> Send for SendPtr<T> {}
+
 impl<
         'a,
         L: SplittableExclusiveLock + RawExclusiveLock + RawLockInfo,
@@ -186,3 +247,118 @@ impl<L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized, T: ?Sized, St> Der
         unsafe { &mut *self.value }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ExclusiveGuard;
+    use crate::async_std::AsyncStdWakerSet;
+    use crate::conformance::Count;
+
+    use std::future::Future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+
+    type Mutex<T> = crate::mutex::Mutex<locker::mutex::spin::SpinLock, AsyncStdWakerSet, T>;
+
+    fn waker(count: &Arc<Count>) -> Waker {
+        Waker::from(count.clone())
+    }
+
+    fn ready<F: Future>(future: F, cx: &mut Context) -> F::Output {
+        match Box::pin(future).as_mut().poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future wasn't ready"),
+        }
+    }
+
+    #[test]
+    fn unlocked() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = waker(&count);
+        let mut cx = Context::from_waker(&waker);
+
+        let mutex = Mutex::new(0);
+        let guard = mutex.try_lock().unwrap();
+
+        let (mut guard, ()) = ready(
+            guard.unlocked(|| async { *mutex.try_lock().unwrap() += 1 }),
+            &mut cx,
+        );
+        assert_eq!(*guard, 1);
+        assert!(mutex.try_lock().is_none());
+
+        let (guard, ()) = ready(
+            ExclusiveGuard::unlocked_fair(guard, || async {
+                *mutex.try_lock().unwrap() += 1;
+            }),
+            &mut cx,
+        );
+        assert_eq!(*guard, 2);
+        assert!(mutex.try_lock().is_none());
+    }
+
+    #[test]
+    fn bump_fair() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = waker(&count);
+        let mut cx = Context::from_waker(&waker);
+
+        let mutex = Mutex::new(0);
+        let guard = mutex.try_lock().unwrap();
+
+        // no one is waiting, so nothing is woken
+        let guard = ready(guard.bump_fair(), &mut cx);
+        assert_eq!(count.0.load(Ordering::SeqCst), 0);
+
+        let mut waiter = Box::pin(mutex.lock());
+        assert!(waiter.as_mut().poll(&mut cx).is_pending());
+
+        // the waiter is woken, and the lock is still held afterwards
+        let guard = ready(guard.bump_fair(), &mut cx);
+        assert_eq!(count.0.load(Ordering::SeqCst), 1);
+        assert!(mutex.try_lock().is_none());
+
+        drop(guard);
+        assert!(waiter.as_mut().poll(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn relock_cancelled() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = waker(&count);
+        let mut cx = Context::from_waker(&waker);
+
+        let mutex = Mutex::new(0);
+        let guard = mutex.try_lock().unwrap();
+
+        // the future returned by the closure holds the lock, so the guard can't re-acquire it
+        let mut unlocked = Box::pin(guard.unlocked(|| async { mutex.try_lock().unwrap() }));
+        assert!(unlocked.as_mut().poll(&mut cx).is_pending());
+        assert!(mutex.try_lock().is_none());
+
+        let mut waiter = Box::pin(mutex.lock());
+        assert!(waiter.as_mut().poll(&mut cx).is_pending());
+
+        // cancelling drops the guard without re-acquiring the lock
+        drop(unlocked);
+        assert!(waiter.as_mut().poll(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn bump_cancelled() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = waker(&count);
+        let mut cx = Context::from_waker(&waker);
+
+        let mutex = Mutex::new(0);
+        let guard = mutex.try_lock().unwrap();
+
+        let mut waiter = Box::pin(mutex.lock());
+        assert!(waiter.as_mut().poll(&mut cx).is_pending());
+
+        // cancelling a bump releases the lock instead of blocking to re-acquire it
+        drop(guard.bump());
+        assert!(waiter.as_mut().poll(&mut cx).is_ready());
+    }
+}
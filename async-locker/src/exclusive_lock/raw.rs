@@ -4,18 +4,26 @@ use locker::exclusive_lock::{
     SplittableExclusiveLock,
 };
 use locker::RawLockInfo;
+use std::future::Future;
 use std::mem::ManuallyDrop;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 pub struct RawExclusiveGuard<'a, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized> {
     inner: ManuallyDrop<Inner<'a, L>>,
     waker_set: &'a W,
+    wake_batch: usize,
 }
 
 impl<L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized> Drop for RawExclusiveGuard<'_, L, W> {
     fn drop(&mut self) {
         unsafe {
             ManuallyDrop::drop(&mut self.inner);
-            self.waker_set.notify_any();
+
+            // don't touch the waker storage if nothing is waiting on this lock
+            if !self.waker_set.is_empty() {
+                self.waker_set.notify_batch(self.wake_batch);
+            }
         }
     }
 }
@@ -27,14 +35,14 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized> RawExclusiveGu
             ///
             /// The share lock must be held
             pub const fn from_raw_parts(inner: Inner<'a, L>, waker_set: &'a WakerSet) -> Self {
-                Self { inner: ManuallyDrop::new(inner), waker_set }
+                Self { inner: ManuallyDrop::new(inner), waker_set, wake_batch: 1 }
             }
         } else {
             /// # Safety
             ///
             /// The share lock must be held
             pub fn from_raw_parts(inner: Inner<'a, L>, waker_set: &'a W) -> Self {
-                Self { inner: ManuallyDrop::new(inner), waker_set }
+                Self { inner: ManuallyDrop::new(inner), waker_set, wake_batch: 1 }
             }
         }
     }
@@ -45,6 +53,13 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized> RawExclusiveGu
         &self.inner
     }
 
+    /// Notify up to `wake_batch` blocked operations when this guard is released,
+    /// instead of just one
+    pub fn with_wake_batch(mut self, wake_batch: usize) -> Self {
+        self.wake_batch = wake_batch;
+        self
+    }
+
     pub fn into_raw_parts(self) -> (Inner<'a, L>, &'a W) {
         let mut this = std::mem::ManuallyDrop::new(self);
 
@@ -53,75 +68,138 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized> RawExclusiveGu
             this.waker_set,
         )
     }
+}
 
-    pub async fn bump(&self) {
-        pub struct LockFuture<'a, 'b, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized>(
-            &'a RawExclusiveGuard<'b, L, W>,
-            Option<W::Index>,
-        );
+impl<'a, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized> RawExclusiveGuard<'a, L, W>
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+{
+    /// Release the lock and return a future that re-acquires it
+    ///
+    /// # Safety
+    ///
+    /// `unlock` must release the *exc lock* held by this guard
+    unsafe fn release(self, unlock: impl FnOnce(&L)) -> Relock<'a, L, W> {
+        let wake_batch = self.wake_batch;
+        let (inner, waker_set) = self.into_raw_parts();
+        let lock = inner.into_inner();
+
+        unlock(lock);
+
+        if !waker_set.is_empty() {
+            waker_set.notify_batch(wake_batch);
+        }
 
-        pub struct LockOnDrop<'a>(&'a dyn RawExclusiveLock);
+        Relock {
+            lock,
+            waker_set,
+            wake_batch,
+            key: None,
+        }
+    }
 
-        impl Drop for LockOnDrop<'_> {
-            fn drop(&mut self) {
-                self.0.exc_lock();
-            }
+    /// Release the lock, and wait until it can be re-acquired
+    ///
+    /// # Safety
+    ///
+    /// `unlock` must release the *exc lock* held by this guard
+    async unsafe fn bump_with(self, unlock: impl FnOnce(&L)) -> Self {
+        if self.waker_set.is_empty() {
+            return self;
         }
 
-        use std::pin::Pin;
-        use std::task::{Context, Poll};
+        self.release(unlock).await
+    }
 
-        impl<L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized> std::future::Future
-            for LockFuture<'_, '_, L, W>
-        {
-            type Output = ();
+    /// Temporarily yield the lock to a waiting operation, if there are any
+    ///
+    /// If the returned future is dropped before the lock is re-acquired, the lock is not
+    /// re-acquired and the guard is consumed.
+    pub async fn bump(self) -> Self {
+        unsafe { self.bump_with(|lock| lock.exc_unlock()).await }
+    }
 
-            fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
-                let Self(mutex, opt_key) = Pin::into_inner(self);
+    /// Temporarily unlock the lock while running the future returned by `f`,
+    /// and re-acquire it once that future completes
+    ///
+    /// If the returned future is dropped before it completes, the lock is not
+    /// re-acquired and the guard is consumed.
+    pub async fn unlocked<F: Future>(self, f: impl FnOnce() -> F) -> (Self, F::Output) {
+        let relock = unsafe { self.release(|lock| lock.exc_unlock()) };
+        let output = f().await;
+        (relock.await, output)
+    }
+}
 
-                if let Some(key) = opt_key.take() {
-                    mutex.waker_set.remove(key);
-                }
+impl<'a, L: RawExclusiveLockFair + RawLockInfo, W: WakerSet + ?Sized> RawExclusiveGuard<'a, L, W>
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+{
+    /// Temporarily yield the lock to a waiting operation using a fair unlocking protocol,
+    /// if there are any waiting operations
+    ///
+    /// See [`RawExclusiveGuard::bump`] for cancellation semantics
+    pub async fn bump_fair(self) -> Self {
+        unsafe { self.bump_with(|lock| lock.exc_unlock_fair()).await }
+    }
 
-                let inner = mutex.inner().inner();
+    /// Temporarily unlock the lock using a fair unlocking protocol while running the future
+    /// returned by `f`, and re-acquire it once that future completes
+    ///
+    /// See [`RawExclusiveGuard::unlocked`] for cancellation semantics
+    pub async fn unlocked_fair<F: Future>(self, f: impl FnOnce() -> F) -> (Self, F::Output) {
+        let relock = unsafe { self.release(|lock| lock.exc_unlock_fair()) };
+        let output = f().await;
+        (relock.await, output)
+    }
+}
 
-                let key = if inner.exc_try_lock() {
-                    return Poll::Ready(());
-                } else {
-                    mutex.waker_set.insert(ctx)
-                };
+/// A future that re-acquires the lock released by a [`RawExclusiveGuard`]
+struct Relock<'a, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized> {
+    lock: &'a L,
+    waker_set: &'a W,
+    wake_batch: usize,
+    key: Option<W::Index>,
+}
 
-                if inner.exc_try_lock() {
-                    mutex.waker_set.remove(key);
-                    Poll::Ready(())
-                } else {
-                    *opt_key = Some(key);
-                    Poll::Pending
-                }
-            }
+impl<L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized> Drop for Relock<'_, L, W> {
+    fn drop(&mut self) {
+        // if this operation was notified, pass the notification on to another one
+        if let Some(key) = self.key.take() {
+            self.waker_set.cancel(key);
         }
+    }
+}
+
+impl<'a, L: RawExclusiveLock + RawLockInfo, W: WakerSet + ?Sized> Future for Relock<'a, L, W>
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+{
+    type Output = RawExclusiveGuard<'a, L, W>;
 
-        if !self.waker_set.is_empty() {
-            {
-                let raw = self.inner.inner();
-                unsafe {
-                    raw.exc_unlock();
-                }
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let this = Pin::into_inner(self);
 
-                // if waker_set.notify_any() panics we must not leave this guard in an unlocked state
-                // as that would violate `RawExclusiveGuard`'s safety invariants
-                // so we bite the bullet and do a synchronous blocking lock
-                // but given that if waker_set.notify_any() does panic, something has already gone horribly wrong
-                // // this shouldn't be an issue. We also use dynamic dispatch in order to minimize codegen
-                let _lock_on_drop = LockOnDrop(raw as _);
+        if let Some(key) = this.key.take() {
+            this.waker_set.remove(key);
+        }
 
-                self.waker_set.notify_any();
+        if !this.lock.exc_try_lock() {
+            let key = this.waker_set.insert(ctx);
 
-                std::mem::forget(_lock_on_drop);
+            if !this.lock.exc_try_lock() {
+                this.key = Some(key);
+                return Poll::Pending;
             }
 
-            LockFuture(self, None).await
+            this.waker_set.remove(key);
         }
+
+        let inner = unsafe { Inner::from_raw(this.lock) };
+        Poll::Ready(
+            RawExclusiveGuard::from_raw_parts(inner, this.waker_set)
+                .with_wake_batch(this.wake_batch),
+        )
     }
 }
 
@@ -136,6 +214,7 @@ where
             unsafe { std::ptr::read(&*g.inner).downgrade() },
             g.waker_set,
         )
+        .with_wake_batch(g.wake_batch)
     }
 }
 
@@ -143,6 +222,6 @@ impl<L: RawExclusiveLock + SplittableExclusiveLock + RawLockInfo, W: WakerSet +
     for RawExclusiveGuard<'_, L, W>
 {
     fn clone(&self) -> Self {
-        Self::from_raw_parts((*self.inner).clone(), self.waker_set)
+        Self::from_raw_parts((*self.inner).clone(), self.waker_set).with_wake_batch(self.wake_batch)
     }
 }
@@ -0,0 +1,317 @@
+//! A mutex with the same method surface as `futures::lock::Mutex`
+//!
+//! This allows projects to swap between `futures::lock::Mutex` and `async_locker`
+//! behind a type alias, for example to benchmark them against each other
+//! without any other code changes.
+//!
+//! ```ignore
+//! #[cfg(feature = "futures-mutex")]
+//! use futures::lock::{Mutex, MutexGuard};
+//! #[cfg(not(feature = "futures-mutex"))]
+//! use async_locker::futures_compat::{Mutex, MutexGuard};
+//! ```
+
+use crate::async_std::AsyncStdWakerSet;
+use crate::exclusive_lock::{guard::Mapped, raw::RawExclusiveGuard, ExclusiveGuard};
+use locker::mutex::default::DefaultLock;
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+type Inner<T> = crate::mutex::Mutex<DefaultLock, AsyncStdWakerSet, T>;
+
+/// An RAII guard returned by [`Mutex::lock`] and [`Mutex::try_lock`]
+pub type MutexGuard<'a, T> = ExclusiveGuard<'a, DefaultLock, AsyncStdWakerSet, T>;
+
+/// An RAII guard returned by [`MutexGuard::map`](ExclusiveGuard::map)
+///
+/// Unlike `futures::lock::MappedMutexGuard`, this only has the mapped type as a parameter
+pub type MappedMutexGuard<'a, U> = ExclusiveGuard<'a, DefaultLock, AsyncStdWakerSet, U, Mapped>;
+
+/// An async mutex with the same method surface as `futures::lock::Mutex`
+#[repr(transparent)]
+pub struct Mutex<T: ?Sized> {
+    inner: Inner<T>,
+}
+
+impl<T> Mutex<T> {
+    /// Creates a new mutex in an unlocked state ready for use.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Inner::new(value),
+        }
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Attempt to acquire the lock immediately.
+    ///
+    /// If the lock is currently held, this will return `None`.
+    #[inline]
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.inner.try_lock()
+    }
+
+    /// Acquire the lock asynchronously.
+    ///
+    /// The returned future resolves once the lock is acquired.
+    #[inline]
+    pub fn lock(&self) -> MutexLockFuture<'_, T> {
+        MutexLockFuture {
+            mutex: Some(self),
+            future: self.inner.raw().lock(),
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `Mutex` mutably, no actual locking needs to take place
+    /// ---the mutable borrow statically guarantees no locks exist.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
+    /// The underlying `async_locker` mutex
+    #[inline]
+    pub fn as_async_locker(&self) -> &Inner<T> {
+        &self.inner
+    }
+
+    /// Attempt to acquire the lock immediately, with a guard that keeps the mutex alive.
+    ///
+    /// If the lock is currently held, this will return `None`.
+    #[inline]
+    pub fn try_lock_owned(self: &Arc<Self>) -> Option<OwnedMutexGuard<T>> {
+        let guard = self.try_lock()?;
+        Some(OwnedMutexGuard::new(self.clone(), guard))
+    }
+
+    /// Acquire the lock asynchronously, with a guard that keeps the mutex alive.
+    ///
+    /// The returned future resolves once the lock is acquired.
+    #[inline]
+    pub fn lock_owned(self: &Arc<Self>) -> impl Future<Output = OwnedMutexGuard<T>> {
+        let mutex = self.clone();
+
+        async move {
+            let guard = mutex.lock().await;
+            OwnedMutexGuard::new(mutex.clone(), guard)
+        }
+    }
+}
+
+/// A future which resolves when the target mutex has been successfully acquired.
+pub struct MutexLockFuture<'a, T: ?Sized> {
+    mutex: Option<&'a Mutex<T>>,
+    future: crate::mutex::raw::LockFuture<'a, DefaultLock, AsyncStdWakerSet>,
+}
+
+impl<'a, T: ?Sized> Future for MutexLockFuture<'a, T> {
+    type Output = MutexGuard<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let this = Pin::into_inner(self);
+        let mutex = this.mutex.expect("polled MutexLockFuture after completion");
+        let raw = match Pin::new(&mut this.future).poll(ctx) {
+            Poll::Ready(raw) => raw,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        this.mutex = None;
+        Poll::Ready(unsafe { ExclusiveGuard::from_raw_parts(raw, mutex.inner.data_ptr()) })
+    }
+}
+
+impl<T: ?Sized> std::fmt::Debug for MutexLockFuture<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MutexLockFuture")
+            .field("was_acquired", &self.mutex.is_none())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An RAII guard returned by [`Mutex::lock_owned`] and [`Mutex::try_lock_owned`]
+///
+/// Unlike [`MutexGuard`], this holds an `Arc` to the mutex, so it isn't tied to a borrow.
+pub struct OwnedMutexGuard<T: ?Sized> {
+    mutex: Arc<Mutex<T>>,
+    // the guard gives out `&T`, so it is only `Sync` if `T: Sync`
+    _marker: PhantomData<*mut ()>,
+}
+
+// the guard owns the lock like a `MutexGuard`, so it follows the lock's guard markers
+unsafe impl<T: ?Sized + Send> Send for OwnedMutexGuard<T> where
+    RawExclusiveGuard<'static, DefaultLock, AsyncStdWakerSet>: Send
+{
+}
+unsafe impl<T: ?Sized + Sync> Sync for OwnedMutexGuard<T> where
+    RawExclusiveGuard<'static, DefaultLock, AsyncStdWakerSet>: Sync
+{
+}
+
+impl<T: ?Sized> OwnedMutexGuard<T> {
+    fn new(mutex: Arc<Mutex<T>>, guard: MutexGuard<'_, T>) -> Self {
+        debug_assert!(std::ptr::eq(
+            guard.raw().inner().inner(),
+            mutex.inner.raw().raw_mutex().inner()
+        ));
+
+        // the lock is released when this guard is dropped instead
+        let (raw, _) = guard.into_raw_parts();
+        std::mem::forget(raw);
+
+        Self {
+            mutex,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for OwnedMutexGuard<T> {
+    fn drop(&mut self) {
+        let raw = self.mutex.inner.raw();
+
+        unsafe {
+            drop(RawExclusiveGuard::from_raw_parts(
+                locker::exclusive_lock::RawExclusiveGuard::from_raw(raw.raw_mutex().inner()),
+                raw.waker_set(),
+            ));
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for OwnedMutexGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.inner.data_ptr() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for OwnedMutexGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.inner.data_ptr() }
+    }
+}
+
+impl<T: ?Sized + std::fmt::Debug> std::fmt::Debug for OwnedMutexGuard<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T> From<T> for Mutex<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> From<Inner<T>> for Mutex<T> {
+    #[inline]
+    fn from(inner: Inner<T>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized> std::fmt::Debug for Mutex<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // checking if the lock is held would mean trying to lock it, which could
+        // briefly block other tasks, so this doesn't show anything
+        f.debug_struct("Mutex").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mutex, MutexLockFuture, OwnedMutexGuard};
+    use crate::conformance::Count;
+
+    use std::future::Future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+
+    #[test]
+    fn lock_future() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mutex = Mutex::new(0);
+        let guard = mutex.try_lock().unwrap();
+
+        let mut lock: MutexLockFuture<'_, i32> = mutex.lock();
+        assert!(std::pin::Pin::new(&mut lock).poll(&mut cx).is_pending());
+
+        drop(guard);
+        assert_eq!(count.0.load(Ordering::SeqCst), 1);
+
+        match std::pin::Pin::new(&mut lock).poll(&mut cx) {
+            Poll::Ready(mut guard) => *guard += 1,
+            Poll::Pending => panic!("could not acquire the lock"),
+        }
+
+        drop(lock);
+        assert_eq!(mutex.into_inner(), 1);
+    }
+
+    #[test]
+    fn lock_owned() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mutex = Arc::new(Mutex::new(0));
+
+        let mut guard: OwnedMutexGuard<i32> = mutex.try_lock_owned().unwrap();
+        *guard += 1;
+        assert!(mutex.try_lock().is_none());
+
+        let mut lock = Box::pin(mutex.lock_owned());
+        assert!(lock.as_mut().poll(&mut cx).is_pending());
+
+        // the guard keeps the mutex alive
+        let weak = Arc::downgrade(&mutex);
+        drop(mutex);
+        drop(guard);
+
+        let guard = match lock.as_mut().poll(&mut cx) {
+            Poll::Ready(guard) => guard,
+            Poll::Pending => panic!("could not acquire the lock"),
+        };
+        drop(lock);
+
+        assert_eq!(*guard, 1);
+        let mutex = weak.upgrade().unwrap();
+        assert!(mutex.try_lock().is_none());
+        drop(guard);
+        assert!(mutex.try_lock().is_some());
+    }
+
+    #[test]
+    fn debug_does_not_lock() {
+        let mutex = Mutex::new(0);
+        let _guard = mutex.try_lock().unwrap();
+        assert_eq!(format!("{:?}", mutex), "Mutex { .. }");
+    }
+}
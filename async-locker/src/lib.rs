@@ -9,22 +9,147 @@ macro_rules! defer {
 }
 
 pub mod async_std;
+pub mod cancel;
+pub mod conformance;
 mod defer;
 pub mod exclusive_lock;
+pub mod futures_compat;
 pub mod local_async_std;
+pub mod marker;
 pub mod mutex;
+pub mod notify;
+pub mod queue;
 pub mod remutex;
 pub mod rwlock;
 pub mod share_lock;
 mod slab;
+pub mod watch;
 
 pub trait WakerSet {
     type Index: std::marker::Unpin;
 
+    /// Registers a blocked operation, and returns a key associated with it
+    ///
+    /// The new entry must be visible to any [`is_empty`](WakerSet::is_empty) that runs after
+    /// this returns, so implementations that keep an atomic "any waiters" flag must issue a
+    /// `SeqCst` fence after setting it. The blocked operation retries after registering itself,
+    /// and that fence pairs with the one in `is_empty` so that either the retry sees the
+    /// released lock, or the releasing side sees the new entry.
     fn insert(&self, cx: &mut Context) -> Self::Index;
+
+    /// Checks if there are no operations in the set, notified or not
+    ///
+    /// This is called every time a lock is released, so it must be cheap and must not lock
+    /// the waker storage, usually it is a relaxed load of an atomic "any waiters" flag which
+    /// is maintained by the other functions. Implementations must issue a `SeqCst` fence
+    /// before that load, which pairs with the fence in [`insert`](WakerSet::insert).
     fn is_empty(&self) -> bool;
     fn remove(&self, key: Self::Index);
     fn cancel(&self, key: Self::Index) -> bool;
     fn notify_any(&self) -> bool;
     fn notify_all(&self) -> bool;
+
+    /// Notifies blocked operations until up to `max` of them have been notified,
+    /// counting the ones that were notified but haven't removed themselves yet
+    ///
+    /// Returns `true` if at least one operation was notified.
+    fn notify_batch(&self, max: usize) -> bool {
+        self.notify_any()
+    }
+}
+
+/// The registration of a blocked operation in a [`WakerSet`], for acquiring locks from
+/// a manual `Future::poll` with the `poll_*` lock methods
+///
+/// While the operation is pending, the `Waiter` holds the key of the task's waker. Every
+/// poll replaces the registered waker with the one from the current `Context`, so the
+/// operation can be polled from a different task than the one that started it.
+///
+/// If the operation is abandoned while it is pending, pass the `Waiter` to the lock's
+/// `cancel_poll`, so that a notification which was meant for it is passed on to another
+/// operation. Dropping a pending `Waiter` panics in debug builds.
+///
+/// A pending `Waiter` is tied to the lock it was registered with, passing it to
+/// another lock panics.
+pub struct Waiter<W: WakerSet> {
+    key: Option<W::Index>,
+    // the address of the `WakerSet` that `key` belongs to
+    waker_set: usize,
+}
+
+impl<W: WakerSet> Default for Waiter<W> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: WakerSet> Waiter<W> {
+    /// Create a `Waiter` that isn't registered
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            key: None,
+            waker_set: 0,
+        }
+    }
+
+    /// Check if the operation is registered in a `WakerSet`
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Check that a pending `Waiter` is used with the `WakerSet` it was registered with
+    #[inline]
+    fn check(&self, waker_set: &W) {
+        assert!(
+            self.key.is_none() || self.waker_set == waker_set as *const W as usize,
+            "a pending `Waiter` was passed to a different lock than the one it was registered with"
+        );
+    }
+
+    /// Remove the previous registration, returns true if there was one
+    #[inline]
+    pub(crate) fn remove(&mut self, waker_set: &W) -> bool {
+        self.check(waker_set);
+
+        match self.key.take() {
+            Some(key) => {
+                waker_set.remove(key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Register the current task, the lock must be retried after this
+    #[inline]
+    pub(crate) fn insert(&mut self, waker_set: &W, cx: &mut Context) {
+        debug_assert!(self.key.is_none());
+        self.key = Some(waker_set.insert(cx));
+        self.waker_set = waker_set as *const W as usize;
+    }
+
+    /// Remove the registration of an operation that is abandoned
+    ///
+    /// Returns `true` if another blocked operation was notified in its place
+    #[inline]
+    pub(crate) fn cancel(&mut self, waker_set: &W) -> bool {
+        self.check(waker_set);
+
+        match self.key.take() {
+            Some(key) => waker_set.cancel(key),
+            None => false,
+        }
+    }
+}
+
+impl<W: WakerSet> Drop for Waiter<W> {
+    fn drop(&mut self) {
+        debug_assert!(
+            self.key.is_none() || std::thread::panicking(),
+            "a pending `Waiter` was dropped without being passed to `cancel_poll`"
+        );
+    }
 }
@@ -12,7 +12,26 @@ pub use crate::slab::Index;
 use crate::slab::Slab;
 use locker::mutex::local_tagged::LocalTaggedLock;
 
-type Mutex<T> = locker::mutex::Mutex<LocalTaggedLock, T>;
+type SetMutex<T> = locker::mutex::Mutex<LocalTaggedLock, T>;
+
+type LocalReLock = locker::remutex::lock::ReLock<
+    locker::mutex::local::LocalLock,
+    locker::remutex::counter::SubWord,
+    locker::remutex::std_thread::StdThreadInfo,
+>;
+
+/// A local raw async mutex
+pub type RawMutex = crate::mutex::raw::Mutex<locker::mutex::local::LocalLock, AsyncStdWakerSet>;
+/// A local async mutex
+pub type Mutex<T> = crate::mutex::Mutex<locker::mutex::local::LocalLock, AsyncStdWakerSet, T>;
+/// A local raw async rwlock
+pub type RawRwLock = crate::rwlock::raw::RwLock<locker::rwlock::local::LocalLock, AsyncStdWakerSet>;
+/// A local async rwlock
+pub type RwLock<T> = crate::rwlock::RwLock<locker::rwlock::local::LocalLock, AsyncStdWakerSet, T>;
+/// A local raw async reentrant mutex
+pub type RawReentrantMutex = crate::remutex::raw::ReentrantMutex<LocalReLock, AsyncStdWakerSet>;
+/// A local async reentrant mutex
+pub type ReentrantMutex<T> = crate::remutex::ReentrantMutex<LocalReLock, AsyncStdWakerSet, T>;
 
 /// Set when there is at least one entry that has already been notified.
 const NOTIFIED: u8 = 0b01;
@@ -38,7 +57,11 @@ struct Inner {
 /// A set holding wakers.
 pub struct AsyncStdWakerSet {
     /// Holds 2 bits: `NOTIFY_ONE`, and `NOTIFY_ALL`.
-    inner: Mutex<Inner>,
+    inner: SetMutex<Inner>,
+}
+
+impl locker::Init for AsyncStdWakerSet {
+    const INIT: Self = Self::new();
 }
 
 impl AsyncStdWakerSet {
@@ -66,14 +89,27 @@ impl AsyncStdWakerSet {
         let mut inner = &mut *self.lock();
         let mut notified = false;
 
+        let limit = match n {
+            Notify::Batch(max) => max,
+            _ => usize::MAX,
+        };
+
+        // The number of entries that were notified, but haven't removed themselves yet.
+        let mut in_flight = inner.entries.len() - inner.notifiable;
+
+        if in_flight >= limit {
+            return false;
+        }
+
         for (_, opt_waker) in inner.entries.iter_mut() {
             // If there is no waker in this entry, that means it was already woken.
             if let Some(w) = opt_waker.take() {
                 w.wake();
                 inner.notifiable -= 1;
+                in_flight += 1;
                 notified = true;
 
-                if n == Notify::One {
+                if n == Notify::One || in_flight >= limit {
                     break;
                 }
             }
@@ -97,8 +133,11 @@ impl AsyncStdWakerSet {
 impl crate::WakerSet for AsyncStdWakerSet {
     type Index = Index;
 
+    /// Checks if there are no entries, without locking the set.
+    #[inline]
     fn is_empty(&self) -> bool {
-        self.inner.lock().entries.is_empty()
+        // Both flags are clear exactly when there are no entries.
+        self.flag() == 0
     }
 
     /// Inserts a waker for a blocked operation and returns a key associated with it.
@@ -172,6 +211,20 @@ impl crate::WakerSet for AsyncStdWakerSet {
             false
         }
     }
+
+    /// Notifies blocked operations until up to `max` of them have been notified.
+    ///
+    /// Returns `true` if at least one operation was notified.
+    #[inline]
+    fn notify_batch(&self, max: usize) -> bool {
+        if max <= 1 {
+            self.notify_any()
+        } else if self.flag() & NOTIFIABLE != 0 {
+            self.notify(Notify::Batch(max))
+        } else {
+            false
+        }
+    }
 }
 
 /// A guard holding a `AsyncStdWakerSet` locked.
@@ -223,6 +276,8 @@ enum Notify {
     Any,
     /// Notify one additional entry.
     One,
+    /// Notify entries until the given number of entries are notified.
+    Batch(usize),
     /// Notify all entries.
     All,
 }
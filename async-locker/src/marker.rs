@@ -0,0 +1,53 @@
+//! Markers that specify what auto traits can't be implemented for guards
+//!
+//! Async locks use the same raw locks as `locker`, so they use the same markers:
+//! a raw lock declares the auto traits of its guards with
+//! [`RawLockInfo::ExclusiveGuardTraits`](locker::RawLockInfo::ExclusiveGuardTraits) and
+//! [`RawLockInfo::ShareGuardTraits`](locker::RawLockInfo::ShareGuardTraits), and every async guard
+//! wraps a `locker` raw guard, so it inherits those restrictions. An async guard also holds
+//! a reference to its [`WakerSet`](crate::WakerSet), so it is only `Send` if the waker set is `Sync`.
+//!
+//! So guards of thread-safe locks can be held across `await`s in `Send` futures
+//!
+//! ```
+//! use async_locker::async_std::AsyncStdWakerSet;
+//! use locker::mutex::spin::SpinLock;
+//!
+//! type Mutex<T> = async_locker::mutex::Mutex<SpinLock, AsyncStdWakerSet, T>;
+//!
+//! fn assert_send<T: Send>(_: &T) {}
+//!
+//! let mutex = Mutex::new(0);
+//! assert_send(&async {
+//!     let guard = mutex.lock().await;
+//!     async {}.await;
+//!     drop(guard);
+//! });
+//! ```
+//!
+//! But guards of local locks, and of reentrant locks (which are owned by a thread) can't
+//!
+//! ```compile_fail,E0277
+//! use async_locker::local_async_std::Mutex;
+//!
+//! fn assert_send<T: Send>(_: &T) {}
+//!
+//! let mutex = Mutex::new(0);
+//! assert_send(&mutex.try_lock().unwrap());
+//! ```
+//!
+//! ```compile_fail,E0277
+//! use async_locker::async_std::AsyncStdWakerSet;
+//! use locker::mutex::spin::SpinLock;
+//! use locker::remutex::{lock::ReLock, std_thread::StdThreadInfo};
+//!
+//! type ReentrantMutex<T> =
+//!     async_locker::remutex::ReentrantMutex<ReLock<SpinLock, usize, StdThreadInfo>, AsyncStdWakerSet, T>;
+//!
+//! fn assert_send<T: Send>(_: &T) {}
+//!
+//! let mutex = ReentrantMutex::new(0);
+//! assert_send(&mutex.try_lock().unwrap());
+//! ```
+
+pub use locker::marker::{Inhabitted, Marker, NoSend, NoSync};
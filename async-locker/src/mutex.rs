@@ -1,15 +1,16 @@
-use std::cell::UnsafeCell;
+use locker::cell::SharedCell;
 
 use crate::exclusive_lock::ExclusiveGuard;
-use crate::WakerSet;
+use crate::{Waiter, WakerSet};
 use locker::mutex::RawMutex;
+use std::task::{Context, Poll};
 
 pub mod raw;
 
 #[repr(C)]
 pub struct Mutex<L, W, T: ?Sized> {
     raw: raw::Mutex<L, W>,
-    value: UnsafeCell<T>,
+    value: SharedCell<T>,
 }
 
 impl<L: RawMutex + locker::Init, W: WakerSet + locker::Init, T: Default> Default
@@ -32,7 +33,7 @@ impl<L, W, T> Mutex<L, W, T> {
     pub const fn from_raw_parts(raw: raw::Mutex<L, W>, value: T) -> Self {
         Self {
             raw,
-            value: UnsafeCell::new(value),
+            value: SharedCell::new(value),
         }
     }
 
@@ -53,6 +54,27 @@ impl<L, W, T: ?Sized> Mutex<L, W, T> {
         &self.raw
     }
 
+    /// Abandon an operation that was started by `poll_lock`, see [`raw::Mutex::cancel_poll`]
+    #[inline]
+    pub fn cancel_poll(&self, waiter: &mut Waiter<W>) -> bool
+    where
+        W: WakerSet,
+    {
+        self.raw.cancel_poll(waiter)
+    }
+
+    #[inline]
+    pub(crate) fn data_ptr(&self) -> *mut T {
+        self.value.as_ptr()
+    }
+
+    /// Set the number of times the lock will be retried before registering a waker,
+    /// see [`raw::Mutex::set_spin_budget`]
+    #[inline]
+    pub fn set_spin_budget(&mut self, spin_budget: u32) {
+        self.raw.set_spin_budget(spin_budget)
+    }
+
     cfg_if::cfg_if! {
         if #[cfg(feature = "nightly")] {
             #[inline]
@@ -62,7 +84,7 @@ impl<L, W, T: ?Sized> Mutex<L, W, T> {
 
             #[inline]
             pub const fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                unsafe { &mut *self.value.as_ptr() }
             }
         } else {
             #[inline]
@@ -72,7 +94,7 @@ impl<L, W, T: ?Sized> Mutex<L, W, T> {
 
             #[inline]
             pub fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                self.value.get_mut()
             }
         }
     }
@@ -100,7 +122,29 @@ where
 {
     #[inline]
     pub async fn lock(&self) -> ExclusiveGuard<'_, L, W, T> {
-        unsafe { ExclusiveGuard::from_raw_parts(self.raw.lock().await, self.value.get()) }
+        unsafe { ExclusiveGuard::from_raw_parts(self.raw.lock().await, self.value.as_ptr()) }
+    }
+
+    /// Acquire the lock, unless `cancel` completes first, see [`raw::Mutex::lock_cancellable`]
+    #[inline]
+    pub async fn lock_cancellable<C: std::future::Future + Unpin>(
+        &self,
+        cancel: C,
+    ) -> Result<ExclusiveGuard<'_, L, W, T>, crate::cancel::Cancelled> {
+        let raw = self.raw.lock_cancellable(cancel).await?;
+        unsafe { Ok(ExclusiveGuard::from_raw_parts(raw, self.value.as_ptr())) }
+    }
+
+    /// Try to acquire the lock from a manual `Future::poll`, see [`raw::Mutex::poll_lock`]
+    #[inline]
+    pub fn poll_lock(
+        &self,
+        ctx: &mut Context,
+        waiter: &mut Waiter<W>,
+    ) -> Poll<ExclusiveGuard<'_, L, W, T>> {
+        self.raw
+            .poll_lock(ctx, waiter)
+            .map(|raw| unsafe { ExclusiveGuard::from_raw_parts(raw, self.value.as_ptr()) })
     }
 
     #[inline]
@@ -108,7 +152,7 @@ where
         unsafe {
             Some(ExclusiveGuard::from_raw_parts(
                 self.raw.try_lock()?,
-                self.value.get(),
+                self.value.as_ptr(),
             ))
         }
     }
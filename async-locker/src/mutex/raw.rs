@@ -1,10 +1,64 @@
-use crate::{exclusive_lock::raw::RawExclusiveGuard, WakerSet};
+use crate::{exclusive_lock::raw::RawExclusiveGuard, Waiter, WakerSet};
 
 use locker::mutex::{raw, RawMutex};
+use std::future::Future;
+use std::pin::Pin;
+#[cfg(feature = "stats")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+/// The default number of times the async mutex will try to acquire the lock,
+/// before registering a waker
+pub const DEFAULT_SPIN_BUDGET: u32 = 4;
 
 pub struct Mutex<L, W> {
     raw: raw::Mutex<L>,
     waker_set: W,
+    spin_budget: u32,
+    counters: Counters,
+}
+
+// counts how contended lock acquisitions were resolved, this is empty without the `stats` feature
+struct Counters {
+    #[cfg(feature = "stats")]
+    spin_acquired: AtomicUsize,
+    #[cfg(feature = "stats")]
+    registered: AtomicUsize,
+}
+
+impl Counters {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: Self = Self {
+        #[cfg(feature = "stats")]
+        spin_acquired: AtomicUsize::new(0),
+        #[cfg(feature = "stats")]
+        registered: AtomicUsize::new(0),
+    };
+
+    #[inline]
+    fn spin_acquired(&self) {
+        #[cfg(feature = "stats")]
+        self.spin_acquired.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn registered(&self) {
+        #[cfg(feature = "stats")]
+        self.registered.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Statistics about how contended lock acquisitions were resolved
+///
+/// This is only available with the `stats` feature flag, so that other
+/// users don't pay for the counters
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Stats {
+    /// The number of times the lock was acquired by spinning, without registering a waker
+    pub spin_acquired: usize,
+    /// The number of times a waker was registered
+    pub registered: usize,
 }
 
 impl<L: RawMutex + locker::Init, W: WakerSet + locker::Init> Default for Mutex<L, W> {
@@ -20,7 +74,12 @@ impl<L, W> Mutex<L, W> {
     /// You must pass `RawLockInfo::INIT` as lock
     #[inline]
     pub const fn from_raw_parts(raw: raw::Mutex<L>, waker_set: W) -> Self {
-        Self { raw, waker_set }
+        Self {
+            raw,
+            waker_set,
+            spin_budget: DEFAULT_SPIN_BUDGET,
+            counters: Counters::INIT,
+        }
     }
 
     #[inline]
@@ -33,6 +92,38 @@ impl<L, W> Mutex<L, W> {
         &self.raw
     }
 
+    /// The `WakerSet` that tasks waiting on this mutex are registered in
+    #[inline]
+    pub const fn waker_set(&self) -> &W {
+        &self.waker_set
+    }
+
+    /// The number of times the lock will be retried before registering a waker
+    #[inline]
+    pub const fn spin_budget(&self) -> u32 {
+        self.spin_budget
+    }
+
+    /// Set the number of times the lock will be retried before registering a waker
+    ///
+    /// Under short critical sections, the lock is often released after a few spins,
+    /// so this avoids most of the cost of registering and removing wakers.
+    /// A spin budget of zero disables spinning.
+    #[inline]
+    pub fn set_spin_budget(&mut self, spin_budget: u32) {
+        self.spin_budget = spin_budget;
+    }
+
+    /// Statistics about how contended lock acquisitions were resolved
+    #[inline]
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        Stats {
+            spin_acquired: self.counters.spin_acquired.load(Ordering::Relaxed),
+            registered: self.counters.registered.load(Ordering::Relaxed),
+        }
+    }
+
     cfg_if::cfg_if! {
         if #[cfg(feature = "nightly")] {
             #[inline]
@@ -73,44 +164,70 @@ where
     L::ExclusiveGuardTraits: locker::marker::Inhabitted,
 {
     #[inline]
-    pub async fn lock(&self) -> RawExclusiveGuard<'_, L, W> {
-        pub struct LockFuture<'a, L, W, I>(&'a Mutex<L, W>, Option<I>);
+    pub fn lock(&self) -> LockFuture<'_, L, W> {
+        LockFuture(self, Waiter::new())
+    }
 
-        use std::pin::Pin;
-        use std::task::{Context, Poll};
+    /// Try to acquire the lock, and register the current task in `waiter` if it is locked
+    ///
+    /// This drives a lock acquisition from a manual `Future::poll`, without storing a
+    /// [`LockFuture`], and `waiter` must be used for the same operation until it completes.
+    /// If the operation is abandoned while it is pending, pass `waiter` to
+    /// [`cancel_poll`](Mutex::cancel_poll).
+    ///
+    /// # Panics
+    ///
+    /// If `waiter` is pending on a different lock
+    pub fn poll_lock(
+        &self,
+        ctx: &mut Context,
+        waiter: &mut Waiter<W>,
+    ) -> Poll<RawExclusiveGuard<'_, L, W>> {
+        waiter.remove(&self.waker_set);
 
-        impl<'a, L: RawMutex, W: WakerSet> std::future::Future for LockFuture<'a, L, W, W::Index>
-        where
-            L::ExclusiveGuardTraits: locker::marker::Inhabitted,
-        {
-            type Output = RawExclusiveGuard<'a, L, W>;
+        if let Some(guard) = self.try_lock() {
+            return Poll::Ready(guard);
+        }
 
-            fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
-                let Self(mutex, opt_key) = Pin::into_inner(self);
+        if let Some(guard) = self.spin_lock() {
+            return Poll::Ready(guard);
+        }
 
-                if let Some(key) = opt_key.take() {
-                    mutex.waker_set.remove(key);
-                }
+        self.counters.registered();
+        waiter.insert(&self.waker_set, ctx);
 
-                let key = match mutex.try_lock() {
-                    Some(gaurd) => return Poll::Ready(gaurd),
-                    None => mutex.waker_set.insert(ctx),
-                };
-
-                match mutex.try_lock() {
-                    Some(gaurd) => {
-                        mutex.waker_set.remove(key);
-                        Poll::Ready(gaurd)
-                    }
-                    None => {
-                        *opt_key = Some(key);
-                        Poll::Pending
-                    }
-                }
+        match self.try_lock() {
+            Some(guard) => {
+                waiter.remove(&self.waker_set);
+                Poll::Ready(guard)
             }
+            None => Poll::Pending,
         }
+    }
 
-        LockFuture(self, None).await
+    /// Acquire the lock, unless `cancel` completes first
+    ///
+    /// `cancel` can be any `Future + Unpin`, like [`CancelToken::cancelled`](crate::cancel::CancelToken::cancelled)
+    #[inline]
+    pub async fn lock_cancellable<C: std::future::Future + Unpin>(
+        &self,
+        cancel: C,
+    ) -> Result<RawExclusiveGuard<'_, L, W>, crate::cancel::Cancelled> {
+        crate::cancel::Cancellable::new(self.lock(), cancel).await
+    }
+
+    #[cold]
+    fn spin_lock(&self) -> Option<RawExclusiveGuard<'_, L, W>> {
+        for _ in 0..self.spin_budget {
+            std::hint::spin_loop();
+
+            if let Some(guard) = self.try_lock() {
+                self.counters.spin_acquired();
+                return Some(guard);
+            }
+        }
+
+        None
     }
 
     #[inline]
@@ -120,3 +237,154 @@ where
         Some(RawExclusiveGuard::from_raw_parts(guard, &self.waker_set))
     }
 }
+
+/// A future that acquires a [`Mutex`], returned by [`Mutex::lock`]
+pub struct LockFuture<'a, L, W: WakerSet>(&'a Mutex<L, W>, Waiter<W>);
+
+impl<L, W: WakerSet> Mutex<L, W> {
+    /// Abandon an operation that was started by [`poll_lock`](Mutex::poll_lock)
+    ///
+    /// Returns `true` if another blocked operation was notified in its place
+    #[inline]
+    pub fn cancel_poll(&self, waiter: &mut Waiter<W>) -> bool {
+        waiter.cancel(&self.waker_set)
+    }
+}
+
+impl<L, W: WakerSet> Drop for LockFuture<'_, L, W> {
+    fn drop(&mut self) {
+        // if this operation was notified, pass the notification on to another one
+        self.0.cancel_poll(&mut self.1);
+    }
+}
+
+impl<'a, L: RawMutex, W: WakerSet> Future for LockFuture<'a, L, W>
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+{
+    type Output = RawExclusiveGuard<'a, L, W>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let Self(mutex, waiter) = Pin::into_inner(self);
+        mutex.poll_lock(ctx, waiter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mutex, DEFAULT_SPIN_BUDGET};
+    use crate::async_std::AsyncStdWakerSet;
+    use crate::conformance::Count;
+
+    use std::future::Future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::task::{Context, Poll, Waker};
+
+    type RawMutex = Mutex<locker::mutex::spin::SpinLock, AsyncStdWakerSet>;
+
+    #[test]
+    fn no_spin_budget() {
+        let waker = Waker::from(Arc::new(Count(AtomicUsize::new(0))));
+        let mut cx = Context::from_waker(&waker);
+
+        let mut mutex = RawMutex::new();
+        assert_eq!(mutex.spin_budget(), DEFAULT_SPIN_BUDGET);
+        mutex.set_spin_budget(0);
+        assert_eq!(mutex.spin_budget(), 0);
+
+        let guard = mutex.try_lock().unwrap();
+        let mut lock = Box::pin(mutex.lock());
+        assert!(lock.as_mut().poll(&mut cx).is_pending());
+
+        #[cfg(feature = "stats")]
+        assert_eq!(
+            mutex.stats(),
+            super::Stats {
+                spin_acquired: 0,
+                registered: 1
+            }
+        );
+
+        drop(guard);
+        assert!(lock.as_mut().poll(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn spin_budget() {
+        let waker = Waker::from(Arc::new(Count(AtomicUsize::new(0))));
+        let mut cx = Context::from_waker(&waker);
+
+        // with an unbounded budget, a lock that is released soon is acquired without registering
+        let mut mutex = RawMutex::new();
+        mutex.set_spin_budget(u32::MAX);
+        let barrier = Barrier::new(2);
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let guard = mutex.try_lock().unwrap();
+                barrier.wait();
+
+                for _ in 0..1000 {
+                    std::hint::spin_loop();
+                }
+
+                drop(guard);
+            });
+
+            barrier.wait();
+            let mut lock = Box::pin(mutex.lock());
+            assert!(matches!(lock.as_mut().poll(&mut cx), Poll::Ready(_)));
+        });
+
+        #[cfg(feature = "stats")]
+        assert_eq!(mutex.stats().registered, 0);
+    }
+
+    #[test]
+    fn poll_lock_from_another_task() {
+        let first = Arc::new(Count(AtomicUsize::new(0)));
+        let second = Arc::new(Count(AtomicUsize::new(0)));
+        let first_waker = Waker::from(first.clone());
+        let second_waker = Waker::from(second.clone());
+
+        let mut mutex = RawMutex::new();
+        mutex.set_spin_budget(0);
+        let mut waiter = crate::Waiter::new();
+
+        let guard = mutex.try_lock().unwrap();
+        let mut cx = Context::from_waker(&first_waker);
+        assert!(mutex.poll_lock(&mut cx, &mut waiter).is_pending());
+        assert!(waiter.is_pending());
+
+        // polling from another task replaces the registered waker
+        let mut cx = Context::from_waker(&second_waker);
+        assert!(mutex.poll_lock(&mut cx, &mut waiter).is_pending());
+
+        drop(guard);
+        assert_eq!(first.0.load(Ordering::SeqCst), 0);
+        assert_eq!(second.0.load(Ordering::SeqCst), 1);
+
+        assert!(mutex.poll_lock(&mut cx, &mut waiter).is_ready());
+        assert!(!waiter.is_pending());
+    }
+
+    #[test]
+    #[should_panic = "a pending `Waiter` was passed to a different lock"]
+    fn waiter_on_another_lock() {
+        let waker = Waker::from(Arc::new(Count(AtomicUsize::new(0))));
+        let mut cx = Context::from_waker(&waker);
+
+        let mut mutex = RawMutex::new();
+        mutex.set_spin_budget(0);
+        let other = RawMutex::new();
+        let mut waiter = crate::Waiter::new();
+
+        let _guard = mutex.try_lock().unwrap();
+        assert!(mutex.poll_lock(&mut cx, &mut waiter).is_pending());
+
+        // this would remove the registration of an unrelated task on `other`
+        other.cancel_poll(&mut waiter);
+    }
+}
@@ -0,0 +1,344 @@
+//! Async versions of [`locker::notify`], which block tasks in a [`WakerSet`]
+
+use crate::WakerSet;
+use locker::notify::{self, Wait, WaitQueue};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A [`WaitQueue`] that wakes the tasks registered in a [`WakerSet`]
+pub struct Wakers<W>(pub W);
+
+impl<W: WakerSet> WaitQueue for Wakers<W> {
+    #[inline]
+    fn wake_one(&self) {
+        self.0.notify_any();
+    }
+
+    #[inline]
+    fn wake_all(&self) {
+        self.0.notify_all();
+    }
+}
+
+impl<W: locker::Init> locker::Init for Wakers<W> {
+    const INIT: Self = Self(locker::Init::INIT);
+}
+
+/// Notify a single blocked task, or all of them
+///
+/// See [`locker::notify::Notify`] for the semantics of permits
+pub struct Notify<W> {
+    inner: notify::Notify<Wakers<W>>,
+}
+
+impl<W: locker::Init> locker::Init for Notify<W> {
+    const INIT: Self = Self::from_waker_set(locker::Init::INIT);
+}
+
+impl<W: locker::Init> Default for Notify<W> {
+    #[inline]
+    fn default() -> Self {
+        locker::Init::INIT
+    }
+}
+
+impl<W> Notify<W> {
+    /// Create a new `Notify` without a permit, which blocks tasks in `waker_set`
+    #[inline]
+    pub const fn from_waker_set(waker_set: W) -> Self {
+        Self {
+            inner: notify::Notify::with_queue(Wakers(waker_set)),
+        }
+    }
+
+    /// Try to consume the permit without waiting
+    #[inline]
+    pub fn try_acquire(&self) -> bool {
+        self.inner.try_acquire()
+    }
+}
+
+impl<W: WakerSet> Notify<W> {
+    /// Store a permit, and wake up a blocked task to consume it
+    #[inline]
+    pub fn notify_one(&self) {
+        self.inner.notify_one()
+    }
+
+    /// Wake up all tasks that are currently waiting, without storing a permit
+    #[inline]
+    pub fn notify_waiters(&self) {
+        self.inner.notify_waiters()
+    }
+
+    /// Wait until this task is notified
+    ///
+    /// The wait starts when this function is called, not when the future is first polled,
+    /// so calls to `notify_waiters` between the two will complete the future.
+    #[inline]
+    pub fn notified(&self) -> Notified<'_, W> {
+        Notified::new(&self.inner)
+    }
+}
+
+/// A future that completes when a [`Notify`] is notified
+pub struct Notified<'a, W: WakerSet> {
+    notify: &'a notify::Notify<Wakers<W>>,
+    wait: Wait,
+    key: Option<W::Index>,
+}
+
+impl<'a, W: WakerSet> Notified<'a, W> {
+    #[inline]
+    fn new(notify: &'a notify::Notify<Wakers<W>>) -> Self {
+        Self {
+            wait: notify.start_wait(),
+            notify,
+            key: None,
+        }
+    }
+}
+
+impl<W: WakerSet> Future for Notified<'_, W> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let this = Pin::into_inner(self);
+        let inner = this.notify;
+        let waker_set = &inner.queue().0;
+
+        if let Some(key) = this.key.take() {
+            waker_set.remove(key);
+        }
+
+        if inner.try_finish_wait(&this.wait) {
+            return Poll::Ready(());
+        }
+
+        let key = waker_set.insert(ctx);
+
+        // synchronizes with the fence in `notify_one` and `notify_waiters`
+        std::sync::atomic::fence(std::sync::atomic::Ordering::SeqCst);
+
+        if inner.try_finish_wait(&this.wait) {
+            waker_set.remove(key);
+            Poll::Ready(())
+        } else {
+            this.key = Some(key);
+            Poll::Pending
+        }
+    }
+}
+
+impl<W: WakerSet> Drop for Notified<'_, W> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            // if this task was woken to consume a permit, pass the wakeup on to another task
+            self.notify.queue().0.cancel(key);
+        }
+    }
+}
+
+/// A flag that can be set once, and waited on until it is set
+///
+/// See [`locker::notify::OnceFlag`]
+pub struct OnceFlag<W> {
+    inner: notify::OnceFlag<Wakers<W>>,
+}
+
+impl<W: locker::Init> locker::Init for OnceFlag<W> {
+    const INIT: Self = Self::from_waker_set(locker::Init::INIT);
+}
+
+impl<W: locker::Init> Default for OnceFlag<W> {
+    #[inline]
+    fn default() -> Self {
+        locker::Init::INIT
+    }
+}
+
+impl<W> OnceFlag<W> {
+    /// Create a new unset flag, which blocks tasks in `waker_set`
+    #[inline]
+    pub const fn from_waker_set(waker_set: W) -> Self {
+        Self {
+            inner: notify::OnceFlag::with_queue(Wakers(waker_set)),
+        }
+    }
+
+    /// Check if the flag is set
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.inner.is_set()
+    }
+}
+
+impl<W: WakerSet> OnceFlag<W> {
+    /// Set the flag and wake up all tasks waiting on it
+    ///
+    /// Returns false if the flag was already set
+    #[inline]
+    pub fn set(&self) -> bool {
+        self.inner.set()
+    }
+
+    /// Wait until the flag is set
+    #[inline]
+    pub fn wait(&self) -> WaitForSet<'_, W> {
+        WaitForSet {
+            flag: self,
+            notified: Notified::new(self.inner.notify()),
+        }
+    }
+}
+
+/// A future that completes when a [`OnceFlag`] is set
+///
+/// This is `Unpin`, so it can be used as the cancellation signal of the `*_cancellable`
+/// lock methods, see [`CancelToken::cancelled`](crate::cancel::CancelToken::cancelled)
+pub struct WaitForSet<'a, W: WakerSet> {
+    flag: &'a OnceFlag<W>,
+    notified: Notified<'a, W>,
+}
+
+impl<W: WakerSet> Future for WaitForSet<'_, W> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let this = Pin::into_inner(self);
+
+        loop {
+            if this.flag.is_set() {
+                return Poll::Ready(());
+            }
+
+            match Pin::new(&mut this.notified).poll(ctx) {
+                Poll::Pending => return Poll::Pending,
+                // start a new wait before checking the flag again
+                Poll::Ready(()) => this.notified = Notified::new(this.flag.inner.notify()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Notify, OnceFlag};
+    use crate::async_std::AsyncStdWakerSet;
+    use crate::conformance::Count;
+
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Waker};
+
+    #[test]
+    fn notify_one() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+        let woken = || count.0.load(Ordering::SeqCst);
+
+        let notify = Notify::<AsyncStdWakerSet>::default();
+        assert!(!notify.try_acquire());
+
+        let mut notified = notify.notified();
+        assert!(Pin::new(&mut notified).poll(&mut cx).is_pending());
+        assert_eq!(woken(), 0);
+
+        notify.notify_one();
+        assert_eq!(woken(), 1);
+        assert!(Pin::new(&mut notified).poll(&mut cx).is_ready());
+
+        // the permit was consumed by the woken task
+        assert!(!notify.try_acquire());
+
+        // without any waiters, the permit is stored for the next one
+        notify.notify_one();
+        assert!(Pin::new(&mut notify.notified()).poll(&mut cx).is_ready());
+        assert!(!notify.try_acquire());
+        assert_eq!(woken(), 1);
+    }
+
+    #[test]
+    fn notify_waiters() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let notify = Notify::<AsyncStdWakerSet>::default();
+
+        // the wait starts when `notified` is called, even before the first poll
+        let mut early = notify.notified();
+        let mut waiters = (0..3).map(|_| notify.notified()).collect::<Vec<_>>();
+        for waiter in &mut waiters {
+            assert!(Pin::new(waiter).poll(&mut cx).is_pending());
+        }
+
+        notify.notify_waiters();
+        assert_eq!(count.0.load(Ordering::SeqCst), 3);
+
+        for waiter in &mut waiters {
+            assert!(Pin::new(waiter).poll(&mut cx).is_ready());
+        }
+        assert!(Pin::new(&mut early).poll(&mut cx).is_ready());
+
+        // no permit is stored
+        assert!(!notify.try_acquire());
+        assert!(Pin::new(&mut notify.notified()).poll(&mut cx).is_pending());
+    }
+
+    #[test]
+    fn drop_woken() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+        let woken = || count.0.load(Ordering::SeqCst);
+
+        let notify = Notify::<AsyncStdWakerSet>::default();
+
+        let mut first = notify.notified();
+        let mut second = notify.notified();
+        assert!(Pin::new(&mut first).poll(&mut cx).is_pending());
+        assert!(Pin::new(&mut second).poll(&mut cx).is_pending());
+
+        notify.notify_one();
+        assert_eq!(woken(), 1);
+
+        // the woken task gave up, so the other task is woken to consume the permit
+        drop(first);
+        assert_eq!(woken(), 2);
+        assert!(Pin::new(&mut second).poll(&mut cx).is_ready());
+        assert!(!notify.try_acquire());
+    }
+
+    #[test]
+    fn once_flag() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let flag = OnceFlag::<AsyncStdWakerSet>::default();
+        assert!(!flag.is_set());
+
+        let mut waiters = (0..2).map(|_| flag.wait()).collect::<Vec<_>>();
+        for waiter in &mut waiters {
+            assert!(Pin::new(waiter).poll(&mut cx).is_pending());
+        }
+
+        assert!(flag.set());
+        assert!(!flag.set());
+        assert!(flag.is_set());
+        assert_eq!(count.0.load(Ordering::SeqCst), 2);
+
+        for waiter in &mut waiters {
+            assert!(Pin::new(waiter).poll(&mut cx).is_ready());
+        }
+
+        // the flag stays set
+        assert!(Pin::new(&mut flag.wait()).poll(&mut cx).is_ready());
+    }
+}
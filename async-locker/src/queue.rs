@@ -0,0 +1,239 @@
+//! An async version of [`locker::queue::MpmcQueue`]
+//!
+//! The ring buffer is behind a blocking mutex which is only held to move a single item,
+//! and tasks wait for items or free slots on a [`Notify`] for each side, so
+//! blocked tasks are parked in a [`WakerSet`] instead of blocking the thread.
+
+use crate::async_std::AsyncStdWakerSet;
+use crate::cancel::Cancellable;
+use crate::notify::Notify;
+use crate::WakerSet;
+
+use locker::mutex::default::Mutex;
+use std::collections::VecDeque;
+use std::future::Future;
+
+/// A bounded queue with async `push` and `pop`, see the [module docs](self) for details
+pub struct MpmcQueue<T, W = AsyncStdWakerSet> {
+    buffer: Mutex<VecDeque<T>>,
+    capacity: usize,
+    not_empty: Notify<W>,
+    not_full: Notify<W>,
+}
+
+impl<T, W: locker::Init> MpmcQueue<T, W> {
+    /// Create an empty queue which holds at most `capacity` items
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity != 0,
+            "a queue must be able to hold at least one item"
+        );
+
+        Self {
+            buffer: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            not_empty: locker::Init::INIT,
+            not_full: locker::Init::INIT,
+        }
+    }
+}
+
+impl<T, W: WakerSet> MpmcQueue<T, W> {
+    /// The maximum number of items in the queue
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of items in the queue
+    pub fn len(&self) -> usize {
+        self.buffer.lock().len()
+    }
+
+    /// Check if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.buffer.lock().is_empty()
+    }
+
+    /// Check if the queue is full
+    pub fn is_full(&self) -> bool {
+        self.buffer.lock().len() == self.capacity
+    }
+
+    /// Push an item to the back of the queue, or give it back if the queue is full
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        let mut buffer = self.buffer.lock();
+
+        if buffer.len() == self.capacity {
+            return Err(value);
+        }
+
+        buffer.push_back(value);
+        let has_room = buffer.len() < self.capacity;
+        drop(buffer);
+
+        self.not_empty.notify_one();
+
+        // the notification that woke this task may have been meant for another one too,
+        // so pass it on while there is room left
+        if has_room {
+            self.not_full.notify_one();
+        }
+
+        Ok(())
+    }
+
+    /// Pop an item from the front of the queue, if there is one
+    pub fn try_pop(&self) -> Option<T> {
+        let mut buffer = self.buffer.lock();
+        let value = buffer.pop_front()?;
+        let has_items = !buffer.is_empty();
+        drop(buffer);
+
+        self.not_full.notify_one();
+
+        // same as in `try_push`, pass the notification on while there are items left
+        if has_items {
+            self.not_empty.notify_one();
+        }
+
+        Some(value)
+    }
+
+    /// Push an item to the back of the queue, waiting while the queue is full
+    pub async fn push(&self, value: T) {
+        if self.push_cancellable(value, Never).await.is_err() {
+            unreachable!()
+        }
+    }
+
+    /// Pop an item from the front of the queue, waiting while the queue is empty
+    pub async fn pop(&self) -> T {
+        match self.pop_cancellable(Never).await {
+            Ok(value) => value,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Push an item to the back of the queue, unless `cancel` completes first
+    ///
+    /// If the operation was cancelled, the item is given back
+    pub async fn push_cancellable<C: Future + Unpin>(
+        &self,
+        mut value: T,
+        mut cancel: C,
+    ) -> Result<(), T> {
+        loop {
+            // start waiting before checking, so a slot that frees up in between isn't missed
+            let not_full = self.not_full.notified();
+
+            match self.try_push(value) {
+                Ok(()) => return Ok(()),
+                Err(rejected) => value = rejected,
+            }
+
+            if Cancellable::new(not_full, &mut cancel).await.is_err() {
+                return Err(value);
+            }
+        }
+    }
+
+    /// Pop an item from the front of the queue, unless `cancel` completes first
+    pub async fn pop_cancellable<C: Future + Unpin>(
+        &self,
+        mut cancel: C,
+    ) -> Result<T, crate::cancel::Cancelled> {
+        loop {
+            let not_empty = self.not_empty.notified();
+
+            if let Some(value) = self.try_pop() {
+                return Ok(value);
+            }
+
+            Cancellable::new(not_empty, &mut cancel).await?;
+        }
+    }
+}
+
+// a cancellation signal that never completes
+struct Never;
+
+impl Future for Never {
+    type Output = ();
+
+    #[inline]
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context,
+    ) -> std::task::Poll<Self::Output> {
+        std::task::Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MpmcQueue;
+    use crate::async_std::AsyncStdWakerSet;
+    use crate::cancel::{CancelToken, Cancelled};
+    use crate::conformance::Count;
+
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+
+    #[test]
+    fn push_and_pop() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+        let woken = || count.0.load(Ordering::SeqCst);
+
+        let queue = MpmcQueue::<u32>::new(1);
+
+        let mut pop = Box::pin(queue.pop());
+        assert!(pop.as_mut().poll(&mut cx).is_pending());
+
+        assert_eq!(queue.try_push(0), Ok(()));
+        assert_eq!(woken(), 1);
+        assert_eq!(queue.try_push(1), Err(1));
+
+        let mut push = Box::pin(queue.push(1));
+        assert!(push.as_mut().poll(&mut cx).is_pending());
+
+        assert_eq!(pop.as_mut().poll(&mut cx), Poll::Ready(0));
+        assert_eq!(woken(), 2);
+        assert_eq!(push.as_mut().poll(&mut cx), Poll::Ready(()));
+
+        assert!(queue.is_full());
+        assert_eq!(queue.try_pop(), Some(1));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn cancel() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let queue = MpmcQueue::<u32>::new(1);
+        let token = CancelToken::<AsyncStdWakerSet>::default();
+
+        assert_eq!(queue.try_push(0), Ok(()));
+
+        let mut push = Box::pin(queue.push_cancellable(1, token.cancelled()));
+        assert!(push.as_mut().poll(&mut cx).is_pending());
+
+        token.cancel();
+        assert_eq!(push.as_mut().poll(&mut cx), Poll::Ready(Err(1)));
+
+        assert_eq!(queue.try_pop(), Some(0));
+        let mut pop = Box::pin(queue.pop_cancellable(token.cancelled()));
+        assert_eq!(pop.as_mut().poll(&mut cx), Poll::Ready(Err(Cancelled)));
+    }
+}
@@ -1,8 +1,8 @@
-use std::cell::UnsafeCell;
+use locker::cell::SharedCell;
 
 use crate::share_lock::ShareGuard;
 use crate::WakerSet;
-use locker::remutex::RawReentrantMutex;
+use locker::remutex::{counter::Scalar, lock::ReLock, RawReentrantMutex, ThreadInfo};
 
 #[cfg(feature = "extra")]
 pub mod simple;
@@ -18,7 +18,7 @@ pub mod raw;
 #[repr(C)]
 pub struct ReentrantMutex<L, W, T: ?Sized> {
     raw: raw::ReentrantMutex<L, W>,
-    value: UnsafeCell<T>,
+    value: SharedCell<T>,
 }
 
 impl<L: RawReentrantMutex + locker::Init, W: WakerSet + locker::Init, T: Default> Default
@@ -40,7 +40,7 @@ impl<L, W, T> ReentrantMutex<L, W, T> {
     pub const fn from_raw_parts(raw: raw::ReentrantMutex<L, W>, value: T) -> Self {
         Self {
             raw,
-            value: UnsafeCell::new(value),
+            value: SharedCell::new(value),
         }
     }
 
@@ -70,7 +70,7 @@ impl<L, W, T: ?Sized> ReentrantMutex<L, W, T> {
 
             #[inline]
             pub const fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                unsafe { &mut *self.value.as_ptr() }
             }
         } else {
             #[inline]
@@ -80,7 +80,7 @@ impl<L, W, T: ?Sized> ReentrantMutex<L, W, T> {
 
             #[inline]
             pub fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                self.value.get_mut()
             }
         }
     }
@@ -102,13 +102,32 @@ impl<L: RawReentrantMutex + locker::Init, W: WakerSet + locker::Init, T> Reentra
     }
 }
 
+impl<L, S, I, W, T> ReentrantMutex<ReLock<L, S, I>, W, T>
+where
+    L: locker::mutex::RawMutex + locker::Init,
+    S: Scalar,
+    I: ThreadInfo,
+    W: WakerSet + locker::Init,
+{
+    /// Create a new reentrant mutex that uses the given thread info to
+    /// decide which thread or task owns the lock
+    ///
+    /// This shares the owner and re-entry bookkeeping with the blocking
+    /// [`ReentrantMutex`](locker::remutex::ReentrantMutex), so a custom
+    /// [`ThreadInfo`], like one that identifies the running task, works for both.
+    #[inline]
+    pub fn with_thread_info(thread_info: I, value: T) -> Self {
+        Self::from_raw_parts(raw::ReentrantMutex::with_thread_info(thread_info), value)
+    }
+}
+
 impl<L: RawReentrantMutex, W: WakerSet, T: ?Sized> ReentrantMutex<L, W, T>
 where
     L::ShareGuardTraits: locker::marker::Inhabitted,
 {
     #[inline]
     pub async fn lock(&self) -> ShareGuard<'_, L, W, T> {
-        unsafe { ShareGuard::from_raw_parts(self.raw.lock().await, self.value.get()) }
+        unsafe { ShareGuard::from_raw_parts(self.raw.lock().await, self.value.as_ptr()) }
     }
 
     #[inline]
@@ -116,8 +135,85 @@ where
         unsafe {
             Some(ShareGuard::from_raw_parts(
                 self.raw.try_lock()?,
-                self.value.get(),
+                self.value.as_ptr(),
             ))
         }
     }
 }
+
+#[test]
+fn task_owner() {
+    use crate::conformance::MockExecutor;
+    use locker::remutex::custom::FnThreadInfo;
+    use std::cell::Cell;
+    use std::num::NonZeroUsize;
+
+    thread_local! {
+        static TASK: Cell<usize> = const { Cell::new(1) };
+    }
+
+    fn current_task() -> NonZeroUsize {
+        NonZeroUsize::new(TASK.with(Cell::get)).unwrap()
+    }
+
+    type Lock = ReLock<locker::mutex::spin::SpinLock, u8, FnThreadInfo<fn() -> NonZeroUsize>>;
+
+    let thread_info = unsafe { FnThreadInfo::new(current_task as fn() -> NonZeroUsize) };
+    let remutex =
+        ReentrantMutex::<Lock, crate::async_std::AsyncStdWakerSet, _>::with_thread_info(
+            thread_info,
+            Cell::new(0),
+        );
+
+    let mut executor = MockExecutor::new();
+    let first = executor.spawn(async {
+        let outer = remutex.lock().await;
+        let inner = remutex.lock().await;
+        inner.set(inner.get() + 1);
+        drop(inner);
+        outer.set(outer.get() + 1);
+    });
+    let second = executor.spawn(async { remutex.lock().await.set(10) });
+
+    // the first task holds the lock while the second one runs
+    let guard = remutex.try_lock().unwrap();
+
+    TASK.with(|task| task.set(2));
+    assert!(!executor.poll(second));
+    TASK.with(|task| task.set(1));
+
+    assert!(executor.poll(first));
+    drop(guard);
+    assert!(executor.is_woken(second));
+
+    TASK.with(|task| task.set(2));
+    assert!(executor.poll(second));
+    drop(executor);
+
+    assert_eq!(remutex.into_inner().get(), 10);
+}
+
+#[test]
+fn guard_clone_and_map() {
+    use crate::share_lock::ShareGuard;
+    use locker::remutex::std_thread::StdThreadInfo;
+
+    type Lock = ReLock<locker::mutex::spin::SpinLock, u8, StdThreadInfo>;
+
+    let remutex =
+        ReentrantMutex::<Lock, crate::async_std::AsyncStdWakerSet, _>::new((1, [2, 3]));
+    let state = remutex.raw().inner().inner().state();
+
+    let guard = remutex.try_lock().unwrap();
+    let clone = guard.clone();
+
+    let [a, b] = clone.map(|(_, array)| array).split_into(|[a, b]| [a, b]);
+    let first = ShareGuard::try_clone(&guard).unwrap().map(|(first, _)| first);
+    assert_eq!((*first, *a, *b), (1, 2, 3));
+
+    // the lock is held until the last of the split guards is dropped
+    drop((guard, first, a));
+    assert!(state.is_owned_by_current_thread());
+    drop(b);
+    assert!(!state.is_owned_by_current_thread());
+}
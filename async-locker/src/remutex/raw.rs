@@ -1,6 +1,6 @@
 use super::RawReentrantMutex;
 use crate::{share_lock::RawShareGuard, WakerSet};
-use locker::remutex::raw;
+use locker::remutex::{counter::Scalar, lock::ReLock, raw, ThreadInfo};
 
 #[repr(C)]
 pub struct ReentrantMutex<L, W> {
@@ -73,6 +73,26 @@ impl<L: RawReentrantMutex + locker::Init, W: WakerSet + locker::Init> ReentrantM
     }
 }
 
+impl<L, S, I, W> ReentrantMutex<ReLock<L, S, I>, W>
+where
+    L: locker::mutex::RawMutex + locker::Init,
+    S: Scalar,
+    I: ThreadInfo,
+    W: WakerSet + locker::Init,
+{
+    /// Create a new reentrant mutex that uses the given thread info to
+    /// decide which thread or task owns the lock
+    #[inline]
+    pub fn with_thread_info(thread_info: I) -> Self {
+        unsafe {
+            Self::from_raw_parts(
+                raw::ReentrantMutex::from_raw(ReLock::with_thread_info(thread_info)),
+                locker::Init::INIT,
+            )
+        }
+    }
+}
+
 impl<L: RawReentrantMutex, W: WakerSet> ReentrantMutex<L, W>
 where
     L::ShareGuardTraits: locker::marker::Inhabitted,
@@ -1,16 +1,17 @@
-use std::cell::UnsafeCell;
+use locker::cell::SharedCell;
 
 use crate::exclusive_lock::ExclusiveGuard;
 use crate::share_lock::ShareGuard;
-use crate::WakerSet;
+use crate::{Waiter, WakerSet};
 use locker::rwlock::RawRwLock;
+use std::task::{Context, Poll};
 
-mod raw;
+pub mod raw;
 
 #[repr(C)]
 pub struct RwLock<L, W, T: ?Sized> {
     raw: raw::RwLock<L, W>,
-    value: UnsafeCell<T>,
+    value: SharedCell<T>,
 }
 
 impl<L: RawRwLock + locker::Init, W: WakerSet + locker::Init, T: Default> Default
@@ -33,7 +34,7 @@ impl<L, W, T> RwLock<L, W, T> {
     pub const fn from_raw_parts(raw: raw::RwLock<L, W>, value: T) -> Self {
         Self {
             raw,
-            value: UnsafeCell::new(value),
+            value: SharedCell::new(value),
         }
     }
 
@@ -54,6 +55,23 @@ impl<L, W, T: ?Sized> RwLock<L, W, T> {
         &self.raw
     }
 
+    /// Abandon an operation that was started by `poll_write` or `poll_read`,
+    /// see [`raw::RwLock::cancel_poll`]
+    #[inline]
+    pub fn cancel_poll(&self, waiter: &mut Waiter<W>) -> bool
+    where
+        W: WakerSet,
+    {
+        self.raw.cancel_poll(waiter)
+    }
+
+    /// Set the maximum number of blocked operations that can be woken at once,
+    /// see [`raw::RwLock::set_max_wake_batch`]
+    #[inline]
+    pub fn set_max_wake_batch(&mut self, max_wake_batch: usize) {
+        self.raw.set_max_wake_batch(max_wake_batch)
+    }
+
     cfg_if::cfg_if! {
         if #[cfg(feature = "nightly")] {
             #[inline]
@@ -63,7 +81,7 @@ impl<L, W, T: ?Sized> RwLock<L, W, T> {
 
             #[inline]
             pub const fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                unsafe { &mut *self.value.as_ptr() }
             }
         } else {
             #[inline]
@@ -73,7 +91,7 @@ impl<L, W, T: ?Sized> RwLock<L, W, T> {
 
             #[inline]
             pub fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                self.value.get_mut()
             }
         }
     }
@@ -102,7 +120,29 @@ where
 {
     #[inline]
     pub async fn write(&self) -> ExclusiveGuard<'_, L, W, T> {
-        unsafe { ExclusiveGuard::from_raw_parts(self.raw.write().await, self.value.get()) }
+        unsafe { ExclusiveGuard::from_raw_parts(self.raw.write().await, self.value.as_ptr()) }
+    }
+
+    /// Acquire a write lock, unless `cancel` completes first, see [`raw::RwLock::write_cancellable`]
+    #[inline]
+    pub async fn write_cancellable<C: std::future::Future + Unpin>(
+        &self,
+        cancel: C,
+    ) -> Result<ExclusiveGuard<'_, L, W, T>, crate::cancel::Cancelled> {
+        let raw = self.raw.write_cancellable(cancel).await?;
+        unsafe { Ok(ExclusiveGuard::from_raw_parts(raw, self.value.as_ptr())) }
+    }
+
+    /// Try to acquire a write lock from a manual `Future::poll`, see [`raw::RwLock::poll_write`]
+    #[inline]
+    pub fn poll_write(
+        &self,
+        ctx: &mut Context,
+        waiter: &mut Waiter<W>,
+    ) -> Poll<ExclusiveGuard<'_, L, W, T>> {
+        self.raw
+            .poll_write(ctx, waiter)
+            .map(|raw| unsafe { ExclusiveGuard::from_raw_parts(raw, self.value.as_ptr()) })
     }
 
     #[inline]
@@ -110,14 +150,36 @@ where
         unsafe {
             Some(ExclusiveGuard::from_raw_parts(
                 self.raw.try_write()?,
-                self.value.get(),
+                self.value.as_ptr(),
             ))
         }
     }
 
     #[inline]
     pub async fn read(&self) -> ShareGuard<'_, L, W, T> {
-        unsafe { ShareGuard::from_raw_parts(self.raw.read().await, self.value.get()) }
+        unsafe { ShareGuard::from_raw_parts(self.raw.read().await, self.value.as_ptr()) }
+    }
+
+    /// Acquire a read lock, unless `cancel` completes first, see [`raw::RwLock::read_cancellable`]
+    #[inline]
+    pub async fn read_cancellable<C: std::future::Future + Unpin>(
+        &self,
+        cancel: C,
+    ) -> Result<ShareGuard<'_, L, W, T>, crate::cancel::Cancelled> {
+        let raw = self.raw.read_cancellable(cancel).await?;
+        unsafe { Ok(ShareGuard::from_raw_parts(raw, self.value.as_ptr())) }
+    }
+
+    /// Try to acquire a read lock from a manual `Future::poll`, see [`raw::RwLock::poll_read`]
+    #[inline]
+    pub fn poll_read(
+        &self,
+        ctx: &mut Context,
+        waiter: &mut Waiter<W>,
+    ) -> Poll<ShareGuard<'_, L, W, T>> {
+        self.raw
+            .poll_read(ctx, waiter)
+            .map(|raw| unsafe { ShareGuard::from_raw_parts(raw, self.value.as_ptr()) })
     }
 
     #[inline]
@@ -125,7 +187,7 @@ where
         unsafe {
             Some(ShareGuard::from_raw_parts(
                 self.raw.try_read()?,
-                self.value.get(),
+                self.value.as_ptr(),
             ))
         }
     }
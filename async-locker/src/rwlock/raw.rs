@@ -1,11 +1,19 @@
 use super::RawRwLock;
-use crate::{exclusive_lock::RawExclusiveGuard, share_lock::RawShareGuard, WakerSet};
+use crate::{exclusive_lock::RawExclusiveGuard, share_lock::RawShareGuard, Waiter, WakerSet};
 use locker::rwlock::raw;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The default number of blocked operations that can be woken at once
+/// when the rwlock is released, see [`RwLock::set_max_wake_batch`]
+pub const DEFAULT_MAX_WAKE_BATCH: usize = 16;
 
 #[repr(C)]
 pub struct RwLock<L, W> {
     raw: raw::RwLock<L>,
     waker_set: W,
+    max_wake_batch: usize,
 }
 
 impl<L: RawRwLock + locker::Init, W: WakerSet + locker::Init> Default for RwLock<L, W> {
@@ -21,7 +29,11 @@ impl<L, W> RwLock<L, W> {
     /// You must pass `RawLockInfo::INIT` as lock
     #[inline]
     pub const unsafe fn from_raw_parts(raw: raw::RwLock<L>, waker_set: W) -> Self {
-        Self { raw, waker_set }
+        Self {
+            raw,
+            waker_set,
+            max_wake_batch: DEFAULT_MAX_WAKE_BATCH,
+        }
     }
 
     #[inline]
@@ -34,6 +46,28 @@ impl<L, W> RwLock<L, W> {
         &self.raw
     }
 
+    /// The maximum number of blocked operations that can be woken, but not yet completed, at once
+    #[inline]
+    pub const fn max_wake_batch(&self) -> usize {
+        self.max_wake_batch
+    }
+
+    /// Set the maximum number of blocked operations that can be woken, but not yet completed, at once
+    ///
+    /// When the rwlock is released, at most `max_wake_batch` blocked operations are woken,
+    /// instead of all of them at once. Then as each woken reader acquires the rwlock, it wakes
+    /// the next blocked operation. This avoids waking thousands of tasks at once when a writer
+    /// releases a heavily contended rwlock.
+    ///
+    /// # Panics
+    ///
+    /// If `max_wake_batch` is zero
+    #[inline]
+    pub fn set_max_wake_batch(&mut self, max_wake_batch: usize) {
+        assert_ne!(max_wake_batch, 0, "at least one operation must be woken");
+        self.max_wake_batch = max_wake_batch;
+    }
+
     cfg_if::cfg_if! {
         if #[cfg(feature = "nightly")] {
             #[inline]
@@ -76,103 +110,242 @@ where
 {
     #[inline]
     pub async fn write(&self) -> RawExclusiveGuard<'_, L, W> {
-        use crate::slab::Index;
-
-        pub struct LockFuture<'a, L, W, I>(&'a RwLock<L, W>, Option<I>);
-
-        use std::pin::Pin;
-        use std::task::{Context, Poll};
-
-        impl<'a, L: RawRwLock, W: WakerSet> std::future::Future for LockFuture<'a, L, W, W::Index>
-        where
-            L::ExclusiveGuardTraits: locker::marker::Inhabitted,
-            L::ShareGuardTraits: locker::marker::Inhabitted,
-        {
-            type Output = RawExclusiveGuard<'a, L, W>;
-
-            fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
-                let Self(rwlock, opt_key) = Pin::into_inner(self);
-
-                if let Some(key) = opt_key.take() {
-                    rwlock.waker_set.remove(key);
-                }
-
-                let key = match rwlock.try_write() {
-                    Some(gaurd) => return Poll::Ready(gaurd),
-                    None => rwlock.waker_set.insert(ctx),
-                };
-
-                match rwlock.try_write() {
-                    Some(gaurd) => {
-                        rwlock.waker_set.remove(key);
-                        Poll::Ready(gaurd)
-                    }
-                    None => {
-                        *opt_key = Some(key);
-                        Poll::Pending
-                    }
-                }
+        LockFuture::new(self, RwLock::poll_write).await
+    }
+
+    /// Try to acquire a write lock, and register the current task in `waiter` if that
+    /// would block
+    ///
+    /// This drives a lock acquisition from a manual `Future::poll`, without storing the
+    /// future returned by [`write`](RwLock::write), and `waiter` must be used for the same
+    /// operation until it completes. If the operation is abandoned while it is pending,
+    /// pass `waiter` to [`cancel_poll`](RwLock::cancel_poll).
+    ///
+    /// # Panics
+    ///
+    /// If `waiter` is pending on a different lock
+    pub fn poll_write(
+        &self,
+        ctx: &mut Context,
+        waiter: &mut Waiter<W>,
+    ) -> Poll<RawExclusiveGuard<'_, L, W>> {
+        waiter.remove(&self.waker_set);
+
+        if let Some(guard) = self.try_write() {
+            return Poll::Ready(guard);
+        }
+
+        waiter.insert(&self.waker_set, ctx);
+
+        match self.try_write() {
+            Some(guard) => {
+                waiter.remove(&self.waker_set);
+                Poll::Ready(guard)
             }
+            None => Poll::Pending,
         }
+    }
 
-        LockFuture(self, None).await
+    /// Acquire a write lock, unless `cancel` completes first
+    ///
+    /// `cancel` can be any `Future + Unpin`, like [`CancelToken::cancelled`](crate::cancel::CancelToken::cancelled)
+    #[inline]
+    pub async fn write_cancellable<C: std::future::Future + Unpin>(
+        &self,
+        cancel: C,
+    ) -> Result<RawExclusiveGuard<'_, L, W>, crate::cancel::Cancelled> {
+        crate::cancel::Cancellable::new(self.write(), cancel).await
     }
 
     #[inline]
     pub fn try_write(&self) -> Option<RawExclusiveGuard<'_, L, W>> {
-        Some(RawExclusiveGuard::from_raw_parts(
-            self.raw.try_write()?,
-            &self.waker_set,
-        ))
+        Some(
+            RawExclusiveGuard::from_raw_parts(self.raw.try_write()?, &self.waker_set)
+                .with_wake_batch(self.max_wake_batch),
+        )
     }
 
     #[inline]
     pub async fn read(&self) -> RawShareGuard<'_, L, W> {
-        pub struct LockFuture<'a, L, W, I>(&'a RwLock<L, W>, Option<I>);
-
-        use std::pin::Pin;
-        use std::task::{Context, Poll};
-
-        impl<'a, L: RawRwLock, W: WakerSet> std::future::Future for LockFuture<'a, L, W, W::Index>
-        where
-            L::ExclusiveGuardTraits: locker::marker::Inhabitted,
-            L::ShareGuardTraits: locker::marker::Inhabitted,
-        {
-            type Output = RawShareGuard<'a, L, W>;
-
-            fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
-                let Self(rwlock, opt_key) = Pin::into_inner(self);
-
-                if let Some(key) = opt_key.take() {
-                    rwlock.waker_set.remove(key);
-                }
-
-                let key = match rwlock.try_read() {
-                    Some(gaurd) => return Poll::Ready(gaurd),
-                    None => rwlock.waker_set.insert(ctx),
-                };
-
-                match rwlock.try_read() {
-                    Some(gaurd) => {
-                        rwlock.waker_set.remove(key);
-                        Poll::Ready(gaurd)
-                    }
-                    None => {
-                        *opt_key = Some(key);
-                        Poll::Pending
-                    }
-                }
+        LockFuture::new(self, RwLock::poll_read).await
+    }
+
+    /// Try to acquire a read lock, and register the current task in `waiter` if that
+    /// would block
+    ///
+    /// See [`poll_write`](RwLock::poll_write) for how to use `waiter`
+    pub fn poll_read(
+        &self,
+        ctx: &mut Context,
+        waiter: &mut Waiter<W>,
+    ) -> Poll<RawShareGuard<'_, L, W>> {
+        let woken = waiter.remove(&self.waker_set);
+
+        if let Some(guard) = self.try_read() {
+            // this operation was woken, so wake the next one in the batch
+            if woken {
+                self.waker_set.notify_batch(self.max_wake_batch);
+            }
+
+            return Poll::Ready(guard);
+        }
+
+        waiter.insert(&self.waker_set, ctx);
+
+        match self.try_read() {
+            Some(guard) => {
+                waiter.remove(&self.waker_set);
+                Poll::Ready(guard)
             }
+            None => Poll::Pending,
         }
+    }
 
-        LockFuture(self, None).await
+    /// Acquire a read lock, unless `cancel` completes first
+    ///
+    /// `cancel` can be any `Future + Unpin`, like [`CancelToken::cancelled`](crate::cancel::CancelToken::cancelled)
+    #[inline]
+    pub async fn read_cancellable<C: std::future::Future + Unpin>(
+        &self,
+        cancel: C,
+    ) -> Result<RawShareGuard<'_, L, W>, crate::cancel::Cancelled> {
+        crate::cancel::Cancellable::new(self.read(), cancel).await
     }
 
     #[inline]
     pub fn try_read(&self) -> Option<RawShareGuard<'_, L, W>> {
-        Some(RawShareGuard::from_raw_parts(
-            self.raw.try_read()?,
-            &self.waker_set,
-        ))
+        Some(
+            RawShareGuard::from_raw_parts(self.raw.try_read()?, &self.waker_set)
+                .with_wake_batch(self.max_wake_batch),
+        )
+    }
+}
+
+impl<L, W: WakerSet> RwLock<L, W> {
+    /// Abandon an operation that was started by [`poll_write`](RwLock::poll_write)
+    /// or [`poll_read`](RwLock::poll_read)
+    ///
+    /// Returns `true` if another blocked operation was notified in its place
+    #[inline]
+    pub fn cancel_poll(&self, waiter: &mut Waiter<W>) -> bool {
+        waiter.cancel(&self.waker_set)
+    }
+}
+
+/// A future that drives one of the `poll_*` methods of a [`RwLock`]
+struct LockFuture<'a, L, W: WakerSet, F> {
+    rwlock: &'a RwLock<L, W>,
+    waiter: Waiter<W>,
+    poll: F,
+}
+
+impl<'a, L, W: WakerSet, F> LockFuture<'a, L, W, F> {
+    #[inline]
+    fn new(rwlock: &'a RwLock<L, W>, poll: F) -> Self {
+        Self {
+            rwlock,
+            waiter: Waiter::new(),
+            poll,
+        }
+    }
+}
+
+impl<L, W: WakerSet, F> Drop for LockFuture<'_, L, W, F> {
+    fn drop(&mut self) {
+        // if this operation was notified, pass the notification on to another one
+        self.rwlock.cancel_poll(&mut self.waiter);
+    }
+}
+
+impl<'a, L, W: WakerSet, F, G> Future for LockFuture<'a, L, W, F>
+where
+    F: FnMut(&'a RwLock<L, W>, &mut Context, &mut Waiter<W>) -> Poll<G> + Unpin,
+{
+    type Output = G;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let this = Pin::into_inner(self);
+        (this.poll)(this.rwlock, ctx, &mut this.waiter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RwLock;
+    use crate::async_std::AsyncStdWakerSet;
+    use crate::conformance::Count;
+
+    use std::future::Future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+
+    #[test]
+    fn wake_batch() {
+        let mut rwlock = RwLock::<locker::rwlock::spin::SpinLock, AsyncStdWakerSet>::new();
+        rwlock.set_max_wake_batch(2);
+
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+        let woken = || count.0.load(Ordering::SeqCst);
+
+        let writer = rwlock.try_write().unwrap();
+        let mut readers: Vec<_> = (0..5).map(|_| Box::pin(rwlock.read())).collect();
+
+        for reader in &mut readers {
+            assert!(reader.as_mut().poll(&mut cx).is_pending());
+        }
+
+        // only the first batch is woken
+        drop(writer);
+        assert_eq!(woken(), 2);
+
+        // each woken reader that acquires the lock wakes the next one
+        let mut guards = Vec::new();
+        for (i, reader) in readers.iter_mut().enumerate() {
+            match reader.as_mut().poll(&mut cx) {
+                Poll::Ready(guard) => guards.push(guard),
+                Poll::Pending => panic!("reader {} wasn't able to acquire the lock", i),
+            }
+
+            assert_eq!(woken(), (i + 3).min(5));
+        }
+    }
+
+    #[test]
+    fn cancel_poll() {
+        let mut rwlock = RwLock::<locker::rwlock::spin::SpinLock, AsyncStdWakerSet>::new();
+        rwlock.set_max_wake_batch(1);
+
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+        let woken = || count.0.load(Ordering::SeqCst);
+
+        let reader = rwlock.try_read().unwrap();
+        let mut writer = crate::Waiter::new();
+        let mut other_writer = crate::Waiter::new();
+        assert!(rwlock.poll_write(&mut cx, &mut writer).is_pending());
+        assert!(rwlock.poll_write(&mut cx, &mut other_writer).is_pending());
+
+        drop(reader);
+        assert_eq!(woken(), 1);
+
+        // the notification of the abandoned writer is passed on to the other one
+        assert!(rwlock.cancel_poll(&mut writer));
+        assert_eq!(woken(), 2);
+        assert!(!rwlock.cancel_poll(&mut writer));
+
+        let guard = match rwlock.poll_write(&mut cx, &mut other_writer) {
+            Poll::Ready(guard) => guard,
+            Poll::Pending => panic!("the writer wasn't able to acquire the lock"),
+        };
+
+        let mut reader = crate::Waiter::new();
+        assert!(rwlock.poll_read(&mut cx, &mut reader).is_pending());
+        drop(guard);
+        assert!(rwlock.poll_read(&mut cx, &mut reader).is_ready());
     }
 }
@@ -116,6 +116,26 @@ impl<'a, L: RawShareLock + RawLockInfo, W: WakerSet + ?Sized, T: ?Sized, St>
         })
     }
 
+    /// Make `N` new `MappedShareGuard`s for components of the locked data
+    pub fn split_into<U: ?Sized, const N: usize>(
+        self,
+        f: impl FnOnce(&T) -> [&U; N],
+    ) -> [ShareGuard<'a, L, W, U, Mapped>; N] {
+        let values = f(unsafe { &*self.value });
+        let raw = self.raw;
+
+        values.map(|value| unsafe { ShareGuard::from_raw_parts(raw.clone(), value) })
+    }
+
+    /// Acquire another share lock for the same data, like `clone`, but returns `None`
+    /// instead of panicking if the number of share locks would overflow
+    pub fn try_clone(g: &Self) -> Option<Self>
+    where
+        L::ShareGuardTraits: locker::marker::Inhabitted,
+    {
+        Some(unsafe { ShareGuard::from_raw_parts(g.raw.try_split()?, g.value) })
+    }
+
     #[allow(clippy::type_complexity)]
     pub fn try_split_map<E, U: ?Sized, V: ?Sized>(
         self,
@@ -150,3 +170,11 @@ impl<L: RawShareLock + RawLockInfo, W: WakerSet + ?Sized, T: ?Sized, St> Deref
         unsafe { &*self.value }
     }
 }
+
+impl<L: RawShareLock + RawLockInfo, W: WakerSet + ?Sized, T: ?Sized, St> Clone
+    for ShareGuard<'_, L, W, T, St>
+{
+    fn clone(&self) -> Self {
+        unsafe { ShareGuard::from_raw_parts(self.raw.clone(), self.value) }
+    }
+}
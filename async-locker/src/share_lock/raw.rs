@@ -6,13 +6,18 @@ use std::mem::ManuallyDrop;
 pub struct RawShareGuard<'a, L: RawShareLock + RawLockInfo, W: WakerSet + ?Sized> {
     inner: ManuallyDrop<Inner<'a, L>>,
     waker_set: &'a W,
+    wake_batch: usize,
 }
 
 impl<L: RawShareLock + RawLockInfo, W: WakerSet + ?Sized> Drop for RawShareGuard<'_, L, W> {
     fn drop(&mut self) {
         unsafe {
             ManuallyDrop::drop(&mut self.inner);
-            self.waker_set.notify_any();
+
+            // don't touch the waker storage if nothing is waiting on this lock
+            if !self.waker_set.is_empty() {
+                self.waker_set.notify_batch(self.wake_batch);
+            }
         }
     }
 }
@@ -24,14 +29,14 @@ impl<'a, L: RawShareLock + RawLockInfo, W: WakerSet + ?Sized> RawShareGuard<'a,
             ///
             /// The share lock must be held
             pub const fn from_raw_parts(inner: Inner<'a, L>, waker_set: &'a W) -> Self {
-                Self { inner: ManuallyDrop::new(inner), waker_set }
+                Self { inner: ManuallyDrop::new(inner), waker_set, wake_batch: 1 }
             }
         } else {
             /// # Safety
             ///
             /// The share lock must be held
             pub fn from_raw_parts(inner: Inner<'a, L>, waker_set: &'a W) -> Self {
-                Self { inner: ManuallyDrop::new(inner), waker_set }
+                Self { inner: ManuallyDrop::new(inner), waker_set, wake_batch: 1 }
             }
         }
     }
@@ -42,6 +47,34 @@ impl<'a, L: RawShareLock + RawLockInfo, W: WakerSet + ?Sized> RawShareGuard<'a,
         &self.inner
     }
 
+    /// Notify up to `wake_batch` blocked operations when this guard is released,
+    /// instead of just one
+    pub fn with_wake_batch(mut self, wake_batch: usize) -> Self {
+        self.wake_batch = wake_batch;
+        self
+    }
+
+    /// Acquire another share lock on the same lock, like `clone`, but returns `None`
+    /// instead of panicking if the number of share locks would overflow
+    pub fn try_split(&self) -> Option<Self>
+    where
+        L::ShareGuardTraits: locker::marker::Inhabitted,
+    {
+        // the lock is borrowed for `'a` by `self.inner`
+        let lock: &'a L = unsafe { &*(self.inner.inner() as *const L) };
+
+        unsafe {
+            if lock.shr_try_split() {
+                Some(
+                    Self::from_raw_parts(Inner::from_raw(lock), self.waker_set)
+                        .with_wake_batch(self.wake_batch),
+                )
+            } else {
+                None
+            }
+        }
+    }
+
     pub fn into_raw_parts(self) -> (Inner<'a, L>, &'a W) {
         let mut this = std::mem::ManuallyDrop::new(self);
 
@@ -124,6 +157,6 @@ impl<'a, L: RawShareLock + RawLockInfo, W: WakerSet + ?Sized> RawShareGuard<'a,
 
 impl<L: RawShareLock + RawLockInfo, W: WakerSet + ?Sized> Clone for RawShareGuard<'_, L, W> {
     fn clone(&self) -> Self {
-        Self::from_raw_parts((*self.inner).clone(), self.waker_set)
+        Self::from_raw_parts((*self.inner).clone(), self.waker_set).with_wake_batch(self.wake_batch)
     }
 }
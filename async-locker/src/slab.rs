@@ -44,6 +44,7 @@ impl<T> Slab<T> {
             debug_assert_eq!(self.next, self.entries.len());
 
             self.entries.push(Entry::Occupied(value));
+            self.next = self.entries.len();
         }
 
         Index(index)
@@ -52,12 +53,16 @@ impl<T> Slab<T> {
     pub fn remove(&mut self, Index(index): Index) -> T {
         let entry = &mut self.entries[index];
 
-        let entry = std::mem::replace(entry, Entry::Vacant(self.next));
-        self.next = index;
-
-        match entry {
-            Entry::Vacant(_) => panic!("tried to remove from an empty slot"),
-            Entry::Occupied(value) => value,
+        match std::mem::replace(entry, Entry::Vacant(self.next)) {
+            Entry::Vacant(next) => {
+                *entry = Entry::Vacant(next);
+                panic!("tried to remove from an empty slot")
+            }
+            Entry::Occupied(value) => {
+                self.next = index;
+                self.len -= 1;
+                value
+            }
         }
     }
 
@@ -79,12 +84,12 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let len = &mut self.len;
-        self.inner.by_ref().find_map(|(index, entry)| {
-            *len -= 1;
-            match entry {
-                Entry::Occupied(value) => Some((Index(index), value)),
-                Entry::Vacant(_) => None,
+        self.inner.by_ref().find_map(|(index, entry)| match entry {
+            Entry::Occupied(value) => {
+                *len -= 1;
+                Some((Index(index), value))
             }
+            Entry::Vacant(_) => None,
         })
     }
 
@@ -92,3 +97,42 @@ impl<'a, T> Iterator for IterMut<'a, T> {
         (self.len, Some(self.len))
     }
 }
+
+#[test]
+fn insert_remove() {
+    let mut slab = Slab::new();
+
+    let a = slab.insert('a');
+    let b = slab.insert('b');
+    let c = slab.insert('c');
+    assert_eq!(slab.len(), 3);
+
+    assert_eq!(slab.remove(b), 'b');
+    assert_eq!(slab.len(), 2);
+
+    // vacant slots are reused before growing
+    let d = slab.insert('d');
+    assert!(d == b);
+    assert_eq!(slab.len(), 3);
+
+    let mut iter = slab.iter_mut();
+    assert_eq!(iter.size_hint(), (3, Some(3)));
+    let values: Vec<_> = iter.by_ref().map(|(_, &mut value)| value).collect();
+    assert_eq!(values, ['a', 'd', 'c']);
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+
+    assert_eq!(slab.remove(a), 'a');
+    assert_eq!(slab.remove(c), 'c');
+    assert_eq!(slab.remove(d), 'd');
+    assert!(slab.is_empty());
+}
+
+#[test]
+#[should_panic = "tried to remove from an empty slot"]
+fn remove_vacant() {
+    let mut slab = Slab::new();
+
+    let a = slab.insert(0);
+    slab.remove(a);
+    slab.remove(a);
+}
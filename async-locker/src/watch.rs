@@ -0,0 +1,267 @@
+//! Watch a value behind a sync [`RwLock`] from async tasks
+//!
+//! [`Watch`] is for values that are written by sync threads, and read by async tasks
+//! that react to every change, like a configuration that can be reloaded. Writers
+//! modify the value with [`send_modify`](Watch::send_modify) or [`send`](Watch::send).
+//! Each [`Subscriber`] can wait until the value changes with [`changed`](Subscriber::changed),
+//! and then read a snapshot of it with [`borrow`](Subscriber::borrow).
+//!
+//! Writers never wait for subscribers that are waiting for a change, but they take the write
+//! lock, so they do block while a subscriber holds the read guard returned by `borrow`.
+//!
+//! Subscribers only see the latest value, so several writes that happen while a
+//! subscriber isn't waiting are observed as a single change.
+
+use crate::notify::Notify;
+use crate::WakerSet;
+use locker::rwlock::{RawRwLock, RwLock};
+use locker::share_lock::ShareGuard;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A value behind a sync [`RwLock`], which async tasks can wait for changes of
+pub struct Watch<L, W, T: ?Sized> {
+    version: AtomicUsize,
+    notify: Notify<W>,
+    rwlock: RwLock<L, T>,
+}
+
+impl<L: locker::Init, W: locker::Init, T> Watch<L, W, T> {
+    /// Create a new `Watch` with an initial value
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self::from_raw_parts(
+            RwLock::from_raw_parts(locker::Init::INIT, value),
+            locker::Init::INIT,
+        )
+    }
+}
+
+impl<L, W, T> Watch<L, W, T> {
+    /// Create a new `Watch` around `rwlock`, which blocks tasks in `waker_set`
+    #[inline]
+    pub const fn from_raw_parts(rwlock: RwLock<L, T>, waker_set: W) -> Self {
+        Self {
+            version: AtomicUsize::new(0),
+            notify: Notify::from_waker_set(waker_set),
+            rwlock,
+        }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.rwlock.into_inner()
+    }
+}
+
+impl<L, W, T: ?Sized> Watch<L, W, T> {
+    /// The `RwLock` that holds the value
+    ///
+    /// Writing through the `RwLock` directly doesn't notify subscribers
+    #[inline]
+    pub const fn rwlock(&self) -> &RwLock<L, T> {
+        &self.rwlock
+    }
+
+    /// Subscribe to changes that happen after this call
+    #[inline]
+    pub fn subscribe(&self) -> Subscriber<'_, L, W, T> {
+        Subscriber {
+            watch: self,
+            seen: self.version(),
+        }
+    }
+
+    #[inline]
+    fn version(&self) -> usize {
+        self.version.load(Ordering::Acquire)
+    }
+}
+
+impl<L: RawRwLock, W: WakerSet, T: ?Sized> Watch<L, W, T>
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+    L::ShareGuardTraits: locker::marker::Inhabitted,
+{
+    /// Modify the value, and notify all subscribers once the write lock is released
+    pub fn send_modify<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let output = f(&mut self.rwlock.write());
+
+        self.version.fetch_add(1, Ordering::Release);
+        self.notify.notify_waiters();
+
+        output
+    }
+
+    /// Read the current value, without marking it as seen by any subscriber
+    #[inline]
+    pub fn borrow(&self) -> ShareGuard<'_, L, T> {
+        self.rwlock.read()
+    }
+}
+
+impl<L: RawRwLock, W: WakerSet, T> Watch<L, W, T>
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+    L::ShareGuardTraits: locker::marker::Inhabitted,
+{
+    /// Replace the value, and notify all subscribers, returns the old value
+    #[inline]
+    pub fn send(&self, value: T) -> T {
+        self.send_modify(|old| std::mem::replace(old, value))
+    }
+}
+
+/// A handle that waits for changes of a [`Watch`], created by [`Watch::subscribe`]
+pub struct Subscriber<'a, L, W, T: ?Sized> {
+    watch: &'a Watch<L, W, T>,
+    seen: usize,
+}
+
+impl<L, W, T: ?Sized> Clone for Subscriber<'_, L, W, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            watch: self.watch,
+            seen: self.seen,
+        }
+    }
+}
+
+impl<'a, L, W, T: ?Sized> Subscriber<'a, L, W, T> {
+    /// The `Watch` that this subscribes to
+    #[inline]
+    pub const fn watch(&self) -> &'a Watch<L, W, T> {
+        self.watch
+    }
+
+    /// Check if the value was changed since it was last marked as seen
+    #[inline]
+    pub fn has_changed(&self) -> bool {
+        self.watch.version() != self.seen
+    }
+}
+
+impl<'a, L: RawRwLock, W: WakerSet, T: ?Sized> Subscriber<'a, L, W, T>
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+    L::ShareGuardTraits: locker::marker::Inhabitted,
+{
+    /// Wait until the value changes, and mark the change as seen
+    ///
+    /// If the value was changed since it was last marked as seen, this completes immediately.
+    /// The version is updated after the write lock is released, so a value that was read
+    /// right before this was called may already include the change.
+    pub async fn changed(&mut self) {
+        loop {
+            // start waiting before checking the version, so a change that happens
+            // in between wakes this task
+            let notified = self.watch.notify.notified();
+            let version = self.watch.version();
+
+            if version != self.seen {
+                self.seen = version;
+                return;
+            }
+
+            notified.await;
+        }
+    }
+
+    /// Read the current value, without marking it as seen
+    ///
+    /// The read lock blocks writers, so the guard should be dropped before awaiting.
+    #[inline]
+    pub fn borrow(&self) -> ShareGuard<'a, L, T> {
+        self.watch.borrow()
+    }
+
+    /// Read the current value, and mark it as seen
+    #[inline]
+    pub fn borrow_and_update(&mut self) -> ShareGuard<'a, L, T> {
+        // the version is read before the value, so a write that isn't included in the
+        // value will be seen as a change
+        self.seen = self.watch.version();
+        self.watch.borrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Watch;
+    use crate::async_std::AsyncStdWakerSet;
+    use crate::conformance::Count;
+
+    use std::future::Future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Waker};
+
+    type Config = Watch<locker::rwlock::spin::SpinLock, AsyncStdWakerSet, String>;
+
+    #[test]
+    fn changed() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let config = Config::new("a".to_string());
+        let mut first = config.subscribe();
+        let mut second = config.subscribe();
+        assert!(!first.has_changed());
+
+        {
+            let mut changed = Box::pin(first.changed());
+            assert!(changed.as_mut().poll(&mut cx).is_pending());
+
+            assert_eq!(config.send("b".to_string()), "a");
+            assert_eq!(count.0.load(Ordering::SeqCst), 1);
+            assert!(changed.as_mut().poll(&mut cx).is_ready());
+        }
+
+        assert!(!first.has_changed());
+        assert_eq!(*first.borrow(), "b");
+
+        // `second` wasn't waiting for either write, so both are seen as one change
+        config.send_modify(|value| value.push('c'));
+        assert!(second.has_changed());
+        assert!(Box::pin(second.changed()).as_mut().poll(&mut cx).is_ready());
+        assert!(Box::pin(second.changed())
+            .as_mut()
+            .poll(&mut cx)
+            .is_pending());
+        assert_eq!(*second.borrow_and_update(), "bc");
+
+        assert!(first.has_changed());
+        assert_eq!(*first.borrow_and_update(), "bc");
+        assert!(!first.has_changed());
+    }
+
+    #[test]
+    fn sync_writer() {
+        let count = Arc::new(Count(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let config = Config::new(String::new());
+        let mut subscriber = config.subscribe();
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for _ in 0..100 {
+                    config.send_modify(|value| value.push('x'));
+                }
+            });
+
+            while subscriber.borrow().len() != 100 {
+                let mut changed = Box::pin(subscriber.changed());
+
+                while changed.as_mut().poll(&mut cx).is_pending() {
+                    std::thread::yield_now();
+                }
+            }
+        });
+
+        assert_eq!(config.into_inner().len(), 100);
+    }
+}
@@ -0,0 +1,16 @@
+//! Acquire any one of a set of locks
+//!
+//! This is the shared implementation of [`Mutex::try_lock_any`](crate::mutex::Mutex::try_lock_any)
+//! and [`RwLock::try_read_any`](crate::rwlock::RwLock::try_read_any).
+
+/// Try to acquire each lock in order, and return the first one that succeeds
+#[inline]
+pub(crate) fn try_lock_any<'a, T, G>(
+    locks: &'a [T],
+    try_lock: impl Fn(&'a T) -> Option<G>,
+) -> Option<(usize, G)> {
+    locks
+        .iter()
+        .enumerate()
+        .find_map(|(i, lock)| Some((i, try_lock(lock)?)))
+}
@@ -0,0 +1,342 @@
+//! A barrier that blocks threads until all of them reach it
+//!
+//! [`Barrier`] is reusable: once enough threads arrive, they are all released and the
+//! barrier starts its next generation. Every wait reports the generation it was part of,
+//! so phased computations can tell which round they are in.
+//!
+//! With the `parking_lot_core` feature flag waiting threads are parked, otherwise they spin
+//! (and yield to the OS with the `std` feature flag). [`DefaultBarrier`] picks whichever
+//! is available.
+//!
+//! ```
+//! use locker::barrier::DefaultBarrier;
+//!
+//! let barrier = DefaultBarrier::new(1);
+//!
+//! let result = barrier.wait();
+//! assert!(result.is_leader());
+//! assert_eq!(result.generation(), 0);
+//! assert_eq!(barrier.wait().generation(), 1);
+//! ```
+
+use crate::notify::{Notify, Spin, Wait, WaitQueue};
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(any(feature = "std", feature = "parking_lot_core"))]
+use std::time::{Duration, Instant};
+
+/// A barrier that parks waiting threads if `parking_lot_core` is enabled,
+/// and spins otherwise
+#[cfg(feature = "parking_lot_core")]
+pub type DefaultBarrier = Barrier<crate::notify::Park>;
+
+/// A barrier that parks waiting threads if `parking_lot_core` is enabled,
+/// and spins otherwise
+#[cfg(not(feature = "parking_lot_core"))]
+pub type DefaultBarrier = Barrier<Spin>;
+
+/// The result of waiting on a [`Barrier`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BarrierWaitResult {
+    is_leader: bool,
+    generation: usize,
+}
+
+impl BarrierWaitResult {
+    /// Check if this thread was the last one to reach the barrier in its generation
+    ///
+    /// Exactly one thread per generation is the leader, unless the
+    /// generation was ended early by [`Barrier::reset`]
+    #[inline]
+    pub fn is_leader(&self) -> bool {
+        self.is_leader
+    }
+
+    /// The generation of the barrier that this wait was part of
+    ///
+    /// Generations start at zero, and increase by one each time the barrier releases
+    /// its threads or is reset
+    #[inline]
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+}
+
+/// A reusable barrier, which blocks until `num_threads` threads are waiting on it
+pub struct Barrier<Q> {
+    // `generation * num_threads + arrived`, so the last thread to arrive
+    // moves the barrier to the next generation
+    state: AtomicUsize,
+    num_threads: usize,
+    notify: Notify<Q>,
+}
+
+impl<Q> Barrier<Q> {
+    /// Create a new barrier that blocks in the given queue until `num_threads` threads are waiting
+    ///
+    /// A barrier for zero threads behaves like a barrier for one thread
+    #[inline]
+    pub const fn with_queue(num_threads: usize, queue: Q) -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            num_threads: if num_threads == 0 { 1 } else { num_threads },
+            notify: Notify::with_queue(queue),
+        }
+    }
+
+    /// The number of threads that the barrier waits for
+    #[inline]
+    pub fn num_threads(&self) -> usize {
+        self.num_threads
+    }
+
+    /// The current generation of the barrier
+    #[inline]
+    pub fn generation(&self) -> usize {
+        self.state.load(Ordering::Acquire) / self.num_threads
+    }
+}
+
+impl<Q: WaitQueue> Barrier<Q> {
+    /// End the current generation early, releasing every thread that is waiting on it
+    ///
+    /// Released threads don't see a leader. Threads that wait after this are part of the
+    /// next generation.
+    pub fn reset(&self) {
+        let n = self.num_threads;
+        let _ = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |state| {
+                Some((state / n + 1) * n)
+            });
+
+        self.notify.notify_waiters();
+    }
+
+    /// Arrive at the barrier, and block with `block` until the generation ends
+    ///
+    /// `block` returns false if it timed out, then the thread leaves
+    /// the barrier and this returns None
+    fn wait_with(
+        &self,
+        mut block: impl FnMut(&Notify<Q>, Wait) -> bool,
+    ) -> Option<BarrierWaitResult> {
+        let n = self.num_threads;
+        let state = self.state.fetch_add(1, Ordering::AcqRel);
+        let generation = state / n;
+
+        if state % n == n - 1 {
+            self.notify.notify_waiters();
+
+            return Some(BarrierWaitResult {
+                is_leader: true,
+                generation,
+            });
+        }
+
+        let released = Some(BarrierWaitResult {
+            is_leader: false,
+            generation,
+        });
+
+        loop {
+            let wait = self.notify.start_wait();
+
+            if self.state.load(Ordering::Acquire) / n != generation {
+                return released;
+            }
+
+            if !block(&self.notify, wait) {
+                break;
+            }
+        }
+
+        // leave the barrier, unless the generation ended in the meantime
+        let mut state = self.state.load(Ordering::Acquire);
+
+        loop {
+            if state / n != generation {
+                return released;
+            }
+
+            match self.state.compare_exchange_weak(
+                state,
+                state - 1,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return None,
+                Err(x) => state = x,
+            }
+        }
+    }
+}
+
+impl Barrier<Spin> {
+    /// Create a new barrier that spins until `num_threads` threads are waiting
+    #[inline]
+    pub const fn new(num_threads: usize) -> Self {
+        Self::with_queue(num_threads, Spin)
+    }
+
+    /// Block the current thread until `num_threads` threads are waiting
+    #[inline]
+    pub fn wait(&self) -> BarrierWaitResult {
+        match self.wait_with(|notify, wait| spin(notify, wait, || false)) {
+            Some(result) => result,
+            None => unreachable!("waiting without a timeout never times out"),
+        }
+    }
+
+    /// Block the current thread until `num_threads` threads are waiting, or the timeout is reached
+    ///
+    /// Returns None if the timeout was reached, then this thread doesn't count
+    /// towards the barrier anymore
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn wait_until(&self, timeout: Instant) -> Option<BarrierWaitResult> {
+        self.wait_with(|notify, wait| spin(notify, wait, || Instant::now() >= timeout))
+    }
+
+    /// Block the current thread until `num_threads` threads are waiting, or the timeout is reached
+    ///
+    /// Returns None if the timeout was reached, then this thread doesn't count
+    /// towards the barrier anymore
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn wait_timeout(&self, duration: Duration) -> Option<BarrierWaitResult> {
+        match Instant::now().checked_add(duration) {
+            Some(timeout) => self.wait_until(timeout),
+            None => Some(self.wait()),
+        }
+    }
+}
+
+/// Spin until the wait is ready, returns false if `timed_out` returns true first
+fn spin<Q>(notify: &Notify<Q>, wait: Wait, timed_out: impl Fn() -> bool) -> bool {
+    let mut spin = SpinWait::new();
+
+    while !notify.is_ready(&wait) {
+        if timed_out() {
+            return false;
+        }
+
+        if !spin.spin() {
+            spin.reset();
+        }
+    }
+
+    true
+}
+
+#[cfg(feature = "parking_lot_core")]
+impl Barrier<crate::notify::Park> {
+    /// Create a new barrier that parks threads until `num_threads` threads are waiting
+    #[inline]
+    pub const fn new(num_threads: usize) -> Self {
+        Self::with_queue(num_threads, crate::notify::Park::new())
+    }
+
+    /// Block the current thread until `num_threads` threads are waiting
+    #[inline]
+    pub fn wait(&self) -> BarrierWaitResult {
+        match self.wait_with(|notify, wait| notify.park(wait, None)) {
+            Some(result) => result,
+            None => unreachable!("waiting without a timeout never times out"),
+        }
+    }
+
+    /// Block the current thread until `num_threads` threads are waiting, or the timeout is reached
+    ///
+    /// Returns None if the timeout was reached, then this thread doesn't count
+    /// towards the barrier anymore
+    #[inline]
+    pub fn wait_until(&self, timeout: Instant) -> Option<BarrierWaitResult> {
+        self.wait_with(|notify, wait| notify.park(wait, Some(timeout)))
+    }
+
+    /// Block the current thread until `num_threads` threads are waiting, or the timeout is reached
+    ///
+    /// Returns None if the timeout was reached, then this thread doesn't count
+    /// towards the barrier anymore
+    #[inline]
+    pub fn wait_timeout(&self, duration: Duration) -> Option<BarrierWaitResult> {
+        let timeout = Instant::now().checked_add(duration);
+        self.wait_with(|notify, wait| notify.park(wait, timeout))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+fn check<Q: WaitQueue + Sync>(barrier: &Barrier<Q>, wait: impl Fn() -> BarrierWaitResult + Sync) {
+    use std::sync::atomic::AtomicUsize;
+
+    let leaders = AtomicUsize::new(0);
+    let start = barrier.generation();
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..barrier.num_threads() {
+            s.spawn(|_| {
+                for round in 0..3 {
+                    let result = wait();
+                    assert_eq!(result.generation(), start + round);
+
+                    if result.is_leader() {
+                        leaders.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(leaders.into_inner(), 3);
+    assert_eq!(barrier.generation(), start + 3);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn spin_barrier() {
+    let barrier = Barrier::<Spin>::new(4);
+    check(&barrier, || barrier.wait());
+
+    // a thread that times out doesn't count towards the barrier
+    assert_eq!(barrier.wait_timeout(Duration::from_millis(1)), None);
+    let barrier = Barrier::<Spin>::new(1);
+    assert!(barrier
+        .wait_timeout(Duration::from_millis(1))
+        .unwrap()
+        .is_leader());
+}
+
+#[test]
+#[cfg(feature = "parking_lot_core")]
+fn park_barrier() {
+    let barrier = Barrier::<crate::notify::Park>::new(4);
+    check(&barrier, || barrier.wait());
+
+    assert_eq!(barrier.wait_timeout(Duration::from_millis(1)), None);
+
+    crossbeam_utils::thread::scope(|s| {
+        let waiter = s.spawn(|_| barrier.wait());
+
+        while barrier
+            .state
+            .load(Ordering::Relaxed)
+            .is_multiple_of(barrier.num_threads())
+        {
+            std::thread::yield_now();
+        }
+
+        barrier.reset();
+        let result = waiter.join().unwrap();
+        assert!(!result.is_leader());
+        assert_eq!(result.generation(), 3);
+    })
+    .unwrap();
+
+    assert_eq!(barrier.generation(), 4);
+    check(&barrier, || {
+        barrier.wait_timeout(Duration::from_secs(60)).unwrap()
+    });
+}
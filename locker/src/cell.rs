@@ -0,0 +1,217 @@
+//! A cell for data that is shared between threads, and guarded by a lock
+//!
+//! [`SharedCell`] is a thin wrapper around [`UnsafeCell`] that the containers in this crate
+//! use to hold their data. It never hands out references that outlive the access they were
+//! created for: guards keep the raw pointer from [`SharedCell::as_ptr`], and only create
+//! references when they are dereferenced, and short accesses go through [`SharedCell::with`]
+//! and [`SharedCell::with_mut`], which scope the reference to a closure.
+//!
+//! This way, the containers never create a long-lived `&mut T` that could overlap with a
+//! shared reference handed out by another guard.
+//! Raw lock authors can use it to build their own containers the same way.
+//!
+//! ```
+//! use locker::cell::SharedCell;
+//! use locker::exclusive_lock::RawExclusiveLock;
+//! use locker::mutex::spin::SpinLock;
+//!
+//! struct Counter {
+//!     lock: SpinLock,
+//!     count: SharedCell<u32>,
+//! }
+//!
+//! impl Counter {
+//!     fn increment(&self) -> u32 {
+//!         self.lock.exc_lock();
+//!         // SAFETY: the lock is held, so no other thread accesses `count`
+//!         let count = unsafe { self.count.with_mut(|count| { *count += 1; *count }) };
+//!         unsafe { self.lock.exc_unlock() }
+//!         count
+//!     }
+//! }
+//!
+//! let counter = Counter { lock: SpinLock::new(), count: SharedCell::new(0) };
+//! counter.increment();
+//! assert_eq!(counter.increment(), 2);
+//! ```
+
+use core::cell::UnsafeCell;
+
+/// A cell for data that is guarded by a lock
+///
+/// This has the same layout as `T`
+#[repr(transparent)]
+#[derive(Default)]
+pub struct SharedCell<T: ?Sized> {
+    value: UnsafeCell<T>,
+}
+
+impl<T> SharedCell<T> {
+    /// Create a new cell containing `value`
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes the cell, returning the value
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> SharedCell<T> {
+    /// Get a raw pointer to the value
+    ///
+    /// The pointer is valid for reads and writes for as long as the cell is alive,
+    /// but the caller is responsible for synchronizing accesses through it
+    #[inline]
+    pub const fn as_ptr(&self) -> *mut T {
+        self.value.get()
+    }
+
+    /// Returns a mutable reference to the value
+    ///
+    /// This is safe because `&mut` guarantees that no other thread is using the cell
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    /// Access the value through a shared reference, which doesn't escape `f`
+    ///
+    /// # Safety
+    ///
+    /// No other thread may write to the value until `f` returns
+    #[inline]
+    pub unsafe fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&*self.value.get())
+    }
+
+    /// Access the value through a unique reference, which doesn't escape `f`
+    ///
+    /// # Safety
+    ///
+    /// No other thread or guard may access the value until `f` returns
+    #[inline]
+    pub unsafe fn with_mut<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut *self.value.get())
+    }
+}
+
+impl<T> From<T> for SharedCell<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// A cell that only ever gives exclusive access to its value
+///
+/// Unlike a [`Mutex`](crate::mutex::Mutex), there is no way to get a `&T` (and no guard that
+/// could be shared), the value can only be accessed through a `&mut T` that is scoped to a
+/// closure while the lock is held. So the cell is `Sync` whenever `T` is `Send`, even if `T`
+/// isn't `Sync`, which makes it a good fit for state like FFI handles or types with interior
+/// mutability that can be moved between threads but not shared between them.
+///
+/// Any [`RawMutex`](crate::mutex::RawMutex) backend can be used.
+///
+/// ```
+/// use locker::cell::ExclusiveCell;
+/// use locker::mutex::default::DefaultLock;
+/// use std::cell::Cell;
+///
+/// // `Cell` is `Send`, but not `Sync`
+/// let cell = ExclusiveCell::<DefaultLock, _>::new(Cell::new(0));
+///
+/// std::thread::scope(|s| {
+///     s.spawn(|| cell.lock(|value| value.set(value.get() + 1)));
+///     s.spawn(|| cell.lock(|value| value.set(value.get() + 1)));
+/// });
+///
+/// assert_eq!(cell.into_inner().get(), 2);
+/// ```
+pub struct ExclusiveCell<L, T: ?Sized> {
+    lock: L,
+    value: SharedCell<T>,
+}
+
+unsafe impl<L: Sync + crate::mutex::RawMutex, T: ?Sized + Send> Sync for ExclusiveCell<L, T> {}
+
+impl<L, T> ExclusiveCell<L, T> {
+    /// Create a new cell from the given lock and value
+    #[inline]
+    pub const fn from_raw_parts(lock: L, value: T) -> Self {
+        Self {
+            lock,
+            value: SharedCell::new(value),
+        }
+    }
+
+    /// Consumes the cell, returning the value
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<L: crate::Init, T> ExclusiveCell<L, T> {
+    /// Create a new unlocked cell
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self::from_raw_parts(L::INIT, value)
+    }
+}
+
+impl<L: crate::Init, T: Default> Default for ExclusiveCell<L, T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<L: crate::mutex::RawMutex, T: ?Sized> ExclusiveCell<L, T> {
+    /// Lock the cell, and run `f` with exclusive access to the value
+    ///
+    /// blocks until lock is acquired, the lock is released when `f` returns or panics
+    #[inline]
+    pub fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.lock.exc_lock();
+        defer! { unsafe { self.lock.exc_unlock() } }
+
+        unsafe { self.value.with_mut(f) }
+    }
+
+    /// Try to lock the cell, and run `f` with exclusive access to the value
+    ///
+    /// returns `None` without calling `f` if the lock couldn't be acquired
+    #[inline]
+    pub fn try_lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        if !self.lock.exc_try_lock() {
+            return None;
+        }
+
+        defer! { unsafe { self.lock.exc_unlock() } }
+
+        Some(unsafe { self.value.with_mut(f) })
+    }
+}
+
+impl<L, T: ?Sized> ExclusiveCell<L, T> {
+    /// Returns a mutable reference to the value
+    ///
+    /// This is safe because `&mut` guarantees that no other thread is using the cell
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<L, T: ?Sized> core::fmt::Debug for ExclusiveCell<L, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // the value can't be read without exclusive access
+        f.debug_struct("ExclusiveCell").finish_non_exhaustive()
+    }
+}
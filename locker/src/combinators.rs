@@ -8,3 +8,17 @@ pub use reentrant_panic::ReentrantPanic;
 
 mod debug_checked;
 pub use debug_checked::DebugChecked;
+
+mod or_else;
+pub use or_else::OrElse;
+
+mod block_on_overflow;
+pub use block_on_overflow::BlockOnOverflow;
+
+mod instrumented;
+pub use instrumented::{BumpStats, Instrumented};
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod intent;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use intent::{Committed, Intent};
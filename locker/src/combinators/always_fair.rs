@@ -1,7 +1,7 @@
 use crate::exclusive_lock::{
     RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair, SplittableExclusiveLock,
 };
-use crate::share_lock::{RawShareLock, RawShareLockFair};
+use crate::share_lock::{Contention, RawShareLock, RawShareLockFair};
 use crate::{Init, RawLockInfo};
 
 use crate::mutex::RawMutex;
@@ -18,13 +18,18 @@ unsafe impl<L: RawMutex + RawExclusiveLockFair> RawMutex for Fair<L> {}
 unsafe impl<L: RawRwLock + RawExclusiveLockFair + RawShareLockFair> RawRwLock for Fair<L> {}
 unsafe impl<L: RawReentrantMutex + RawShareLockFair> RawReentrantMutex for Fair<L> {}
 
-impl<L: Init> Init for Fair<L> {
-    const INIT: Self = Self(Init::INIT);
+crate::impl_init! {
+    [L: Init] Fair<L> = Self(Init::INIT)
 }
 
 unsafe impl<L: RawLockInfo + ?Sized> RawLockInfo for Fair<L> {
     type ExclusiveGuardTraits = <L as RawLockInfo>::ExclusiveGuardTraits;
     type ShareGuardTraits = <L as RawLockInfo>::ShareGuardTraits;
+
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
 }
 
 unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLock for Fair<L> {
@@ -40,7 +45,7 @@ unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLock for Fair<L> {
         self.0.exc_unlock_fair()
     }
 
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         self.0.exc_bump_fair()
     }
 }
@@ -50,7 +55,7 @@ unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLockFair for Fair<L> {
         self.0.exc_unlock_fair()
     }
 
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         self.0.exc_bump_fair()
     }
 }
@@ -62,6 +67,10 @@ where
     unsafe fn downgrade(&self) {
         self.0.downgrade()
     }
+
+    unsafe fn try_downgrade(&self) -> bool {
+        self.0.try_downgrade()
+    }
 }
 
 unsafe impl<L: ?Sized + SplittableExclusiveLock + RawExclusiveLockFair> SplittableExclusiveLock
@@ -81,15 +90,29 @@ unsafe impl<L: ?Sized + RawShareLockFair> RawShareLock for Fair<L> {
         self.0.shr_try_lock()
     }
 
+    #[inline]
+    fn shr_lock_hint(&self, contention: Contention) {
+        self.0.shr_lock_hint(contention)
+    }
+
+    #[inline]
+    fn shr_try_lock_hint(&self, contention: Contention) -> bool {
+        self.0.shr_try_lock_hint(contention)
+    }
+
     unsafe fn shr_split(&self) {
         self.0.shr_split()
     }
 
+    unsafe fn shr_try_split(&self) -> bool {
+        self.0.shr_try_split()
+    }
+
     unsafe fn shr_unlock(&self) {
         self.0.shr_unlock_fair()
     }
 
-    unsafe fn shr_bump(&self) {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
         self.0.shr_bump_fair()
     }
 }
@@ -99,7 +122,7 @@ unsafe impl<L: ?Sized + RawShareLockFair> RawShareLockFair for Fair<L> {
         self.0.shr_unlock_fair()
     }
 
-    unsafe fn shr_bump_fair(&self) {
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
         self.0.shr_bump_fair()
     }
 }
@@ -0,0 +1,116 @@
+use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair};
+use crate::share_lock::{Contention, OverflowPolicy, RawShareLock, RawShareLockFair};
+use crate::{Init, RawLockInfo};
+
+use crate::mutex::RawMutex;
+use crate::rwlock::RawRwLock;
+
+/// Wraps a lock and blocks instead of panicking when splitting a *shr lock*
+/// would overflow the lock's reader count
+///
+/// See [`OverflowPolicy::Block`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockOnOverflow<L: ?Sized>(pub L);
+
+unsafe impl<L: RawMutex> RawMutex for BlockOnOverflow<L> {}
+unsafe impl<L: RawRwLock> RawRwLock for BlockOnOverflow<L> {}
+
+crate::impl_init! {
+    [L: Init] BlockOnOverflow<L> = Self(Init::INIT)
+}
+
+unsafe impl<L: RawLockInfo + ?Sized> RawLockInfo for BlockOnOverflow<L> {
+    type ExclusiveGuardTraits = <L as RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <L as RawLockInfo>::ShareGuardTraits;
+
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLock> RawExclusiveLock for BlockOnOverflow<L> {
+    fn exc_lock(&self) {
+        self.0.exc_lock()
+    }
+
+    fn exc_try_lock(&self) -> bool {
+        self.0.exc_try_lock()
+    }
+
+    unsafe fn exc_unlock(&self) {
+        self.0.exc_unlock()
+    }
+
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        self.0.exc_bump()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLockFair for BlockOnOverflow<L> {
+    unsafe fn exc_unlock_fair(&self) {
+        self.0.exc_unlock_fair()
+    }
+
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        self.0.exc_bump_fair()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockDowngrade> RawExclusiveLockDowngrade
+    for BlockOnOverflow<L>
+{
+    unsafe fn downgrade(&self) {
+        self.0.downgrade()
+    }
+
+    unsafe fn try_downgrade(&self) -> bool {
+        self.0.try_downgrade()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLock> RawShareLock for BlockOnOverflow<L> {
+    fn shr_lock(&self) {
+        self.0.shr_lock()
+    }
+
+    fn shr_try_lock(&self) -> bool {
+        self.0.shr_try_lock()
+    }
+
+    #[inline]
+    fn shr_lock_hint(&self, contention: Contention) {
+        self.0.shr_lock_hint(contention)
+    }
+
+    #[inline]
+    fn shr_try_lock_hint(&self, contention: Contention) -> bool {
+        self.0.shr_try_lock_hint(contention)
+    }
+
+    unsafe fn shr_split(&self) {
+        OverflowPolicy::Block.split(|| self.0.shr_try_split())
+    }
+
+    unsafe fn shr_try_split(&self) -> bool {
+        self.0.shr_try_split()
+    }
+
+    unsafe fn shr_unlock(&self) {
+        self.0.shr_unlock()
+    }
+
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        self.0.shr_bump()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockFair> RawShareLockFair for BlockOnOverflow<L> {
+    unsafe fn shr_unlock_fair(&self) {
+        self.0.shr_unlock_fair()
+    }
+
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        self.0.shr_bump_fair()
+    }
+}
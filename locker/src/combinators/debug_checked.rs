@@ -90,9 +90,16 @@ unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLock for DebugChecked<
         self.inner.exc_unlock_fair()
     }
 
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         #[cfg(debug_assertions)]
-        self.inner.exc_bump_fair()
+        {
+            self.inner.exc_bump_fair()
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            crate::BumpResult::KEPT
+        }
     }
 }
 
@@ -102,9 +109,16 @@ unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLockFair for DebugChec
         self.inner.exc_unlock_fair()
     }
 
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         #[cfg(debug_assertions)]
-        self.inner.exc_bump_fair()
+        {
+            self.inner.exc_bump_fair()
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            crate::BumpResult::KEPT
+        }
     }
 }
 
@@ -116,6 +130,18 @@ where
         #[cfg(debug_assertions)]
         self.inner.downgrade()
     }
+
+    unsafe fn try_downgrade(&self) -> bool {
+        #[cfg(debug_assertions)]
+        {
+            self.inner.try_downgrade()
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            true
+        }
+    }
 }
 
 unsafe impl<L: ?Sized + SplittableExclusiveLock + RawExclusiveLockFair> SplittableExclusiveLock
@@ -147,14 +173,30 @@ unsafe impl<L: ?Sized + RawShareLockFair> RawShareLock for DebugChecked<L> {
         self.inner.shr_split()
     }
 
+    unsafe fn shr_try_split(&self) -> bool {
+        #[cfg(debug_assertions)]
+        {
+            self.inner.shr_try_split()
+        }
+        #[cfg(not(debug_assertions))]
+        true
+    }
+
     unsafe fn shr_unlock(&self) {
         #[cfg(debug_assertions)]
         self.inner.shr_unlock_fair()
     }
 
-    unsafe fn shr_bump(&self) {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
         #[cfg(debug_assertions)]
-        self.inner.shr_bump_fair()
+        {
+            self.inner.shr_bump_fair()
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            crate::BumpResult::KEPT
+        }
     }
 }
 
@@ -164,8 +206,15 @@ unsafe impl<L: ?Sized + RawShareLockFair> RawShareLockFair for DebugChecked<L> {
         self.inner.shr_unlock_fair()
     }
 
-    unsafe fn shr_bump_fair(&self) {
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
         #[cfg(debug_assertions)]
-        self.inner.shr_bump_fair()
+        {
+            self.inner.shr_bump_fair()
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            crate::BumpResult::KEPT
+        }
     }
 }
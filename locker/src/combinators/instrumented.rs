@@ -0,0 +1,301 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::exclusive_lock::{
+    RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair, RawExclusiveLockTimed,
+    SplittableExclusiveLock,
+};
+use crate::share_lock::{
+    Contention, RawShareLock, RawShareLockFair, RawShareLockTimed, RawShareLockUpgrade,
+};
+use crate::{BumpResult, Init, RawLockInfo, RawTimedLock};
+
+use crate::mutex::RawMutex;
+use crate::remutex::RawReentrantMutex;
+use crate::rwlock::RawRwLock;
+
+/// How often the `bump` functions of an [`Instrumented`] lock were called,
+/// and how often they actually yielded the lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BumpStats {
+    /// The number of calls to any of the `*bump` or `*bump_fair` functions
+    pub bumps: usize,
+    /// The number of those calls which released the lock, see [`BumpResult::yielded`]
+    pub yields: usize,
+    /// The [name](RawLockInfo::name) of the lock, if it has one
+    pub name: Option<&'static str>,
+}
+
+/// Wraps a lock and counts how often bumping it yielded the lock to other threads
+///
+/// Code that bumps a lock in a loop can use this to adapt how often it bumps,
+/// for example by bumping less often if most bumps didn't yield. The timed
+/// `*bump_for` functions aren't counted.
+///
+/// ```
+/// use locker::combinators::{BumpStats, Instrumented};
+/// use locker::exclusive_lock::ExclusiveGuard;
+/// use locker::mutex::{default::DefaultLock, Mutex};
+///
+/// let mutex = Mutex::<Instrumented<DefaultLock>, _>::new(0);
+/// let mut guard = mutex.lock();
+///
+/// for _ in 0..10 {
+///     *guard += 1;
+///     // nobody is waiting for the lock, so this doesn't release it
+///     assert!(!ExclusiveGuard::bump(&mut guard).yielded);
+/// }
+///
+/// drop(guard);
+/// let stats = mutex.raw().inner().take_stats();
+/// assert_eq!(stats, BumpStats { bumps: 10, yields: 0, name: None });
+/// ```
+#[derive(Debug)]
+pub struct Instrumented<L: ?Sized> {
+    bumps: AtomicUsize,
+    yields: AtomicUsize,
+    lock: L,
+}
+
+impl<L> Instrumented<L> {
+    /// Wrap `lock`, with all counters starting at zero
+    #[inline]
+    pub const fn new(lock: L) -> Self {
+        Self {
+            bumps: AtomicUsize::new(0),
+            yields: AtomicUsize::new(0),
+            lock,
+        }
+    }
+
+    /// The underlying lock
+    #[inline]
+    pub fn into_inner(self) -> L {
+        self.lock
+    }
+}
+
+impl<L: ?Sized> Instrumented<L> {
+    /// The underlying lock
+    #[inline]
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    #[inline]
+    fn count(&self, result: BumpResult) -> BumpResult {
+        self.bumps.fetch_add(1, Ordering::Relaxed);
+
+        if result.yielded {
+            self.yields.fetch_add(1, Ordering::Relaxed);
+        }
+
+        result
+    }
+}
+
+impl<L: ?Sized + RawLockInfo> Instrumented<L> {
+    /// The counters since this lock was created, or since they were last taken
+    pub fn stats(&self) -> BumpStats {
+        BumpStats {
+            bumps: self.bumps.load(Ordering::Relaxed),
+            yields: self.yields.load(Ordering::Relaxed),
+            name: self.lock.name(),
+        }
+    }
+
+    /// Get the counters, and reset them to zero
+    pub fn take_stats(&self) -> BumpStats {
+        BumpStats {
+            bumps: self.bumps.swap(0, Ordering::Relaxed),
+            yields: self.yields.swap(0, Ordering::Relaxed),
+            name: self.lock.name(),
+        }
+    }
+}
+
+unsafe impl<L: RawMutex> RawMutex for Instrumented<L> {}
+unsafe impl<L: RawRwLock> RawRwLock for Instrumented<L> {}
+unsafe impl<L: RawReentrantMutex> RawReentrantMutex for Instrumented<L> {}
+
+crate::impl_init! {
+    [L: Init] Instrumented<L> = Self::new(Init::INIT)
+}
+
+unsafe impl<L: RawLockInfo + ?Sized> RawLockInfo for Instrumented<L> {
+    type ExclusiveGuardTraits = <L as RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <L as RawLockInfo>::ShareGuardTraits;
+
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        self.lock.name()
+    }
+}
+
+impl<L: ?Sized + RawTimedLock> RawTimedLock for Instrumented<L> {
+    type Instant = L::Instant;
+    type Duration = L::Duration;
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLock> RawExclusiveLock for Instrumented<L> {
+    fn exc_lock(&self) {
+        self.lock.exc_lock()
+    }
+
+    fn exc_try_lock(&self) -> bool {
+        self.lock.exc_try_lock()
+    }
+
+    unsafe fn exc_unlock(&self) {
+        self.lock.exc_unlock()
+    }
+
+    unsafe fn exc_bump(&self) -> BumpResult {
+        self.count(self.lock.exc_bump())
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLockFair for Instrumented<L> {
+    unsafe fn exc_unlock_fair(&self) {
+        self.lock.exc_unlock_fair()
+    }
+
+    unsafe fn exc_bump_fair(&self) -> BumpResult {
+        self.count(self.lock.exc_bump_fair())
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockTimed> RawExclusiveLockTimed for Instrumented<L> {
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.lock.exc_try_lock_until(instant)
+    }
+
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.lock.exc_try_lock_for(duration)
+    }
+
+    unsafe fn exc_bump_for(&self, duration: Self::Duration) -> bool {
+        self.lock.exc_bump_for(duration)
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockDowngrade> RawExclusiveLockDowngrade for Instrumented<L> {
+    unsafe fn downgrade(&self) {
+        self.lock.downgrade()
+    }
+
+    unsafe fn try_downgrade(&self) -> bool {
+        self.lock.try_downgrade()
+    }
+}
+
+unsafe impl<L: ?Sized + SplittableExclusiveLock> SplittableExclusiveLock for Instrumented<L> {
+    unsafe fn exc_split(&self) {
+        self.lock.exc_split()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLock> RawShareLock for Instrumented<L> {
+    fn shr_lock(&self) {
+        self.lock.shr_lock()
+    }
+
+    fn shr_try_lock(&self) -> bool {
+        self.lock.shr_try_lock()
+    }
+
+    #[inline]
+    fn shr_lock_hint(&self, contention: Contention) {
+        self.lock.shr_lock_hint(contention)
+    }
+
+    #[inline]
+    fn shr_try_lock_hint(&self, contention: Contention) -> bool {
+        self.lock.shr_try_lock_hint(contention)
+    }
+
+    unsafe fn shr_split(&self) {
+        self.lock.shr_split()
+    }
+
+    unsafe fn shr_try_split(&self) -> bool {
+        self.lock.shr_try_split()
+    }
+
+    unsafe fn shr_unlock(&self) {
+        self.lock.shr_unlock()
+    }
+
+    unsafe fn shr_bump(&self) -> BumpResult {
+        self.count(self.lock.shr_bump())
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockFair> RawShareLockFair for Instrumented<L> {
+    unsafe fn shr_unlock_fair(&self) {
+        self.lock.shr_unlock_fair()
+    }
+
+    unsafe fn shr_bump_fair(&self) -> BumpResult {
+        self.count(self.lock.shr_bump_fair())
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockTimed> RawShareLockTimed for Instrumented<L> {
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.lock.shr_try_lock_until(instant)
+    }
+
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.lock.shr_try_lock_for(duration)
+    }
+
+    unsafe fn shr_bump_for(&self, duration: Self::Duration) -> bool {
+        self.lock.shr_bump_for(duration)
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockUpgrade> RawShareLockUpgrade for Instrumented<L> {
+    unsafe fn upgrade(&self) {
+        self.lock.upgrade()
+    }
+
+    unsafe fn try_upgrade(&self) -> bool {
+        self.lock.try_upgrade()
+    }
+}
+
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn yields_to_waiters() {
+    use crate::exclusive_lock::ExclusiveGuard;
+    use crate::mutex::{adaptive::AdaptiveLock, Mutex};
+    use crate::raw_state::RawStateBits;
+
+    let mutex = Mutex::<Instrumented<AdaptiveLock>, _>::new(0);
+    let mut guard = mutex.lock();
+
+    assert_eq!(ExclusiveGuard::bump_fair(&mut guard), BumpResult::KEPT);
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| *mutex.lock() += 1);
+
+        while !mutex.raw().inner().inner().decoded_state().parked {
+            std::thread::yield_now();
+        }
+
+        // the waiting thread gets the lock before the bump returns
+        assert_eq!(ExclusiveGuard::bump_fair(&mut guard), BumpResult::YIELDED);
+        assert_eq!(*guard, 1);
+        drop(guard);
+    })
+    .unwrap();
+
+    assert_eq!(
+        mutex.raw().inner().stats(),
+        BumpStats {
+            bumps: 2,
+            yields: 1,
+            name: None,
+        }
+    );
+}
@@ -0,0 +1,431 @@
+use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveGuard, RawExclusiveLock};
+use crate::mutex::{Mutex, RawMutex};
+use crate::rwlock::{RawRwLock, RwLock};
+use crate::share_lock::{RawShareGuard, RawShareLock, ShareGuard};
+
+use std::vec::Vec;
+
+#[derive(Clone, Copy)]
+enum Access<'a> {
+    Exclusive(&'a dyn RawExclusiveLock),
+    Shared(&'a dyn RawShareLock),
+}
+
+impl Access<'_> {
+    #[inline]
+    fn addr(self) -> usize {
+        match self {
+            Access::Exclusive(lock) => lock as *const dyn RawExclusiveLock as *const () as usize,
+            Access::Shared(lock) => lock as *const dyn RawShareLock as *const () as usize,
+        }
+    }
+
+    #[inline]
+    fn lock(self) {
+        match self {
+            Access::Exclusive(lock) => lock.exc_lock(),
+            Access::Shared(lock) => lock.shr_lock(),
+        }
+    }
+
+    #[inline]
+    fn try_lock(self) -> bool {
+        match self {
+            Access::Exclusive(lock) => lock.exc_try_lock(),
+            Access::Shared(lock) => lock.shr_try_lock(),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The lock must be held with this access
+    #[inline]
+    unsafe fn unlock(self) {
+        match self {
+            Access::Exclusive(lock) => lock.exc_unlock(),
+            Access::Shared(lock) => lock.shr_unlock(),
+        }
+    }
+}
+
+/// A set of locks that are acquired together, or not at all
+///
+/// Locks of any type can be added to the set, either exclusively or shared, as long as every
+/// lock is added at most once. Committing the intent tries to lock all of them without
+/// blocking. If any lock can't be acquired, all of the locks that were already taken are
+/// released again, so a thread never holds part of the set while it waits. This way
+/// threads that commit intents over overlapping locks can't deadlock on each other,
+/// no matter which order the locks were added in.
+///
+/// Adding the same lock twice panics, but locks that share an underlying lock can't be
+/// detected, for example [`GlobalLock`](crate::mutex::global::GlobalLock) mutexes that
+/// map to the same global lock (see
+/// [`GlobalLock::will_mutex_contend`](crate::mutex::global::GlobalLock::will_mutex_contend)).
+/// Such locks can never be held at the same time, so [`Intent::commit`] never returns,
+/// and [`Intent::try_commit`] always fails.
+///
+/// [`Intent::commit`] then blocks on the lock that couldn't be acquired, and starts
+/// over once it gets it, while [`Intent::try_commit`] gives up instead.
+///
+/// Once committed, guards for the typed locks in the set can be taken out of the
+/// [`Committed`] set, and every lock that wasn't taken out is released when it is dropped.
+///
+/// ```
+/// use locker::combinators::Intent;
+/// use locker::rwlock::default::DefaultLock;
+///
+/// let balance = DefaultLock::mutex(10);
+/// let savings = DefaultLock::rwlock(0);
+/// let rate = DefaultLock::rwlock(2);
+///
+/// let mut intent = Intent::new();
+/// intent.write(&savings).mutex(&balance).read(&rate);
+///
+/// let mut committed = intent.commit();
+/// let mut balance = committed.take_mutex(&balance);
+/// *committed.take_write(&savings) += *balance * *committed.take_read(&rate);
+/// *balance = 0;
+/// ```
+pub struct Intent<'a> {
+    locks: Vec<Access<'a>>,
+}
+
+/// A set of locks that were all acquired by committing an [`Intent`]
+///
+/// The locks that weren't taken out of the set are released when this is dropped
+#[must_use = "if unused the locks of the `Committed` set will immediately unlock"]
+pub struct Committed<'a> {
+    locks: Vec<Access<'a>>,
+}
+
+impl Default for Intent<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Intent<'a> {
+    /// Create an empty intent
+    #[inline]
+    pub const fn new() -> Self {
+        Self { locks: Vec::new() }
+    }
+
+    /// The number of locks in this intent
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.locks.len()
+    }
+
+    /// Check if there are no locks in this intent
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.locks.is_empty()
+    }
+
+    fn push(&mut self, access: Access<'a>) -> &mut Self {
+        let addr = access.addr();
+
+        assert!(
+            self.locks.iter().all(|lock| lock.addr() != addr),
+            "tried to add the same lock to an intent twice"
+        );
+
+        self.locks.push(access);
+        self
+    }
+
+    /// Add an *exc lock* on a raw lock
+    ///
+    /// # Panic
+    ///
+    /// Panics if the lock was already added to this intent
+    pub fn exclusive(&mut self, lock: &'a dyn RawExclusiveLock) -> &mut Self {
+        self.push(Access::Exclusive(lock))
+    }
+
+    /// Add a *shr lock* on a raw lock
+    ///
+    /// # Panic
+    ///
+    /// Panics if the lock was already added to this intent
+    pub fn shared(&mut self, lock: &'a dyn RawShareLock) -> &mut Self {
+        self.push(Access::Shared(lock))
+    }
+
+    /// Add a mutex, which can be taken out of the committed set with [`Committed::take_mutex`]
+    ///
+    /// # Panic
+    ///
+    /// Panics if the mutex was already added to this intent
+    pub fn mutex<L: RawMutex, T: ?Sized>(&mut self, mutex: &'a Mutex<L, T>) -> &mut Self {
+        self.exclusive(mutex.raw().inner())
+    }
+
+    /// Add shared read access to an rwlock, which can be taken out of the committed
+    /// set with [`Committed::take_read`]
+    ///
+    /// # Panic
+    ///
+    /// Panics if the rwlock was already added to this intent
+    pub fn read<L: RawRwLock, T: ?Sized>(&mut self, rwlock: &'a RwLock<L, T>) -> &mut Self {
+        self.shared(rwlock.raw().inner())
+    }
+
+    /// Add exclusive write access to an rwlock, which can be taken out of the committed
+    /// set with [`Committed::take_write`]
+    ///
+    /// # Panic
+    ///
+    /// Panics if the rwlock was already added to this intent
+    pub fn write<L: RawRwLock, T: ?Sized>(&mut self, rwlock: &'a RwLock<L, T>) -> &mut Self {
+        self.exclusive(rwlock.raw().inner())
+    }
+
+    /// Try to acquire every lock starting at `first`, which is already held
+    ///
+    /// On failure, all locks are released, and the index of the lock that couldn't be
+    /// acquired is returned
+    fn try_lock_rest(&self, first: usize) -> Result<(), usize> {
+        let len = self.locks.len();
+
+        for offset in 1..len {
+            let index = (first + offset) % len;
+
+            if !self.locks[index].try_lock() {
+                for taken in 0..offset {
+                    unsafe { self.locks[(first + taken) % len].unlock() }
+                }
+
+                return Err(index);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Attempt to acquire every lock in this intent, without blocking
+    ///
+    /// If any of the locks can't be acquired, then none of them are held,
+    /// and the intent is returned
+    pub fn try_commit(self) -> Result<Committed<'a>, Self> {
+        match self.locks.first() {
+            None => Ok(Committed { locks: self.locks }),
+            Some(lock) if !lock.try_lock() => Err(self),
+            Some(_) => match self.try_lock_rest(0) {
+                Ok(()) => Ok(Committed { locks: self.locks }),
+                Err(_) => Err(self),
+            },
+        }
+    }
+
+    /// Acquire every lock in this intent, blocking until all of them are available
+    ///
+    /// This blocks on one lock at a time, and tries to acquire all the others without
+    /// blocking. If one of them is unavailable, every lock is released, and this blocks
+    /// on the one that was unavailable next.
+    ///
+    /// If two of the locks share an underlying lock, this never returns, see the
+    /// [type docs](Intent) for details.
+    pub fn commit(self) -> Committed<'a> {
+        if !self.locks.is_empty() {
+            let mut first = 0;
+
+            loop {
+                self.locks[first].lock();
+
+                match self.try_lock_rest(first) {
+                    Ok(()) => break,
+                    Err(index) => first = index,
+                }
+            }
+        }
+
+        Committed { locks: self.locks }
+    }
+}
+
+impl<'a> Committed<'a> {
+    fn take(&mut self, addr: usize, exclusive: bool) {
+        let index = self
+            .locks
+            .iter()
+            .position(|lock| lock.addr() == addr)
+            .expect("tried to take a lock that isn't in the committed set");
+
+        assert_eq!(
+            matches!(self.locks[index], Access::Exclusive(_)),
+            exclusive,
+            "tried to take a lock with different access than it was added with"
+        );
+
+        self.locks.swap_remove(index);
+    }
+
+    /// Take the guard of a mutex out of the committed set
+    ///
+    /// The mutex will be released when the guard is dropped, instead of with the rest of the set
+    ///
+    /// # Panic
+    ///
+    /// Panics if the mutex isn't in the committed set, or if it was already taken out
+    pub fn take_mutex<L: RawMutex, T: ?Sized>(
+        &mut self,
+        mutex: &'a Mutex<L, T>,
+    ) -> ExclusiveGuard<'a, L, T>
+    where
+        L::ExclusiveGuardTraits: crate::Inhabitted,
+    {
+        let lock = mutex.raw().inner();
+        self.take(lock as *const L as *const () as usize, true);
+
+        unsafe {
+            ExclusiveGuard::from_raw_parts(RawExclusiveGuard::from_raw(lock), mutex.as_mut_ptr())
+        }
+    }
+
+    /// Take the read guard of an rwlock out of the committed set
+    ///
+    /// The rwlock will be released when the guard is dropped, instead of with the rest of the set
+    ///
+    /// # Panic
+    ///
+    /// Panics if the rwlock isn't in the committed set with shared access,
+    /// or if it was already taken out
+    pub fn take_read<L: RawRwLock, T: ?Sized>(
+        &mut self,
+        rwlock: &'a RwLock<L, T>,
+    ) -> ShareGuard<'a, L, T>
+    where
+        L::ShareGuardTraits: crate::Inhabitted,
+    {
+        let lock = rwlock.raw().inner();
+        self.take(lock as *const L as *const () as usize, false);
+
+        unsafe { ShareGuard::from_raw_parts(RawShareGuard::from_raw(lock), rwlock.as_mut_ptr()) }
+    }
+
+    /// Take the write guard of an rwlock out of the committed set
+    ///
+    /// The rwlock will be released when the guard is dropped, instead of with the rest of the set
+    ///
+    /// # Panic
+    ///
+    /// Panics if the rwlock isn't in the committed set with exclusive access,
+    /// or if it was already taken out
+    pub fn take_write<L: RawRwLock, T: ?Sized>(
+        &mut self,
+        rwlock: &'a RwLock<L, T>,
+    ) -> ExclusiveGuard<'a, L, T>
+    where
+        L::ExclusiveGuardTraits: crate::Inhabitted,
+    {
+        let lock = rwlock.raw().inner();
+        self.take(lock as *const L as *const () as usize, true);
+
+        unsafe {
+            ExclusiveGuard::from_raw_parts(RawExclusiveGuard::from_raw(lock), rwlock.as_mut_ptr())
+        }
+    }
+
+    /// Release every lock that wasn't taken out of the set, and get back the intent
+    /// to commit it again later
+    ///
+    /// The locks that were taken out are not part of the returned intent
+    pub fn rollback(mut self) -> Intent<'a> {
+        let locks = core::mem::take(&mut self.locks);
+
+        for lock in &locks {
+            unsafe { lock.unlock() }
+        }
+
+        Intent { locks }
+    }
+}
+
+impl Drop for Committed<'_> {
+    fn drop(&mut self) {
+        for lock in &self.locks {
+            unsafe { lock.unlock() }
+        }
+    }
+}
+
+#[test]
+#[cfg(all(feature = "extra", feature = "std"))]
+fn try_commit_rolls_back() {
+    use crate::rwlock::spin::SpinLock;
+
+    let a = SpinLock::mutex(0);
+    let b = SpinLock::rwlock(0);
+    let c = SpinLock::rwlock(0);
+
+    let read = c.read();
+    let mut intent = Intent::new();
+    intent.mutex(&a).read(&b).write(&c);
+
+    let intent = intent.try_commit().err().unwrap();
+    assert!(a.try_lock().is_some());
+    assert!(b.try_write().is_some());
+    drop(read);
+
+    let mut committed = intent.try_commit().ok().unwrap();
+    assert!(a.try_lock().is_none());
+    assert!(b.try_read().is_some());
+    assert!(b.try_write().is_none());
+
+    *committed.take_write(&c) += 1;
+    assert!(c.try_read().is_some());
+
+    let intent = committed.rollback();
+    assert_eq!(intent.len(), 2);
+    assert!(a.try_lock().is_some());
+    assert!(b.try_write().is_some());
+    assert_eq!(*c.read(), 1);
+}
+
+#[test]
+#[cfg(all(feature = "extra", feature = "std"))]
+fn commit_in_any_order() {
+    use crate::rwlock::default::DefaultLock;
+
+    let a = DefaultLock::mutex(0);
+    let b = DefaultLock::rwlock(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            for _ in 0..1000 {
+                let mut intent = Intent::new();
+                intent.mutex(&a).write(&b);
+
+                let mut committed = intent.commit();
+                *committed.take_mutex(&a) += 1;
+                *committed.take_write(&b) += 1;
+            }
+        });
+
+        s.spawn(|_| {
+            for _ in 0..1000 {
+                let mut intent = Intent::new();
+                intent.write(&b).mutex(&a);
+
+                let mut committed = intent.commit();
+                let a = committed.take_mutex(&a);
+                let b = committed.take_write(&b);
+                assert_eq!(*a, *b);
+            }
+        });
+    })
+    .unwrap();
+
+    assert_eq!(*a.lock(), 1000);
+}
+
+#[test]
+#[should_panic = "tried to add the same lock to an intent twice"]
+fn duplicate_lock() {
+    let lock = crate::mutex::spin::SpinLock::mutex(());
+
+    Intent::new().mutex(&lock).mutex(&lock);
+}
@@ -0,0 +1,153 @@
+use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockFair};
+use crate::spin_wait::SpinWait;
+use crate::{Init, RawLockInfo};
+
+use crate::mutex::RawMutex;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A lock that tries to acquire `L1` and falls back to `L2` for blocking
+///
+/// `L1` is the lock that actually provides exclusion, so every *exc lock* on an `OrElse`
+/// holds an *exc lock* on `L1`. When `exc_lock` can't immediately acquire `L1`, it will
+/// block on `L2` and then, while holding `L2`, wait for `L1`. This way only a single
+/// thread waits on `L1` at a time, while all other threads block on `L2`. That thread
+/// spins on `L1` for a bit, and then backs off by yielding the thread between attempts
+/// (if the `std` feature is enabled), so a long critical section doesn't keep it spinning.
+///
+/// For example, `OrElse<SpinLock, AdaptiveLock>` uses a spin lock for the fast path
+/// and parks threads in the slow path.
+///
+/// # Unlocking
+///
+/// The combinator keeps a tag that records whether the current holder took the slow path.
+/// When unlocking, `L1` is always released, and `L2` is released only if the tag is set.
+pub struct OrElse<L1, L2> {
+    fast: L1,
+    slow: L2,
+    slow_held: AtomicBool,
+}
+
+unsafe impl<L1: RawMutex, L2: RawMutex> RawMutex for OrElse<L1, L2> {}
+
+impl<L1, L2> OrElse<L1, L2> {
+    /// Create a new `OrElse` lock
+    ///
+    /// # Safety
+    ///
+    /// `fast` and `slow` must both be unlocked, and must not be shared
+    #[inline]
+    pub const unsafe fn from_raw_parts(fast: L1, slow: L2) -> Self {
+        Self {
+            fast,
+            slow,
+            slow_held: AtomicBool::new(false),
+        }
+    }
+
+    /// The lock used for the fast path
+    #[inline]
+    pub fn fast(&self) -> &L1 {
+        &self.fast
+    }
+
+    /// The lock used for the slow path
+    #[inline]
+    pub fn slow(&self) -> &L2 {
+        &self.slow
+    }
+}
+
+impl<L1: Init, L2: Init> Init for OrElse<L1, L2> {
+    const INIT: Self = unsafe { Self::from_raw_parts(Init::INIT, Init::INIT) };
+}
+
+unsafe impl<L1: RawLockInfo, L2: RawLockInfo> RawLockInfo for OrElse<L1, L2> {
+    type ExclusiveGuardTraits = (L1::ExclusiveGuardTraits, L2::ExclusiveGuardTraits);
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+impl<L1: RawExclusiveLock, L2: RawExclusiveLock> OrElse<L1, L2> {
+    #[cold]
+    fn exc_lock_slow(&self) {
+        self.slow.exc_lock();
+
+        let mut spin = SpinWait::new();
+
+        while !self.fast.exc_try_lock() {
+            if !spin.spin() {
+                #[cfg(feature = "std")]
+                std::thread::yield_now();
+                spin.reset();
+            }
+        }
+
+        self.slow_held.store(true, Ordering::Relaxed);
+    }
+
+    #[inline]
+    unsafe fn exc_unlock_with(&self, unlock_fast: impl FnOnce(&L1), unlock_slow: impl FnOnce(&L2)) {
+        // this must be read before `fast` is unlocked, because
+        // the next owner of `fast` may overwrite it
+        let slow_held = self.slow_held.load(Ordering::Relaxed);
+
+        if slow_held {
+            self.slow_held.store(false, Ordering::Relaxed);
+        }
+
+        unlock_fast(&self.fast);
+
+        if slow_held {
+            unlock_slow(&self.slow);
+        }
+    }
+}
+
+unsafe impl<L1: RawExclusiveLock, L2: RawExclusiveLock> RawExclusiveLock for OrElse<L1, L2> {
+    #[inline]
+    fn exc_lock(&self) {
+        if !self.fast.exc_try_lock() {
+            self.exc_lock_slow()
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        self.fast.exc_try_lock()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.exc_unlock_with(|fast| fast.exc_unlock(), |slow| slow.exc_unlock())
+    }
+}
+
+unsafe impl<L1: RawExclusiveLockFair, L2: RawExclusiveLockFair> RawExclusiveLockFair
+    for OrElse<L1, L2>
+{
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        self.exc_unlock_with(|fast| fast.exc_unlock_fair(), |slow| slow.exc_unlock_fair())
+    }
+}
+
+#[test]
+#[cfg(feature = "extra")]
+fn or_else() {
+    use crate::mutex::{default::DefaultLock, spin::SpinLock};
+
+    let mutex = crate::mutex::Mutex::<OrElse<SpinLock, DefaultLock>, _>::new(0);
+
+    let guard = mutex.lock();
+    assert!(mutex.try_lock().is_none());
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| *mutex.lock() += 1);
+
+        drop(guard);
+    })
+    .unwrap();
+
+    assert_eq!(*mutex.lock(), 1);
+    assert!(!mutex.raw().inner().slow_held.load(Ordering::Relaxed));
+}
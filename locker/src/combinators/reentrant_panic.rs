@@ -1,5 +1,5 @@
 use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair};
-use crate::share_lock::{RawShareLock, RawShareLockFair};
+use crate::share_lock::{RawShareLock, RawShareLockFair, RawShareLockUpgrade};
 use crate::{Init, RawLockInfo};
 
 use crate::mutex::RawMutex;
@@ -10,8 +10,24 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
-        /// Wraps a lock and panics on reentrant exclusive lock, leaves the
-        /// share lock untouched
+        /// Wraps a lock and panics on reentrant exclusive lock
+        ///
+        /// In debug mode, this also tracks how many *shr lock*s each thread holds, and
+        /// panics if a thread tries to upgrade a *shr lock* while it holds another *shr lock*
+        /// on the same lock (which would otherwise silently deadlock). In this case `try_upgrade`
+        /// will fail fast instead. *shr lock*s are tracked per thread, so in debug mode the share
+        /// guards of a `ReentrantPanic` are not `Send`, which makes sure that they are released on
+        /// the same thread that acquired them.
+        ///
+        /// ```compile_fail
+        /// use locker::combinators::ReentrantPanic;
+        /// use locker::rwlock::{default::DefaultLock, RwLock};
+        ///
+        /// fn assert_send<T: Send>(_: T) {}
+        ///
+        /// let rwlock = RwLock::<ReentrantPanic<DefaultLock>, _>::new(0);
+        /// assert_send(rwlock.read());
+        /// ```
         #[derive(Debug)]
         pub struct ReentrantPanic<L: ?Sized, I = crate::remutex::std_thread::StdThreadInfo> {
             owner: AtomicUsize,
@@ -20,7 +36,7 @@ cfg_if::cfg_if! {
         }
     } else {
         /// Wraps a lock and panics on reentrant exclusive lock, leaves the
-        /// share lock untouched
+        /// share lock untouched (without `std`, *shr lock*s are not tracked)
         #[derive(Debug)]
         pub struct ReentrantPanic<L: ?Sized, I> {
             owner: AtomicUsize,
@@ -41,6 +57,91 @@ impl<L, I> ReentrantPanic<L, I> {
     }
 }
 
+#[cfg(all(debug_assertions, feature = "std"))]
+mod held {
+    use std::cell::RefCell;
+
+    /// *shr lock*s are tracked per thread, so they must be released on the thread that acquired them
+    pub type GuardTraits = crate::NoSend;
+
+    std::thread_local! {
+        /// the number of *shr lock*s held by this thread for each lock
+        static HELD: RefCell<Vec<(usize, usize)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub fn acquire(lock: usize) {
+        HELD.with(|held| {
+            let mut held = held.borrow_mut();
+
+            match held.iter_mut().find(|(addr, _)| *addr == lock) {
+                Some((_, count)) => *count += 1,
+                None => held.push((lock, 1)),
+            }
+        })
+    }
+
+    pub fn release(lock: usize) {
+        HELD.with(|held| {
+            let mut held = held.borrow_mut();
+
+            // the *shr lock* may have been acquired on another thread
+            if let Some(index) = held.iter().position(|&(addr, _)| addr == lock) {
+                held[index].1 -= 1;
+
+                if held[index].1 == 0 {
+                    held.swap_remove(index);
+                }
+            }
+        })
+    }
+
+    /// returns true if this thread holds more than one *shr lock* on `lock`
+    pub fn holds_others(lock: usize) -> bool {
+        HELD.with(|held| {
+            held.borrow()
+                .iter()
+                .any(|&(addr, count)| addr == lock && count > 1)
+        })
+    }
+}
+
+#[cfg(not(all(debug_assertions, feature = "std")))]
+mod held {
+    pub type GuardTraits = ();
+
+    pub fn acquire(_: usize) {}
+    pub fn release(_: usize) {}
+    // *shr lock*s aren't tracked, so upgrades are never rejected
+    pub fn holds_others(_: usize) -> bool {
+        false
+    }
+}
+
+impl<L: ?Sized, I> ReentrantPanic<L, I> {
+    #[inline]
+    fn addr(&self) -> usize {
+        self as *const Self as *const u8 as usize
+    }
+
+    /// returns true if the current thread holds any *shr lock* besides the one being upgraded
+    #[inline]
+    fn holds_other_shr_locks(&self) -> bool {
+        held::holds_others(self.addr())
+    }
+}
+
+impl<L: ?Sized + RawLockInfo, I> ReentrantPanic<L, I> {
+    /// Panic instead of deadlocking, with the name of the lock if it has one
+    #[cold]
+    #[inline(never)]
+    fn deadlock(&self, message: &str) -> ! {
+        match self.inner.name() {
+            Some(name) => panic!("lock `{}`: {}", name, message),
+            None => panic!("{}", message),
+        }
+    }
+}
+
 unsafe impl<L: RawMutex, I: ThreadInfo> RawMutex for ReentrantPanic<L, I> {}
 unsafe impl<L: RawRwLock, I: ThreadInfo> RawRwLock for ReentrantPanic<L, I> {}
 
@@ -54,18 +155,24 @@ impl<L: Init, I: Init> Init for ReentrantPanic<L, I> {
 
 unsafe impl<L: RawLockInfo, I: ThreadInfo> RawLockInfo for ReentrantPanic<L, I> {
     type ExclusiveGuardTraits = <L as RawLockInfo>::ExclusiveGuardTraits;
-    type ShareGuardTraits = <L as RawLockInfo>::ShareGuardTraits;
+    type ShareGuardTraits = (held::GuardTraits, <L as RawLockInfo>::ShareGuardTraits);
+
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        self.inner.name()
+    }
 }
 
-unsafe impl<L: ?Sized + RawExclusiveLock, I: ThreadInfo> RawExclusiveLock for ReentrantPanic<L, I> {
+unsafe impl<L: ?Sized + RawExclusiveLock + RawLockInfo, I: ThreadInfo> RawExclusiveLock
+    for ReentrantPanic<L, I>
+{
     fn exc_lock(&self) {
         let curr = self.thread_info.id().get();
         let owner = self.owner.load(Ordering::Acquire);
 
-        assert_ne!(
-            owner, curr,
-            "tried to lock a locked exclusive lock from the same thread!"
-        );
+        if owner == curr {
+            self.deadlock("tried to lock a locked exclusive lock from the same thread!")
+        }
 
         self.inner.exc_lock();
 
@@ -87,14 +194,15 @@ unsafe impl<L: ?Sized + RawExclusiveLock, I: ThreadInfo> RawExclusiveLock for Re
         self.inner.exc_unlock();
     }
 
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         let owner = self.owner.swap(0, Ordering::Acquire);
-        self.inner.exc_bump();
+        let result = self.inner.exc_bump();
         self.owner.store(owner, Ordering::Release);
+        result
     }
 }
 
-unsafe impl<L: ?Sized + RawExclusiveLockFair, I: ThreadInfo> RawExclusiveLockFair
+unsafe impl<L: ?Sized + RawExclusiveLockFair + RawLockInfo, I: ThreadInfo> RawExclusiveLockFair
     for ReentrantPanic<L, I>
 {
     unsafe fn exc_unlock_fair(&self) {
@@ -102,54 +210,103 @@ unsafe impl<L: ?Sized + RawExclusiveLockFair, I: ThreadInfo> RawExclusiveLockFai
         self.inner.exc_unlock_fair();
     }
 
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         let owner = self.owner.swap(0, Ordering::Acquire);
-        self.inner.exc_bump_fair();
+        let result = self.inner.exc_bump_fair();
         self.owner.store(owner, Ordering::Release);
+        result
     }
 }
 
-unsafe impl<L: ?Sized + RawExclusiveLockDowngrade, I: ThreadInfo> RawExclusiveLockDowngrade
-    for ReentrantPanic<L, I>
+unsafe impl<L: ?Sized + RawExclusiveLockDowngrade + RawLockInfo, I: ThreadInfo>
+    RawExclusiveLockDowngrade for ReentrantPanic<L, I>
 {
     unsafe fn downgrade(&self) {
         self.owner.store(0, Ordering::Release);
         self.inner.downgrade();
+        held::acquire(self.addr());
     }
 }
 
 unsafe impl<L: ?Sized + RawShareLock, I: ThreadInfo> RawShareLock for ReentrantPanic<L, I> {
     fn shr_lock(&self) {
-        self.inner.shr_lock()
+        self.inner.shr_lock();
+        held::acquire(self.addr());
     }
 
     fn shr_try_lock(&self) -> bool {
-        self.inner.shr_try_lock()
+        if self.inner.shr_try_lock() {
+            held::acquire(self.addr());
+            true
+        } else {
+            false
+        }
     }
 
     unsafe fn shr_split(&self) {
-        self.inner.shr_split()
+        self.inner.shr_split();
+        held::acquire(self.addr());
+    }
+
+    unsafe fn shr_try_split(&self) -> bool {
+        let split = self.inner.shr_try_split();
+
+        if split {
+            held::acquire(self.addr());
+        }
+
+        split
     }
 
     unsafe fn shr_unlock(&self) {
+        held::release(self.addr());
         self.inner.shr_unlock()
     }
 
-    unsafe fn shr_bump(&self) {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
         self.inner.shr_bump()
     }
 }
 
 unsafe impl<L: ?Sized + RawShareLockFair, I: ThreadInfo> RawShareLockFair for ReentrantPanic<L, I> {
     unsafe fn shr_unlock_fair(&self) {
+        held::release(self.addr());
         self.inner.shr_unlock_fair()
     }
 
-    unsafe fn shr_bump_fair(&self) {
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
         self.inner.shr_bump_fair()
     }
 }
 
+unsafe impl<L: ?Sized + RawShareLockUpgrade + RawLockInfo, I: ThreadInfo> RawShareLockUpgrade
+    for ReentrantPanic<L, I>
+{
+    unsafe fn upgrade(&self) {
+        if self.holds_other_shr_locks() {
+            self.deadlock("tried to upgrade a shared lock while the same thread holds another shared lock, this would deadlock!")
+        }
+
+        self.inner.upgrade();
+        held::release(self.addr());
+
+        let curr = self.thread_info.id().get();
+        self.owner.store(curr, Ordering::Release);
+    }
+
+    unsafe fn try_upgrade(&self) -> bool {
+        if self.holds_other_shr_locks() || !self.inner.try_upgrade() {
+            return false;
+        }
+
+        held::release(self.addr());
+
+        let curr = self.thread_info.id().get();
+        self.owner.store(curr, Ordering::Release);
+        true
+    }
+}
+
 #[test]
 #[cfg(all(feature = "extra", feature = "std"))]
 #[should_panic = "tried to lock a locked exclusive lock from the same thread!"]
@@ -166,3 +323,24 @@ fn reentrant_panic() {
 
     drop(_guard);
 }
+
+#[test]
+#[cfg(all(feature = "extra", feature = "std", debug_assertions))]
+#[should_panic = "tried to upgrade a shared lock while the same thread holds another shared lock"]
+fn upgrade_with_other_shr_lock() {
+    use crate::share_lock::ShareGuard;
+
+    let rwlock =
+        crate::rwlock::RwLock::<ReentrantPanic<crate::rwlock::default::DefaultLock>, _>::new(10);
+
+    let a = rwlock.read();
+    let b = rwlock.read();
+
+    let a = ShareGuard::try_upgrade(a).err().unwrap();
+    drop(a);
+    let b = ShareGuard::try_upgrade(b).ok().unwrap();
+    let b = crate::exclusive_lock::ExclusiveGuard::downgrade(b);
+
+    let _a = rwlock.read();
+    let _ = ShareGuard::upgrade(b);
+}
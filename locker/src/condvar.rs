@@ -1,19 +1,25 @@
-use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveLock};
+use crate::exclusive_lock::{
+    ExclusiveGuard, MappedExclusiveGuard, RawExclusiveGuard, RawExclusiveLock,
+};
+use crate::mutex::{Mutex, RawMutex};
 use crate::share_lock::{RawShareLock, ShareGuard};
 
 use crate::RawLockInfo;
 
 use std::time::{Duration, Instant};
 
+pub mod cell;
 pub mod raw;
 
+pub use cell::CondvarCell;
+
 pub struct Condvar {
     raw: raw::Condvar,
 }
 
 /// # Safety
 ///
-/// `exc_unlock` cannot call `parking_lot_core::park`, or panic
+/// `exc_unlock` cannot call `crate::parking::park`, or panic
 pub unsafe trait Parkable {}
 
 /// A type indicating whether a timed wait on a condition variable returned
@@ -78,6 +84,105 @@ impl Condvar {
     }
 }
 
+impl Condvar {
+    /// Blocks the current thread until this condition variable is notified, with a guard
+    /// that was mapped from a guard of `mutex`
+    ///
+    /// Mapped guards can't [`Wait`], because another thread may move or free the data they
+    /// point into while the lock is released. Instead, the old projection is thrown away,
+    /// and the guard is mapped again with `reproject` once the lock is re-acquired.
+    ///
+    /// # Panics
+    ///
+    /// If `guard` doesn't hold the lock of `mutex`
+    ///
+    /// ```
+    /// use locker::condvar::Condvar;
+    /// use locker::exclusive_lock::ExclusiveGuard;
+    /// use locker::mutex::default::DefaultLock;
+    ///
+    /// let mutex = DefaultLock::mutex(vec![1, 2, 3]);
+    /// let cv = Condvar::new();
+    ///
+    /// let guard = ExclusiveGuard::map(mutex.lock(), |v| &mut v[0]);
+    /// let (guard, result) = cv.wait_mapped_for(&mutex, guard, std::time::Duration::from_millis(1), |v| {
+    ///     v.last_mut().unwrap()
+    /// });
+    ///
+    /// assert!(result.timed_out());
+    /// assert_eq!(*guard, 3);
+    /// ```
+    pub fn wait_mapped<'a, L: RawMutex + Parkable, T: ?Sized, U: ?Sized>(
+        &self,
+        mutex: &'a Mutex<L, T>,
+        guard: MappedExclusiveGuard<'a, L, U>,
+        reproject: impl FnOnce(&mut T) -> &mut U,
+    ) -> MappedExclusiveGuard<'a, L, U> {
+        Self::wait_mapped_with(mutex, guard, reproject, |raw| {
+            self.raw.exc_wait(raw);
+            WaitTimeoutResult(false)
+        })
+        .0
+    }
+
+    /// Waits on this condition variable for a notification, timing out after the specified time instant,
+    /// with a guard that was mapped from a guard of `mutex`
+    ///
+    /// see [`wait_mapped`](Condvar::wait_mapped) for details
+    pub fn wait_mapped_until<'a, L: RawMutex + Parkable, T: ?Sized, U: ?Sized>(
+        &self,
+        mutex: &'a Mutex<L, T>,
+        guard: MappedExclusiveGuard<'a, L, U>,
+        instant: Instant,
+        reproject: impl FnOnce(&mut T) -> &mut U,
+    ) -> (MappedExclusiveGuard<'a, L, U>, WaitTimeoutResult) {
+        Self::wait_mapped_with(mutex, guard, reproject, |raw| {
+            self.raw.exc_wait_until(raw, instant)
+        })
+    }
+
+    /// Waits on this condition variable for a notification, timing out after the specified duration,
+    /// with a guard that was mapped from a guard of `mutex`
+    ///
+    /// see [`wait_mapped`](Condvar::wait_mapped) for details
+    pub fn wait_mapped_for<'a, L: RawMutex + Parkable, T: ?Sized, U: ?Sized>(
+        &self,
+        mutex: &'a Mutex<L, T>,
+        guard: MappedExclusiveGuard<'a, L, U>,
+        duration: Duration,
+        reproject: impl FnOnce(&mut T) -> &mut U,
+    ) -> (MappedExclusiveGuard<'a, L, U>, WaitTimeoutResult) {
+        Self::wait_mapped_with(mutex, guard, reproject, |raw| {
+            self.raw.exc_wait_for(raw, duration)
+        })
+    }
+
+    fn wait_mapped_with<'a, L: RawMutex, T: ?Sized, U: ?Sized>(
+        mutex: &'a Mutex<L, T>,
+        guard: MappedExclusiveGuard<'a, L, U>,
+        reproject: impl FnOnce(&mut T) -> &mut U,
+        wait: impl FnOnce(&mut RawExclusiveGuard<'a, L>) -> WaitTimeoutResult,
+    ) -> (MappedExclusiveGuard<'a, L, U>, WaitTimeoutResult) {
+        // the old projection may dangle once the lock is released, so it is never used again
+        let (mut raw, _) = ExclusiveGuard::into_raw_parts(guard);
+
+        assert!(
+            core::ptr::eq(raw.inner(), mutex.raw().inner()),
+            "tried to wait with a guard of a different mutex"
+        );
+
+        let result = wait(&mut raw);
+
+        // SAFETY: `raw` holds the lock of `mutex`, so we have exclusive access to its value
+        let value: *mut U = reproject(unsafe { &mut *mutex.as_mut_ptr() });
+
+        (
+            unsafe { ExclusiveGuard::from_raw_parts(raw, value) },
+            result,
+        )
+    }
+}
+
 pub trait Wait {
     fn wait(&mut self, cv: &Condvar);
 
@@ -0,0 +1,141 @@
+//! A mutex and condition variable that are used together to wait for a value
+
+use super::{Condvar, Parkable, WaitTimeoutResult};
+use crate::exclusive_lock::ExclusiveGuard;
+use crate::mutex::{Mutex, RawMutex};
+
+use std::time::{Duration, Instant};
+
+/// A value protected by a mutex, with a condition variable that is
+/// notified every time the value is modified
+///
+/// This removes the boilerplate of pairing a `Mutex` with a `Condvar`, and
+/// re-checking a predicate in a loop. Because the value can only be modified while
+/// the lock is held, and waiters are notified before the lock is released, no
+/// modification can be missed by a waiting thread.
+pub struct CondvarCell<L, T: ?Sized> {
+    cv: Condvar,
+    mutex: Mutex<L, T>,
+}
+
+impl<L: RawMutex + crate::Init, T: Default> Default for CondvarCell<L, T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<L, T> CondvarCell<L, T> {
+    /// Create a new `CondvarCell` from the given mutex
+    #[inline]
+    pub const fn from_mutex(mutex: Mutex<L, T>) -> Self {
+        Self {
+            cv: Condvar::new(),
+            mutex,
+        }
+    }
+
+    /// Consumes this `CondvarCell`, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.mutex.into_inner()
+    }
+}
+
+impl<L: RawMutex + crate::Init, T> CondvarCell<L, T> {
+    /// Create a new `CondvarCell` with the given value
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self::from_mutex(Mutex::new(value))
+    }
+}
+
+impl<L, T: ?Sized> CondvarCell<L, T> {
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `CondvarCell` mutably, no actual locking needs to take place
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.mutex.get_mut()
+    }
+
+    /// The underlying condition variable
+    #[inline]
+    pub fn condvar(&self) -> &Condvar {
+        &self.cv
+    }
+}
+
+impl<L: RawMutex + Parkable, T: ?Sized> CondvarCell<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// Acquire the lock without waiting for any condition
+    ///
+    /// Modifications made through this guard will not notify any waiters,
+    /// use `modify_and_notify` for that.
+    #[inline]
+    pub fn lock(&self) -> ExclusiveGuard<'_, L, T> {
+        self.mutex.lock()
+    }
+
+    /// Blocks the current thread until `f` returns true for the stored value
+    ///
+    /// `f` is checked with the lock held, and the lock is still held once
+    /// this function returns
+    pub fn wait_until(&self, mut f: impl FnMut(&T) -> bool) -> ExclusiveGuard<'_, L, T> {
+        let mut guard = self.mutex.lock();
+
+        while !f(&guard) {
+            self.cv.wait(&mut guard);
+        }
+
+        guard
+    }
+
+    /// Blocks the current thread until `f` returns true for the stored value,
+    /// or until the given duration has elapsed
+    ///
+    /// The returned `WaitTimeoutResult` only indicates a time out
+    /// if `f` still returns false.
+    pub fn wait_until_for(
+        &self,
+        duration: Duration,
+        mut f: impl FnMut(&T) -> bool,
+    ) -> (ExclusiveGuard<'_, L, T>, WaitTimeoutResult) {
+        let instant = Instant::now() + duration;
+        let mut guard = self.mutex.lock();
+
+        while !f(&guard) {
+            if self.cv.wait_until(&mut guard, instant).timed_out() {
+                let timed_out = !f(&guard);
+                return (guard, WaitTimeoutResult(timed_out));
+            }
+        }
+
+        (guard, WaitTimeoutResult(false))
+    }
+
+    /// Modifies the stored value, and notifies all waiting threads
+    ///
+    /// The waiting threads are notified before the lock is released
+    pub fn modify_and_notify<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut guard = self.mutex.lock();
+        let value = f(&mut guard);
+        self.cv.notify_all();
+        drop(guard);
+        value
+    }
+
+    /// Modifies the stored value, and notifies one waiting thread
+    ///
+    /// Only use this if any waiting thread can make progress after this modification,
+    /// otherwise use `modify_and_notify`
+    pub fn modify_and_notify_one<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut guard = self.mutex.lock();
+        let value = f(&mut guard);
+        self.cv.notify_one();
+        drop(guard);
+        value
+    }
+}
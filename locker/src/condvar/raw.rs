@@ -1,4 +1,4 @@
-use parking_lot_core::{self, UnparkResult, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+use crate::parking::{FilterOp, UnparkResult, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
 
 use super::{Parkable, WaitTimeoutResult};
 use crate::exclusive_lock::{RawExclusiveGuard, RawExclusiveLock};
@@ -41,7 +41,7 @@ impl Condvar {
     #[cold]
     fn notify_one_slow(&self) -> bool {
         unsafe {
-            // Unpark one thread and requeue the rest onto the mutex
+            // Unpark one thread, the rest stay parked on the condvar
             let key = self as *const _ as usize;
             let callback = |result: UnparkResult| {
                 // Clear our state if there are no more waiting threads
@@ -51,7 +51,7 @@ impl Condvar {
 
                 DEFAULT_UNPARK_TOKEN
             };
-            let res = parking_lot_core::unpark_one(key, callback);
+            let res = crate::parking::unpark_one(key, callback);
 
             res.unparked_threads != 0
         }
@@ -72,11 +72,17 @@ impl Condvar {
     #[cold]
     fn notify_all_slow(&self) -> usize {
         unsafe {
-            // Unpark one thread and requeue the rest onto the mutex
+            // Unpark all of the threads, they each re-acquire the mutex on their own
             let key = self as *const _ as usize;
-            let unpark_count = parking_lot_core::unpark_all(key, DEFAULT_UNPARK_TOKEN);
-            self.is_parked.store(false, Ordering::Relaxed);
-            unpark_count
+            // Clear our state while the queue is still locked, otherwise a woken thread
+            // could park again before the state is cleared, and miss the next notification
+            let callback = |_| {
+                self.is_parked.store(false, Ordering::Relaxed);
+                DEFAULT_UNPARK_TOKEN
+            };
+            let res = crate::parking::unpark_filter(key, |_| FilterOp::Unpark, callback);
+
+            res.unparked_threads
         }
     }
 
@@ -90,6 +96,7 @@ impl Condvar {
     ) -> WaitTimeoutResult {
         let result;
         {
+            let _trace = crate::trace::SlowPath::enter("condvar_wait", self);
             let addr = self as *const _ as usize;
             let validate = || self.is_parked.load(Ordering::Relaxed);
             let timed_out = |_, was_last_thread| {
@@ -103,7 +110,7 @@ impl Condvar {
 
             self.is_parked.store(true, Ordering::Relaxed);
 
-            result = parking_lot_core::park(
+            result = crate::parking::park(
                 addr,
                 validate,
                 unlock,
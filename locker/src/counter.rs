@@ -0,0 +1,159 @@
+//! A striped counter, which spreads updates across shards
+//!
+//! Updating a single atomic from many threads makes every update contend on the same cache
+//! line. [`ShardedCounter`] instead picks a shard based on the current thread, so threads
+//! mostly update their own shard, and only reads have to visit all of the shards. Threads are
+//! assigned shards the same way as in [`GlobalLock::lock_for_current_thread`].
+//!
+//! [`GlobalLock::lock_for_current_thread`]: crate::mutex::global::GlobalLock::lock_for_current_thread
+
+use crate::mutex::global::GlobalLock;
+use crate::remutex::std_thread::StdThreadInfo;
+use crate::remutex::ThreadInfo;
+use core::sync::atomic::{AtomicIsize, Ordering};
+
+const SHARDS: usize = 16;
+
+// each shard gets its own cache line (two on some platforms that prefetch pairs of lines),
+// so that updates to different shards don't contend
+#[repr(align(128))]
+struct Shard(AtomicIsize);
+
+impl crate::Init for Shard {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: Self = Self(AtomicIsize::new(0));
+}
+
+/// A counter that is cheap to update from many threads at once, and aggregated on read
+///
+/// Each thread updates one of a fixed number of shards, chosen by the thread's id as given by
+/// `I`. Reads sum up all of the shards, so they are slower than reading a single atomic, and
+/// they are not a snapshot: updates that race with a read may or may not be counted.
+///
+/// All arithmetic wraps on overflow.
+pub struct ShardedCounter<I = StdThreadInfo> {
+    thread_info: I,
+    shards: [Shard; SHARDS],
+}
+
+impl<I: crate::Init> crate::Init for ShardedCounter<I> {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: Self = Self::with_thread_info(crate::Init::INIT);
+}
+
+impl<I: crate::Init> Default for ShardedCounter<I> {
+    #[inline]
+    fn default() -> Self {
+        crate::Init::INIT
+    }
+}
+
+impl ShardedCounter {
+    /// Create a new counter, starting at zero
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_thread_info(StdThreadInfo)
+    }
+}
+
+impl<I> ShardedCounter<I> {
+    /// Create a new counter, starting at zero, which picks shards based on `thread_info`
+    #[inline]
+    pub const fn with_thread_info(thread_info: I) -> Self {
+        Self {
+            thread_info,
+            shards: [crate::Init::INIT; SHARDS],
+        }
+    }
+
+    /// Sum up all of the shards
+    ///
+    /// This is not a snapshot, updates made while summing may or may not be counted
+    pub fn sum(&self) -> isize {
+        self.shards.iter().fold(0, |sum, shard| {
+            sum.wrapping_add(shard.0.load(Ordering::Relaxed))
+        })
+    }
+
+    /// Reset the counter to zero, and return the sum of the shards before the reset
+    ///
+    /// Every update is either included in the returned sum, or kept in the counter,
+    /// but like [`sum`](Self::sum), this is not a snapshot.
+    pub fn reset(&self) -> isize {
+        self.shards.iter().fold(0, |sum, shard| {
+            sum.wrapping_add(shard.0.swap(0, Ordering::Relaxed))
+        })
+    }
+
+    /// Get the value of the counter, without any synchronization
+    pub fn get_mut(&mut self) -> isize {
+        self.shards
+            .iter_mut()
+            .fold(0, |sum, shard| sum.wrapping_add(*shard.0.get_mut()))
+    }
+
+    /// Get the value of the counter
+    pub fn into_inner(mut self) -> isize {
+        self.get_mut()
+    }
+}
+
+impl<I: ThreadInfo> ShardedCounter<I> {
+    #[inline]
+    fn shard(&self) -> &AtomicIsize {
+        let thread = self.thread_info.id().get();
+        let index = GlobalLock::shard_for_thread(self.shards.as_ptr() as usize, thread, SHARDS);
+        &self.shards[index].0
+    }
+
+    /// Add `value` to the counter
+    #[inline]
+    pub fn add(&self, value: isize) {
+        self.shard().fetch_add(value, Ordering::Relaxed);
+    }
+
+    /// Subtract `value` from the counter
+    #[inline]
+    pub fn sub(&self, value: isize) {
+        self.shard().fetch_sub(value, Ordering::Relaxed);
+    }
+
+    /// Add one to the counter
+    #[inline]
+    pub fn increment(&self) {
+        self.add(1)
+    }
+
+    /// Subtract one from the counter
+    #[inline]
+    pub fn decrement(&self) {
+        self.sub(1)
+    }
+}
+
+#[test]
+fn sharded_counter() {
+    static COUNTER: ShardedCounter = ShardedCounter::new();
+
+    crossbeam_utils::thread::scope(|s| {
+        for i in 0..8 {
+            s.spawn(move |_| {
+                for _ in 0..1000 {
+                    COUNTER.increment();
+                }
+
+                COUNTER.sub(i);
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(COUNTER.sum(), 8000 - 28);
+    assert_eq!(COUNTER.reset(), 8000 - 28);
+    assert_eq!(COUNTER.sum(), 0);
+
+    let mut counter = ShardedCounter::new();
+    counter.add(-3);
+    assert_eq!(counter.get_mut(), -3);
+    assert_eq!(counter.into_inner(), -3);
+}
@@ -0,0 +1,132 @@
+//! Diagnostics about who currently holds a lock
+//!
+//! This is enabled by the `debug-owners` feature flag, and is meant to be used in
+//! error paths, like when `RwLock::try_write_for` times out.
+
+use core::panic::Location;
+use std::thread::ThreadId;
+
+use crate::mutex::spin::{Mutex, SpinLock};
+
+/// Information about the current holder of a *exc lock*
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HolderInfo {
+    /// The id of the thread that acquired the *exc lock*
+    pub thread: ThreadId,
+
+    /// The location where the *exc lock* was acquired
+    pub location: &'static Location<'static>,
+}
+
+/// Raw locks which keep track of who holds their *exc lock*
+pub trait RawLockHolder {
+    /// Information about the current holder of the *exc lock*,
+    /// or `None` if the *exc lock* is not held
+    fn holder_info(&self) -> Option<HolderInfo>;
+}
+
+macro_rules! trait_impls {
+    ($L:ident => $($type:ty),*) => {$(
+        impl<$L: ?Sized + RawLockHolder> RawLockHolder for $type {
+            #[inline]
+            fn holder_info(&self) -> Option<HolderInfo> {
+                L::holder_info(self)
+            }
+        }
+    )*};
+}
+
+trait_impls! {
+    L => &L, &mut L, std::boxed::Box<L>, std::rc::Rc<L>, std::sync::Arc<L>
+}
+
+/// Storage for the holder of a *exc lock*
+pub(crate) struct HolderSlot {
+    holder: Mutex<Option<HolderInfo>>,
+}
+
+// the holder is only ever replaced as a whole while the mutex is held, so it can't be
+// observed in a broken state after a panic, and locks stay unwind safe with this feature
+impl std::panic::UnwindSafe for HolderSlot {}
+impl std::panic::RefUnwindSafe for HolderSlot {}
+
+impl HolderSlot {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            holder: SpinLock::mutex(None),
+        }
+    }
+
+    /// record the current thread and caller as the holder
+    #[inline]
+    #[track_caller]
+    pub fn record(&self) {
+        let info = HolderInfo {
+            thread: std::thread::current().id(),
+            location: Location::caller(),
+        };
+
+        *self.holder.lock() = Some(info);
+    }
+
+    /// record the current thread and caller as the holder if `locked` is true
+    #[inline]
+    #[track_caller]
+    pub fn record_if(&self, locked: bool) -> bool {
+        if locked {
+            self.record();
+        }
+
+        locked
+    }
+
+    #[inline]
+    pub fn clear(&self) {
+        *self.holder.lock() = None;
+    }
+
+    #[inline]
+    pub fn get(&self) -> Option<HolderInfo> {
+        *self.holder.lock()
+    }
+}
+
+#[test]
+#[cfg(feature = "parking_lot_core")]
+fn holder_info() {
+    let rwlock = crate::rwlock::adaptive::AdaptiveLock::rwlock(0);
+    assert!(rwlock.holder_info().is_none());
+
+    let guard = rwlock.write();
+    let info = rwlock.holder_info().unwrap();
+    assert_eq!(info.thread, std::thread::current().id());
+    assert_eq!(info.location.file(), file!());
+    assert!(rwlock
+        .try_write_for(std::time::Duration::from_millis(1))
+        .is_none());
+    assert_eq!(rwlock.holder_info(), Some(info));
+
+    drop(guard);
+    assert!(rwlock.holder_info().is_none());
+}
+
+#[test]
+fn spin_holder_info() {
+    use crate::exclusive_lock::ExclusiveGuard;
+
+    let rwlock = crate::rwlock::spin::SpinLock::rwlock(0);
+    assert!(rwlock.holder_info().is_none());
+
+    let mut guard = rwlock.write();
+    let info = rwlock.holder_info().unwrap();
+    assert_eq!(info.thread, std::thread::current().id());
+    assert_eq!(info.location.file(), file!());
+
+    ExclusiveGuard::bump(&mut guard);
+    assert_eq!(rwlock.holder_info(), Some(info));
+
+    let guard = ExclusiveGuard::downgrade(guard);
+    assert!(rwlock.holder_info().is_none());
+    drop(guard);
+}
@@ -6,7 +6,7 @@ mod guard;
 mod raw;
 
 pub use guard::{ExclusiveGuard, MappedExclusiveGuard};
-pub use raw::{RawExclusiveGuard, _RawExclusiveGuard};
+pub use raw::{_RawExclusiveGuard, RawExclusiveGuard};
 
 #[cfg(doc)]
 use crate::RawLockInfo;
@@ -61,6 +61,21 @@ pub unsafe trait RawExclusiveLock {
     /// returns true on success
     fn exc_try_lock(&self) -> bool;
 
+    /// acquire an *exc lock* through a unique borrow of the lock
+    ///
+    /// Since no other thread can see the lock, this doesn't need to synchronize with
+    /// other threads, and implementations may set the lock state directly instead of
+    /// going through the locking protocol. The *exc lock* is released with `exc_unlock`
+    /// as usual.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic or deadlock if the lock is already locked, i.e. a guard was leaked
+    #[inline]
+    fn exc_lock_mut(&mut self) {
+        self.exc_lock()
+    }
+
     /// Unlock a single exclusive lock
     ///
     /// This releases a *exc lock*
@@ -75,14 +90,16 @@ pub unsafe trait RawExclusiveLock {
     ///
     /// This method is functionally equivalent to calling `exc_unlock` followed by `exc_lock`,
     /// however it can be much more efficient in the case where there are no waiting threads.
+    /// Returns whether the lock was actually released, see [`BumpResult`](crate::BumpResult).
     ///
     /// # Safety
     ///
     /// * the caller must own a *exc lock*
     /// * the lock must not have been moved since it was locked
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         self.exc_unlock();
         self.exc_lock();
+        crate::BumpResult::YIELDED
     }
 }
 
@@ -105,6 +122,32 @@ pub unsafe trait RawExclusiveLockTimed: RawExclusiveLock + crate::RawTimedLock {
     ///
     /// returns true on success
     fn exc_try_lock_for(&self, duration: Self::Duration) -> bool;
+
+    /// Temporarily yields the lock to a waiting thread if there is one, but only
+    /// waits up to `duration` to get the lock back.
+    ///
+    /// This is useful for cooperative yielding in latency-sensitive loops. If the lock
+    /// could not be re-acquired within `duration` (for example, because the lock was
+    /// handed over to a slow thread), then this blocks until the lock is re-acquired, and
+    /// returns false. The caller should use this as a signal to stop yielding.
+    ///
+    /// returns true if the lock was re-acquired within `duration`
+    ///
+    /// # Safety
+    ///
+    /// * the caller must own a *exc lock*
+    /// * the lock must not have been moved since it was locked
+    #[cfg_attr(feature = "debug-owners", track_caller)]
+    unsafe fn exc_bump_for(&self, duration: Self::Duration) -> bool {
+        self.exc_unlock();
+
+        if self.exc_try_lock_for(duration) {
+            true
+        } else {
+            self.exc_lock();
+            false
+        }
+    }
 }
 
 /// # Safety
@@ -156,14 +199,16 @@ pub unsafe trait RawExclusiveLockFair: RawExclusiveLock {
     ///
     /// This method is functionally equivalent to calling `exc_unlock_fair` followed by `exc_lock`,
     /// however it can be much more efficient in the case where there are no waiting threads.
+    /// Returns whether the lock was actually released, see [`BumpResult`](crate::BumpResult).
     ///
     /// # Safety
     ///
     /// * the caller must own a *exc lock*
     /// * the lock must not have been moved since it was locked
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         self.exc_unlock_fair();
         self.exc_lock();
+        crate::BumpResult::YIELDED
     }
 }
 
@@ -173,6 +218,11 @@ pub unsafe trait RawExclusiveLockFair: RawExclusiveLock {
 ///
 /// [`RawExclusiveLockDowngrade::downgrade`] must release a *exc lock* and acquire a *shr lock*, and must not let any other thread
 /// acquire a lock in between.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not support downgrading a *exc lock* to a *shr lock*",
+    label = "this lock can't be downgraded",
+    note = "only rwlock-capable locks (see `AsRwLockCapable`) can downgrade their guards"
+)]
 pub unsafe trait RawExclusiveLockDowngrade:
     RawExclusiveLock + crate::share_lock::RawShareLock
 {
@@ -188,6 +238,54 @@ pub unsafe trait RawExclusiveLockDowngrade:
     /// * the caller must own a *exc lock*
     /// * the lock must not have been moved since it was locked
     unsafe fn downgrade(&self);
+
+    /// Attempts to atomically downgrade a *exc lock* to a *shr lock*
+    ///
+    /// If this returns true, then the *exc lock* was released and a *shr lock* was acquired,
+    /// like `downgrade`. Otherwise, the *exc lock* is still held.
+    ///
+    /// By default this always downgrades, locks that can't always downgrade
+    /// should override this.
+    ///
+    /// # Safety
+    ///
+    /// * the caller must own a *exc lock*
+    /// * the lock must not have been moved since it was locked
+    unsafe fn try_downgrade(&self) -> bool {
+        self.downgrade();
+        true
+    }
+}
+
+/// A marker for locks that support both *exc locks* and *shr locks*, and can
+/// atomically downgrade a *exc lock* to a *shr lock*.
+///
+/// This is implemented for every lock that implements [`RawExclusiveLockDowngrade`],
+/// and whose *shr lock* guards can be created. It is used to give better error messages
+/// when calling `downgrade` on the guard of an exclusive-only lock, like a mutex.
+///
+/// This bound also implies that `Self::ShareGuardTraits: Inhabitted`, so it is
+/// the only bound needed to downgrade guards.
+///
+/// ```compile_fail,E0277
+/// use locker::exclusive_lock::ExclusiveGuard;
+///
+/// let mutex = locker::mutex::default::DefaultLock::mutex(0);
+/// let _guard = ExclusiveGuard::downgrade(mutex.lock());
+/// ```
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is an exclusive-only lock, so its guards can't be downgraded",
+    label = "this lock does not support *shr locks*",
+    note = "use a rwlock-capable lock, like `rwlock::default::DefaultLock`, to downgrade guards"
+)]
+pub trait AsRwLockCapable:
+    RawExclusiveLockDowngrade + crate::RawLockInfo<ShareGuardTraits: crate::Inhabitted>
+{
+}
+
+impl<L> AsRwLockCapable for L where
+    L: ?Sized + RawExclusiveLockDowngrade + crate::RawLockInfo<ShareGuardTraits: crate::Inhabitted>
+{
 }
 
 macro_rules! trait_impls {
@@ -205,7 +303,7 @@ macro_rules! trait_impls {
                 L::exc_unlock(self)
             }
 
-            unsafe fn exc_bump(&self) {
+            unsafe fn exc_bump(&self) -> crate::BumpResult {
                 L::exc_bump(self)
             }
         }
@@ -218,6 +316,10 @@ macro_rules! trait_impls {
             fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
                 L::exc_try_lock_for(self, duration)
             }
+
+            unsafe fn exc_bump_for(&self, duration: Self::Duration) -> bool {
+                L::exc_bump_for(self, duration)
+            }
         }
 
         unsafe impl<$L: ?Sized + SplittableExclusiveLock> SplittableExclusiveLock for $type {
@@ -231,7 +333,7 @@ macro_rules! trait_impls {
                 L::exc_unlock_fair(self)
             }
 
-            unsafe fn exc_bump_fair(&self) {
+            unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
                 L::exc_bump_fair(self)
             }
         }
@@ -240,6 +342,10 @@ macro_rules! trait_impls {
             unsafe fn downgrade(&self) {
                 L::downgrade(self)
             }
+
+            unsafe fn try_downgrade(&self) -> bool {
+                L::try_downgrade(self)
+            }
         }
 
     )*};
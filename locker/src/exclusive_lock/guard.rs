@@ -1,11 +1,10 @@
-use super::{
-    RawExclusiveGuard, RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair,
-    SplittableExclusiveLock,
-};
+use super::{RawExclusiveGuard, RawExclusiveLock, RawExclusiveLockFair, SplittableExclusiveLock};
 use crate::RawLockInfo;
+use core::fmt;
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
 
+use crate::guard::Origin;
 pub use crate::guard::{Mapped, Pure, TryMapError};
 
 /// An RAII exclusive guard guard returned by `ExclusiveGuard::map`,
@@ -21,6 +20,7 @@ pub type MappedExclusiveGuard<'a, L, T> = ExclusiveGuard<'a, L, T, Mapped>;
 pub struct ExclusiveGuard<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St = Pure> {
     raw: RawExclusiveGuard<'a, L>,
     value: *mut T,
+    origin: Origin,
     _repr: PhantomData<(&'a mut T, St)>,
 }
 
@@ -45,10 +45,23 @@ impl<L: RawExclusiveLockFair + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'_, L,
     }
 }
 
+impl<L: super::RawExclusiveLockTimed + RawLockInfo, T: ?Sized> ExclusiveGuard<'_, L, T> {
+    /// Temporarily yields the lock to another thread if there is one,
+    /// but only waits up to `duration` to get the lock back.
+    ///
+    /// Returns false if the lock could not be re-acquired in time, the lock is held either way.
+    /// [read more](super::RawExclusiveLockTimed#method.exc_bump_for)
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub fn bump_for(g: &mut Self, duration: L::Duration) -> bool {
+        g.raw.bump_for(duration)
+    }
+}
+
 impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized> ExclusiveGuard<'a, L, T> {
     /// Temporarily yields the lock to another thread if there is one.
     /// [read more](RawExclusiveLock#method.exc_bump)
-    pub fn bump(g: &mut Self) {
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub fn bump(g: &mut Self) -> crate::BumpResult {
         g.raw.bump()
     }
 
@@ -63,8 +76,9 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized> ExclusiveGuard<'a, L, T>
 impl<'a, L: RawExclusiveLockFair + RawLockInfo, T: ?Sized> ExclusiveGuard<'a, L, T> {
     /// Temporarily yields the lock to a waiting thread if there is one.
     /// [read more](RawExclusiveLockFair#method.exc_bump_fair)
-    pub fn bump_fair(g: &mut Self) {
-        g.raw.bump_fair();
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub fn bump_fair(g: &mut Self) -> crate::BumpResult {
+        g.raw.bump_fair()
     }
 
     /// Temporarily unlocks the lock to execute the given function.
@@ -92,13 +106,14 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L,
                 Self {
                     raw,
                     value,
+                    origin: Origin::NONE,
                     _repr: PhantomData,
                 }
             }
 
             /// The inner `RawExclusiveGuard`
-            pub const fn raw(&self) -> &RawExclusiveGuard<'a, L> {
-                &self.raw
+            pub const fn raw(g: &Self) -> &RawExclusiveGuard<'a, L> {
+                &g.raw
             }
 
             /// The inner `RawExclusiveGuard`
@@ -107,8 +122,8 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L,
             ///
             /// * You must not unlock this lock temporarily if this is a mapped lock
             /// * You must not overwrite the raw guard with another raw guard
-            pub const unsafe fn raw_mut(&mut self) -> &mut RawExclusiveGuard<'a, L> {
-                &mut self.raw
+            pub const unsafe fn raw_mut(g: &mut Self) -> &mut RawExclusiveGuard<'a, L> {
+                &mut g.raw
             }
         } else {
             /// Create a new guard from the given raw guard and pointer
@@ -123,6 +138,7 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L,
                 Self {
                     raw,
                     value,
+                    origin: Origin::NONE,
                     _repr: PhantomData,
                 }
             }
@@ -144,6 +160,13 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L,
         }
     }
 
+    /// Remember the typed lock this guard was created from
+    #[inline]
+    pub(crate) fn with_origin(mut g: Self, origin: Origin) -> Self {
+        g.origin = origin;
+        g
+    }
+
     /// Decomposes the `ExclusiveGuard` into it's raw parts
     ///
     /// Returns the [`RawExclusiveGuard`] and a pointer to the guarded value.
@@ -161,13 +184,24 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L,
         (g.raw, g.value)
     }
 
+    /// Convert this guard into a `MappedExclusiveGuard` of the same data
+    ///
+    /// This gives up the ability to temporarily unlock the lock, and is how generic code can
+    /// treat a guard in any [`GuardState`](crate::GuardState) as a `MappedExclusiveGuard`.
+    ///
+    /// This is an associated function that needs to be used as `ExclusiveGuard::into_mapped(...)`.
+    #[inline]
+    pub fn into_mapped(g: Self) -> MappedExclusiveGuard<'a, L, T> {
+        unsafe { ExclusiveGuard::from_raw_parts(g.raw, g.value) }
+    }
+
     /// Make a new `MappedExclusiveGuard` for a component of the locked data.
     ///
     /// This operation cannot fail as the `ExclusiveGuard` passed in already locked the data.
     ///
     /// This is an associated function that needs to be used as `ExclusiveGuard::map(...)`.
     /// A method would interfere with methods of the same name on the contents of the locked data.
-    pub fn map<F, U: ?Sized>(
+    pub fn map<U: ?Sized>(
         g: Self,
         f: impl FnOnce(&mut T) -> &mut U,
     ) -> MappedExclusiveGuard<'a, L, U> {
@@ -176,6 +210,27 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L,
         unsafe { ExclusiveGuard::from_raw_parts(g.raw, value) }
     }
 
+    /// Coerces the guarded pointer, this is how [`unsize_guard`](crate::unsize_guard) is implemented
+    ///
+    /// # Safety
+    ///
+    /// `f` must return the pointer it was given, only changed by an unsizing coercion
+    #[doc(hidden)]
+    #[inline]
+    pub unsafe fn __locker_unsize<U: ?Sized>(
+        self,
+        f: impl FnOnce(*const T) -> *const U,
+    ) -> ExclusiveGuard<'a, L, U, St> {
+        let g = self;
+
+        ExclusiveGuard {
+            raw: g.raw,
+            value: f(g.value) as *mut U,
+            origin: Origin::NONE,
+            _repr: PhantomData,
+        }
+    }
+
     /// Attempts to make a new `MappedExclusiveGuard` for a component of the locked data.
     /// The original guard is return if the closure returns `Err` as well as the error.
     ///
@@ -219,6 +274,22 @@ impl<'a, L: SplittableExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard
         )
     }
 
+    /// Make `N` new `MappedExclusiveGuard`s for disjoint components of the locked data.
+    ///
+    /// This is like `split_map`, but for any number of components of the same type.
+    ///
+    /// This is an associated function that needs to be used as `ExclusiveGuard::split_into(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn split_into<U: ?Sized, const N: usize>(
+        g: Self,
+        f: impl FnOnce(&mut T) -> [&mut U; N],
+    ) -> [MappedExclusiveGuard<'a, L, U>; N] {
+        let values = f(unsafe { &mut *g.value });
+        let raw = g.raw;
+
+        values.map(|value| unsafe { ExclusiveGuard::from_raw_parts(raw.clone(), value) })
+    }
+
     /// Attempts to make two new `MappedExclusiveGuard`s for a component of the locked data.
     /// The original guard is return if the closure returns `Err` as well as the error.
     ///
@@ -252,17 +323,131 @@ impl<'a, L: SplittableExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard
     }
 }
 
-impl<'a, L: RawExclusiveLockDowngrade + RawLockInfo, T: ?Sized> ExclusiveGuard<'a, L, T>
-where
-    L::ShareGuardTraits: crate::Inhabitted,
-{
+impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized> ExclusiveGuard<'a, L, T> {
     /// Atomically downgrades a *exc lock* into a *shr lock* without allowing any new
     /// *exc locks* in the meantime.
-    pub fn downgrade(g: Self) -> crate::share_lock::ShareGuard<'a, L, T> {
-        unsafe { crate::share_lock::ShareGuard::from_raw_parts(g.raw.downgrade(), g.value) }
+    pub fn downgrade(g: Self) -> crate::share_lock::ShareGuard<'a, L, T>
+    where
+        L: super::AsRwLockCapable,
+    {
+        let origin = g.origin;
+        let g =
+            unsafe { crate::share_lock::ShareGuard::from_raw_parts(g.raw.downgrade(), g.value) };
+        crate::share_lock::ShareGuard::with_origin(g, origin)
+    }
+
+    /// Attempts to atomically downgrade a *exc lock* into a *shr lock* without allowing any new
+    /// *exc locks* in the meantime.
+    ///
+    /// If the lock can't be downgraded, then the original guard is returned.
+    pub fn try_downgrade(g: Self) -> Result<crate::share_lock::ShareGuard<'a, L, T>, Self>
+    where
+        L: super::AsRwLockCapable,
+    {
+        let origin = g.origin;
+
+        match g.raw.try_downgrade_to_raw_share() {
+            Ok(raw) => Ok(crate::share_lock::ShareGuard::with_origin(
+                unsafe { crate::share_lock::ShareGuard::from_raw_parts(raw, g.value) },
+                origin,
+            )),
+            Err(raw) => Err(ExclusiveGuard::with_origin(
+                unsafe { ExclusiveGuard::from_raw_parts(raw, g.value) },
+                origin,
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "guard-origin")]
+impl<'a, L: RawExclusiveLock + RawLockInfo, T> ExclusiveGuard<'a, L, T> {
+    /// The [`RwLock`](crate::rwlock::RwLock) this guard was created from
+    ///
+    /// This allows functions that only take a guard to lock the same `RwLock` again, for
+    /// example to take another read lock after downgrading. The origin is kept across
+    /// `bump_for`, downgrades and upgrades, but not by mapping, so this returns `None` if
+    /// the guard wasn't created by a `RwLock` (i.e. with `from_raw_parts` or by a `Mutex`).
+    ///
+    /// This is an associated function that needs to be used as `ExclusiveGuard::rwlock(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    #[inline]
+    pub fn rwlock(g: &Self) -> Option<&'a crate::rwlock::RwLock<L, T>> {
+        // SAFETY: only `RwLock<L, T>` sets the origin of a pure guard over a `T`,
+        // and it is reset whenever the guard is mapped
+        unsafe { g.origin.get() }
     }
 }
 
+impl<L: RawExclusiveLock + RawLockInfo, T, St> ExclusiveGuard<'_, L, T, St> {
+    /// Replace the locked value, returning the old value
+    ///
+    /// This is an associated function that needs to be used as `ExclusiveGuard::replace(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    #[inline]
+    pub fn replace(g: &mut Self, value: T) -> T {
+        core::mem::replace(&mut **g, value)
+    }
+
+    /// Overwrite the locked value, dropping the old value
+    ///
+    /// This is an associated function that needs to be used as `ExclusiveGuard::set(...)`.
+    #[inline]
+    pub fn set(g: &mut Self, value: T) {
+        **g = value;
+    }
+
+    /// Replace the locked value with the result of `f` on the old value
+    ///
+    /// The old value is moved out with [`Default::default`] left in its place, so if `f`
+    /// panics, the lock is left holding the default value.
+    ///
+    /// This is an associated function that needs to be used as `ExclusiveGuard::update(...)`.
+    #[inline]
+    pub fn update(g: &mut Self, f: impl FnOnce(T) -> T)
+    where
+        T: Default,
+    {
+        let value = core::mem::take(&mut **g);
+        **g = f(value);
+    }
+
+    /// Replace the locked value with the result of `f` on the old value, in place
+    ///
+    /// Unlike [`update`](Self::update), this doesn't need a temporary value, but there is
+    /// nothing to leave behind if `f` panics.
+    ///
+    /// This is an associated function that needs to be used as `ExclusiveGuard::replace_with(...)`.
+    ///
+    /// # Panic
+    ///
+    /// If `f` panics, then the process is aborted
+    #[inline]
+    pub fn replace_with(g: &mut Self, f: impl FnOnce(T) -> T) {
+        struct Abort;
+
+        impl Drop for Abort {
+            fn drop(&mut self) {
+                // panicking while already panicking aborts
+                panic!("aborting because the function passed to `replace_with` panicked")
+            }
+        }
+
+        let value = g.value;
+
+        unsafe {
+            let abort = Abort;
+            value.write(f(value.read()));
+            core::mem::forget(abort);
+        }
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized + core::marker::Unsize<U>, U: ?Sized, St>
+    core::ops::CoerceUnsized<ExclusiveGuard<'a, L, U, St>> for ExclusiveGuard<'a, L, T, St>
+{
+}
+
 impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> Deref for ExclusiveGuard<'_, L, T, St> {
     type Target = T;
 
@@ -276,3 +461,39 @@ impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> DerefMut for ExclusiveGua
         unsafe { &mut *self.value }
     }
 }
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + fmt::Debug, St> fmt::Debug
+    for ExclusiveGuard<'_, L, T, St>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + fmt::Display, St> fmt::Display
+    for ExclusiveGuard<'_, L, T, St>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+/// Compares the protected value with `U`, this only reads through the guard,
+/// so it never locks anything
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + PartialEq<U>, U: ?Sized, St> PartialEq<U>
+    for ExclusiveGuard<'_, L, T, St>
+{
+    fn eq(&self, other: &U) -> bool {
+        T::eq(self, other)
+    }
+}
+
+/// Compares the protected value with `U`, this only reads through the guard,
+/// so it never locks anything
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + PartialOrd<U>, U: ?Sized, St> PartialOrd<U>
+    for ExclusiveGuard<'_, L, T, St>
+{
+    fn partial_cmp(&self, other: &U) -> Option<core::cmp::Ordering> {
+        T::partial_cmp(self, other)
+    }
+}
@@ -72,13 +72,24 @@ where
     }
 }
 
+impl<L: super::RawExclusiveLockTimed + RawLockInfo> RawExclusiveGuard<'_, L> {
+    /// Temporarily yields the lock to another thread if there is one,
+    /// but only waits up to `duration` to get the lock back.
+    ///
+    /// Returns false if the lock could not be re-acquired in time, the lock is held either way.
+    /// [read more](super::RawExclusiveLockTimed#method.exc_bump_for)
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub fn bump_for(&mut self, duration: L::Duration) -> bool {
+        unsafe { self.lock.exc_bump_for(duration) }
+    }
+}
+
 impl<'a, L: RawExclusiveLock + RawLockInfo> RawExclusiveGuard<'a, L> {
     /// Temporarily yields the lock to another thread if there is one.
     /// [read more](RawExclusiveLock#method.exc_bump)
-    pub fn bump(&mut self) {
-        unsafe {
-            self.lock.exc_bump();
-        }
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub fn bump(&mut self) -> crate::BumpResult {
+        unsafe { self.lock.exc_bump() }
     }
 
     /// Temporarily unlocks the lock to execute the given function.
@@ -92,6 +103,12 @@ impl<'a, L: RawExclusiveLock + RawLockInfo> RawExclusiveGuard<'a, L> {
         f()
     }
 
+    /// The kind of access this guard has, which is always [`GuardKind::Exclusive`](crate::rwlock::transition::GuardKind::Exclusive)
+    #[inline]
+    pub fn kind(&self) -> crate::rwlock::transition::GuardKind {
+        crate::rwlock::transition::GuardKind::Exclusive
+    }
+
     /// The inner lock
     pub fn inner(&self) -> &L {
         self.lock
@@ -115,10 +132,9 @@ impl<L: RawExclusiveLockFair + RawLockInfo> RawExclusiveGuard<'_, L> {
 
     /// Temporarily yields the lock to a waiting thread if there is one.
     /// [read more](RawExclusiveLockFair#method.exc_bump_fair)
-    pub fn bump_fair(&mut self) {
-        unsafe {
-            self.lock.exc_bump_fair();
-        }
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub fn bump_fair(&mut self) -> crate::BumpResult {
+        unsafe { self.lock.exc_bump_fair() }
     }
 
     /// Temporarily unlocks the lock to execute the given function.
@@ -135,15 +151,35 @@ impl<L: RawExclusiveLockFair + RawLockInfo> RawExclusiveGuard<'_, L> {
     }
 }
 
-impl<'a, L: RawExclusiveLockDowngrade + RawLockInfo> RawExclusiveGuard<'a, L>
-where
-    L::ShareGuardTraits: Inhabitted,
-{
+impl<'a, L: RawExclusiveLock + RawLockInfo> RawExclusiveGuard<'a, L> {
     /// Atomically downgrades a write lock into a read lock without allowing
     /// any writers to take exclusive access of the lock in the meantime.
-    pub fn downgrade(self) -> crate::share_lock::RawShareGuard<'a, L> {
+    pub fn downgrade(self) -> crate::share_lock::RawShareGuard<'a, L>
+    where
+        L: super::AsRwLockCapable,
+    {
         self.into()
     }
+
+    /// Attempts to atomically downgrade a write lock into a read lock without allowing
+    /// any writers to take exclusive access of the lock in the meantime.
+    ///
+    /// If the lock can't be downgraded, then the write lock is returned.
+    /// [read more](super::RawExclusiveLockDowngrade#method.try_downgrade)
+    pub fn try_downgrade_to_raw_share(self) -> Result<crate::share_lock::RawShareGuard<'a, L>, Self>
+    where
+        L: super::AsRwLockCapable,
+    {
+        unsafe {
+            if self.lock.try_downgrade() {
+                Ok(crate::share_lock::RawShareGuard::from_raw(
+                    self.into_inner(),
+                ))
+            } else {
+                Err(self)
+            }
+        }
+    }
 }
 
 impl<'a, L: RawExclusiveLockDowngrade + RawLockInfo> From<RawExclusiveGuard<'a, L>>
@@ -4,6 +4,80 @@ pub enum Pure {}
 /// Represents an mapped guard
 pub enum Mapped {}
 
+mod seal {
+    pub trait Seal {}
+}
+
+impl seal::Seal for Pure {}
+impl seal::Seal for Mapped {}
+
+/// The state of a guard, either [`Pure`] or [`Mapped`]
+///
+/// Both states are uninhabited, so they don't change the size of a guard. This trait lets
+/// generic code accept either kind of guard, and check at compile time which operations
+/// are available.
+pub trait GuardState: seal::Seal {
+    /// If a guard in this state may temporarily unlock the lock (i.e. `bump` or `unlocked`)
+    ///
+    /// Only [`Pure`] guards can, because a [`Mapped`] guard may point into data that
+    /// another thread could move or free while the lock is released.
+    const CAN_UNLOCK_TEMPORARILY: bool;
+
+    /// If a guard in this state may temporarily unlock the lock
+    ///
+    /// see [`CAN_UNLOCK_TEMPORARILY`](GuardState::CAN_UNLOCK_TEMPORARILY)
+    #[inline]
+    fn can_unlock_temporarily() -> bool {
+        Self::CAN_UNLOCK_TEMPORARILY
+    }
+}
+
+impl GuardState for Pure {
+    const CAN_UNLOCK_TEMPORARILY: bool = true;
+}
+
+impl GuardState for Mapped {
+    const CAN_UNLOCK_TEMPORARILY: bool = false;
+}
+
+/// The typed lock that a guard was created from
+///
+/// This is only tracked with the `guard-origin` feature flag, otherwise it is zero-sized
+/// and guards never know their origin.
+#[derive(Clone, Copy)]
+pub(crate) struct Origin {
+    #[cfg(feature = "guard-origin")]
+    ptr: *const (),
+}
+
+impl Origin {
+    /// The guard wasn't created from a typed lock, or it was mapped since
+    pub(crate) const NONE: Self = Self {
+        #[cfg(feature = "guard-origin")]
+        ptr: core::ptr::null(),
+    };
+
+    #[inline]
+    #[allow(unused_variables)]
+    pub(crate) fn new<T: ?Sized>(lock: &T) -> Self {
+        Self {
+            #[cfg(feature = "guard-origin")]
+            ptr: lock as *const T as *const (),
+        }
+    }
+
+    /// The typed lock this guard was created from
+    ///
+    /// # Safety
+    ///
+    /// This origin must have been created from a `&'a T`, or be `NONE`
+    #[cfg(feature = "guard-origin")]
+    #[inline]
+    pub(crate) unsafe fn get<'a, T>(self) -> Option<&'a T> {
+        self.ptr.cast::<T>().as_ref()
+    }
+}
+
 /// The error return type of `try_map` and `try_split_map`
 ///
 /// Contains the error and the old guard in that order
@@ -0,0 +1,205 @@
+//! A set of guards that are released in a declared order
+//!
+//! Normally guards are released in the reverse order of the local variables that hold them,
+//! so code that depends on the release order is easy to break by moving a `let` around.
+//! A [`GuardSet`] owns guards of any type, and releases them in the [`Order`] it was
+//! created with, either all at once when it is dropped, or part of the way with
+//! [`GuardSet::release_until`].
+//!
+//! ```
+//! use locker::guard_set::{GuardSet, Order};
+//! use locker::rwlock::default::DefaultLock;
+//!
+//! let root = DefaultLock::mutex(0);
+//! let page = DefaultLock::rwlock(1);
+//! let leaf = DefaultLock::mutex(2);
+//!
+//! let mut set = GuardSet::new(Order::Fifo);
+//! let root_marker = set.push(root.lock());
+//! set.push(page.read());
+//! set.push(leaf.lock());
+//!
+//! // release the root latch once we are inside of the page, keep the rest
+//! set.release_until(root_marker);
+//! assert!(root.try_lock().is_some());
+//! assert!(leaf.try_lock().is_none());
+//!
+//! drop(set);
+//! assert!(leaf.try_lock().is_some());
+//! ```
+
+use std::boxed::Box;
+use std::collections::VecDeque;
+
+// every type can be dropped, so this erases everything but the drop glue
+trait Erased {}
+impl<T: ?Sized> Erased for T {}
+
+/// The order that a [`GuardSet`] releases its guards in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Order {
+    /// The last guard that was pushed is released first, like local variables
+    Lifo,
+    /// The first guard that was pushed is released first
+    Fifo,
+}
+
+/// Identifies a guard in a [`GuardSet`]
+///
+/// Markers are only meaningful for the set that returned them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Marker(usize);
+
+/// A set of guards of any type, see the [module docs](self) for details
+pub struct GuardSet<'a> {
+    guards: VecDeque<(Marker, Box<dyn Erased + 'a>)>,
+    next: usize,
+    order: Order,
+}
+
+impl Default for GuardSet<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Order::Lifo)
+    }
+}
+
+impl<'a> GuardSet<'a> {
+    /// Create an empty guard set, which releases its guards in the given order
+    #[inline]
+    pub fn new(order: Order) -> Self {
+        Self {
+            guards: VecDeque::new(),
+            next: 0,
+            order,
+        }
+    }
+
+    /// The order that guards are released in
+    #[inline]
+    pub fn order(&self) -> Order {
+        self.order
+    }
+
+    /// The number of guards that are still held
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.guards.len()
+    }
+
+    /// Check if all guards were released
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.guards.is_empty()
+    }
+
+    /// Add a guard to the set, and return a marker that identifies it
+    pub fn push<G: 'a>(&mut self, guard: G) -> Marker {
+        let marker = Marker(self.next);
+        self.next += 1;
+        self.guards.push_back((marker, Box::new(guard)));
+        marker
+    }
+
+    /// Check if the guard identified by `marker` is still held by this set
+    pub fn contains(&self, marker: Marker) -> bool {
+        self.position(marker).is_some()
+    }
+
+    fn position(&self, marker: Marker) -> Option<usize> {
+        // markers are handed out in increasing order, so the guards are sorted by them
+        self.guards
+            .binary_search_by_key(&marker.0, |(m, _)| m.0)
+            .ok()
+    }
+
+    /// Release the next guard in the set's order
+    ///
+    /// Returns false if the set was empty
+    pub fn release_one(&mut self) -> bool {
+        let guard = match self.order {
+            Order::Lifo => self.guards.pop_back(),
+            Order::Fifo => self.guards.pop_front(),
+        };
+
+        guard.is_some()
+    }
+
+    /// Release guards in the set's order, up to and including the guard identified by `marker`
+    ///
+    /// If that guard was already released, then nothing is released, and this returns false.
+    pub fn release_until(&mut self, marker: Marker) -> bool {
+        if !self.contains(marker) {
+            return false;
+        }
+
+        while self.contains(marker) {
+            self.release_one();
+        }
+
+        true
+    }
+
+    /// Release the guards identified by `markers`, in the order they are given,
+    /// regardless of the set's order
+    ///
+    /// Markers of guards which were already released are skipped
+    pub fn release_in(&mut self, markers: impl IntoIterator<Item = Marker>) {
+        for marker in markers {
+            if let Some(index) = self.position(marker) {
+                self.guards.remove(index);
+            }
+        }
+    }
+
+    /// Release all guards in the set's order
+    pub fn release_all(&mut self) {
+        while self.release_one() {}
+    }
+}
+
+impl Drop for GuardSet<'_> {
+    fn drop(&mut self) {
+        self.release_all()
+    }
+}
+
+#[test]
+fn release_order() {
+    use core::cell::RefCell;
+    use std::vec::Vec;
+
+    struct Log<'a>(&'a RefCell<Vec<u8>>, u8);
+
+    impl Drop for Log<'_> {
+        fn drop(&mut self) {
+            self.0.borrow_mut().push(self.1)
+        }
+    }
+
+    let log = RefCell::new(Vec::new());
+    let take = || core::mem::take(&mut *log.borrow_mut());
+
+    let mut set = GuardSet::new(Order::Lifo);
+    let markers: Vec<_> = (0..5).map(|i| set.push(Log(&log, i))).collect();
+
+    assert!(set.release_until(markers[3]));
+    assert_eq!(take(), [4, 3]);
+    assert!(!set.release_until(markers[4]));
+    assert_eq!(set.len(), 3);
+
+    drop(set);
+    assert_eq!(take(), [2, 1, 0]);
+
+    let mut set = GuardSet::new(Order::Fifo);
+    let markers: Vec<_> = (0..5).map(|i| set.push(Log(&log, i))).collect();
+
+    set.release_in([markers[2], markers[4], markers[2]]);
+    assert_eq!(take(), [2, 4]);
+    assert!(set.release_until(markers[1]));
+    assert_eq!(take(), [0, 1]);
+    assert!(set.contains(markers[3]));
+
+    drop(set);
+    assert_eq!(take(), [3]);
+}
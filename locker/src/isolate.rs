@@ -0,0 +1,49 @@
+//! Scoped, thread-local overrides of the global lock sets, used by `with_isolated_set`
+
+use core::cell::Cell;
+use std::boxed::Box;
+use std::thread::LocalKey;
+
+/// The lock set that the current thread should use instead of the global one
+pub(crate) type Slot<S> = Cell<Option<&'static S>>;
+
+/// Get the lock set installed on the current thread, if any
+#[inline]
+pub(crate) fn current<S>(slot: &'static LocalKey<Slot<S>>) -> Option<&'static S> {
+    slot.with(Cell::get)
+}
+
+/// Run `f` with a fresh lock set installed in `slot`
+///
+/// # Safety
+///
+/// No reference into the fresh lock set may outlive `f`
+pub(crate) unsafe fn with_isolated<S: crate::Init + 'static, R>(
+    slot: &'static LocalKey<Slot<S>>,
+    f: impl FnOnce() -> R,
+) -> R {
+    struct Restore<S: 'static> {
+        slot: &'static LocalKey<Slot<S>>,
+        previous: Option<&'static S>,
+        set: *mut S,
+    }
+
+    impl<S> Drop for Restore<S> {
+        fn drop(&mut self) {
+            let previous = self.previous;
+            self.slot.with(|slot| slot.set(previous));
+            // the caller guarantees that nothing borrows from the set anymore
+            drop(unsafe { Box::from_raw(self.set) });
+        }
+    }
+
+    let set = Box::into_raw(Box::new(S::INIT));
+    let previous = slot.with(|slot| slot.replace(Some(&*set)));
+    let _restore = Restore {
+        slot,
+        previous,
+        set,
+    };
+
+    f()
+}
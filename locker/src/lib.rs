@@ -8,7 +8,9 @@
         const_mut_refs,
         const_raw_ptr_deref,
         const_loop,
-        const_generics
+        const_generics,
+        coerce_unsized,
+        unsize
     )
 )]
 
@@ -30,6 +32,37 @@ macro_rules! defer {
     };
 }
 
+/// Coerces a guard over a sized type into a guard over an unsized type,
+/// like a trait object or a slice
+///
+/// This works for both `ExclusiveGuard`s and `ShareGuard`s, and keeps the state of the guard,
+/// so a `Pure` guard can still be temporarily unlocked after it is coerced.
+/// Only unsizing coercions are allowed, so the coerced guard always points to the same value.
+///
+/// With the `nightly` feature flag, guards also implement `CoerceUnsized`, so they
+/// can be coerced implicitly.
+///
+/// ```
+/// use locker::exclusive_lock::ExclusiveGuard;
+/// use locker::mutex::default::{DefaultLock, Mutex};
+/// use std::fmt::Debug;
+///
+/// fn debug(mutex: &Mutex<u32>) -> ExclusiveGuard<'_, DefaultLock, dyn Debug> {
+///     locker::unsize_guard!(mutex.lock() => dyn Debug)
+/// }
+///
+/// let mutex = Mutex::new(10);
+/// assert_eq!(format!("{:?}", &*debug(&mutex)), "10");
+/// ```
+#[macro_export]
+macro_rules! unsize_guard {
+    ($guard:expr => $type:ty) => {
+        match $guard {
+            guard => unsafe { guard.__locker_unsize(|ptr| -> *const $type { ptr }) },
+        }
+    };
+}
+
 /// Create an item at compile time
 pub trait Init: Sized {
     #[allow(clippy::declare_interior_mutable_const)]
@@ -37,6 +70,129 @@ pub trait Init: Sized {
     const INIT: Self;
 }
 
+/// Implement [`Init`] and [`Default`] for a type, so that both create the same value
+///
+/// Generic parameters go in square brackets before the type
+///
+/// ```
+/// use locker::Init;
+///
+/// struct Counter(u32);
+/// struct Pair<T>(T, T);
+///
+/// locker::impl_init! {
+///     Counter = Counter(0);
+///     [T: Init] Pair<T> = Pair(T::INIT, T::INIT);
+/// }
+///
+/// assert_eq!(Counter::default().0, 0);
+/// assert_eq!(Pair::<Counter>::INIT.1 .0, 0);
+/// ```
+#[macro_export]
+macro_rules! impl_init {
+    ([$($generics:tt)*] $type:ty = $init:expr $(; $($rest:tt)*)?) => {
+        impl<$($generics)*> $crate::Init for $type {
+            #[allow(clippy::declare_interior_mutable_const)]
+            const INIT: Self = $init;
+        }
+
+        impl<$($generics)*> Default for $type {
+            #[inline]
+            fn default() -> Self {
+                <Self as $crate::Init>::INIT
+            }
+        }
+
+        $($crate::impl_init! { $($rest)* })?
+    };
+    ($type:ty = $init:expr $(; $($rest:tt)*)?) => {
+        $crate::impl_init! { [] $type = $init $(; $($rest)*)? }
+    };
+    () => {};
+}
+
+macro_rules! init_tuple {
+    ($($T:ident),*) => {
+        impl<$($T: Init),*> Init for ($($T,)*) {
+            #[allow(clippy::declare_interior_mutable_const)]
+            const INIT: Self = ($($T::INIT,)*);
+        }
+    };
+}
+
+init_tuple!();
+init_tuple!(A);
+init_tuple!(A, B);
+init_tuple!(A, B, C);
+init_tuple!(A, B, C, D);
+init_tuple!(A, B, C, D, E);
+init_tuple!(A, B, C, D, E, F);
+init_tuple!(A, B, C, D, E, F, G);
+init_tuple!(A, B, C, D, E, F, G, H);
+
+impl<T: Init, const N: usize> Init for [T; N] {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: Self = [T::INIT; N];
+}
+
+/// Convert an unlocked lock into a lock with another backend
+///
+/// Because locks are taken by value, there can't be any guards borrowing them,
+/// so the converted lock can always start out unlocked. This allows applications
+/// to pick a lock backend at initialization, for example based on runtime configuration.
+///
+/// This is implemented between the general purpose backends of each module, to convert
+/// into any other lock that implements [`Init`], use `convert_init` instead.
+///
+/// # Safety
+///
+/// `convert_lock` must return a lock that is unlocked, and that is valid to
+/// pass to the `from_raw` constructors of the raw mutex and rwlock
+pub unsafe trait ConvertLock<L>: Sized {
+    /// Convert this lock into `L`
+    fn convert_lock(self) -> L;
+}
+
+// implements `ConvertLock` between each pair of the given locks
+#[cfg(feature = "extra")]
+macro_rules! convert_lock {
+    ($($lock:ty),* $(,)?) => {
+        convert_lock!(@from [$($lock),*] $($lock),*);
+    };
+    (@from $all:tt $($from:ty),*) => {$(
+        convert_lock!(@to $from => $all);
+    )*};
+    (@to $from:ty => [$($to:ty),*]) => {$(
+        unsafe impl crate::ConvertLock<$to> for $from {
+            #[inline]
+            fn convert_lock(self) -> $to {
+                crate::Init::INIT
+            }
+        }
+    )*};
+}
+
+/// The outcome of temporarily yielding a lock with one of the `bump` functions
+///
+/// Cooperative loops that bump a lock every so often can use this to tell if anyone
+/// is actually waiting for the lock, and bump less often if nobody is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BumpResult {
+    /// If the lock was released and re-acquired, giving other threads a chance to take it
+    ///
+    /// Locks only do this if there are threads waiting for it, or if they can't tell
+    /// if there are, so `false` means that no other thread could have taken the lock
+    pub yielded: bool,
+}
+
+impl BumpResult {
+    /// The lock was kept the entire time
+    pub const KEPT: Self = Self { yielded: false };
+
+    /// The lock was released and re-acquired
+    pub const YIELDED: Self = Self { yielded: true };
+}
+
 /// Some basic information about raw locks, like how to create them and
 /// what traits their guards should implement
 ///
@@ -56,6 +212,16 @@ pub unsafe trait RawLockInfo {
 
     /// A type that will remove auto-trait implementations for the `*ShareGuard` types
     type ShareGuardTraits: marker::Marker;
+
+    /// The name of this lock, which is shown in diagnostics like panic messages
+    /// and watchdog reports
+    ///
+    /// Locks are unnamed by default, with the `names` feature flag they can be
+    /// named by wrapping them in `named::Named`
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
 }
 
 /// Used in the `*LockTimed` traits
@@ -73,16 +239,54 @@ pub trait RawTimedLock: RawLockInfo {
     type Duration;
 }
 
+mod any;
+pub mod barrier;
+pub mod cell;
 pub mod combinators;
+#[cfg(all(feature = "extra", feature = "std"))]
+pub mod counter;
+#[cfg(all(feature = "debug-owners", feature = "extra"))]
+pub mod debug_owners;
 mod defer;
 pub mod exclusive_lock;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod guard_set;
+#[cfg(feature = "test-util")]
+mod isolate;
+pub mod lock_all;
+#[cfg(feature = "parking_lot_core")]
+pub mod lock_set;
+#[cfg(feature = "test-util")]
+pub mod mock;
 pub mod mutex;
+#[cfg(all(feature = "names", feature = "extra"))]
+pub mod named;
+pub mod nostd;
+pub mod notify;
 #[allow(missing_docs)]
 pub mod once;
+pub mod parking;
+pub mod prelude;
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+pub mod queue;
+pub mod raw_state;
+#[cfg(all(feature = "registry", feature = "extra"))]
+pub mod registry;
 pub mod remutex;
 pub mod rwlock;
+#[cfg(feature = "std")]
+pub mod scope;
 pub mod share_lock;
+#[cfg(feature = "std")]
+pub mod shutdown;
 mod spin_wait;
+#[cfg(all(feature = "extra", any(feature = "std", feature = "alloc")))]
+pub mod swap;
+#[cfg(all(test, feature = "std"))]
+mod test_waker;
+mod trace;
+#[cfg(all(feature = "watchdog", feature = "extra"))]
+pub mod watchdog;
 
 #[allow(missing_docs)]
 #[cfg(feature = "parking_lot_core")]
@@ -93,7 +297,27 @@ pub mod marker;
 #[cfg(feature = "parking_lot_core")]
 pub mod waiter; // 25
 
-pub use guard::{Mapped, Pure, TryMapError};
+pub use guard::{GuardState, Mapped, Pure, TryMapError};
+
+/// A mutex that uses the [default mutex lock](crate::mutex::default)
+#[cfg(feature = "extra")]
+pub type Mutex<T> = mutex::default::Mutex<T>;
+
+/// A rwlock that uses the [default rwlock lock](crate::rwlock::default)
+#[cfg(feature = "extra")]
+pub type RwLock<T> = rwlock::default::RwLock<T>;
+
+/// A reentrant mutex that uses a [`ReLock`](crate::remutex::lock::ReLock) over the
+/// [default mutex lock](crate::mutex::default)
+#[cfg(all(feature = "extra", feature = "std"))]
+pub type ReentrantMutex<T> =
+    remutex::ReentrantMutex<remutex::lock::ReLock<mutex::default::DefaultLock>, T>;
+
+/// A once cell that uses the [default once lock](crate::once::default)
+pub type OnceCell<T> = once::default::OnceCell<T>;
+
+/// A lazy value that uses the [default once lock](crate::once::default)
+pub type Lazy<T, F = fn() -> T> = once::default::Lazy<T, F>;
 use marker::*;
 
 macro_rules! trait_impls {
@@ -101,6 +325,11 @@ macro_rules! trait_impls {
         unsafe impl<$L: ?Sized + RawLockInfo> RawLockInfo for $type {
             type ExclusiveGuardTraits = L::ExclusiveGuardTraits;
             type ShareGuardTraits = L::ShareGuardTraits;
+
+            #[inline]
+            fn name(&self) -> Option<&'static str> {
+                L::name(self)
+            }
         }
 
         impl<$L: ?Sized + RawTimedLock> RawTimedLock for $type {
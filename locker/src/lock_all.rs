@@ -0,0 +1,286 @@
+//! Acquire several locks at once, without deadlocking
+//!
+//! [`lock_all`] takes a tuple of lock requests, and blocks until it has acquired all of them.
+//! The locks are always acquired in order of their address, so threads that acquire the
+//! same locks, listed in any order, can't deadlock on each other. The guards are returned
+//! in the same order as the requests.
+//!
+//! A `&Mutex` requests exclusive access to a mutex, and [`Read`] and [`Write`] request
+//! shared or exclusive access to an rwlock. Up to 8 locks can be acquired at once.
+//!
+//! ```
+//! use locker::lock_all::{lock_all, Read, Write};
+//! use locker::rwlock::default::DefaultLock;
+//!
+//! let balance = DefaultLock::mutex(10);
+//! let savings = DefaultLock::rwlock(0);
+//! let rate = DefaultLock::rwlock(2);
+//!
+//! let (mut balance, mut savings, rate) = lock_all((&balance, Write(&savings), Read(&rate)));
+//! *savings += *balance * *rate;
+//! *balance = 0;
+//! ```
+
+use crate::exclusive_lock::ExclusiveGuard;
+use crate::mutex::{Mutex, RawMutex};
+use crate::rwlock::{RawRwLock, RwLock};
+use crate::share_lock::ShareGuard;
+
+/// A request for shared access to an rwlock
+pub struct Read<'a, L, T: ?Sized>(pub &'a RwLock<L, T>);
+
+/// A request for exclusive access to an rwlock
+pub struct Write<'a, L, T: ?Sized>(pub &'a RwLock<L, T>);
+
+impl<L, T: ?Sized> Clone for Read<'_, L, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<L, T: ?Sized> Copy for Read<'_, L, T> {}
+
+impl<L, T: ?Sized> Clone for Write<'_, L, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<L, T: ?Sized> Copy for Write<'_, L, T> {}
+
+/// A single lock that can be acquired by [`lock_all`]
+pub trait LockRequest {
+    /// The guard that is returned once the lock is acquired
+    type Guard;
+
+    /// The address of the lock, which decides the order that locks are acquired in
+    fn addr(&self) -> usize;
+
+    /// Block until the lock is acquired
+    fn lock(&self) -> Self::Guard;
+
+    /// Attempt to acquire the lock without blocking
+    fn try_lock(&self) -> Option<Self::Guard>;
+}
+
+/// A tuple of [`LockRequest`]s, which can be acquired all at once
+pub trait LockAll {
+    /// A tuple of the guards of each request
+    type Guards;
+
+    /// Block until all of the locks are acquired, see [`lock_all`]
+    fn lock_all(self) -> Self::Guards;
+
+    /// Attempt to acquire all of the locks without blocking, see [`try_lock_all`]
+    fn try_lock_all(self) -> Option<Self::Guards>;
+}
+
+/// Block until all of the locks are acquired
+///
+/// The locks are acquired in order of their address, which prevents deadlocks between
+/// threads that use `lock_all` for the same locks. If this panics, then every lock that
+/// was already acquired is released.
+///
+/// # Panic
+///
+/// Panics if the same lock is requested more than once
+#[inline]
+#[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+pub fn lock_all<R: LockAll>(requests: R) -> R::Guards {
+    requests.lock_all()
+}
+
+/// Attempt to acquire all of the locks, without blocking
+///
+/// This is all or nothing: if any lock can't be acquired, then the locks that were
+/// already acquired are released and None is returned.
+#[inline]
+#[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+pub fn try_lock_all<R: LockAll>(requests: R) -> Option<R::Guards> {
+    requests.try_lock_all()
+}
+
+#[inline]
+fn addr<T: ?Sized>(lock: &T) -> usize {
+    lock as *const T as *const () as usize
+}
+
+impl<'a, L: RawMutex, T: ?Sized> LockRequest for &'a Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    type Guard = ExclusiveGuard<'a, L, T>;
+
+    #[inline]
+    fn addr(&self) -> usize {
+        addr(*self)
+    }
+
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    fn lock(&self) -> Self::Guard {
+        Mutex::lock(*self)
+    }
+
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    fn try_lock(&self) -> Option<Self::Guard> {
+        Mutex::try_lock(*self)
+    }
+}
+
+impl<'a, L: RawRwLock, T: ?Sized> LockRequest for Read<'a, L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    type Guard = ShareGuard<'a, L, T>;
+
+    #[inline]
+    fn addr(&self) -> usize {
+        addr(self.0)
+    }
+
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    fn lock(&self) -> Self::Guard {
+        self.0.read()
+    }
+
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    fn try_lock(&self) -> Option<Self::Guard> {
+        self.0.try_read()
+    }
+}
+
+impl<'a, L: RawRwLock, T: ?Sized> LockRequest for Write<'a, L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    type Guard = ExclusiveGuard<'a, L, T>;
+
+    #[inline]
+    fn addr(&self) -> usize {
+        addr(self.0)
+    }
+
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    fn lock(&self) -> Self::Guard {
+        self.0.write()
+    }
+
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    fn try_lock(&self) -> Option<Self::Guard> {
+        self.0.try_write()
+    }
+}
+
+/// Sort the indices of the requests by the address of their lock
+#[inline]
+fn lock_order<const N: usize>(addrs: [usize; N]) -> [usize; N] {
+    let mut order = [0; N];
+    for (i, index) in order.iter_mut().enumerate() {
+        *index = i;
+    }
+
+    order.sort_unstable_by_key(|&i| addrs[i]);
+
+    assert!(
+        order.windows(2).all(|w| addrs[w[0]] != addrs[w[1]]),
+        "tried to acquire the same lock more than once"
+    );
+
+    order
+}
+
+macro_rules! lock_all_tuple {
+    ($($R:ident $guard:ident $i:tt),*) => {
+        impl<$($R: LockRequest),*> LockAll for ($($R,)*) {
+            type Guards = ($($R::Guard,)*);
+
+            #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+            fn lock_all(self) -> Self::Guards {
+                $(let mut $guard = None;)*
+
+                for i in lock_order([$(self.$i.addr()),*]) {
+                    match i {
+                        $($i => $guard = Some(self.$i.lock()),)*
+                        _ => unreachable!(),
+                    }
+                }
+
+                ($($guard.unwrap(),)*)
+            }
+
+            #[inline]
+            #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+            fn try_lock_all(self) -> Option<Self::Guards> {
+                // if a lock fails, the guards that were already acquired are dropped
+                Some(($(self.$i.try_lock()?,)*))
+            }
+        }
+    };
+}
+
+lock_all_tuple!(A a 0);
+lock_all_tuple!(A a 0, B b 1);
+lock_all_tuple!(A a 0, B b 1, C c 2);
+lock_all_tuple!(A a 0, B b 1, C c 2, D d 3);
+lock_all_tuple!(A a 0, B b 1, C c 2, D d 3, E e 4);
+lock_all_tuple!(A a 0, B b 1, C c 2, D d 3, E e 4, F f 5);
+lock_all_tuple!(A a 0, B b 1, C c 2, D d 3, E e 4, F f 5, G g 6);
+lock_all_tuple!(A a 0, B b 1, C c 2, D d 3, E e 4, F f 5, G g 6, H h 7);
+
+impl<L: RawMutex, T: ?Sized> Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// Lock this mutex and `other`, in an order that can't deadlock with other calls
+    /// to `lock_2` or [`lock_all`] on the same mutexes
+    ///
+    /// # Panic
+    ///
+    /// Panics if `other` is this mutex
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub fn lock_2<'a, M: RawMutex, U: ?Sized>(
+        &'a self,
+        other: &'a Mutex<M, U>,
+    ) -> (ExclusiveGuard<'a, L, T>, ExclusiveGuard<'a, M, U>)
+    where
+        M::ExclusiveGuardTraits: crate::Inhabitted,
+    {
+        lock_all((self, other))
+    }
+}
+
+impl<L: RawRwLock, T: ?Sized> RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Acquire exclusive access to this rwlock and `other`, in an order that can't
+    /// deadlock with other calls to `write_2` or [`lock_all`] on the same rwlocks
+    ///
+    /// # Panic
+    ///
+    /// Panics if `other` is this rwlock
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub fn write_2<'a, M: RawRwLock, U: ?Sized>(
+        &'a self,
+        other: &'a RwLock<M, U>,
+    ) -> (ExclusiveGuard<'a, L, T>, ExclusiveGuard<'a, M, U>)
+    where
+        M::ExclusiveGuardTraits: crate::Inhabitted,
+        M::ShareGuardTraits: crate::Inhabitted,
+    {
+        lock_all((Write(self), Write(other)))
+    }
+}
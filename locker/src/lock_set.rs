@@ -0,0 +1,490 @@
+//! A set of locks that can be waited on all at once
+//!
+//! A thread can only be parked on one address at a time, so it can't block on many locks
+//! directly. A [`LockSet`] instead keeps an [`AtomicBitSet`] of which of its locks may be
+//! available, inside of a [`Waiter`]. Every guard handed out by the set marks its lock as
+//! available when it's dropped and notifies the waiter, so a thread that is waiting for
+//! any of the locks only has to park on the waiter.
+//!
+//! ```
+//! use locker::lock_set::LockSet;
+//! use locker::mutex::adaptive::AdaptiveLock;
+//!
+//! let pool = LockSet::new(vec![AdaptiveLock::mutex(0), AdaptiveLock::mutex(1)]);
+//!
+//! let first = pool.lock_any();
+//! let second = pool.lock_any();
+//! assert_eq!((first.index(), second.index()), (0, 1));
+//! assert!(pool.try_lock_any().is_none());
+//!
+//! drop(first);
+//! assert_eq!(*pool.lock_any(), 0);
+//! ```
+
+use crate::exclusive_lock::ExclusiveGuard;
+use crate::mutex::{Mutex, RawMutex};
+use crate::rwlock::{RawRwLock, RwLock};
+use crate::share_lock::ShareGuard;
+use crate::waiter::Waiter;
+
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const BITS: usize = std::mem::size_of::<usize>() * 8;
+
+/// A fixed size set of bits, which can be updated atomically
+#[derive(Debug)]
+pub struct AtomicBitSet {
+    words: Box<[AtomicUsize]>,
+}
+
+impl AtomicBitSet {
+    /// Create a set that can hold `len` bits, and set all of them
+    pub fn full(len: usize) -> Self {
+        let mut words: Vec<_> = (0..len.div_ceil(BITS))
+            .map(|_| AtomicUsize::new(!0))
+            .collect();
+
+        if let Some(last) = words.last_mut() {
+            if !len.is_multiple_of(BITS) {
+                *last.get_mut() = (1 << (len % BITS)) - 1;
+            }
+        }
+
+        Self {
+            words: words.into_boxed_slice(),
+        }
+    }
+
+    #[inline]
+    fn word(&self, index: usize) -> (&AtomicUsize, usize) {
+        (&self.words[index / BITS], 1 << (index % BITS))
+    }
+
+    /// Check if the bit at `index` is set
+    ///
+    /// # Panic
+    ///
+    /// Panics if `index` is out of bounds
+    #[inline]
+    pub fn get(&self, index: usize) -> bool {
+        let (word, bit) = self.word(index);
+        word.load(Ordering::Acquire) & bit != 0
+    }
+
+    /// Set the bit at `index`, and return if it was already set
+    ///
+    /// # Panic
+    ///
+    /// Panics if `index` is out of bounds
+    #[inline]
+    pub fn set(&self, index: usize) -> bool {
+        let (word, bit) = self.word(index);
+        word.fetch_or(bit, Ordering::AcqRel) & bit != 0
+    }
+
+    /// Clear the bit at `index`, and return if it was set
+    ///
+    /// # Panic
+    ///
+    /// Panics if `index` is out of bounds
+    #[inline]
+    pub fn clear(&self, index: usize) -> bool {
+        let (word, bit) = self.word(index);
+        word.fetch_and(!bit, Ordering::AcqRel) & bit != 0
+    }
+
+    /// Check if none of the bits are set
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.words
+            .iter()
+            .all(|word| word.load(Ordering::Acquire) == 0)
+    }
+}
+
+/// A set of locks, where a thread can wait until any one of them is available
+///
+/// The locks can only be acquired through the set, otherwise the set won't be
+/// notified when they are released.
+///
+/// The set works with both [`Mutex`]es and [`RwLock`]s, and does not need the locks
+/// to support timeouts, because all of the waiting is done by the set.
+///
+/// A thread that fails to get exclusive access to a read locked rwlock marks it as
+/// unavailable until a guard for it is dropped, so readers may wait behind writers
+/// for a while, like with a writer preferring rwlock.
+pub struct LockSet<M> {
+    // a bit is set if the lock at that index may be available,
+    // and is cleared right before a thread tries to acquire it.
+    // So if a lock is available, either its bit is set, or some
+    // thread is about to try to acquire it
+    available: Waiter<AtomicBitSet>,
+    locks: Box<[M]>,
+}
+
+/// An RAII guard returned by a [`LockSet`]
+///
+/// When this is dropped, the lock is released and the set is notified
+pub struct SetGuard<'a, M, G> {
+    set: &'a LockSet<M>,
+    index: usize,
+    guard: ManuallyDrop<G>,
+}
+
+impl<M> LockSet<M> {
+    /// Create a new set of locks
+    pub fn new(locks: Vec<M>) -> Self {
+        let available = AtomicBitSet::full(locks.len());
+
+        Self {
+            available: unsafe { Waiter::with_value(available) },
+            locks: locks.into_boxed_slice(),
+        }
+    }
+
+    /// The number of locks in the set
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.locks.len()
+    }
+
+    /// Check if there are no locks in the set
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.locks.is_empty()
+    }
+
+    /// Get mutable access to the locks
+    ///
+    /// This is safe because the mutable reference guarantees that no guards exist
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut [M] {
+        &mut self.locks
+    }
+
+    /// Get the locks out of the set
+    #[inline]
+    pub fn into_inner(self) -> Vec<M> {
+        self.locks.into_vec()
+    }
+
+    fn try_acquire<'a, G>(
+        &'a self,
+        shared: bool,
+        try_lock: &impl Fn(&'a M) -> Option<G>,
+    ) -> Option<SetGuard<'a, M, G>> {
+        let available = &self.available.inner;
+
+        self.locks.iter().enumerate().find_map(|(index, lock)| {
+            // if the bit is already clear, then the lock is held, or another thread is trying
+            // to lock it. If that thread fails to lock it, then it is held, and the holder
+            // will set the bit again when it releases the lock.
+            if !available.clear(index) {
+                return None;
+            }
+
+            let guard = try_lock(lock)?;
+
+            // other threads can still acquire shared access, including readers that parked
+            // while the bit was cleared, so they have to be woken up
+            if shared {
+                available.set(index);
+                self.available.notify_all();
+            }
+
+            Some(SetGuard {
+                set: self,
+                index,
+                guard: ManuallyDrop::new(guard),
+            })
+        })
+    }
+
+    fn acquire<'a, G>(
+        &'a self,
+        shared: bool,
+        timeout: Option<Instant>,
+        try_lock: impl Fn(&'a M) -> Option<G>,
+    ) -> Option<SetGuard<'a, M, G>> {
+        loop {
+            if let Some(guard) = self.try_acquire(shared, &try_lock) {
+                return Some(guard);
+            }
+
+            if self.is_empty() {
+                return None;
+            }
+
+            // park only if every bit is still clear, this is checked under the parking queue
+            // lock, so a guard that is released after the check will wake us up
+            if !self.available.park_if(timeout, AtomicBitSet::is_empty) {
+                return None;
+            }
+        }
+    }
+
+    fn release(&self, index: usize) {
+        self.available.inner.set(index);
+        self.available.notify_one();
+    }
+}
+
+impl<L: RawMutex, T> LockSet<Mutex<L, T>>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// Attempts to lock any one of the mutexes, without blocking
+    ///
+    /// The mutexes are tried in order, and the guard of the first one that could be locked
+    /// is returned. If all of them are locked, then None is returned.
+    #[inline]
+    pub fn try_lock_any(&self) -> Option<SetGuard<'_, Mutex<L, T>, ExclusiveGuard<'_, L, T>>> {
+        self.try_acquire(false, &Mutex::try_lock)
+    }
+
+    /// Lock any one of the mutexes, blocking until one of them is available
+    ///
+    /// # Panic
+    ///
+    /// Panics if the set is empty
+    #[inline]
+    pub fn lock_any(&self) -> SetGuard<'_, Mutex<L, T>, ExclusiveGuard<'_, L, T>> {
+        self.acquire(false, None, Mutex::try_lock)
+            .expect("tried to lock an empty `LockSet`")
+    }
+
+    /// Attempts to lock any one of the mutexes until a timeout is reached
+    ///
+    /// If none of the mutexes could be locked before the timeout, then None is returned
+    #[inline]
+    pub fn try_lock_any_until(
+        &self,
+        instant: Instant,
+    ) -> Option<SetGuard<'_, Mutex<L, T>, ExclusiveGuard<'_, L, T>>> {
+        self.acquire(false, Some(instant), Mutex::try_lock)
+    }
+
+    /// Attempts to lock any one of the mutexes until a timeout is reached
+    ///
+    /// If none of the mutexes could be locked before the timeout, then None is returned
+    #[inline]
+    pub fn try_lock_any_for(
+        &self,
+        duration: Duration,
+    ) -> Option<SetGuard<'_, Mutex<L, T>, ExclusiveGuard<'_, L, T>>> {
+        self.acquire(false, Instant::now().checked_add(duration), Mutex::try_lock)
+    }
+}
+
+impl<L: RawRwLock, T> LockSet<RwLock<L, T>>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Attempts to acquire shared access to any one of the rwlocks, without blocking
+    ///
+    /// The rwlocks are tried in order, and the guard of the first one that could be locked
+    /// is returned. If all of them are write locked, then None is returned.
+    #[inline]
+    pub fn try_read_any(&self) -> Option<SetGuard<'_, RwLock<L, T>, ShareGuard<'_, L, T>>> {
+        self.try_acquire(true, &RwLock::try_read)
+    }
+
+    /// Acquire shared access to any one of the rwlocks, blocking until one of them is available
+    ///
+    /// # Panic
+    ///
+    /// Panics if the set is empty
+    #[inline]
+    pub fn read_any(&self) -> SetGuard<'_, RwLock<L, T>, ShareGuard<'_, L, T>> {
+        self.acquire(true, None, RwLock::try_read)
+            .expect("tried to lock an empty `LockSet`")
+    }
+
+    /// Attempts to acquire shared access to any one of the rwlocks until a timeout is reached
+    ///
+    /// If none of the rwlocks could be locked before the timeout, then None is returned
+    #[inline]
+    pub fn try_read_any_until(
+        &self,
+        instant: Instant,
+    ) -> Option<SetGuard<'_, RwLock<L, T>, ShareGuard<'_, L, T>>> {
+        self.acquire(true, Some(instant), RwLock::try_read)
+    }
+
+    /// Attempts to acquire shared access to any one of the rwlocks until a timeout is reached
+    ///
+    /// If none of the rwlocks could be locked before the timeout, then None is returned
+    #[inline]
+    pub fn try_read_any_for(
+        &self,
+        duration: Duration,
+    ) -> Option<SetGuard<'_, RwLock<L, T>, ShareGuard<'_, L, T>>> {
+        self.acquire(true, Instant::now().checked_add(duration), RwLock::try_read)
+    }
+
+    /// Attempts to acquire exclusive access to any one of the rwlocks, without blocking
+    ///
+    /// The rwlocks are tried in order, and the guard of the first one that could be locked
+    /// is returned. If all of them are locked, then None is returned.
+    #[inline]
+    pub fn try_write_any(&self) -> Option<SetGuard<'_, RwLock<L, T>, ExclusiveGuard<'_, L, T>>> {
+        self.try_acquire(false, &RwLock::try_write)
+    }
+
+    /// Acquire exclusive access to any one of the rwlocks, blocking until one of them is available
+    ///
+    /// # Panic
+    ///
+    /// Panics if the set is empty
+    #[inline]
+    pub fn write_any(&self) -> SetGuard<'_, RwLock<L, T>, ExclusiveGuard<'_, L, T>> {
+        self.acquire(false, None, RwLock::try_write)
+            .expect("tried to lock an empty `LockSet`")
+    }
+
+    /// Attempts to acquire exclusive access to any one of the rwlocks until a timeout is reached
+    ///
+    /// If none of the rwlocks could be locked before the timeout, then None is returned
+    #[inline]
+    pub fn try_write_any_until(
+        &self,
+        instant: Instant,
+    ) -> Option<SetGuard<'_, RwLock<L, T>, ExclusiveGuard<'_, L, T>>> {
+        self.acquire(false, Some(instant), RwLock::try_write)
+    }
+
+    /// Attempts to acquire exclusive access to any one of the rwlocks until a timeout is reached
+    ///
+    /// If none of the rwlocks could be locked before the timeout, then None is returned
+    #[inline]
+    pub fn try_write_any_for(
+        &self,
+        duration: Duration,
+    ) -> Option<SetGuard<'_, RwLock<L, T>, ExclusiveGuard<'_, L, T>>> {
+        self.acquire(
+            false,
+            Instant::now().checked_add(duration),
+            RwLock::try_write,
+        )
+    }
+}
+
+impl<M, G> SetGuard<'_, M, G> {
+    /// The index of the lock that this guard is for
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<M, G> Drop for SetGuard<'_, M, G> {
+    fn drop(&mut self) {
+        unsafe { ManuallyDrop::drop(&mut self.guard) }
+        self.set.release(self.index);
+    }
+}
+
+impl<M, G: Deref> Deref for SetGuard<'_, M, G> {
+    type Target = G::Target;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<M, G: DerefMut> DerefMut for SetGuard<'_, M, G> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+#[test]
+fn lock_set() {
+    use crate::mutex::adaptive::AdaptiveLock;
+
+    let pool = LockSet::new((0..3).map(AdaptiveLock::mutex).collect());
+    let mut guards: Vec<_> = (0..3).map(|_| pool.lock_any()).collect();
+    assert!(pool.try_lock_any().is_none());
+    assert!(pool.try_lock_any_for(Duration::from_millis(10)).is_none());
+
+    crossbeam_utils::thread::scope(|s| {
+        let last = guards.remove(2);
+        s.spawn(move |_| {
+            std::thread::sleep(Duration::from_millis(20));
+            drop(last);
+        });
+
+        let guard = pool.lock_any();
+        assert_eq!((guard.index(), *guard), (2, 2));
+    })
+    .unwrap();
+
+    let rwlocks = LockSet::new(vec![
+        crate::rwlock::adaptive::AdaptiveLock::rwlock(0),
+        crate::rwlock::adaptive::AdaptiveLock::rwlock(1),
+    ]);
+    let write = rwlocks.write_any();
+    let read = rwlocks.read_any();
+    assert_eq!((write.index(), read.index()), (0, 1));
+    assert_eq!(rwlocks.try_read_any().map(|guard| guard.index()), Some(1));
+    assert!(rwlocks
+        .try_write_any_for(Duration::from_millis(1))
+        .is_none());
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(move |_| {
+            std::thread::sleep(Duration::from_millis(20));
+            drop(read);
+        });
+
+        assert_eq!(rwlocks.write_any().index(), 1);
+    })
+    .unwrap();
+
+    drop(write);
+    assert!(
+        LockSet::<crate::mutex::adaptive::Mutex<()>>::new(Vec::new())
+            .try_lock_any_for(Duration::from_secs(10))
+            .is_none()
+    );
+}
+
+#[test]
+fn readers_wake_each_other() {
+    use std::sync::atomic::AtomicUsize;
+
+    let rwlocks = LockSet::new(vec![crate::rwlock::adaptive::AdaptiveLock::rwlock(0)]);
+    let write = rwlocks.write_any();
+    let readers = AtomicUsize::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|_| {
+                let read = rwlocks.try_read_any_for(Duration::from_secs(5));
+                assert!(
+                    read.is_some(),
+                    "a reader wasn't woken while the rwlock was readable"
+                );
+                readers.fetch_add(1, Ordering::SeqCst);
+
+                // both readers hold the rwlock at the same time
+                let deadline = Instant::now() + Duration::from_secs(5);
+                while readers.load(Ordering::SeqCst) != 2 && Instant::now() < deadline {
+                    std::thread::yield_now();
+                }
+            });
+        }
+
+        std::thread::sleep(Duration::from_millis(20));
+        drop(write);
+    })
+    .unwrap();
+
+    assert_eq!(readers.into_inner(), 2);
+}
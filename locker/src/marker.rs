@@ -9,6 +9,22 @@ pub trait Marker: Copy {}
 /// A [`Marker`](crate::Marker)
 ///
 /// These should be zero-sized
+///
+/// Guards can only be created if their lock's guard traits are inhabited, so
+/// creating a *shr lock* guard of a mutex, whose `ShareGuardTraits` are
+/// `Infallible`, doesn't compile
+///
+/// ```compile_fail,E0277
+/// use locker::share_lock::RawShareGuard;
+///
+/// let lock = locker::mutex::adaptive::AdaptiveLock::new();
+/// let _guard = RawShareGuard::new(&lock);
+/// ```
+#[diagnostic::on_unimplemented(
+    message = "this kind of guard can't be created for this lock",
+    label = "the lock doesn't support this kind of guard",
+    note = "the lock's `RawLockInfo` guard traits are `{Self}`, which is uninhabited"
+)]
 pub trait Inhabitted: Marker {
     // # Safety note
     //
@@ -0,0 +1,452 @@
+//! a deterministic mock lock, for testing code that is generic over raw locks
+//!
+//! [`MockLock`] implements every raw lock trait, but it never blocks or spins. Instead,
+//! each acquisition that could be contended takes the next [`Outcome`] from a script,
+//! so contention and timeouts can be simulated on a single thread. Every call is
+//! recorded, so tests can check how the code under test drove the lock.
+//!
+//! A scripted [`Outcome::Succeed`] can't force an acquisition that conflicts with the
+//! locks that are currently held, so the mock lock never hands out overlapping guards.
+//!
+//! If the script is empty, the lock behaves like a real lock held only by the current
+//! thread: acquisitions succeed if they are compatible with the locks that are currently held.
+//! Blocking calls panic instead of deadlocking, because no other thread could release the lock.
+//!
+//! ```
+//! use locker::mock::{Call, MockLock, Outcome};
+//! use std::time::Duration;
+//!
+//! let mutex = MockLock::mutex(0);
+//! mutex.raw().inner().script([Outcome::Fail, Outcome::Timeout]);
+//!
+//! assert!(mutex.try_lock().is_none());
+//! assert!(mutex.try_lock_for(Duration::from_millis(10)).is_none());
+//! *mutex.lock() += 1;
+//!
+//! assert_eq!(
+//!     mutex.raw().inner().take_calls(),
+//!     [
+//!         Call::ExcTryLock,
+//!         Call::ExcTryLockFor(Duration::from_millis(10)),
+//!         Call::ExcLock,
+//!         Call::ExcUnlock,
+//!     ]
+//! );
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::{Mutex as StdMutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+/// a raw mutex backed by a mock lock
+pub type RawMutex = crate::mutex::raw::Mutex<MockLock>;
+/// a mutex backed by a mock lock
+pub type Mutex<T> = crate::mutex::Mutex<MockLock, T>;
+/// a raw rwlock backed by a mock lock
+pub type RawRwLock = crate::rwlock::raw::RwLock<MockLock>;
+/// a rwlock backed by a mock lock
+pub type RwLock<T> = crate::rwlock::RwLock<MockLock, T>;
+
+/// The scripted result of an acquisition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// The lock is acquired
+    ///
+    /// This panics if the acquisition conflicts with the locks that are currently held,
+    /// because that would hand out a guard that overlaps with another one.
+    Succeed,
+    /// The lock is contended, so a `try_*` call fails
+    ///
+    /// Timed calls fail too, as if the lock stayed contended until the timeout.
+    Fail,
+    /// A timed call times out
+    ///
+    /// Only timed calls may take this outcome, other calls panic if they do.
+    Timeout,
+}
+
+/// A call made on a [`MockLock`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum Call {
+    ExcLock,
+    ExcTryLock,
+    ExcTryLockUntil(Instant),
+    ExcTryLockFor(Duration),
+    ExcUnlock,
+    ExcUnlockFair,
+    ExcBump,
+    ExcBumpFair,
+    ExcBumpFor(Duration),
+    ExcSplit,
+    Downgrade,
+    TryDowngrade,
+    ShrLock,
+    ShrTryLock,
+    ShrTryLockUntil(Instant),
+    ShrTryLockFor(Duration),
+    ShrSplit,
+    ShrTrySplit,
+    ShrUnlock,
+    ShrUnlockFair,
+    ShrBump,
+    ShrBumpFair,
+    ShrBumpFor(Duration),
+    Upgrade,
+    TryUpgrade,
+    TryUpgradeUntil(Instant),
+    TryUpgradeFor(Duration),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Exc,
+    Shr,
+    Upgrade,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Wait {
+    Try,
+    Timed,
+    Block,
+}
+
+struct State {
+    script: VecDeque<Outcome>,
+    calls: Vec<Call>,
+    exc: usize,
+    shr: usize,
+}
+
+/// A scriptable lock which never blocks, see the [module docs](self) for details
+pub struct MockLock {
+    state: StdMutex<State>,
+}
+
+crate::impl_init! {
+    MockLock = Self::new()
+}
+
+impl MockLock {
+    /// create a new mock lock, with an empty script
+    pub const fn new() -> Self {
+        Self {
+            state: StdMutex::new(State {
+                script: VecDeque::new(),
+                calls: Vec::new(),
+                exc: 0,
+                shr: 0,
+            }),
+        }
+    }
+
+    /// create a new mock lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new mock lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// create a new mock lock based raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a new mock lock based rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // failed expectations panic while the state is locked,
+        // that shouldn't hide the state from the rest of the test
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Append outcomes to the script
+    pub fn script(&self, outcomes: impl IntoIterator<Item = Outcome>) {
+        self.state().script.extend(outcomes)
+    }
+
+    /// The number of scripted outcomes that haven't been used yet
+    pub fn remaining(&self) -> usize {
+        self.state().script.len()
+    }
+
+    /// The calls made on this lock so far
+    pub fn calls(&self) -> Vec<Call> {
+        self.state().calls.clone()
+    }
+
+    /// The calls made on this lock so far, clearing the call log
+    pub fn take_calls(&self) -> Vec<Call> {
+        core::mem::take(&mut self.state().calls)
+    }
+
+    /// Check if a *exc lock* is currently held
+    pub fn is_exc_locked(&self) -> bool {
+        self.state().exc != 0
+    }
+
+    /// The number of *shr lock*s that are currently held
+    pub fn shr_count(&self) -> usize {
+        self.state().shr
+    }
+
+    fn record(&self, call: Call) -> MutexGuard<'_, State> {
+        let mut state = self.state();
+        state.calls.push(call);
+        state
+    }
+
+    fn acquire(&self, call: Call, kind: Kind, wait: Wait) -> bool {
+        let mut state = self.record(call);
+        Self::acquire_in(&mut state, call, kind, wait)
+    }
+
+    fn acquire_in(state: &mut State, call: Call, kind: Kind, wait: Wait) -> bool {
+        if kind == Kind::Upgrade {
+            Self::assert_held_in(state, call, Kind::Shr);
+        }
+
+        let available = match kind {
+            Kind::Exc => state.exc == 0 && state.shr == 0,
+            Kind::Shr => state.exc == 0,
+            Kind::Upgrade => state.exc == 0 && state.shr == 1,
+        };
+
+        let acquired = match state.script.pop_front() {
+            Some(Outcome::Succeed) if !available => panic!(
+                "`{:?}` can't succeed while conflicting locks are held, but the script says it does",
+                call
+            ),
+            Some(Outcome::Succeed) => true,
+            Some(Outcome::Timeout) if wait != Wait::Timed => {
+                panic!("`{:?}` can't time out, but the script says it does", call)
+            }
+            Some(_) => false,
+            None => available,
+        };
+
+        if !acquired && wait == Wait::Block {
+            panic!("`{:?}` would block forever on a mock lock", call)
+        }
+
+        if acquired {
+            match kind {
+                Kind::Exc => state.exc += 1,
+                Kind::Shr => state.shr += 1,
+                Kind::Upgrade => {
+                    state.shr -= 1;
+                    state.exc += 1;
+                }
+            }
+        }
+
+        acquired
+    }
+
+    fn release(&self, call: Call, kind: Kind) {
+        Self::release_in(&mut self.record(call), call, kind)
+    }
+
+    fn release_in(state: &mut State, call: Call, kind: Kind) {
+        Self::assert_held_in(state, call, kind);
+
+        match kind {
+            Kind::Shr => state.shr -= 1,
+            _ => state.exc -= 1,
+        }
+    }
+
+    fn assert_held(&self, call: Call, kind: Kind) {
+        Self::assert_held_in(&self.record(call), call, kind)
+    }
+
+    fn assert_held_in(state: &State, call: Call, kind: Kind) {
+        let held = match kind {
+            Kind::Shr => state.shr,
+            _ => state.exc,
+        };
+
+        assert!(
+            held != 0,
+            "`{:?}` was called without holding the lock",
+            call
+        );
+    }
+
+    // `bump_for` releases the lock, and then tries to re-acquire it, which takes an outcome.
+    // If that fails, it blocks until the lock is re-acquired, which takes another outcome
+    fn bump_for(&self, call: Call, kind: Kind) -> bool {
+        let mut state = self.record(call);
+        Self::release_in(&mut state, call, kind);
+
+        if Self::acquire_in(&mut state, call, kind, Wait::Timed) {
+            true
+        } else {
+            Self::acquire_in(&mut state, call, kind, Wait::Block);
+            false
+        }
+    }
+}
+
+unsafe impl crate::mutex::RawMutex for MockLock {}
+unsafe impl crate::rwlock::RawRwLock for MockLock {}
+unsafe impl crate::RawLockInfo for MockLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = ();
+}
+
+impl crate::RawTimedLock for MockLock {
+    type Instant = Instant;
+    type Duration = Duration;
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLock for MockLock {
+    fn exc_lock(&self) {
+        self.acquire(Call::ExcLock, Kind::Exc, Wait::Block);
+    }
+
+    fn exc_try_lock(&self) -> bool {
+        self.acquire(Call::ExcTryLock, Kind::Exc, Wait::Try)
+    }
+
+    unsafe fn exc_unlock(&self) {
+        self.release(Call::ExcUnlock, Kind::Exc)
+    }
+
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        self.assert_held(Call::ExcBump, Kind::Exc);
+        // nothing is waiting on a mock lock
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for MockLock {
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.acquire(Call::ExcTryLockUntil(instant), Kind::Exc, Wait::Timed)
+    }
+
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.acquire(Call::ExcTryLockFor(duration), Kind::Exc, Wait::Timed)
+    }
+
+    unsafe fn exc_bump_for(&self, duration: Self::Duration) -> bool {
+        self.bump_for(Call::ExcBumpFor(duration), Kind::Exc)
+    }
+}
+
+unsafe impl crate::exclusive_lock::SplittableExclusiveLock for MockLock {
+    unsafe fn exc_split(&self) {
+        self.assert_held(Call::ExcSplit, Kind::Exc);
+        self.state().exc += 1;
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for MockLock {
+    unsafe fn exc_unlock_fair(&self) {
+        self.release(Call::ExcUnlockFair, Kind::Exc)
+    }
+
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        self.assert_held(Call::ExcBumpFair, Kind::Exc);
+        // nothing is waiting on a mock lock
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockDowngrade for MockLock {
+    unsafe fn downgrade(&self) {
+        self.release(Call::Downgrade, Kind::Exc);
+        self.state().shr += 1;
+    }
+
+    unsafe fn try_downgrade(&self) -> bool {
+        self.release(Call::TryDowngrade, Kind::Exc);
+        self.state().shr += 1;
+        true
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLock for MockLock {
+    fn shr_lock(&self) {
+        self.acquire(Call::ShrLock, Kind::Shr, Wait::Block);
+    }
+
+    fn shr_try_lock(&self) -> bool {
+        self.acquire(Call::ShrTryLock, Kind::Shr, Wait::Try)
+    }
+
+    unsafe fn shr_split(&self) {
+        self.assert_held(Call::ShrSplit, Kind::Shr);
+        self.state().shr += 1;
+    }
+
+    unsafe fn shr_try_split(&self) -> bool {
+        self.assert_held(Call::ShrTrySplit, Kind::Shr);
+        self.state().shr += 1;
+        true
+    }
+
+    unsafe fn shr_unlock(&self) {
+        self.release(Call::ShrUnlock, Kind::Shr)
+    }
+
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        self.assert_held(Call::ShrBump, Kind::Shr);
+        // nothing is waiting on a mock lock
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockTimed for MockLock {
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.acquire(Call::ShrTryLockUntil(instant), Kind::Shr, Wait::Timed)
+    }
+
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.acquire(Call::ShrTryLockFor(duration), Kind::Shr, Wait::Timed)
+    }
+
+    unsafe fn shr_bump_for(&self, duration: Self::Duration) -> bool {
+        self.bump_for(Call::ShrBumpFor(duration), Kind::Shr)
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockFair for MockLock {
+    unsafe fn shr_unlock_fair(&self) {
+        self.release(Call::ShrUnlockFair, Kind::Shr)
+    }
+
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        self.assert_held(Call::ShrBumpFair, Kind::Shr);
+        // nothing is waiting on a mock lock
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockUpgrade for MockLock {
+    unsafe fn upgrade(&self) {
+        self.acquire(Call::Upgrade, Kind::Upgrade, Wait::Block);
+    }
+
+    unsafe fn try_upgrade(&self) -> bool {
+        self.acquire(Call::TryUpgrade, Kind::Upgrade, Wait::Try)
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockUpgradeTimed for MockLock {
+    unsafe fn try_upgrade_until(&self, instant: Self::Instant) -> bool {
+        self.acquire(Call::TryUpgradeUntil(instant), Kind::Upgrade, Wait::Timed)
+    }
+
+    unsafe fn try_upgrade_for(&self, duration: Self::Duration) -> bool {
+        self.acquire(Call::TryUpgradeFor(duration), Kind::Upgrade, Wait::Timed)
+    }
+}
@@ -1,22 +1,30 @@
 //! A type-safe implementation of a `Mutex`
 
-use core::cell::UnsafeCell;
+use crate::cell::SharedCell;
 
 use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveLock, RawExclusiveLockTimed};
 
+pub mod local;
+pub mod spin;
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "extra")] {
         pub mod global;
-        pub mod spin;
         pub mod tagged_spin;
-        pub mod local;
         pub mod local_tagged;
         pub mod local_splittable;
         pub mod default;
         pub mod tagged_default;
+        pub mod tag_field;
         pub mod splittable_spin;
         pub mod splittable_default;
+        pub mod ticket;
+
+        #[cfg(feature = "critical-section")]
+        pub mod critical_section;
 
+        #[cfg(feature = "std")]
+        pub mod word_lock;
         #[cfg(feature = "parking_lot_core")]
         pub mod adaptive;
         #[cfg(feature = "parking_lot_core")]
@@ -28,6 +36,42 @@ cfg_if::cfg_if! {
 
 pub mod raw;
 
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+convert_lock!(
+    spin::SpinLock,
+    default::DefaultLock,
+    ticket::TicketLock,
+    word_lock::WordLock,
+    adaptive::AdaptiveLock,
+);
+#[cfg(all(feature = "extra", feature = "std", not(feature = "parking_lot_core")))]
+convert_lock!(
+    spin::SpinLock,
+    default::DefaultLock,
+    ticket::TicketLock,
+    word_lock::WordLock,
+);
+#[cfg(all(feature = "extra", not(feature = "std")))]
+convert_lock!(spin::SpinLock, default::DefaultLock, ticket::TicketLock);
+
+/// The strongest failure ordering that can be paired with the success ordering `order`
+#[inline]
+#[cfg(feature = "extra")]
+pub(crate) fn strongest_failure_ordering(
+    order: core::sync::atomic::Ordering,
+) -> core::sync::atomic::Ordering {
+    use core::sync::atomic::Ordering::*;
+
+    match order {
+        Release => Relaxed,
+        Relaxed => Relaxed,
+        SeqCst => SeqCst,
+        Acquire => Acquire,
+        AcqRel => Acquire,
+        _ => unreachable!(),
+    }
+}
+
 /// Types implementing this trait can be used by [`Mutex`] to form a safe and fully-functioning mutex type.
 ///
 /// # Safety
@@ -46,7 +90,7 @@ pub unsafe trait RawMutex: crate::RawLockInfo + RawExclusiveLock {}
 #[repr(C)]
 pub struct Mutex<L, T: ?Sized> {
     raw: raw::Mutex<L>,
-    value: UnsafeCell<T>,
+    value: SharedCell<T>,
 }
 
 impl<L: RawMutex + crate::Init, T: Default> Default for Mutex<L, T> {
@@ -65,7 +109,7 @@ impl<L, T> Mutex<L, T> {
     pub const fn from_raw_parts(raw: raw::Mutex<L>, value: T) -> Self {
         Self {
             raw,
-            value: UnsafeCell::new(value),
+            value: SharedCell::new(value),
         }
     }
 
@@ -80,6 +124,39 @@ impl<L, T> Mutex<L, T> {
     pub fn into_inner(self) -> T {
         self.value.into_inner()
     }
+
+    /// Convert this mutex into a mutex with another backend
+    ///
+    /// ```
+    /// use locker::mutex::{adaptive::AdaptiveLock, spin::SpinLock};
+    ///
+    /// let mutex = SpinLock::mutex(10);
+    /// let mutex = mutex.convert::<AdaptiveLock>();
+    /// assert_eq!(*mutex.lock(), 10);
+    /// ```
+    #[inline]
+    pub fn convert<M: RawMutex>(self) -> Mutex<M, T>
+    where
+        L: crate::ConvertLock<M>,
+    {
+        let (raw, value) = self.into_raw_parts();
+        Mutex::from_raw_parts(raw.convert(), value)
+    }
+
+    /// Convert this mutex into a mutex with any backend that implements [`Init`](crate::Init)
+    ///
+    /// ```
+    /// use locker::mutex::{local::LocalLock, spin::SpinLock};
+    ///
+    /// let mutex = SpinLock::mutex(10);
+    /// let mutex = mutex.convert_init::<LocalLock>();
+    /// assert_eq!(*mutex.lock(), 10);
+    /// ```
+    #[inline]
+    pub fn convert_init<M: RawMutex + crate::Init>(self) -> Mutex<M, T> {
+        let (raw, value) = self.into_raw_parts();
+        Mutex::from_raw_parts(raw.convert_init(), value)
+    }
 }
 
 impl<L, T: ?Sized> Mutex<L, T> {
@@ -91,7 +168,7 @@ impl<L, T: ?Sized> Mutex<L, T> {
 
     /// Get a raw pointer to the value
     pub fn as_mut_ptr(&self) -> *mut T {
-        self.value.get()
+        self.value.as_ptr()
     }
 
     cfg_if::cfg_if! {
@@ -112,7 +189,7 @@ impl<L, T: ?Sized> Mutex<L, T> {
             /// ---the mutable borrow statically guarantees no locks exist.
             #[inline]
             pub const fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                unsafe { &mut *self.value.as_ptr() }
             }
         } else {
             /// the underlying raw mutex
@@ -131,7 +208,7 @@ impl<L, T: ?Sized> Mutex<L, T> {
             /// ---the mutable borrow statically guarantees no locks exist.
             #[inline]
             pub fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                self.value.get_mut()
             }
         }
     }
@@ -165,7 +242,7 @@ where
         raw: crate::exclusive_lock::RawExclusiveGuard<'s, L>,
     ) -> ExclusiveGuard<'s, L, T> {
         assert!(core::ptr::eq(self.raw.inner(), raw.inner()));
-        unsafe { ExclusiveGuard::from_raw_parts(raw, self.value.get()) }
+        unsafe { ExclusiveGuard::from_raw_parts(raw, self.value.as_ptr()) }
     }
 
     /// Acquires a mutex, blocking the current thread until it is able to do so.
@@ -182,6 +259,7 @@ where
     /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
     /// single threaded mutex)
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn lock(&self) -> ExclusiveGuard<'_, L, T> {
         self.wrap(self.raw.lock())
     }
@@ -193,9 +271,32 @@ where
     ///
     /// This function does not block.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_lock(&self) -> Option<ExclusiveGuard<'_, L, T>> {
         Some(self.wrap(self.raw.try_lock()?))
     }
+
+    /// Acquires a guard through a mutable borrow of the mutex
+    ///
+    /// Since this call borrows the `Mutex` mutably, no other guards can exist, so this
+    /// doesn't go through the locking protocol, it sets the lock state directly where
+    /// the lock supports it. [read more](RawExclusiveLock#method.exc_lock_mut)
+    /// This is useful for code that is generic over guards, but already has exclusive
+    /// access to the mutex.
+    ///
+    /// ```
+    /// use locker::mutex::spin::SpinLock;
+    ///
+    /// let mut mutex = SpinLock::mutex(Vec::new());
+    /// mutex.guard_from_mut().push(10);
+    /// assert_eq!(*mutex.try_lock().unwrap(), [10]);
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn guard_from_mut(&mut self) -> ExclusiveGuard<'_, L, T> {
+        let value = self.value.as_ptr();
+        unsafe { ExclusiveGuard::from_raw_parts(self.raw.lock_mut(), value) }
+    }
 }
 
 impl<L: RawMutex + RawExclusiveLockTimed, T: ?Sized> Mutex<L, T>
@@ -208,6 +309,7 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_lock_until(&self, instant: L::Instant) -> Option<ExclusiveGuard<'_, L, T>> {
         Some(self.wrap(self.raw.try_lock_until(instant)?))
     }
@@ -218,11 +320,42 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_lock_for(&self, duration: L::Duration) -> Option<ExclusiveGuard<'_, L, T>> {
         Some(self.wrap(self.raw.try_lock_for(duration)?))
     }
 }
 
+impl<L: RawMutex, T> Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// Attempts to acquire any one of the `mutexes`, without blocking
+    ///
+    /// The mutexes are tried in order, and the index and guard of the first one that
+    /// could be locked is returned. If all of them are locked, then None is returned.
+    /// To block until one of them is released, put them in a
+    /// [`LockSet`](crate::lock_set::LockSet) instead.
+    ///
+    /// This is an associated function that needs to be used as `Mutex::try_lock_any(...)`.
+    ///
+    /// ```
+    /// use locker::mutex::spin::SpinLock;
+    ///
+    /// let pool = [SpinLock::mutex(0), SpinLock::mutex(1)];
+    /// let (index, first) = locker::mutex::Mutex::try_lock_any(&pool).unwrap();
+    /// assert_eq!((index, *first), (0, 0));
+    ///
+    /// let (index, second) = locker::mutex::Mutex::try_lock_any(&pool).unwrap();
+    /// assert_eq!((index, *second), (1, 1));
+    /// assert!(locker::mutex::Mutex::try_lock_any(&pool).is_none());
+    /// ```
+    #[inline]
+    pub fn try_lock_any(mutexes: &[Self]) -> Option<(usize, ExclusiveGuard<'_, L, T>)> {
+        crate::any::try_lock_any(mutexes, Self::try_lock)
+    }
+}
+
 unsafe impl<L: ?Sized + RawMutex> RawMutex for &L {}
 unsafe impl<L: ?Sized + RawMutex> RawMutex for &mut L {}
 
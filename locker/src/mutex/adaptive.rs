@@ -1,7 +1,8 @@
 //! an adaptive raw mutex
 
 use crate::exclusive_lock::RawExclusiveLock;
-use parking_lot_core::{self, ParkResult, SpinWait, UnparkResult, UnparkToken, DEFAULT_PARK_TOKEN};
+use crate::parking::{ParkResult, UnparkResult, UnparkToken, DEFAULT_PARK_TOKEN};
+use crate::waiter::{ParkKey, SpinThenPark, WaitStrategy};
 
 // UnparkToken used to indicate that that the target thread should attempt to
 // lock the mutex again as soon as it is unparked.
@@ -11,6 +12,7 @@ const TOKEN_NORMAL: UnparkToken = UnparkToken(0);
 // thread directly without unlocking it.
 const TOKEN_HANDOFF: UnparkToken = UnparkToken(1);
 
+use core::marker::PhantomData;
 use core::sync::atomic::{AtomicU8, Ordering};
 use std::time::{Duration, Instant};
 
@@ -20,19 +22,22 @@ pub type RawMutex = crate::mutex::raw::Mutex<AdaptiveLock>;
 pub type Mutex<T> = crate::mutex::Mutex<AdaptiveLock, T>;
 
 /// An adaptive mutex lock backed by `parking_lot_core`
-pub struct AdaptiveLock {
+///
+/// How a thread waits on a contended lock before parking is decided by the
+/// [`WaitStrategy`], which defaults to [`SpinThenPark`]
+///
+/// Threads park on a key derived from the address of the lock, see [`ParkKey`] for
+/// how the `stable-keys` feature flag keeps the key stable for locks that move
+pub struct AdaptiveLock<S = SpinThenPark> {
     state: AtomicU8,
+    key: ParkKey,
+    strategy: PhantomData<fn() -> S>,
 }
 
 impl AdaptiveLock {
-    const LOCK_BIT: u8 = 0b01;
-    const PARK_BIT: u8 = 0b10;
-
     /// Create a new adaptive mutex lock
     pub const fn new() -> Self {
-        AdaptiveLock {
-            state: AtomicU8::new(0),
-        }
+        Self::with_strategy()
     }
 
     /// Create a new raw mutex
@@ -44,11 +49,28 @@ impl AdaptiveLock {
     pub const fn mutex<T>(value: T) -> Mutex<T> {
         Mutex::from_raw_parts(Self::raw_mutex(), value)
     }
+}
+
+impl<S> AdaptiveLock<S> {
+    const LOCK_BIT: u8 = 0b01;
+    const PARK_BIT: u8 = 0b10;
+
+    /// Create a new adaptive mutex lock that waits with the wait strategy `S`
+    pub const fn with_strategy() -> Self {
+        AdaptiveLock {
+            state: AtomicU8::new(0),
+            key: ParkKey::new(),
+            strategy: PhantomData,
+        }
+    }
+}
 
+impl<S: WaitStrategy> AdaptiveLock<S> {
     #[cold]
     #[inline(never)]
     fn lock_slow(&self, timeout: Option<Instant>) -> bool {
-        let mut spinwait = SpinWait::new();
+        let _trace = crate::trace::SlowPath::enter("exc_lock", self);
+        let mut spinner = S::start(timeout);
         let mut state = self.state.load(Ordering::Relaxed);
         loop {
             // Grab the lock if it isn't locked, even if there is a queue on it
@@ -66,7 +88,7 @@ impl AdaptiveLock {
             }
 
             // If there is no queue, try spinning a few times
-            if state & Self::PARK_BIT == 0 && spinwait.spin() {
+            if state & Self::PARK_BIT == 0 && S::spin(&mut spinner) {
                 state = self.state.load(Ordering::Relaxed);
                 continue;
             }
@@ -85,8 +107,11 @@ impl AdaptiveLock {
             }
 
             // Park our thread until we are woken up by an unlock
-            let addr = self as *const _ as usize;
-            let validate = || self.state.load(Ordering::Relaxed) == Self::LOCK_BIT | Self::PARK_BIT;
+            let addr = self.key.key(self);
+            let validate = || {
+                self.key.record_parked(self);
+                self.state.load(Ordering::Relaxed) == Self::LOCK_BIT | Self::PARK_BIT
+            };
             let before_sleep = || {};
             let timed_out = |_, was_last_thread| {
                 // Clear the parked bit if we were the last parked thread
@@ -100,7 +125,7 @@ impl AdaptiveLock {
             //   * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
             //   * `before_sleep` does not call `park`, nor does it panic.
             match unsafe {
-                parking_lot_core::park(
+                crate::parking::park(
                     addr,
                     validate,
                     before_sleep,
@@ -124,7 +149,7 @@ impl AdaptiveLock {
             }
 
             // Loop back and try locking again
-            spinwait.reset();
+            S::reset(&mut spinner);
             state = self.state.load(Ordering::Relaxed);
         }
     }
@@ -134,7 +159,7 @@ impl AdaptiveLock {
     fn unlock_slow(&self, force_fair: bool) {
         // Unpark one thread and leave the parked bit set if there might
         // still be parked threads on this address.
-        let addr = self as *const _ as usize;
+        let addr = self.key.key(self);
         let callback = |result: UnparkResult| {
             // If we are using a fair unlock then we should keep the
             // mutex locked and hand it off to the unparked thread.
@@ -160,9 +185,11 @@ impl AdaptiveLock {
         // SAFETY:
         //   * `addr` is an address we control.
         //   * `callback` does not panic or call into any function of `parking_lot`.
-        unsafe {
-            parking_lot_core::unpark_one(addr, callback);
-        }
+        let result = unsafe { crate::parking::unpark_one(addr, callback) };
+        self.key.check_unparked(self, result.unparked_threads);
+
+        let fair = result.unparked_threads != 0 && (force_fair || result.be_fair);
+        crate::trace::unlock_slow(self, result.unparked_threads, fair);
     }
 
     #[cold]
@@ -172,17 +199,34 @@ impl AdaptiveLock {
     }
 }
 
-impl crate::Init for AdaptiveLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    [S] AdaptiveLock<S> = Self::with_strategy()
+}
+
+impl<S: WaitStrategy> crate::raw_state::RawStateBits for AdaptiveLock<S> {
+    #[inline]
+    fn raw_state_bits(&self) -> usize {
+        self.state.load(Ordering::Relaxed) as usize
+    }
+
+    fn decode_state(bits: usize) -> crate::raw_state::DecodedState {
+        let bits = bits as u8;
+
+        crate::raw_state::DecodedState {
+            exc: (bits & Self::LOCK_BIT != 0) as usize,
+            parked: bits & Self::PARK_BIT != 0,
+            ..Default::default()
+        }
+    }
 }
 
-unsafe impl crate::mutex::RawMutex for AdaptiveLock {}
-unsafe impl crate::RawLockInfo for AdaptiveLock {
+unsafe impl<S: WaitStrategy> crate::mutex::RawMutex for AdaptiveLock<S> {}
+unsafe impl<S: WaitStrategy> crate::RawLockInfo for AdaptiveLock<S> {
     type ExclusiveGuardTraits = ();
     type ShareGuardTraits = core::convert::Infallible;
 }
 
-unsafe impl RawExclusiveLock for AdaptiveLock {
+unsafe impl<S: WaitStrategy> RawExclusiveLock for AdaptiveLock<S> {
     #[inline]
     fn exc_lock(&self) {
         if !self.exc_try_lock() {
@@ -206,6 +250,17 @@ unsafe impl RawExclusiveLock for AdaptiveLock {
                 .is_ok()
     }
 
+    #[inline]
+    fn exc_lock_mut(&mut self) {
+        let state = self.state.get_mut();
+        assert_eq!(
+            *state & Self::LOCK_BIT,
+            0,
+            "tried to lock a leaked exc lock"
+        );
+        *state |= Self::LOCK_BIT;
+    }
+
     #[inline]
     unsafe fn exc_unlock(&self) {
         if self
@@ -218,14 +273,17 @@ unsafe impl RawExclusiveLock for AdaptiveLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         if self.state.load(Ordering::Relaxed) & Self::PARK_BIT != 0 {
             self.bump_slow(false);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock {
+unsafe impl<S: WaitStrategy> crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock<S> {
     #[inline]
     unsafe fn exc_unlock_fair(&self) {
         if self
@@ -238,19 +296,22 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         if self.state.load(Ordering::Relaxed) & Self::PARK_BIT != 0 {
             self.bump_slow(true);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
 
-impl crate::RawTimedLock for AdaptiveLock {
+impl<S: WaitStrategy> crate::RawTimedLock for AdaptiveLock<S> {
     type Instant = Instant;
     type Duration = Duration;
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock {
+unsafe impl<S: WaitStrategy> crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock<S> {
     fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
         if self.exc_try_lock() {
             true
@@ -268,4 +329,4 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock {
     }
 }
 
-unsafe impl crate::condvar::Parkable for AdaptiveLock {}
+unsafe impl<S: WaitStrategy> crate::condvar::Parkable for AdaptiveLock<S> {}
@@ -0,0 +1,203 @@
+//! Adapters between this crate and the [`critical-section`](https://docs.rs/critical-section) crate
+//!
+//! This is enabled by the `critical-section` feature flag, and works in both directions
+//!
+//! * [`CriticalSectionLock`] is a raw mutex which holds a critical section for as long as
+//!   it is locked. On single-core targets, where a critical section disables interrupts,
+//!   this is how data is shared between the main program and interrupt handlers.
+//! * [`spin_critical_section!`](crate::spin_critical_section) implements
+//!   `critical_section::Impl` with a reentrant spin lock, so that crates which need a
+//!   critical section can run on targets where this crate's locks are available, but
+//!   nothing else provides one. With `std`, [`SpinCriticalSection`] is ready to use.
+//!
+//! ```
+//! use locker::mutex::critical_section::{CriticalSectionLock, SpinCriticalSection};
+//! use locker::mutex::Mutex;
+//!
+//! critical_section::set_impl!(SpinCriticalSection);
+//!
+//! static COUNTER: Mutex<CriticalSectionLock, u32> = CriticalSectionLock::mutex(0);
+//!
+//! *COUNTER.lock() += 1;
+//! assert_eq!(critical_section::with(|_| *COUNTER.lock()), 1);
+//! ```
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use critical_section::RestoreState;
+
+/// The number of `CriticalSectionLock`s that are currently locked
+///
+/// This is only accessed while in a critical section, so it doesn't need
+/// read-modify-write atomics, which aren't available on some embedded targets
+static DEPTH: AtomicUsize = AtomicUsize::new(0);
+
+/// a mutex backed by a critical section
+pub type Mutex<T> = crate::mutex::Mutex<CriticalSectionLock, T>;
+
+/// A raw mutex that holds a critical section while it is locked
+///
+/// Locking acquires a critical section, and unlocking releases it, so nothing else can
+/// enter a critical section (and on single-core targets, no interrupt handler can run)
+/// while this is locked. Keep the guards short lived.
+///
+/// Critical sections must be released in the opposite order that they were acquired in,
+/// so guards of `CriticalSectionLock`s must be dropped in the opposite order that they
+/// were locked in, unlocking them in any other order panics. The guards can't be sent
+/// to other threads, because a critical section must be released on the thread that
+/// acquired it.
+///
+/// # Panics
+///
+/// Locking a `CriticalSectionLock` that is already locked panics instead of deadlocking,
+/// because the owner can't release it until the current critical section is released.
+pub struct CriticalSectionLock {
+    locked: AtomicBool,
+    depth: AtomicUsize,
+    restore_state: UnsafeCell<RestoreState>,
+}
+
+// the restore state is only accessed by the owner of the lock
+unsafe impl Sync for CriticalSectionLock {}
+
+impl CriticalSectionLock {
+    /// create a new unlocked critical section lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            depth: AtomicUsize::new(0),
+            restore_state: UnsafeCell::new(RestoreState::invalid()),
+        }
+    }
+
+    /// create a new critical section based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        unsafe { Mutex::from_raw_parts(crate::mutex::raw::Mutex::from_raw(Self::new()), value) }
+    }
+}
+
+crate::impl_init! {
+    CriticalSectionLock = Self::new()
+}
+
+unsafe impl crate::mutex::RawMutex for CriticalSectionLock {}
+unsafe impl crate::RawLockInfo for CriticalSectionLock {
+    type ExclusiveGuardTraits = crate::NoSend;
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLock for CriticalSectionLock {
+    #[inline]
+    fn exc_lock(&self) {
+        assert!(
+            self.exc_try_lock(),
+            "tried to lock a `CriticalSectionLock` while it was locked, which would deadlock"
+        );
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let restore_state = unsafe { critical_section::acquire() };
+
+        if self.locked.load(Ordering::Relaxed) {
+            unsafe { critical_section::release(restore_state) };
+            return false;
+        }
+
+        let depth = DEPTH.load(Ordering::Relaxed);
+        DEPTH.store(depth + 1, Ordering::Relaxed);
+
+        self.locked.store(true, Ordering::Relaxed);
+        self.depth.store(depth, Ordering::Relaxed);
+        unsafe { self.restore_state.get().write(restore_state) };
+
+        true
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        let depth = self.depth.load(Ordering::Relaxed);
+
+        assert_eq!(
+            DEPTH.load(Ordering::Relaxed),
+            depth + 1,
+            "`CriticalSectionLock`s must be unlocked in the opposite order they were locked in"
+        );
+
+        DEPTH.store(depth, Ordering::Relaxed);
+        self.locked.store(false, Ordering::Relaxed);
+
+        critical_section::release(self.restore_state.get().read())
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        // other threads can't be waiting, they would be blocked on the critical section
+        crate::BumpResult::KEPT
+    }
+}
+
+/// Implement `critical_section::Impl` for a new type, with a global reentrant spin lock
+///
+/// The critical section is owned by a thread, as decided by the
+/// [`ThreadInfo`](crate::remutex::ThreadInfo) type, which must implement `Init`. Nested
+/// critical sections of the same thread only increment a counter. Unlike critical
+/// sections that disable interrupts, this doesn't protect against interrupt handlers,
+/// so it is meant for targets with threads, like an RTOS or a hosted test environment.
+///
+/// ```ignore
+/// locker::spin_critical_section! {
+///     pub struct RtosCriticalSection(my_rtos::TaskInfo);
+/// }
+///
+/// critical_section::set_impl!(RtosCriticalSection);
+/// ```
+#[macro_export]
+macro_rules! spin_critical_section {
+    ($(#[$meta:meta])* $v:vis struct $name:ident($thread_info:ty);) => {
+        $(#[$meta])*
+        $v struct $name;
+
+        impl $name {
+            #[inline]
+            fn lock() -> &'static $crate::remutex::lock::ReLock<
+                $crate::mutex::spin::SpinLock,
+                $crate::remutex::counter::SubWord,
+                $thread_info,
+            > {
+                static LOCK: $crate::remutex::lock::ReLock<
+                    $crate::mutex::spin::SpinLock,
+                    $crate::remutex::counter::SubWord,
+                    $thread_info,
+                > = $crate::Init::INIT;
+
+                &LOCK
+            }
+        }
+
+        unsafe impl $crate::mutex::critical_section::Impl for $name {
+            #[inline]
+            unsafe fn acquire() -> $crate::mutex::critical_section::RawRestoreState {
+                $crate::share_lock::RawShareLock::shr_lock(Self::lock());
+                Default::default()
+            }
+
+            #[inline]
+            unsafe fn release(_: $crate::mutex::critical_section::RawRestoreState) {
+                $crate::share_lock::RawShareLock::shr_unlock(Self::lock())
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+pub use critical_section::{Impl, RawRestoreState};
+
+#[cfg(feature = "std")]
+spin_critical_section! {
+    /// A `critical_section::Impl` which uses a reentrant spin lock, see
+    /// [`spin_critical_section!`](crate::spin_critical_section) for details
+    pub struct SpinCriticalSection(crate::remutex::std_thread::StdThreadInfo);
+}
@@ -8,17 +8,24 @@ pub type RawMutex = crate::mutex::raw::Mutex<DefaultLock>;
 /// A default mutex
 pub type Mutex<T> = crate::mutex::Mutex<DefaultLock, T>;
 
-#[cfg(feature = "parking_lot_core")]
-type Lock = crate::mutex::adaptive::AdaptiveLock;
-
-#[cfg(not(feature = "parking_lot_core"))]
-type Lock = crate::mutex::spin::SpinLock;
+cfg_if::cfg_if! {
+    if #[cfg(feature = "parking_lot_core")] {
+        type Lock = crate::mutex::adaptive::AdaptiveLock;
+    } else if #[cfg(feature = "std")] {
+        type Lock = crate::mutex::word_lock::WordLock;
+    } else {
+        type Lock = crate::mutex::spin::SpinLock;
+    }
+}
 
 /// A default mutex lock implementation
 ///
 /// This implementation will be a spin-lock by default, but if
 /// the `parking_lot_core` feature is enabled then it will use
-/// an adaptive strategy
+/// an adaptive strategy, and otherwise with the `std` feature
+/// it will use a [word lock](crate::mutex::word_lock) that parks threads.
+///
+/// So with either `parking_lot_core` or `std`, it implements `RawExclusiveLockTimed`
 #[repr(transparent)]
 pub struct DefaultLock(Lock);
 
@@ -42,8 +49,8 @@ impl DefaultLock {
     }
 }
 
-impl crate::Init for DefaultLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    DefaultLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for DefaultLock {}
@@ -69,12 +76,11 @@ unsafe impl RawExclusiveLock for DefaultLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         self.0.exc_bump()
     }
 }
 
-#[cfg(feature = "parking_lot_core")]
 unsafe impl RawExclusiveLockFair for DefaultLock {
     #[inline]
     unsafe fn exc_unlock_fair(&self) {
@@ -82,18 +88,18 @@ unsafe impl RawExclusiveLockFair for DefaultLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         self.0.exc_bump_fair()
     }
 }
 
-#[cfg(feature = "parking_lot_core")]
+#[cfg(feature = "std")]
 impl crate::RawTimedLock for DefaultLock {
     type Instant = std::time::Instant;
     type Duration = std::time::Duration;
 }
 
-#[cfg(feature = "parking_lot_core")]
+#[cfg(feature = "std")]
 unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for DefaultLock {
     fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
         self.0.exc_try_lock_until(instant)
@@ -24,6 +24,32 @@ impl GlobalLock {
         Mutex::from_raw_parts(Self::raw_mutex(), value)
     }
 
+    /// Create a new global raw reentrant mutex
+    ///
+    /// This uses the [global reentrant lock set](crate::remutex::global), so locking the same
+    /// global reentrant mutex again on the same thread will not deadlock.
+    #[cfg(feature = "std")]
+    pub const fn raw_reentrant_mutex() -> crate::remutex::global::RawReentrantMutex {
+        crate::remutex::global::GlobalLock::raw_remutex()
+    }
+
+    /// Create a new global reentrant mutex
+    ///
+    /// This uses the [global reentrant lock set](crate::remutex::global), so locking the same
+    /// global reentrant mutex again on the same thread will not deadlock.
+    #[cfg(feature = "std")]
+    pub const fn reentrant_mutex<T>(value: T) -> crate::remutex::global::ReentrantMutex<T> {
+        crate::remutex::global::GlobalLock::remutex(value)
+    }
+
+    /// Create a new global reentrant mutex
+    #[cfg(feature = "std")]
+    pub fn reentrant_mutex_from_mut<T: ?Sized>(
+        value: &mut T,
+    ) -> &mut crate::remutex::global::ReentrantMutex<T> {
+        crate::remutex::global::GlobalLock::remutex_from_mut(value)
+    }
+
     /// Create a new global mutex
     #[allow(clippy::transmute_ptr_to_ptr)]
     pub fn mutex_from_mut<T: ?Sized>(value: &mut T) -> &mut Mutex<T> {
@@ -42,6 +68,24 @@ impl GlobalLock {
         unsafe { core::mem::transmute(value) }
     }
 
+    /// Run `f` with a fresh global lock set on the current thread
+    ///
+    /// While `f` runs, all global mutexes locked on this thread use a lock set
+    /// that is private to this call, so tests that run in parallel can't contend with each
+    /// other (or with leftover state from another test) through the shared global set.
+    /// Calls may be nested, the previous set is restored when `f` returns or panics.
+    ///
+    /// # Safety
+    ///
+    /// * no guard of a global lock created inside `f` may outlive `f`
+    /// * no global lock may be locked inside `f` and unlocked outside of it, or vice versa
+    /// * a global lock must not be held both inside and outside of `f` at the same time,
+    ///   whether on this thread or another one, because only this thread sees the fresh set
+    #[cfg(feature = "test-util")]
+    pub unsafe fn with_isolated_set<R>(f: impl FnOnce() -> R) -> R {
+        crate::isolate::with_isolated(&ISOLATED, f)
+    }
+
     #[inline(always)]
     #[allow(clippy::trivially_copy_pass_by_ref)]
     fn addr(&self) -> usize {
@@ -51,7 +95,14 @@ impl GlobalLock {
     #[inline(always)]
     #[allow(clippy::trivially_copy_pass_by_ref)]
     fn get(&self) -> &'static DefaultLock {
-        &GLOBAL[self.addr()]
+        #[cfg(feature = "test-util")]
+        {
+            if let Some(set) = crate::isolate::current(&ISOLATED) {
+                return &set[self.addr()].0;
+            }
+        }
+
+        &GLOBAL[self.addr()].0
     }
 
     /// Checks if two global locks will contend
@@ -66,6 +117,67 @@ impl GlobalLock {
     pub fn will_mutex_contend<T: ?Sized, U: ?Sized>(a: &Mutex<T>, b: &Mutex<U>) -> bool {
         a.raw().inner().addr() == b.raw().inner().addr()
     }
+
+    /// Lock one of `shards`, preferring the shard assigned to the current thread
+    ///
+    /// This is meant for statistic-style data, like counters or histograms, where any shard
+    /// will do and the shards are combined on read. Each thread is assigned a shard by mixing
+    /// the address of `shards` with a per-thread salt, so threads mostly stay on their own
+    /// shard instead of all contending on the same one. If the assigned shard is locked, the
+    /// other shards are tried before blocking on the assigned shard.
+    ///
+    /// The locks of the global set are each on their own cache line, but the values are
+    /// stored in `shards`, so small values should be wrapped in a cache line aligned type
+    /// to keep threads that update neighbouring shards from contending on the same cache line.
+    ///
+    /// # Panic
+    ///
+    /// If `shards` is empty
+    #[cfg(feature = "std")]
+    pub fn lock_for_current_thread<T>(
+        shards: &[Mutex<T>],
+    ) -> crate::exclusive_lock::ExclusiveGuard<'_, Self, T> {
+        assert!(
+            !shards.is_empty(),
+            "tried to lock a shard of an empty slice"
+        );
+
+        use crate::remutex::ThreadInfo;
+
+        let thread = crate::remutex::std_thread::StdThreadInfo.id().get();
+        let start = Self::shard_for_thread(shards.as_ptr() as usize, thread, shards.len());
+
+        (start..shards.len())
+            .chain(0..start)
+            .find_map(|shard| shards[shard].try_lock())
+            .unwrap_or_else(|| shards[start].lock())
+    }
+
+    /// The shard of `len` shards at `addr` which is assigned to the thread with the id `thread`
+    #[cfg(feature = "std")]
+    pub(crate) fn shard_for_thread(addr: usize, thread: usize, len: usize) -> usize {
+        // thread ids and addresses are aligned, so mix the bits before reducing them
+        fn mix(value: usize) -> usize {
+            ((value as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32) as usize
+        }
+
+        (mix(addr) ^ mix(thread)) % len
+    }
+}
+
+#[cfg(feature = "test-util")]
+std::thread_local! {
+    static ISOLATED: crate::isolate::Slot<[Stripe; 61]> = const { core::cell::Cell::new(None) };
+}
+
+// each lock gets its own cache line (two on some platforms that prefetch pairs of lines),
+// so that threads that use unrelated locks don't contend on the same cache line
+#[repr(align(128))]
+struct Stripe(DefaultLock);
+
+impl crate::Init for Stripe {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: Self = Self(crate::Init::INIT);
 }
 
 // 61 because it is a large prime number,
@@ -73,7 +185,7 @@ impl GlobalLock {
 // because unrealated locks will be unlikely to pick up the same lock,
 // even they are contigious in memory
 #[rustfmt::skip]
-static GLOBAL: [DefaultLock; 61] = [
+static GLOBAL: [Stripe; 61] = [
     crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
     crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
     crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
@@ -95,8 +207,8 @@ static GLOBAL: [DefaultLock; 61] = [
     crate::Init::INIT,
 ];
 
-impl crate::Init for GlobalLock {
-    const INIT: Self = Self;
+crate::impl_init! {
+    GlobalLock = Self
 }
 
 unsafe impl crate::mutex::RawMutex for GlobalLock {}
@@ -122,7 +234,7 @@ unsafe impl RawExclusiveLock for GlobalLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         self.get().exc_bump()
     }
 }
@@ -135,7 +247,7 @@ unsafe impl RawExclusiveLockFair for GlobalLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         self.get().exc_bump_fair()
     }
 }
@@ -176,3 +288,53 @@ fn test_contention() {
     let _lock = a.lock();
     let _lock = b.lock();
 }
+
+#[test]
+#[cfg(feature = "std")]
+fn lock_for_current_thread() {
+    let shards: Vec<Mutex<u32>> = (0..8).map(|_| GlobalLock::mutex(0)).collect();
+
+    // while its shard is locked, a thread moves on to another one
+    let held = GlobalLock::lock_for_current_thread(&shards);
+    let addr = &*held as *const u32;
+    assert_ne!(
+        &*GlobalLock::lock_for_current_thread(&shards) as *const u32,
+        addr
+    );
+    drop(held);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    *GlobalLock::lock_for_current_thread(&shards) += 1;
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    let total: u32 = shards.iter().map(|shard| *shard.lock()).sum();
+    assert_eq!(total, 8000);
+}
+
+#[test]
+#[cfg(feature = "test-util")]
+fn lock_for_current_thread_uncontended() {
+    let shards: Vec<Mutex<u32>> = (0..8).map(|_| GlobalLock::mutex(0)).collect();
+
+    // other tests may hold locks of the global set that the shards share,
+    // so only an isolated set is guaranteed to be uncontended
+    unsafe {
+        GlobalLock::with_isolated_set(|| {
+            // uncontended, a thread always gets the same shard
+            let shard = GlobalLock::lock_for_current_thread(&shards);
+            let addr = &*shard as *const u32;
+            drop(shard);
+            assert_eq!(
+                &*GlobalLock::lock_for_current_thread(&shards) as *const u32,
+                addr
+            );
+        })
+    }
+}
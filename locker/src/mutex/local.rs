@@ -31,8 +31,8 @@ impl LocalLock {
         Mutex::from_raw_parts(Self::raw_mutex(), value)
     }
 }
-impl crate::Init for LocalLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    LocalLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for LocalLock {}
@@ -55,6 +55,13 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for LocalLock {
         !self.lock.replace(true)
     }
 
+    #[inline]
+    fn exc_lock_mut(&mut self) {
+        let lock = self.lock.get_mut();
+        assert!(!*lock, "tried to lock a leaked exc lock");
+        *lock = true;
+    }
+
     #[inline]
     unsafe fn exc_unlock(&self) {
         debug_assert!(self.lock.get(), "tried to unlock an unlocked exc lock");
@@ -63,5 +70,7 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for LocalLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {}
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
 }
@@ -32,8 +32,8 @@ impl LocalSplitLock {
     }
 }
 
-impl crate::Init for LocalSplitLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    LocalSplitLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for LocalSplitLock {}
@@ -72,7 +72,9 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for LocalSplitLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {}
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
 }
 
 unsafe impl crate::exclusive_lock::SplittableExclusiveLock for LocalSplitLock {
@@ -74,7 +74,7 @@ impl LocalTaggedLock {
     pub fn replace_tag(&self, tag: u8) -> u8 {
         let state = self.state.get();
 
-        self.state.set((state & Self::MASK) | tag);
+        self.state.set((state & Self::MASK) | (tag << Self::SHIFT));
 
         state >> Self::SHIFT
     }
@@ -94,8 +94,8 @@ impl LocalTaggedLock {
     }
 }
 
-impl crate::Init for LocalTaggedLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    LocalTaggedLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for LocalTaggedLock {}
@@ -135,5 +135,26 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for LocalTaggedLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {}
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
+}
+
+#[test]
+fn replace_tag() {
+    use crate::exclusive_lock::RawExclusiveLock;
+
+    let lock = LocalTaggedLock::new();
+    lock.set_tag(0b101);
+    assert!(lock.exc_try_lock());
+
+    // the tag doesn't overwrite the lock bit
+    assert_eq!(lock.replace_tag(0b011), 0b101);
+    assert_eq!(lock.tag(), 0b011);
+    assert!(!lock.exc_try_lock());
+
+    unsafe { lock.exc_unlock() }
+    assert_eq!(lock.replace_tag(0b001), 0b011);
+    assert!(lock.exc_try_lock());
+    assert_eq!(lock.tag(), 0b001);
 }
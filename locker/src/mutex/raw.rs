@@ -1,6 +1,9 @@
 //! A type-safe implementation of a `Mutex`
 
-use crate::exclusive_lock::{RawExclusiveGuard, RawExclusiveLockTimed};
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+
+use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveGuard, RawExclusiveLockTimed};
 use crate::mutex::RawMutex;
 
 /// A mutual exclusion primitive useful for protecting shared data
@@ -12,14 +15,13 @@ pub struct Mutex<L> {
     lock: L,
 }
 
-impl<L: RawMutex + crate::Init> Default for Mutex<L> {
-    #[inline]
-    fn default() -> Self {
-        crate::Init::INIT
-    }
-}
-
 impl<L> Mutex<L> {
+    /// The size of this raw mutex in bytes
+    pub const SIZE: usize = core::mem::size_of::<Self>();
+
+    /// The alignment of this raw mutex in bytes
+    pub const ALIGN: usize = core::mem::align_of::<Self>();
+
     /// Create a new raw mutex
     ///
     /// # Safety
@@ -36,6 +38,31 @@ impl<L> Mutex<L> {
         self.lock
     }
 
+    /// Convert the underlying lock with the given function
+    ///
+    /// # Safety
+    ///
+    /// `f` must return a lock that is valid to pass to [`from_raw`](Self::from_raw)
+    #[inline]
+    pub unsafe fn map_lock<M>(self, f: impl FnOnce(L) -> M) -> Mutex<M> {
+        Mutex::from_raw(f(self.lock))
+    }
+
+    /// Convert this raw mutex into a raw mutex with another backend
+    #[inline]
+    pub fn convert<M: crate::mutex::RawMutex>(self) -> Mutex<M>
+    where
+        L: crate::ConvertLock<M>,
+    {
+        unsafe { self.map_lock(crate::ConvertLock::convert_lock) }
+    }
+
+    /// Convert this raw mutex into a raw mutex with any backend that implements [`Init`](crate::Init)
+    #[inline]
+    pub fn convert_init<M: crate::mutex::RawMutex + crate::Init>(self) -> Mutex<M> {
+        unsafe { self.map_lock(|_| M::INIT) }
+    }
+
     /// the underlying lock
     #[inline]
     pub const fn inner(&self) -> &L {
@@ -67,8 +94,76 @@ impl<L> Mutex<L> {
     }
 }
 
-impl<L: RawMutex + crate::Init> crate::Init for Mutex<L> {
-    const INIT: Self = unsafe { Self::from_raw(L::INIT) };
+crate::impl_init! {
+    [L: RawMutex + crate::Init] Mutex<L> = unsafe { Self::from_raw(L::INIT) }
+}
+
+impl<L: RawMutex + crate::Init> Mutex<L> {
+    /// Initialize a raw mutex in place, and return a reference to it
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null, aligned to `Self::ALIGN`, valid for writes of `Self::SIZE` bytes,
+    /// and must not be aliased for the lifetime `'a`. Any previous value at `ptr` will be
+    /// overwritten without being dropped.
+    #[inline]
+    pub unsafe fn init_in_place<'a>(ptr: *mut MaybeUninit<Self>) -> &'a Self {
+        let ptr = ptr as *mut Self;
+        ptr.write(crate::Init::INIT);
+        &*ptr
+    }
+}
+
+/// Uninitialized storage for a raw mutex that can be placed in a `static`
+///
+/// This is useful for placing raw mutexes in custom static memory pools
+/// (for example in bootloader or kernel contexts), where the mutex
+/// can only be initialized once the memory is known to be usable.
+#[repr(transparent)]
+pub struct StaticMutexCell<L> {
+    slot: UnsafeCell<MaybeUninit<Mutex<L>>>,
+}
+
+unsafe impl<L: Send> Send for StaticMutexCell<L> {}
+unsafe impl<L: Sync> Sync for StaticMutexCell<L> {}
+
+impl<L> StaticMutexCell<L> {
+    /// Create a new uninitialized cell
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            slot: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Get a raw pointer to the (possibly uninitialized) raw mutex
+    #[inline]
+    pub fn as_mut_ptr(&self) -> *mut MaybeUninit<Mutex<L>> {
+        self.slot.get()
+    }
+
+    /// Get a reference to the raw mutex
+    ///
+    /// # Safety
+    ///
+    /// The raw mutex must have been initialized
+    #[inline]
+    pub unsafe fn assume_init(&self) -> &Mutex<L> {
+        &*(self.slot.get() as *const Mutex<L>)
+    }
+}
+
+impl<L: RawMutex + crate::Init> StaticMutexCell<L> {
+    /// Initialize the raw mutex, and return a reference to it
+    ///
+    /// # Safety
+    ///
+    /// This must be called at most once, and before any call to `assume_init`.
+    /// It must not race with any other access to this cell.
+    #[inline]
+    pub unsafe fn init(&self) -> &Mutex<L> {
+        Mutex::init_in_place(self.as_mut_ptr())
+    }
 }
 
 impl<L: RawMutex> Mutex<L>
@@ -93,6 +188,7 @@ where
     /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
     /// single threaded mutex)
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn lock(&self) -> RawExclusiveGuard<'_, L> {
         unsafe {
             self.lock.exc_lock();
@@ -100,6 +196,18 @@ where
         }
     }
 
+    /// Locks this `Mutex` through a mutable borrow
+    ///
+    /// Since the mutex is borrowed mutably, no other thread can access it, so this
+    /// sets the lock state without synchronizing. [read more](crate::exclusive_lock::RawExclusiveLock#method.exc_lock_mut)
+    #[inline]
+    pub fn lock_mut(&mut self) -> RawExclusiveGuard<'_, L> {
+        unsafe {
+            self.lock.exc_lock_mut();
+            self.lock_unchecked()
+        }
+    }
+
     /// Attempts to acquire this lock.
     ///
     /// If the lock could not be acquired at this time, then None is returned.
@@ -107,6 +215,7 @@ where
     ///
     /// This function does not block.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_lock(&self) -> Option<RawExclusiveGuard<'_, L>> {
         if self.lock.exc_try_lock() {
             unsafe { Some(self.lock_unchecked()) }
@@ -114,6 +223,35 @@ where
             None
         }
     }
+
+    /// Acquires a lock, and returns a guard to the data behind `value`
+    ///
+    /// This pairs the raw mutex with data that it doesn't own, like data in an arena
+    /// or in an FFI struct, in the same way that [`Mutex`](crate::mutex::Mutex) does.
+    ///
+    /// # Safety
+    ///
+    /// `value` must be valid for reads and writes for as long as the guard is alive,
+    /// and must only be accessed while this mutex is locked
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub unsafe fn lock_with<T: ?Sized>(&self, value: *mut T) -> ExclusiveGuard<'_, L, T> {
+        ExclusiveGuard::from_raw_parts(self.lock(), value)
+    }
+
+    /// Attempts to acquire this lock, and returns a guard to the data behind `value`
+    ///
+    /// # Safety
+    ///
+    /// See [`Mutex::lock_with`]
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub unsafe fn try_lock_with<T: ?Sized>(
+        &self,
+        value: *mut T,
+    ) -> Option<ExclusiveGuard<'_, L, T>> {
+        Some(ExclusiveGuard::from_raw_parts(self.try_lock()?, value))
+    }
 }
 
 impl<L: RawMutex + RawExclusiveLockTimed> Mutex<L>
@@ -126,6 +264,7 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_lock_until(&self, instant: L::Instant) -> Option<RawExclusiveGuard<'_, L>> {
         if self.lock.exc_try_lock_until(instant) {
             unsafe { Some(self.lock_unchecked()) }
@@ -140,6 +279,7 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_lock_for(&self, duration: L::Duration) -> Option<RawExclusiveGuard<'_, L>> {
         if self.lock.exc_try_lock_for(duration) {
             unsafe { Some(self.lock_unchecked()) }
@@ -148,3 +288,35 @@ where
         }
     }
 }
+
+#[test]
+#[cfg(feature = "extra")]
+fn lock_with() {
+    use crate::mutex::spin::SpinLock;
+
+    let mut values = [0, 0];
+    let mutex = SpinLock::raw_mutex();
+
+    unsafe {
+        let mut guard = mutex.lock_with(values.as_mut_ptr().add(1));
+        *guard += 1;
+        assert!(mutex.try_lock_with(values.as_mut_ptr()).is_none());
+    }
+
+    assert_eq!(values, [0, 1]);
+}
+
+#[test]
+#[cfg(feature = "extra")]
+fn static_mutex_cell() {
+    static CELL: StaticMutexCell<crate::mutex::spin::SpinLock> = StaticMutexCell::uninit();
+
+    assert_eq!(
+        Mutex::<crate::mutex::spin::SpinLock>::SIZE,
+        core::mem::size_of::<crate::mutex::spin::SpinLock>()
+    );
+
+    let mutex = unsafe { CELL.init() };
+    let _guard = mutex.lock();
+    assert!(unsafe { CELL.assume_init() }.try_lock().is_none());
+}
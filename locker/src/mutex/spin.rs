@@ -31,6 +31,9 @@ pub type Mutex<T> = crate::mutex::Mutex<SpinLock, T>;
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
+///
+/// There are never any parked threads in a spin lock, so unlocking fairly
+/// is the same as unlocking normally, and `bump_fair` is the same as `bump`.
 pub struct SpinLock {
     lock: AtomicBool,
 }
@@ -55,8 +58,22 @@ impl SpinLock {
     }
 }
 
-impl crate::Init for SpinLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    SpinLock = Self::new()
+}
+
+impl crate::raw_state::RawStateBits for SpinLock {
+    #[inline]
+    fn raw_state_bits(&self) -> usize {
+        self.lock.load(Ordering::Relaxed) as usize
+    }
+
+    fn decode_state(bits: usize) -> crate::raw_state::DecodedState {
+        crate::raw_state::DecodedState {
+            exc: bits,
+            ..Default::default()
+        }
+    }
 }
 
 unsafe impl crate::mutex::RawMutex for SpinLock {}
@@ -86,13 +103,36 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for SpinLock {
             .is_ok()
     }
 
+    #[inline]
+    fn exc_lock_mut(&mut self) {
+        let lock = self.lock.get_mut();
+        assert!(!*lock, "tried to lock a leaked exc lock");
+        *lock = true;
+    }
+
     #[inline]
     unsafe fn exc_unlock(&self) {
         self.lock.store(false, Ordering::Release);
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        // there are never any parked threads in a spin lock
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for SpinLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        use crate::exclusive_lock::RawExclusiveLock;
+
+        self.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         // there are never any parked threads in a spin lock
+        crate::BumpResult::KEPT
     }
 }
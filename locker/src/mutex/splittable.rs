@@ -1,7 +1,8 @@
 //! a splittable lock
 
 use crate::exclusive_lock::RawExclusiveLock;
-use parking_lot_core::{self, ParkResult, SpinWait, UnparkResult, UnparkToken, DEFAULT_PARK_TOKEN};
+use crate::parking::{ParkResult, UnparkResult, UnparkToken, DEFAULT_PARK_TOKEN};
+use crate::spin_wait::SpinWait;
 
 // UnparkToken used to indicate that that the target thread should attempt to
 // lock the mutex again as soon as it is unparked.
@@ -61,7 +62,8 @@ impl SplitLock {
     fn unlock_fast(&self) -> bool {
         let mut state = self.state.load(Ordering::Relaxed);
 
-        while state > INC || state & PARK_BIT != 0 {
+        // if this is the last lock, and there are parked threads, one of them must be unparked
+        while state >= 2 * INC || state == INC {
             if let Err(x) = self.state.compare_exchange_weak(
                 state,
                 state - INC,
@@ -133,7 +135,7 @@ impl SplitLock {
             //   * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
             //   * `before_sleep` does not call `park`, nor does it panic.
             match unsafe {
-                parking_lot_core::park(
+                crate::parking::park(
                     addr,
                     validate,
                     before_sleep,
@@ -194,7 +196,7 @@ impl SplitLock {
         //   * `addr` is an address we control.
         //   * `callback` does not panic or call into any function of `parking_lot`.
         unsafe {
-            parking_lot_core::unpark_one(addr, callback);
+            crate::parking::unpark_one(addr, callback);
         }
     }
 
@@ -205,8 +207,23 @@ impl SplitLock {
     }
 }
 
-impl crate::Init for SplitLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    SplitLock = Self::new()
+}
+
+impl crate::raw_state::RawStateBits for SplitLock {
+    #[inline]
+    fn raw_state_bits(&self) -> usize {
+        self.state.load(Ordering::Relaxed)
+    }
+
+    fn decode_state(bits: usize) -> crate::raw_state::DecodedState {
+        crate::raw_state::DecodedState {
+            exc: bits / INC,
+            parked: bits & PARK_BIT != 0,
+            ..Default::default()
+        }
+    }
 }
 
 unsafe impl crate::mutex::RawMutex for SplitLock {}
@@ -242,9 +259,12 @@ unsafe impl RawExclusiveLock for SplitLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         if self.state.load(Ordering::Relaxed) == INC | PARK_BIT {
-            self.bump_slow(false)
+            self.bump_slow(false);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
@@ -258,9 +278,12 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockFair for SplitLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         if self.state.load(Ordering::Relaxed) == INC | PARK_BIT {
-            self.bump_slow(true)
+            self.bump_slow(true);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
@@ -290,6 +313,23 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for SplitLock {
 
 unsafe impl crate::exclusive_lock::SplittableExclusiveLock for SplitLock {
     unsafe fn exc_split(&self) {
-        self.state.fetch_add(INC, Ordering::Relaxed);
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        loop {
+            let new_state = state
+                .checked_add(INC)
+                .expect("tried to split the exclusive lock too many times");
+
+            if let Err(x) = self.state.compare_exchange_weak(
+                state,
+                new_state,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                state = x;
+            } else {
+                return;
+            }
+        }
     }
 }
@@ -42,8 +42,8 @@ impl SplitDefaultLock {
     }
 }
 
-impl crate::Init for SplitDefaultLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    SplitDefaultLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for SplitDefaultLock {}
@@ -69,12 +69,11 @@ unsafe impl RawExclusiveLock for SplitDefaultLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         self.0.exc_bump()
     }
 }
 
-#[cfg(feature = "parking_lot_core")]
 unsafe impl RawExclusiveLockFair for SplitDefaultLock {
     #[inline]
     unsafe fn exc_unlock_fair(&self) {
@@ -82,7 +81,7 @@ unsafe impl RawExclusiveLockFair for SplitDefaultLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         self.0.exc_bump_fair()
     }
 }
@@ -43,6 +43,9 @@ const INC: usize = 1;
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
+///
+/// There are never any parked threads in a spin lock, so unlocking fairly
+/// is the same as unlocking normally, and `bump_fair` is the same as `bump`.
 pub struct SplitSpinLock {
     state: AtomicUsize,
 }
@@ -82,8 +85,8 @@ impl SplitSpinLock {
     }
 }
 
-impl crate::Init for SplitSpinLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    SplitSpinLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for SplitSpinLock {}
@@ -120,11 +123,45 @@ unsafe impl RawExclusiveLock for SplitSpinLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {}
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for SplitSpinLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        use crate::exclusive_lock::RawExclusiveLock;
+
+        self.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        // there are never any parked threads in a spin lock
+        crate::BumpResult::KEPT
+    }
 }
 
 unsafe impl crate::exclusive_lock::SplittableExclusiveLock for SplitSpinLock {
     unsafe fn exc_split(&self) {
-        self.state.fetch_add(INC, Ordering::Relaxed);
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        loop {
+            let new_state = state
+                .checked_add(INC)
+                .expect("tried to split the exclusive lock too many times");
+
+            if let Err(x) = self.state.compare_exchange_weak(
+                state,
+                new_state,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                state = x;
+            } else {
+                return;
+            }
+        }
     }
 }
@@ -0,0 +1,264 @@
+//! Typed bitfields inside of the tag of a tagged lock
+//!
+//! The tag of a tagged lock is a handful of bits, and it is common to pack multiple
+//! unrelated values into it. Instead of masking and shifting by hand, declare each
+//! value as a [`TagField`], and read or write it through [`AtomicTag`]. The masks
+//! are computed at compile time, and a field that doesn't fit in the tag of a lock
+//! is a compile error when it is used with that lock.
+//!
+//! Fields can hold any [`TagValue`], which includes `u8`, `bool`, and fieldless
+//! `#[repr(u8)]` enums that implement it with [`impl_tag_value!`](crate::impl_tag_value).
+//!
+//! ```
+//! use locker::mutex::tag_field::{AtomicTag, Bits};
+//! use locker::mutex::tagged_default::TaggedDefaultLock;
+//! use std::sync::atomic::Ordering;
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq)]
+//! #[repr(u8)]
+//! enum Phase {
+//!     Idle,
+//!     Running,
+//!     Done,
+//! }
+//!
+//! locker::impl_tag_value! { Phase { Idle, Running, Done } }
+//!
+//! type PhaseField = Bits<0, 2, Phase>;
+//! type Dirty = Bits<2, 1, bool>;
+//!
+//! let lock = TaggedDefaultLock::new();
+//!
+//! lock.set_field::<PhaseField>(Phase::Running, Ordering::Relaxed);
+//! lock.set_field::<Dirty>(true, Ordering::Relaxed);
+//!
+//! assert_eq!(lock.tag_field::<PhaseField>(Ordering::Relaxed), Phase::Running);
+//! assert!(lock.tag_field::<Dirty>(Ordering::Relaxed));
+//! assert_eq!(lock.tag(Ordering::Relaxed), 0b101);
+//! ```
+//!
+//! A field that doesn't fit in the tag is rejected at compile time
+//!
+//! ```compile_fail
+//! use locker::mutex::tag_field::{AtomicTag, Bits};
+//! use locker::mutex::tagged_default::TaggedDefaultLock;
+//! use std::sync::atomic::Ordering;
+//!
+//! let lock = TaggedDefaultLock::new();
+//! lock.tag_field::<Bits<4, 4>>(Ordering::Relaxed);
+//! ```
+
+use core::marker::PhantomData;
+use core::sync::atomic::Ordering;
+
+/// A value that can be stored in a [`TagField`]
+pub trait TagValue: Copy {
+    /// Convert this value to the bits that are stored in the tag
+    fn into_bits(self) -> u8;
+
+    /// Convert the bits stored in the tag back into a value
+    ///
+    /// `bits` was returned by `into_bits`, unless the tag was changed without going
+    /// through the field, so implementations may panic on other bit patterns
+    fn from_bits(bits: u8) -> Self;
+}
+
+impl TagValue for u8 {
+    #[inline]
+    fn into_bits(self) -> u8 {
+        self
+    }
+
+    #[inline]
+    fn from_bits(bits: u8) -> Self {
+        bits
+    }
+}
+
+impl TagValue for bool {
+    #[inline]
+    fn into_bits(self) -> u8 {
+        self as u8
+    }
+
+    #[inline]
+    fn from_bits(bits: u8) -> Self {
+        bits != 0
+    }
+}
+
+/// Implement [`TagValue`](crate::mutex::tag_field::TagValue) for fieldless `#[repr(u8)]` enums
+///
+/// List every variant of the enum, `from_bits` panics on bits that don't belong
+/// to any of them.
+///
+/// ```
+/// #[derive(Clone, Copy)]
+/// #[repr(u8)]
+/// enum Color {
+///     Red = 1,
+///     Green = 2,
+/// }
+///
+/// locker::impl_tag_value! { Color { Red, Green } }
+/// ```
+#[macro_export]
+macro_rules! impl_tag_value {
+    ($($type:ident { $($variant:ident),* $(,)? })*) => {$(
+        impl $crate::mutex::tag_field::TagValue for $type {
+            #[inline]
+            fn into_bits(self) -> u8 {
+                self as u8
+            }
+
+            #[inline]
+            fn from_bits(bits: u8) -> Self {
+                $(if bits == $type::$variant as u8 {
+                    return $type::$variant
+                })*
+
+                panic!(concat!("invalid tag bits for `", stringify!($type), "`: {}"), bits)
+            }
+        }
+    )*};
+}
+
+/// A range of bits in a tag, which holds a value of type `Value`
+pub trait TagField {
+    /// The type of value in this field
+    type Value: TagValue;
+
+    /// The index of the lowest bit of this field
+    const OFFSET: u8;
+
+    /// The number of bits in this field
+    const WIDTH: u8;
+
+    /// The largest value that fits in this field
+    const MAX: u8 = (((1_u16 << Self::WIDTH) - 1) & 0xff) as u8;
+
+    /// The bits of the tag which belong to this field
+    const MASK: u8 = Self::MAX << Self::OFFSET;
+}
+
+/// A field of `WIDTH` bits, starting at bit `OFFSET` of the tag
+pub struct Bits<const OFFSET: u8, const WIDTH: u8, V = u8>(PhantomData<fn() -> V>);
+
+impl<const OFFSET: u8, const WIDTH: u8, V: TagValue> TagField for Bits<OFFSET, WIDTH, V> {
+    type Value = V;
+
+    const OFFSET: u8 = OFFSET;
+    const WIDTH: u8 = WIDTH;
+}
+
+struct Fits<F, L: ?Sized>(PhantomData<F>, PhantomData<L>);
+
+impl<F: TagField, L: ?Sized + AtomicTag> Fits<F, L> {
+    const CHECK: () = assert!(
+        F::WIDTH != 0 && F::WIDTH <= 8 && F::OFFSET as u16 + F::WIDTH as u16 <= L::TAG_BITS as u16,
+        "the field doesn't fit in the tag of the lock"
+    );
+}
+
+/// Tagged locks whose tag can be updated atomically, see the [module docs](self) for details
+pub trait AtomicTag {
+    /// The number of bits in the tag
+    const TAG_BITS: u8;
+
+    /// Get the tag with the specified load ordering
+    fn tag(&self, order: Ordering) -> u8;
+
+    /// update the tag with the given function until it returns `None` or succeeds using the specied orderings
+    fn update_tag(
+        &self,
+        success: Ordering,
+        failure: Ordering,
+        f: impl FnMut(u8) -> Option<u8>,
+    ) -> Result<u8, u8>;
+
+    /// Get the value of the field `F` with the specified load ordering
+    #[inline]
+    fn tag_field<F: TagField>(&self, order: Ordering) -> F::Value {
+        #[allow(clippy::let_unit_value)]
+        let () = Fits::<F, Self>::CHECK;
+
+        F::Value::from_bits((self.tag(order) & F::MASK) >> F::OFFSET)
+    }
+
+    /// Set the field `F` to `value` using the specified ordering, without touching
+    /// the rest of the tag
+    ///
+    /// returns the old value of the field
+    ///
+    /// # Panics
+    ///
+    /// If `value` doesn't fit in the field
+    #[inline]
+    fn set_field<F: TagField>(&self, value: F::Value, order: Ordering) -> F::Value {
+        let failure = super::strongest_failure_ordering(order);
+
+        match self.update_field::<F>(order, failure, move |_| Some(value)) {
+            Ok(x) => x,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// update the field `F` with the given function until it returns `None` or succeeds
+    /// using the specied orderings, without touching the rest of the tag
+    ///
+    /// returns the old value of the field
+    ///
+    /// # Panics
+    ///
+    /// If `f` returns a value that doesn't fit in the field
+    fn update_field<F: TagField>(
+        &self,
+        success: Ordering,
+        failure: Ordering,
+        mut f: impl FnMut(F::Value) -> Option<F::Value>,
+    ) -> Result<F::Value, F::Value> {
+        #[allow(clippy::let_unit_value)]
+        let () = Fits::<F, Self>::CHECK;
+
+        let get = |tag: u8| F::Value::from_bits((tag & F::MASK) >> F::OFFSET);
+
+        self.update_tag(success, failure, |tag| {
+            let bits = f(get(tag))?.into_bits();
+            assert!(bits <= F::MAX, "the value doesn't fit in the field");
+            Some((tag & !F::MASK) | (bits << F::OFFSET))
+        })
+        .map(get)
+        .map_err(get)
+    }
+}
+
+macro_rules! atomic_tag {
+    ($($(#[$meta:meta])* $lock:ty;)*) => {$(
+        $(#[$meta])*
+        impl AtomicTag for $lock {
+            const TAG_BITS: u8 = <$lock>::TAG_BITS;
+
+            #[inline]
+            fn tag(&self, order: Ordering) -> u8 {
+                <$lock>::tag(self, order)
+            }
+
+            #[inline]
+            fn update_tag(
+                &self,
+                success: Ordering,
+                failure: Ordering,
+                f: impl FnMut(u8) -> Option<u8>,
+            ) -> Result<u8, u8> {
+                <$lock>::update_tag(self, success, failure, f)
+            }
+        }
+    )*};
+}
+
+atomic_tag! {
+    super::tagged_spin::TaggedSpinLock;
+    super::tagged_default::TaggedDefaultLock;
+    #[cfg(feature = "parking_lot_core")]
+    super::tagged::TaggedLock;
+}
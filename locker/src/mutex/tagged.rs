@@ -1,9 +1,12 @@
 //! a tagged lock
 
 use crate::exclusive_lock::RawExclusiveLock;
+use crate::parking::{
+    FilterOp, ParkResult, ParkToken, UnparkResult, UnparkToken, DEFAULT_PARK_TOKEN,
+};
+use crate::spin_wait::SpinWait;
 use core::sync::atomic::{AtomicU8, Ordering};
-use parking_lot_core::{self, ParkResult, SpinWait, UnparkResult, UnparkToken, DEFAULT_PARK_TOKEN};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 // UnparkToken used to indicate that that the target thread should attempt to
 // state the mutex again as soon as it is unparked.
@@ -13,26 +16,16 @@ const TOKEN_NORMAL: UnparkToken = UnparkToken(0);
 // thread directly without unlocking it.
 const TOKEN_HANDOFF: UnparkToken = UnparkToken(1);
 
+// ParkToken used by threads waiting in `wait_for_tag`, these share the lock's
+// address with threads waiting for the lock, so they must be told apart when unparking
+const TOKEN_TAG_WAITER: ParkToken = ParkToken(1);
+
 /// A tagged raw mutex that can store up to `TAG_BITS` bits in the lower bits of the lock
 pub type RawMutex = crate::mutex::raw::Mutex<TaggedLock>;
 
 /// A tagged mutex that can store up to `TAG_BITS` bits in the lower bits of the lock
 pub type Mutex<T> = crate::mutex::Mutex<TaggedLock, T>;
 
-#[inline]
-fn strongest_failure_ordering(order: Ordering) -> Ordering {
-    use Ordering::*;
-
-    match order {
-        Release => Relaxed,
-        Relaxed => Relaxed,
-        SeqCst => SeqCst,
-        Acquire => Acquire,
-        AcqRel => Acquire,
-        _ => unreachable!(),
-    }
-}
-
 /// A tagged lock that can store up to `TAG_BITS` bits in the lower bits of the lock
 pub struct TaggedLock {
     state: AtomicU8,
@@ -97,7 +90,7 @@ impl TaggedLock {
     ///
     /// returns the old tag
     pub fn swap_tag(&self, tag: u8, order: Ordering) -> u8 {
-        self.exchange_tag(tag, order, strongest_failure_ordering(order))
+        self.exchange_tag(tag, order, super::strongest_failure_ordering(order))
     }
 
     /// swap the tag with the given tag using the specied orderings
@@ -133,6 +126,122 @@ impl TaggedLock {
         Err(state & Self::MASK)
     }
 
+    /// update the tag like [`update_tag`](Self::update_tag), and wake up all threads that are
+    /// waiting in [`wait_for_tag`](Self::wait_for_tag)
+    ///
+    /// If no threads are waiting on this lock, this is as cheap as `update_tag`.
+    /// Threads that wait for a tag are only woken by this function, not by the other
+    /// functions that change the tag.
+    pub fn update_tag_and_notify(
+        &self,
+        success: Ordering,
+        failure: Ordering,
+        mut f: impl FnMut(u8) -> Option<u8>,
+    ) -> Result<u8, u8> {
+        let mut state = self.state.load(failure);
+
+        while let Some(tag) = f(state & Self::MASK) {
+            match self.state.compare_exchange_weak(
+                state,
+                (state & !Self::MASK) | (tag & Self::MASK),
+                success,
+                failure,
+            ) {
+                Err(x) => state = x,
+                Ok(x) => {
+                    if x & Self::PARK_BIT != 0 {
+                        self.notify_tag_slow();
+                    }
+
+                    return Ok(x & Self::MASK);
+                }
+            }
+        }
+
+        Err(state & Self::MASK)
+    }
+
+    /// Block the current thread until the tag satisfies `pred`, or until `timeout` elapses
+    ///
+    /// The tag is loaded with `Acquire` ordering, and the thread is only woken by
+    /// [`update_tag_and_notify`](Self::update_tag_and_notify). This doesn't lock the lock.
+    ///
+    /// Returns `Ok` with the tag that satisfied `pred`, or `Err` with the latest tag
+    /// if the timeout elapsed first.
+    pub fn wait_for_tag(
+        &self,
+        mut pred: impl FnMut(u8) -> bool,
+        timeout: Option<Duration>,
+    ) -> Result<u8, u8> {
+        let timeout = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let mut spinwait = SpinWait::new();
+        let mut state = self.state.load(Ordering::Acquire);
+
+        loop {
+            if pred(state & Self::MASK) {
+                return Ok(state & Self::MASK);
+            }
+
+            // If there is no queue, try spinning a few times
+            if state & Self::PARK_BIT == 0 && spinwait.spin() {
+                state = self.state.load(Ordering::Acquire);
+                continue;
+            }
+
+            // Set the parked bit
+            if state & Self::PARK_BIT == 0 {
+                if let Err(x) = self.state.compare_exchange_weak(
+                    state,
+                    state | Self::PARK_BIT,
+                    Ordering::Relaxed,
+                    Ordering::Acquire,
+                ) {
+                    state = x;
+                    continue;
+                }
+
+                state |= Self::PARK_BIT;
+            }
+
+            // Park our thread until the tag is updated, `pred` was already checked
+            // against `state`, and any change to the tag will change the state
+            let addr = self as *const _ as usize;
+            let validate = || self.state.load(Ordering::Relaxed) == state;
+            let before_sleep = || {};
+            let timed_out = |_, was_last_thread| {
+                // Clear the parked bit if we were the last parked thread
+                if was_last_thread {
+                    self.state.fetch_and(!Self::PARK_BIT, Ordering::Relaxed);
+                }
+            };
+
+            // SAFETY:
+            //   * `addr` is an address we control.
+            //   * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
+            //   * `before_sleep` does not call `park`, nor does it panic.
+            let result = unsafe {
+                crate::parking::park(
+                    addr,
+                    validate,
+                    before_sleep,
+                    timed_out,
+                    TOKEN_TAG_WAITER,
+                    timeout,
+                )
+            };
+
+            state = self.state.load(Ordering::Acquire);
+
+            if let ParkResult::TimedOut = result {
+                let tag = state & Self::MASK;
+                return if pred(tag) { Ok(tag) } else { Err(tag) };
+            }
+
+            // Loop back and check the tag again
+            spinwait.reset();
+        }
+    }
+
     /// Create a new raw tagged mutex
     pub const fn raw_mutex() -> RawMutex {
         unsafe { RawMutex::from_raw(Self::new()) }
@@ -144,8 +253,26 @@ impl TaggedLock {
     }
 }
 
-impl crate::Init for TaggedLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    TaggedLock = Self::new()
+}
+
+/// The tag is not part of the decoded state, use [`TaggedLock::tag`] to read it
+impl crate::raw_state::RawStateBits for TaggedLock {
+    #[inline]
+    fn raw_state_bits(&self) -> usize {
+        self.state.load(Ordering::Relaxed) as usize
+    }
+
+    fn decode_state(bits: usize) -> crate::raw_state::DecodedState {
+        let bits = bits as u8;
+
+        crate::raw_state::DecodedState {
+            exc: (bits & Self::LOCK_BIT != 0) as usize,
+            parked: bits & Self::PARK_BIT != 0,
+            ..Default::default()
+        }
+    }
 }
 
 unsafe impl crate::mutex::RawMutex for TaggedLock {}
@@ -199,13 +326,16 @@ unsafe impl RawExclusiveLock for TaggedLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         let state = self.state.load(Ordering::Relaxed);
 
         debug_assert_ne!(state & Self::LOCK_BIT, 0);
 
         if state & Self::PARK_BIT != 0 {
             self.bump_slow(false);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
@@ -232,13 +362,16 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockFair for TaggedLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         let state = self.state.load(Ordering::Relaxed);
 
         debug_assert_ne!(state & Self::LOCK_BIT, 0);
 
         if state & Self::PARK_BIT != 0 {
             self.bump_slow(true);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
@@ -300,7 +433,7 @@ impl TaggedLock {
             //   * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
             //   * `before_sleep` does not call `park`, nor does it panic.
             match unsafe {
-                parking_lot_core::park(
+                crate::parking::park(
                     addr,
                     validate,
                     before_sleep,
@@ -357,11 +490,52 @@ impl TaggedLock {
             TOKEN_NORMAL
         };
 
+        // Threads waiting for a tag share this address, so skip over them
+        let mut unparked = false;
+        let filter = |token| {
+            if token == TOKEN_TAG_WAITER {
+                FilterOp::Skip
+            } else if unparked {
+                FilterOp::Stop
+            } else {
+                unparked = true;
+                FilterOp::Unpark
+            }
+        };
+
+        // SAFETY:
+        //   * `addr` is an address we control.
+        //   * `filter`/`callback` does not panic or call into any function of `parking_lot`.
+        unsafe {
+            crate::parking::unpark_filter(addr, filter, callback);
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn notify_tag_slow(&self) {
+        // Unpark every thread waiting for a tag, and leave the parked bit set
+        // if threads waiting for the lock are still parked
+        let addr = self as *const _ as usize;
+        let filter = |token| {
+            if token == TOKEN_TAG_WAITER {
+                FilterOp::Unpark
+            } else {
+                FilterOp::Skip
+            }
+        };
+        let callback = |result: UnparkResult| {
+            if !result.have_more_threads {
+                self.state.fetch_and(!Self::PARK_BIT, Ordering::Relaxed);
+            }
+            TOKEN_NORMAL
+        };
+
         // SAFETY:
         //   * `addr` is an address we control.
-        //   * `callback` does not panic or call into any function of `parking_lot`.
+        //   * `filter`/`callback` does not panic or call into any function of `parking_lot`.
         unsafe {
-            parking_lot_core::unpark_one(addr, callback);
+            crate::parking::unpark_filter(addr, filter, callback);
         }
     }
 
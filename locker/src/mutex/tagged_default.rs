@@ -107,8 +107,8 @@ impl TaggedDefaultLock {
     }
 }
 
-impl crate::Init for TaggedDefaultLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    TaggedDefaultLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for TaggedDefaultLock {}
@@ -134,7 +134,7 @@ unsafe impl RawExclusiveLock for TaggedDefaultLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         self.0.exc_bump()
     }
 }
@@ -147,7 +147,7 @@ unsafe impl RawExclusiveLockFair for TaggedDefaultLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         self.0.exc_bump_fair()
     }
 }
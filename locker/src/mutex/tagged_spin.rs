@@ -24,20 +24,6 @@ pub type RawMutex = crate::mutex::raw::Mutex<TaggedSpinLock>;
 /// platforms that can't support adaptive strategys.
 pub type Mutex<T> = crate::mutex::Mutex<TaggedSpinLock, T>;
 
-#[inline]
-fn strongest_failure_ordering(order: Ordering) -> Ordering {
-    use Ordering::*;
-
-    match order {
-        Release => Relaxed,
-        Relaxed => Relaxed,
-        SeqCst => SeqCst,
-        Acquire => Acquire,
-        AcqRel => Acquire,
-        _ => unreachable!(),
-    }
-}
-
 /// A tagged spin lock that can store up to `TAG_BITS` bits in the lower bits of the lock
 ///
 /// It is not reccomended to use this type in libraries,
@@ -108,7 +94,7 @@ impl TaggedSpinLock {
     ///
     /// returns the old tag
     pub fn swap_tag(&self, tag: u8, order: Ordering) -> u8 {
-        self.exchange_tag(tag, order, strongest_failure_ordering(order))
+        self.exchange_tag(tag, order, super::strongest_failure_ordering(order))
     }
 
     /// swap the tag with the given tag using the specied orderings
@@ -155,8 +141,8 @@ impl TaggedSpinLock {
     }
 }
 
-impl crate::Init for TaggedSpinLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    TaggedSpinLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for TaggedSpinLock {}
@@ -206,7 +192,9 @@ unsafe impl RawExclusiveLock for TaggedSpinLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {}
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
 }
 
 impl TaggedSpinLock {
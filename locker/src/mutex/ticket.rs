@@ -0,0 +1,141 @@
+//! a ticket lock
+
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// a raw mutex backed by a ticket lock
+pub type RawMutex = crate::mutex::raw::Mutex<TicketLock>;
+
+/// a mutex backed by a ticket lock
+pub type Mutex<T> = crate::mutex::Mutex<TicketLock, T>;
+
+/// A ticket lock
+///
+/// Each thread that tries to acquire the lock takes a ticket, and the lock is
+/// handed to the threads in the order that they took their tickets. This
+/// guarantees strict FIFO fairness, and bounded waiting, without parking threads.
+///
+/// Because of this, the lock is always unlocked fairly, and `unlock_fair` is the same as `unlock`.
+///
+/// Like the [spin lock](crate::mutex::spin), it is not reccomended to use this type unless you
+/// need these fairness guarantees in environments that can't park threads.
+pub struct TicketLock {
+    next: AtomicUsize,
+    serving: AtomicUsize,
+}
+
+impl TicketLock {
+    /// create a new ticket lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            next: AtomicUsize::new(0),
+            serving: AtomicUsize::new(0),
+        }
+    }
+
+    /// create a new ticket lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new ticket lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+}
+
+crate::impl_init! {
+    TicketLock = Self::new()
+}
+
+unsafe impl crate::mutex::RawMutex for TicketLock {}
+unsafe impl crate::RawLockInfo for TicketLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLock for TicketLock {
+    #[inline]
+    fn exc_lock(&self) {
+        let ticket = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut spin = SpinWait::new();
+
+        while self.serving.load(Ordering::Acquire) != ticket {
+            if !spin.spin() {
+                spin.reset();
+            }
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let serving = self.serving.load(Ordering::Relaxed);
+
+        self.next
+            .compare_exchange(
+                serving,
+                serving.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        // only the owner of the lock can change `serving`
+        let serving = self.serving.load(Ordering::Relaxed);
+        self.serving
+            .store(serving.wrapping_add(1), Ordering::Release);
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        let serving = self.serving.load(Ordering::Relaxed);
+
+        if self.next.load(Ordering::Relaxed) != serving.wrapping_add(1) {
+            self.exc_unlock();
+            self.exc_lock();
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
+        }
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for TicketLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        use crate::exclusive_lock::RawExclusiveLock;
+
+        self.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        use crate::exclusive_lock::RawExclusiveLock;
+
+        self.exc_bump()
+    }
+}
+
+#[test]
+fn ticket_lock() {
+    let mtx = TicketLock::mutex(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    *mtx.lock() += 1;
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    let _lock = mtx.lock();
+    assert!(mtx.try_lock().is_none());
+    assert_eq!(*_lock, 4000);
+}
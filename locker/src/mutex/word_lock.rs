@@ -0,0 +1,317 @@
+//! a word sized mutex that parks threads with `std`, without `parking_lot_core`
+//!
+//! The lock itself is a single word. Threads spin for a short while, and then park
+//! with the [`parking`](crate::parking) facade, which uses [`std::thread::park`]
+//! (or [`std::thread::park_timeout`] for timed acquisition) if `parking_lot_core` isn't enabled.
+//!
+//! Parked threads are queued in the order that they parked in, and unlocking wakes exactly
+//! the thread at the front of that queue, so there is no polling while parked. Unlocking
+//! normally lets the woken thread race with threads that are spinning, but every so often
+//! (and always with `exc_unlock_fair`) the lock is handed off directly to the woken thread,
+//! so a thread can't be starved by threads that keep re-acquiring the lock.
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::parking::{self, ParkResult, UnparkResult, UnparkToken, DEFAULT_PARK_TOKEN};
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// a raw mutex backed by a word lock
+pub type RawMutex = crate::mutex::raw::Mutex<WordLock>;
+/// a mutex backed by a word lock
+pub type Mutex<T> = crate::mutex::Mutex<WordLock, T>;
+
+// the parked thread was woken, and should try to lock again
+const TOKEN_NORMAL: UnparkToken = UnparkToken(0);
+// the lock was handed off to the parked thread without being unlocked
+const TOKEN_HANDOFF: UnparkToken = UnparkToken(1);
+
+/// A word lock
+///
+/// This is the default mutex lock if `std` is enabled, but `parking_lot_core` isn't.
+/// Unlike a spin lock, threads that wait for a long time are parked, so it
+/// supports timed acquisition and fair unlocking.
+pub struct WordLock {
+    state: AtomicUsize,
+}
+
+impl WordLock {
+    const LOCK_BIT: usize = 0b01;
+    const PARK_BIT: usize = 0b10;
+
+    /// create a new word lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+        }
+    }
+
+    /// create a new word lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new word lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    #[inline]
+    fn key(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    #[cold]
+    fn lock_slow(&self, timeout: Option<Instant>) -> bool {
+        let _trace = crate::trace::SlowPath::enter("exc_lock", self);
+        let mut spin = SpinWait::new();
+
+        loop {
+            let state = self.state.load(Ordering::Relaxed);
+
+            if state & Self::LOCK_BIT == 0 {
+                if self.exc_try_lock() {
+                    return true;
+                }
+
+                continue;
+            }
+
+            if state & Self::PARK_BIT == 0 {
+                if spin.spin() {
+                    continue;
+                }
+
+                // set the park bit before parking, so that the unlocking thread unparks us
+                if self
+                    .state
+                    .compare_exchange_weak(
+                        state,
+                        state | Self::PARK_BIT,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    )
+                    .is_err()
+                {
+                    continue;
+                }
+            }
+
+            let validate = || self.state.load(Ordering::Relaxed) == Self::LOCK_BIT | Self::PARK_BIT;
+            let before_sleep = || {};
+            let timed_out = |_, was_last_thread| {
+                // Clear the parked bit if we were the last parked thread
+                if was_last_thread {
+                    self.state.fetch_and(!Self::PARK_BIT, Ordering::Relaxed);
+                }
+            };
+
+            // SAFETY:
+            //   * `key` is an address we control.
+            //   * `validate`/`timed_out` does not panic or call into any function of `parking`.
+            //   * `before_sleep` does not call `park`, nor does it panic.
+            match unsafe {
+                parking::park(
+                    self.key(),
+                    validate,
+                    before_sleep,
+                    timed_out,
+                    DEFAULT_PARK_TOKEN,
+                    timeout,
+                )
+            } {
+                ParkResult::Unparked(TOKEN_HANDOFF) => return true,
+                ParkResult::Unparked(_) | ParkResult::Invalid => (),
+                ParkResult::TimedOut => return false,
+            }
+
+            spin.reset();
+        }
+    }
+
+    #[cold]
+    fn unlock_slow(&self, force_fair: bool) {
+        let callback = |result: UnparkResult| {
+            let park_bit = if result.have_more_threads {
+                Self::PARK_BIT
+            } else {
+                0
+            };
+
+            // the lock is held, and the park bit is only set while it is held, so
+            // nothing else can change the state while the queue is locked
+            if result.unparked_threads != 0 && (force_fair || result.be_fair) {
+                self.state
+                    .store(Self::LOCK_BIT | park_bit, Ordering::Relaxed);
+                TOKEN_HANDOFF
+            } else {
+                self.state.store(park_bit, Ordering::Release);
+                TOKEN_NORMAL
+            }
+        };
+
+        // SAFETY:
+        //   * `key` is an address we control.
+        //   * `callback` does not panic or call into any function of `parking`.
+        let result = unsafe { parking::unpark_one(self.key(), callback) };
+
+        let fair = result.unparked_threads != 0 && (force_fair || result.be_fair);
+        crate::trace::unlock_slow(self, result.unparked_threads, fair);
+    }
+
+    #[cold]
+    fn bump_slow(&self, force_fair: bool) {
+        self.unlock_slow(force_fair);
+        self.exc_lock();
+    }
+}
+
+crate::impl_init! {
+    WordLock = Self::new()
+}
+
+impl crate::raw_state::RawStateBits for WordLock {
+    #[inline]
+    fn raw_state_bits(&self) -> usize {
+        self.state.load(Ordering::Relaxed)
+    }
+
+    fn decode_state(bits: usize) -> crate::raw_state::DecodedState {
+        crate::raw_state::DecodedState {
+            exc: (bits & Self::LOCK_BIT != 0) as usize,
+            parked: bits & Self::PARK_BIT != 0,
+            ..Default::default()
+        }
+    }
+}
+
+unsafe impl crate::mutex::RawMutex for WordLock {}
+unsafe impl crate::RawLockInfo for WordLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl RawExclusiveLock for WordLock {
+    #[inline]
+    fn exc_lock(&self) {
+        if !self.exc_try_lock() {
+            self.lock_slow(None);
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let state = self.state.load(Ordering::Relaxed);
+
+        state & Self::LOCK_BIT == 0
+            && self
+                .state
+                .compare_exchange(
+                    state,
+                    state | Self::LOCK_BIT,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    #[inline]
+    fn exc_lock_mut(&mut self) {
+        let state = self.state.get_mut();
+        assert_eq!(
+            *state & Self::LOCK_BIT,
+            0,
+            "tried to lock a leaked exc lock"
+        );
+        *state |= Self::LOCK_BIT;
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        if self
+            .state
+            .compare_exchange(Self::LOCK_BIT, 0, Ordering::Release, Ordering::Relaxed)
+            .is_err()
+        {
+            self.unlock_slow(false);
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        if self.state.load(Ordering::Relaxed) & Self::PARK_BIT != 0 {
+            self.bump_slow(false);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
+        }
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for WordLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        if self
+            .state
+            .compare_exchange(Self::LOCK_BIT, 0, Ordering::Release, Ordering::Relaxed)
+            .is_err()
+        {
+            self.unlock_slow(true);
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        if self.state.load(Ordering::Relaxed) & Self::PARK_BIT != 0 {
+            self.bump_slow(true);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
+        }
+    }
+}
+
+// unlocking only unparks threads, it never parks
+#[cfg(feature = "parking_lot_core")]
+unsafe impl crate::condvar::Parkable for WordLock {}
+
+impl crate::RawTimedLock for WordLock {
+    type Instant = Instant;
+    type Duration = Duration;
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for WordLock {
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.exc_try_lock() || self.lock_slow(Some(instant))
+    }
+
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.exc_try_lock() || self.lock_slow(Instant::now().checked_add(duration))
+    }
+}
+
+#[test]
+fn timed() {
+    use crate::exclusive_lock::RawExclusiveLockTimed;
+
+    let lock = WordLock::new();
+    lock.exc_lock();
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| assert!(!lock.exc_try_lock_for(Duration::from_millis(10))));
+    })
+    .unwrap();
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| assert!(lock.exc_try_lock_for(Duration::from_secs(10))));
+
+        std::thread::sleep(Duration::from_millis(10));
+        unsafe { lock.exc_unlock() }
+    })
+    .unwrap();
+
+    assert!(!lock.exc_try_lock());
+    unsafe { lock.exc_unlock() }
+    assert_eq!(lock.state.load(Ordering::Relaxed), 0);
+}
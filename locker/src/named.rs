@@ -0,0 +1,290 @@
+//! Attach a name to a lock, so that it can be told apart in diagnostics
+//!
+//! This is enabled by the `names` feature flag. A lock wrapped in [`Named`] reports
+//! its name through [`RawLockInfo::name`], which is shown in [watchdog](crate::watchdog)
+//! reports, the panics of [`ReentrantPanic`](crate::combinators::ReentrantPanic) when a
+//! thread would deadlock on the lock, and the [`BumpStats`](crate::combinators::BumpStats)
+//! of [`Instrumented`](crate::combinators::Instrumented) locks.
+//!
+//! ```
+//! use locker::mutex::{default::DefaultLock, Mutex};
+//!
+//! static CONFIG: Mutex<locker::named::Named<DefaultLock>, u32> = Mutex::with_name("config", 0);
+//!
+//! assert_eq!(CONFIG.name(), Some("config"));
+//! ```
+//!
+//! The other wrappers can only see the name if `Named` is inside of them, for example
+//! `Watched<Named<L>>` or `ReentrantPanic<Named<L>>`.
+
+use crate::exclusive_lock::{
+    RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair, RawExclusiveLockTimed,
+};
+use crate::mutex::RawMutex;
+use crate::remutex::RawReentrantMutex;
+use crate::rwlock::RawRwLock;
+use crate::share_lock::{
+    RawShareLock, RawShareLockFair, RawShareLockTimed, RawShareLockUpgrade,
+    RawShareLockUpgradeTimed,
+};
+use crate::{Init, RawLockInfo, RawTimedLock};
+
+/// Wraps a lock and gives it a name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Named<L: ?Sized> {
+    name: &'static str,
+    lock: L,
+}
+
+impl<L> Named<L> {
+    /// Give `lock` the name `name`
+    #[inline]
+    pub const fn new(name: &'static str, lock: L) -> Self {
+        Self { name, lock }
+    }
+
+    /// The underlying lock
+    #[inline]
+    pub fn into_inner(self) -> L {
+        self.lock
+    }
+}
+
+impl<L: ?Sized> Named<L> {
+    /// The underlying lock
+    #[inline]
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+}
+
+impl<L: RawMutex + Init, T> crate::mutex::Mutex<Named<L>, T> {
+    /// Create a new mutex named `name`
+    #[inline]
+    pub const fn with_name(name: &'static str, value: T) -> Self {
+        unsafe {
+            Self::from_raw_parts(
+                crate::mutex::raw::Mutex::from_raw(Named::new(name, Init::INIT)),
+                value,
+            )
+        }
+    }
+}
+
+impl<L: RawRwLock + Init, T> crate::rwlock::RwLock<Named<L>, T> {
+    /// Create a new rwlock named `name`
+    #[inline]
+    pub const fn with_name(name: &'static str, value: T) -> Self {
+        unsafe {
+            Self::from_raw_parts(
+                crate::rwlock::raw::RwLock::from_raw(Named::new(name, Init::INIT)),
+                value,
+            )
+        }
+    }
+}
+
+impl<L: RawLockInfo, T: ?Sized> crate::mutex::Mutex<L, T> {
+    /// The [name](RawLockInfo::name) of this mutex, if it has one
+    #[inline]
+    pub fn name(&self) -> Option<&'static str> {
+        self.raw().inner().name()
+    }
+}
+
+impl<L: RawLockInfo, T: ?Sized> crate::rwlock::RwLock<L, T> {
+    /// The [name](RawLockInfo::name) of this rwlock, if it has one
+    #[inline]
+    pub fn name(&self) -> Option<&'static str> {
+        self.raw().inner().name()
+    }
+}
+
+unsafe impl<L: RawMutex> RawMutex for Named<L> {}
+unsafe impl<L: RawRwLock> RawRwLock for Named<L> {}
+unsafe impl<L: RawReentrantMutex> RawReentrantMutex for Named<L> {}
+
+unsafe impl<L: RawLockInfo + ?Sized> RawLockInfo for Named<L> {
+    type ExclusiveGuardTraits = <L as RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <L as RawLockInfo>::ShareGuardTraits;
+
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        Some(self.name)
+    }
+}
+
+impl<L: RawTimedLock + ?Sized> RawTimedLock for Named<L> {
+    type Instant = L::Instant;
+    type Duration = L::Duration;
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLock> RawExclusiveLock for Named<L> {
+    #[inline]
+    fn exc_lock(&self) {
+        self.lock.exc_lock()
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        self.lock.exc_try_lock()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.lock.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        self.lock.exc_bump()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLockFair for Named<L> {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        self.lock.exc_unlock_fair()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        self.lock.exc_bump_fair()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockTimed> RawExclusiveLockTimed for Named<L> {
+    #[inline]
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.lock.exc_try_lock_until(instant)
+    }
+
+    #[inline]
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.lock.exc_try_lock_for(duration)
+    }
+
+    #[inline]
+    unsafe fn exc_bump_for(&self, duration: Self::Duration) -> bool {
+        self.lock.exc_bump_for(duration)
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockDowngrade> RawExclusiveLockDowngrade for Named<L> {
+    #[inline]
+    unsafe fn downgrade(&self) {
+        self.lock.downgrade()
+    }
+
+    #[inline]
+    unsafe fn try_downgrade(&self) -> bool {
+        self.lock.try_downgrade()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLock> RawShareLock for Named<L> {
+    #[inline]
+    fn shr_lock(&self) {
+        self.lock.shr_lock()
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        self.lock.shr_try_lock()
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        self.lock.shr_split()
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.lock.shr_try_split()
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        self.lock.shr_unlock()
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        self.lock.shr_bump()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockFair> RawShareLockFair for Named<L> {
+    #[inline]
+    unsafe fn shr_unlock_fair(&self) {
+        self.lock.shr_unlock_fair()
+    }
+
+    #[inline]
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        self.lock.shr_bump_fair()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockTimed> RawShareLockTimed for Named<L> {
+    #[inline]
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.lock.shr_try_lock_until(instant)
+    }
+
+    #[inline]
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.lock.shr_try_lock_for(duration)
+    }
+
+    #[inline]
+    unsafe fn shr_bump_for(&self, duration: Self::Duration) -> bool {
+        self.lock.shr_bump_for(duration)
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockUpgrade> RawShareLockUpgrade for Named<L> {
+    #[inline]
+    unsafe fn upgrade(&self) {
+        self.lock.upgrade()
+    }
+
+    #[inline]
+    unsafe fn try_upgrade(&self) -> bool {
+        self.lock.try_upgrade()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockUpgradeTimed> RawShareLockUpgradeTimed for Named<L> {
+    #[inline]
+    unsafe fn try_upgrade_until(&self, instant: Self::Instant) -> bool {
+        self.lock.try_upgrade_until(instant)
+    }
+
+    #[inline]
+    unsafe fn try_upgrade_for(&self, duration: Self::Duration) -> bool {
+        self.lock.try_upgrade_for(duration)
+    }
+}
+
+#[test]
+fn named() {
+    use crate::combinators::ReentrantPanic;
+    use crate::mutex::local::LocalLock;
+    use crate::remutex::std_thread::StdThreadInfo;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let rwlock =
+        crate::rwlock::RwLock::<Named<crate::rwlock::local::LocalLock>, _>::with_name("local", 0);
+    assert_eq!(rwlock.name(), Some("local"));
+
+    // `ReentrantPanic` names the lock when it panics instead of deadlocking
+    let lock = ReentrantPanic::wrap(Named::new("local", LocalLock::new()), StdThreadInfo);
+    let mutex =
+        crate::mutex::Mutex::from_raw_parts(unsafe { crate::mutex::raw::Mutex::from_raw(lock) }, 0);
+
+    let _guard = mutex.lock();
+    let payload = catch_unwind(AssertUnwindSafe(|| mutex.lock())).unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(message.starts_with("lock `local`: "), "{}", message);
+}
@@ -0,0 +1,57 @@
+//! Locks which are available in every configuration, even without any feature flags
+//!
+//! These only depend on `core`, so they can be used in kernels and on embedded targets.
+//! With no feature flags enabled, the rest of `locker` still provides the lock traits,
+//! the safe lock wrappers, and the spin and local backends in [`mutex`](crate::mutex)
+//! and [`rwlock`](crate::rwlock).
+//!
+//! ```
+//! use locker::nostd::{Lazy, SpinMutex, SpinRwLock};
+//!
+//! static COUNT: SpinMutex<u32> = locker::nostd::spin_mutex(0);
+//! static TABLE: SpinRwLock<[u8; 4]> = locker::nostd::spin_rwlock([1, 2, 3, 4]);
+//!
+//! *COUNT.lock() += 1;
+//! TABLE.write()[0] = 10;
+//!
+//! let sum = Lazy::<u32>::new(|| TABLE.read().iter().map(|&x| u32::from(x)).sum());
+//!
+//! assert_eq!(*COUNT.lock(), 1);
+//! assert_eq!(*sum, 19);
+//! ```
+
+/// a mutex backed by a [spin lock](crate::mutex::spin::SpinLock)
+pub type SpinMutex<T> = crate::mutex::spin::Mutex<T>;
+/// a rwlock backed by a [spin lock](crate::rwlock::spin::SpinLock)
+pub type SpinRwLock<T> = crate::rwlock::spin::RwLock<T>;
+/// a local (single threaded) mutex
+pub type LocalMutex<T> = crate::mutex::local::Mutex<T>;
+/// a local (single threaded) rwlock
+pub type LocalRwLock<T> = crate::rwlock::local::RwLock<T>;
+
+/// a spin-based once
+pub type Once = crate::once::spin::Once;
+/// a spin-based once cell
+pub type OnceCell<T> = crate::once::spin::OnceCell<T>;
+/// a spin-based lazy value
+pub type Lazy<T, F = fn() -> T> = crate::once::spin::Lazy<T, F>;
+
+/// create a new spin mutex in a `const` context
+pub const fn spin_mutex<T>(value: T) -> SpinMutex<T> {
+    crate::mutex::spin::SpinLock::mutex(value)
+}
+
+/// create a new spin rwlock in a `const` context
+pub const fn spin_rwlock<T>(value: T) -> SpinRwLock<T> {
+    crate::rwlock::spin::SpinLock::rwlock(value)
+}
+
+/// create a new local mutex in a `const` context
+pub const fn local_mutex<T>(value: T) -> LocalMutex<T> {
+    crate::mutex::local::LocalLock::mutex(value)
+}
+
+/// create a new local rwlock in a `const` context
+pub const fn local_rwlock<T>(value: T) -> LocalRwLock<T> {
+    crate::rwlock::local::LocalLock::rwlock(value)
+}
@@ -0,0 +1,471 @@
+//! A primitive to notify blocked operations, and a flag built on top of it
+//!
+//! [`Notify`] holds the state shared by all waiters, and is generic over a [`WaitQueue`]
+//! which handles blocking and waking. This way the same logic can be used to block threads
+//! (with [`Park`], via the `parking_lot_core` feature flag) or tasks (by implementing `WaitQueue`
+//! for a set of wakers).
+//!
+//! To build a blocking operation on top of a `Notify`, call [`Notify::start_wait`]
+//! *before* checking the condition you are waiting on, then block in the `WaitQueue` until
+//! [`Notify::is_ready`] returns true, and finish with [`Notify::try_finish_wait`].
+
+use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+
+// set when there is a stored permit
+const PERMIT: usize = 0b1;
+// the increment for the generation, which is bumped by `notify_waiters`
+const GENERATION: usize = 0b10;
+
+/// A queue of blocked operations that a [`Notify`] can wake up
+///
+/// Woken operations will recheck the `Notify` they are waiting on, so
+/// spurious wakeups are allowed.
+pub trait WaitQueue {
+    /// Wake up at least one blocked operation, if there are any
+    fn wake_one(&self);
+
+    /// Wake up all blocked operations
+    fn wake_all(&self);
+}
+
+/// Notify a single blocked operation, or all of them
+///
+/// `Notify` has single permit semantics: [`notify_one`](Notify::notify_one) stores a permit
+/// that is consumed by the next operation to wait on it, even if that operation starts waiting
+/// after the permit was stored. Multiple calls to `notify_one` before a waiter consumes
+/// the permit only store a single permit.
+///
+/// [`notify_waiters`](Notify::notify_waiters) wakes all of the operations that are currently
+/// waiting without storing a permit.
+pub struct Notify<Q> {
+    state: AtomicUsize,
+    queue: Q,
+}
+
+/// A single wait on a [`Notify`], created by [`Notify::start_wait`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Wait {
+    generation: usize,
+}
+
+impl<Q: crate::Init> crate::Init for Notify<Q> {
+    const INIT: Self = Self::with_queue(Q::INIT);
+}
+
+impl<Q: Default> Default for Notify<Q> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_queue(Q::default())
+    }
+}
+
+impl<Q> Notify<Q> {
+    /// Create a new `Notify` without a permit that blocks in the given queue
+    #[inline]
+    pub const fn with_queue(queue: Q) -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            queue,
+        }
+    }
+
+    /// The queue that blocked operations wait in
+    #[inline]
+    pub const fn queue(&self) -> &Q {
+        &self.queue
+    }
+
+    /// Start a wait, this must be called before checking the condition that is waited on,
+    /// so that notifications sent after the check aren't missed
+    #[inline]
+    pub fn start_wait(&self) -> Wait {
+        Wait {
+            generation: self.state.load(Ordering::Acquire) & !PERMIT,
+        }
+    }
+
+    /// Check if the wait can be finished, without consuming a permit
+    #[inline]
+    pub fn is_ready(&self, wait: &Wait) -> bool {
+        let state = self.state.load(Ordering::Acquire);
+
+        state & PERMIT != 0 || state & !PERMIT != wait.generation
+    }
+
+    /// Try to finish the wait, consuming the permit if there is one
+    ///
+    /// Returns true if there was a permit, or if [`notify_waiters`](Notify::notify_waiters)
+    /// was called since the wait was started.
+    #[inline]
+    pub fn try_finish_wait(&self, wait: &Wait) -> bool {
+        let mut state = self.state.load(Ordering::Acquire);
+
+        loop {
+            if state & !PERMIT != wait.generation {
+                return true;
+            }
+
+            if state & PERMIT == 0 {
+                return false;
+            }
+
+            match self.state.compare_exchange_weak(
+                state,
+                state & !PERMIT,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    /// Try to consume the permit without waiting
+    #[inline]
+    pub fn try_acquire(&self) -> bool {
+        self.state.fetch_and(!PERMIT, Ordering::Acquire) & PERMIT != 0
+    }
+}
+
+impl<Q: WaitQueue> Notify<Q> {
+    /// Store a permit, and wake up a blocked operation to consume it
+    #[inline]
+    pub fn notify_one(&self) {
+        self.state.fetch_or(PERMIT, Ordering::Release);
+
+        // make sure that waiters that registered before their last check of the state
+        // are seen by the queue
+        fence(Ordering::SeqCst);
+
+        self.queue.wake_one();
+    }
+
+    /// Wake up all operations that are currently waiting, without storing a permit
+    #[inline]
+    pub fn notify_waiters(&self) {
+        self.state.fetch_add(GENERATION, Ordering::Release);
+
+        fence(Ordering::SeqCst);
+
+        self.queue.wake_all();
+    }
+}
+
+/// A flag that can be set once, and waited on until it is set
+///
+/// This can be used as a latch, for example to wait until some
+/// resource is initialized.
+pub struct OnceFlag<Q> {
+    is_set: AtomicBool,
+    notify: Notify<Q>,
+}
+
+impl<Q: crate::Init> crate::Init for OnceFlag<Q> {
+    const INIT: Self = Self::with_queue(Q::INIT);
+}
+
+impl<Q: Default> Default for OnceFlag<Q> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_queue(Q::default())
+    }
+}
+
+impl<Q> OnceFlag<Q> {
+    /// Create a new unset `OnceFlag` that blocks in the given queue
+    #[inline]
+    pub const fn with_queue(queue: Q) -> Self {
+        Self {
+            is_set: AtomicBool::new(false),
+            notify: Notify::with_queue(queue),
+        }
+    }
+
+    /// Check if the flag is set
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.is_set.load(Ordering::Acquire)
+    }
+
+    /// The `Notify` that is used to wake up operations waiting on the flag
+    #[inline]
+    pub const fn notify(&self) -> &Notify<Q> {
+        &self.notify
+    }
+}
+
+impl<Q: WaitQueue> OnceFlag<Q> {
+    /// Set the flag and wake up all operations waiting on it
+    ///
+    /// Returns false if the flag was already set
+    #[inline]
+    pub fn set(&self) -> bool {
+        if self.is_set.swap(true, Ordering::Release) {
+            false
+        } else {
+            self.notify.notify_waiters();
+            true
+        }
+    }
+}
+
+/// A [`WaitQueue`] for operations that spin until they are ready, instead of blocking
+///
+/// Waking is a no-op, so this is available on every platform
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Spin;
+
+impl crate::Init for Spin {
+    const INIT: Self = Self;
+}
+
+impl WaitQueue for Spin {
+    #[inline]
+    fn wake_one(&self) {}
+
+    #[inline]
+    fn wake_all(&self) {}
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "parking_lot_core")] {
+        use crate::parking::{DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+        use std::time::{Duration, Instant};
+
+        /// A [`WaitQueue`] that parks threads using `parking_lot_core`
+        #[derive(Debug, Default)]
+        pub struct Park {
+            // make sure that each `Park` has a unique address
+            _key: u8,
+        }
+
+        impl Park {
+            /// Create a new `Park` queue
+            #[inline]
+            pub const fn new() -> Self {
+                Self { _key: 0 }
+            }
+
+            #[inline]
+            fn key(&self) -> usize {
+                self as *const Self as usize
+            }
+        }
+
+        impl crate::Init for Park {
+            const INIT: Self = Self::new();
+        }
+
+        impl WaitQueue for Park {
+            #[inline]
+            fn wake_one(&self) {
+                unsafe {
+                    crate::parking::unpark_one(self.key(), |_| DEFAULT_UNPARK_TOKEN);
+                }
+            }
+
+            #[inline]
+            fn wake_all(&self) {
+                unsafe {
+                    crate::parking::unpark_all(self.key(), DEFAULT_UNPARK_TOKEN);
+                }
+            }
+        }
+
+        impl Notify<Park> {
+            /// Create a new `Notify` without a permit
+            #[inline]
+            pub const fn new() -> Self {
+                Self::with_queue(Park::new())
+            }
+
+            /// Block the current thread until it is notified
+            #[inline]
+            pub fn wait(&self) {
+                self.park(self.start_wait(), None);
+            }
+
+            /// Block the current thread until it is notified, or the timeout is reached
+            ///
+            /// Returns false if the timeout was reached
+            #[inline]
+            pub fn wait_until(&self, timeout: Instant) -> bool {
+                self.park(self.start_wait(), Some(timeout))
+            }
+
+            /// Block the current thread until it is notified, or the timeout is reached
+            ///
+            /// Returns false if the timeout was reached
+            #[inline]
+            pub fn wait_for(&self, duration: Duration) -> bool {
+                self.park(self.start_wait(), Instant::now().checked_add(duration))
+            }
+
+            pub(crate) fn park(&self, wait: Wait, timeout: Option<Instant>) -> bool {
+                let mut spin = crate::spin_wait::SpinWait::new();
+
+                loop {
+                    if self.try_finish_wait(&wait) {
+                        return true;
+                    }
+
+                    if spin.spin() {
+                        continue;
+                    }
+
+                    let validate = || !self.is_ready(&wait);
+                    let before_sleep = || {};
+                    let timed_out = |_, _| {};
+
+                    // SAFETY:
+                    // * `key` is an address we control.
+                    // * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
+                    // * `before_sleep` does not call `park`, nor does it panic.
+                    let park_result = unsafe {
+                        crate::parking::park(
+                            self.queue.key(),
+                            validate,
+                            before_sleep,
+                            timed_out,
+                            DEFAULT_PARK_TOKEN,
+                            timeout,
+                        )
+                    };
+
+                    if let crate::parking::ParkResult::TimedOut = park_result {
+                        return self.try_finish_wait(&wait);
+                    }
+                }
+            }
+        }
+
+        impl OnceFlag<Park> {
+            /// Create a new unset `OnceFlag`
+            #[inline]
+            pub const fn new() -> Self {
+                Self::with_queue(Park::new())
+            }
+
+            /// Block the current thread until the flag is set
+            #[inline]
+            pub fn wait(&self) {
+                self.park(None);
+            }
+
+            /// Block the current thread until the flag is set, or the timeout is reached
+            ///
+            /// Returns false if the timeout was reached
+            #[inline]
+            pub fn wait_until(&self, timeout: Instant) -> bool {
+                self.park(Some(timeout))
+            }
+
+            /// Block the current thread until the flag is set, or the timeout is reached
+            ///
+            /// Returns false if the timeout was reached
+            #[inline]
+            pub fn wait_for(&self, duration: Duration) -> bool {
+                self.park(Instant::now().checked_add(duration))
+            }
+
+            fn park(&self, timeout: Option<Instant>) -> bool {
+                loop {
+                    let wait = self.notify.start_wait();
+
+                    if self.is_set() {
+                        return true;
+                    }
+
+                    if !self.notify.park(wait, timeout) {
+                        return self.is_set();
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn notify() {
+            let notify = Notify::new();
+
+            // permits are stored until they are consumed
+            notify.notify_one();
+            notify.notify_one();
+            assert!(notify.wait_for(Duration::from_millis(1)));
+            assert!(!notify.wait_for(Duration::from_millis(1)));
+
+            // `notify_waiters` doesn't store a permit
+            notify.notify_waiters();
+            assert!(!notify.try_acquire());
+
+            let started = AtomicUsize::new(0);
+
+            crossbeam_utils::thread::scope(|s| {
+                let waiters = (0..4)
+                    .map(|_| {
+                        s.spawn(|_| {
+                            // `notify_waiters` only wakes waits that already started
+                            let wait = notify.start_wait();
+                            started.fetch_add(1, Ordering::SeqCst);
+                            notify.park(wait, None);
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                while started.load(Ordering::SeqCst) != 4 {
+                    std::thread::yield_now();
+                }
+
+                notify.notify_waiters();
+
+                for waiter in waiters {
+                    waiter.join().unwrap();
+                }
+
+                // the permit is stored if the waiter didn't start waiting yet
+                let waiter = s.spawn(|_| notify.wait());
+                notify.notify_one();
+                waiter.join().unwrap();
+            })
+            .unwrap();
+
+            assert!(!notify.try_acquire());
+        }
+
+        #[test]
+        fn once_flag() {
+            let flag = OnceFlag::new();
+
+            assert!(!flag.wait_for(Duration::from_millis(1)));
+
+            let started = AtomicUsize::new(0);
+
+            crossbeam_utils::thread::scope(|s| {
+                let waiters = (0..4)
+                    .map(|_| {
+                        s.spawn(|_| {
+                            started.fetch_add(1, Ordering::SeqCst);
+                            flag.wait();
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                // the flag stays set, so waiters that start waiting late still return
+                while started.load(Ordering::SeqCst) != 4 {
+                    std::thread::yield_now();
+                }
+
+                assert!(flag.set());
+                assert!(!flag.set());
+
+                for waiter in waiters {
+                    waiter.join().unwrap();
+                }
+            })
+            .unwrap();
+
+            assert!(flag.is_set());
+            flag.wait();
+        }
+    }
+}
@@ -1,16 +1,34 @@
 use crate::exclusive_lock::RawExclusiveLock;
 use crate::RawLockInfo;
 
-use core::cell::UnsafeCell;
+use crate::cell::SharedCell;
 use core::marker::PhantomData;
 use core::mem::MaybeUninit;
 
 use core::ops::{Deref, DerefMut};
 
-#[cfg(feature = "parking_lot_core")]
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod boxed;
+pub mod default;
+#[cfg(feature = "extra")]
 pub mod local;
-#[cfg(feature = "parking_lot_core")]
+#[cfg(feature = "std")]
+pub mod park;
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
 pub mod simple;
+pub mod spin;
+
+mod poison;
+#[cfg(feature = "std")]
+mod subscribe;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use boxed::OnceBoxCell;
+use poison::CauseSlot;
+#[cfg(feature = "std")]
+pub use poison::{OnceError, PoisonCause};
+#[cfg(feature = "std")]
+pub use subscribe::Subscription;
 
 pub trait AsRawExclusiveLock {
     fn as_raw_exclusive_lock(&self) -> &dyn RawExclusiveLock;
@@ -34,6 +52,7 @@ pub unsafe trait Finish: RawExclusiveLock + AsRawExclusiveLock {
 
 pub struct Once<L> {
     lock: L,
+    cause: CauseSlot,
 }
 
 #[cfg(feature = "std")]
@@ -56,29 +75,79 @@ impl<L> Once<L> {
     /// * `lock` must not be shared, and must be freshly created
     #[inline]
     pub const unsafe fn from_raw(lock: L) -> Self {
-        Self { lock }
+        Self {
+            lock,
+            cause: CauseSlot::new(),
+        }
     }
 }
 
-pub struct OnceState(bool);
+/// The error returned by [`OnceCell::get_or_try_init`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryInitError<E> {
+    /// The cell was poisoned by an earlier initialization, so the initializer wasn't run
+    Poisoned,
+    /// The initializer returned an error, and the cell was left uninitialized
+    Init(E),
+}
+
+pub struct OnceState {
+    is_poisoned: bool,
+    #[cfg(feature = "std")]
+    cause: poison::Cause,
+}
 
 impl OnceState {
+    /// # Safety
+    ///
+    /// the caller must own the *exc lock* of the `Once` that `cause` belongs to
+    #[inline]
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    unsafe fn new(is_poisoned: bool, cause: &CauseSlot) -> Self {
+        Self {
+            is_poisoned,
+            #[cfg(feature = "std")]
+            cause: if is_poisoned {
+                cause.get()
+            } else {
+                Default::default()
+            },
+        }
+    }
+
     #[inline]
     pub const fn is_poisoned(&self) -> bool {
-        self.0
+        self.is_poisoned
+    }
+
+    /// Why the `Once` was poisoned, if it was poisoned
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn poison_cause(&self) -> Option<&PoisonCause> {
+        self.cause.as_ref()
     }
 }
 
 #[inline(always)]
 fn panic_on_poison(f: impl FnOnce()) -> impl FnOnce(&OnceState) {
     #[cold]
-    fn handle_poison() {
+    #[cfg(feature = "std")]
+    fn handle_poison(once_state: &OnceState) {
+        match once_state.poison_cause() {
+            Some(cause) => panic!("tried to call `call_once*` on a poisoned `Once`: {}", cause),
+            None => panic!("tried to call `call_once*` on a poisoned `Once`"),
+        }
+    }
+
+    #[cold]
+    #[cfg(not(feature = "std"))]
+    fn handle_poison(_: &OnceState) {
         panic!("tried to call `call_once*` on a poisoned `Once`");
     }
 
     move |once_state| {
         if once_state.is_poisoned() {
-            handle_poison()
+            handle_poison(once_state)
         }
 
         f()
@@ -87,28 +156,26 @@ fn panic_on_poison(f: impl FnOnce()) -> impl FnOnce(&OnceState) {
 
 #[cold]
 #[inline(never)]
-fn run_once_unchecked<F: ?Sized + Finish>(lock: &F, f: impl FnOnce(&OnceState)) {
-    struct Poison<'a, F: ?Sized + Finish>(&'a F);
-
-    impl<F: ?Sized + Finish> Drop for Poison<'_, F> {
-        fn drop(&mut self) {
-            self.0.mark_poisoned();
-        }
+fn run_once_unchecked<F: ?Sized + Finish>(lock: &F, cause: &CauseSlot, f: impl FnOnce(&OnceState)) {
+    unsafe {
+        let once_state = OnceState::new(lock.is_poisoned(), cause);
+        cause.catch(|| f(&once_state), || lock.mark_poisoned());
     }
 
-    let is_poisoned = lock.is_poisoned();
-    let poison = Poison(lock);
-
-    f(&OnceState(is_poisoned));
-
-    core::mem::forget(poison);
-
     lock.mark_done();
+    notify_done(lock);
+}
+
+/// Wake up the subscribers of a `Once` that was just marked done
+#[inline]
+fn notify_done<F: ?Sized>(_lock: &F) {
+    #[cfg(feature = "std")]
+    subscribe::notify(_lock as *const F as *const () as usize);
 }
 
 #[cold]
 #[inline(never)]
-fn force_call_once_slow(lock: &dyn Finish, f: &mut dyn FnMut(&OnceState)) {
+fn force_call_once_slow(lock: &dyn Finish, cause: &CauseSlot, f: &mut dyn FnMut(&OnceState)) {
     struct LocalGuard<'a>(&'a dyn RawExclusiveLock);
 
     impl Drop for LocalGuard<'_> {
@@ -121,7 +188,7 @@ fn force_call_once_slow(lock: &dyn Finish, f: &mut dyn FnMut(&OnceState)) {
     let _guard = LocalGuard(lock.as_raw_exclusive_lock());
 
     if !lock.is_done() {
-        run_once_unchecked(lock, f)
+        run_once_unchecked(lock, cause, f)
     }
 }
 
@@ -143,21 +210,225 @@ impl<L: Finish> Once<L> {
 
             let mut f = move |once_state: &OnceState| f.take().unwrap()(once_state);
 
-            force_call_once_slow(&self.lock, &mut f);
+            force_call_once_slow(&self.lock, &self.cause, &mut f);
         }
     }
 
     #[inline]
     pub fn force_call_once_mut(&mut self, f: impl FnOnce(&OnceState)) {
         if !self.lock.is_done() {
-            run_once_unchecked(&self.lock, f);
+            run_once_unchecked(&self.lock, &self.cause, f);
+        }
+    }
+
+    /// Start initializing this `Once`, blocking until no other thread is initializing it
+    ///
+    /// Returns `None` if the `Once` is already done. Otherwise returns a token that
+    /// must be completed to finish initialization. If the token is dropped without being
+    /// completed, then the `Once` will be poisoned.
+    ///
+    /// This is useful if initialization spans FFI callbacks or suspension points
+    /// where a closure-based `call_once` cannot be used.
+    #[inline]
+    pub fn begin_init(&self) -> Option<InitToken<'_, L>> {
+        if self.lock.is_done() {
+            return None;
+        }
+
+        self.lock.exc_lock();
+
+        unsafe { InitToken::new(self, core::ptr::NonNull::dangling().as_ptr()) }
+    }
+
+    /// Try to start initializing this `Once` without blocking
+    ///
+    /// Returns `None` if the `Once` is already done, or if another thread is initializing it.
+    /// See [`Once::begin_init`] for details
+    #[inline]
+    pub fn try_begin_init(&self) -> Option<InitToken<'_, L>> {
+        if self.lock.is_done() || !self.lock.exc_try_lock() {
+            return None;
+        }
+
+        unsafe { InitToken::new(self, core::ptr::NonNull::dangling().as_ptr()) }
+    }
+
+    /// Call `f` if this `Once` hasn't completed yet
+    ///
+    /// Unlike [`Once::call_once`], if the `Once` is poisoned then `f` isn't called,
+    /// and the cause of the poison is returned instead of panicking.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub fn try_call_once(&self, f: impl FnOnce()) -> Result<(), OnceError> {
+        match self.begin_init() {
+            None => Ok(()),
+            Some(token) => {
+                let token = token.check_poison()?;
+                let (token, ()) = token.catch(f);
+                token.complete(());
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<L: Finish + crate::Init> Once<L> {
+    /// Clear the poison from a `Once` whose initialization failed, so that it can be
+    /// initialized again
+    ///
+    /// This is safe because `&mut` guarantees that no other thread is using the `Once`.
+    /// This does nothing if the `Once` is not poisoned, or if it has already completed.
+    #[inline]
+    pub fn reset_poison(&mut self) {
+        if self.lock.is_poisoned() && !self.lock.is_done() {
+            *self = crate::Init::INIT;
+        }
+    }
+}
+
+/// A token representing an in-progress initialization of a [`Once`] or [`OnceCell`]
+///
+/// Created by `begin_init` or `try_begin_init`, if this token is dropped without
+/// calling [`InitToken::complete`], then the `Once` will be poisoned.
+#[must_use = "if unused the `Once` will immediately be poisoned"]
+pub struct InitToken<'a, L: Finish, T = ()> {
+    lock: &'a L,
+    cause: &'a CauseSlot,
+    slot: *mut T,
+    is_poisoned: bool,
+}
+
+// the token may finish initialization on another thread, which needs the lock to allow
+// unlocking from that thread, and the value to be sent back to threads reading the `Once`
+unsafe impl<L: Finish + RawLockInfo + Sync, T: Send> Send for InitToken<'_, L, T> where
+    L::ExclusiveGuardTraits: Send
+{
+}
+
+impl<L: Finish, T> Drop for InitToken<'_, L, T> {
+    fn drop(&mut self) {
+        unsafe {
+            // keep the cause of an earlier poison, dropping a token that was
+            // retrying initialization doesn't tell us anything new
+            if !self.is_poisoned {
+                self.cause.set_current();
+            }
+
+            self.lock.mark_poisoned();
+            self.lock.exc_unlock()
+        }
+    }
+}
+
+impl<'a, L: Finish, T> InitToken<'a, L, T> {
+    /// # Safety
+    ///
+    /// * the caller must own the *exc lock* of `once`
+    /// * `slot` must be valid for writes for the lifetime `'a`
+    #[inline]
+    unsafe fn new(once: &'a Once<L>, slot: *mut T) -> Option<Self> {
+        let lock = &once.lock;
+
+        if lock.is_done() {
+            lock.exc_unlock();
+            None
+        } else {
+            Some(Self {
+                lock,
+                cause: &once.cause,
+                slot,
+                is_poisoned: lock.is_poisoned(),
+            })
+        }
+    }
+
+    /// The state of the `Once` before initialization began
+    #[inline]
+    pub fn state(&self) -> OnceState {
+        unsafe { OnceState::new(self.is_poisoned, self.cause) }
+    }
+
+    /// Run `f`, and poison the `Once` with the panic payload if `f` panics
+    #[inline]
+    #[cfg(feature = "std")]
+    fn catch<R>(self, f: impl FnOnce() -> R) -> (Self, R) {
+        let token = core::mem::ManuallyDrop::new(self);
+
+        let value = unsafe {
+            token.cause.catch(f, || {
+                token.lock.mark_poisoned();
+                token.lock.exc_unlock()
+            })
+        };
+
+        (core::mem::ManuallyDrop::into_inner(token), value)
+    }
+
+    /// Cancel initialization and return the cause if the `Once` is poisoned
+    #[inline]
+    #[cfg(feature = "std")]
+    fn check_poison(self) -> Result<Self, OnceError> {
+        match self.state().cause {
+            Some(cause) => {
+                self.cancel();
+                Err(OnceError::Poisoned(cause))
+            }
+            None => Ok(self),
+        }
+    }
+
+    /// Finish initialization with the given value
+    #[inline]
+    pub fn complete(self, value: T) -> &'a T {
+        let token = core::mem::ManuallyDrop::new(self);
+
+        unsafe {
+            token.slot.write(value);
+            token.lock.mark_done();
+            notify_done(token.lock);
+            token.lock.exc_unlock();
+            &*token.slot
+        }
+    }
+
+    /// Abandon initialization, and poison the `Once`
+    #[inline]
+    pub fn poison(self) {
+        drop(self)
+    }
+
+    /// Abandon initialization, and poison the `Once` with the given error
+    ///
+    /// Later callers will see the error as the cause of the poison.
+    #[cfg(feature = "std")]
+    pub fn poison_with<E: std::error::Error + Send + Sync + 'static>(self, error: E) -> OnceError {
+        let token = core::mem::ManuallyDrop::new(self);
+        let cause = PoisonCause::Error(std::sync::Arc::new(error));
+
+        unsafe {
+            token.cause.set(cause.clone());
+            token.lock.mark_poisoned();
+            token.lock.exc_unlock();
         }
+
+        OnceError::Poisoned(cause)
+    }
+
+    /// Abandon initialization without poisoning the `Once`
+    ///
+    /// The `Once` is left in the same state it was in before initialization began,
+    /// so another thread may try to initialize it.
+    #[inline]
+    pub fn cancel(self) {
+        let token = core::mem::ManuallyDrop::new(self);
+
+        unsafe { token.lock.exc_unlock() }
     }
 }
 
 pub struct OnceCell<L: Finish, T> {
     once: Once<L>,
-    value: UnsafeCell<MaybeUninit<T>>,
+    value: SharedCell<MaybeUninit<T>>,
 }
 
 unsafe impl<L: Finish, T: Send + Sync> Sync for OnceCell<L, T> where Once<L>: Sync {}
@@ -165,7 +436,7 @@ unsafe impl<L: Finish, T: Send + Sync> Sync for OnceCell<L, T> where Once<L>: Sy
 impl<L: Finish, T> Drop for OnceCell<L, T> {
     fn drop(&mut self) {
         if core::mem::needs_drop::<T>() && self.once.lock.is_done() {
-            unsafe { self.value.get().cast::<T>().drop_in_place() }
+            unsafe { self.value.as_ptr().cast::<T>().drop_in_place() }
         }
     }
 }
@@ -175,7 +446,7 @@ impl<L: Finish + crate::Init, T> Default for OnceCell<L, T> {
     fn default() -> Self {
         Self {
             once: crate::Init::INIT,
-            value: UnsafeCell::new(MaybeUninit::uninit()),
+            value: SharedCell::new(MaybeUninit::uninit()),
         }
     }
 }
@@ -183,7 +454,7 @@ impl<L: Finish + crate::Init, T> Default for OnceCell<L, T> {
 impl<L: Finish + crate::Init, T> crate::Init for OnceCell<L, T> {
     const INIT: Self = Self {
         once: crate::Init::INIT,
-        value: UnsafeCell::new(MaybeUninit::uninit()),
+        value: SharedCell::new(MaybeUninit::uninit()),
     };
 }
 
@@ -214,12 +485,23 @@ impl<L: Finish, T> OnceCell<L, T> {
         }
     }
 
+    /// Check if the cell is initialized, without blocking or initializing it
+    ///
+    /// To be notified when the cell is initialized, see [`OnceCell::subscribe`]
+    #[inline]
+    pub fn poll_initialized(&self) -> core::task::Poll<&T> {
+        match self.get() {
+            Some(value) => core::task::Poll::Ready(value),
+            None => core::task::Poll::Pending,
+        }
+    }
+
     /// # Safety
     ///
     /// The `OnceCell` must have be initialized
     #[inline]
     pub unsafe fn get_unchecked(&self) -> &T {
-        &*self.value.get().cast::<T>()
+        &*self.value.as_ptr().cast::<T>()
     }
 
     /// # Safety
@@ -227,12 +509,12 @@ impl<L: Finish, T> OnceCell<L, T> {
     /// The `OnceCell` must have be initialized
     #[inline]
     pub unsafe fn get_unchecked_mut(&mut self) -> &mut T {
-        &mut *self.value.get().cast::<T>()
+        &mut *self.value.get_mut().as_mut_ptr()
     }
 
     #[inline]
     pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
-        let ptr = self.value.get().cast::<T>();
+        let ptr = self.value.as_ptr().cast::<T>();
 
         self.once
             .force_call_once(move |_once_state| unsafe { ptr.write(f()) });
@@ -242,22 +524,103 @@ impl<L: Finish, T> OnceCell<L, T> {
 
     #[inline]
     pub fn get_or_init_mut(&mut self, f: impl FnOnce() -> T) -> &mut T {
-        let ptr = self.value.get().cast::<T>();
+        let ptr = self.value.as_ptr().cast::<T>();
 
         if !self.once.lock.is_done() {
             let value = f();
 
-            run_once_unchecked(&self.once.lock, move |_once_state| unsafe {
-                ptr.write(value)
-            });
+            run_once_unchecked(
+                &self.once.lock,
+                &self.once.cause,
+                move |_once_state| unsafe { ptr.write(value) },
+            );
         }
 
         unsafe { &mut *ptr }
     }
 
+    /// Start initializing this `OnceCell`, blocking until no other thread is initializing it
+    ///
+    /// See [`Once::begin_init`] for details
+    #[inline]
+    pub fn begin_init(&self) -> Option<InitToken<'_, L, T>> {
+        if self.once.lock.is_done() {
+            return None;
+        }
+
+        self.once.lock.exc_lock();
+
+        unsafe { InitToken::new(&self.once, self.value.as_ptr().cast::<T>()) }
+    }
+
+    /// Try to start initializing this `OnceCell` without blocking
+    ///
+    /// See [`Once::try_begin_init`] for details
+    #[inline]
+    pub fn try_begin_init(&self) -> Option<InitToken<'_, L, T>> {
+        if self.once.lock.is_done() || !self.once.lock.exc_try_lock() {
+            return None;
+        }
+
+        unsafe { InitToken::new(&self.once, self.value.as_ptr().cast::<T>()) }
+    }
+
+    /// Gets the contents of the cell, initializing it with `f` if the cell was uninitialized
+    ///
+    /// If `f` returns an error, then the error is returned and the cell is left
+    /// uninitialized, without being poisoned. If `f` panics, then the cell is poisoned,
+    /// and later calls return [`TryInitError::Poisoned`] without calling `f`.
+    #[inline]
+    pub fn get_or_try_init<E>(
+        &self,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<&T, TryInitError<E>> {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+
+        match self.begin_init() {
+            None => Ok(unsafe { self.get_unchecked() }),
+            Some(token) if token.state().is_poisoned() => {
+                token.cancel();
+                Err(TryInitError::Poisoned)
+            }
+            Some(token) => match f() {
+                Ok(value) => Ok(token.complete(value)),
+                Err(err) => {
+                    token.cancel();
+                    Err(TryInitError::Init(err))
+                }
+            },
+        }
+    }
+
+    /// Gets the contents of the cell, initializing it with `f` if the cell was uninitialized
+    ///
+    /// Unlike [`OnceCell::get_or_try_init`], if `f` returns an error then the cell is
+    /// poisoned with that error. If the cell is poisoned, `f` isn't called, and the cause
+    /// of the poison is returned.
+    #[cfg(feature = "std")]
+    pub fn get_or_try_init_poison<E: std::error::Error + Send + Sync + 'static>(
+        &self,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<&T, OnceError> {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+
+        match self.begin_init() {
+            None => Ok(unsafe { self.get_unchecked() }),
+            Some(token) => match token.check_poison()?.catch(f) {
+                (token, Ok(value)) => Ok(token.complete(value)),
+                (token, Err(error)) => Err(token.poison_with(error)),
+            },
+        }
+    }
+
     #[inline]
     pub fn get_or_init_racy(&self, f: impl FnOnce() -> T) -> &T {
-        let ptr = self.value.get().cast::<T>();
+        let ptr = self.value.as_ptr().cast::<T>();
 
         if !self.once.lock.is_done() {
             let value = f();
@@ -270,6 +633,24 @@ impl<L: Finish, T> OnceCell<L, T> {
     }
 }
 
+impl<L: Finish + crate::Init, T> OnceCell<L, T> {
+    /// Take the value out of the cell, and reset it to an uninitialized and unpoisoned state
+    ///
+    /// This is safe because `&mut` guarantees that no other thread is using the `OnceCell`.
+    #[inline]
+    pub fn reset(&mut self) -> Option<T> {
+        let value = if self.once.lock.is_done() {
+            unsafe { Some(self.value.as_ptr().cast::<T>().read()) }
+        } else {
+            None
+        };
+
+        self.once = crate::Init::INIT;
+
+        value
+    }
+}
+
 enum LazyInner<F, T> {
     Func(F),
     Value(T),
@@ -281,7 +662,7 @@ pub enum Retry {}
 
 pub struct Lazy<L, T, F, S> {
     once: Once<L>,
-    inner: UnsafeCell<LazyInner<F, T>>,
+    inner: SharedCell<LazyInner<F, T>>,
     strategy: PhantomData<S>,
 }
 
@@ -328,7 +709,7 @@ impl<L, F, T, S> Lazy<L, T, F, S> {
         Self {
             once,
             strategy: PhantomData,
-            inner: UnsafeCell::new(LazyInner::Func(func)),
+            inner: SharedCell::new(LazyInner::Func(func)),
         }
     }
 
@@ -338,7 +719,7 @@ impl<L, F, T, S> Lazy<L, T, F, S> {
     #[inline]
     #[allow(unreachable_code)]
     pub unsafe fn get_unchecked(this: &Self) -> &T {
-        if let LazyInner::Value(ref value) = *this.inner.get() {
+        if let LazyInner::Value(ref value) = *this.inner.as_ptr() {
             value
         } else {
             #[cfg(debug_assertions)]
@@ -353,7 +734,7 @@ impl<L, F, T, S> Lazy<L, T, F, S> {
     #[inline]
     #[allow(unreachable_code)]
     pub unsafe fn get_unchecked_mut(this: &mut Self) -> &mut T {
-        if let LazyInner::Value(ref mut value) = *this.inner.get() {
+        if let LazyInner::Value(ref mut value) = *this.inner.get_mut() {
             value
         } else {
             #[cfg(debug_assertions)]
@@ -366,7 +747,7 @@ impl<L, F, T, S> Lazy<L, T, F, S> {
 impl<L: Finish, F: FnOnce() -> T, T> Lazy<L, T, F, Panic> {
     #[inline]
     pub fn force(this: &Self) -> &T {
-        let inner = this.inner.get();
+        let inner = this.inner.as_ptr();
 
         this.once.call_once(move || {
             let inner = unsafe { &mut *inner };
@@ -382,7 +763,7 @@ impl<L: Finish, F: FnOnce() -> T, T> Lazy<L, T, F, Panic> {
 
     #[inline]
     pub fn force_mut(this: &mut Self) -> &mut T {
-        let inner = this.inner.get();
+        let inner = this.inner.as_ptr();
 
         this.once.call_once(move || {
             let inner = unsafe { &mut *inner };
@@ -400,7 +781,7 @@ impl<L: Finish, F: FnOnce() -> T, T> Lazy<L, T, F, Panic> {
 impl<L: Finish, F: FnMut(&OnceState) -> T, T> Lazy<L, T, F, Retry> {
     #[inline]
     pub fn force(this: &Self) -> &T {
-        let inner = this.inner.get();
+        let inner = this.inner.as_ptr();
 
         this.once.force_call_once(move |once_state| {
             let inner = unsafe { &mut *inner };
@@ -415,7 +796,7 @@ impl<L: Finish, F: FnMut(&OnceState) -> T, T> Lazy<L, T, F, Retry> {
 
     #[inline]
     pub fn force_mut(this: &mut Self) -> &mut T {
-        let inner = this.inner.get();
+        let inner = this.inner.as_ptr();
 
         this.once.force_call_once_mut(move |once_state| {
             let inner = unsafe { &mut *inner };
@@ -0,0 +1,106 @@
+//! A once cell for unsized values
+
+use super::{Finish, OnceCell, TryInitError};
+use std::boxed::Box;
+
+/// A [`OnceCell`] that stores its value in a [`Box`], so it can hold unsized values like
+/// trait objects and slices
+///
+/// ```
+/// use locker::once::OnceBoxCell;
+/// use locker::once::default::RawLock;
+/// use std::fmt::Debug;
+///
+/// let cell = OnceBoxCell::<RawLock, dyn Debug>::default();
+/// assert!(cell.get().is_none());
+///
+/// let value = cell.get_or_init_boxed(|| Box::new(10));
+/// assert_eq!(format!("{:?}", value), "10");
+///
+/// let value = cell.get_or_init_boxed(|| Box::new("unused"));
+/// assert_eq!(format!("{:?}", value), "10");
+/// ```
+pub struct OnceBoxCell<L: Finish, T: ?Sized> {
+    cell: OnceCell<L, Box<T>>,
+}
+
+impl<L: Finish + crate::Init, T: ?Sized> Default for OnceBoxCell<L, T> {
+    #[inline]
+    fn default() -> Self {
+        crate::Init::INIT
+    }
+}
+
+impl<L: Finish + crate::Init, T: ?Sized> crate::Init for OnceBoxCell<L, T> {
+    const INIT: Self = Self {
+        cell: crate::Init::INIT,
+    };
+}
+
+impl<L: Finish, T: ?Sized> OnceBoxCell<L, T> {
+    /// Get the value, if the cell was initialized
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.cell.get().map(|value| &**value)
+    }
+
+    /// Get the value, if the cell was initialized
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.cell.get_mut().map(|value| &mut **value)
+    }
+
+    /// Gets the contents of the cell, initializing it with `f` if the cell was uninitialized
+    ///
+    /// If another thread is initializing the cell, this blocks until it is done
+    #[inline]
+    pub fn get_or_init_boxed(&self, f: impl FnOnce() -> Box<T>) -> &T {
+        self.cell.get_or_init(f)
+    }
+
+    /// Gets the contents of the cell, initializing it with `f` if the cell was uninitialized
+    ///
+    /// See [`OnceCell::get_or_try_init`] for how errors and poisoning are handled
+    #[inline]
+    pub fn get_or_try_init_boxed<E>(
+        &self,
+        f: impl FnOnce() -> Result<Box<T>, E>,
+    ) -> Result<&T, TryInitError<E>> {
+        self.cell.get_or_try_init(f).map(|value| &**value)
+    }
+
+    /// The underlying `OnceCell`
+    #[inline]
+    pub fn as_once_cell(&self) -> &OnceCell<L, Box<T>> {
+        &self.cell
+    }
+}
+
+impl<L: Finish + crate::Init, T: ?Sized> OnceBoxCell<L, T> {
+    /// Take the value out of the cell, and reset it to an uninitialized and unpoisoned state
+    #[inline]
+    pub fn reset(&mut self) -> Option<Box<T>> {
+        self.cell.reset()
+    }
+}
+
+impl<L: Finish, T: ?Sized> From<OnceCell<L, Box<T>>> for OnceBoxCell<L, T> {
+    #[inline]
+    fn from(cell: OnceCell<L, Box<T>>) -> Self {
+        Self { cell }
+    }
+}
+
+#[test]
+fn slice() {
+    let mut cell = OnceBoxCell::<super::spin::RawLock, [u32]>::default();
+
+    assert_eq!(
+        cell.get_or_try_init_boxed(|| Err(())).err(),
+        Some(TryInitError::Init(()))
+    );
+    assert_eq!(cell.get_or_init_boxed(|| vec![1, 2, 3].into()), [1, 2, 3]);
+    cell.get_mut().unwrap()[0] = 10;
+    assert_eq!(cell.reset().as_deref(), Some(&[10, 2, 3][..]));
+    assert!(cell.get().is_none());
+}
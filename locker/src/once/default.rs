@@ -0,0 +1,25 @@
+//! The default once lock
+//!
+//! This is [`simple`](super::simple) if `parking_lot_core` is enabled, otherwise
+//! [`park`](super::park) with the `std` feature flag, and [`spin`](super::spin)
+//! if neither is available. So `OnceCell` and `Lazy` can be used in every configuration.
+
+cfg_if::cfg_if! {
+    if #[cfg(all(feature = "extra", feature = "parking_lot_core"))] {
+        pub use super::simple::RawLock;
+    } else if #[cfg(feature = "std")] {
+        pub use super::park::RawLock;
+    } else {
+        pub use super::spin::RawLock;
+    }
+}
+
+pub type RawMutex = crate::mutex::raw::Mutex<RawLock>;
+pub type Mutex<T> = crate::mutex::Mutex<RawLock, T>;
+pub type Once = crate::once::Once<RawLock>;
+pub type OnceCell<T> = crate::once::OnceCell<RawLock, T>;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub type OnceBoxCell<T> = crate::once::OnceBoxCell<RawLock, T>;
+pub type Lazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Panic>;
+pub type RetryLazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
+pub type RacyLazy<T, F = fn() -> T> = crate::once::RacyLazy<RawLock, T, F>;
@@ -6,7 +6,7 @@ pub type Mutex<T> = crate::mutex::Mutex<RawLock, T>;
 pub type Once = crate::once::Once<RawLock>;
 pub type OnceCell<T> = crate::once::OnceCell<RawLock, T>;
 pub type Lazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Panic>;
-pub type RertyLazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
+pub type RetryLazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
 pub type RacyLazy<T, F = fn() -> T> = crate::once::RacyLazy<RawLock, T, F>;
 
 pub struct RawLock {
@@ -39,7 +39,7 @@ impl RawLock {
         unsafe {
             OnceCell {
                 once: Once::from_raw(Self::new()),
-                value: super::UnsafeCell::new(super::MaybeUninit::uninit()),
+                value: super::SharedCell::new(super::MaybeUninit::uninit()),
             }
         }
     }
@@ -48,8 +48,8 @@ impl RawLock {
         unsafe { Lazy::from_raw_parts(Self::once(), func) }
     }
 
-    pub const fn retry_lazy<T, F>(func: F) -> Lazy<T, F> {
-        unsafe { Lazy::from_raw_parts(Self::once(), func) }
+    pub const fn retry_lazy<T, F>(func: F) -> RetryLazy<T, F> {
+        unsafe { RetryLazy::from_raw_parts(Self::once(), func) }
     }
 
     pub const fn racy_lazy<T, F>(func: F) -> RacyLazy<T, F> {
@@ -82,8 +82,8 @@ unsafe impl crate::once::Finish for RawLock {
     }
 }
 
-impl crate::Init for RawLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    RawLock = Self::new()
 }
 
 unsafe impl crate::RawLockInfo for RawLock {
@@ -108,7 +108,7 @@ unsafe impl RawExclusiveLock for RawLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         self.inner.exc_bump()
     }
 }
@@ -0,0 +1,240 @@
+//! A once lock that parks waiting threads with `std`, without `parking_lot_core`
+//!
+//! Threads spin for a short while, and then park with the [`parking`](crate::parking)
+//! facade, which is only touched when a thread actually has to wait, so uncontended
+//! initialization stays lock-free.
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::parking::{self, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+pub type RawMutex = crate::mutex::raw::Mutex<RawLock>;
+pub type Mutex<T> = crate::mutex::Mutex<RawLock, T>;
+pub type Once = crate::once::Once<RawLock>;
+pub type OnceCell<T> = crate::once::OnceCell<RawLock, T>;
+pub type Lazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Panic>;
+pub type RetryLazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
+pub type RacyLazy<T, F = fn() -> T> = crate::once::RacyLazy<RawLock, T, F>;
+
+pub struct RawLock {
+    state: AtomicU8,
+}
+
+unsafe impl crate::once::Finish for RawLock {
+    #[inline]
+    fn is_done(&self) -> bool {
+        self.state.load(Ordering::Acquire) & Self::DONE_BIT != 0
+    }
+
+    #[inline]
+    fn mark_done(&self) {
+        self.state.fetch_or(Self::DONE_BIT, Ordering::Release);
+    }
+
+    #[inline]
+    fn is_poisoned(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & Self::POISON_BIT != 0
+    }
+
+    #[inline]
+    fn mark_poisoned(&self) {
+        self.state.fetch_or(Self::POISON_BIT, Ordering::Relaxed);
+    }
+}
+
+impl RawLock {
+    const DONE_BIT: u8 = 0b0001;
+    const POISON_BIT: u8 = 0b0010;
+    const LOCK_BIT: u8 = 0b0100;
+    const PARK_BIT: u8 = 0b1000;
+
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(0),
+        }
+    }
+
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    pub const fn once() -> Once {
+        unsafe { Once::from_raw(Self::new()) }
+    }
+
+    pub const fn once_cell<T>() -> OnceCell<T> {
+        unsafe {
+            OnceCell {
+                once: Once::from_raw(Self::new()),
+                value: super::SharedCell::new(super::MaybeUninit::uninit()),
+            }
+        }
+    }
+
+    pub const fn lazy<T, F>(func: F) -> Lazy<T, F> {
+        unsafe { Lazy::from_raw_parts(Self::once(), func) }
+    }
+
+    pub const fn retry_lazy<T, F>(func: F) -> RetryLazy<T, F> {
+        unsafe { RetryLazy::from_raw_parts(Self::once(), func) }
+    }
+
+    pub const fn racy_lazy<T, F>(func: F) -> RacyLazy<T, F> {
+        RacyLazy {
+            once: Self::once_cell(),
+            func,
+        }
+    }
+
+    #[inline]
+    fn key(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    #[cold]
+    fn lock_slow(&self) {
+        let mut spin = SpinWait::new();
+
+        loop {
+            let state = self.state.load(Ordering::Relaxed);
+
+            if state & Self::LOCK_BIT == 0 {
+                if self.exc_try_lock() {
+                    return;
+                }
+
+                continue;
+            }
+
+            if state & Self::PARK_BIT == 0 && spin.spin() {
+                continue;
+            }
+
+            self.park();
+            spin.reset();
+        }
+    }
+
+    /// Park the current thread until the lock is released
+    ///
+    /// This may return spuriously, so the caller must check the lock again
+    fn park(&self) {
+        let set_park_bit = self
+            .state
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |state| {
+                if state & Self::LOCK_BIT == 0 {
+                    None
+                } else {
+                    Some(state | Self::PARK_BIT)
+                }
+            })
+            .is_ok();
+
+        if !set_park_bit {
+            return;
+        }
+
+        // the park bit is checked while the queue is locked, so that the
+        // unlocking thread can't look for parked threads before we are queued
+        let validate = || {
+            let state = self.state.load(Ordering::Relaxed);
+            state & Self::LOCK_BIT != 0 && state & Self::PARK_BIT != 0
+        };
+
+        // SAFETY:
+        //   * `key` is an address we control.
+        //   * `validate`/`timed_out` does not panic or call into any function of `parking`.
+        //   * `before_sleep` does not call `park`, nor does it panic.
+        unsafe {
+            parking::park(
+                self.key(),
+                validate,
+                || {},
+                |_, _| {},
+                DEFAULT_PARK_TOKEN,
+                None,
+            );
+        }
+    }
+
+    #[cold]
+    fn unpark_all(&self) {
+        // SAFETY: `key` is an address we control.
+        unsafe {
+            parking::unpark_all(self.key(), DEFAULT_UNPARK_TOKEN);
+        }
+    }
+}
+
+crate::impl_init! {
+    RawLock = Self::new()
+}
+
+unsafe impl crate::RawLockInfo for RawLock {
+    // the lock doesn't care which thread unlocks it,
+    // so init tokens can be sent across threads
+    type ExclusiveGuardTraits = crate::NoSync;
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl RawExclusiveLock for RawLock {
+    #[inline]
+    fn exc_lock(&self) {
+        if !self.exc_try_lock() {
+            self.lock_slow();
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let state = self.state.load(Ordering::Relaxed);
+
+        state & Self::LOCK_BIT == 0
+            && self
+                .state
+                .compare_exchange(
+                    state,
+                    state | Self::LOCK_BIT,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        let state = self
+            .state
+            .fetch_and(!(Self::LOCK_BIT | Self::PARK_BIT), Ordering::Release);
+
+        if state & Self::PARK_BIT != 0 {
+            self.unpark_all();
+        }
+    }
+}
+
+#[test]
+fn contended() {
+    use std::sync::atomic::AtomicUsize;
+
+    let calls = AtomicUsize::new(0);
+    let lazy = RawLock::lazy(|| {
+        calls.fetch_add(1, Ordering::Relaxed);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        10
+    });
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|_| assert_eq!(*lazy, 10));
+        }
+    })
+    .unwrap();
+
+    assert_eq!(calls.into_inner(), 1);
+}
@@ -0,0 +1,173 @@
+//! The reason a [`Once`](super::Once) was poisoned
+//!
+//! With the `std` feature flag, the cause is recorded when a `Once` is poisoned, and is
+//! handed to later callers so that they can report why initialization failed.
+//! Without it, only the fact that the `Once` was poisoned is recorded.
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        use core::cell::UnsafeCell;
+        use std::sync::Arc;
+
+        /// Why a [`Once`](super::Once) was poisoned
+        #[derive(Debug, Clone)]
+        pub enum PoisonCause {
+            /// The initializer panicked
+            ///
+            /// This holds the panic message if the payload was a string, which covers
+            /// all panics raised by `panic!` and friends.
+            Panic(Option<Arc<str>>),
+            /// The initializer failed with an error
+            Error(Arc<dyn std::error::Error + Send + Sync>),
+            /// Initialization was abandoned by [`InitToken::poison`](super::InitToken::poison)
+            Abandoned,
+        }
+
+        impl PoisonCause {
+            fn from_panic(payload: &(dyn std::any::Any + Send)) -> Self {
+                let message = if let Some(&message) = payload.downcast_ref::<&'static str>() {
+                    Some(message.into())
+                } else {
+                    payload
+                        .downcast_ref::<String>()
+                        .map(|message| message.as_str().into())
+                };
+
+                Self::Panic(message)
+            }
+
+            fn current() -> Self {
+                if std::thread::panicking() {
+                    Self::Panic(None)
+                } else {
+                    Self::Abandoned
+                }
+            }
+        }
+
+        impl std::fmt::Display for PoisonCause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    Self::Panic(Some(message)) => write!(f, "the initializer panicked: {}", message),
+                    Self::Panic(None) => f.write_str("the initializer panicked"),
+                    Self::Error(error) => write!(f, "the initializer failed: {}", error),
+                    Self::Abandoned => f.write_str("initialization was abandoned"),
+                }
+            }
+        }
+
+        /// The error returned by the non-panicking `Once` and `OnceCell` methods
+        #[derive(Debug, Clone)]
+        pub enum OnceError {
+            /// The `Once` was poisoned by an earlier initialization
+            Poisoned(PoisonCause),
+        }
+
+        impl OnceError {
+            /// Why the `Once` was poisoned
+            #[inline]
+            pub fn cause(&self) -> &PoisonCause {
+                match self {
+                    Self::Poisoned(cause) => cause,
+                }
+            }
+        }
+
+        impl std::fmt::Display for OnceError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    Self::Poisoned(cause) => write!(f, "the `Once` is poisoned ({})", cause),
+                }
+            }
+        }
+
+        impl std::error::Error for OnceError {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    Self::Poisoned(PoisonCause::Error(error)) => Some(&**error),
+                    Self::Poisoned(_) => None,
+                }
+            }
+        }
+
+        pub(super) type Cause = Option<PoisonCause>;
+
+        pub(super) struct CauseSlot(UnsafeCell<Option<PoisonCause>>);
+
+        // SAFETY: the slot is only accessed while holding the *exc lock* of the `Once`
+        // that it belongs to, or through a `&mut`
+        unsafe impl Sync for CauseSlot {}
+
+        impl CauseSlot {
+            pub const fn new() -> Self {
+                Self(UnsafeCell::new(None))
+            }
+
+            /// # Safety
+            ///
+            /// the caller must own the *exc lock* of the `Once`
+            pub unsafe fn get(&self) -> Cause {
+                (*self.0.get()).clone()
+            }
+
+            /// # Safety
+            ///
+            /// the caller must own the *exc lock* of the `Once`
+            pub unsafe fn set(&self, cause: PoisonCause) {
+                *self.0.get() = Some(cause);
+            }
+
+            /// Record the cause of a poison that wasn't given an explicit cause
+            ///
+            /// # Safety
+            ///
+            /// the caller must own the *exc lock* of the `Once`
+            pub unsafe fn set_current(&self) {
+                self.set(PoisonCause::current())
+            }
+
+            /// Run `f`, and record the panic payload if it panics
+            ///
+            /// # Safety
+            ///
+            /// the caller must own the *exc lock* of the `Once`
+            pub unsafe fn catch<R>(&self, f: impl FnOnce() -> R, poison: impl FnOnce()) -> R {
+                match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+                    Ok(value) => value,
+                    Err(payload) => {
+                        self.set(PoisonCause::from_panic(&*payload));
+                        poison();
+                        std::panic::resume_unwind(payload)
+                    }
+                }
+            }
+        }
+    } else {
+        pub(super) struct CauseSlot;
+
+        impl CauseSlot {
+            pub const fn new() -> Self {
+                Self
+            }
+
+            pub unsafe fn set_current(&self) {}
+
+            pub unsafe fn catch<R>(&self, f: impl FnOnce() -> R, poison: impl FnOnce()) -> R {
+                struct Poison<F: FnOnce()>(Option<F>);
+
+                impl<F: FnOnce()> Drop for Poison<F> {
+                    fn drop(&mut self) {
+                        if let Some(poison) = self.0.take() {
+                            poison()
+                        }
+                    }
+                }
+
+                let mut guard = Poison(Some(poison));
+                let value = f();
+                guard.0 = None;
+                value
+            }
+        }
+    }
+}
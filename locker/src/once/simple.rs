@@ -7,7 +7,7 @@ pub type Mutex<T> = crate::mutex::Mutex<RawLock, T>;
 pub type Once = crate::once::Once<RawLock>;
 pub type OnceCell<T> = crate::once::OnceCell<RawLock, T>;
 pub type Lazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Panic>;
-pub type RertyLazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
+pub type RetryLazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
 pub type RacyLazy<T, F = fn() -> T> = crate::once::RacyLazy<RawLock, T, F>;
 
 pub struct RawLock {
@@ -62,7 +62,7 @@ impl RawLock {
         unsafe {
             OnceCell {
                 once: Once::from_raw(Self::new()),
-                value: super::UnsafeCell::new(super::MaybeUninit::uninit()),
+                value: super::SharedCell::new(super::MaybeUninit::uninit()),
             }
         }
     }
@@ -71,8 +71,8 @@ impl RawLock {
         unsafe { Lazy::from_raw_parts(Self::once(), func) }
     }
 
-    pub const fn retry_lazy<T, F>(func: F) -> Lazy<T, F> {
-        unsafe { Lazy::from_raw_parts(Self::once(), func) }
+    pub const fn retry_lazy<T, F>(func: F) -> RetryLazy<T, F> {
+        unsafe { RetryLazy::from_raw_parts(Self::once(), func) }
     }
 
     pub const fn racy_lazy<T, F>(func: F) -> RacyLazy<T, F> {
@@ -83,12 +83,25 @@ impl RawLock {
     }
 }
 
-impl crate::Init for RawLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    RawLock = Self::new()
+}
+
+impl crate::raw_state::RawStateBits for RawLock {
+    #[inline]
+    fn raw_state_bits(&self) -> usize {
+        self.inner.raw_state_bits()
+    }
+
+    fn decode_state(bits: usize) -> crate::raw_state::DecodedState {
+        Tagged::decode_state(bits)
+    }
 }
 
 unsafe impl crate::RawLockInfo for RawLock {
-    type ExclusiveGuardTraits = <Tagged as crate::RawLockInfo>::ExclusiveGuardTraits;
+    // the tagged lock doesn't care which thread unlocks it,
+    // so init tokens can be sent across threads
+    type ExclusiveGuardTraits = crate::NoSync;
     type ShareGuardTraits = <Tagged as crate::RawLockInfo>::ShareGuardTraits;
 }
 
@@ -109,8 +122,8 @@ unsafe impl RawExclusiveLock for RawLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
-        self.inner.exc_bump_fair();
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        self.inner.exc_bump_fair()
     }
 }
 
@@ -121,7 +134,180 @@ unsafe impl RawExclusiveLockFair for RawLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
-        self.inner.exc_bump_fair();
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        self.inner.exc_bump_fair()
+    }
+}
+
+#[test]
+fn begin_init() {
+    let cell = OnceCell::<u32>::default();
+
+    let token = cell.begin_init().unwrap();
+    assert!(cell.try_begin_init().is_none());
+    assert!(!token.state().is_poisoned());
+    assert_eq!(*token.complete(10), 10);
+
+    assert!(cell.begin_init().is_none());
+    assert_eq!(cell.get(), Some(&10));
+
+    let once = RawLock::once();
+    once.begin_init().unwrap().poison();
+    assert!(once.try_begin_init().unwrap().state().is_poisoned());
+}
+
+#[test]
+fn send_init_token() {
+    fn assert_send<T: Send>(_: &T) {}
+
+    let cell = OnceCell::<u32>::default();
+    let token = cell.begin_init().unwrap();
+    assert_send(&token);
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(move |_| token.complete(10));
+    })
+    .unwrap();
+
+    assert_eq!(cell.get(), Some(&10));
+}
+
+#[test]
+fn reset_poison() {
+    use crate::once::TryInitError;
+
+    let mut cell = OnceCell::<u32>::default();
+
+    assert_eq!(
+        cell.get_or_try_init(|| Err(())),
+        Err(TryInitError::Init(()))
+    );
+    let token = cell.begin_init().unwrap();
+    assert!(!token.state().is_poisoned());
+    token.cancel();
+
+    assert_eq!(cell.get_or_try_init(|| Ok::<_, ()>(10)), Ok(&10));
+    assert_eq!(cell.reset(), Some(10));
+    assert_eq!(cell.get(), None);
+
+    // a poisoned cell isn't initialized again
+    cell.begin_init().unwrap().poison();
+    assert_eq!(
+        cell.get_or_try_init(|| -> Result<_, ()> { panic!("called on a poisoned cell") }),
+        Err(TryInitError::Poisoned)
+    );
+    assert_eq!(cell.get(), None);
+
+    assert_eq!(cell.reset(), None);
+    assert_eq!(cell.get_or_try_init(|| Ok::<_, ()>(20)), Ok(&20));
+
+    let mut once = RawLock::once();
+    once.begin_init().unwrap().poison();
+    once.reset_poison();
+    assert!(!once.begin_init().unwrap().state().is_poisoned());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn poison_cause() {
+    use crate::once::{OnceError, PoisonCause};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let once = RawLock::once();
+    assert!(catch_unwind(|| once.call_once(|| panic!("init failed"))).is_err());
+
+    match once.try_call_once(|| ()) {
+        Err(OnceError::Poisoned(PoisonCause::Panic(Some(message)))) => {
+            assert_eq!(&*message, "init failed")
+        }
+        _ => panic!("expected the panic message"),
     }
+
+    let payload = catch_unwind(|| once.call_once(|| ())).unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(message.ends_with("the initializer panicked: init failed"));
+
+    let cell = OnceCell::<u32>::default();
+    let error = cell
+        .get_or_try_init_poison(|| Err(std::fmt::Error))
+        .unwrap_err();
+    assert!(matches!(error.cause(), PoisonCause::Error(error) if error.is::<std::fmt::Error>()));
+
+    let error = cell
+        .get_or_try_init_poison(|| Ok::<_, std::fmt::Error>(10))
+        .unwrap_err();
+    assert!(matches!(error.cause(), PoisonCause::Error(_)));
+
+    // a token that was retrying initialization doesn't replace the original cause
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _token = cell.begin_init().unwrap();
+        panic!("token dropped")
+    }));
+    assert!(result.is_err());
+    assert!(matches!(
+        cell.begin_init().unwrap().state().poison_cause(),
+        Some(PoisonCause::Error(_))
+    ));
+
+    let once = RawLock::once();
+    let result = catch_unwind(|| {
+        let _token = once.begin_init().unwrap();
+        panic!("token dropped")
+    });
+    assert!(result.is_err());
+    assert!(matches!(
+        once.begin_init().unwrap().state().poison_cause(),
+        Some(PoisonCause::Panic(None))
+    ));
+    assert!(matches!(
+        once.begin_init().unwrap().state().poison_cause(),
+        Some(PoisonCause::Panic(None))
+    ));
+
+    let once = RawLock::once();
+    once.begin_init().unwrap().poison();
+    assert!(matches!(
+        once.begin_init().unwrap().state().poison_cause(),
+        Some(PoisonCause::Abandoned)
+    ));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn subscribe() {
+    use crate::test_waker::Count;
+    use std::future::Future;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+    use std::time::Duration;
+
+    let cell = OnceCell::<u32>::default();
+    assert!(cell.poll_initialized().is_pending());
+
+    let subscription = cell.subscribe();
+    assert_eq!(subscription.wait_for(Duration::from_millis(10)), None);
+
+    let count = Arc::new(Count(AtomicUsize::new(0)));
+    let waker = Waker::from(count.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut future = cell.subscribe();
+    assert!(std::pin::Pin::new(&mut future).poll(&mut cx).is_pending());
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| assert_eq!(*cell.subscribe().wait(), 10));
+        s.spawn(|_| {
+            std::thread::sleep(Duration::from_millis(10));
+            cell.get_or_init(|| 10)
+        });
+    })
+    .unwrap();
+
+    assert_eq!(count.0.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        std::pin::Pin::new(&mut future).poll(&mut cx),
+        Poll::Ready(&10)
+    );
+    assert_eq!(cell.poll_initialized(), Poll::Ready(&10));
+    assert_eq!(subscription.wait(), &10);
 }
@@ -0,0 +1,165 @@
+//! A spin-based once lock, which is available in every configuration
+//!
+//! Threads that wait for another thread to finish initialization spin (and yield to the OS
+//! with the `std` feature flag), so this is best suited to short initializers, or to
+//! targets that can't park threads.
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+pub type RawMutex = crate::mutex::raw::Mutex<RawLock>;
+pub type Mutex<T> = crate::mutex::Mutex<RawLock, T>;
+pub type Once = crate::once::Once<RawLock>;
+pub type OnceCell<T> = crate::once::OnceCell<RawLock, T>;
+pub type Lazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Panic>;
+pub type RetryLazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
+pub type RacyLazy<T, F = fn() -> T> = crate::once::RacyLazy<RawLock, T, F>;
+
+pub struct RawLock {
+    state: AtomicU8,
+}
+
+unsafe impl crate::once::Finish for RawLock {
+    #[inline]
+    fn is_done(&self) -> bool {
+        self.state.load(Ordering::Acquire) & Self::DONE_BIT != 0
+    }
+
+    #[inline]
+    fn mark_done(&self) {
+        self.state.fetch_or(Self::DONE_BIT, Ordering::Release);
+    }
+
+    #[inline]
+    fn is_poisoned(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & Self::POISON_BIT != 0
+    }
+
+    #[inline]
+    fn mark_poisoned(&self) {
+        self.state.fetch_or(Self::POISON_BIT, Ordering::Relaxed);
+    }
+}
+
+impl RawLock {
+    const DONE_BIT: u8 = 0b001;
+    const POISON_BIT: u8 = 0b010;
+    const LOCK_BIT: u8 = 0b100;
+
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(0),
+        }
+    }
+
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    pub const fn once() -> Once {
+        unsafe { Once::from_raw(Self::new()) }
+    }
+
+    pub const fn once_cell<T>() -> OnceCell<T> {
+        unsafe {
+            OnceCell {
+                once: Once::from_raw(Self::new()),
+                value: super::SharedCell::new(super::MaybeUninit::uninit()),
+            }
+        }
+    }
+
+    pub const fn lazy<T, F>(func: F) -> Lazy<T, F> {
+        unsafe { Lazy::from_raw_parts(Self::once(), func) }
+    }
+
+    pub const fn retry_lazy<T, F>(func: F) -> RetryLazy<T, F> {
+        unsafe { RetryLazy::from_raw_parts(Self::once(), func) }
+    }
+
+    pub const fn racy_lazy<T, F>(func: F) -> RacyLazy<T, F> {
+        RacyLazy {
+            once: Self::once_cell(),
+            func,
+        }
+    }
+
+    #[cold]
+    fn lock_slow(&self) {
+        let mut spin = SpinWait::new();
+
+        while !self.exc_try_lock() {
+            if !spin.spin() {
+                spin.reset();
+            }
+        }
+    }
+}
+
+crate::impl_init! {
+    RawLock = Self::new()
+}
+
+unsafe impl crate::RawLockInfo for RawLock {
+    // the spin lock doesn't care which thread unlocks it,
+    // so init tokens can be sent across threads
+    type ExclusiveGuardTraits = crate::NoSync;
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl RawExclusiveLock for RawLock {
+    #[inline]
+    fn exc_lock(&self) {
+        if !self.exc_try_lock() {
+            self.lock_slow();
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let state = self.state.load(Ordering::Relaxed);
+
+        state & Self::LOCK_BIT == 0
+            && self
+                .state
+                .compare_exchange(
+                    state,
+                    state | Self::LOCK_BIT,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.state.fetch_and(!Self::LOCK_BIT, Ordering::Release);
+    }
+}
+
+#[test]
+fn once_cell() {
+    let cell = OnceCell::<u32>::default();
+    assert_eq!(cell.get(), None);
+
+    let token = cell.begin_init().unwrap();
+    assert!(cell.try_begin_init().is_none());
+    assert_eq!(*token.complete(10), 10);
+    assert_eq!(cell.get_or_init(|| 20), &10);
+
+    let once = RawLock::once();
+    once.begin_init().unwrap().poison();
+    assert!(once.try_begin_init().unwrap().state().is_poisoned());
+}
+
+#[test]
+fn retry_lazy() {
+    // a `RetryLazy` passes the state of the `Once` to its initializer
+    let lazy = RawLock::retry_lazy(|state: &crate::once::OnceState| state.is_poisoned());
+    assert!(!*lazy);
+}
@@ -0,0 +1,265 @@
+//! Wait for a [`OnceCell`] to be initialized, without initializing it
+//!
+//! [`OnceCell::subscribe`] returns a [`Subscription`], which can block the current thread
+//! or be awaited until another thread initializes the cell. Subscribers are kept in a single
+//! global registry keyed by the address of the cell's lock, so cells don't pay for subscriptions
+//! unless someone is actually waiting on them.
+
+use super::{Finish, OnceCell};
+
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{fence, AtomicUsize, Ordering};
+use core::task::{Context, Poll, Waker};
+use std::sync::Mutex;
+use std::thread::Thread;
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+enum Notify {
+    Thread(Thread),
+    Waker(Waker),
+}
+
+struct Subscriber {
+    key: usize,
+    notify: Notify,
+}
+
+// the number of subscribers in the registry, so that initialization can skip the registry
+static WAITING: AtomicUsize = AtomicUsize::new(0);
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+
+/// Wake up everyone subscribed to the `Once` with the given key
+///
+/// This must be called after the `Once` is marked done
+pub(super) fn notify(key: usize) {
+    // pairs with the fence in `subscribe`, so either we see the new subscriber,
+    // or the subscriber sees that the `Once` is done
+    fence(Ordering::SeqCst);
+
+    if WAITING.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+
+    notify_slow(key)
+}
+
+#[cold]
+fn notify_slow(key: usize) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap_or_else(|err| err.into_inner());
+    let mut woken = Vec::new();
+
+    subscribers.retain(|subscriber| {
+        if subscriber.key == key {
+            woken.push(match &subscriber.notify {
+                Notify::Thread(thread) => Notify::Thread(thread.clone()),
+                Notify::Waker(waker) => Notify::Waker(waker.clone()),
+            });
+            false
+        } else {
+            true
+        }
+    });
+
+    WAITING.fetch_sub(woken.len(), Ordering::Relaxed);
+    drop(subscribers);
+
+    for notify in woken {
+        match notify {
+            Notify::Thread(thread) => thread.unpark(),
+            Notify::Waker(waker) => waker.wake(),
+        }
+    }
+}
+
+/// Register `notify` to be woken when `is_done` becomes true
+///
+/// Returns false without registering if `is_done` is already true
+fn subscribe(key: usize, is_done: impl FnOnce() -> bool, notify: Notify) -> bool {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap_or_else(|err| err.into_inner());
+
+    WAITING.fetch_add(1, Ordering::Relaxed);
+    fence(Ordering::SeqCst);
+
+    if is_done() {
+        WAITING.fetch_sub(1, Ordering::Relaxed);
+        false
+    } else {
+        subscribers.push(Subscriber { key, notify });
+        true
+    }
+}
+
+/// Remove the first subscriber to the given key that `matches`, if it wasn't woken yet
+fn unsubscribe(key: usize, matches: impl Fn(&Notify) -> bool) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap_or_else(|err| err.into_inner());
+
+    if let Some(index) = subscribers
+        .iter()
+        .position(|subscriber| subscriber.key == key && matches(&subscriber.notify))
+    {
+        subscribers.swap_remove(index);
+        WAITING.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A handle that waits until a [`OnceCell`] is initialized
+///
+/// created by [`OnceCell::subscribe`]
+///
+/// Waiting never initializes the cell, and if initialization is abandoned or poisoned,
+/// the subscription keeps waiting for a later initialization to succeed.
+pub struct Subscription<'a, L: Finish, T> {
+    cell: &'a OnceCell<L, T>,
+    waker: Option<Waker>,
+}
+
+impl<L: Finish, T> OnceCell<L, T> {
+    /// Subscribe to the initialization of this cell, without initializing it
+    #[inline]
+    pub fn subscribe(&self) -> Subscription<'_, L, T> {
+        Subscription {
+            cell: self,
+            waker: None,
+        }
+    }
+}
+
+impl<'a, L: Finish, T> Subscription<'a, L, T> {
+    #[inline]
+    fn key(&self) -> usize {
+        &self.cell.once.lock as *const L as usize
+    }
+
+    /// The contents of the cell, if it is initialized
+    #[inline]
+    pub fn get(&self) -> Option<&'a T> {
+        self.cell.get()
+    }
+
+    /// Block the current thread until the cell is initialized
+    pub fn wait(&self) -> &'a T {
+        match self.wait_until(None) {
+            Some(value) => value,
+            None => {
+                unreachable!("waiting without a deadline only returns once the cell is initialized")
+            }
+        }
+    }
+
+    /// Block the current thread until the cell is initialized, or the timeout expires
+    ///
+    /// Returns `None` if the timeout expired before the cell was initialized
+    pub fn wait_for(&self, duration: Duration) -> Option<&'a T> {
+        self.wait_until(Instant::now().checked_add(duration))
+    }
+
+    /// Block the current thread until the cell is initialized, or the deadline is reached
+    ///
+    /// Returns `None` if the deadline passed before the cell was initialized
+    pub fn wait_until(&self, deadline: Option<Instant>) -> Option<&'a T> {
+        if let Some(value) = self.get() {
+            return Some(value);
+        }
+
+        let lock = &self.cell.once.lock;
+        let thread = Notify::Thread(std::thread::current());
+
+        if subscribe(self.key(), || lock.is_done(), thread) {
+            while !lock.is_done() {
+                match deadline {
+                    None => std::thread::park(),
+                    Some(deadline) => {
+                        let now = Instant::now();
+
+                        if now >= deadline {
+                            let id = std::thread::current().id();
+
+                            unsubscribe(self.key(), |notify| match notify {
+                                Notify::Thread(thread) => thread.id() == id,
+                                Notify::Waker(_) => false,
+                            });
+                            break;
+                        }
+
+                        std::thread::park_timeout(deadline - now);
+                    }
+                }
+            }
+        }
+
+        self.get()
+    }
+}
+
+impl<'a, L: Finish, T> Future for Subscription<'a, L, T> {
+    type Output = &'a T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = Pin::into_inner(self);
+
+        if let Poll::Ready(value) = this.cell.poll_initialized() {
+            return Poll::Ready(value);
+        }
+
+        // only register again if we are polled by a different task,
+        // the old waker is still in the registry otherwise
+        if this
+            .waker
+            .as_ref()
+            .is_none_or(|waker| !waker.will_wake(cx.waker()))
+        {
+            if let Some(old) = this.waker.take() {
+                unsubscribe(this.key(), |notify| match notify {
+                    Notify::Waker(registered) => registered.will_wake(&old),
+                    Notify::Thread(_) => false,
+                });
+            }
+
+            let lock = &this.cell.once.lock;
+            let waker = Notify::Waker(cx.waker().clone());
+
+            if !subscribe(this.key(), || lock.is_done(), waker) {
+                return this.cell.poll_initialized();
+            }
+
+            this.waker = Some(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<L: Finish, T> Drop for Subscription<'_, L, T> {
+    fn drop(&mut self) {
+        // the cell may be done while `notify` hasn't gotten to this waker yet,
+        // so it is unregistered even then, `notify` skips wakers that are gone
+        if let Some(waker) = self.waker.take() {
+            unsubscribe(self.key(), |notify| match notify {
+                Notify::Waker(registered) => registered.will_wake(&waker),
+                Notify::Thread(_) => false,
+            });
+        }
+    }
+}
+
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn drop_unsubscribes() {
+    let registered = |key| {
+        let subscribers = SUBSCRIBERS.lock().unwrap_or_else(|err| err.into_inner());
+        subscribers.iter().filter(|s| s.key == key).count()
+    };
+
+    let cell = super::simple::OnceCell::<u32>::default();
+    let mut cx = Context::from_waker(Waker::noop());
+
+    let mut future = cell.subscribe();
+    let key = future.key();
+    assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+    assert_eq!(registered(key), 1);
+
+    drop(future);
+    assert_eq!(registered(key), 0);
+}
@@ -0,0 +1,167 @@
+//! A low-level parking facade
+//!
+//! This is the minimal API that the parking backends are written against, modeled after
+//! [`parking_lot_core`](https://docs.rs/parking_lot_core). Threads park on a `key`
+//! (usually the address of a lock), and are woken up by other threads unparking that key.
+//!
+//! * with the `parking_lot_core` feature flag, this is `parking_lot_core` itself
+//! * otherwise with `std`, threads are kept in a queue inside of this crate,
+//!   and parked with [`std::thread::park`] and [`std::thread::park_timeout`]
+//! * otherwise parking is done through the `Hooks` installed with `set_hooks`,
+//!   which allows platforms without `std` (like an RTOS) to supply their own parking
+//!   primitive. Timeouts aren't supported without `std`, so `Instant` is uninhabited.
+//!
+//! The functions here have the same signatures and semantics as their
+//! counterparts in `parking_lot_core`.
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "parking_lot_core")] {
+        pub use parking_lot_core::{
+            park, unpark_all, unpark_filter, unpark_one, FilterOp, ParkResult, ParkToken,
+            UnparkResult, UnparkToken, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN,
+        };
+        pub use std::time::Instant;
+    } else {
+        mod queue;
+
+        pub use queue::{park, unpark_all, unpark_filter, unpark_one};
+
+        #[cfg(feature = "std")]
+        pub use std::time::Instant;
+
+        /// The instant that a thread can park until
+        ///
+        /// Timeouts aren't supported without `std`, so this can't be constructed
+        #[cfg(not(feature = "std"))]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum Instant {}
+
+        /// A value which is passed from an unparker to a parked thread
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct UnparkToken(pub usize);
+
+        /// A value associated with a parked thread which can be used by `unpark_filter`
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct ParkToken(pub usize);
+
+        /// A default unpark token to use
+        pub const DEFAULT_UNPARK_TOKEN: UnparkToken = UnparkToken(0);
+
+        /// A default park token to use
+        pub const DEFAULT_PARK_TOKEN: ParkToken = ParkToken(0);
+
+        /// Result of a park operation
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum ParkResult {
+            /// We were unparked by another thread with the given token
+            Unparked(UnparkToken),
+
+            /// The validation callback returned false
+            Invalid,
+
+            /// The timeout expired
+            TimedOut,
+        }
+
+        impl ParkResult {
+            /// Returns true if we were unparked by another thread
+            #[inline]
+            pub fn is_unparked(self) -> bool {
+                matches!(self, ParkResult::Unparked(_))
+            }
+        }
+
+        /// Result of an unpark operation
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct UnparkResult {
+            /// The number of threads that were unparked
+            pub unparked_threads: usize,
+
+            /// Whether there are any threads remaining in the queue
+            pub have_more_threads: bool,
+
+            /// This is set to true on average once every 0.5ms for any given key. It
+            /// should be used to switch to a fair unlocking mechanism for a particular unlock
+            pub be_fair: bool,
+        }
+
+        /// Operation that `unpark_filter` should perform for each thread
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum FilterOp {
+            /// Unpark the thread and continue scanning the list of parked threads
+            Unpark,
+
+            /// Don't unpark the thread and continue scanning the list of parked threads
+            Skip,
+
+            /// Don't unpark the thread and stop scanning the list of parked threads
+            Stop,
+        }
+    }
+}
+
+#[cfg(not(any(feature = "std", feature = "parking_lot_core")))]
+pub use queue::{set_hooks, Hooks};
+
+#[test]
+#[cfg(feature = "std")]
+fn filter_and_timeout() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    let key = &0u8 as *const u8 as usize;
+    let parked = AtomicUsize::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        let threads = [1, 2, 1].map(|token| {
+            let parked = &parked;
+
+            s.spawn(move |_| {
+                let validate = || {
+                    parked.fetch_add(1, Ordering::Relaxed);
+                    true
+                };
+
+                unsafe { park(key, validate, || {}, |_, _| {}, ParkToken(token), None) }
+            })
+        });
+
+        while parked.load(Ordering::Relaxed) != 3 {
+            std::thread::yield_now();
+        }
+
+        let filter = |token| {
+            if token == ParkToken(1) {
+                FilterOp::Unpark
+            } else {
+                FilterOp::Skip
+            }
+        };
+        let result = unsafe { unpark_filter(key, filter, |_| UnparkToken(10)) };
+        assert_eq!(result.unparked_threads, 2);
+        assert!(result.have_more_threads);
+
+        assert_eq!(unsafe { unpark_all(key, UnparkToken(20)) }, 1);
+
+        let [a, b, c] = threads.map(|thread| thread.join().unwrap());
+        assert_eq!(a, ParkResult::Unparked(UnparkToken(10)));
+        assert_eq!(b, ParkResult::Unparked(UnparkToken(20)));
+        assert_eq!(c, ParkResult::Unparked(UnparkToken(10)));
+    })
+    .unwrap();
+
+    let timeout = Instant::now() + Duration::from_millis(10);
+    let mut was_last = false;
+    let result = unsafe {
+        park(
+            key,
+            || true,
+            || {},
+            |_, last| was_last = last,
+            DEFAULT_PARK_TOKEN,
+            Some(timeout),
+        )
+    };
+    assert_eq!(result, ParkResult::TimedOut);
+    assert!(was_last);
+}
@@ -0,0 +1,491 @@
+//! The wait queues used when `parking_lot_core` isn't available
+//!
+//! Parked threads are kept in intrusive linked lists, one for each of a fixed number of
+//! buckets, and each node lives on the stack of the thread that is parked. So parking
+//! never allocates, and this works the same with `std` and with user provided `Hooks`.
+
+use super::{FilterOp, Instant, ParkResult, ParkToken, UnparkResult, UnparkToken};
+use crate::spin_wait::SpinWait;
+
+use core::cell::{Cell, UnsafeCell};
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+const BUCKET_BITS: u32 = 6;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const BUCKET: Bucket = Bucket::new();
+static BUCKETS: [Bucket; 1 << BUCKET_BITS] = [BUCKET; 1 << BUCKET_BITS];
+
+/// A thread parked in a bucket
+struct Node {
+    key: usize,
+    park_token: ParkToken,
+    thread: sys::Thread,
+    // only accessed while the bucket is locked
+    next: Cell<*const Node>,
+    // written before `woken` is set
+    unpark_token: Cell<UnparkToken>,
+    woken: AtomicBool,
+}
+
+struct Queue {
+    head: *const Node,
+    tail: *const Node,
+    #[cfg(feature = "std")]
+    fair_timeout: Option<Instant>,
+}
+
+struct Bucket {
+    lock: AtomicBool,
+    queue: UnsafeCell<Queue>,
+}
+
+// SAFETY: the queue is only accessed while the bucket is locked
+unsafe impl Sync for Bucket {}
+
+struct BucketGuard<'a> {
+    bucket: &'a Bucket,
+}
+
+impl Bucket {
+    const fn new() -> Self {
+        Self {
+            lock: AtomicBool::new(false),
+            queue: UnsafeCell::new(Queue {
+                head: ptr::null(),
+                tail: ptr::null(),
+                #[cfg(feature = "std")]
+                fair_timeout: None,
+            }),
+        }
+    }
+
+    fn lock(key: usize) -> BucketGuard<'static> {
+        // fibonacci hashing, so that keys which are close together use different buckets
+        let hash = key.wrapping_mul(0x9E37_79B9_7F4A_7C15_u64 as usize);
+        let bucket = &BUCKETS[hash >> (usize::BITS - BUCKET_BITS)];
+        let mut spin = SpinWait::new();
+
+        while bucket
+            .lock
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spin.spin();
+        }
+
+        BucketGuard { bucket }
+    }
+}
+
+impl Drop for BucketGuard<'_> {
+    fn drop(&mut self) {
+        self.bucket.lock.store(false, Ordering::Release);
+    }
+}
+
+impl BucketGuard<'_> {
+    fn queue(&mut self) -> &mut Queue {
+        // SAFETY: the bucket is locked
+        unsafe { &mut *self.bucket.queue.get() }
+    }
+}
+
+impl Queue {
+    fn push(&mut self, node: &Node) {
+        if self.tail.is_null() {
+            self.head = node;
+        } else {
+            // SAFETY: nodes stay alive for as long as they are in the queue
+            unsafe { (*self.tail).next.set(node) }
+        }
+
+        self.tail = node;
+    }
+
+    /// Remove `node` from the queue, `prev` must be the node before it (or null if it is the head)
+    ///
+    /// # Safety
+    ///
+    /// `node` must be in the queue
+    unsafe fn remove(&mut self, prev: *const Node, node: *const Node) {
+        let next = (*node).next.get();
+
+        if prev.is_null() {
+            self.head = next;
+        } else {
+            (*prev).next.set(next);
+        }
+
+        if self.tail == node {
+            self.tail = prev;
+        }
+    }
+
+    /// Remove `node` from the queue if it is in it, and check if any other threads are parked on `key`
+    ///
+    /// Returns `None` if `node` wasn't in the queue
+    fn remove_node(&mut self, node: &Node) -> Option<bool> {
+        let mut prev = ptr::null();
+        let mut current = self.head;
+        let mut found = false;
+        let mut has_more_threads = false;
+
+        while !current.is_null() {
+            // SAFETY: nodes stay alive for as long as they are in the queue
+            let next = unsafe { (*current).next.get() };
+
+            if ptr::eq(current, node) {
+                // SAFETY: `current` is in the queue
+                unsafe { self.remove(prev, current) }
+                found = true;
+            } else {
+                has_more_threads |= unsafe { (*current).key } == node.key;
+                prev = current;
+            }
+
+            current = next;
+        }
+
+        if found {
+            Some(has_more_threads)
+        } else {
+            None
+        }
+    }
+
+    /// Check if an unparking thread should be fair, about once every 0.5ms
+    fn be_fair(&mut self) -> bool {
+        #[cfg(feature = "std")]
+        {
+            let now = Instant::now();
+            let be_fair = matches!(self.fair_timeout, Some(timeout) if now > timeout);
+
+            if be_fair || self.fair_timeout.is_none() {
+                self.fair_timeout = Some(now + std::time::Duration::from_micros(500));
+            }
+
+            be_fair
+        }
+
+        #[cfg(not(feature = "std"))]
+        {
+            false
+        }
+    }
+}
+
+/// Parks the current thread in the queue associated with the given key
+///
+/// See [`parking_lot_core::park`](https://docs.rs/parking_lot_core/*/parking_lot_core/fn.park.html)
+///
+/// # Safety
+///
+/// You should only call this function with an address that you control, since
+/// you could otherwise interfere with the operation of other synchronization
+/// primitives.
+///
+/// The `validate` and `timed_out` functions are called while the queue is
+/// locked and must not panic or call into any function in `parking`.
+///
+/// The `before_sleep` function is called outside the queue lock and is allowed
+/// to call `unpark_one`, `unpark_all` or `unpark_filter`, but it is not
+/// allowed to call `park` or panic.
+pub unsafe fn park(
+    key: usize,
+    validate: impl FnOnce() -> bool,
+    before_sleep: impl FnOnce(),
+    timed_out: impl FnOnce(usize, bool),
+    park_token: ParkToken,
+    timeout: Option<Instant>,
+) -> ParkResult {
+    let node = Node {
+        key,
+        park_token,
+        thread: sys::current(),
+        next: Cell::new(ptr::null()),
+        unpark_token: Cell::new(super::DEFAULT_UNPARK_TOKEN),
+        woken: AtomicBool::new(false),
+    };
+
+    {
+        let mut bucket = Bucket::lock(key);
+
+        if !validate() {
+            return ParkResult::Invalid;
+        }
+
+        bucket.queue().push(&node);
+    }
+
+    before_sleep();
+
+    loop {
+        // parking may return spuriously, so check if we were actually woken up
+        if node.woken.load(Ordering::Acquire) {
+            return ParkResult::Unparked(node.unpark_token.get());
+        }
+
+        if !sys::park(timeout) {
+            break;
+        }
+    }
+
+    // the timeout expired, but we may have been woken up before we could leave the queue
+    let mut bucket = Bucket::lock(key);
+
+    if node.woken.load(Ordering::Acquire) {
+        return ParkResult::Unparked(node.unpark_token.get());
+    }
+
+    let has_more_threads = bucket
+        .queue()
+        .remove_node(&node)
+        .expect("a parked thread wasn't woken up, but it left the queue");
+    timed_out(key, !has_more_threads);
+
+    ParkResult::TimedOut
+}
+
+/// Unparks one thread from the queue associated with the given key
+///
+/// See [`parking_lot_core::unpark_one`](https://docs.rs/parking_lot_core/*/parking_lot_core/fn.unpark_one.html)
+///
+/// # Safety
+///
+/// You should only call this function with an address that you control, since
+/// you could otherwise interfere with the operation of other synchronization
+/// primitives.
+///
+/// The `callback` function is called while the queue is locked and must not
+/// panic or call into any function in `parking`.
+pub unsafe fn unpark_one(
+    key: usize,
+    callback: impl FnOnce(UnparkResult) -> UnparkToken,
+) -> UnparkResult {
+    let mut found = false;
+    let filter = |_| {
+        if found {
+            FilterOp::Stop
+        } else {
+            found = true;
+            FilterOp::Unpark
+        }
+    };
+
+    unpark_filter(key, filter, callback)
+}
+
+/// Unparks all threads in the queue associated with the given key
+///
+/// See [`parking_lot_core::unpark_all`](https://docs.rs/parking_lot_core/*/parking_lot_core/fn.unpark_all.html)
+///
+/// # Safety
+///
+/// You should only call this function with an address that you control, since
+/// you could otherwise interfere with the operation of other synchronization
+/// primitives.
+pub unsafe fn unpark_all(key: usize, unpark_token: UnparkToken) -> usize {
+    unpark_filter(key, |_| FilterOp::Unpark, |_| unpark_token).unparked_threads
+}
+
+/// Unparks a number of threads from the front of the queue associated with
+/// `key` depending on the results of a filter function which inspects the
+/// `ParkToken` associated with each thread
+///
+/// See [`parking_lot_core::unpark_filter`](https://docs.rs/parking_lot_core/*/parking_lot_core/fn.unpark_filter.html)
+///
+/// # Safety
+///
+/// You should only call this function with an address that you control, since
+/// you could otherwise interfere with the operation of other synchronization
+/// primitives.
+///
+/// The `filter` and `callback` functions are called while the queue is locked
+/// and must not panic or call into any function in `parking`.
+pub unsafe fn unpark_filter(
+    key: usize,
+    mut filter: impl FnMut(ParkToken) -> FilterOp,
+    callback: impl FnOnce(UnparkResult) -> UnparkToken,
+) -> UnparkResult {
+    let mut bucket = Bucket::lock(key);
+    let queue = bucket.queue();
+    let mut result = UnparkResult::default();
+
+    // the threads that will be unparked, linked through `next` in the order they were parked
+    let mut unparked_head: *const Node = ptr::null();
+    let mut unparked_tail: *const Node = ptr::null();
+
+    let mut prev = ptr::null();
+    let mut current = queue.head;
+
+    while !current.is_null() {
+        let next = (*current).next.get();
+
+        if (*current).key == key {
+            match filter((*current).park_token) {
+                FilterOp::Unpark => {
+                    queue.remove(prev, current);
+                    (*current).next.set(ptr::null());
+
+                    if unparked_tail.is_null() {
+                        unparked_head = current;
+                    } else {
+                        (*unparked_tail).next.set(current);
+                    }
+
+                    unparked_tail = current;
+                    result.unparked_threads += 1;
+                    current = next;
+                    continue;
+                }
+                FilterOp::Skip => result.have_more_threads = true,
+                FilterOp::Stop => {
+                    result.have_more_threads = true;
+                    break;
+                }
+            }
+        }
+
+        prev = current;
+        current = next;
+    }
+
+    if result.unparked_threads != 0 {
+        result.be_fair = queue.be_fair();
+    }
+
+    let unpark_token = callback(result);
+
+    // the threads are woken up while the queue is locked, because they can't be
+    // stored anywhere else without allocating. A woken thread doesn't touch the queue
+    // unless it timed out, so this doesn't contend with the threads being woken up.
+    let mut current = unparked_head;
+
+    while !current.is_null() {
+        // once `woken` is set the node may be freed, so read everything before that
+        let next = (*current).next.get();
+        // without `std` the thread is a `usize` handle
+        #[allow(clippy::clone_on_copy)]
+        let thread = (*current).thread.clone();
+
+        (*current).unpark_token.set(unpark_token);
+        (*current).woken.store(true, Ordering::Release);
+        sys::unpark(thread);
+
+        current = next;
+    }
+
+    drop(bucket);
+
+    result
+}
+
+#[cfg(feature = "std")]
+mod sys {
+    use super::Instant;
+
+    pub type Thread = std::thread::Thread;
+
+    #[inline]
+    pub fn current() -> Thread {
+        std::thread::current()
+    }
+
+    /// Park the current thread, returns false if the timeout expired
+    #[inline]
+    pub fn park(timeout: Option<Instant>) -> bool {
+        match timeout {
+            None => std::thread::park(),
+            Some(timeout) => {
+                let now = Instant::now();
+
+                if now >= timeout {
+                    return false;
+                }
+
+                std::thread::park_timeout(timeout - now);
+            }
+        }
+
+        true
+    }
+
+    #[inline]
+    pub fn unpark(thread: Thread) {
+        thread.unpark()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub use sys::{set_hooks, Hooks};
+
+#[cfg(not(feature = "std"))]
+mod sys {
+    use super::Instant;
+    use core::sync::atomic::{AtomicPtr, Ordering};
+
+    /// The parking primitive of the platform, used when `std` isn't available
+    ///
+    /// Threads are identified by a `usize` handle, which could be a task id or a
+    /// pointer to a thread control block. These are usually plain functions that call
+    /// into the scheduler of the platform.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Hooks {
+        /// Get the handle of the current thread
+        pub current: fn() -> usize,
+
+        /// Block the current thread until it is unparked
+        ///
+        /// This may return spuriously. If the current thread was unparked
+        /// before it is parked, then this must return immediately.
+        pub park: fn(),
+
+        /// Unpark the thread with the given handle
+        pub unpark: fn(usize),
+    }
+
+    static HOOKS: AtomicPtr<Hooks> = AtomicPtr::new(core::ptr::null_mut());
+
+    /// Set the hooks that are used to park and unpark threads
+    ///
+    /// This must be called before any thread parks, and should
+    /// only be changed while there are no parked threads.
+    pub fn set_hooks(hooks: &'static Hooks) {
+        HOOKS.store(hooks as *const Hooks as *mut Hooks, Ordering::Release);
+    }
+
+    fn hooks() -> &'static Hooks {
+        let hooks = HOOKS.load(Ordering::Acquire);
+        assert!(
+            !hooks.is_null(),
+            "`locker::parking::set_hooks` must be called before parking threads"
+        );
+        // SAFETY: `HOOKS` is only set to a `&'static Hooks`
+        unsafe { &*hooks }
+    }
+
+    pub type Thread = usize;
+
+    #[inline]
+    pub fn current() -> Thread {
+        (hooks().current)()
+    }
+
+    /// Park the current thread, returns false if the timeout expired
+    #[inline]
+    pub fn park(timeout: Option<Instant>) -> bool {
+        if let Some(timeout) = timeout {
+            match timeout {}
+        }
+
+        (hooks().park)();
+        true
+    }
+
+    #[inline]
+    pub fn unpark(thread: Thread) {
+        (hooks().unpark)(thread)
+    }
+}
@@ -0,0 +1,29 @@
+//! The commonly used items of `locker`
+//!
+//! ```
+//! use locker::prelude::*;
+//!
+//! let count = Mutex::new(0);
+//! let names: Lazy<RwLock<Vec<&str>>> = Lazy::new(|| RwLock::new(vec!["locker"]));
+//!
+//! *count.lock() += 1;
+//! names.write().push("prelude");
+//!
+//! assert_eq!(*count.lock(), 1);
+//! assert_eq!(names.read().len(), 2);
+//! ```
+//!
+//! The lock types are aliases for the default backends, so you don't need to pick a
+//! backend to get started. Every lock also has a raw type (and a type alias for each
+//! backend) in its module, see [`mutex`](crate::mutex), [`rwlock`](crate::rwlock),
+//! [`remutex`](crate::remutex) and [`once`](crate::once).
+
+#[cfg(all(feature = "extra", feature = "std"))]
+pub use crate::ReentrantMutex;
+pub use crate::{Lazy, OnceCell};
+#[cfg(feature = "extra")]
+pub use crate::{Mutex, RwLock};
+
+pub use crate::exclusive_lock::{ExclusiveGuard, MappedExclusiveGuard, RawExclusiveLock};
+pub use crate::share_lock::{MappedShareGuard, RawShareLock, ShareGuard};
+pub use crate::{Init, RawLockInfo};
@@ -0,0 +1,266 @@
+//! A bounded multi-producer multi-consumer blocking queue
+//!
+//! [`MpmcQueue`] is a ring buffer behind one of this crate's mutexes, with a
+//! [`Condvar`](crate::condvar::Condvar) for each side: producers wait on it while
+//! the queue is full, and consumers wait on it while the queue is empty. Every operation
+//! comes in a blocking, a non-blocking (`try_*`), and a timed (`*_timeout`) flavor.
+//!
+//! ```
+//! use locker::queue::MpmcQueue;
+//!
+//! let queue = MpmcQueue::new(2);
+//!
+//! std::thread::scope(|s| {
+//!     s.spawn(|| (0..10).for_each(|i| queue.push(i)));
+//!
+//!     let sum: i32 = (0..10).map(|_| queue.pop()).sum();
+//!     assert_eq!(sum, 45);
+//! });
+//!
+//! assert_eq!(queue.try_pop(), None);
+//! ```
+
+use crate::condvar::{Condvar, Parkable};
+use crate::exclusive_lock::ExclusiveGuard;
+use crate::mutex::{default::DefaultLock, Mutex, RawMutex};
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// A bounded blocking queue, see the [module docs](self) for details
+pub struct MpmcQueue<T, L: RawMutex = DefaultLock> {
+    buffer: Mutex<L, VecDeque<T>>,
+    capacity: usize,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> MpmcQueue<T> {
+    /// Create an empty queue which holds at most `capacity` items
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero
+    #[inline]
+    pub fn new(capacity: usize) -> Self {
+        Self::with_lock(capacity)
+    }
+}
+
+impl<T, L: RawMutex + Parkable + crate::Init> MpmcQueue<T, L>
+where
+    L::ExclusiveGuardTraits: crate::marker::Inhabitted,
+{
+    /// Create an empty queue which holds at most `capacity` items, and which is
+    /// protected by the lock `L`
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero
+    pub fn with_lock(capacity: usize) -> Self {
+        assert!(
+            capacity != 0,
+            "a queue must be able to hold at least one item"
+        );
+
+        Self {
+            buffer: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+}
+
+impl<T, L: RawMutex + Parkable> MpmcQueue<T, L>
+where
+    L::ExclusiveGuardTraits: crate::marker::Inhabitted,
+{
+    /// The maximum number of items in the queue
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of items in the queue
+    pub fn len(&self) -> usize {
+        self.buffer.lock().len()
+    }
+
+    /// Check if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.buffer.lock().is_empty()
+    }
+
+    /// Check if the queue is full
+    pub fn is_full(&self) -> bool {
+        self.buffer.lock().len() == self.capacity
+    }
+
+    // a waiting producer or consumer may have been woken for the item or slot that was
+    // just taken, and notifications coalesce, so the next waiter is woken while more
+    // items or slots are available
+    fn push_locked(&self, buffer: &mut VecDeque<T>, value: T) {
+        buffer.push_back(value);
+        self.not_empty.notify_one();
+
+        if buffer.len() < self.capacity {
+            self.not_full.notify_one();
+        }
+    }
+
+    fn pop_locked(&self, buffer: &mut VecDeque<T>) -> Option<T> {
+        let value = buffer.pop_front()?;
+        self.not_full.notify_one();
+
+        if !buffer.is_empty() {
+            self.not_empty.notify_one();
+        }
+
+        Some(value)
+    }
+
+    /// Push an item to the back of the queue, blocking while the queue is full
+    pub fn push(&self, value: T) {
+        let mut buffer = self.buffer.lock();
+
+        while buffer.len() == self.capacity {
+            self.not_full.wait(&mut buffer);
+        }
+
+        self.push_locked(&mut buffer, value)
+    }
+
+    /// Push an item to the back of the queue, or give it back if the queue is full
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        let mut buffer = self.buffer.lock();
+
+        if buffer.len() == self.capacity {
+            Err(value)
+        } else {
+            self.push_locked(&mut buffer, value);
+            Ok(())
+        }
+    }
+
+    /// Push an item to the back of the queue, blocking while the queue is full for at
+    /// most `timeout`
+    ///
+    /// If the queue stayed full, the item is given back
+    pub fn push_timeout(&self, value: T, timeout: Duration) -> Result<(), T> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut buffer = self.buffer.lock();
+
+        while buffer.len() == self.capacity {
+            if !Self::wait_until(&self.not_full, &mut buffer, deadline) {
+                return Err(value);
+            }
+        }
+
+        self.push_locked(&mut buffer, value);
+        Ok(())
+    }
+
+    /// Pop an item from the front of the queue, blocking while the queue is empty
+    pub fn pop(&self) -> T {
+        let mut buffer = self.buffer.lock();
+
+        loop {
+            if let Some(value) = self.pop_locked(&mut buffer) {
+                return value;
+            }
+
+            self.not_empty.wait(&mut buffer);
+        }
+    }
+
+    /// Pop an item from the front of the queue, if there is one
+    pub fn try_pop(&self) -> Option<T> {
+        self.pop_locked(&mut self.buffer.lock())
+    }
+
+    /// Pop an item from the front of the queue, blocking while the queue is empty for
+    /// at most `timeout`
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut buffer = self.buffer.lock();
+
+        loop {
+            if let Some(value) = self.pop_locked(&mut buffer) {
+                return Some(value);
+            }
+
+            if !Self::wait_until(&self.not_empty, &mut buffer, deadline) {
+                return None;
+            }
+        }
+    }
+
+    // returns false if the deadline passed, a deadline that overflowed is never reached
+    fn wait_until(
+        cv: &Condvar,
+        buffer: &mut ExclusiveGuard<'_, L, VecDeque<T>>,
+        deadline: Option<Instant>,
+    ) -> bool {
+        match deadline {
+            Some(deadline) => !cv.wait_until(buffer, deadline).timed_out(),
+            None => {
+                cv.wait(buffer);
+                true
+            }
+        }
+    }
+
+    /// Take all items out of the queue
+    pub fn drain(&self) -> VecDeque<T> {
+        let items = core::mem::replace(
+            &mut *self.buffer.lock(),
+            VecDeque::with_capacity(self.capacity),
+        );
+        self.not_full.notify_all();
+        items
+    }
+}
+
+#[test]
+fn timeouts() {
+    let queue = MpmcQueue::new(1);
+    let timeout = Duration::from_millis(10);
+
+    assert_eq!(queue.pop_timeout(timeout), None);
+    assert_eq!(queue.push_timeout(0, timeout), Ok(()));
+    assert_eq!(queue.push_timeout(1, timeout), Err(1));
+    assert_eq!(queue.try_push(2), Err(2));
+    assert!(queue.is_full());
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| assert_eq!(queue.push_timeout(3, Duration::from_secs(10)), Ok(())));
+
+        std::thread::sleep(timeout);
+        assert_eq!(queue.pop(), 0);
+        assert_eq!(queue.pop_timeout(Duration::from_secs(10)), Some(3));
+    })
+    .unwrap();
+
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn contended() {
+    let queue = MpmcQueue::new(4);
+    let total = core::sync::atomic::AtomicUsize::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| (1..=1000).for_each(|i| queue.push(i)));
+            s.spawn(|_| {
+                let sum: usize = (0..1000).map(|_| queue.pop()).sum();
+                total.fetch_add(sum, core::sync::atomic::Ordering::Relaxed);
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(total.into_inner(), 4 * 500 * 1001);
+    assert!(queue.drain().is_empty());
+}
@@ -0,0 +1,60 @@
+//! Snapshots of the lock words of raw locks, for crash dumps and debuggers
+//!
+//! The bit layout of a lock is an implementation detail that may change between releases,
+//! so tooling that reads lock words (from a core dump, or a debugger attached to a stuck
+//! process) should decode them with [`RawStateBits::decode_state`] instead of hard-coding it.
+//!
+//! ```
+//! # #[cfg(feature = "extra")] {
+//! use locker::exclusive_lock::RawExclusiveLock;
+//! use locker::raw_state::{DecodedState, RawStateBits};
+//! use locker::rwlock::spin::SpinLock;
+//!
+//! let lock = SpinLock::new();
+//! lock.exc_lock();
+//!
+//! let bits = lock.raw_state_bits();
+//! assert_eq!(SpinLock::decode_state(bits).exc, 1);
+//! # unsafe { lock.exc_unlock() }
+//! # }
+//! ```
+
+/// The decoded state of a lock word
+///
+/// Locks that don't track some part of the state leave it at its default
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct DecodedState {
+    /// The number of *exc lock*s held
+    ///
+    /// This is at most one, except for splittable locks. Some rwlocks set this while a
+    /// writer is waiting for readers to leave, so `readers` may be non-zero at the same time.
+    pub exc: usize,
+    /// The number of *shr lock*s held
+    pub readers: usize,
+    /// If there are threads parked on the lock
+    pub parked: bool,
+    /// If there is a writer parked until the readers leave
+    pub exc_parked: bool,
+}
+
+/// A raw lock whose state is stored in a single lock word
+pub trait RawStateBits {
+    /// A snapshot of the underlying lock word
+    ///
+    /// This is only meant for debugging, the lock may change state right after the snapshot
+    fn raw_state_bits(&self) -> usize;
+
+    /// Decode a lock word that was read with [`raw_state_bits`](RawStateBits::raw_state_bits)
+    fn decode_state(bits: usize) -> DecodedState
+    where
+        Self: Sized;
+
+    /// Take a snapshot of the lock word, and decode it
+    fn decoded_state(&self) -> DecodedState
+    where
+        Self: Sized,
+    {
+        Self::decode_state(self.raw_state_bits())
+    }
+}
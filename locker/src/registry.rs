@@ -0,0 +1,416 @@
+//! A process-wide registry of locks, for orderly shutdown
+//!
+//! This is enabled by the `registry` feature flag. Locks wrapped in [`Registered`]
+//! register themselves the first time they are acquired, and the registry keeps track
+//! of which of them are currently held.
+//!
+//! Once the process starts tearing down, call [`freeze`]. After that, blocking attempts
+//! to acquire a registered lock panic with a message that names the lock, and `try_lock`s
+//! fail, instead of possibly hanging forever on a lock that will never be released.
+//! Guards that were acquired before the freeze can still be released normally, and
+//! [`report_held`] lists the locks that are still held.
+//!
+//! ```
+//! use locker::mutex::{default::DefaultLock, Mutex};
+//! use locker::registry::{self, Access, Registered};
+//!
+//! static STATE: Mutex<Registered<DefaultLock>, u32> = Mutex::from_raw_parts(locker::Init::INIT, 0);
+//!
+//! let guard = STATE.lock();
+//!
+//! registry::freeze();
+//! assert!(STATE.try_lock().is_none());
+//!
+//! let held = registry::report_held();
+//! assert_eq!(held.len(), 1);
+//! assert_eq!(held[0].access, Access::Exclusive);
+//!
+//! drop(guard);
+//! assert!(registry::report_held().is_empty());
+//! ```
+//!
+//! The registry identifies locks by their address. If a registered lock is moved, its entry
+//! is moved to the new address the next time the lock is acquired, but this is meant for
+//! long-lived locks, like statics or locks behind an `Arc`. Every acquisition of a registered
+//! lock also updates the registry, so it isn't a good fit for hot locks.
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::collections::BTreeMap;
+
+use crate::exclusive_lock::{
+    RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair, RawExclusiveLockTimed,
+};
+use crate::mutex::spin::{Mutex, SpinLock};
+use crate::mutex::RawMutex;
+use crate::rwlock::RawRwLock;
+use crate::share_lock::{RawShareLock, RawShareLockFair, RawShareLockTimed, RawShareLockUpgrade};
+use crate::{Init, RawLockInfo, RawTimedLock};
+
+static FROZEN: AtomicBool = AtomicBool::new(false);
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+static REGISTRY: Mutex<BTreeMap<usize, Entry>> = SpinLock::mutex(BTreeMap::new());
+
+/// The kind of access a held lock is held with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// the lock is held by a *exc lock*
+    Exclusive,
+    /// the lock is held by one or more *shr lock*s
+    Shared,
+}
+
+/// A registered lock that is still held, see [`report_held`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Held {
+    /// The address of the lock
+    pub addr: usize,
+    /// The [name](RawLockInfo::name) of the lock, if it has one
+    pub name: Option<&'static str>,
+    /// The kind of access the lock is held with
+    pub access: Access,
+    /// The number of guards that hold the lock, this is always 1 for exclusive access
+    pub guards: usize,
+}
+
+struct Entry {
+    // the id of the `Registered` lock that this entry belongs to
+    id: usize,
+    name: Option<&'static str>,
+    exclusive: bool,
+    shared: usize,
+}
+
+/// Mark every registered lock as shutting down
+///
+/// After this, acquiring a registered lock panics, and trying to acquire one fails,
+/// see the [module docs](self) for details. Threads that were already blocked on a
+/// registered lock keep waiting.
+pub fn freeze() {
+    FROZEN.store(true, Ordering::Release);
+}
+
+/// Check if [`freeze`] was called
+#[inline]
+pub fn is_frozen() -> bool {
+    FROZEN.load(Ordering::Acquire)
+}
+
+/// List the registered locks that are currently held, ordered by address
+pub fn report_held() -> Vec<Held> {
+    REGISTRY
+        .lock()
+        .iter()
+        .filter(|(_, entry)| entry.exclusive || entry.shared != 0)
+        .map(|(&addr, entry)| Held {
+            addr,
+            name: entry.name,
+            access: if entry.exclusive {
+                Access::Exclusive
+            } else {
+                Access::Shared
+            },
+            guards: if entry.exclusive { 1 } else { entry.shared },
+        })
+        .collect()
+}
+
+/// Wraps a lock and registers it in the [registry](self)
+#[derive(Debug, Default)]
+pub struct Registered<L: ?Sized> {
+    // the id of this lock in the registry, or 0 if it isn't registered yet
+    id: AtomicUsize,
+    lock: L,
+}
+
+impl<L> Registered<L> {
+    /// Wrap `lock`, it is registered the first time it is acquired
+    #[inline]
+    pub const fn new(lock: L) -> Self {
+        Self {
+            id: AtomicUsize::new(0),
+            lock,
+        }
+    }
+}
+
+impl<L: ?Sized> Registered<L> {
+    /// The underlying lock
+    #[inline]
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    #[inline]
+    fn addr(&self) -> usize {
+        self as *const Self as *const () as usize
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Entry)) {
+        if let Some(entry) = REGISTRY.lock().get_mut(&self.addr()) {
+            f(entry)
+        }
+    }
+}
+
+impl<L: ?Sized + RawLockInfo> Registered<L> {
+    /// Panic if the registry is frozen, this must be called before blocking on the lock
+    #[inline]
+    #[track_caller]
+    fn check_frozen(&self) {
+        if is_frozen() {
+            self.frozen()
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn frozen(&self) -> ! {
+        panic!(
+            "lock `{}` at {:#x} was acquired after the lock registry was frozen",
+            self.lock.name().unwrap_or("<unnamed>"),
+            self.addr()
+        )
+    }
+
+    fn acquired(&self, f: impl FnOnce(&mut Entry)) {
+        let mut registry = REGISTRY.lock();
+        let mut id = self.id.load(Ordering::Relaxed);
+
+        match registry.get_mut(&self.addr()) {
+            Some(entry) if entry.id == id => return f(entry),
+            _ if id == 0 => {
+                id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+                self.id.store(id, Ordering::Relaxed);
+            }
+            // this lock was moved after it was registered, so remove the entry
+            // at the old address, the lock can't have been held while it moved
+            _ => registry.retain(|_, entry| entry.id != id),
+        }
+
+        // any entry at this address belongs to a lock that moved away
+        let mut entry = Entry {
+            id,
+            name: self.lock.name(),
+            exclusive: false,
+            shared: 0,
+        };
+
+        f(&mut entry);
+        registry.insert(self.addr(), entry);
+    }
+
+    #[inline]
+    fn acquired_exc(&self, locked: bool) -> bool {
+        if locked {
+            self.acquired(|entry| entry.exclusive = true);
+        }
+
+        locked
+    }
+
+    #[inline]
+    fn acquired_shr(&self, locked: bool) -> bool {
+        if locked {
+            self.acquired(|entry| entry.shared += 1);
+        }
+
+        locked
+    }
+
+    #[inline]
+    fn released_exc(&self) {
+        self.update(|entry| entry.exclusive = false);
+    }
+
+    #[inline]
+    fn released_shr(&self) {
+        self.update(|entry| entry.shared = entry.shared.saturating_sub(1));
+    }
+}
+
+impl<L: ?Sized> Drop for Registered<L> {
+    fn drop(&mut self) {
+        let id = *self.id.get_mut();
+
+        if id == 0 {
+            return;
+        }
+
+        let mut registry = REGISTRY.lock();
+
+        match registry.get(&self.addr()) {
+            Some(entry) if entry.id == id => drop(registry.remove(&self.addr())),
+            // this lock was moved since it was last acquired
+            _ => registry.retain(|_, entry| entry.id != id),
+        }
+    }
+}
+
+unsafe impl<L: RawMutex> RawMutex for Registered<L> {}
+unsafe impl<L: RawRwLock> RawRwLock for Registered<L> {}
+
+impl<L: Init> Init for Registered<L> {
+    const INIT: Self = Self::new(Init::INIT);
+}
+
+unsafe impl<L: RawLockInfo + ?Sized> RawLockInfo for Registered<L> {
+    type ExclusiveGuardTraits = <L as RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <L as RawLockInfo>::ShareGuardTraits;
+
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        self.lock.name()
+    }
+}
+
+impl<L: RawTimedLock + ?Sized> RawTimedLock for Registered<L> {
+    type Instant = L::Instant;
+    type Duration = L::Duration;
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLock + RawLockInfo> RawExclusiveLock for Registered<L> {
+    #[track_caller]
+    fn exc_lock(&self) {
+        self.check_frozen();
+        self.lock.exc_lock();
+        self.acquired_exc(true);
+    }
+
+    fn exc_try_lock(&self) -> bool {
+        !is_frozen() && self.acquired_exc(self.lock.exc_try_lock())
+    }
+
+    unsafe fn exc_unlock(&self) {
+        self.released_exc();
+        self.lock.exc_unlock()
+    }
+
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        self.lock.exc_bump()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockFair + RawLockInfo> RawExclusiveLockFair for Registered<L> {
+    unsafe fn exc_unlock_fair(&self) {
+        self.released_exc();
+        self.lock.exc_unlock_fair()
+    }
+
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        self.lock.exc_bump_fair()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockTimed> RawExclusiveLockTimed for Registered<L> {
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        !is_frozen() && self.acquired_exc(self.lock.exc_try_lock_until(instant))
+    }
+
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        !is_frozen() && self.acquired_exc(self.lock.exc_try_lock_for(duration))
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockDowngrade + RawLockInfo> RawExclusiveLockDowngrade
+    for Registered<L>
+{
+    unsafe fn downgrade(&self) {
+        self.update(|entry| {
+            entry.exclusive = false;
+            entry.shared += 1;
+        });
+
+        self.lock.downgrade()
+    }
+
+    unsafe fn try_downgrade(&self) -> bool {
+        let downgraded = self.lock.try_downgrade();
+
+        if downgraded {
+            self.update(|entry| {
+                entry.exclusive = false;
+                entry.shared += 1;
+            });
+        }
+
+        downgraded
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLock + RawLockInfo> RawShareLock for Registered<L> {
+    #[track_caller]
+    fn shr_lock(&self) {
+        self.check_frozen();
+        self.lock.shr_lock();
+        self.acquired_shr(true);
+    }
+
+    fn shr_try_lock(&self) -> bool {
+        !is_frozen() && self.acquired_shr(self.lock.shr_try_lock())
+    }
+
+    unsafe fn shr_split(&self) {
+        // the lock is already held, so this can't block
+        self.lock.shr_split();
+        self.acquired_shr(true);
+    }
+
+    unsafe fn shr_try_split(&self) -> bool {
+        self.acquired_shr(self.lock.shr_try_split())
+    }
+
+    unsafe fn shr_unlock(&self) {
+        self.released_shr();
+        self.lock.shr_unlock()
+    }
+
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        self.lock.shr_bump()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockFair + RawLockInfo> RawShareLockFair for Registered<L> {
+    unsafe fn shr_unlock_fair(&self) {
+        self.released_shr();
+        self.lock.shr_unlock_fair()
+    }
+
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        self.lock.shr_bump_fair()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockTimed> RawShareLockTimed for Registered<L> {
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        !is_frozen() && self.acquired_shr(self.lock.shr_try_lock_until(instant))
+    }
+
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        !is_frozen() && self.acquired_shr(self.lock.shr_try_lock_for(duration))
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockUpgrade + RawLockInfo> RawShareLockUpgrade for Registered<L> {
+    unsafe fn upgrade(&self) {
+        self.lock.upgrade();
+
+        self.update(|entry| {
+            entry.shared = entry.shared.saturating_sub(1);
+            entry.exclusive = true;
+        });
+    }
+
+    unsafe fn try_upgrade(&self) -> bool {
+        let upgraded = self.lock.try_upgrade();
+
+        if upgraded {
+            self.update(|entry| {
+                entry.shared = entry.shared.saturating_sub(1);
+                entry.exclusive = true;
+            });
+        }
+
+        upgraded
+    }
+}
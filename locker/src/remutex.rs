@@ -1,6 +1,6 @@
 //! a reentrant mutex
 
-use core::cell::UnsafeCell;
+use crate::cell::SharedCell;
 use core::num::NonZeroUsize;
 
 use crate::share_lock::{RawShareLock, RawShareLockTimed, ShareGuard};
@@ -11,6 +11,19 @@ pub mod lock;
 #[cfg(feature = "extra")]
 pub mod counter;
 
+pub mod custom;
+
+pub mod ceiling;
+
+#[cfg(feature = "extra")]
+pub mod state;
+
+#[cfg(feature = "extra")]
+pub mod rwlock;
+
+mod outermost;
+pub use outermost::OutermostGuard;
+
 #[cfg(feature = "std")]
 pub mod std_thread;
 
@@ -40,6 +53,32 @@ pub unsafe trait ThreadInfo {
 /// own a *shr lock* at the same time.
 pub unsafe trait RawReentrantMutex: crate::RawLockInfo + RawShareLock {}
 
+/// Reentrant mutexes that can hand out unique access, when the current thread
+/// only entered them once
+///
+/// # Safety
+///
+/// After `try_make_exclusive` returns true, no other *shr lock* can be acquired until
+/// `make_shared` is called, not even by the current thread
+pub unsafe trait RawReentrantMutexExclusive: RawReentrantMutex {
+    /// Stop the current thread from re-entering the lock
+    ///
+    /// Returns false without doing anything if the current thread owns more than one *shr lock*
+    ///
+    /// # Safety
+    ///
+    /// The current thread must own a *shr lock*
+    unsafe fn try_make_exclusive(&self) -> bool;
+
+    /// Allow the current thread to re-enter the lock again
+    ///
+    /// # Safety
+    ///
+    /// `try_make_exclusive` must have returned true, and `make_shared` must not
+    /// have been called since then
+    unsafe fn make_shared(&self);
+}
+
 /// A mutual exclusion primitive useful for protecting shared data
 ///
 /// This reentrant mutex will block threads waiting for the lock to become available.
@@ -54,7 +93,7 @@ pub unsafe trait RawReentrantMutex: crate::RawLockInfo + RawShareLock {}
 #[repr(C)]
 pub struct ReentrantMutex<L, T: ?Sized> {
     raw: raw::ReentrantMutex<L>,
-    value: UnsafeCell<T>,
+    value: SharedCell<T>,
 }
 
 impl<L: RawReentrantMutex + crate::Init, T: Default> Default for ReentrantMutex<L, T> {
@@ -74,7 +113,7 @@ impl<L, T> ReentrantMutex<L, T> {
     pub const fn from_raw_parts(raw: raw::ReentrantMutex<L>, value: T) -> Self {
         Self {
             raw,
-            value: UnsafeCell::new(value),
+            value: SharedCell::new(value),
         }
     }
 
@@ -100,7 +139,7 @@ impl<L, T: ?Sized> ReentrantMutex<L, T> {
 
     /// Get a raw pointer to the value
     pub fn as_mut_ptr(&self) -> *mut T {
-        self.value.get()
+        self.value.as_ptr()
     }
 
     cfg_if::cfg_if! {
@@ -119,7 +158,7 @@ impl<L, T: ?Sized> ReentrantMutex<L, T> {
             /// ---the mutable borrow statically guarantees no locks exist.
             #[inline]
             pub const fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                unsafe { &mut *self.value.as_ptr() }
             }
         } else {
             /// # Safety
@@ -136,7 +175,7 @@ impl<L, T: ?Sized> ReentrantMutex<L, T> {
             /// ---the mutable borrow statically guarantees no locks exist.
             #[inline]
             pub fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                self.value.get_mut()
             }
         }
     }
@@ -167,7 +206,7 @@ where
     #[inline]
     fn wrap<'s>(&'s self, raw: crate::share_lock::RawShareGuard<'s, L>) -> ShareGuard<'s, L, T> {
         assert!(core::ptr::eq(self.raw.inner(), raw.inner()));
-        unsafe { ShareGuard::from_raw_parts(raw, self.value.get()) }
+        unsafe { ShareGuard::from_raw_parts(raw, self.value.as_ptr()) }
     }
 
     /// Acquires a lock, blocking the current thread until it is able to do so.
@@ -201,6 +240,32 @@ where
     }
 }
 
+impl<L: RawReentrantMutexExclusive, T: ?Sized> ReentrantMutex<L, T>
+where
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Acquires a lock with unique access to the data, if the current thread doesn't
+    /// already hold the lock
+    ///
+    /// This blocks until no other thread holds the lock, like [`ReentrantMutex::lock`],
+    /// but returns None if the current thread already holds a guard. While the returned
+    /// guard is alive the mutex can't be re-entered, so locking it again on the current
+    /// thread blocks just like a normal mutex, and `try_lock` returns None.
+    ///
+    /// This replaces the usual `ReentrantMutex<RefCell<T>>` for code that
+    /// only needs to mutate the data from the outermost lock.
+    #[inline]
+    pub fn lock_mut_outermost(&self) -> Option<OutermostGuard<'_, L, T>> {
+        let raw = self.raw.lock();
+
+        if unsafe { raw.inner().try_make_exclusive() } {
+            Some(unsafe { OutermostGuard::from_raw_parts(raw, self.value.as_ptr()) })
+        } else {
+            None
+        }
+    }
+}
+
 impl<L: RawReentrantMutex + RawShareLockTimed, T: ?Sized> ReentrantMutex<L, T>
 where
     L::ShareGuardTraits: crate::Inhabitted,
@@ -0,0 +1,359 @@
+//! A reentrant mutex that follows the immediate priority ceiling protocol
+//!
+//! Every [`Ceiling`] lock has a ceiling, the highest priority of any task that uses it.
+//! Before a task acquires the lock, its priority is raised to the ceiling, and once it
+//! releases the outermost lock, its previous priority is restored. So while the lock is
+//! held, no other task that uses it can preempt the owner, and a high priority task can
+//! only be blocked for as long as a single critical section takes. This prevents unbounded
+//! priority inversion, and on a single core it also means that the lock is never contended,
+//! so spin locks can be used even if the scheduler is preemptive.
+//!
+//! Locks may be nested, as long as they are released in the opposite order that they
+//! were acquired in. The priority of a task never goes down while it acquires more
+//! locks, so locking a lock with a lower ceiling while holding one with a higher ceiling
+//! is fine.
+//!
+//! The scheduler is accessed through the [`Priority`] trait, which is supplied by the caller,
+//! for example by an RTOS binding, or by masking interrupts up to some level.
+//!
+//! ```
+//! use core::cell::Cell;
+//! use locker::mutex::spin::SpinLock;
+//! use locker::remutex::ceiling::{Ceiling, CeilingMutex, Priority};
+//! use locker::remutex::lock::ReLock;
+//!
+//! thread_local! {
+//!     static CURRENT: Cell<u8> = const { Cell::new(1) };
+//!     static HELD: Cell<usize> = const { Cell::new(0) };
+//! }
+//!
+//! // a stand-in for the scheduler of the current task
+//! struct TaskPriority;
+//!
+//! impl Priority for TaskPriority {
+//!     type Level = u8;
+//!
+//!     fn current(&self) -> u8 {
+//!         CURRENT.with(Cell::get)
+//!     }
+//!
+//!     fn set(&self, level: u8) {
+//!         CURRENT.with(|current| current.set(level))
+//!     }
+//!
+//!     fn held(&self) -> usize {
+//!         HELD.with(Cell::get)
+//!     }
+//!
+//!     fn set_held(&self, held: usize) {
+//!         HELD.with(|current| current.set(held))
+//!     }
+//! }
+//!
+//! let mutex: CeilingMutex<ReLock<SpinLock>, _, _> = Ceiling::mutex(TaskPriority, 5, 0);
+//!
+//! let guard = mutex.lock();
+//! assert_eq!(TaskPriority.current(), 5);
+//!
+//! // re-entering the lock keeps the raised priority
+//! let nested = mutex.lock();
+//! drop(nested);
+//! assert_eq!(TaskPriority.current(), 5);
+//!
+//! drop(guard);
+//! assert_eq!(TaskPriority.current(), 1);
+//! ```
+
+use core::cell::UnsafeCell;
+
+use super::{RawReentrantMutex, RawReentrantMutexExclusive};
+use crate::share_lock::{RawShareLock, RawShareLockFair, RawShareLockTimed};
+use crate::{Init, RawLockInfo, RawTimedLock};
+
+/// a reentrant mutex that follows the immediate priority ceiling protocol
+pub type CeilingMutex<L, P, T> = super::ReentrantMutex<Ceiling<L, P>, T>;
+
+/// The priority of the currently running task
+///
+/// Implementations don't need to be correct for [`Ceiling`] to be memory safe, but if
+/// `set` doesn't change the priority that the scheduler uses, then [`Ceiling`] degrades
+/// into a plain reentrant mutex.
+pub trait Priority {
+    /// A priority level, greater levels are more urgent
+    ///
+    /// If the scheduler treats lower numbers as more urgent, like the interrupt priorities
+    /// on many microcontrollers, use [`core::cmp::Reverse`] to flip the order.
+    type Level: Copy + Ord;
+
+    /// The priority of the current task
+    fn current(&self) -> Self::Level;
+
+    /// Change the priority of the current task
+    ///
+    /// When this raises the priority, it must take effect before this returns, so that
+    /// the current task can't be preempted by a task that has a lower priority than `level`
+    fn set(&self, level: Self::Level);
+
+    /// The number of [`Ceiling`] locks that the current task holds
+    ///
+    /// This is per task bookkeeping, which starts at zero for every task, and is only
+    /// changed through [`set_held`](Self::set_held). It is used to check that the locks
+    /// are released in the opposite order that they were acquired in.
+    fn held(&self) -> usize;
+
+    /// Change the number of [`Ceiling`] locks that the current task holds
+    fn set_held(&self, held: usize);
+}
+
+/// Wraps a raw reentrant mutex, and raises the priority of the owner to a ceiling
+/// while it is locked
+///
+/// See the [module docs](self) for details.
+///
+/// # Panics
+///
+/// Locking panics if the current task doesn't hold any `Ceiling` locks, and its priority
+/// is above the ceiling, because the ceiling must be at least the priority of every task
+/// that uses the lock.
+///
+/// Releasing a `Ceiling` lock while the current task holds another `Ceiling` lock that
+/// was acquired after it panics, because restoring the priority would lower it below
+/// the ceiling of the lock that is still held.
+pub struct Ceiling<L, P: Priority> {
+    lock: L,
+    priority: P,
+    ceiling: P::Level,
+    // these are only accessed by the owner of the lock
+    count: UnsafeCell<usize>,
+    restore: UnsafeCell<Option<(P::Level, usize)>>,
+}
+
+unsafe impl<L: Sync, P: Priority + Sync> Sync for Ceiling<L, P> where P::Level: Send + Sync {}
+
+impl<L, P: Priority> Ceiling<L, P> {
+    /// Wrap `lock`, raising the priority of its owner to `ceiling`
+    #[inline]
+    pub const fn new(lock: L, priority: P, ceiling: P::Level) -> Self {
+        Self {
+            lock,
+            priority,
+            ceiling,
+            count: UnsafeCell::new(0),
+            restore: UnsafeCell::new(None),
+        }
+    }
+
+    /// The underlying lock
+    #[inline]
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    /// The priority provider
+    #[inline]
+    pub const fn priority(&self) -> &P {
+        &self.priority
+    }
+
+    /// The priority that the owner of this lock runs at
+    #[inline]
+    pub fn ceiling(&self) -> P::Level {
+        self.ceiling
+    }
+
+    /// Raise the current priority to the ceiling, returning the previous priority
+    #[inline]
+    #[track_caller]
+    fn raise(&self) -> P::Level {
+        let previous = self.priority.current();
+
+        // if the task holds other `Ceiling` locks, its priority may already be raised
+        // above this ceiling, and that's fine
+        assert!(
+            previous <= self.ceiling || self.priority.held() != 0,
+            "tried to lock a `Ceiling` lock from a task with a priority above its ceiling"
+        );
+
+        if previous < self.ceiling {
+            self.priority.set(self.ceiling);
+        }
+
+        previous
+    }
+
+    /// Record a *shr lock*, this must be called while holding the lock
+    #[inline]
+    unsafe fn acquired(&self, previous: P::Level) {
+        let count = &mut *self.count.get();
+
+        if *count == 0 {
+            let held = self.priority.held();
+            self.priority.set_held(held + 1);
+            *self.restore.get() = Some((previous, held));
+        }
+
+        *count += 1;
+    }
+
+    /// Acquire the lock with `acquire`, and restore the priority if that fails
+    #[inline]
+    #[track_caller]
+    fn lock_with(&self, acquire: impl FnOnce(&L) -> bool) -> bool {
+        let previous = self.raise();
+        let locked = acquire(&self.lock);
+
+        if locked {
+            unsafe { self.acquired(previous) }
+        } else {
+            self.priority.set(previous);
+        }
+
+        locked
+    }
+
+    /// Release a *shr lock* with `release`, and restore the priority once
+    /// the outermost lock is released
+    #[inline]
+    #[track_caller]
+    unsafe fn unlock_with(&self, release: impl FnOnce(&L)) {
+        let count = &mut *self.count.get();
+
+        if *count != 1 {
+            *count -= 1;
+            return release(&self.lock);
+        }
+
+        let (previous, held) =
+            (*self.restore.get()).expect("a held `Ceiling` lock saves the priority");
+
+        assert_eq!(
+            self.priority.held(),
+            held + 1,
+            "`Ceiling` locks must be unlocked in the opposite order they were locked in"
+        );
+
+        *count = 0;
+        *self.restore.get() = None;
+        self.priority.set_held(held);
+        release(&self.lock);
+        self.priority.set(previous);
+    }
+}
+
+impl<L: RawReentrantMutex + Init, P: Priority> Ceiling<L, P> {
+    /// create a new reentrant mutex, which raises the priority of its owner to `ceiling`
+    pub const fn mutex<T>(priority: P, ceiling: P::Level, value: T) -> CeilingMutex<L, P, T> {
+        unsafe {
+            CeilingMutex::from_raw_parts(
+                super::raw::ReentrantMutex::from_raw(Self::new(L::INIT, priority, ceiling)),
+                value,
+            )
+        }
+    }
+}
+
+unsafe impl<L: RawReentrantMutex, P: Priority> RawReentrantMutex for Ceiling<L, P> {}
+
+unsafe impl<L: RawReentrantMutexExclusive, P: Priority> RawReentrantMutexExclusive
+    for Ceiling<L, P>
+{
+    #[inline]
+    unsafe fn try_make_exclusive(&self) -> bool {
+        self.lock.try_make_exclusive()
+    }
+
+    #[inline]
+    unsafe fn make_shared(&self) {
+        self.lock.make_shared()
+    }
+}
+
+unsafe impl<L: RawLockInfo, P: Priority> RawLockInfo for Ceiling<L, P> {
+    type ExclusiveGuardTraits = <L as RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <L as RawLockInfo>::ShareGuardTraits;
+
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        self.lock.name()
+    }
+}
+
+impl<L: RawTimedLock, P: Priority> RawTimedLock for Ceiling<L, P> {
+    type Instant = L::Instant;
+    type Duration = L::Duration;
+}
+
+unsafe impl<L: RawShareLock, P: Priority> RawShareLock for Ceiling<L, P> {
+    #[inline]
+    #[track_caller]
+    fn shr_lock(&self) {
+        self.lock_with(|lock| {
+            lock.shr_lock();
+            true
+        });
+    }
+
+    #[inline]
+    #[track_caller]
+    fn shr_try_lock(&self) -> bool {
+        self.lock_with(L::shr_try_lock)
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        // the current task owns the lock, so it already runs at the ceiling
+        self.lock.shr_split();
+        *self.count.get() += 1;
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        let split = self.lock.shr_try_split();
+
+        if split {
+            *self.count.get() += 1;
+        }
+
+        split
+    }
+
+    #[inline]
+    #[track_caller]
+    unsafe fn shr_unlock(&self) {
+        self.unlock_with(|lock| lock.shr_unlock())
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        // the priority stays at the ceiling, so only tasks that can preempt
+        // the owner anyways get a chance to take the lock
+        self.lock.shr_bump()
+    }
+}
+
+unsafe impl<L: RawShareLockFair, P: Priority> RawShareLockFair for Ceiling<L, P> {
+    #[inline]
+    #[track_caller]
+    unsafe fn shr_unlock_fair(&self) {
+        self.unlock_with(|lock| lock.shr_unlock_fair())
+    }
+
+    #[inline]
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        self.lock.shr_bump_fair()
+    }
+}
+
+unsafe impl<L: RawShareLockTimed, P: Priority> RawShareLockTimed for Ceiling<L, P> {
+    #[inline]
+    #[track_caller]
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.lock_with(|lock| lock.shr_try_lock_until(instant))
+    }
+
+    #[inline]
+    #[track_caller]
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.lock_with(|lock| lock.shr_try_lock_for(duration))
+    }
+}
@@ -0,0 +1,70 @@
+//! Thread info supplied by a user function
+//!
+//! This is useful for custom schedulers, like green threads, where many tasks
+//! run on a single os thread, or for platforms where the std thread info is not
+//! available.
+
+use core::num::NonZeroUsize;
+
+/// Gives the current thread's id by calling a function
+///
+/// # Example
+///
+/// Using the id of the currently running green thread as the owner of a reentrant mutex
+///
+/// ```
+/// use core::num::NonZeroUsize;
+/// use core::sync::atomic::{AtomicUsize, Ordering};
+/// use locker::mutex::spin::SpinLock;
+/// use locker::remutex::{custom::FnThreadInfo, lock::ReLock, ReentrantMutex};
+///
+/// // the id of the green thread that is currently running
+/// static CURRENT_TASK: AtomicUsize = AtomicUsize::new(1);
+///
+/// fn current_task() -> NonZeroUsize {
+///     NonZeroUsize::new(CURRENT_TASK.load(Ordering::Relaxed)).unwrap()
+/// }
+///
+/// // Safety: the scheduler never runs two tasks with the same id at the same time
+/// let thread_info = unsafe { FnThreadInfo::new(current_task) };
+/// let remutex = ReentrantMutex::<ReLock<SpinLock, u8, _>, _>::with_thread_info(thread_info, 0);
+///
+/// let a = remutex.lock();
+/// let b = remutex.lock();
+///
+/// // the scheduler switches to another task
+/// CURRENT_TASK.store(2, Ordering::Relaxed);
+/// assert!(remutex.try_lock().is_none());
+///
+/// // and back
+/// CURRENT_TASK.store(1, Ordering::Relaxed);
+/// assert_eq!(*a + *b, 0);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct FnThreadInfo<F>(F);
+
+impl<F> FnThreadInfo<F> {
+    /// Create a new thread info from the given function
+    ///
+    /// # Safety
+    ///
+    /// `id` must uphold the safety requirements of [`ThreadInfo`](super::ThreadInfo),
+    /// i.e. no two active threads may get the same id
+    #[inline]
+    pub const unsafe fn new(id: F) -> Self {
+        Self(id)
+    }
+
+    /// Get the underlying function
+    #[inline]
+    pub fn into_inner(self) -> F {
+        self.0
+    }
+}
+
+unsafe impl<F: Fn() -> NonZeroUsize> super::ThreadInfo for FnThreadInfo<F> {
+    #[inline]
+    fn id(&self) -> NonZeroUsize {
+        (self.0)()
+    }
+}
@@ -44,6 +44,24 @@ impl GlobalLock {
         unsafe { core::mem::transmute(value) }
     }
 
+    /// Run `f` with a fresh global lock set on the current thread
+    ///
+    /// While `f` runs, all global reentrant mutexes locked on this thread use a lock set
+    /// that is private to this call, so tests that run in parallel can't contend with each
+    /// other (or with leftover state from another test) through the shared global set.
+    /// Calls may be nested, the previous set is restored when `f` returns or panics.
+    ///
+    /// # Safety
+    ///
+    /// * no guard of a global lock created inside `f` may outlive `f`
+    /// * no global lock may be locked inside `f` and unlocked outside of it, or vice versa
+    /// * a global lock must not be held both inside and outside of `f` at the same time,
+    ///   whether on this thread or another one, because only this thread sees the fresh set
+    #[cfg(feature = "test-util")]
+    pub unsafe fn with_isolated_set<R>(f: impl FnOnce() -> R) -> R {
+        crate::isolate::with_isolated(&ISOLATED, f)
+    }
+
     #[inline(always)]
     #[allow(clippy::trivially_copy_pass_by_ref)]
     fn addr(&self) -> usize {
@@ -53,6 +71,13 @@ impl GlobalLock {
     #[inline(always)]
     #[allow(clippy::trivially_copy_pass_by_ref)]
     fn get(&self) -> &'static ReLock {
+        #[cfg(feature = "test-util")]
+        {
+            if let Some(set) = crate::isolate::current(&ISOLATED) {
+                return &set[self.addr()];
+            }
+        }
+
         &GLOBAL[self.addr()]
     }
 
@@ -75,6 +100,11 @@ impl GlobalLock {
 
 type ReLock = crate::remutex::lock::ReLock<DefaultLock>;
 
+#[cfg(feature = "test-util")]
+std::thread_local! {
+    static ISOLATED: crate::isolate::Slot<[ReLock; 61]> = const { core::cell::Cell::new(None) };
+}
+
 // 61 because it is a large prime number,
 // this will reduce contention between unrelated locks
 // because unrealated locks will be unlikely to pick up the same lock,
@@ -102,8 +132,8 @@ static GLOBAL: [ReLock; 61] = [
     crate::Init::INIT,
 ];
 
-impl crate::Init for GlobalLock {
-    const INIT: Self = Self;
+crate::impl_init! {
+    GlobalLock = Self
 }
 
 unsafe impl crate::remutex::RawReentrantMutex for GlobalLock {}
@@ -128,13 +158,18 @@ unsafe impl RawShareLock for GlobalLock {
         self.get().shr_split()
     }
 
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.get().shr_try_split()
+    }
+
     #[inline]
     unsafe fn shr_unlock(&self) {
         self.get().shr_unlock()
     }
 
     #[inline]
-    unsafe fn shr_bump(&self) {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
         self.get().shr_bump()
     }
 }
@@ -147,7 +182,7 @@ unsafe impl RawShareLockFair for GlobalLock {
     }
 
     #[inline]
-    unsafe fn shr_bump_fair(&self) {
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
         self.get().shr_bump_fair()
     }
 }
@@ -170,3 +205,37 @@ unsafe impl crate::share_lock::RawShareLockTimed for GlobalLock {
         self.get().shr_try_lock_for(duration)
     }
 }
+
+#[test]
+fn recursive_traversal() {
+    struct Node {
+        lock: ReentrantMutex<()>,
+        edges: Vec<usize>,
+    }
+
+    fn visit(graph: &[Node], node: usize, depth: usize, visited: &mut usize) {
+        let _guard = graph[node].lock.lock();
+        *visited += 1;
+
+        if depth != 0 {
+            for &next in graph[node].edges.iter() {
+                visit(graph, next, depth - 1, visited);
+            }
+        }
+    }
+
+    // every node has an edge to itself and to the next node, so nodes are revisited
+    // while they are still locked
+    let graph = (0..8)
+        .map(|i| Node {
+            lock: crate::mutex::global::GlobalLock::reentrant_mutex(()),
+            edges: vec![i, (i + 1) % 8],
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(core::mem::size_of::<ReentrantMutex<()>>(), 0);
+
+    let mut visited = 0;
+    visit(&graph, 0, 4, &mut visited);
+    assert_eq!(visited, 31);
+}
@@ -1,13 +1,10 @@
 //! A wrapper around an [`RawExclusiveLock`] that allows it to be used as a
 //! reentrant lock
 
-use core::cell::Cell;
-use core::sync::atomic::{AtomicUsize, Ordering};
-
 use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockFair, RawExclusiveLockTimed};
 use crate::share_lock::{RawShareLock, RawShareLockFair, RawShareLockTimed};
 
-use super::{counter::Scalar, ThreadInfo};
+use super::{counter::Scalar, state::ReentrantState, ThreadInfo};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
@@ -15,18 +12,14 @@ cfg_if::cfg_if! {
         /// reentrant mutex
         pub struct ReLock<L, S = super::counter::SubWord, I = super::std_thread::StdThreadInfo> {
             inner: L,
-            thread_info: I,
-            owner: AtomicUsize,
-            count: Cell<S>,
+            state: ReentrantState<S, I>,
         }
     } else {
         /// A wrapper around a [`RawExclusiveLock`] that allows it to be used as a
         /// reentrant mutex
         pub struct ReLock<L, S, I> {
             inner: L,
-            thread_info: I,
-            owner: AtomicUsize,
-            count: Cell<S>,
+            state: ReentrantState<S, I>,
         }
     }
 }
@@ -41,12 +34,21 @@ impl<L, I, S> ReLock<L, S, I> {
     pub const unsafe fn from_raw_parts(inner: L, thread_info: I, counter: S) -> Self {
         Self {
             inner,
-            thread_info,
-            owner: AtomicUsize::new(0),
-            count: Cell::new(counter),
+            state: ReentrantState::from_raw_parts(thread_info, counter),
         }
     }
 
+    /// Create a new unlocked `ReLock` that uses the given thread info to
+    /// decide which thread owns the lock
+    #[inline]
+    pub fn with_thread_info(thread_info: I) -> Self
+    where
+        L: crate::Init,
+        S: Scalar,
+    {
+        unsafe { Self::from_raw_parts(L::INIT, thread_info, S::ZERO) }
+    }
+
     /// the underlying lock
     pub fn inner(&self) -> &L {
         &self.inner
@@ -54,7 +56,25 @@ impl<L, I, S> ReLock<L, S, I> {
 
     /// the underlying thread info
     pub fn thread_info(&self) -> &I {
-        &self.thread_info
+        self.state.thread_info()
+    }
+
+    /// the owner and re-entry count of this lock
+    pub fn state(&self) -> &ReentrantState<S, I> {
+        &self.state
+    }
+}
+
+impl<L: crate::mutex::RawMutex + crate::Init, S: Scalar, I: ThreadInfo, T>
+    super::ReentrantMutex<ReLock<L, S, I>, T>
+{
+    /// Create a new reentrant mutex that uses the given thread info to
+    /// decide which thread owns the lock
+    #[inline]
+    pub fn with_thread_info(thread_info: I, value: T) -> Self {
+        let raw =
+            unsafe { super::raw::ReentrantMutex::from_raw(ReLock::with_thread_info(thread_info)) };
+        Self::from_raw_parts(raw, value)
     }
 }
 
@@ -63,84 +83,72 @@ unsafe impl<L: crate::mutex::RawMutex, S: Scalar, I: ThreadInfo> super::RawReent
 {
 }
 
-impl<L: crate::Init, S: Scalar, I: crate::Init> crate::Init for ReLock<L, S, I> {
-    const INIT: Self = unsafe { Self::from_raw_parts(L::INIT, I::INIT, S::ZERO) };
+crate::impl_init! {
+    [L: crate::Init, S: Scalar, I: crate::Init] ReLock<L, S, I> = unsafe { Self::from_raw_parts(L::INIT, I::INIT, S::ZERO) }
 }
 
-unsafe impl<L: crate::RawLockInfo, S: Scalar, I: ThreadInfo> crate::RawLockInfo
+unsafe impl<L: crate::mutex::RawMutex, S: Scalar, I: ThreadInfo> super::RawReentrantMutexExclusive
     for ReLock<L, S, I>
 {
-    type ExclusiveGuardTraits = core::convert::Infallible;
-    type ShareGuardTraits = (crate::NoSend, crate::NoSync);
-}
-
-impl<L: RawExclusiveLock, S: Scalar, I: ThreadInfo> ReLock<L, S, I> {
     #[inline]
-    fn lock_internal(&self, try_lock: impl FnOnce() -> bool) -> bool {
-        let id = self.thread_info.id().get();
-        let owner = self.owner.load(Ordering::Relaxed);
-
-        if owner == id {
-            unsafe { self.shr_split() }
-        } else {
-            if !try_lock() {
-                return false;
-            }
-
-            self.owner.store(id, Ordering::Relaxed);
-        }
-
-        true
+    unsafe fn try_make_exclusive(&self) -> bool {
+        self.state.try_make_exclusive()
     }
 
     #[inline]
-    fn unlock_internal(&self, unlock_slow: impl FnOnce()) {
-        if let Some(count) = self.count.get().to_usize().checked_sub(1) {
-            self.count.set(S::from_usize_unchecked(count));
-        } else {
-            self.owner.store(0, Ordering::Relaxed);
-            unlock_slow()
-        }
+    unsafe fn make_shared(&self) {
+        self.state.make_shared()
     }
 }
 
+unsafe impl<L: crate::RawLockInfo, S: Scalar, I: ThreadInfo> crate::RawLockInfo
+    for ReLock<L, S, I>
+{
+    type ExclusiveGuardTraits = core::convert::Infallible;
+    type ShareGuardTraits = (crate::NoSend, crate::NoSync);
+}
+
 unsafe impl<L: RawExclusiveLock, S: Scalar, I: ThreadInfo> RawShareLock for ReLock<L, S, I> {
     #[inline]
     fn shr_lock(&self) {
-        self.lock_internal(|| {
-            self.inner.exc_lock();
-            true
-        });
+        // the underlying lock provides mutual exclusion
+        unsafe {
+            self.state.lock(|| {
+                self.inner.exc_lock();
+                true
+            });
+        }
     }
 
     #[inline]
     fn shr_try_lock(&self) -> bool {
-        self.lock_internal(|| self.inner.exc_try_lock())
+        unsafe { self.state.lock(|| self.inner.exc_try_lock()) }
     }
 
     #[inline]
     unsafe fn shr_split(&self) {
-        debug_assert_eq!(
-            self.owner.load(Ordering::Relaxed),
-            self.thread_info.id().get()
-        );
-        let (count, ovf) = self.count.get().to_usize().overflowing_add(1);
-        assert!(!ovf && S::is_in_bounds(count), "Cannot overflow");
-        self.count.set(S::from_usize_unchecked(count));
+        self.state.split()
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.state.try_split()
     }
 
     #[inline]
     unsafe fn shr_unlock(&self) {
-        self.unlock_internal(
+        self.state.unlock(
             #[cold]
             || self.inner.exc_unlock(),
         )
     }
 
     #[inline]
-    unsafe fn shr_bump(&self) {
-        if self.count.get().to_usize() == 0 {
-            self.inner.exc_bump();
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        if self.state.is_outermost() {
+            self.inner.exc_bump()
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
@@ -150,16 +158,18 @@ unsafe impl<L: RawExclusiveLockFair, S: Scalar, I: ThreadInfo> RawShareLockFair
 {
     #[inline]
     unsafe fn shr_unlock_fair(&self) {
-        self.unlock_internal(
+        self.state.unlock(
             #[cold]
             || self.inner.exc_unlock_fair(),
         )
     }
 
     #[inline]
-    unsafe fn shr_bump_fair(&self) {
-        if self.count.get().to_usize() == 0 {
-            self.inner.exc_bump_fair();
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        if self.state.is_outermost() {
+            self.inner.exc_bump_fair()
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
@@ -173,16 +183,43 @@ unsafe impl<L: RawExclusiveLockTimed, S: Scalar, I: ThreadInfo> RawShareLockTime
     for ReLock<L, S, I>
 {
     fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
-        self.lock_internal(|| self.inner.exc_try_lock_until(instant))
+        unsafe { self.state.lock(|| self.inner.exc_try_lock_until(instant)) }
     }
 
     fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
-        self.lock_internal(|| self.inner.exc_try_lock_for(duration))
+        unsafe { self.state.lock(|| self.inner.exc_try_lock_for(duration)) }
     }
 }
 
 #[cfg(test)]
 mod test {
+    #[test]
+    #[cfg(feature = "std")]
+    fn split_overflow() {
+        use super::ReLock;
+        use crate::exclusive_lock::RawExclusiveLock;
+        use crate::mutex::spin::SpinLock;
+        use crate::remutex::std_thread::StdThreadInfo;
+        use crate::share_lock::RawShareLock;
+
+        let lock = ReLock::<SpinLock, u8, _>::with_thread_info(StdThreadInfo);
+        lock.shr_lock();
+
+        unsafe {
+            for _ in 0..u8::MAX {
+                assert!(lock.shr_try_split());
+            }
+
+            assert!(!lock.shr_try_split());
+
+            for _ in 0..=u8::MAX {
+                lock.shr_unlock();
+            }
+        }
+
+        assert!(lock.inner().exc_try_lock());
+    }
+
     #[test]
     #[cfg(all(feature = "std", feature = "parking_lot"))]
     fn reentrant() {
@@ -243,4 +280,71 @@ mod test {
 
         t.join().unwrap();
     }
+
+    #[test]
+    #[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+    fn fair_unlock() {
+        use super::ReLock;
+        use crate::exclusive_lock::RawExclusiveLock;
+        use crate::mutex::adaptive::AdaptiveLock;
+        use crate::raw_state::RawStateBits;
+        use crate::remutex::std_thread::StdThreadInfo;
+        use crate::share_lock::{RawShareLock, RawShareLockFair};
+
+        let lock = ReLock::<AdaptiveLock, usize, _>::with_thread_info(StdThreadInfo);
+        lock.shr_lock();
+        lock.shr_lock();
+
+        crossbeam_utils::thread::scope(|s| {
+            s.spawn(|_| {
+                lock.shr_lock();
+                unsafe { lock.shr_unlock() }
+            });
+
+            while !lock.inner().decoded_state().parked {
+                std::thread::yield_now();
+            }
+
+            unsafe {
+                // only the outermost lock can bump or release the inner lock
+                lock.shr_bump_fair();
+                lock.shr_unlock_fair();
+                assert!(lock.inner().decoded_state().parked);
+
+                // hands the inner lock off to the parked thread
+                lock.shr_unlock_fair();
+            }
+        })
+        .unwrap();
+
+        assert!(lock.inner().exc_try_lock());
+        unsafe { lock.inner().exc_unlock() }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn lock_mut_outermost() {
+        use crate::mutex::spin::SpinLock;
+        use crate::remutex::std_thread::StdThreadInfo;
+        use crate::remutex::ReentrantMutex;
+
+        let mtx: ReentrantMutex<super::ReLock<SpinLock, usize, _>, Vec<u32>> =
+            ReentrantMutex::with_thread_info(StdThreadInfo, Vec::new());
+
+        mtx.lock_mut_outermost().unwrap().push(1);
+
+        let guard = mtx.lock();
+        assert!(mtx.lock_mut_outermost().is_none());
+        drop(guard);
+
+        let mut guard = mtx.lock_mut_outermost().unwrap();
+        guard.push(2);
+        // the mutex can't be re-entered while the data is borrowed mutably
+        assert!(mtx.try_lock().is_none());
+        drop(guard);
+
+        let guard = mtx.lock();
+        assert_eq!(*mtx.lock(), [1, 2]);
+        drop(guard);
+    }
 }
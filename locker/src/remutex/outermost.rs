@@ -0,0 +1,71 @@
+use super::RawReentrantMutexExclusive;
+use crate::share_lock::RawShareGuard;
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+/// An RAII guard with unique access to the data of a reentrant mutex
+///
+/// created by [`ReentrantMutex::lock_mut_outermost`](super::ReentrantMutex::lock_mut_outermost)
+///
+/// While this guard is alive, the mutex can't be re-entered, not even by the current thread
+#[must_use = "if unused the `OutermostGuard` will immediately unlock"]
+pub struct OutermostGuard<'a, L: RawReentrantMutexExclusive, T: ?Sized> {
+    raw: RawShareGuard<'a, L>,
+    value: *mut T,
+    _repr: PhantomData<&'a mut T>,
+}
+
+unsafe impl<'a, L: RawReentrantMutexExclusive, T: ?Sized + Send> Send for OutermostGuard<'a, L, T> where
+    RawShareGuard<'a, L>: Send
+{
+}
+unsafe impl<'a, L: RawReentrantMutexExclusive, T: ?Sized + Sync> Sync for OutermostGuard<'a, L, T> where
+    RawShareGuard<'a, L>: Sync
+{
+}
+
+impl<'a, L: RawReentrantMutexExclusive, T: ?Sized> OutermostGuard<'a, L, T> {
+    /// # Safety
+    ///
+    /// `raw` must have been made exclusive with
+    /// [`try_make_exclusive`](RawReentrantMutexExclusive::try_make_exclusive),
+    /// and `value` must be protected by the lock
+    pub(super) unsafe fn from_raw_parts(raw: RawShareGuard<'a, L>, value: *mut T) -> Self {
+        Self {
+            raw,
+            value,
+            _repr: PhantomData,
+        }
+    }
+}
+
+impl<L: RawReentrantMutexExclusive, T: ?Sized> Drop for OutermostGuard<'_, L, T> {
+    fn drop(&mut self) {
+        // `raw` releases the lock after this
+        unsafe { self.raw.inner().make_shared() }
+    }
+}
+
+impl<L: RawReentrantMutexExclusive, T: ?Sized> Deref for OutermostGuard<'_, L, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.value }
+    }
+}
+
+impl<L: RawReentrantMutexExclusive, T: ?Sized> DerefMut for OutermostGuard<'_, L, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.value }
+    }
+}
+
+impl<L: RawReentrantMutexExclusive, T: ?Sized + fmt::Debug> fmt::Debug
+    for OutermostGuard<'_, L, T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
@@ -0,0 +1,568 @@
+//! A reentrant reader-writer lock
+//!
+//! [`ReentrantRwLock`] lets a thread re-enter a lock that it already holds, both for
+//! reading and for writing. A plain [`RwLock`](crate::rwlock::RwLock) can't do this safely:
+//! a second read lock on a writer-preferring lock deadlocks behind a waiting writer,
+//! and a second write lock always deadlocks.
+//!
+//! * reads are reader-preferring, while any thread holds a read lock, new read locks
+//!   join the readers without waiting. So recursive read locks never block, but
+//!   writers can be starved by a steady stream of readers
+//! * a thread that holds the write lock can take the write lock or read locks again,
+//!   these only bump the re-entry count (like [`ReLock`](super::lock::ReLock))
+//! * a thread that only holds read locks can't take the write lock, this deadlocks
+//!   (or fails, for the `try_*` functions), just like upgrading any other rwlock
+//!
+//! Since the same thread can hold multiple write locks at once, write locks only give
+//! shared access to the data, like the guards of a [`ReentrantMutex`](super::ReentrantMutex).
+//!
+//! ```
+//! use locker::remutex::rwlock::{ReRwLock, ReentrantRwLock};
+//! use locker::mutex::default::DefaultLock;
+//! use std::sync::atomic::{AtomicU32, Ordering};
+//!
+//! let lock = ReentrantRwLock::<ReRwLock<DefaultLock>, _>::new(AtomicU32::new(0));
+//!
+//! let write = lock.write();
+//! // re-entering the lock for reading or writing doesn't block
+//! let read = lock.read();
+//! lock.write().fetch_add(1, Ordering::Relaxed);
+//! assert_eq!(read.load(Ordering::Relaxed), 1);
+//!
+//! // but other threads are still excluded
+//! std::thread::scope(|s| {
+//!     s.spawn(|| assert!(lock.try_read().is_none()));
+//! });
+//! # drop((read, write));
+//! ```
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::cell::SharedCell;
+use crate::mutex::RawMutex;
+use crate::share_lock::{RawShareGuard, RawShareLock, RawShareLockTimed, ShareGuard};
+
+use super::{counter::Scalar, state::ReentrantState, ThreadInfo};
+
+/// Types implementing this trait can be used by [`ReentrantRwLock`] to
+/// form a safe and fully-functioning reentrant rwlock type.
+///
+/// A *shr lock* of these locks is either a read lock or a write lock, depending on
+/// how it was acquired. [`RawShareLock::shr_lock`] acquires a read lock, and
+/// [`write_lock`](Self::write_lock) acquires a write lock.
+///
+/// # Safety
+///
+/// While a thread holds a write lock, no other thread can hold a *shr lock*
+pub unsafe trait RawReentrantRwLock: crate::RawLockInfo + RawShareLock {
+    /// Acquire a write lock, blocking until no other thread holds a *shr lock*
+    fn write_lock(&self);
+
+    /// Try to acquire a write lock without blocking
+    fn try_write_lock(&self) -> bool;
+
+    /// Check if the current thread holds a write lock
+    fn is_write_locked_by_current_thread(&self) -> bool;
+}
+
+/// A [`RawReentrantRwLock`] that supports timeouts
+///
+/// # Safety
+///
+/// The same rules as [`RawReentrantRwLock`] apply to the write locks acquired by this trait
+pub unsafe trait RawReentrantRwLockTimed: RawReentrantRwLock + RawShareLockTimed {
+    /// Try to acquire a write lock, blocking until the timeout is reached
+    fn try_write_lock_until(&self, instant: Self::Instant) -> bool;
+
+    /// Try to acquire a write lock, blocking until the timeout is reached
+    fn try_write_lock_for(&self, duration: Self::Duration) -> bool;
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        /// A wrapper around a [`RawMutex`] that allows it to be used as a
+        /// reentrant rwlock
+        pub struct ReRwLock<L, S = super::counter::SubWord, I = super::std_thread::StdThreadInfo> {
+            inner: L,
+            readers: AtomicUsize,
+            writer: ReentrantState<S, I>,
+        }
+    } else {
+        /// A wrapper around a [`RawMutex`] that allows it to be used as a
+        /// reentrant rwlock
+        pub struct ReRwLock<L, S, I> {
+            inner: L,
+            readers: AtomicUsize,
+            writer: ReentrantState<S, I>,
+        }
+    }
+}
+
+impl<L, S, I> ReRwLock<L, S, I> {
+    /// # Safety
+    ///
+    /// `inner` must not be shared
+    #[inline]
+    pub const unsafe fn from_raw_parts(inner: L, thread_info: I, counter: S) -> Self {
+        Self {
+            inner,
+            readers: AtomicUsize::new(0),
+            writer: ReentrantState::from_raw_parts(thread_info, counter),
+        }
+    }
+
+    /// Create a new unlocked `ReRwLock` that uses the given thread info to
+    /// decide which thread owns the write lock
+    #[inline]
+    pub fn with_thread_info(thread_info: I) -> Self
+    where
+        L: crate::Init,
+        S: Scalar,
+    {
+        unsafe { Self::from_raw_parts(L::INIT, thread_info, S::ZERO) }
+    }
+
+    /// the underlying lock, which is held by the writer, or by all readers together
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    /// the number of read locks held by threads that don't hold the write lock
+    pub fn readers(&self) -> usize {
+        self.readers.load(Ordering::Relaxed)
+    }
+
+    /// the owner and re-entry count of the write lock
+    pub fn writer(&self) -> &ReentrantState<S, I> {
+        &self.writer
+    }
+}
+
+impl<L: RawMutex, S: Scalar, I: ThreadInfo> ReRwLock<L, S, I> {
+    // joins the readers if there are any, otherwise calls `acquire` to acquire the
+    // underlying lock on behalf of all readers
+    #[inline]
+    fn read_lock(&self, acquire: impl FnOnce() -> bool) -> bool {
+        if self.writer.is_owned_by_current_thread() {
+            unsafe { self.writer.split() };
+            return true;
+        }
+
+        let mut readers = self.readers.load(Ordering::Acquire);
+
+        while readers != 0 {
+            let next = readers.checked_add(1).expect("too many readers");
+
+            match self.readers.compare_exchange_weak(
+                readers,
+                next,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(x) => readers = x,
+            }
+        }
+
+        if !acquire() {
+            return false;
+        }
+
+        // the underlying lock is held, so there are no other readers
+        self.readers.store(1, Ordering::Release);
+        true
+    }
+}
+
+unsafe impl<L: RawMutex, S: Scalar, I: ThreadInfo> RawReentrantRwLock for ReRwLock<L, S, I> {
+    #[inline]
+    fn write_lock(&self) {
+        // the underlying lock provides mutual exclusion
+        unsafe {
+            self.writer.lock(|| {
+                self.inner.exc_lock();
+                true
+            });
+        }
+    }
+
+    #[inline]
+    fn try_write_lock(&self) -> bool {
+        unsafe { self.writer.lock(|| self.inner.exc_try_lock()) }
+    }
+
+    #[inline]
+    fn is_write_locked_by_current_thread(&self) -> bool {
+        self.writer.is_owned_by_current_thread()
+    }
+}
+
+unsafe impl<L: RawMutex + crate::exclusive_lock::RawExclusiveLockTimed, S: Scalar, I: ThreadInfo>
+    RawReentrantRwLockTimed for ReRwLock<L, S, I>
+{
+    #[inline]
+    fn try_write_lock_until(&self, instant: Self::Instant) -> bool {
+        unsafe { self.writer.lock(|| self.inner.exc_try_lock_until(instant)) }
+    }
+
+    #[inline]
+    fn try_write_lock_for(&self, duration: Self::Duration) -> bool {
+        unsafe { self.writer.lock(|| self.inner.exc_try_lock_for(duration)) }
+    }
+}
+
+crate::impl_init! {
+    [L: crate::Init, S: Scalar, I: crate::Init] ReRwLock<L, S, I> = unsafe { Self::from_raw_parts(L::INIT, I::INIT, S::ZERO) }
+}
+
+unsafe impl<L: crate::RawLockInfo, S: Scalar, I: ThreadInfo> crate::RawLockInfo
+    for ReRwLock<L, S, I>
+{
+    type ExclusiveGuardTraits = core::convert::Infallible;
+    type ShareGuardTraits = (crate::NoSend, crate::NoSync);
+}
+
+unsafe impl<L: RawMutex, S: Scalar, I: ThreadInfo> RawShareLock for ReRwLock<L, S, I> {
+    #[inline]
+    fn shr_lock(&self) {
+        self.read_lock(|| {
+            self.inner.exc_lock();
+            true
+        });
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        self.read_lock(|| self.inner.exc_try_lock())
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        if self.writer.is_owned_by_current_thread() {
+            self.writer.split()
+        } else {
+            self.readers.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        if self.writer.is_owned_by_current_thread() {
+            self.writer.try_split()
+        } else {
+            self.readers
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| x.checked_add(1))
+                .is_ok()
+        }
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        if self.writer.is_owned_by_current_thread() {
+            self.writer.unlock(
+                #[cold]
+                || self.inner.exc_unlock(),
+            )
+        } else if self.readers.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // the last reader releases the underlying lock for everyone
+            self.inner.exc_unlock()
+        }
+    }
+}
+
+impl<L: crate::RawTimedLock, S: Scalar, I: ThreadInfo> crate::RawTimedLock for ReRwLock<L, S, I> {
+    type Instant = L::Instant;
+    type Duration = L::Duration;
+}
+
+unsafe impl<L: RawMutex + crate::exclusive_lock::RawExclusiveLockTimed, S: Scalar, I: ThreadInfo>
+    RawShareLockTimed for ReRwLock<L, S, I>
+{
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.read_lock(|| self.inner.exc_try_lock_until(instant))
+    }
+
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.read_lock(|| self.inner.exc_try_lock_for(duration))
+    }
+}
+
+/// A reader-writer lock that can be re-entered by the thread that holds it,
+/// see the [module docs](self) for details
+#[repr(C)]
+pub struct ReentrantRwLock<L, T: ?Sized> {
+    raw: L,
+    value: SharedCell<T>,
+}
+
+unsafe impl<L: Sync + RawReentrantRwLock, T: Send + Sync> Sync for ReentrantRwLock<L, T> {}
+
+impl<L: RawReentrantRwLock + crate::Init, T: Default> Default for ReentrantRwLock<L, T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<L, T> ReentrantRwLock<L, T> {
+    /// # Safety
+    ///
+    /// `raw` must be unlocked
+    #[inline]
+    pub const unsafe fn from_raw_parts(raw: L, value: T) -> Self {
+        Self {
+            raw,
+            value: SharedCell::new(value),
+        }
+    }
+
+    /// Decomposes the rwlock into a raw lock and it's value
+    #[inline]
+    pub fn into_raw_parts(self) -> (L, T) {
+        (self.raw, self.value.into_inner())
+    }
+
+    /// Consumes this rwlock, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<L: RawReentrantRwLock + crate::Init, T> ReentrantRwLock<L, T> {
+    /// Create a new reentrant rwlock
+    #[inline]
+    pub fn new(value: T) -> Self {
+        unsafe { Self::from_raw_parts(L::INIT, value) }
+    }
+}
+
+impl<L: RawMutex + crate::Init, S: Scalar, I: ThreadInfo, T> ReentrantRwLock<ReRwLock<L, S, I>, T> {
+    /// Create a new reentrant rwlock that uses the given thread info to
+    /// decide which thread owns the write lock
+    #[inline]
+    pub fn with_thread_info(thread_info: I, value: T) -> Self {
+        unsafe { Self::from_raw_parts(ReRwLock::with_thread_info(thread_info), value) }
+    }
+}
+
+impl<L, T: ?Sized> ReentrantRwLock<L, T> {
+    /// the underlying raw lock
+    #[inline]
+    pub const fn raw(&self) -> &L {
+        &self.raw
+    }
+
+    /// Get a raw pointer to the value
+    pub fn as_mut_ptr(&self) -> *mut T {
+        self.value.as_ptr()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `ReentrantRwLock` mutably, no actual locking needs to take place
+    /// ---the mutable borrow statically guarantees no locks exist.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<L: RawReentrantRwLock, T: ?Sized> ReentrantRwLock<L, T>
+where
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    #[inline]
+    fn wrap(&self) -> ShareGuard<'_, L, T> {
+        unsafe {
+            ShareGuard::from_raw_parts(RawShareGuard::from_raw(&self.raw), self.value.as_ptr())
+        }
+    }
+
+    /// Check if the current thread holds a write lock
+    #[inline]
+    pub fn is_write_locked_by_current_thread(&self) -> bool {
+        self.raw.is_write_locked_by_current_thread()
+    }
+
+    /// Acquires a read lock, blocking the current thread until no other thread holds
+    /// the write lock
+    ///
+    /// If any thread holds a read lock, or the current thread holds the write lock,
+    /// then this function is non-blocking and is guaranteed to acquire the lock.
+    #[inline]
+    pub fn read(&self) -> ShareGuard<'_, L, T> {
+        self.raw.shr_lock();
+        self.wrap()
+    }
+
+    /// Attempts to acquire a read lock without blocking
+    #[inline]
+    pub fn try_read(&self) -> Option<ShareGuard<'_, L, T>> {
+        if self.raw.shr_try_lock() {
+            Some(self.wrap())
+        } else {
+            None
+        }
+    }
+
+    /// Acquires the write lock, blocking the current thread until no other thread holds
+    /// any lock
+    ///
+    /// If the current thread already holds the write lock, then this function is non-blocking
+    /// and is guaranteed to acquire the lock.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic or deadlock if the current thread only holds read locks
+    #[inline]
+    pub fn write(&self) -> ShareGuard<'_, L, T> {
+        self.raw.write_lock();
+        self.wrap()
+    }
+
+    /// Attempts to acquire the write lock without blocking
+    #[inline]
+    pub fn try_write(&self) -> Option<ShareGuard<'_, L, T>> {
+        if self.raw.try_write_lock() {
+            Some(self.wrap())
+        } else {
+            None
+        }
+    }
+}
+
+impl<L: RawReentrantRwLockTimed, T: ?Sized> ReentrantRwLock<L, T>
+where
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Attempts to acquire a read lock until a timeout is reached
+    #[inline]
+    pub fn try_read_until(&self, instant: L::Instant) -> Option<ShareGuard<'_, L, T>> {
+        if self.raw.shr_try_lock_until(instant) {
+            Some(self.wrap())
+        } else {
+            None
+        }
+    }
+
+    /// Attempts to acquire a read lock until a timeout is reached
+    #[inline]
+    pub fn try_read_for(&self, duration: L::Duration) -> Option<ShareGuard<'_, L, T>> {
+        if self.raw.shr_try_lock_for(duration) {
+            Some(self.wrap())
+        } else {
+            None
+        }
+    }
+
+    /// Attempts to acquire the write lock until a timeout is reached
+    #[inline]
+    pub fn try_write_until(&self, instant: L::Instant) -> Option<ShareGuard<'_, L, T>> {
+        if self.raw.try_write_lock_until(instant) {
+            Some(self.wrap())
+        } else {
+            None
+        }
+    }
+
+    /// Attempts to acquire the write lock until a timeout is reached
+    #[inline]
+    pub fn try_write_for(&self, duration: L::Duration) -> Option<ShareGuard<'_, L, T>> {
+        if self.raw.try_write_lock_for(duration) {
+            Some(self.wrap())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::{ReRwLock, ReentrantRwLock};
+    use crate::mutex::default::DefaultLock;
+    use crate::share_lock::ShareGuard;
+
+    use core::cell::Cell;
+    use std::time::Duration;
+
+    type Lock<T> = ReentrantRwLock<ReRwLock<DefaultLock>, T>;
+
+    #[test]
+    fn recursion() {
+        let lock = Lock::new(Cell::new(0));
+
+        let write = lock.write();
+        let nested = lock.write();
+        lock.read().set(1);
+        assert!(lock.is_write_locked_by_current_thread());
+
+        drop(write);
+        assert_eq!(nested.get(), 1);
+        assert_eq!(lock.raw().readers(), 0);
+        drop(nested);
+
+        assert!(!lock.is_write_locked_by_current_thread());
+        assert!(lock.try_write().is_some());
+    }
+
+    #[test]
+    #[allow(clippy::redundant_clone)]
+    fn readers() {
+        let lock = Lock::new(0_u32);
+
+        let read = lock.read();
+        let mapped = ShareGuard::map(lock.read(), |x| x);
+        assert_eq!(lock.raw().readers(), 2);
+        // a thread that only reads can't take the write lock
+        assert!(lock.try_write().is_none());
+
+        crossbeam_utils::thread::scope(|s| {
+            s.spawn(|_| {
+                assert!(lock.try_read().is_some());
+                assert!(lock.try_write_for(Duration::from_millis(1)).is_none());
+            });
+        })
+        .unwrap();
+
+        drop((read, mapped));
+        assert_eq!(lock.raw().readers(), 0);
+        assert!(lock.try_write().is_some());
+    }
+
+    #[test]
+    fn recursive_read_with_waiting_writer() {
+        let lock = Lock::new(0_u32);
+        let read = lock.read();
+
+        crossbeam_utils::thread::scope(|s| {
+            let writer = s.spawn(|_| {
+                assert!(lock.try_read().is_some());
+                *lock.write()
+            });
+
+            std::thread::sleep(Duration::from_millis(10));
+            // the writer is waiting, but this doesn't block behind it
+            let nested = lock.read();
+            drop((read, nested));
+
+            assert_eq!(writer.join().unwrap(), 0);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn writer_excludes_other_threads() {
+        let lock = Lock::new(0_u32);
+        let _write = lock.write();
+
+        crossbeam_utils::thread::scope(|s| {
+            s.spawn(|_| {
+                assert!(lock.try_read().is_none());
+                assert!(lock.try_read_for(Duration::from_millis(1)).is_none());
+                assert!(lock.try_write().is_none());
+            });
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,223 @@
+//! The bookkeeping of a reentrant lock
+//!
+//! [`ReentrantState`] tracks which thread owns a lock, and how many times it re-entered
+//! the lock. It doesn't decide how a thread waits for the lock, that is up to the
+//! caller. So the same state backs both the blocking [`ReLock`](super::lock::ReLock),
+//! and async reentrant mutexes that wait on a waker set instead.
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{counter::Scalar, ThreadInfo};
+
+/// The owner and re-entry count of a reentrant lock
+///
+/// The outermost acquisition and release are delegated to the caller, through the
+/// closures passed to [`lock`](Self::lock) and [`unlock`](Self::unlock), every other
+/// acquisition only bumps the count.
+pub struct ReentrantState<S, I> {
+    thread_info: I,
+    owner: AtomicUsize,
+    count: Cell<S>,
+}
+
+unsafe impl<S: Send, I: Sync> Sync for ReentrantState<S, I> {}
+
+impl<S, I> ReentrantState<S, I> {
+    /// Create a new unowned state
+    ///
+    /// # Safety
+    ///
+    /// `counter` must be zero
+    #[inline]
+    pub const unsafe fn from_raw_parts(thread_info: I, counter: S) -> Self {
+        Self {
+            thread_info,
+            owner: AtomicUsize::new(0),
+            count: Cell::new(counter),
+        }
+    }
+
+    /// the underlying thread info
+    #[inline]
+    pub fn thread_info(&self) -> &I {
+        &self.thread_info
+    }
+}
+
+impl<S: Scalar, I> ReentrantState<S, I> {
+    /// Create a new unowned state that uses the given thread info to
+    /// decide which thread owns the lock
+    #[inline]
+    pub fn with_thread_info(thread_info: I) -> Self {
+        unsafe { Self::from_raw_parts(thread_info, S::ZERO) }
+    }
+}
+
+impl<S: Scalar, I: crate::Init> crate::Init for ReentrantState<S, I> {
+    const INIT: Self = unsafe { Self::from_raw_parts(I::INIT, S::ZERO) };
+}
+
+impl<S: Scalar, I: ThreadInfo> ReentrantState<S, I> {
+    /// Check if the current thread owns the lock
+    #[inline]
+    pub fn is_owned_by_current_thread(&self) -> bool {
+        self.owner.load(Ordering::Relaxed) == self.thread_info.id().get()
+    }
+
+    /// Acquire the lock for the current thread
+    ///
+    /// If the current thread already owns the lock, the count is bumped. Otherwise
+    /// `acquire` is called to acquire the underlying lock, and if it succeeds,
+    /// the current thread becomes the owner.
+    ///
+    /// # Safety
+    ///
+    /// `acquire` must only return true if it acquired a lock that no other thread
+    /// can hold at the same time, and that isn't released until [`unlock`](Self::unlock)
+    /// calls `release`, i.e. the underlying lock must provide mutual exclusion
+    ///
+    /// # Panic
+    ///
+    /// Panics if the count overflows
+    #[inline]
+    pub unsafe fn lock(&self, acquire: impl FnOnce() -> bool) -> bool {
+        let id = self.thread_info.id().get();
+
+        if self.owner.load(Ordering::Relaxed) == id {
+            self.split()
+        } else {
+            if !acquire() {
+                return false;
+            }
+
+            self.owner.store(id, Ordering::Relaxed);
+        }
+
+        true
+    }
+
+    /// Re-enter the lock
+    ///
+    /// # Safety
+    ///
+    /// The current thread must own the lock
+    ///
+    /// # Panic
+    ///
+    /// Panics if the count overflows
+    #[inline]
+    pub unsafe fn split(&self) {
+        crate::share_lock::OverflowPolicy::Panic.split(|| self.try_split())
+    }
+
+    /// Re-enter the lock, unless the count would overflow
+    ///
+    /// # Safety
+    ///
+    /// The current thread must own the lock
+    #[inline]
+    pub unsafe fn try_split(&self) -> bool {
+        debug_assert!(self.is_owned_by_current_thread());
+
+        match self.count.get().to_usize().checked_add(1) {
+            Some(count) if S::is_in_bounds(count) => {
+                self.count.set(S::from_usize_unchecked(count));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Check if the next unlock releases the underlying lock
+    ///
+    /// # Safety
+    ///
+    /// The current thread must own the lock
+    #[inline]
+    pub unsafe fn is_outermost(&self) -> bool {
+        self.count.get().to_usize() == 0
+    }
+
+    /// Stop the current thread from re-entering the lock, if it only entered once
+    ///
+    /// While the lock is exclusive, no thread owns it, so the current thread
+    /// has to acquire the underlying lock like every other thread
+    ///
+    /// # Safety
+    ///
+    /// The current thread must own the lock
+    #[inline]
+    pub unsafe fn try_make_exclusive(&self) -> bool {
+        debug_assert!(self.is_owned_by_current_thread());
+
+        let is_outermost = self.is_outermost();
+
+        if is_outermost {
+            self.owner.store(0, Ordering::Relaxed);
+        }
+
+        is_outermost
+    }
+
+    /// Make the current thread the owner of the lock again
+    ///
+    /// # Safety
+    ///
+    /// The lock must have been made exclusive by the current thread
+    #[inline]
+    pub unsafe fn make_shared(&self) {
+        self.owner
+            .store(self.thread_info.id().get(), Ordering::Relaxed);
+    }
+
+    /// Exit the lock once
+    ///
+    /// If this is the outermost acquisition, the owner is cleared and then
+    /// `release` is called to release the underlying lock
+    ///
+    /// # Safety
+    ///
+    /// The current thread must own the lock
+    #[inline]
+    pub unsafe fn unlock(&self, release: impl FnOnce()) {
+        if let Some(count) = self.count.get().to_usize().checked_sub(1) {
+            self.count.set(S::from_usize_unchecked(count));
+        } else {
+            self.owner.store(0, Ordering::Relaxed);
+            release()
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn reentrant_state() {
+    use super::std_thread::StdThreadInfo;
+
+    let state = ReentrantState::<u8, _>::with_thread_info(StdThreadInfo);
+    let acquired = Cell::new(0);
+    let released = Cell::new(0);
+
+    assert!(!state.is_owned_by_current_thread());
+
+    // there is only one thread, so acquiring always provides mutual exclusion
+    unsafe {
+        assert!(!state.lock(|| false));
+        assert!(state.lock(|| {
+            acquired.set(acquired.get() + 1);
+            true
+        }));
+        assert!(state.lock(|| unreachable!()));
+        assert!(state.is_owned_by_current_thread());
+
+        assert!(!state.is_outermost());
+        state.unlock(|| released.set(released.get() + 1));
+        assert!(state.is_outermost());
+        assert_eq!(released.get(), 0);
+        state.unlock(|| released.set(released.get() + 1));
+    }
+
+    assert_eq!((acquired.get(), released.get()), (1, 1));
+    assert!(!state.is_owned_by_current_thread());
+}
@@ -3,6 +3,12 @@
 use core::num::NonZeroUsize;
 
 /// Gives the current thread's id based on a thread local
+///
+/// The id is derived from the address of a thread local, and not from
+/// [`ThreadId`](std::thread::ThreadId), so it is cheap to get and works
+/// on every platform that supports thread locals (including musl).
+///
+/// For custom schedulers, like green threads, see [`FnThreadInfo`](super::custom::FnThreadInfo)
 pub struct StdThreadInfo;
 
 impl crate::Init for StdThreadInfo {
@@ -1,19 +1,29 @@
 //! a type safe implementation of a `RwLock`
 
-use core::cell::UnsafeCell;
+use crate::cell::SharedCell;
 
 use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveLockTimed};
-use crate::share_lock::{RawShareLock, RawShareLockTimed, ShareGuard};
+use crate::share_lock::{
+    Contention, RawShareLock, RawShareLockTimed, RawShareLockUpgrade, ShareGuard,
+};
+#[cfg(feature = "std")]
+use crate::{exclusive_lock::MappedExclusiveGuard, share_lock::MappedShareGuard};
+
+pub mod local;
+pub mod spin;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "extra")] {
         pub mod global;
-        pub mod spin;
-        pub mod local;
+        pub mod local_tagged;
         pub mod default;
         pub mod local_splittable;
         pub mod splittable_spin;
         pub mod splittable_default;
+        pub mod ticket;
+        pub mod intent;
+        #[cfg(all(windows, feature = "windows-srw"))]
+        pub mod srw;
         #[cfg(feature = "std")]
         pub mod sharded;
 
@@ -21,10 +31,82 @@ cfg_if::cfg_if! {
         pub mod adaptive;
         #[cfg(feature = "parking_lot_core")]
         pub mod splittable;
+        #[cfg(feature = "parking_lot_core")]
+        pub mod phase_fair;
     }
 }
 
+pub mod cow;
 pub mod raw;
+pub mod transition;
+#[cfg(target_has_atomic = "64")]
+pub mod versioned;
+
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+convert_lock!(
+    spin::SpinLock,
+    default::DefaultLock,
+    ticket::TicketLock,
+    adaptive::AdaptiveLock,
+    phase_fair::PhaseFairLock,
+);
+#[cfg(all(feature = "extra", not(feature = "parking_lot_core")))]
+convert_lock!(spin::SpinLock, default::DefaultLock, ticket::TicketLock);
+
+/// Which kind of access [`RwLock::read_or_write`] should prefer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preference {
+    /// prefer shared read access
+    Read,
+    /// prefer exclusive write access
+    Write,
+}
+
+/// Either a read guard or a write guard, returned by [`RwLock::read_or_write`]
+pub enum ReadOrWrite<'a, L: RawRwLock, T: ?Sized> {
+    /// shared read access was acquired
+    Read(ShareGuard<'a, L, T>),
+    /// exclusive write access was acquired
+    Write(ExclusiveGuard<'a, L, T>),
+}
+
+impl<'a, L: RawRwLock, T: ?Sized> ReadOrWrite<'a, L, T> {
+    /// Returns true if this is a read guard
+    #[inline]
+    pub fn is_read(&self) -> bool {
+        match self {
+            ReadOrWrite::Read(_) => true,
+            ReadOrWrite::Write(_) => false,
+        }
+    }
+
+    /// Returns true if this is a write guard
+    #[inline]
+    pub fn is_write(&self) -> bool {
+        !self.is_read()
+    }
+
+    /// Get a mutable reference to the value, if this is a write guard
+    #[inline]
+    pub fn as_mut(&mut self) -> Option<&mut T> {
+        match self {
+            ReadOrWrite::Read(_) => None,
+            ReadOrWrite::Write(guard) => Some(guard),
+        }
+    }
+}
+
+impl<L: RawRwLock, T: ?Sized> core::ops::Deref for ReadOrWrite<'_, L, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        match self {
+            ReadOrWrite::Read(guard) => guard,
+            ReadOrWrite::Write(guard) => guard,
+        }
+    }
+}
 
 /// Types implementing this trait can be used by [`RwLock`] to form a safe and fully-functioning rwlock type.
 ///
@@ -42,7 +124,7 @@ pub unsafe trait RawRwLock: crate::mutex::RawMutex + RawShareLock {}
 #[repr(C)]
 pub struct RwLock<L, T: ?Sized> {
     raw: raw::RwLock<L>,
-    value: UnsafeCell<T>,
+    value: SharedCell<T>,
 }
 
 impl<L: RawRwLock + crate::Init, T: Default> Default for RwLock<L, T> {
@@ -63,7 +145,7 @@ impl<L, T> RwLock<L, T> {
     pub const fn from_raw_parts(raw: raw::RwLock<L>, value: T) -> Self {
         Self {
             raw,
-            value: UnsafeCell::new(value),
+            value: SharedCell::new(value),
         }
     }
 
@@ -78,6 +160,23 @@ impl<L, T> RwLock<L, T> {
     pub fn into_inner(self) -> T {
         self.value.into_inner()
     }
+
+    /// Convert this rwlock into a rwlock with another backend
+    #[inline]
+    pub fn convert<M: RawRwLock>(self) -> RwLock<M, T>
+    where
+        L: crate::ConvertLock<M>,
+    {
+        let (raw, value) = self.into_raw_parts();
+        RwLock::from_raw_parts(raw.convert(), value)
+    }
+
+    /// Convert this rwlock into a rwlock with any backend that implements [`Init`](crate::Init)
+    #[inline]
+    pub fn convert_init<M: RawRwLock + crate::Init>(self) -> RwLock<M, T> {
+        let (raw, value) = self.into_raw_parts();
+        RwLock::from_raw_parts(raw.convert_init(), value)
+    }
 }
 
 impl<L, T: ?Sized> RwLock<L, T> {
@@ -89,7 +188,7 @@ impl<L, T: ?Sized> RwLock<L, T> {
 
     /// Get a raw pointer to the value
     pub fn as_mut_ptr(&self) -> *mut T {
-        self.value.get()
+        self.value.as_ptr()
     }
 
     cfg_if::cfg_if! {
@@ -110,7 +209,7 @@ impl<L, T: ?Sized> RwLock<L, T> {
             /// ---the mutable borrow statically guarantees no locks exist.
             #[inline]
             pub const fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                unsafe { &mut *self.value.as_ptr() }
             }
         } else {
             /// the underlying raw rwlock
@@ -129,12 +228,23 @@ impl<L, T: ?Sized> RwLock<L, T> {
             /// ---the mutable borrow statically guarantees no locks exist.
             #[inline]
             pub fn get_mut(&mut self) -> &mut T {
-                unsafe { &mut *self.value.get() }
+                self.value.get_mut()
             }
         }
     }
 }
 
+#[cfg(feature = "debug-owners")]
+impl<L: crate::debug_owners::RawLockHolder, T: ?Sized> RwLock<L, T> {
+    /// Information about the thread that currently holds the write lock, if any
+    ///
+    /// This is useful for logging who blocked a write lock that timed out
+    #[inline]
+    pub fn holder_info(&self) -> Option<crate::debug_owners::HolderInfo> {
+        self.raw.holder_info()
+    }
+}
+
 impl<L: RawRwLock + crate::Init, T> RwLock<L, T> {
     cfg_if::cfg_if! {
         if #[cfg(feature = "nightly")] {
@@ -164,7 +274,8 @@ where
         raw: crate::exclusive_lock::RawExclusiveGuard<'s, L>,
     ) -> ExclusiveGuard<'s, L, T> {
         assert!(core::ptr::eq(self.raw.inner(), raw.inner()));
-        unsafe { ExclusiveGuard::from_raw_parts(raw, self.value.get()) }
+        let guard = unsafe { ExclusiveGuard::from_raw_parts(raw, self.value.as_ptr()) };
+        ExclusiveGuard::with_origin(guard, crate::guard::Origin::new(self))
     }
 
     #[inline]
@@ -173,7 +284,8 @@ where
         raw: crate::share_lock::RawShareGuard<'s, L>,
     ) -> ShareGuard<'s, L, T> {
         assert!(core::ptr::eq(self.raw.inner(), raw.inner()));
-        unsafe { ShareGuard::from_raw_parts(raw, self.value.get()) }
+        let guard = unsafe { ShareGuard::from_raw_parts(raw, self.value.as_ptr()) };
+        ShareGuard::with_origin(guard, crate::guard::Origin::new(self))
     }
 
     /// Locks this `RwLock` with exclusive write access, blocking the current thread until it can be acquired.
@@ -187,6 +299,7 @@ where
     /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
     /// single threaded rwlock)
     #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
     pub fn write(&self) -> ExclusiveGuard<'_, L, T> {
         self.wrap_write(self.raw.write())
     }
@@ -198,10 +311,28 @@ where
     ///
     /// This function does not block or panic.
     #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
     pub fn try_write(&self) -> Option<ExclusiveGuard<'_, L, T>> {
         Some(self.wrap_write(self.raw.try_write()?))
     }
 
+    /// Acquires a write guard through a mutable borrow of the rwlock
+    ///
+    /// Since this call borrows the `RwLock` mutably, no other guards can exist, so this
+    /// doesn't go through the locking protocol, it sets the lock state directly where
+    /// the lock supports it. [read more](crate::exclusive_lock::RawExclusiveLock#method.exc_lock_mut)
+    /// This is useful for code that is generic over guards, but already has exclusive
+    /// access to the rwlock.
+    ///
+    /// There is no way to upgrade an existing `ShareGuard` through `&mut RwLock`, because
+    /// the guard already borrows the rwlock, so drop it and use this instead.
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub fn write_from_mut(&mut self) -> ExclusiveGuard<'_, L, T> {
+        let value = self.value.as_ptr();
+        unsafe { ExclusiveGuard::from_raw_parts(self.raw.write_mut(), value) }
+    }
+
     /// Locks this `RwLock` with shared read access, blocking the current thread until it can be acquired.
     ///
     /// The calling thread will be blocked until there are no more writers which hold the lock.
@@ -217,6 +348,7 @@ where
     /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
     /// single threaded rwlock)
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn read(&self) -> ShareGuard<'_, L, T> {
         self.wrap_read(self.raw.read())
     }
@@ -228,9 +360,330 @@ where
     ///
     /// This function does not block or panic.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_read(&self) -> Option<ShareGuard<'_, L, T>> {
         Some(self.wrap_read(self.raw.try_read()?))
     }
+
+    /// Locks this `RwLock` with shared read access, with a hint about the expected contention
+    ///
+    /// This behaves like [`RwLock::read`], but lets the lock pick a strategy for acquiring
+    /// the lock, see [`RawShareLock::shr_try_lock_hint`]
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read_hint(&self, contention: Contention) -> ShareGuard<'_, L, T> {
+        self.wrap_read(self.raw.read_hint(contention))
+    }
+
+    /// Attempts to acquire this `RwLock` with shared read access, with a hint about the
+    /// expected contention
+    ///
+    /// This behaves like [`RwLock::try_read`], see [`RwLock::read_hint`]
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_read_hint(&self, contention: Contention) -> Option<ShareGuard<'_, L, T>> {
+        Some(self.wrap_read(self.raw.try_read_hint(contention)?))
+    }
+
+    /// Locks this `RwLock` with shared read access, expecting that it is unlocked
+    ///
+    /// This is a shorthand for [`read_hint(Contention::Low)`](RwLock::read_hint), which is
+    /// useful for hot paths that are rarely contended.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read_uncontended_hint(&self) -> ShareGuard<'_, L, T> {
+        self.read_hint(Contention::Low)
+    }
+
+    /// Attempts to acquire either shared read access or exclusive write access,
+    /// trying the preferred kind of access first.
+    ///
+    /// If neither could be acquired at this time, then None is returned.
+    ///
+    /// This function does not block or panic.
+    #[inline]
+    pub fn try_read_or_write(&self, pref: Preference) -> Option<ReadOrWrite<'_, L, T>> {
+        match pref {
+            Preference::Read => self
+                .try_read()
+                .map(ReadOrWrite::Read)
+                .or_else(|| self.try_write().map(ReadOrWrite::Write)),
+            Preference::Write => self
+                .try_write()
+                .map(ReadOrWrite::Write)
+                .or_else(|| self.try_read().map(ReadOrWrite::Read)),
+        }
+    }
+
+    /// Acquires either shared read access or exclusive write access, whichever is available first.
+    ///
+    /// The preferred kind of access is tried first, then the other kind of access.
+    /// If neither is available, then the lock is write locked (or a writer is queued), and
+    /// write access can't become available before read access does. So this blocks until
+    /// read access can be acquired, which is whichever kind of access is available first.
+    /// This means that this may return read access even if `pref` is [`Preference::Write`].
+    ///
+    /// This is useful for tasks that have a cheaper read-only path, but will take
+    /// write access if it is free.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
+    /// single threaded rwlock)
+    #[inline]
+    pub fn read_or_write(&self, pref: Preference) -> ReadOrWrite<'_, L, T> {
+        match self.try_read_or_write(pref) {
+            Some(guard) => guard,
+            None => ReadOrWrite::Read(self.read()),
+        }
+    }
+
+    /// Checks the value under a read lock, and only takes a write lock if it needs to be updated
+    ///
+    /// `check` is called with read access. If it returns `None`, then nothing needs to be done
+    /// and `None` is returned. Otherwise the read lock is released and a write lock is acquired.
+    /// Since the value may have changed in between, `check` is called again with the
+    /// write lock held, and only if it still returns `Some` is `update` applied.
+    ///
+    /// This packages the check-then-act pattern, without the race of acting on
+    /// a check that was done under a lock which was released since. If the lock supports
+    /// upgrading, then [`RwLock::read_then_maybe_upgrade`] can avoid the second check.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
+    /// single threaded rwlock)
+    ///
+    /// ```
+    /// let cache = locker::rwlock::default::DefaultLock::rwlock(None);
+    ///
+    /// let fill = |cache: &Option<u32>| if cache.is_none() { Some(10) } else { None };
+    /// let set = |cache: &mut Option<u32>, value| *cache.insert(value);
+    ///
+    /// assert_eq!(cache.read_then_maybe_write(fill, set), Some(10));
+    /// assert_eq!(cache.read_then_maybe_write(fill, set), None);
+    /// ```
+    pub fn read_then_maybe_write<U, R>(
+        &self,
+        mut check: impl FnMut(&T) -> Option<U>,
+        update: impl FnOnce(&mut T, U) -> R,
+    ) -> Option<R> {
+        check(&self.read())?;
+
+        let mut guard = self.write();
+        let value = check(&guard)?;
+        Some(update(&mut guard, value))
+    }
+}
+
+impl<L: RawRwLock + RawShareLockUpgrade, T: ?Sized> RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Checks the value under a read lock, and upgrades it if the value needs to be updated
+    ///
+    /// This behaves like [`RwLock::read_then_maybe_write`], but if the read lock can be
+    /// upgraded without blocking, then `update` is applied right away, because the value
+    /// couldn't have changed since it was checked. Otherwise the read lock is released so
+    /// that concurrent upgrades don't deadlock, and `check` is called again with a write lock.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
+    /// single threaded rwlock)
+    pub fn read_then_maybe_upgrade<U, R>(
+        &self,
+        mut check: impl FnMut(&T) -> Option<U>,
+        update: impl FnOnce(&mut T, U) -> R,
+    ) -> Option<R> {
+        let guard = self.read();
+        let value = check(&guard)?;
+
+        let (mut guard, value) = match ShareGuard::try_upgrade(guard) {
+            Ok(guard) => (guard, value),
+            Err(guard) => {
+                drop(guard);
+                let guard = self.write();
+                let value = check(&guard)?;
+                (guard, value)
+            }
+        };
+
+        Some(update(&mut guard, value))
+    }
+}
+
+impl<L: RawRwLock + RawShareLockUpgrade, T: Clone> RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Locks this `RwLock` with shared read access, and returns a guard that
+    /// writes to a copy of the value
+    ///
+    /// The value is cloned the first time it is accessed mutably, and the copy replaces
+    /// the value once the guard is dropped. So the write lock is only held long enough
+    /// to swap the copy in, instead of for as long as the value is being updated. This
+    /// is useful for large values that are read often, and updated rarely. The copy is
+    /// only written if the read lock can be upgraded without blocking.
+    ///
+    /// See [`CowWriteGuard`](cow::CowWriteGuard) for details
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
+    /// single threaded rwlock)
+    ///
+    /// ```
+    /// use locker::rwlock::cow::CowWriteGuard;
+    ///
+    /// let config = locker::rwlock::default::DefaultLock::rwlock(vec![1, 2, 3]);
+    ///
+    /// let mut update = config.write_cow();
+    /// update.push(4);
+    ///
+    /// // readers still see the old value until the guard is dropped
+    /// assert_eq!(*config.read(), [1, 2, 3]);
+    ///
+    /// assert_eq!(CowWriteGuard::commit(update), Ok(Some(vec![1, 2, 3])));
+    /// assert_eq!(*config.read(), [1, 2, 3, 4]);
+    /// ```
+    pub fn write_cow(&self) -> cow::CowWriteGuard<'_, L, T> {
+        cow::CowWriteGuard::new(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L: RawRwLock, K, V, S> RwLock<L, std::collections::HashMap<K, V, S>>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+    K: Eq + core::hash::Hash,
+    S: core::hash::BuildHasher,
+{
+    /// Locks the map with shared read access, and looks up the `key`
+    ///
+    /// If the `key` is in the map, this returns a guard for its value. The guard keeps
+    /// the whole map locked until it is dropped. Otherwise, the lock is released and
+    /// this returns `None`.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
+    /// single threaded rwlock)
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// let map = locker::rwlock::default::DefaultLock::rwlock(HashMap::new());
+    /// map.write().insert("locker", 10);
+    ///
+    /// assert_eq!(*map.read_key("locker").unwrap(), 10);
+    /// assert!(map.read_key("parking").is_none());
+    /// ```
+    pub fn read_key<Q>(&self, key: &Q) -> Option<MappedShareGuard<'_, L, V>>
+    where
+        K: core::borrow::Borrow<Q>,
+        Q: ?Sized + Eq + core::hash::Hash,
+    {
+        ShareGuard::try_map(self.read(), |map| map.get(key).ok_or(())).ok()
+    }
+
+    /// Locks the map with exclusive write access, and returns a guard for the value
+    /// of the `key`, inserting the default value if it isn't in the map
+    ///
+    /// The guard keeps the whole map locked until it is dropped.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
+    /// single threaded rwlock)
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// let map = locker::rwlock::default::DefaultLock::rwlock(HashMap::<_, u32>::new());
+    ///
+    /// *map.write_entry("locker") += 1;
+    /// *map.write_entry("locker") += 1;
+    ///
+    /// assert_eq!(*map.read_key("locker").unwrap(), 2);
+    /// ```
+    pub fn write_entry(&self, key: K) -> MappedExclusiveGuard<'_, L, V>
+    where
+        V: Default,
+    {
+        self.write_entry_with(key, V::default)
+    }
+
+    /// Locks the map with exclusive write access, and returns a guard for the value
+    /// of the `key`, inserting the result of `insert` if it isn't in the map
+    ///
+    /// The guard keeps the whole map locked until it is dropped.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
+    /// single threaded rwlock)
+    pub fn write_entry_with(
+        &self,
+        key: K,
+        insert: impl FnOnce() -> V,
+    ) -> MappedExclusiveGuard<'_, L, V> {
+        ExclusiveGuard::map(self.write(), |map| map.entry(key).or_insert_with(insert))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L: RawRwLock + crate::exclusive_lock::AsRwLockCapable, K, V, S>
+    RwLock<L, std::collections::HashMap<K, V, S>>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+    K: Eq + core::hash::Hash,
+    S: core::hash::BuildHasher,
+{
+    /// Returns a read guard for the value of the `key`, inserting the result of `insert`
+    /// if it isn't in the map
+    ///
+    /// The map is first locked with shared read access, so concurrent lookups of
+    /// keys that are already in the map don't block each other. If the `key` is missing,
+    /// then the read lock is released and the map is locked with exclusive write access.
+    /// Another thread may have inserted the `key` in the meantime, in which case `insert`
+    /// isn't called. Finally the write lock is downgraded, so that no other writer can
+    /// remove the value before the read guard is returned.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
+    /// single threaded rwlock)
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// let map = locker::rwlock::default::DefaultLock::rwlock(HashMap::new());
+    ///
+    /// assert_eq!(*map.read_key_or_insert_with("locker", || 10), 10);
+    /// assert_eq!(*map.read_key_or_insert_with("locker", || 20), 10);
+    /// ```
+    pub fn read_key_or_insert_with(
+        &self,
+        key: K,
+        insert: impl FnOnce() -> V,
+    ) -> MappedShareGuard<'_, L, V> {
+        if let Some(guard) = self.read_key(&key) {
+            return guard;
+        }
+
+        let mut guard = self.write();
+        let value: *const V = guard.entry(key).or_insert_with(insert);
+        let guard = ExclusiveGuard::downgrade(guard);
+
+        // SAFETY: the map was never unlocked after `value` was inserted,
+        // so no other writer could have moved or removed it
+        ShareGuard::map(guard, |_| unsafe { &*value })
+    }
 }
 
 impl<L: RawRwLock + RawExclusiveLockTimed + RawShareLockTimed, T: ?Sized> RwLock<L, T>
@@ -244,6 +697,7 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
     pub fn try_write_until(&self, instant: L::Instant) -> Option<ExclusiveGuard<'_, L, T>> {
         Some(self.wrap_write(self.raw.try_write_until(instant)?))
     }
@@ -254,6 +708,7 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
     pub fn try_write_for(&self, duration: L::Duration) -> Option<ExclusiveGuard<'_, L, T>> {
         Some(self.wrap_write(self.raw.try_write_for(duration)?))
     }
@@ -264,6 +719,7 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_read_until(&self, instant: L::Instant) -> Option<ShareGuard<'_, L, T>> {
         Some(self.wrap_read(self.raw.try_read_until(instant)?))
     }
@@ -274,9 +730,78 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_read_for(&self, duration: L::Duration) -> Option<ShareGuard<'_, L, T>> {
         Some(self.wrap_read(self.raw.try_read_for(duration)?))
     }
+
+    /// Attempts to acquire either shared read access or exclusive write access
+    /// until a timeout is reached.
+    ///
+    /// The preferred kind of access is tried first, then the other kind of access.
+    /// If neither is available, this waits for whichever kind of access is available
+    /// first until the timeout is reached, see [`RwLock::read_or_write`] for details.
+    /// Returns None if neither could be acquired.
+    #[inline]
+    pub fn try_read_or_write_until(
+        &self,
+        pref: Preference,
+        instant: L::Instant,
+    ) -> Option<ReadOrWrite<'_, L, T>> {
+        match self.try_read_or_write(pref) {
+            Some(guard) => Some(guard),
+            None => self.try_read_until(instant).map(ReadOrWrite::Read),
+        }
+    }
+
+    /// Attempts to acquire either shared read access or exclusive write access
+    /// until a timeout is reached.
+    ///
+    /// The preferred kind of access is tried first, then the other kind of access.
+    /// If neither is available, this waits for whichever kind of access is available
+    /// first until the timeout is reached, see [`RwLock::read_or_write`] for details.
+    /// Returns None if neither could be acquired.
+    #[inline]
+    pub fn try_read_or_write_for(
+        &self,
+        pref: Preference,
+        duration: L::Duration,
+    ) -> Option<ReadOrWrite<'_, L, T>> {
+        match self.try_read_or_write(pref) {
+            Some(guard) => Some(guard),
+            None => self.try_read_for(duration).map(ReadOrWrite::Read),
+        }
+    }
+}
+
+impl<L: RawRwLock, T> RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Attempts to acquire shared access to any one of the `rwlocks`, without blocking
+    ///
+    /// The rwlocks are tried in order, and the index and guard of the first one that
+    /// could be locked is returned. If all of them are write locked, then None is returned.
+    /// To block until one of them is released, put them in a
+    /// [`LockSet`](crate::lock_set::LockSet) instead.
+    ///
+    /// This is an associated function that needs to be used as `RwLock::try_read_any(...)`.
+    #[inline]
+    pub fn try_read_any(rwlocks: &[Self]) -> Option<(usize, ShareGuard<'_, L, T>)> {
+        crate::any::try_lock_any(rwlocks, Self::try_read)
+    }
+
+    /// Attempts to acquire exclusive access to any one of the `rwlocks`, without blocking
+    ///
+    /// The rwlocks are tried in order, and the index and guard of the first one that
+    /// could be locked is returned. If all of them are locked, then None is returned.
+    ///
+    /// This is an associated function that needs to be used as `RwLock::try_write_any(...)`.
+    #[inline]
+    pub fn try_write_any(rwlocks: &[Self]) -> Option<(usize, ExclusiveGuard<'_, L, T>)> {
+        crate::any::try_lock_any(rwlocks, Self::try_write)
+    }
 }
 
 unsafe impl<L: ?Sized + RawRwLock> RawRwLock for &L {}
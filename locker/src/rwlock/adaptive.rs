@@ -1,9 +1,11 @@
 //! an adaptive raw rwlock
 
 use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockDowngrade};
-use crate::share_lock::RawShareLock;
+use crate::share_lock::{Contention, OverflowPolicy, RawShareLock};
 
-use parking_lot_core::{self, ParkResult, ParkToken, SpinWait, UnparkResult, UnparkToken};
+use crate::parking::{ParkResult, ParkToken, UnparkResult, UnparkToken};
+use crate::spin_wait::SpinWait;
+use crate::waiter::{ParkKey, SpinThenPark, WaitStrategy};
 
 const PARK_BIT: usize = 0b0001;
 const EXC_PARK_BIT: usize = 0b0010;
@@ -43,19 +45,139 @@ pub type RawRwLock = crate::rwlock::raw::RwLock<AdaptiveLock>;
 /// an adaptive rwlock
 pub type RwLock<T> = crate::rwlock::RwLock<AdaptiveLock, T>;
 
+/// The state transitions of an [`AdaptiveLock`]
+///
+/// This is kept free of atomics and parking, so that every transition is a
+/// deterministic function of the current state. The lock only commits a
+/// transition after a successful compare-exchange from the state it was computed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct State(usize);
+
+impl State {
+    const UNLOCKED: Self = Self(0);
+
+    /// the number of *shr lock*s held
+    #[inline]
+    fn readers(self) -> usize {
+        (self.0 & READERS) / INC
+    }
+
+    /// if there are any threads parked on the main key
+    #[inline]
+    fn is_parked(self) -> bool {
+        self.0 & PARK_BIT != 0
+    }
+
+    /// the fast path of acquiring a *exc lock*
+    ///
+    /// fails if the lock is held, or if there is a writer waiting for readers to leave
+    #[inline]
+    fn try_exc_lock(self) -> Option<Self> {
+        if self.0 & (EXC_PARK_BIT | EXC_BIT | READERS) == 0 {
+            Some(Self(self.0 | EXC_BIT))
+        } else {
+            None
+        }
+    }
+
+    /// the fast path of releasing a *exc lock*
+    ///
+    /// fails if any threads need to be unparked
+    #[inline]
+    fn exc_unlock(self) -> Option<Self> {
+        if self.0 == EXC_BIT {
+            Some(Self::UNLOCKED)
+        } else {
+            None
+        }
+    }
+
+    /// the fast path of acquiring a *shr lock*
+    ///
+    /// fails if there is a *exc lock*, or if the reader count would overflow
+    #[inline]
+    fn try_shr_lock(self) -> Option<Self> {
+        let (next, overflow) = self.0.overflowing_add(INC);
+
+        if self.0 & EXC_BIT == 0 && !overflow {
+            Some(Self(next))
+        } else {
+            None
+        }
+    }
+
+    /// the fast path of releasing a *shr lock*
+    ///
+    /// fails for the last reader, which may need to unpark other threads
+    #[inline]
+    fn shr_unlock(self) -> Option<Self> {
+        if self.readers() >= 2 {
+            Some(Self(self.0 - INC))
+        } else {
+            None
+        }
+    }
+
+    /// convert the only *shr lock* into a *exc lock*
+    ///
+    /// fails if there are other readers, or another writer is waiting for readers to leave
+    #[inline]
+    fn try_upgrade(self) -> Option<Self> {
+        if self.readers() == 1 && self.0 & EXC_PARK_BIT == 0 {
+            Some(Self((self.0 - INC) | EXC_BIT))
+        } else {
+            None
+        }
+    }
+
+    /// convert a *exc lock* into a single *shr lock*, keeping the park bit
+    #[inline]
+    fn downgrade(self) -> Self {
+        Self((self.0 & PARK_BIT) | INC)
+    }
+}
+
 /// An adaptive rwlock lock backed by `parking_lot_core`
-pub struct AdaptiveLock {
+///
+/// How a thread waits on a contended lock before parking is decided by the
+/// [`WaitStrategy`], which defaults to [`SpinThenPark`]
+///
+/// Threads park on a key derived from the address of the lock, see [`ParkKey`] for
+/// how the `stable-keys` feature flag keeps the key stable for locks that move
+///
+/// Like `parking_lot`, the lock is eventually fair by default: roughly every 0.5ms
+/// an unlock hands the lock off directly to a parked thread, so that a thread that keeps
+/// re-acquiring the lock can't starve the others forever. This can be turned off by
+/// setting `EVENTUALLY_FAIR` to false, then the lock is only handed off by fair unlocks,
+/// which maximizes throughput, but may starve parked threads.
+pub struct AdaptiveLock<S = SpinThenPark, const EVENTUALLY_FAIR: bool = true> {
     state: AtomicUsize,
+    key: ParkKey,
+    #[cfg(feature = "debug-owners")]
+    holder: crate::debug_owners::HolderSlot,
+    strategy: core::marker::PhantomData<fn() -> S>,
 }
 
-impl AdaptiveLock {
-    /// Create a new adaptive rwlock lock
+impl<S, const EVENTUALLY_FAIR: bool> AdaptiveLock<S, EVENTUALLY_FAIR> {
+    /// Create a new adaptive rwlock lock that waits with the wait strategy `S`
     #[inline]
-    pub const fn new() -> Self {
+    pub const fn with_strategy() -> Self {
         Self {
             state: AtomicUsize::new(0),
+            key: ParkKey::new(),
+            #[cfg(feature = "debug-owners")]
+            holder: crate::debug_owners::HolderSlot::new(),
+            strategy: core::marker::PhantomData,
         }
     }
+}
+
+impl AdaptiveLock {
+    /// Create a new adaptive rwlock lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_strategy()
+    }
 
     /// Create a new adaptive raw mutex
     pub const fn raw_mutex() -> RawMutex {
@@ -78,76 +200,126 @@ impl AdaptiveLock {
     }
 }
 
-impl crate::Init for AdaptiveLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    [S, const EVENTUALLY_FAIR: bool] AdaptiveLock<S, EVENTUALLY_FAIR> = Self::with_strategy()
+}
+
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> crate::mutex::RawMutex
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
+}
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> crate::rwlock::RawRwLock
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
 }
+impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> crate::raw_state::RawStateBits
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
+    #[inline]
+    fn raw_state_bits(&self) -> usize {
+        self.state.load(Ordering::Relaxed)
+    }
 
-unsafe impl crate::mutex::RawMutex for AdaptiveLock {}
-unsafe impl crate::rwlock::RawRwLock for AdaptiveLock {}
-unsafe impl crate::RawLockInfo for AdaptiveLock {
+    fn decode_state(bits: usize) -> crate::raw_state::DecodedState {
+        let state = State(bits);
+
+        crate::raw_state::DecodedState {
+            exc: (bits & EXC_BIT != 0) as usize,
+            readers: state.readers(),
+            parked: state.is_parked(),
+            exc_parked: bits & EXC_PARK_BIT != 0,
+        }
+    }
+}
+
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> crate::RawLockInfo
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
     type ExclusiveGuardTraits = ();
     type ShareGuardTraits = ();
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLock for AdaptiveLock {
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> crate::exclusive_lock::RawExclusiveLock
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_lock(&self) {
         if !self.exc_try_lock() {
             self.exc_lock_slow(None);
+
+            #[cfg(feature = "debug-owners")]
+            self.holder.record();
         }
     }
 
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_try_lock(&self) -> bool {
-        let state = self.state.load(Ordering::Relaxed);
+        let state = State(self.state.load(Ordering::Relaxed));
 
-        state & (EXC_PARK_BIT | EXC_BIT | READERS) == 0
-            && self
-                .state
-                .compare_exchange(state, state | EXC_BIT, Ordering::Acquire, Ordering::Relaxed)
+        let locked = state.try_exc_lock().is_some_and(|next| {
+            self.state
+                .compare_exchange(state.0, next.0, Ordering::Acquire, Ordering::Relaxed)
                 .is_ok()
+        });
+
+        #[cfg(feature = "debug-owners")]
+        self.holder.record_if(locked);
+
+        locked
     }
 
     #[inline]
     unsafe fn exc_unlock(&self) {
-        if self
-            .state
-            .compare_exchange(EXC_BIT, 0, Ordering::Release, Ordering::Relaxed)
-            .is_err()
-        {
+        #[cfg(feature = "debug-owners")]
+        self.holder.clear();
+
+        if !self.exc_try_unlock_fast() {
             self.exc_unlock_slow(false);
         }
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
-        if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
+    #[cfg_attr(feature = "debug-owners", track_caller)]
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        if State(self.state.load(Ordering::Relaxed)).is_parked() {
             self.exc_bump_slow(false);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock {
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool>
+    crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
     #[inline]
     unsafe fn exc_unlock_fair(&self) {
-        if self
-            .state
-            .compare_exchange(EXC_BIT, 0, Ordering::Release, Ordering::Relaxed)
-            .is_err()
-        {
+        #[cfg(feature = "debug-owners")]
+        self.holder.clear();
+
+        if !self.exc_try_unlock_fast() {
             self.exc_unlock_slow(true);
         }
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
-        if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
+    #[cfg_attr(feature = "debug-owners", track_caller)]
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        if State(self.state.load(Ordering::Relaxed)).is_parked() {
             self.exc_bump_slow(true);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
 
-unsafe impl RawShareLock for AdaptiveLock {
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> RawShareLock
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
     #[inline]
     fn shr_lock(&self) {
         if !self.shr_try_lock() {
@@ -157,21 +329,57 @@ unsafe impl RawShareLock for AdaptiveLock {
 
     #[inline]
     fn shr_try_lock(&self) -> bool {
-        let state = self.state.load(Ordering::Relaxed);
-        let (next_state, overflow) = state.overflowing_add(INC);
+        let state = State(self.state.load(Ordering::Relaxed));
 
-        state & EXC_BIT == 0
-            && !overflow
-            && self
-                .state
-                .compare_exchange(state, next_state, Ordering::Acquire, Ordering::Relaxed)
+        state.try_shr_lock().is_some_and(|next| {
+            self.state
+                .compare_exchange(state.0, next.0, Ordering::Acquire, Ordering::Relaxed)
                 .is_ok()
+        })
+    }
+
+    #[inline]
+    fn shr_try_lock_hint(&self, contention: Contention) -> bool {
+        match contention {
+            Contention::High => self.shr_try_lock(),
+            // guess that there are no readers or writers, which skips the initial load
+            Contention::Low => {
+                self.state
+                    .compare_exchange(0, INC, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+                    || self.shr_try_lock()
+            }
+        }
     }
 
     #[inline]
     unsafe fn shr_split(&self) {
-        let was_locked = self.shr_try_lock();
-        assert!(was_locked, "Tried to create too many shared locks!");
+        OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        // a writer may be waiting for the current readers to leave,
+        // so only the reader count is checked
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        loop {
+            let new_state = match state.checked_add(INC) {
+                Some(new_state) => new_state,
+                None => return false,
+            };
+
+            if let Err(x) = self.state.compare_exchange_weak(
+                state,
+                new_state,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                state = x;
+            } else {
+                return true;
+            }
+        }
     }
 
     #[inline]
@@ -180,51 +388,77 @@ unsafe impl RawShareLock for AdaptiveLock {
     }
 
     #[inline]
-    unsafe fn shr_bump(&self) {
-        if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        if State(self.state.load(Ordering::Relaxed)).is_parked() {
             self.shr_bump_slow(false);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
 
-unsafe impl crate::share_lock::RawShareLockFair for AdaptiveLock {
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> crate::share_lock::RawShareLockFair
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
     #[inline]
     unsafe fn shr_unlock_fair(&self) {
         self.shr_unlock_inner(true)
     }
 
     #[inline]
-    unsafe fn shr_bump_fair(&self) {
-        if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        if State(self.state.load(Ordering::Relaxed)).is_parked() {
             self.shr_bump_slow(true);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
 
-impl crate::RawTimedLock for AdaptiveLock {
+impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> crate::RawTimedLock
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
     type Instant = std::time::Instant;
     type Duration = std::time::Duration;
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock {
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool>
+    crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
         if self.exc_try_lock() {
             true
         } else {
-            self.exc_lock_slow(Some(instant))
+            let locked = self.exc_lock_slow(Some(instant));
+
+            #[cfg(feature = "debug-owners")]
+            self.holder.record_if(locked);
+
+            locked
         }
     }
 
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
         if self.exc_try_lock() {
             true
         } else {
-            self.exc_lock_slow(Instant::now().checked_add(duration))
+            let locked = self.exc_lock_slow(Instant::now().checked_add(duration));
+
+            #[cfg(feature = "debug-owners")]
+            self.holder.record_if(locked);
+
+            locked
         }
     }
 }
 
-unsafe impl crate::share_lock::RawShareLockTimed for AdaptiveLock {
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> crate::share_lock::RawShareLockTimed
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
     fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
         if self.shr_try_lock() {
             true
@@ -242,75 +476,122 @@ unsafe impl crate::share_lock::RawShareLockTimed for AdaptiveLock {
     }
 }
 
-unsafe impl RawExclusiveLockDowngrade for AdaptiveLock {
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> RawExclusiveLockDowngrade
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
     unsafe fn downgrade(&self) {
+        #[cfg(feature = "debug-owners")]
+        self.holder.clear();
+
         let mut state = self.state.load(Ordering::Relaxed);
 
         while let Err(x) = self.state.compare_exchange_weak(
             state,
-            (state & PARK_BIT) | INC,
+            State(state).downgrade().0,
             Ordering::Relaxed,
             Ordering::Relaxed,
         ) {
             state = x;
         }
 
-        if state & PARK_BIT != 0 {
+        // the *exc lock* bits were already cleared above, and may have been
+        // set again by a writer waiting for this reader to leave
+        if State(state).is_parked() {
             self.unpark_shared();
-            self.state
-                .fetch_and(!(EXC_BIT | EXC_PARK_BIT), Ordering::Relaxed);
         }
     }
 }
 
-unsafe impl crate::share_lock::RawShareLockUpgrade for AdaptiveLock {
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> crate::share_lock::RawShareLockUpgrade
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     unsafe fn upgrade(&self) {
         if !self.try_upgrade() {
             self.upgrade_slow(None);
+
+            #[cfg(feature = "debug-owners")]
+            self.holder.record();
         }
     }
 
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     unsafe fn try_upgrade(&self) -> bool {
-        let state = self.state.load(Ordering::Relaxed);
+        let state = State(self.state.load(Ordering::Relaxed));
 
-        state & READERS == INC
-            && state & EXC_PARK_BIT == 0
-            && self
-                .state
-                .compare_exchange(
-                    state,
-                    (state - INC) | EXC_BIT,
-                    Ordering::Acquire,
-                    Ordering::Relaxed,
-                )
+        let locked = state.try_upgrade().is_some_and(|next| {
+            self.state
+                .compare_exchange(state.0, next.0, Ordering::Acquire, Ordering::Relaxed)
                 .is_ok()
+        });
+
+        #[cfg(feature = "debug-owners")]
+        self.holder.record_if(locked);
+
+        locked
     }
 }
 
-unsafe impl crate::share_lock::RawShareLockUpgradeTimed for AdaptiveLock {
+unsafe impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool>
+    crate::share_lock::RawShareLockUpgradeTimed for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     unsafe fn try_upgrade_until(&self, instant: Self::Instant) -> bool {
         use crate::share_lock::RawShareLockUpgrade;
 
         if self.try_upgrade() {
             true
         } else {
-            self.upgrade_slow(Some(instant))
+            let locked = self.upgrade_slow(Some(instant));
+
+            #[cfg(feature = "debug-owners")]
+            self.holder.record_if(locked);
+
+            locked
         }
     }
 
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     unsafe fn try_upgrade_for(&self, duration: Self::Duration) -> bool {
         use crate::share_lock::RawShareLockUpgrade;
 
         if self.try_upgrade() {
             true
         } else {
-            self.upgrade_slow(Instant::now().checked_add(duration))
+            let locked = self.upgrade_slow(Instant::now().checked_add(duration));
+
+            #[cfg(feature = "debug-owners")]
+            self.holder.record_if(locked);
+
+            locked
         }
     }
 }
 
-impl AdaptiveLock {
+#[cfg(feature = "debug-owners")]
+impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> crate::debug_owners::RawLockHolder
+    for AdaptiveLock<S, EVENTUALLY_FAIR>
+{
+    #[inline]
+    fn holder_info(&self) -> Option<crate::debug_owners::HolderInfo> {
+        self.holder.get()
+    }
+}
+
+impl<S: WaitStrategy, const EVENTUALLY_FAIR: bool> AdaptiveLock<S, EVENTUALLY_FAIR> {
+    #[inline]
+    fn exc_try_unlock_fast(&self) -> bool {
+        let state = State(EXC_BIT);
+
+        state.exc_unlock().is_some_and(|next| {
+            self.state
+                .compare_exchange(state.0, next.0, Ordering::Release, Ordering::Relaxed)
+                .is_ok()
+        })
+    }
+
     #[cold]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_bump_slow(&self, force_fair: bool) {
         self.exc_unlock_slow(force_fair);
         self.exc_lock();
@@ -328,10 +609,10 @@ impl AdaptiveLock {
 
         debug_assert!(state >= INC);
 
-        while state & READERS >= 2 * INC {
+        while let Some(next) = State(state).shr_unlock() {
             if let Err(x) = self.state.compare_exchange_weak(
                 state,
-                state - INC,
+                next.0,
                 Ordering::Release,
                 Ordering::Relaxed,
             ) {
@@ -346,12 +627,12 @@ impl AdaptiveLock {
 
     #[cold]
     fn unpark_shared(&self) {
+        use crate::parking::FilterOp;
         use core::cell::Cell;
-        use parking_lot_core::FilterOp;
 
         let count = Cell::new(0);
 
-        let key = self as *const _ as usize;
+        let key = self.key.key(self);
         let filter = |token| {
             if self
                 .state
@@ -376,9 +657,8 @@ impl AdaptiveLock {
             TOKEN_HANDOFF_SHARED
         };
 
-        unsafe {
-            parking_lot_core::unpark_filter(key, filter, callback);
-        }
+        let result = unsafe { crate::parking::unpark_filter(key, filter, callback) };
+        self.key.check_unparked(self, result.unparked_threads);
     }
 
     #[cold]
@@ -397,12 +677,21 @@ impl AdaptiveLock {
         has_upgraded
     }
 
+    /// Check if an unlock should hand the lock off to the unparked thread
+    ///
+    /// `parking_lot_core` sets `be_fair` about every 0.5ms, which bounds how long
+    /// a parked thread can be passed over by threads that barge in
+    #[inline]
+    fn be_fair(&self, force_fair: bool, result: &UnparkResult) -> bool {
+        force_fair || (EVENTUALLY_FAIR && result.be_fair)
+    }
+
     #[cold]
     #[inline(never)]
     fn exc_unlock_slow(&self, force_fair: bool) {
-        let key = self as *const _ as usize;
+        let key = self.key.key(self);
         let callback = |result: UnparkResult| {
-            if result.unparked_threads != 0 && (force_fair || result.be_fair) {
+            if result.unparked_threads != 0 && self.be_fair(force_fair, &result) {
                 if result.have_more_threads {
                     self.state.fetch_or(PARK_BIT, Ordering::Release);
                 }
@@ -419,78 +708,120 @@ impl AdaptiveLock {
             }
         };
 
-        unsafe {
-            parking_lot_core::unpark_one(key, callback);
-        }
+        let result = unsafe { crate::parking::unpark_one(key, callback) };
+        self.key.check_unparked(self, result.unparked_threads);
+
+        let fair = result.unparked_threads != 0 && self.be_fair(force_fair, &result);
+        crate::trace::unlock_slow(self, result.unparked_threads, fair);
     }
 
     #[cold]
     #[inline(never)]
     fn shr_unlock_slow(&self, force_fair: bool) {
+        use crate::parking::FilterOp;
+        use core::cell::Cell;
+
         // this is the last reader, but there may be new
         // shared locks acquired during this call
 
-        if self.state.load(Ordering::Relaxed) & EXC_PARK_BIT == 0 {
-            let key = self as *const _ as usize;
-            let callback = |result: UnparkResult| {
-                if result.unparked_threads != 0 && (force_fair || result.be_fair) {
-                    if result.have_more_threads {
-                        self.state.fetch_or(PARK_BIT, Ordering::Release);
-                    }
+        if self.state.load(Ordering::Relaxed) & EXC_PARK_BIT != 0 {
+            self.state.fetch_sub(INC, Ordering::Release);
+            self.unpark_writer();
+            return;
+        }
 
-                    TOKEN_HANDOFF_SHARED
+        // only look at the first parked thread, the *shr lock* can only be handed
+        // off if it is a reader. A writer can't take over a *shr lock*, so it is
+        // woken normally and has to acquire the lock by itself
+        let woken = Cell::new(None);
+        // the state before this *shr lock* was released, if it was released
+        let released = Cell::new(None);
+
+        let key = self.key.key(self);
+        let filter = |token| {
+            if woken.get().is_some() {
+                FilterOp::Stop
+            } else {
+                woken.set(Some(token));
+                FilterOp::Unpark
+            }
+        };
+        let callback = |result: UnparkResult| {
+            if result.unparked_threads != 0
+                && woken.get() == Some(TOKEN_SHARED)
+                && self.be_fair(force_fair, &result)
+            {
+                if result.have_more_threads {
+                    self.state.fetch_or(PARK_BIT, Ordering::Release);
+                }
+
+                TOKEN_HANDOFF_SHARED
+            } else {
+                if result.have_more_threads {
+                    released.set(Some(self.state.fetch_sub(INC, Ordering::Release)));
                 } else {
-                    if result.have_more_threads {
-                        self.state.fetch_sub(INC, Ordering::Release);
-                    } else {
-                        let mut state = self.state.load(Ordering::Relaxed);
-
-                        loop {
-                            let new_state = (state - INC) & !PARK_BIT;
-
-                            if let Err(x) = self.state.compare_exchange_weak(
-                                state,
-                                new_state,
-                                Ordering::Release,
-                                Ordering::Relaxed,
-                            ) {
-                                state = x;
-                            } else {
-                                break;
-                            }
+                    let mut state = self.state.load(Ordering::Relaxed);
+
+                    loop {
+                        let new_state = (state - INC) & !PARK_BIT;
+
+                        if let Err(x) = self.state.compare_exchange_weak(
+                            state,
+                            new_state,
+                            Ordering::Release,
+                            Ordering::Relaxed,
+                        ) {
+                            state = x;
+                        } else {
+                            break;
                         }
                     }
 
-                    TOKEN_NORMAL
+                    released.set(Some(state));
                 }
-            };
 
-            unsafe {
-                parking_lot_core::unpark_one(key, callback);
-            }
-        } else {
-            self.state.fetch_sub(INC, Ordering::Release);
-            let key = self as *const _ as usize + 1;
-            let callback = |result: UnparkResult| {
-                if result.unparked_threads != 0 {
-                    self.state.fetch_and(!EXC_PARK_BIT, Ordering::Relaxed);
-                }
                 TOKEN_NORMAL
-            };
+            }
+        };
+
+        let result = unsafe { crate::parking::unpark_filter(key, filter, callback) };
+        self.key.check_unparked(self, result.unparked_threads);
+
+        // the *shr lock* is only kept if it was handed off
+        let fair = result.unparked_threads != 0 && released.get().is_none();
+        crate::trace::unlock_slow(self, result.unparked_threads, fair);
 
-            unsafe {
-                parking_lot_core::unpark_one(key, callback);
+        // a writer may have started waiting for readers to leave after EXC_PARK_BIT
+        // was checked above, if this was the last reader then it must be woken up
+        if let Some(state) = released.get() {
+            if state & EXC_PARK_BIT != 0 && State(state).readers() == 1 {
+                self.unpark_writer();
             }
         }
     }
 
+    /// Wake the writer that is waiting for readers to leave
+    #[cold]
+    fn unpark_writer(&self) {
+        let key = self.key.key(self) + 1;
+        let callback = |result: UnparkResult| {
+            if result.unparked_threads != 0 {
+                self.state.fetch_and(!EXC_PARK_BIT, Ordering::Relaxed);
+            }
+            TOKEN_NORMAL
+        };
+
+        let result = unsafe { crate::parking::unpark_one(key, callback) };
+        self.key.check_unparked(self, result.unparked_threads);
+    }
+
     #[inline]
     fn wait_for_shared(&self, wait_count: usize, timeout: Option<Instant>) -> bool {
         let mut state = self.state.fetch_or(EXC_BIT, Ordering::Acquire);
-        let mut wait = SpinWait::new();
+        let mut spinner = S::start(timeout);
 
         while state & READERS > wait_count * INC {
-            if wait.spin() {
+            if S::spin(&mut spinner) {
                 state = self.state.load(Ordering::Relaxed);
                 continue;
             }
@@ -510,8 +841,9 @@ impl AdaptiveLock {
 
             // Park our thread until we are woken up by an unlock
             // Using the 2nd key at addr + 1
-            let addr = self as *const _ as usize + 1;
+            let addr = self.key.key(self) + 1;
             let validate = || {
+                self.key.record_parked(self);
                 let state = self.state.load(Ordering::Relaxed);
                 state & READERS != 0 && state & EXC_PARK_BIT != 0
             };
@@ -523,7 +855,7 @@ impl AdaptiveLock {
             //   * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
             //   * `before_sleep` does not call `park`, nor does it panic.
             let park_result = unsafe {
-                parking_lot_core::park(
+                crate::parking::park(
                     addr,
                     validate,
                     before_sleep,
@@ -557,6 +889,11 @@ impl AdaptiveLock {
     #[cold]
     #[inline(never)]
     fn exc_lock_slow(&self, timeout: Option<Instant>) -> bool {
+        let _trace = crate::trace::SlowPath::enter("exc_lock", self);
+
+        // set if EXC_BIT was grabbed while there were still readers
+        let has_readers = core::cell::Cell::new(false);
+
         let try_lock = |state: &mut usize| loop {
             if *state & EXC_BIT != 0 {
                 return false;
@@ -569,40 +906,57 @@ impl AdaptiveLock {
                 Ordering::Acquire,
                 Ordering::Relaxed,
             ) {
-                Ok(_) => return true,
+                Ok(_) => {
+                    has_readers.set(*state & READERS != 0);
+                    return true;
+                }
                 Err(x) => *state = x,
             }
         };
 
+        let wait_for_readers = || {
+            let success = self.wait_for_shared(0, timeout);
+
+            if !success {
+                self.state
+                    .fetch_and(!(EXC_BIT | EXC_PARK_BIT), Ordering::Relaxed);
+            }
+
+            success
+        };
+
         let exclusive = || true;
         let shared = || {
             if self.state.fetch_sub(INC, Ordering::Relaxed) & PARK_BIT != 0 {
-                let success = self.wait_for_shared(0, timeout);
-
-                if !success {
-                    self.state
-                        .fetch_and(!(EXC_BIT | EXC_PARK_BIT), Ordering::Relaxed);
-                }
-
-                success
+                wait_for_readers()
             } else {
                 true
             }
         };
 
-        self.lock_slow(
+        let locked = self.lock_slow(
             TOKEN_EXCLUSIVE,
             timeout,
             EXC_BIT,
             try_lock,
             exclusive,
             shared,
-        )
+        );
+
+        // new readers can't get in once EXC_BIT is set, but the ones that
+        // were already in have to leave before the *exc lock* is acquired
+        if locked && has_readers.get() {
+            wait_for_readers()
+        } else {
+            locked
+        }
     }
 
     #[cold]
     #[inline(never)]
     fn shr_lock_slow(&self, timeout: Option<Instant>) -> bool {
+        let _trace = crate::trace::SlowPath::enter("shr_lock", self);
+
         let try_lock = |state: &mut usize| {
             let mut wait = SpinWait::new();
 
@@ -650,7 +1004,7 @@ impl AdaptiveLock {
         exclusive: impl FnOnce() -> bool,
         shared: impl FnOnce() -> bool,
     ) -> bool {
-        let mut wait = SpinWait::new();
+        let mut spinner = S::start(timeout);
         let mut state = self.state.load(Ordering::Relaxed);
 
         loop {
@@ -659,7 +1013,7 @@ impl AdaptiveLock {
             }
 
             // If there are no parked threads, try spinning a few times.
-            if state & (PARK_BIT | EXC_PARK_BIT) == 0 && wait.spin() {
+            if state & (PARK_BIT | EXC_PARK_BIT) == 0 && S::spin(&mut spinner) {
                 state = self.state.load(Ordering::Relaxed);
                 continue;
             }
@@ -678,8 +1032,9 @@ impl AdaptiveLock {
             }
 
             // Park our thread until we are woken up by an unlock
-            let addr = self as *const _ as usize;
+            let addr = self.key.key(self);
             let validate = || {
+                self.key.record_parked(self);
                 let state = self.state.load(Ordering::Relaxed);
                 state & PARK_BIT != 0 && (state & validate_flags != 0)
             };
@@ -696,7 +1051,7 @@ impl AdaptiveLock {
             // * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
             // * `before_sleep` does not call `park`, nor does it panic.
             let park_result = unsafe {
-                parking_lot_core::park(addr, validate, before_sleep, timed_out, park_token, timeout)
+                crate::parking::park(addr, validate, before_sleep, timed_out, park_token, timeout)
             };
 
             match park_result {
@@ -723,7 +1078,7 @@ impl AdaptiveLock {
             }
 
             // Loop back and try locking again
-            wait.reset();
+            S::reset(&mut spinner);
             state = self.state.load(Ordering::Relaxed);
         }
     }
@@ -795,6 +1150,93 @@ mod tests {
         assert_eq!(SEQUENCE.load(Ordering::Relaxed), 4);
     }
 
+    #[test]
+    fn state_model() {
+        use std::collections::HashSet;
+
+        const MAX_READERS: usize = 3;
+
+        // every combination of flags, with a small number of readers
+        let all_states = (0..=MAX_READERS)
+            .flat_map(|readers| (0..INC).map(move |flags| State((readers * INC) | flags)));
+
+        for state in all_states {
+            let flags = state.0 & !READERS;
+
+            match state.try_exc_lock() {
+                Some(next) => {
+                    assert_eq!(state.readers(), 0);
+                    assert_eq!(state.0 & (EXC_BIT | EXC_PARK_BIT), 0);
+                    assert_eq!(next, State(flags | EXC_BIT));
+                }
+                None => assert!(state.readers() != 0 || state.0 & (EXC_BIT | EXC_PARK_BIT) != 0),
+            }
+
+            match state.try_shr_lock() {
+                Some(next) => {
+                    assert_eq!(state.0 & EXC_BIT, 0);
+                    assert_eq!(next.readers(), state.readers() + 1);
+                    assert_eq!(next.0 & !READERS, flags);
+                    assert_eq!(next.shr_unlock(), Some(state).filter(|s| s.readers() != 0));
+                }
+                None => assert_ne!(state.0 & EXC_BIT, 0),
+            }
+
+            match state.try_upgrade() {
+                Some(next) => {
+                    assert_eq!(state.readers(), 1);
+                    assert_eq!(next.readers(), 0);
+                    assert_eq!(next.0 & !READERS, flags | EXC_BIT);
+                }
+                None => assert!(state.readers() != 1 || state.0 & EXC_PARK_BIT != 0),
+            }
+
+            assert_eq!(state.exc_unlock().is_some(), state == State(EXC_BIT));
+            assert_eq!(state.shr_unlock().is_some(), state.readers() >= 2);
+
+            let downgraded = state.downgrade();
+            assert_eq!(downgraded.readers(), 1);
+            assert_eq!(downgraded.is_parked(), state.is_parked());
+        }
+
+        // the reader count saturates instead of overflowing into the flags
+        assert_eq!(State(READERS).try_shr_lock(), None);
+
+        // explore every state reachable through the fast paths, with parked threads arriving
+        // at any point, and check that a *exc lock* and *shr lock*s are never held together
+        let mut seen = HashSet::new();
+        let mut queue = vec![State::UNLOCKED];
+
+        while let Some(state) = queue.pop() {
+            if !seen.insert(state) {
+                continue;
+            }
+
+            assert!(
+                state.0 & EXC_BIT == 0 || state.readers() == 0,
+                "{:?} is both exclusively and shared locked",
+                state
+            );
+
+            let is_exc = state.0 & EXC_BIT != 0;
+            let next = [
+                state.try_exc_lock(),
+                state.try_shr_lock().filter(|s| s.readers() <= MAX_READERS),
+                state.shr_unlock(),
+                state.try_upgrade(),
+                state.exc_unlock(),
+                Some(state.downgrade()).filter(|_| is_exc),
+                Some(State(state.0 | PARK_BIT)),
+                Some(State(state.0 | EXC_PARK_BIT)),
+            ];
+
+            queue.extend(next.iter().copied().flatten());
+        }
+
+        assert!(seen.contains(&State(EXC_BIT)));
+        assert!(seen.contains(&State((MAX_READERS * INC) | PARK_BIT | EXC_PARK_BIT)));
+    }
+
     #[test]
     fn wait_for_shared() {
         static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
@@ -849,4 +1291,118 @@ mod tests {
 
         t.join().unwrap();
     }
+
+    #[test]
+    fn eventual_fairness() {
+        use core::sync::atomic::AtomicBool;
+
+        let mut result = UnparkResult::default();
+        result.unparked_threads = 1;
+        result.be_fair = true;
+
+        assert!(AdaptiveLock::new().be_fair(false, &result));
+
+        let lock = AdaptiveLock::new();
+        let acquired = AtomicBool::new(false);
+
+        lock.exc_lock();
+
+        crossbeam_utils::thread::scope(|s| {
+            s.spawn(|_| {
+                lock.exc_lock();
+                acquired.store(true, Ordering::Relaxed);
+                unsafe { lock.exc_unlock() }
+            });
+
+            while lock.state.load(Ordering::Relaxed) & PARK_BIT == 0 {
+                std::thread::yield_now();
+            }
+
+            // this thread keeps barging in, but the lock is eventually handed off
+            while !acquired.load(Ordering::Relaxed) {
+                unsafe { lock.exc_unlock() }
+                lock.exc_lock();
+            }
+
+            unsafe { lock.exc_unlock() }
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn without_eventual_fairness() {
+        let lock = AdaptiveLock::<SpinThenPark, false>::with_strategy();
+        let mut result = UnparkResult::default();
+        result.unparked_threads = 1;
+        result.be_fair = true;
+
+        // the flag is part of the type, so it doesn't make the lock any bigger
+        assert_eq!(
+            core::mem::size_of_val(&lock),
+            core::mem::size_of::<AdaptiveLock>()
+        );
+        assert!(!lock.be_fair(false, &result));
+        assert!(lock.be_fair(true, &result));
+    }
+
+    fn contend<S: WaitStrategy>() {
+        let rwlock = crate::rwlock::RwLock::<AdaptiveLock<S>, _>::new(0);
+
+        crossbeam_utils::thread::scope(|s| {
+            for i in 0..4 {
+                let rwlock = &rwlock;
+
+                s.spawn(move |_| {
+                    for _ in 0..1000 {
+                        if i % 2 == 0 {
+                            *rwlock.write() += 1;
+                        } else {
+                            let _ = *rwlock.read();
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        assert_eq!(*rwlock.read(), 2000);
+
+        let guard = rwlock.read();
+        let duration = std::time::Duration::from_millis(1);
+        assert!(rwlock.try_write_for(duration).is_none());
+        drop(guard);
+    }
+
+    #[test]
+    fn wait_strategy() {
+        use crate::waiter::{BusyPoll, ParkImmediately, Spin};
+
+        contend::<SpinThenPark>();
+        contend::<ParkImmediately>();
+        contend::<Spin>();
+        contend::<BusyPoll<50>>();
+        // too long to represent as an `Instant`, so it polls until the timeout
+        contend::<BusyPoll<{ u64::MAX }>>();
+    }
+
+    #[test]
+    #[cfg(feature = "debug-owners")]
+    fn bump_holder_location() {
+        use crate::debug_owners::RawLockHolder;
+        use crate::exclusive_lock::RawExclusiveLock;
+
+        let lock = AdaptiveLock::<SpinThenPark>::new();
+        lock.exc_lock();
+
+        // the re-lock in the slow path is attributed to the caller
+        lock.exc_bump_slow(false);
+        let line = line!() - 1;
+
+        let info = lock.holder_info().unwrap();
+        assert_eq!(info.location.file(), file!());
+        assert_eq!(info.location.line(), line);
+
+        unsafe { lock.exc_unlock() };
+        assert!(lock.holder_info().is_none());
+    }
 }
@@ -0,0 +1,160 @@
+//! A copy-on-write guard, see [`RwLock::write_cow`]
+
+use core::ops::{Deref, DerefMut};
+
+use super::{RawRwLock, RwLock};
+use crate::share_lock::{RawShareLockUpgrade, ShareGuard};
+use crate::Inhabitted;
+
+/// A guard that only holds read access, and writes to a copy of the value,
+/// returned by [`RwLock::write_cow`]
+///
+/// The value is cloned the first time that it is accessed mutably, and once this guard
+/// is dropped, the copy replaces the value in the lock. If the value was never accessed
+/// mutably, then dropping this guard only releases the read lock.
+///
+/// To replace the value, the read lock is upgraded, but only if that can be done without
+/// blocking, so that two `CowWriteGuard`s of the same lock don't deadlock while upgrading.
+/// If some other guard still has read access, the copy isn't written, because releasing
+/// the read lock to wait for a write lock would let other writes land in between, and
+/// the copy would silently overwrite them. [`commit`](CowWriteGuard::commit) hands the
+/// copy back in that case, while dropping the guard throws it away. So `write_cow` is
+/// best suited to values that are updated rarely.
+#[must_use = "if unused the `CowWriteGuard` will immediately unlock"]
+pub struct CowWriteGuard<'a, L: RawRwLock + RawShareLockUpgrade, T: Clone>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+    L::ShareGuardTraits: Inhabitted,
+{
+    guard: Option<ShareGuard<'a, L, T>>,
+    copy: Option<T>,
+}
+
+impl<'a, L: RawRwLock + RawShareLockUpgrade, T: Clone> CowWriteGuard<'a, L, T>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+    L::ShareGuardTraits: Inhabitted,
+{
+    pub(super) fn new(lock: &'a RwLock<L, T>) -> Self {
+        Self {
+            guard: Some(lock.read()),
+            copy: None,
+        }
+    }
+
+    /// Check if the value was accessed mutably, i.e. if dropping this guard will
+    /// replace the value in the lock
+    ///
+    /// This is an associated function that needs to be used as `CowWriteGuard::is_copied(...)`.
+    #[inline]
+    pub fn is_copied(g: &Self) -> bool {
+        g.copy.is_some()
+    }
+
+    /// The value that is currently in the lock, ignoring any changes made through this guard
+    ///
+    /// This is an associated function that needs to be used as `CowWriteGuard::original(...)`.
+    #[inline]
+    pub fn original(g: &Self) -> &T {
+        g.guard.as_ref().unwrap()
+    }
+
+    /// Release the read lock, and throw away any changes made through this guard
+    ///
+    /// This is an associated function that needs to be used as `CowWriteGuard::discard(...)`.
+    pub fn discard(mut g: Self) {
+        g.copy = None;
+    }
+
+    /// Write the copy into the lock, returning the old value
+    ///
+    /// Returns `Ok(None)` if the value was never accessed mutably, in which case only the
+    /// read lock is released. The old value is returned after the write lock is released,
+    /// so that dropping it doesn't extend the time that the write lock is held.
+    ///
+    /// # Errors
+    ///
+    /// If the read lock can't be upgraded without blocking, because some other guard has
+    /// read access, then the read lock is released and the copy is returned in `Err`.
+    ///
+    /// This is an associated function that needs to be used as `CowWriteGuard::commit(...)`.
+    pub fn commit(mut g: Self) -> Result<Option<T>, T> {
+        g.write_back()
+    }
+
+    fn write_back(&mut self) -> Result<Option<T>, T> {
+        let guard = match self.guard.take() {
+            Some(guard) => guard,
+            None => return Ok(None),
+        };
+
+        let copy = match self.copy.take() {
+            Some(copy) => copy,
+            None => return Ok(None),
+        };
+
+        // the read lock must be held until the copy is written, otherwise
+        // writes that land in between would be overwritten by the copy
+        let mut guard = match ShareGuard::try_upgrade(guard) {
+            Ok(guard) => guard,
+            Err(guard) => {
+                drop(guard);
+                return Err(copy);
+            }
+        };
+
+        let old = core::mem::replace(&mut *guard, copy);
+        drop(guard);
+        Ok(Some(old))
+    }
+}
+
+impl<L: RawRwLock + RawShareLockUpgrade, T: Clone> Drop for CowWriteGuard<'_, L, T>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+    L::ShareGuardTraits: Inhabitted,
+{
+    fn drop(&mut self) {
+        let _ = self.write_back();
+    }
+}
+
+impl<L: RawRwLock + RawShareLockUpgrade, T: Clone> Deref for CowWriteGuard<'_, L, T>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+    L::ShareGuardTraits: Inhabitted,
+{
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        match self.copy {
+            Some(ref copy) => copy,
+            None => CowWriteGuard::original(self),
+        }
+    }
+}
+
+impl<L: RawRwLock + RawShareLockUpgrade, T: Clone> DerefMut for CowWriteGuard<'_, L, T>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+    L::ShareGuardTraits: Inhabitted,
+{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        let guard = &self.guard;
+        self.copy
+            .get_or_insert_with(|| T::clone(guard.as_ref().unwrap()))
+    }
+}
+
+impl<L: RawRwLock + RawShareLockUpgrade, T: Clone + core::fmt::Debug> core::fmt::Debug
+    for CowWriteGuard<'_, L, T>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+    L::ShareGuardTraits: Inhabitted,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        T::fmt(self, f)
+    }
+}
@@ -1,7 +1,7 @@
 //! A default raw rwlock lock
 
 use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockFair};
-use crate::share_lock::{RawShareLock, RawShareLockFair};
+use crate::share_lock::{Contention, RawShareLock, RawShareLockFair};
 use crate::RawLockInfo;
 
 /// A default raw mutex
@@ -59,8 +59,8 @@ impl DefaultLock {
     }
 }
 
-impl crate::Init for DefaultLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    DefaultLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for DefaultLock {}
@@ -72,11 +72,13 @@ unsafe impl RawLockInfo for DefaultLock {
 
 unsafe impl RawExclusiveLock for DefaultLock {
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_lock(&self) {
         self.0.exc_lock();
     }
 
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_try_lock(&self) -> bool {
         self.0.exc_try_lock()
     }
@@ -87,12 +89,12 @@ unsafe impl RawExclusiveLock for DefaultLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    #[cfg_attr(feature = "debug-owners", track_caller)]
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         self.0.exc_bump()
     }
 }
 
-#[cfg(feature = "parking_lot_core")]
 unsafe impl RawExclusiveLockFair for DefaultLock {
     #[inline]
     unsafe fn exc_unlock_fair(&self) {
@@ -100,7 +102,8 @@ unsafe impl RawExclusiveLockFair for DefaultLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    #[cfg_attr(feature = "debug-owners", track_caller)]
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         self.0.exc_bump_fair()
     }
 }
@@ -116,18 +119,33 @@ unsafe impl RawShareLock for DefaultLock {
         self.0.shr_try_lock()
     }
 
+    #[inline]
+    fn shr_lock_hint(&self, contention: Contention) {
+        self.0.shr_lock_hint(contention)
+    }
+
+    #[inline]
+    fn shr_try_lock_hint(&self, contention: Contention) -> bool {
+        self.0.shr_try_lock_hint(contention)
+    }
+
     #[inline]
     unsafe fn shr_split(&self) {
         self.0.shr_split()
     }
 
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.0.shr_try_split()
+    }
+
     #[inline]
     unsafe fn shr_unlock(&self) {
         self.0.shr_unlock()
     }
 
     #[inline]
-    unsafe fn shr_bump(&self) {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
         self.0.shr_bump()
     }
 }
@@ -141,11 +159,13 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockDowngrade for DefaultLock {
 
 unsafe impl crate::share_lock::RawShareLockUpgrade for DefaultLock {
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     unsafe fn upgrade(&self) {
         self.0.upgrade()
     }
 
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     unsafe fn try_upgrade(&self) -> bool {
         self.0.try_upgrade()
     }
@@ -154,17 +174,18 @@ unsafe impl crate::share_lock::RawShareLockUpgrade for DefaultLock {
 #[cfg(feature = "parking_lot_core")]
 unsafe impl crate::share_lock::RawShareLockUpgradeTimed for DefaultLock {
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     unsafe fn try_upgrade_until(&self, instant: Self::Instant) -> bool {
         self.0.try_upgrade_until(instant)
     }
 
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     unsafe fn try_upgrade_for(&self, duration: Self::Duration) -> bool {
         self.0.try_upgrade_for(duration)
     }
 }
 
-#[cfg(feature = "parking_lot_core")]
 unsafe impl RawShareLockFair for DefaultLock {
     #[inline]
     unsafe fn shr_unlock_fair(&self) {
@@ -172,7 +193,7 @@ unsafe impl RawShareLockFair for DefaultLock {
     }
 
     #[inline]
-    unsafe fn shr_bump_fair(&self) {
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
         self.0.shr_bump_fair()
     }
 }
@@ -186,11 +207,13 @@ impl crate::RawTimedLock for DefaultLock {
 #[cfg(feature = "parking_lot_core")]
 unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for DefaultLock {
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
         self.0.exc_try_lock_until(instant)
     }
 
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
         self.0.exc_try_lock_for(duration)
     }
@@ -208,3 +231,11 @@ unsafe impl crate::share_lock::RawShareLockTimed for DefaultLock {
         self.0.shr_try_lock_for(duration)
     }
 }
+
+#[cfg(feature = "debug-owners")]
+impl crate::debug_owners::RawLockHolder for DefaultLock {
+    #[inline]
+    fn holder_info(&self) -> Option<crate::debug_owners::HolderInfo> {
+        self.0.holder_info()
+    }
+}
@@ -39,6 +39,32 @@ impl GlobalLock {
         RwLock::from_raw_parts(Self::raw_rwlock(), value)
     }
 
+    /// Create a new global raw reentrant mutex
+    ///
+    /// This uses the [global reentrant lock set](crate::remutex::global), so locking the same
+    /// global reentrant mutex again on the same thread will not deadlock.
+    #[cfg(feature = "std")]
+    pub const fn raw_reentrant_mutex() -> crate::remutex::global::RawReentrantMutex {
+        crate::remutex::global::GlobalLock::raw_remutex()
+    }
+
+    /// Create a new global reentrant mutex
+    ///
+    /// This uses the [global reentrant lock set](crate::remutex::global), so locking the same
+    /// global reentrant mutex again on the same thread will not deadlock.
+    #[cfg(feature = "std")]
+    pub const fn reentrant_mutex<T>(value: T) -> crate::remutex::global::ReentrantMutex<T> {
+        crate::remutex::global::GlobalLock::remutex(value)
+    }
+
+    /// Create a new global reentrant mutex
+    #[cfg(feature = "std")]
+    pub fn reentrant_mutex_from_mut<T: ?Sized>(
+        value: &mut T,
+    ) -> &mut crate::remutex::global::ReentrantMutex<T> {
+        crate::remutex::global::GlobalLock::remutex_from_mut(value)
+    }
+
     /// create a new global mutex
     #[allow(clippy::transmute_ptr_to_ptr)]
     pub fn mutex_from_mut<T: ?Sized>(value: &mut T) -> &mut Mutex<T> {
@@ -75,6 +101,24 @@ impl GlobalLock {
         unsafe { core::mem::transmute(value) }
     }
 
+    /// Run `f` with a fresh global lock set on the current thread
+    ///
+    /// While `f` runs, all global rwlocks locked on this thread use a lock set
+    /// that is private to this call, so tests that run in parallel can't contend with each
+    /// other (or with leftover state from another test) through the shared global set.
+    /// Calls may be nested, the previous set is restored when `f` returns or panics.
+    ///
+    /// # Safety
+    ///
+    /// * no guard of a global lock created inside `f` may outlive `f`
+    /// * no global lock may be locked inside `f` and unlocked outside of it, or vice versa
+    /// * a global lock must not be held both inside and outside of `f` at the same time,
+    ///   whether on this thread or another one, because only this thread sees the fresh set
+    #[cfg(feature = "test-util")]
+    pub unsafe fn with_isolated_set<R>(f: impl FnOnce() -> R) -> R {
+        crate::isolate::with_isolated(&ISOLATED, f)
+    }
+
     #[inline(always)]
     #[allow(clippy::trivially_copy_pass_by_ref)]
     fn addr(&self) -> usize {
@@ -84,6 +128,13 @@ impl GlobalLock {
     #[inline(always)]
     #[allow(clippy::trivially_copy_pass_by_ref)]
     fn get(&self) -> &'static DefaultLock {
+        #[cfg(feature = "test-util")]
+        {
+            if let Some(set) = crate::isolate::current(&ISOLATED) {
+                return &set[self.addr()];
+            }
+        }
+
         &GLOBALLOCK[self.addr()]
     }
 
@@ -107,6 +158,11 @@ impl GlobalLock {
     }
 }
 
+#[cfg(feature = "test-util")]
+std::thread_local! {
+    static ISOLATED: crate::isolate::Slot<[DefaultLock; 61]> = const { core::cell::Cell::new(None) };
+}
+
 // 61 because it is a large prime number,
 // this will reduce contention between unrelated locks
 // because unrealated locks will be unlikely to pick up the same lock,
@@ -134,8 +190,8 @@ static GLOBALLOCK: [DefaultLock; 61] = [
     crate::Init::INIT,
 ];
 
-impl crate::Init for GlobalLock {
-    const INIT: Self = Self;
+crate::impl_init! {
+    GlobalLock = Self
 }
 
 unsafe impl crate::mutex::RawMutex for GlobalLock {}
@@ -162,7 +218,7 @@ unsafe impl RawExclusiveLock for GlobalLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         self.get().exc_bump()
     }
 }
@@ -175,7 +231,7 @@ unsafe impl RawExclusiveLockFair for GlobalLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         self.get().exc_bump_fair()
     }
 }
@@ -193,6 +249,10 @@ unsafe impl RawShareLock for GlobalLock {
         self.get().shr_split()
     }
 
+    unsafe fn shr_try_split(&self) -> bool {
+        self.get().shr_try_split()
+    }
+
     unsafe fn shr_unlock(&self) {
         self.get().shr_unlock()
     }
@@ -206,7 +266,7 @@ unsafe impl RawShareLockFair for GlobalLock {
     }
 
     #[inline]
-    unsafe fn shr_bump_fair(&self) {
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
         self.get().shr_bump_fair()
     }
 }
@@ -0,0 +1,488 @@
+//! Multi-granularity locking with intent locks
+//!
+//! Tree-structured data (like a database's tables, pages and rows) can be locked at any level.
+//! Before locking a node in some mode, every ancestor of that node is locked in the
+//! matching intent mode, which tells other threads that some descendant is locked without
+//! locking the entire subtree. So a thread can write to one subtree while other threads
+//! read disjoint subtrees, and locking the root exclusively still excludes everyone.
+//!
+//! The compatibility of the four [modes](IntentMode) is
+//!
+//! |    | IS | IX | S  | X  |
+//! |----|----|----|----|----|
+//! | IS | ✓  | ✓  | ✓  |    |
+//! | IX | ✓  | ✓  |    |    |
+//! | S  | ✓  |    | ✓  |    |
+//! | X  |    |    |    |    |
+//!
+//! [`IntentLock`] implements [`RawExclusiveLock`] as the `X` mode and [`RawShareLock`] as
+//! the `S` mode, so it can also be used as a plain rwlock. [`IntentGuard::lock_child`] takes
+//! care of the parent/child protocol.
+//!
+//! ```
+//! use locker::rwlock::intent::{IntentGuard, IntentLock, IntentMode};
+//!
+//! let root = IntentLock::new();
+//! let (left, right) = (IntentLock::new(), IntentLock::new());
+//!
+//! // write to the left subtree
+//! let parent = IntentGuard::new(&root, IntentMode::IntentExclusive);
+//! let writer = parent.lock_child(&left, IntentMode::Exclusive);
+//!
+//! // while reading the right subtree
+//! let other = IntentGuard::new(&root, IntentMode::IntentShared);
+//! let reader = other.lock_child(&right, IntentMode::Shared);
+//!
+//! // but the whole tree can't be read until the writer is done
+//! assert!(IntentGuard::try_new(&root, IntentMode::Shared).is_none());
+//! drop(writer);
+//! drop(parent);
+//! assert!(IntentGuard::try_new(&root, IntentMode::Shared).is_some());
+//! ```
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::share_lock::RawShareLock;
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// a raw rwlock backed by an intent lock, using only the `S` and `X` modes
+pub type RawRwLock = crate::rwlock::raw::RwLock<IntentLock>;
+
+/// a rwlock backed by an intent lock, using only the `S` and `X` modes
+pub type RwLock<T> = crate::rwlock::RwLock<IntentLock, T>;
+
+/// A mode that an intent lock can be locked in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntentMode {
+    /// `IS`, some descendant is (or will be) locked in [`Shared`](IntentMode::Shared) mode
+    IntentShared,
+    /// `IX`, some descendant is (or will be) locked in [`Exclusive`](IntentMode::Exclusive) mode
+    IntentExclusive,
+    /// `S`, this node and all of its descendants are locked for reading
+    Shared,
+    /// `X`, this node and all of its descendants are locked for writing
+    Exclusive,
+}
+
+impl IntentMode {
+    /// Checks if two threads can hold the same lock in these modes at the same time
+    pub fn is_compatible(self, other: Self) -> bool {
+        use IntentMode::*;
+
+        match (self, other) {
+            (Exclusive, _) | (_, Exclusive) => false,
+            (IntentShared, _) | (_, IntentShared) => true,
+            (IntentExclusive, IntentExclusive) | (Shared, Shared) => true,
+            (IntentExclusive, Shared) | (Shared, IntentExclusive) => false,
+        }
+    }
+
+    /// The mode that the parent must be locked in before locking a child in this mode
+    pub fn intent(self) -> Self {
+        match self {
+            IntentMode::IntentShared | IntentMode::Shared => IntentMode::IntentShared,
+            IntentMode::IntentExclusive | IntentMode::Exclusive => IntentMode::IntentExclusive,
+        }
+    }
+
+    /// Checks if holding the parent in this mode allows locking a child in the `child` mode
+    ///
+    /// A parent locked in `S` or `X` mode already covers all of its children, so no
+    /// children need to be locked
+    pub fn allows_child(self, child: Self) -> bool {
+        match self {
+            IntentMode::IntentExclusive => true,
+            IntentMode::IntentShared => child.intent() == IntentMode::IntentShared,
+            IntentMode::Shared | IntentMode::Exclusive => false,
+        }
+    }
+}
+
+/// A raw lock that supports multi-granularity locking
+///
+/// # Safety
+///
+/// * two modes may only be held at the same time if they are
+///   [compatible](IntentMode::is_compatible)
+/// * locking in [`Exclusive`](IntentMode::Exclusive) mode must be the same as `exc_lock`,
+///   and locking in [`Shared`](IntentMode::Shared) mode must be the same as `shr_lock`
+pub unsafe trait RawIntentLock: RawExclusiveLock + RawShareLock {
+    /// acquire a lock in the given mode
+    ///
+    /// blocks until lock is acquired
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if the lock is cannot be acquired
+    fn intent_lock(&self, mode: IntentMode);
+
+    /// attempts to acquire a lock in the given mode
+    ///
+    /// This function is non-blocking and may not panic
+    ///
+    /// returns true on success
+    fn intent_try_lock(&self, mode: IntentMode) -> bool;
+
+    /// Unlock a lock that was acquired in the given mode
+    ///
+    /// # Safety
+    ///
+    /// the caller must own a lock in the given mode
+    unsafe fn intent_unlock(&self, mode: IntentMode);
+}
+
+/// A spin-based intent lock
+///
+/// The lock state holds the number of `IS` holders, and the number of `IX` or `S` holders
+/// (which can't be held at the same time), so each can be held by about `2^31` threads at
+/// once on 64-bit targets, and `2^15` threads on 32-bit targets.
+pub struct IntentLock {
+    state: AtomicUsize,
+}
+
+const EXC_LOCK: usize = 0b01;
+// set if the strong holders hold the lock in `S` mode, instead of `IX` mode
+const SHR_KIND: usize = 0b10;
+const HALF: u32 = (usize::MAX.count_ones() - 2) / 2;
+const IS_ONE: usize = 1 << 2;
+const IS_MASK: usize = ((1 << HALF) - 1) << 2;
+const STRONG_ONE: usize = 1 << (2 + HALF);
+const STRONG_MASK: usize = !(IS_MASK | SHR_KIND | EXC_LOCK);
+
+#[inline]
+fn add(state: usize, mode: IntentMode) -> Option<usize> {
+    if state & EXC_LOCK != 0 {
+        return None;
+    }
+
+    let strong = state & STRONG_MASK;
+
+    match mode {
+        IntentMode::IntentShared if state & IS_MASK != IS_MASK => Some(state + IS_ONE),
+        IntentMode::IntentExclusive
+            if strong != STRONG_MASK && (strong == 0 || state & SHR_KIND == 0) =>
+        {
+            Some(state + STRONG_ONE)
+        }
+        IntentMode::Shared if strong != STRONG_MASK && (strong == 0 || state & SHR_KIND != 0) => {
+            Some((state | SHR_KIND) + STRONG_ONE)
+        }
+        IntentMode::Exclusive if state == 0 => Some(EXC_LOCK),
+        _ => None,
+    }
+}
+
+impl IntentLock {
+    /// create a new intent lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+        }
+    }
+
+    /// create a new intent lock based raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a new intent lock based rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+
+    #[cold]
+    fn lock_slow(&self, mode: IntentMode) {
+        let mut spin = SpinWait::new();
+
+        while !self.intent_try_lock(mode) {
+            if !spin.spin() {
+                spin.reset();
+            }
+        }
+    }
+}
+
+crate::impl_init! {
+    IntentLock = Self::new()
+}
+
+unsafe impl crate::mutex::RawMutex for IntentLock {}
+unsafe impl crate::rwlock::RawRwLock for IntentLock {}
+unsafe impl crate::RawLockInfo for IntentLock {
+    // the intent lock doesn't care which thread unlocks it
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = ();
+}
+
+unsafe impl RawIntentLock for IntentLock {
+    #[inline]
+    fn intent_lock(&self, mode: IntentMode) {
+        if !self.intent_try_lock(mode) {
+            self.lock_slow(mode);
+        }
+    }
+
+    #[inline]
+    fn intent_try_lock(&self, mode: IntentMode) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        while let Some(new_state) = add(state, mode) {
+            match self.state.compare_exchange_weak(
+                state,
+                new_state,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => state = x,
+            }
+        }
+
+        false
+    }
+
+    #[inline]
+    unsafe fn intent_unlock(&self, mode: IntentMode) {
+        match mode {
+            IntentMode::IntentShared => {
+                self.state.fetch_sub(IS_ONE, Ordering::Release);
+            }
+            IntentMode::IntentExclusive => {
+                self.state.fetch_sub(STRONG_ONE, Ordering::Release);
+            }
+            IntentMode::Shared => {
+                let mut state = self.state.load(Ordering::Relaxed);
+
+                loop {
+                    debug_assert_ne!(state & SHR_KIND, 0);
+
+                    let mut new_state = state - STRONG_ONE;
+
+                    // the last `S` holder allows `IX` holders again
+                    if new_state & STRONG_MASK == 0 {
+                        new_state &= !SHR_KIND;
+                    }
+
+                    match self.state.compare_exchange_weak(
+                        state,
+                        new_state,
+                        Ordering::Release,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => break,
+                        Err(x) => state = x,
+                    }
+                }
+            }
+            IntentMode::Exclusive => self.state.store(0, Ordering::Release),
+        }
+    }
+}
+
+unsafe impl RawExclusiveLock for IntentLock {
+    #[inline]
+    fn exc_lock(&self) {
+        self.intent_lock(IntentMode::Exclusive)
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        self.intent_try_lock(IntentMode::Exclusive)
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.intent_unlock(IntentMode::Exclusive)
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        // there are never any parked threads in an intent lock
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl RawShareLock for IntentLock {
+    #[inline]
+    fn shr_lock(&self) {
+        self.intent_lock(IntentMode::Shared)
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        self.intent_try_lock(IntentMode::Shared)
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        crate::share_lock::OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        // the caller holds a `S` lock, so only the overflow check can fail
+        self.shr_try_lock()
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        self.intent_unlock(IntentMode::Shared)
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        // there are never any parked threads in an intent lock
+        crate::BumpResult::KEPT
+    }
+}
+
+/// A RAII guard for an intent lock held in some [mode](IntentMode)
+#[must_use = "if unused the `IntentGuard` will immediately unlock"]
+pub struct IntentGuard<'a, L: RawIntentLock + ?Sized> {
+    lock: &'a L,
+    mode: IntentMode,
+}
+
+impl<L: RawIntentLock + ?Sized> Drop for IntentGuard<'_, L> {
+    fn drop(&mut self) {
+        unsafe { self.lock.intent_unlock(self.mode) }
+    }
+}
+
+impl<'a, L: RawIntentLock + ?Sized> IntentGuard<'a, L> {
+    /// Lock `lock` in the given mode
+    ///
+    /// blocks until lock is acquired
+    pub fn new(lock: &'a L, mode: IntentMode) -> Self {
+        lock.intent_lock(mode);
+        Self { lock, mode }
+    }
+
+    /// Attempts to lock `lock` in the given mode
+    ///
+    /// This function is non-blocking
+    pub fn try_new(lock: &'a L, mode: IntentMode) -> Option<Self> {
+        if lock.intent_try_lock(mode) {
+            Some(Self { lock, mode })
+        } else {
+            None
+        }
+    }
+
+    /// The mode that the lock is held in
+    pub fn mode(&self) -> IntentMode {
+        self.mode
+    }
+
+    /// The underlying lock
+    pub fn lock(&self) -> &'a L {
+        self.lock
+    }
+
+    /// Lock a child of this node in the given mode
+    ///
+    /// The child guard borrows this guard, so the parent can't be released first
+    ///
+    /// # Panic
+    ///
+    /// If this guard's mode doesn't [allow](IntentMode::allows_child) locking a child in `mode`
+    pub fn lock_child<'b>(&'b self, child: &'b L, mode: IntentMode) -> IntentGuard<'b, L> {
+        self.assert_allows_child(mode);
+        IntentGuard::new(child, mode)
+    }
+
+    /// Attempts to lock a child of this node in the given mode
+    ///
+    /// # Panic
+    ///
+    /// If this guard's mode doesn't [allow](IntentMode::allows_child) locking a child in `mode`
+    pub fn try_lock_child<'b>(
+        &'b self,
+        child: &'b L,
+        mode: IntentMode,
+    ) -> Option<IntentGuard<'b, L>> {
+        self.assert_allows_child(mode);
+        IntentGuard::try_new(child, mode)
+    }
+
+    fn assert_allows_child(&self, mode: IntentMode) {
+        assert!(
+            self.mode.allows_child(mode),
+            "a parent locked in {:?} mode can't have a child locked in {:?} mode",
+            self.mode,
+            mode
+        );
+    }
+}
+
+impl<L: RawIntentLock + ?Sized> core::fmt::Debug for IntentGuard<'_, L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("IntentGuard")
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+#[test]
+fn compatibility() {
+    use IntentMode::*;
+
+    let modes = [IntentShared, IntentExclusive, Shared, Exclusive];
+
+    for &held in &modes {
+        for &mode in &modes {
+            let lock = IntentLock::new();
+            let _held = IntentGuard::new(&lock, held);
+
+            assert_eq!(
+                IntentGuard::try_new(&lock, mode).is_some(),
+                held.is_compatible(mode),
+                "{:?} {:?}",
+                held,
+                mode
+            );
+        }
+    }
+
+    // the last `S` holder allows `IX` again, even while `IS` is held
+    let lock = IntentLock::new();
+    let is = IntentGuard::new(&lock, IntentShared);
+    drop((
+        IntentGuard::new(&lock, Shared),
+        IntentGuard::new(&lock, Shared),
+    ));
+    assert!(IntentGuard::try_new(&lock, IntentExclusive).is_some());
+    drop(is);
+    assert!(lock.exc_try_lock());
+}
+
+#[test]
+fn subtrees() {
+    let tree = IntentLock::rwlock(0);
+    let root = tree.raw().inner();
+    let children = [IntentLock::new(), IntentLock::new()];
+
+    crossbeam_utils::thread::scope(|s| {
+        for (i, child) in children.iter().enumerate() {
+            let tree = &tree;
+
+            s.spawn(move |_| {
+                for _ in 0..1000 {
+                    if i == 0 {
+                        let parent = IntentGuard::new(root, IntentMode::IntentExclusive);
+                        let _child = parent.lock_child(child, IntentMode::Exclusive);
+                    } else {
+                        let parent = IntentGuard::new(root, IntentMode::IntentShared);
+                        let _child = parent.lock_child(child, IntentMode::Shared);
+                    }
+
+                    *tree.write() += 1;
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(*tree.read(), 2000);
+}
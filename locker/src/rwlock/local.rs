@@ -4,6 +4,12 @@ use core::cell::Cell;
 
 const EXC_LOCK: usize = !0;
 
+// `EXC_LOCK` is the largest lock state, so it can't be reached by adding readers
+#[inline]
+fn add_reader(state: usize) -> Option<usize> {
+    state.checked_add(1).filter(|&state| state != EXC_LOCK)
+}
+
 /// a local (single-threaded) raw mutex
 pub type RawMutex = crate::mutex::raw::Mutex<LocalLock>;
 /// a local (single-threaded) mutex
@@ -11,6 +17,8 @@ pub type Mutex<T> = crate::mutex::Mutex<LocalLock, T>;
 /// a local (single-threaded) raw rwlock
 pub type RawRwLock = crate::rwlock::raw::RwLock<LocalLock>;
 /// a local (single-threaded) rwlock
+///
+/// This can be created in a `const` context, even on stable, with [`LocalLock::rwlock`]
 pub type RwLock<T> = crate::rwlock::RwLock<LocalLock, T>;
 
 /// a local (single-threaded) rwlock lock
@@ -48,8 +56,8 @@ impl LocalLock {
     }
 }
 
-impl crate::Init for LocalLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    LocalLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for LocalLock {}
@@ -75,13 +83,22 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for LocalLock {
         }
     }
 
+    #[inline]
+    fn exc_lock_mut(&mut self) {
+        let state = self.state.get_mut();
+        assert_eq!(*state, 0, "tried to lock a leaked exc lock");
+        *state = EXC_LOCK;
+    }
+
     #[inline]
     unsafe fn exc_unlock(&self) {
         self.state.set(0);
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {}
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
 }
 
 unsafe impl crate::share_lock::RawShareLock for LocalLock {
@@ -95,7 +112,7 @@ unsafe impl crate::share_lock::RawShareLock for LocalLock {
 
     #[inline]
     fn shr_try_lock(&self) -> bool {
-        if let Some(new_state) = self.state.get().checked_add(1) {
+        if let Some(new_state) = add_reader(self.state.get()) {
             self.state.set(new_state);
             true
         } else {
@@ -105,10 +122,12 @@ unsafe impl crate::share_lock::RawShareLock for LocalLock {
 
     #[inline]
     unsafe fn shr_split(&self) {
-        assert!(
-            self.shr_try_lock(),
-            "Tried to create too many shared locks!"
-        );
+        crate::share_lock::OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.shr_try_lock()
     }
 
     #[inline]
@@ -119,7 +138,9 @@ unsafe impl crate::share_lock::RawShareLock for LocalLock {
     }
 
     #[inline]
-    unsafe fn shr_bump(&self) {}
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
 }
 
 unsafe impl crate::exclusive_lock::RawExclusiveLockDowngrade for LocalLock {
@@ -154,3 +175,116 @@ unsafe impl crate::share_lock::RawShareLockUpgrade for LocalLock {
         }
     }
 }
+
+#[test]
+fn bump_for() {
+    use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveLock, RawExclusiveLockTimed};
+    use crate::share_lock::{RawShareLock, RawShareLockTimed, ShareGuard};
+
+    // a lock that always times out while bumping, if `timeout` is set
+    struct Timed {
+        lock: LocalLock,
+        timeout: Cell<bool>,
+    }
+
+    unsafe impl crate::RawLockInfo for Timed {
+        type ExclusiveGuardTraits = (crate::NoSend, crate::NoSync);
+        type ShareGuardTraits = (crate::NoSend, crate::NoSync);
+    }
+
+    impl crate::RawTimedLock for Timed {
+        type Instant = ();
+        type Duration = ();
+    }
+
+    unsafe impl RawExclusiveLock for Timed {
+        fn exc_lock(&self) {
+            self.lock.exc_lock()
+        }
+
+        fn exc_try_lock(&self) -> bool {
+            self.lock.exc_try_lock()
+        }
+
+        unsafe fn exc_unlock(&self) {
+            self.lock.exc_unlock()
+        }
+
+        unsafe fn exc_bump(&self) -> crate::BumpResult {
+            crate::BumpResult::KEPT
+        }
+    }
+
+    unsafe impl RawExclusiveLockTimed for Timed {
+        fn exc_try_lock_until(&self, (): ()) -> bool {
+            !self.timeout.get() && self.exc_try_lock()
+        }
+
+        fn exc_try_lock_for(&self, (): ()) -> bool {
+            !self.timeout.get() && self.exc_try_lock()
+        }
+    }
+
+    unsafe impl RawShareLock for Timed {
+        fn shr_lock(&self) {
+            self.lock.shr_lock()
+        }
+
+        fn shr_try_lock(&self) -> bool {
+            self.lock.shr_try_lock()
+        }
+
+        unsafe fn shr_split(&self) {
+            self.lock.shr_split()
+        }
+
+        unsafe fn shr_unlock(&self) {
+            self.lock.shr_unlock()
+        }
+
+        unsafe fn shr_bump(&self) -> crate::BumpResult {
+            crate::BumpResult::KEPT
+        }
+    }
+
+    unsafe impl RawShareLockTimed for Timed {
+        fn shr_try_lock_until(&self, (): ()) -> bool {
+            !self.timeout.get() && self.shr_try_lock()
+        }
+
+        fn shr_try_lock_for(&self, (): ()) -> bool {
+            !self.timeout.get() && self.shr_try_lock()
+        }
+    }
+
+    let lock = Timed {
+        lock: LocalLock::new(),
+        timeout: Cell::new(false),
+    };
+    let mut value = 0;
+
+    let mut guard = unsafe {
+        ExclusiveGuard::from_raw_parts(
+            crate::exclusive_lock::RawExclusiveGuard::new(&lock),
+            &mut value,
+        )
+    };
+    assert!(ExclusiveGuard::bump_for(&mut guard, ()));
+    assert!(!lock.exc_try_lock());
+    lock.timeout.set(true);
+    assert!(!ExclusiveGuard::bump_for(&mut guard, ()));
+    // the lock was re-acquired anyways
+    assert!(!lock.exc_try_lock());
+    drop(guard);
+
+    lock.timeout.set(false);
+    let mut guard =
+        unsafe { ShareGuard::from_raw_parts(crate::share_lock::RawShareGuard::new(&lock), &value) };
+    assert!(ShareGuard::bump_for(&mut guard, ()));
+    assert!(!lock.exc_try_lock());
+    lock.timeout.set(true);
+    assert!(!ShareGuard::bump_for(&mut guard, ()));
+    assert!(!lock.exc_try_lock());
+    drop(guard);
+    assert!(lock.exc_try_lock());
+}
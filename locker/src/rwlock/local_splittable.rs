@@ -49,8 +49,8 @@ impl LocalSplitLock {
     }
 }
 
-impl crate::Init for LocalSplitLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    LocalSplitLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for LocalSplitLock {}
@@ -87,7 +87,9 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for LocalSplitLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {}
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
 }
 
 unsafe impl crate::exclusive_lock::SplittableExclusiveLock for LocalSplitLock {
@@ -132,13 +134,18 @@ unsafe impl crate::share_lock::RawShareLock for LocalSplitLock {
 
     #[inline]
     unsafe fn shr_split(&self) {
-        let state = self.state.get();
-
-        let state = state
-            .checked_add(INC)
-            .expect("tried to create too many shared locks");
+        crate::share_lock::OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
 
-        self.state.set(state);
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        match self.state.get().checked_add(INC) {
+            Some(state) => {
+                self.state.set(state);
+                true
+            }
+            None => false,
+        }
     }
 
     #[inline]
@@ -147,5 +154,7 @@ unsafe impl crate::share_lock::RawShareLock for LocalSplitLock {
     }
 
     #[inline]
-    unsafe fn shr_bump(&self) {}
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
 }
@@ -0,0 +1,273 @@
+//! a local (single-threaded) tagged rwlock lock
+
+use core::cell::Cell;
+
+/// a local (single-threaded) tagged raw mutex
+pub type RawMutex = crate::mutex::raw::Mutex<LocalTaggedLock>;
+/// a local (single-threaded) tagged mutex
+pub type Mutex<T> = crate::mutex::Mutex<LocalTaggedLock, T>;
+/// a local (single-threaded) tagged raw rwlock
+pub type RawRwLock = crate::rwlock::raw::RwLock<LocalTaggedLock>;
+/// a local (single-threaded) tagged rwlock
+pub type RwLock<T> = crate::rwlock::RwLock<LocalTaggedLock, T>;
+
+/// a local (single-threaded) tagged rwlock lock
+///
+/// The tag is stored in the high bits of the lock state, and the
+/// lock state is stored in the remaining low bits.
+pub struct LocalTaggedLock {
+    state: Cell<usize>,
+}
+
+impl LocalTaggedLock {
+    /// The number of bits that this rwlock can store
+    ///
+    /// This is guaranteed to be exactly 8
+    pub const TAG_BITS: u8 = 8;
+    const SHIFT: usize = core::mem::size_of::<usize>() * 8 - Self::TAG_BITS as usize;
+    const MASK: usize = !(!0 << Self::SHIFT);
+    const EXC_LOCK: usize = Self::MASK;
+
+    /// create a local (single-threaded) tagged rwlock lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_tag(0)
+    }
+
+    /// create a local (single-threaded) tagged rwlock lock with the given tag
+    #[inline]
+    pub const fn with_tag(tag: u8) -> Self {
+        Self {
+            state: Cell::new((tag as usize) << Self::SHIFT),
+        }
+    }
+
+    /// Get the tag
+    pub fn tag(&self) -> u8 {
+        (self.state.get() >> Self::SHIFT) as u8
+    }
+
+    /// perform a bit-wise and with the given tag and the stored tag
+    ///
+    /// returns the old tag
+    pub fn and_tag(&self, tag: u8) -> u8 {
+        let state = self.state.get();
+
+        self.state
+            .set(state & ((tag as usize) << Self::SHIFT | Self::MASK));
+
+        (state >> Self::SHIFT) as u8
+    }
+
+    /// perform a bit-wise or with the given tag and the stored tag
+    ///
+    /// returns the old tag
+    pub fn or_tag(&self, tag: u8) -> u8 {
+        let state = self.state.get();
+
+        self.state.set(state | (tag as usize) << Self::SHIFT);
+
+        (state >> Self::SHIFT) as u8
+    }
+
+    /// swap the tag with the given tag
+    ///
+    /// returns the old tag
+    pub fn replace_tag(&self, tag: u8) -> u8 {
+        let state = self.state.get();
+
+        self.state
+            .set((state & Self::MASK) | (tag as usize) << Self::SHIFT);
+
+        (state >> Self::SHIFT) as u8
+    }
+
+    /// set the tag with the given tag
+    pub fn set_tag(&self, tag: u8) {
+        self.replace_tag(tag);
+    }
+
+    #[inline]
+    fn lock_state(&self) -> usize {
+        self.state.get() & Self::MASK
+    }
+
+    #[inline]
+    fn set_lock_state(&self, lock_state: usize) {
+        let state = self.state.get();
+
+        self.state.set((state & !Self::MASK) | lock_state);
+    }
+
+    /// create a local (single-threaded) tagged raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a local (single-threaded) tagged mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// create a local (single-threaded) tagged raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a local (single-threaded) tagged rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+}
+
+crate::impl_init! {
+    LocalTaggedLock = Self::new()
+}
+
+unsafe impl crate::mutex::RawMutex for LocalTaggedLock {}
+unsafe impl crate::rwlock::RawRwLock for LocalTaggedLock {}
+unsafe impl crate::RawLockInfo for LocalTaggedLock {
+    type ExclusiveGuardTraits = (crate::NoSend, crate::NoSync);
+    type ShareGuardTraits = (crate::NoSend, crate::NoSync);
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLock for LocalTaggedLock {
+    #[inline]
+    fn exc_lock(&self) {
+        assert!(self.exc_try_lock(), "Can't lock a locked local lock");
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        if self.lock_state() == 0 {
+            self.set_lock_state(Self::EXC_LOCK);
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        debug_assert_eq!(
+            self.lock_state(),
+            Self::EXC_LOCK,
+            "tried to unlock an unlocked exc lock"
+        );
+
+        self.set_lock_state(0);
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLock for LocalTaggedLock {
+    #[inline]
+    fn shr_lock(&self) {
+        assert!(
+            self.shr_try_lock(),
+            "Can't lock a unqiuely locked local lock"
+        );
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        let lock_state = self.lock_state() + 1;
+
+        // `EXC_LOCK` is the largest lock state, so this also checks for too many shared locks
+        if lock_state < Self::EXC_LOCK {
+            self.set_lock_state(lock_state);
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        crate::share_lock::OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.shr_try_lock()
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        let lock_state = self.lock_state();
+        debug_assert!(
+            lock_state != 0 && lock_state != Self::EXC_LOCK,
+            "Can't unlock an unlocked local lock"
+        );
+        self.set_lock_state(lock_state - 1);
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockDowngrade for LocalTaggedLock {
+    unsafe fn downgrade(&self) {
+        debug_assert_eq!(
+            self.lock_state(),
+            Self::EXC_LOCK,
+            "cannot downgrade a shared lock!"
+        );
+
+        self.set_lock_state(1);
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockUpgrade for LocalTaggedLock {
+    unsafe fn upgrade(&self) {
+        assert!(
+            self.try_upgrade(),
+            "Cannot upgrade local shared lock while other local shared locks are active"
+        );
+    }
+
+    unsafe fn try_upgrade(&self) -> bool {
+        if self.lock_state() == 1 {
+            self.set_lock_state(Self::EXC_LOCK);
+
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[test]
+fn local_tagged() {
+    use crate::share_lock::ShareGuard;
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const RWLOCK: RwLock<u32> = LocalTaggedLock::rwlock(0);
+
+    let rwlock = RWLOCK;
+    let raw = rwlock.raw().inner();
+
+    raw.set_tag(0b1010_0101);
+
+    let a = rwlock.read();
+    let b = ShareGuard::clone(&a);
+    assert!(rwlock.try_write().is_none());
+    assert_eq!(raw.or_tag(0b0101_1010), 0b1010_0101);
+    drop((a, b));
+
+    let mut guard = rwlock.write();
+    *guard += 1;
+    assert!(rwlock.try_read().is_none());
+    assert_eq!(raw.and_tag(0b1111_0000), 0xff);
+    drop(guard);
+
+    assert_eq!(raw.replace_tag(0), 0b1111_0000);
+    assert_eq!(*rwlock.read(), 1);
+    assert_eq!(raw.tag(), 0);
+}
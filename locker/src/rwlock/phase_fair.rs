@@ -0,0 +1,515 @@
+//! a phase-fair rwlock that parks threads
+
+use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockTimed};
+use crate::mutex::adaptive::AdaptiveLock;
+use crate::parking::{ParkResult, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+// in `read_in`, set while a writer is present
+const WRITER: usize = 0b001;
+// in `read_in`, flipped by each writer so that readers can tell writer phases apart
+const PHASE: usize = 0b010;
+const WRITER_BITS: usize = WRITER | PHASE;
+// in `read_in`, set when readers are parked waiting for a writer phase to end
+// in `read_out`, set when the writer is parked waiting for readers to leave
+const PARKED: usize = 0b100;
+// the increment for reader counts
+const READER_INC: usize = 0b1000;
+const READERS: usize = !(READER_INC - 1);
+
+/// a raw mutex backed by a phase-fair lock
+pub type RawMutex = crate::mutex::raw::Mutex<PhaseFairLock>;
+/// a mutex backed by a phase-fair lock
+pub type Mutex<T> = crate::mutex::Mutex<PhaseFairLock, T>;
+/// a raw rwlock backed by a phase-fair lock
+pub type RawRwLock = crate::rwlock::raw::RwLock<PhaseFairLock>;
+/// a rwlock backed by a phase-fair lock
+pub type RwLock<T> = crate::rwlock::RwLock<PhaseFairLock, T>;
+
+/// A phase-fair rwlock backed by `parking_lot_core`
+///
+/// Readers and writers alternate in phases: when a writer arrives, it waits only for the
+/// readers that are already inside the lock, and readers that arrive after it wait for at
+/// most one writer phase. So under contention neither readers nor writers can be starved.
+///
+/// This is the parking counterpart of the [ticket lock](crate::rwlock::ticket), writers are
+/// queued on an [adaptive mutex](crate::mutex::adaptive), and threads park instead of
+/// spinning when they have to wait for long. Unlike the ticket lock, it supports timeouts.
+pub struct PhaseFairLock {
+    // number of readers that entered, with the writer bits in the low bits
+    read_in: AtomicUsize,
+    // number of readers that exited
+    read_out: AtomicUsize,
+    // serializes writers
+    writer: AdaptiveLock,
+}
+
+impl PhaseFairLock {
+    /// create a new phase-fair rwlock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            read_in: AtomicUsize::new(0),
+            read_out: AtomicUsize::new(0),
+            writer: AdaptiveLock::new(),
+        }
+    }
+
+    /// create a new phase-fair raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new phase-fair mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// create a new phase-fair raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a new phase-fair rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+
+    #[inline]
+    fn reader_key(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    #[inline]
+    fn writer_key(&self) -> usize {
+        self.reader_key() + 1
+    }
+
+    #[inline]
+    fn readers_left(&self, readers: usize) -> bool {
+        self.read_out.load(Ordering::Acquire) & READERS == readers
+    }
+
+    /// Block new readers, and wait for the readers that are already inside to leave
+    ///
+    /// The caller must hold `writer`, if this fails the write phase is ended
+    #[inline]
+    fn start_write_phase(&self, timeout: Option<Instant>) -> bool {
+        // `WRITER` is always clear here, so this sets it and flips the phase
+        let readers = self.read_in.fetch_xor(WRITER_BITS, Ordering::Acquire) & READERS;
+
+        if self.readers_left(readers) || self.wait_for_readers(readers, timeout) {
+            true
+        } else {
+            self.end_write_phase();
+            false
+        }
+    }
+
+    #[inline]
+    fn end_write_phase(&self) {
+        let state = self
+            .read_in
+            .fetch_and(!(WRITER | PARKED), Ordering::Release);
+
+        if state & PARKED != 0 {
+            self.unpark_readers();
+        }
+
+        unsafe { self.writer.exc_unlock() }
+    }
+
+    #[cold]
+    fn unpark_readers(&self) {
+        unsafe {
+            crate::parking::unpark_all(self.reader_key(), DEFAULT_UNPARK_TOKEN);
+        }
+    }
+
+    #[cold]
+    fn unpark_writer(&self) {
+        let callback = |_| {
+            self.read_out.fetch_and(!PARKED, Ordering::Relaxed);
+            DEFAULT_UNPARK_TOKEN
+        };
+
+        // SAFETY:
+        // * `writer_key` is an address we control.
+        // * `callback` does not panic or call into any function of `parking_lot`.
+        unsafe {
+            crate::parking::unpark_one(self.writer_key(), callback);
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn wait_for_readers(&self, readers: usize, timeout: Option<Instant>) -> bool {
+        let mut spin = SpinWait::new();
+        let mut state = self.read_out.load(Ordering::Acquire);
+
+        loop {
+            if state & READERS == readers {
+                if state & PARKED != 0 {
+                    self.read_out.fetch_and(!PARKED, Ordering::Relaxed);
+                }
+
+                return true;
+            }
+
+            if spin.spin() {
+                state = self.read_out.load(Ordering::Acquire);
+                continue;
+            }
+
+            if state & PARKED == 0 {
+                if let Err(x) = self.read_out.compare_exchange_weak(
+                    state,
+                    state | PARKED,
+                    Ordering::Relaxed,
+                    Ordering::Acquire,
+                ) {
+                    state = x;
+                    continue;
+                }
+            }
+
+            let validate = || {
+                let state = self.read_out.load(Ordering::Relaxed);
+                state & READERS != readers && state & PARKED != 0
+            };
+            let before_sleep = || {};
+            let timed_out = |_, _| {};
+
+            // SAFETY:
+            // * `writer_key` is an address we control.
+            // * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
+            // * `before_sleep` does not call `park`, nor does it panic.
+            let park_result = unsafe {
+                crate::parking::park(
+                    self.writer_key(),
+                    validate,
+                    before_sleep,
+                    timed_out,
+                    DEFAULT_PARK_TOKEN,
+                    timeout,
+                )
+            };
+
+            if let ParkResult::TimedOut = park_result {
+                self.read_out.fetch_and(!PARKED, Ordering::Relaxed);
+                return self.readers_left(readers);
+            }
+
+            state = self.read_out.load(Ordering::Acquire);
+        }
+    }
+
+    /// Wait for the writer phase `writer` to end
+    ///
+    /// The reader is already counted in `read_in`, so that the next writer waits for it
+    #[cold]
+    #[inline(never)]
+    fn shr_lock_slow(&self, writer: usize, timeout: Option<Instant>) -> bool {
+        let mut spin = SpinWait::new();
+        let mut state = self.read_in.load(Ordering::Acquire);
+
+        loop {
+            if state & WRITER_BITS != writer {
+                return true;
+            }
+
+            if spin.spin() {
+                state = self.read_in.load(Ordering::Acquire);
+                continue;
+            }
+
+            if state & PARKED == 0 {
+                if let Err(x) = self.read_in.compare_exchange_weak(
+                    state,
+                    state | PARKED,
+                    Ordering::Relaxed,
+                    Ordering::Acquire,
+                ) {
+                    state = x;
+                    continue;
+                }
+            }
+
+            let validate = || {
+                let state = self.read_in.load(Ordering::Relaxed);
+                state & WRITER_BITS == writer && state & PARKED != 0
+            };
+            let before_sleep = || {};
+            let timed_out = |_, _| {};
+
+            // SAFETY:
+            // * `reader_key` is an address we control.
+            // * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
+            // * `before_sleep` does not call `park`, nor does it panic.
+            let park_result = unsafe {
+                crate::parking::park(
+                    self.reader_key(),
+                    validate,
+                    before_sleep,
+                    timed_out,
+                    DEFAULT_PARK_TOKEN,
+                    timeout,
+                )
+            };
+
+            if let ParkResult::TimedOut = park_result {
+                return self.shr_abandon(writer);
+            }
+
+            state = self.read_in.load(Ordering::Acquire);
+        }
+    }
+
+    /// Back out of a *shr lock* that timed out, unless the writer phase ended in the meantime
+    ///
+    /// The reader is removed from `read_in` rather than being counted as exited, because
+    /// the current writer doesn't wait for it, and counting it as exited would let the
+    /// writer in before the readers it does wait for have left.
+    #[cold]
+    fn shr_abandon(&self, writer: usize) -> bool {
+        let mut state = self.read_in.load(Ordering::Acquire);
+
+        loop {
+            if state & WRITER_BITS != writer {
+                return true;
+            }
+
+            match self.read_in.compare_exchange_weak(
+                state,
+                state.wrapping_sub(READER_INC),
+                Ordering::Relaxed,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return false,
+                Err(x) => state = x,
+            }
+        }
+    }
+}
+
+crate::impl_init! {
+    PhaseFairLock = Self::new()
+}
+
+unsafe impl crate::mutex::RawMutex for PhaseFairLock {}
+unsafe impl crate::rwlock::RawRwLock for PhaseFairLock {}
+unsafe impl crate::RawLockInfo for PhaseFairLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = ();
+}
+
+impl crate::RawTimedLock for PhaseFairLock {
+    type Instant = Instant;
+    type Duration = Duration;
+}
+
+unsafe impl RawExclusiveLock for PhaseFairLock {
+    #[inline]
+    fn exc_lock(&self) {
+        self.writer.exc_lock();
+        self.start_write_phase(None);
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        if !self.writer.exc_try_lock() {
+            return false;
+        }
+
+        let readers = self.read_in.fetch_xor(WRITER_BITS, Ordering::Acquire) & READERS;
+
+        if self.readers_left(readers) {
+            true
+        } else {
+            self.end_write_phase();
+            false
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.end_write_phase()
+    }
+}
+
+unsafe impl RawExclusiveLockTimed for PhaseFairLock {
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.writer.exc_try_lock_until(instant) && self.start_write_phase(Some(instant))
+    }
+
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        match Instant::now().checked_add(duration) {
+            Some(instant) => self.exc_try_lock_until(instant),
+            None => {
+                self.exc_lock();
+                true
+            }
+        }
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLock for PhaseFairLock {
+    #[inline]
+    fn shr_lock(&self) {
+        let state = self.read_in.fetch_add(READER_INC, Ordering::Acquire);
+
+        if state & WRITER != 0 {
+            self.shr_lock_slow(state & WRITER_BITS, None);
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        let mut state = self.read_in.load(Ordering::Relaxed);
+
+        while state & WRITER == 0 {
+            match self.read_in.compare_exchange_weak(
+                state,
+                state.wrapping_add(READER_INC),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => state = x,
+            }
+        }
+
+        false
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        crate::share_lock::OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        // A waiting writer only waits for the readers it saw when it arrived, so
+        // the new reader is accounted for by requiring one more reader to exit
+        let mut read_out = self.read_out.load(Ordering::Relaxed);
+
+        loop {
+            let readers =
+                (self.read_in.load(Ordering::Relaxed) & READERS).wrapping_sub(read_out & READERS);
+
+            // one more reader would wrap the reader count around to zero
+            if readers == READERS {
+                return false;
+            }
+
+            match self.read_out.compare_exchange_weak(
+                read_out,
+                read_out.wrapping_sub(READER_INC),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => read_out = x,
+            }
+        }
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        let state = self.read_out.fetch_add(READER_INC, Ordering::Release);
+
+        if state & PARKED != 0 {
+            self.unpark_writer();
+        }
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockTimed for PhaseFairLock {
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        let state = self.read_in.fetch_add(READER_INC, Ordering::Acquire);
+
+        state & WRITER == 0 || self.shr_lock_slow(state & WRITER_BITS, Some(instant))
+    }
+
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        let state = self.read_in.fetch_add(READER_INC, Ordering::Acquire);
+
+        state & WRITER == 0
+            || self.shr_lock_slow(state & WRITER_BITS, Instant::now().checked_add(duration))
+    }
+}
+
+#[test]
+fn phase_fair() {
+    use crate::share_lock::ShareGuard;
+    use std::sync::atomic::AtomicBool;
+
+    let rwlock = PhaseFairLock::rwlock(0);
+    let duration = Duration::from_millis(10);
+
+    let a = rwlock.read();
+    let b = ShareGuard::clone(&a);
+    assert!(rwlock.try_write().is_none());
+    assert!(rwlock.try_write_for(duration).is_none());
+    drop(a);
+    assert!(rwlock.try_write().is_none());
+    drop(b);
+    assert!(rwlock.try_write().is_some());
+
+    // readers that arrive after a writer wait for it, but the writer
+    // only waits for the readers that were already inside
+    let written = AtomicBool::new(false);
+    let guard = rwlock.read();
+
+    crossbeam_utils::thread::scope(|s| {
+        let writer = s.spawn(|_| {
+            let mut guard = rwlock.write();
+            *guard += 1;
+            written.store(true, Ordering::Relaxed);
+        });
+
+        std::thread::sleep(duration);
+        assert!(rwlock.try_read().is_none());
+        assert!(rwlock.try_read_for(duration).is_none());
+
+        let reader = s.spawn(|_| {
+            let value = *rwlock.read();
+            assert!(written.load(Ordering::Relaxed));
+            value
+        });
+
+        std::thread::sleep(duration);
+        drop(guard);
+
+        writer.join().unwrap();
+        assert_eq!(reader.join().unwrap(), 1);
+    })
+    .unwrap();
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for i in 0..1000 {
+                    match i % 4 {
+                        0 => *rwlock.write() += 1,
+                        1 => {
+                            if let Some(mut guard) = rwlock.try_write_for(Duration::from_micros(10))
+                            {
+                                *guard += 1;
+                                *guard -= 1;
+                            }
+                        }
+                        2 => drop(rwlock.try_read_for(Duration::from_micros(10))),
+                        _ => drop(rwlock.read()),
+                    }
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(*rwlock.read(), 1001);
+    assert!(rwlock.try_write().is_some());
+}
@@ -1,26 +1,30 @@
 //! A type-safe implementation of a `RwLock`
 
+use core::mem::MaybeUninit;
+
 use super::RawRwLock;
-use crate::exclusive_lock::{RawExclusiveGuard, RawExclusiveLockTimed};
-use crate::share_lock::{RawShareGuard, RawShareLockTimed};
+use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveGuard, RawExclusiveLockTimed};
+use crate::share_lock::{Contention, RawShareGuard, RawShareLockTimed, ShareGuard};
 
 /// A read-write syncronization primitive useful for protecting shared data
 ///
 /// This rwlock will block threads waiting for the lock to become available.
 /// The rwlock can also be statically initialized or created via a `from_raw` constructor.
+///
+/// Guards can be upgraded and downgraded without releasing the lock, the legal
+/// transitions between them are listed in [`transition`](crate::rwlock::transition).
 #[repr(transparent)]
 pub struct RwLock<L: ?Sized> {
     lock: L,
 }
 
-impl<L: RawRwLock + crate::Init> Default for RwLock<L> {
-    #[inline]
-    fn default() -> Self {
-        crate::Init::INIT
-    }
-}
-
 impl<L> RwLock<L> {
+    /// The size of this raw rwlock in bytes
+    pub const SIZE: usize = core::mem::size_of::<Self>();
+
+    /// The alignment of this raw rwlock in bytes
+    pub const ALIGN: usize = core::mem::align_of::<Self>();
+
     /// Create a new raw mutex
     ///
     /// # Safety
@@ -36,6 +40,31 @@ impl<L> RwLock<L> {
     pub fn into_inner(self) -> L {
         self.lock
     }
+
+    /// Convert the underlying lock with the given function
+    ///
+    /// # Safety
+    ///
+    /// `f` must return a lock that is valid to pass to [`from_raw`](Self::from_raw)
+    #[inline]
+    pub unsafe fn map_lock<M>(self, f: impl FnOnce(L) -> M) -> RwLock<M> {
+        RwLock::from_raw(f(self.lock))
+    }
+
+    /// Convert this raw rwlock into a raw rwlock with another backend
+    #[inline]
+    pub fn convert<M: crate::rwlock::RawRwLock>(self) -> RwLock<M>
+    where
+        L: crate::ConvertLock<M>,
+    {
+        unsafe { self.map_lock(crate::ConvertLock::convert_lock) }
+    }
+
+    /// Convert this raw rwlock into a raw rwlock with any backend that implements [`Init`](crate::Init)
+    #[inline]
+    pub fn convert_init<M: crate::rwlock::RawRwLock + crate::Init>(self) -> RwLock<M> {
+        unsafe { self.map_lock(|_| M::INIT) }
+    }
 }
 
 impl<L: ?Sized> RwLock<L> {
@@ -70,8 +99,35 @@ impl<L: ?Sized> RwLock<L> {
     }
 }
 
-impl<L: crate::Init> crate::Init for RwLock<L> {
-    const INIT: Self = unsafe { Self::from_raw(L::INIT) };
+#[cfg(feature = "debug-owners")]
+impl<L: crate::debug_owners::RawLockHolder + ?Sized> RwLock<L> {
+    /// Information about the thread that currently holds the write lock, if any
+    ///
+    /// This is useful for logging who blocked a write lock that timed out
+    #[inline]
+    pub fn holder_info(&self) -> Option<crate::debug_owners::HolderInfo> {
+        self.lock.holder_info()
+    }
+}
+
+crate::impl_init! {
+    [L: crate::Init] RwLock<L> = unsafe { Self::from_raw(L::INIT) }
+}
+
+impl<L: RawRwLock + crate::Init> RwLock<L> {
+    /// Initialize a raw rwlock in place, and return a reference to it
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null, aligned to `Self::ALIGN`, valid for writes of `Self::SIZE` bytes,
+    /// and must not be aliased for the lifetime `'a`. Any previous value at `ptr` will be
+    /// overwritten without being dropped.
+    #[inline]
+    pub unsafe fn init_in_place<'a>(ptr: *mut MaybeUninit<Self>) -> &'a Self {
+        let ptr = ptr as *mut Self;
+        ptr.write(crate::Init::INIT);
+        &*ptr
+    }
 }
 
 impl<L: RawRwLock + ?Sized> RwLock<L>
@@ -100,6 +156,7 @@ where
     /// This function may panic if it is impossible to acquire the lock (in the case of deadlock or
     /// single threaded rwlock)
     #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
     pub fn write(&self) -> RawExclusiveGuard<'_, L> {
         unsafe {
             self.lock.exc_lock();
@@ -107,6 +164,18 @@ where
         }
     }
 
+    /// Locks this `RwLock` with exclusive write access through a mutable borrow
+    ///
+    /// Since the rwlock is borrowed mutably, no other thread can access it, so this
+    /// sets the lock state without synchronizing. [read more](crate::exclusive_lock::RawExclusiveLock#method.exc_lock_mut)
+    #[inline]
+    pub fn write_mut(&mut self) -> RawExclusiveGuard<'_, L> {
+        unsafe {
+            self.lock.exc_lock_mut();
+            self.write_unchecked()
+        }
+    }
+
     /// Attempts to lock this RwLock with exclusive write access.
     ///
     /// If the lock could not be acquired at this time, then None is returned.
@@ -114,6 +183,7 @@ where
     ///
     /// This function does not block.
     #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
     pub fn try_write(&self) -> Option<RawExclusiveGuard<'_, L>> {
         unsafe {
             if self.lock.exc_try_lock() {
@@ -134,6 +204,7 @@ where
     ///
     /// Returns an RAII guard which will release this thread's shared access once it is dropped.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn read(&self) -> RawShareGuard<'_, L> {
         unsafe {
             self.lock.shr_lock();
@@ -148,6 +219,7 @@ where
     ///
     /// This function does not block.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_read(&self) -> Option<RawShareGuard<'_, L>> {
         unsafe {
             if self.lock.shr_try_lock() {
@@ -157,6 +229,94 @@ where
             }
         }
     }
+
+    /// Locks this RwLock with shared read access, with a hint about the expected contention
+    ///
+    /// See [`RawShareLock::shr_try_lock_hint`](crate::share_lock::RawShareLock::shr_try_lock_hint) for how the hint is used
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read_hint(&self, contention: Contention) -> RawShareGuard<'_, L> {
+        unsafe {
+            self.lock.shr_lock_hint(contention);
+            self.read_unchecked()
+        }
+    }
+
+    /// Attempts to acquire this RwLock with shared read access, with a hint about the
+    /// expected contention
+    ///
+    /// See [`RawShareLock::shr_try_lock_hint`](crate::share_lock::RawShareLock::shr_try_lock_hint) for how the hint is used
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_read_hint(&self, contention: Contention) -> Option<RawShareGuard<'_, L>> {
+        unsafe {
+            if self.lock.shr_try_lock_hint(contention) {
+                Some(self.read_unchecked())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl<L: RawRwLock> RwLock<L>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Locks this `RwLock` with exclusive write access, and returns a guard to the data behind `value`
+    ///
+    /// This pairs the raw rwlock with data that it doesn't own, like data in an arena
+    /// or in an FFI struct, in the same way that [`RwLock`](crate::rwlock::RwLock) does.
+    ///
+    /// # Safety
+    ///
+    /// `value` must be valid for reads and writes for as long as the guard is alive,
+    /// and must only be accessed while this rwlock is locked
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub unsafe fn write_with<T: ?Sized>(&self, value: *mut T) -> ExclusiveGuard<'_, L, T> {
+        ExclusiveGuard::from_raw_parts(self.write(), value)
+    }
+
+    /// Attempts to lock this `RwLock` with exclusive write access,
+    /// and returns a guard to the data behind `value`
+    ///
+    /// # Safety
+    ///
+    /// See [`RwLock::write_with`]
+    #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
+    pub unsafe fn try_write_with<T: ?Sized>(
+        &self,
+        value: *mut T,
+    ) -> Option<ExclusiveGuard<'_, L, T>> {
+        Some(ExclusiveGuard::from_raw_parts(self.try_write()?, value))
+    }
+
+    /// Locks this `RwLock` with shared read access, and returns a guard to the data behind `value`
+    ///
+    /// # Safety
+    ///
+    /// `value` must be valid for reads for as long as the guard is alive, and must
+    /// only be accessed while this rwlock is locked
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub unsafe fn read_with<T: ?Sized>(&self, value: *const T) -> ShareGuard<'_, L, T> {
+        ShareGuard::from_raw_parts(self.read(), value)
+    }
+
+    /// Attempts to lock this `RwLock` with shared read access,
+    /// and returns a guard to the data behind `value`
+    ///
+    /// # Safety
+    ///
+    /// See [`RwLock::read_with`]
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub unsafe fn try_read_with<T: ?Sized>(&self, value: *const T) -> Option<ShareGuard<'_, L, T>> {
+        Some(ShareGuard::from_raw_parts(self.try_read()?, value))
+    }
 }
 
 impl<L: RawRwLock + RawExclusiveLockTimed + RawShareLockTimed + ?Sized> RwLock<L>
@@ -170,6 +330,7 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
     pub fn try_write_until(&self, instant: L::Instant) -> Option<RawExclusiveGuard<'_, L>> {
         if self.lock.exc_try_lock_until(instant) {
             unsafe { Some(self.write_unchecked()) }
@@ -184,6 +345,7 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(any(feature = "debug-owners", feature = "watchdog"), track_caller)]
     pub fn try_write_for(&self, duration: L::Duration) -> Option<RawExclusiveGuard<'_, L>> {
         if self.lock.exc_try_lock_for(duration) {
             unsafe { Some(self.write_unchecked()) }
@@ -198,6 +360,7 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_read_until(&self, instant: L::Instant) -> Option<RawShareGuard<'_, L>> {
         if self.lock.shr_try_lock_until(instant) {
             unsafe { Some(self.read_unchecked()) }
@@ -212,6 +375,7 @@ where
     /// then None is returned. Otherwise, an RAII guard is returned.
     /// The lock will be unlocked when the guard is dropped.
     #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
     pub fn try_read_for(&self, duration: L::Duration) -> Option<RawShareGuard<'_, L>> {
         if self.lock.shr_try_lock_for(duration) {
             unsafe { Some(self.read_unchecked()) }
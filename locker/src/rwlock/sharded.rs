@@ -86,6 +86,14 @@ unsafe impl<I: ThreadInfo, S: RawShareLock> RawShareLock for Sharded<I, [S]> {
         self.get().shr_split()
     }
 
+    unsafe fn shr_try_split(&self) -> bool {
+        debug_assert!(
+            !self.shards.is_empty(),
+            "You cannot use an empty shard list in a `Sharded`"
+        );
+        self.get().shr_try_split()
+    }
+
     unsafe fn shr_unlock(&self) {
         debug_assert!(
             !self.shards.is_empty(),
@@ -94,12 +102,12 @@ unsafe impl<I: ThreadInfo, S: RawShareLock> RawShareLock for Sharded<I, [S]> {
         self.get().shr_unlock();
     }
 
-    unsafe fn shr_bump(&self) {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
         debug_assert!(
             !self.shards.is_empty(),
             "You cannot use an empty shard list in a `Sharded`"
         );
-        self.get().shr_bump();
+        self.get().shr_bump()
     }
 }
 
@@ -112,8 +120,8 @@ unsafe impl<I: ThreadInfo, S: RawShareLockFair> RawShareLockFair for Sharded<I,
         self.get().shr_unlock_fair();
     }
 
-    unsafe fn shr_bump_fair(&self) {
-        self.get().shr_bump_fair();
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        self.get().shr_bump_fair()
     }
 }
 
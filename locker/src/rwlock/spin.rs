@@ -1,10 +1,17 @@
 //! a spin lock
 
+use crate::share_lock::Contention;
 use crate::spin_wait::SpinWait;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
 const EXC_LOCK: usize = !0;
 
+// `EXC_LOCK` is the largest lock state, so it can't be reached by adding readers
+#[inline]
+fn add_reader(state: usize) -> Option<usize> {
+    state.checked_add(1).filter(|&state| state != EXC_LOCK)
+}
+
 /// a raw mutex backed by a spin lock
 ///
 /// It is not reccomended to use this type in libraries,
@@ -53,8 +60,13 @@ pub type RwLock<T> = crate::rwlock::RwLock<SpinLock, T>;
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
+///
+/// There are never any parked threads in a spin lock, so unlocking fairly
+/// is the same as unlocking normally, and `bump_fair` is the same as `bump`.
 pub struct SpinLock {
     state: AtomicUsize,
+    #[cfg(feature = "debug-owners")]
+    holder: crate::debug_owners::HolderSlot,
 }
 
 impl SpinLock {
@@ -63,6 +75,8 @@ impl SpinLock {
     pub const fn new() -> Self {
         Self {
             state: AtomicUsize::new(0),
+            #[cfg(feature = "debug-owners")]
+            holder: crate::debug_owners::HolderSlot::new(),
         }
     }
 
@@ -88,6 +102,7 @@ impl SpinLock {
 
     #[cold]
     fn exc_lock_slow(&self) {
+        let _trace = crate::trace::SlowPath::enter("exc_lock", self);
         let mut spin = SpinWait::new();
 
         loop {
@@ -105,11 +120,13 @@ impl SpinLock {
 
     #[cold]
     fn shr_lock_slow(&self) {
+        let _trace = crate::trace::SlowPath::enter("shr_lock", self);
         let mut spin = SpinWait::new();
-        let state = self.state.load(Ordering::Relaxed);
+        let mut state = self.state.load(Ordering::Relaxed);
 
         loop {
-            if let Some(new_state) = state.checked_add(1) {
+            // this also blocks while there are too many readers
+            if let Some(new_state) = add_reader(state) {
                 if self
                     .state
                     .compare_exchange_weak(state, new_state, Ordering::Acquire, Ordering::Relaxed)
@@ -120,6 +137,7 @@ impl SpinLock {
             }
 
             spin.spin();
+            state = self.state.load(Ordering::Relaxed);
         }
     }
 
@@ -141,8 +159,29 @@ impl SpinLock {
     }
 }
 
-impl crate::Init for SpinLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    SpinLock = Self::new()
+}
+
+impl crate::raw_state::RawStateBits for SpinLock {
+    #[inline]
+    fn raw_state_bits(&self) -> usize {
+        self.state.load(Ordering::Relaxed)
+    }
+
+    fn decode_state(bits: usize) -> crate::raw_state::DecodedState {
+        if bits == EXC_LOCK {
+            crate::raw_state::DecodedState {
+                exc: 1,
+                ..Default::default()
+            }
+        } else {
+            crate::raw_state::DecodedState {
+                readers: bits,
+                ..Default::default()
+            }
+        }
+    }
 }
 
 unsafe impl crate::mutex::RawMutex for SpinLock {}
@@ -154,33 +193,77 @@ unsafe impl crate::RawLockInfo for SpinLock {
 
 unsafe impl crate::exclusive_lock::RawExclusiveLock for SpinLock {
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_lock(&self) {
         if !self.exc_try_lock() {
-            self.exc_lock_slow()
+            self.exc_lock_slow();
+
+            #[cfg(feature = "debug-owners")]
+            self.holder.record();
         }
     }
 
     #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     fn exc_try_lock(&self) -> bool {
-        self.state
+        let locked = self
+            .state
             .compare_exchange(0, EXC_LOCK, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok()
+            .is_ok();
+
+        #[cfg(feature = "debug-owners")]
+        self.holder.record_if(locked);
+
+        locked
+    }
+
+    #[inline]
+    #[cfg_attr(feature = "debug-owners", track_caller)]
+    fn exc_lock_mut(&mut self) {
+        let state = self.state.get_mut();
+        assert_eq!(*state, 0, "tried to lock a leaked exc lock");
+        *state = EXC_LOCK;
+
+        #[cfg(feature = "debug-owners")]
+        self.holder.record();
     }
 
     #[inline]
     unsafe fn exc_unlock(&self) {
+        #[cfg(feature = "debug-owners")]
+        self.holder.clear();
+
         self.state.store(0, Ordering::Release);
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        // there are never any parked threads in a spin lock
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for SpinLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        use crate::exclusive_lock::RawExclusiveLock;
+
+        self.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         // there are never any parked threads in a spin lock
+        crate::BumpResult::KEPT
     }
 }
 
 unsafe impl crate::exclusive_lock::RawExclusiveLockDowngrade for SpinLock {
     #[inline]
     unsafe fn downgrade(&self) {
+        #[cfg(feature = "debug-owners")]
+        self.holder.clear();
+
         self.state.store(1, Ordering::Relaxed);
     }
 }
@@ -197,7 +280,7 @@ unsafe impl crate::share_lock::RawShareLock for SpinLock {
     fn shr_try_lock(&self) -> bool {
         let state = self.state.load(Ordering::Acquire);
 
-        if let Some(new_state) = state.checked_add(1) {
+        if let Some(new_state) = add_reader(state) {
             self.state
                 .compare_exchange(state, new_state, Ordering::Acquire, Ordering::Relaxed)
                 .is_ok()
@@ -206,24 +289,42 @@ unsafe impl crate::share_lock::RawShareLock for SpinLock {
         }
     }
 
+    #[inline]
+    fn shr_try_lock_hint(&self, contention: Contention) -> bool {
+        match contention {
+            Contention::High => self.shr_try_lock(),
+            // guess that there are no readers or writers, which skips the initial load
+            Contention::Low => {
+                self.state
+                    .compare_exchange(0, 1, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+                    || self.shr_try_lock()
+            }
+        }
+    }
+
     #[inline]
     unsafe fn shr_split(&self) {
+        crate::share_lock::OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
         let mut state = self.state.load(Ordering::Relaxed);
 
         loop {
-            if let Some(new_state) = state.checked_add(1) {
-                if let Err(x) = self.state.compare_exchange(
-                    state,
-                    new_state,
-                    Ordering::Relaxed,
-                    Ordering::Relaxed,
-                ) {
-                    state = x;
-                } else {
-                    break;
-                }
+            let new_state = match add_reader(state) {
+                Some(new_state) => new_state,
+                None => return false,
+            };
+
+            if let Err(x) =
+                self.state
+                    .compare_exchange(state, new_state, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                state = x;
             } else {
-                panic!("Tried to create too many shared locks!");
+                return true;
             }
         }
     }
@@ -235,21 +336,126 @@ unsafe impl crate::share_lock::RawShareLock for SpinLock {
     }
 
     #[inline]
-    unsafe fn shr_bump(&self) {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        // there are never any parked threads in a spin lock
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockFair for SpinLock {
+    #[inline]
+    unsafe fn shr_unlock_fair(&self) {
+        use crate::share_lock::RawShareLock;
+
+        self.shr_unlock()
+    }
+
+    #[inline]
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
         // there are never any parked threads in a spin lock
+        crate::BumpResult::KEPT
     }
 }
 
 unsafe impl crate::share_lock::RawShareLockUpgrade for SpinLock {
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     unsafe fn upgrade(&self) {
         if !self.try_upgrade() {
             self.upgrade_slow();
+
+            #[cfg(feature = "debug-owners")]
+            self.holder.record();
         }
     }
 
+    #[cfg_attr(feature = "debug-owners", track_caller)]
     unsafe fn try_upgrade(&self) -> bool {
-        self.state
+        let locked = self
+            .state
             .compare_exchange(1, EXC_LOCK, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok()
+            .is_ok();
+
+        #[cfg(feature = "debug-owners")]
+        self.holder.record_if(locked);
+
+        locked
+    }
+}
+
+#[cfg(feature = "debug-owners")]
+impl crate::debug_owners::RawLockHolder for SpinLock {
+    #[inline]
+    fn holder_info(&self) -> Option<crate::debug_owners::HolderInfo> {
+        self.holder.get()
+    }
+}
+
+#[test]
+fn fair() {
+    use crate::exclusive_lock::ExclusiveGuard;
+    use crate::share_lock::ShareGuard;
+
+    let rwlock = SpinLock::rwlock(0);
+
+    let mut guard = rwlock.read();
+    ShareGuard::bump_fair(&mut guard);
+    assert!(rwlock.try_write().is_none());
+    ShareGuard::unlock_fair(guard);
+
+    let mut guard = rwlock.write();
+    ExclusiveGuard::bump_fair(&mut guard);
+    *guard += 1;
+    ExclusiveGuard::unlock_fair(guard);
+
+    assert_eq!(*rwlock.read(), 1);
+}
+
+#[test]
+fn guard_fmt_and_cmp() {
+    use crate::exclusive_lock::ExclusiveGuard;
+    use crate::share_lock::ShareGuard;
+
+    let rwlock = SpinLock::rwlock((1, String::from("value")));
+
+    let guard = ShareGuard::map(rwlock.read(), |(_, value)| value);
+    assert_eq!(guard, "value");
+    assert_eq!(format!("{} {:?}", guard, guard), "value \"value\"");
+    drop(guard);
+
+    let guard = ExclusiveGuard::map(rwlock.write(), |(value, _)| value);
+    assert_eq!(guard, 1);
+    assert!(guard < 2 && guard > 0);
+    assert_eq!(format!("{} {:?}", guard, guard), "1 1");
+}
+
+#[test]
+fn overflow_policy() {
+    use crate::combinators::BlockOnOverflow;
+    use crate::share_lock::RawShareLock;
+
+    let lock = SpinLock::new();
+    lock.state.store(EXC_LOCK - 1, Ordering::Relaxed);
+
+    unsafe {
+        assert!(!lock.shr_try_split());
+        assert!(!lock.shr_try_lock());
+
+        let result = std::panic::catch_unwind(|| lock.shr_split());
+        assert!(result.is_err());
     }
+
+    let lock = BlockOnOverflow(SpinLock::new());
+    lock.0.state.store(EXC_LOCK - 1, Ordering::Relaxed);
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            unsafe { lock.shr_unlock() }
+        });
+
+        unsafe { lock.shr_split() }
+    })
+    .unwrap();
+
+    assert_eq!(lock.0.state.load(Ordering::Relaxed), EXC_LOCK - 1);
 }
@@ -1,9 +1,10 @@
 //! an adaptive raw rwlock
 
 use crate::exclusive_lock::RawExclusiveLock;
-use crate::share_lock::RawShareLock;
+use crate::share_lock::{OverflowPolicy, RawShareLock};
 
-use parking_lot_core::{self, ParkResult, ParkToken, SpinWait, UnparkResult, UnparkToken};
+use crate::parking::{ParkResult, ParkToken, UnparkResult, UnparkToken};
+use crate::spin_wait::SpinWait;
 
 const PARK_BIT: usize = 0b0001;
 const EXC_PARK_BIT: usize = 0b0010;
@@ -96,8 +97,33 @@ impl SplitLock {
     }
 }
 
-impl crate::Init for SplitLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    SplitLock = Self::new()
+}
+
+impl crate::raw_state::RawStateBits for SplitLock {
+    #[inline]
+    fn raw_state_bits(&self) -> usize {
+        self.state.load(Ordering::Relaxed)
+    }
+
+    fn decode_state(bits: usize) -> crate::raw_state::DecodedState {
+        // readers and writers share the count, so while a writer
+        // waits for readers to leave, the readers are counted in `exc`
+        let count = (bits & COUNT) / INC;
+        let (exc, readers) = if bits & EXC_BIT != 0 {
+            (count, 0)
+        } else {
+            (0, count)
+        };
+
+        crate::raw_state::DecodedState {
+            exc,
+            readers,
+            parked: bits & PARK_BIT != 0,
+            exc_parked: bits & EXC_PARK_BIT != 0,
+        }
+    }
 }
 
 unsafe impl crate::mutex::RawMutex for SplitLock {}
@@ -139,9 +165,12 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for SplitLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
             self.exc_bump_slow(false);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
@@ -155,9 +184,12 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockFair for SplitLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
             self.exc_bump_slow(true);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
@@ -185,7 +217,12 @@ unsafe impl RawShareLock for SplitLock {
 
     #[inline]
     unsafe fn shr_split(&self) {
-        self.state.fetch_add(INC, Ordering::Relaxed);
+        OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.try_split()
     }
 
     #[inline]
@@ -196,9 +233,12 @@ unsafe impl RawShareLock for SplitLock {
     }
 
     #[inline]
-    unsafe fn shr_bump(&self) {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
         if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
             self.shr_bump_slow(false);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
@@ -212,20 +252,50 @@ unsafe impl crate::share_lock::RawShareLockFair for SplitLock {
     }
 
     #[inline]
-    unsafe fn shr_bump_fair(&self) {
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
         if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
             self.shr_bump_slow(true);
+            crate::BumpResult::YIELDED
+        } else {
+            crate::BumpResult::KEPT
         }
     }
 }
 
 unsafe impl crate::exclusive_lock::SplittableExclusiveLock for SplitLock {
     unsafe fn exc_split(&self) {
-        self.state.fetch_add(INC, Ordering::Relaxed);
+        assert!(
+            self.try_split(),
+            "tried to split the exclusive lock too many times"
+        );
     }
 }
 
 impl SplitLock {
+    /// add another lock to the lock count, without overflowing into the flags
+    #[inline]
+    fn try_split(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        loop {
+            let new_state = match state.checked_add(INC) {
+                Some(new_state) => new_state,
+                None => return false,
+            };
+
+            if let Err(x) = self.state.compare_exchange_weak(
+                state,
+                new_state,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                state = x;
+            } else {
+                return true;
+            }
+        }
+    }
+
     #[cold]
     fn exc_bump_slow(&self, force_fair: bool) {
         self.exc_unlock_slow(force_fair);
@@ -261,8 +331,8 @@ impl SplitLock {
 
     #[cold]
     fn unpark_shared(&self) {
+        use crate::parking::FilterOp;
         use core::cell::Cell;
-        use parking_lot_core::FilterOp;
 
         let count = Cell::new(0);
 
@@ -291,7 +361,7 @@ impl SplitLock {
         };
 
         unsafe {
-            parking_lot_core::unpark_filter(key, filter, callback);
+            crate::parking::unpark_filter(key, filter, callback);
         }
     }
 
@@ -318,7 +388,7 @@ impl SplitLock {
         };
 
         unsafe {
-            parking_lot_core::unpark_one(key, callback);
+            crate::parking::unpark_one(key, callback);
         }
     }
 
@@ -364,7 +434,7 @@ impl SplitLock {
             };
 
             unsafe {
-                parking_lot_core::unpark_one(key, callback);
+                crate::parking::unpark_one(key, callback);
             }
         } else {
             self.state.fetch_sub(INC, Ordering::Release);
@@ -377,7 +447,7 @@ impl SplitLock {
             };
 
             unsafe {
-                parking_lot_core::unpark_one(key, callback);
+                crate::parking::unpark_one(key, callback);
             }
         }
     }
@@ -421,7 +491,7 @@ impl SplitLock {
             //   * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
             //   * `before_sleep` does not call `park`, nor does it panic.
             let park_result = unsafe {
-                parking_lot_core::park(
+                crate::parking::park(
                     addr,
                     validate,
                     before_sleep,
@@ -606,7 +676,7 @@ impl SplitLock {
             // * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
             // * `before_sleep` does not call `park`, nor does it panic.
             let park_result = unsafe {
-                parking_lot_core::park(addr, validate, before_sleep, timed_out, park_token, timeout)
+                crate::parking::park(addr, validate, before_sleep, timed_out, park_token, timeout)
             };
 
             match park_result {
@@ -59,8 +59,8 @@ impl SplitDefaultLock {
     }
 }
 
-impl crate::Init for SplitDefaultLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    SplitDefaultLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for SplitDefaultLock {}
@@ -87,12 +87,11 @@ unsafe impl RawExclusiveLock for SplitDefaultLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
         self.0.exc_bump()
     }
 }
 
-#[cfg(feature = "parking_lot_core")]
 unsafe impl RawExclusiveLockFair for SplitDefaultLock {
     #[inline]
     unsafe fn exc_unlock_fair(&self) {
@@ -100,7 +99,7 @@ unsafe impl RawExclusiveLockFair for SplitDefaultLock {
     }
 
     #[inline]
-    unsafe fn exc_bump_fair(&self) {
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
         self.0.exc_bump_fair()
     }
 }
@@ -121,18 +120,22 @@ unsafe impl RawShareLock for SplitDefaultLock {
         self.0.shr_split()
     }
 
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.0.shr_try_split()
+    }
+
     #[inline]
     unsafe fn shr_unlock(&self) {
         self.0.shr_unlock()
     }
 
     #[inline]
-    unsafe fn shr_bump(&self) {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
         self.0.shr_bump()
     }
 }
 
-#[cfg(feature = "parking_lot_core")]
 unsafe impl RawShareLockFair for SplitDefaultLock {
     #[inline]
     unsafe fn shr_unlock_fair(&self) {
@@ -140,7 +143,7 @@ unsafe impl RawShareLockFair for SplitDefaultLock {
     }
 
     #[inline]
-    unsafe fn shr_bump_fair(&self) {
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
         self.0.shr_bump_fair()
     }
 }
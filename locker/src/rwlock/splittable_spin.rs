@@ -70,6 +70,9 @@ const INC: usize = 0b10;
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
+///
+/// There are never any parked threads in a spin lock, so unlocking fairly
+/// is the same as unlocking normally, and `bump_fair` is the same as `bump`.
 pub struct SplitSpinLock {
     state: AtomicUsize,
 }
@@ -158,13 +161,14 @@ impl SplitSpinLock {
         }
     }
 
-    fn split(&self) {
+    fn try_split(&self) -> bool {
         let mut state = self.state.load(Ordering::Relaxed);
 
         loop {
-            let new_state = state
-                .checked_add(INC)
-                .expect("tried to split too many times");
+            let new_state = match state.checked_add(INC) {
+                Some(new_state) => new_state,
+                None => return false,
+            };
 
             if let Err(x) = self.state.compare_exchange_weak(
                 state,
@@ -174,7 +178,7 @@ impl SplitSpinLock {
             ) {
                 state = x;
             } else {
-                return;
+                return true;
             }
         }
     }
@@ -203,8 +207,8 @@ impl SplitSpinLock {
     }
 }
 
-impl crate::Init for SplitSpinLock {
-    const INIT: Self = Self::new();
+crate::impl_init! {
+    SplitSpinLock = Self::new()
 }
 
 unsafe impl crate::mutex::RawMutex for SplitSpinLock {}
@@ -235,12 +239,32 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for SplitSpinLock {
     }
 
     #[inline]
-    unsafe fn exc_bump(&self) {}
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for SplitSpinLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        use crate::exclusive_lock::RawExclusiveLock;
+
+        self.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        // there are never any parked threads in a spin lock
+        crate::BumpResult::KEPT
+    }
 }
 
 unsafe impl crate::exclusive_lock::SplittableExclusiveLock for SplitSpinLock {
     unsafe fn exc_split(&self) {
-        self.split()
+        assert!(
+            self.try_split(),
+            "tried to split the exclusive lock too many times"
+        );
     }
 }
 
@@ -270,7 +294,12 @@ unsafe impl crate::share_lock::RawShareLock for SplitSpinLock {
 
     #[inline]
     unsafe fn shr_split(&self) {
-        self.split()
+        crate::share_lock::OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.try_split()
     }
 
     #[inline]
@@ -279,7 +308,24 @@ unsafe impl crate::share_lock::RawShareLock for SplitSpinLock {
     }
 
     #[inline]
-    unsafe fn shr_bump(&self) {}
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        crate::BumpResult::KEPT
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockFair for SplitSpinLock {
+    #[inline]
+    unsafe fn shr_unlock_fair(&self) {
+        use crate::share_lock::RawShareLock;
+
+        self.shr_unlock()
+    }
+
+    #[inline]
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        // there are never any parked threads in a spin lock
+        crate::BumpResult::KEPT
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,433 @@
+//! a lock backed by the Windows `SRWLOCK`
+//!
+//! This is enabled by the `windows-srw` feature flag, and is only available on Windows.
+//! Because these locks are OS primitives, they are visible to OS-level debugging tools,
+//! like Application Verifier and the `!locks` debugger extension.
+
+use core::cell::UnsafeCell;
+use core::convert::TryFrom;
+use core::ffi::c_void;
+use core::sync::atomic::{fence, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::spin_wait::SpinWait;
+
+#[repr(C)]
+#[allow(clippy::upper_case_acronyms)]
+struct SRWLOCK {
+    ptr: *mut c_void,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+type HANDLE = *mut c_void;
+#[allow(clippy::upper_case_acronyms)]
+type NTSTATUS = i32;
+
+const STATUS_SUCCESS: NTSTATUS = 0;
+const GENERIC_READ: u32 = 0x8000_0000;
+const GENERIC_WRITE: u32 = 0x4000_0000;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn AcquireSRWLockExclusive(lock: *mut SRWLOCK);
+    fn TryAcquireSRWLockExclusive(lock: *mut SRWLOCK) -> u8;
+    fn ReleaseSRWLockExclusive(lock: *mut SRWLOCK);
+    fn AcquireSRWLockShared(lock: *mut SRWLOCK);
+    fn TryAcquireSRWLockShared(lock: *mut SRWLOCK) -> u8;
+    fn ReleaseSRWLockShared(lock: *mut SRWLOCK);
+}
+
+#[link(name = "ntdll")]
+extern "system" {
+    fn NtCreateKeyedEvent(
+        handle: *mut HANDLE,
+        access: u32,
+        attributes: *mut c_void,
+        flags: u32,
+    ) -> NTSTATUS;
+    fn NtReleaseKeyedEvent(
+        handle: HANDLE,
+        key: *mut c_void,
+        alertable: u8,
+        timeout: *mut i64,
+    ) -> NTSTATUS;
+    fn NtWaitForKeyedEvent(
+        handle: HANDLE,
+        key: *mut c_void,
+        alertable: u8,
+        timeout: *mut i64,
+    ) -> NTSTATUS;
+    fn NtClose(handle: HANDLE) -> NTSTATUS;
+}
+
+// the keyed event shared by all `SrwLock`s, 0 if it wasn't created yet,
+// and 1 if it couldn't be created
+static KEYED_EVENT: AtomicUsize = AtomicUsize::new(0);
+
+fn keyed_event() -> Option<HANDLE> {
+    let handle = match KEYED_EVENT.load(Ordering::Acquire) {
+        0 => create_keyed_event(),
+        handle => handle,
+    };
+
+    if handle == 1 {
+        None
+    } else {
+        Some(handle as HANDLE)
+    }
+}
+
+#[cold]
+fn create_keyed_event() -> usize {
+    let mut handle: HANDLE = core::ptr::null_mut();
+
+    let status = unsafe {
+        NtCreateKeyedEvent(
+            &mut handle,
+            GENERIC_READ | GENERIC_WRITE,
+            core::ptr::null_mut(),
+            0,
+        )
+    };
+
+    let handle = if status == STATUS_SUCCESS {
+        handle as usize
+    } else {
+        1
+    };
+
+    match KEYED_EVENT.compare_exchange(0, handle, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => handle,
+        Err(current) => {
+            // another thread created the keyed event first
+            if handle != 1 {
+                unsafe { NtClose(handle as HANDLE) };
+            }
+
+            current
+        }
+    }
+}
+
+/// a raw mutex backed by a `SRWLOCK`
+pub type RawMutex = crate::mutex::raw::Mutex<SrwLock>;
+/// a mutex backed by a `SRWLOCK`
+pub type Mutex<T> = crate::mutex::Mutex<SrwLock, T>;
+/// a raw rwlock backed by a `SRWLOCK`
+pub type RawRwLock = crate::rwlock::raw::RwLock<SrwLock>;
+/// a rwlock backed by a `SRWLOCK`
+pub type RwLock<T> = crate::rwlock::RwLock<SrwLock, T>;
+
+/// A lock backed by the Windows `SRWLOCK`
+///
+/// `SRWLOCK`s can't be recursively acquired in shared mode, so *shr locks*
+/// that are created by `shr_split` are counted separately, and are released before
+/// the *shr lock* held on the `SRWLOCK`.
+///
+/// `SRWLOCK`s don't support timed waits, so timed waits sleep on a keyed event,
+/// keyed by the address of the lock, and every unlock wakes one of the sleeping
+/// threads. If keyed events are not available, timed waits poll the lock with
+/// an exponential backoff until the timeout is reached.
+pub struct SrwLock {
+    lock: UnsafeCell<SRWLOCK>,
+    split: AtomicUsize,
+    // the number of threads in a timed wait, that no unlock has claimed yet
+    sleepers: AtomicUsize,
+}
+
+unsafe impl Send for SrwLock {}
+unsafe impl Sync for SrwLock {}
+
+impl SrwLock {
+    /// create a new `SRWLOCK`
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            lock: UnsafeCell::new(SRWLOCK {
+                ptr: core::ptr::null_mut(),
+            }),
+            split: AtomicUsize::new(0),
+            sleepers: AtomicUsize::new(0),
+        }
+    }
+
+    /// create a new `SRWLOCK` based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new `SRWLOCK` based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// create a new `SRWLOCK` based raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a new `SRWLOCK` based rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+
+    /// a pointer to the underlying `SRWLOCK`, for use with other Windows APIs
+    #[inline]
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.lock.get().cast()
+    }
+
+    #[inline]
+    fn key(&self) -> *mut c_void {
+        self as *const Self as *mut c_void
+    }
+
+    fn try_lock_until(&self, instant: Instant, try_lock: impl Fn() -> bool) -> bool {
+        let mut spin = SpinWait::new();
+        // if this thread took a wake up, then it must pass it on to the next sleeper,
+        // either because the lock may be shared with it, or because this thread timed out
+        let mut woken = false;
+
+        let locked = loop {
+            if try_lock() {
+                break true;
+            }
+
+            let now = Instant::now();
+
+            if now >= instant {
+                break false;
+            }
+
+            if spin.spin() {
+                continue;
+            }
+
+            let handle = match keyed_event() {
+                Some(handle) => handle,
+                None => {
+                    std::thread::yield_now();
+                    continue;
+                }
+            };
+
+            self.sleepers.fetch_add(1, Ordering::SeqCst);
+
+            // the lock may have been released before we registered as a sleeper
+            if try_lock() {
+                woken |= self.cancel_sleep(handle);
+                break true;
+            }
+
+            // keyed event timeouts are relative if they are negative, in units of 100ns
+            let mut timeout = -i64::try_from((instant - now).as_nanos() / 100).unwrap_or(i64::MAX);
+            let status = unsafe { NtWaitForKeyedEvent(handle, self.key(), 0, &mut timeout) };
+
+            woken |= status == STATUS_SUCCESS || self.cancel_sleep(handle);
+            spin.reset();
+        };
+
+        if woken {
+            self.wake();
+        }
+
+        locked
+    }
+
+    /// stop sleeping without being woken up, returns true if a wake up was taken anyways
+    #[cold]
+    fn cancel_sleep(&self, handle: HANDLE) -> bool {
+        let claimed = self
+            .sleepers
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |sleepers| {
+                sleepers.checked_sub(1)
+            })
+            .is_err();
+
+        if claimed {
+            // an unlock has already claimed this thread, and will release the keyed event
+            // for it, which blocks until a thread waits on the key, so take that release
+            unsafe { NtWaitForKeyedEvent(handle, self.key(), 0, core::ptr::null_mut()) };
+        }
+
+        claimed
+    }
+
+    /// wake up one thread that is in a timed wait, if there is one
+    #[inline]
+    fn wake(&self) {
+        fence(Ordering::SeqCst);
+
+        if self.sleepers.load(Ordering::Relaxed) != 0 {
+            self.wake_slow()
+        }
+    }
+
+    #[cold]
+    fn wake_slow(&self) {
+        let claimed = self
+            .sleepers
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |sleepers| {
+                sleepers.checked_sub(1)
+            })
+            .is_ok();
+
+        if let (true, Some(handle)) = (claimed, keyed_event()) {
+            unsafe { NtReleaseKeyedEvent(handle, self.key(), 0, core::ptr::null_mut()) };
+        }
+    }
+}
+
+crate::impl_init! {
+    SrwLock = Self::new()
+}
+
+unsafe impl crate::mutex::RawMutex for SrwLock {}
+unsafe impl crate::rwlock::RawRwLock for SrwLock {}
+unsafe impl crate::RawLockInfo for SrwLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = ();
+}
+
+impl crate::RawTimedLock for SrwLock {
+    type Instant = Instant;
+    type Duration = Duration;
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLock for SrwLock {
+    #[inline]
+    fn exc_lock(&self) {
+        unsafe { AcquireSRWLockExclusive(self.lock.get()) }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        unsafe { TryAcquireSRWLockExclusive(self.lock.get()) != 0 }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        ReleaseSRWLockExclusive(self.lock.get());
+        self.wake();
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for SrwLock {
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        use crate::exclusive_lock::RawExclusiveLock;
+
+        self.try_lock_until(instant, || self.exc_try_lock())
+    }
+
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        use crate::exclusive_lock::RawExclusiveLock;
+
+        match Instant::now().checked_add(duration) {
+            Some(instant) => self.exc_try_lock_until(instant),
+            None => {
+                self.exc_lock();
+                true
+            }
+        }
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLock for SrwLock {
+    #[inline]
+    fn shr_lock(&self) {
+        unsafe { AcquireSRWLockShared(self.lock.get()) }
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        unsafe { TryAcquireSRWLockShared(self.lock.get()) != 0 }
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        crate::share_lock::OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        // `AcquireSRWLockShared` may block if there is a waiting writer,
+        // so split locks are counted separately
+        let mut split = self.split.load(Ordering::Relaxed);
+
+        while let Some(next) = split.checked_add(1) {
+            match self.split.compare_exchange_weak(
+                split,
+                next,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => split = x,
+            }
+        }
+
+        false
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        let mut split = self.split.load(Ordering::Relaxed);
+
+        while let Some(next) = split.checked_sub(1) {
+            match self.split.compare_exchange_weak(
+                split,
+                next,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(x) => split = x,
+            }
+        }
+
+        ReleaseSRWLockShared(self.lock.get());
+        self.wake();
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockTimed for SrwLock {
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        use crate::share_lock::RawShareLock;
+
+        self.try_lock_until(instant, || self.shr_try_lock())
+    }
+
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        use crate::share_lock::RawShareLock;
+
+        match Instant::now().checked_add(duration) {
+            Some(instant) => self.shr_try_lock_until(instant),
+            None => {
+                self.shr_lock();
+                true
+            }
+        }
+    }
+}
+
+#[test]
+fn srw_lock() {
+    use crate::share_lock::ShareGuard;
+
+    let rwlock = SrwLock::rwlock(0);
+
+    *rwlock.write() += 1;
+
+    let a = rwlock.read();
+    let b = ShareGuard::clone(&a);
+    assert!(rwlock.try_write().is_none());
+    drop(a);
+    assert!(rwlock.try_write_for(Duration::from_millis(1)).is_none());
+    drop(b);
+
+    let guard = rwlock.write();
+    assert!(rwlock.try_read_for(Duration::from_millis(1)).is_none());
+    drop(guard);
+
+    assert_eq!(*rwlock.read(), 1);
+}
@@ -0,0 +1,268 @@
+//! a phase-fair ticket rwlock
+
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+// the phase id of the current writer
+const PHASE_ID: usize = 0b01;
+// set when there is a writer present
+const WRITER_PRESENT: usize = 0b10;
+const WRITER_BITS: usize = PHASE_ID | WRITER_PRESENT;
+// the increment for reader counts
+const READER_INC: usize = 0b100;
+
+/// a raw mutex backed by a phase-fair ticket lock
+pub type RawMutex = crate::mutex::raw::Mutex<TicketLock>;
+/// a mutex backed by a phase-fair ticket lock
+pub type Mutex<T> = crate::mutex::Mutex<TicketLock, T>;
+/// a raw rwlock backed by a phase-fair ticket lock
+pub type RawRwLock = crate::rwlock::raw::RwLock<TicketLock>;
+/// a rwlock backed by a phase-fair ticket lock
+pub type RwLock<T> = crate::rwlock::RwLock<TicketLock, T>;
+
+/// A phase-fair ticket rwlock
+///
+/// Writers are served in FIFO order using tickets, and readers and writers alternate
+/// in phases. When a writer arrives, it waits only for the readers that are already
+/// inside the lock, and readers that arrive after it will wait for at most one writer.
+/// So neither readers nor writers can be starved, even without parking threads.
+///
+/// Because of this, the lock is always unlocked fairly, and `unlock_fair` is the same as `unlock`.
+///
+/// Like the [spin lock](crate::rwlock::spin), it is not reccomended to use this type unless you
+/// need these fairness guarantees in environments that can't park threads.
+pub struct TicketLock {
+    // number of readers that entered, with the writer bits in the low bits
+    read_in: AtomicUsize,
+    // number of readers that exited
+    read_out: AtomicUsize,
+    // writer tickets
+    write_in: AtomicUsize,
+    write_out: AtomicUsize,
+}
+
+impl TicketLock {
+    /// create a new ticket rwlock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            read_in: AtomicUsize::new(0),
+            read_out: AtomicUsize::new(0),
+            write_in: AtomicUsize::new(0),
+            write_out: AtomicUsize::new(0),
+        }
+    }
+
+    /// create a new ticket lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new ticket lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// create a new ticket lock based raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a new ticket lock based rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+
+    #[inline]
+    fn wait_until(mut f: impl FnMut() -> bool) {
+        let mut spin = SpinWait::new();
+
+        while !f() {
+            if !spin.spin() {
+                spin.reset();
+            }
+        }
+    }
+}
+
+crate::impl_init! {
+    TicketLock = Self::new()
+}
+
+unsafe impl crate::mutex::RawMutex for TicketLock {}
+unsafe impl crate::rwlock::RawRwLock for TicketLock {}
+unsafe impl crate::RawLockInfo for TicketLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = ();
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLock for TicketLock {
+    #[inline]
+    fn exc_lock(&self) {
+        let ticket = self.write_in.fetch_add(1, Ordering::Relaxed);
+
+        Self::wait_until(|| self.write_out.load(Ordering::Acquire) == ticket);
+
+        // block new readers, and wait for the current readers to leave
+        let writer = WRITER_PRESENT | (ticket & PHASE_ID);
+        let readers = self.read_in.fetch_add(writer, Ordering::Acquire) & !WRITER_BITS;
+
+        Self::wait_until(|| self.read_out.load(Ordering::Acquire) == readers);
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let ticket = self.write_out.load(Ordering::Relaxed);
+
+        if self
+            .write_in
+            .compare_exchange(
+                ticket,
+                ticket.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            return false;
+        }
+
+        let writer = WRITER_PRESENT | (ticket & PHASE_ID);
+        let readers = self.read_in.fetch_add(writer, Ordering::Acquire) & !WRITER_BITS;
+
+        if self.read_out.load(Ordering::Acquire) == readers {
+            true
+        } else {
+            // back out, this will release any readers that started waiting on us
+            self.read_in.fetch_and(!WRITER_BITS, Ordering::Release);
+            self.write_out
+                .store(ticket.wrapping_add(1), Ordering::Release);
+            false
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.read_in.fetch_and(!WRITER_BITS, Ordering::Release);
+
+        // only the owner of the lock can change `write_out`
+        let ticket = self.write_out.load(Ordering::Relaxed);
+        self.write_out
+            .store(ticket.wrapping_add(1), Ordering::Release);
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for TicketLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        use crate::exclusive_lock::RawExclusiveLock;
+
+        self.exc_unlock()
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLock for TicketLock {
+    #[inline]
+    fn shr_lock(&self) {
+        let writer = self.read_in.fetch_add(READER_INC, Ordering::Acquire) & WRITER_BITS;
+
+        if writer != 0 {
+            // wait for the current writer phase to end
+            Self::wait_until(|| self.read_in.load(Ordering::Acquire) & WRITER_BITS != writer);
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        let state = self.read_in.load(Ordering::Relaxed);
+
+        state & WRITER_BITS == 0
+            && self
+                .read_in
+                .compare_exchange(
+                    state,
+                    state.wrapping_add(READER_INC),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        crate::share_lock::OverflowPolicy::Panic.split(|| self.shr_try_split())
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        // A waiting writer only waits for the readers it saw when it arrived, so
+        // the new reader is accounted for by requiring one more reader to exit
+        let mut read_out = self.read_out.load(Ordering::Relaxed);
+
+        loop {
+            let readers =
+                (self.read_in.load(Ordering::Relaxed) & !WRITER_BITS).wrapping_sub(read_out);
+
+            // one more reader would wrap the reader count around to zero
+            if readers == !WRITER_BITS {
+                return false;
+            }
+
+            match self.read_out.compare_exchange_weak(
+                read_out,
+                read_out.wrapping_sub(READER_INC),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => read_out = x,
+            }
+        }
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        self.read_out.fetch_add(READER_INC, Ordering::Release);
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockFair for TicketLock {
+    #[inline]
+    unsafe fn shr_unlock_fair(&self) {
+        use crate::share_lock::RawShareLock;
+
+        self.shr_unlock()
+    }
+}
+
+#[test]
+fn phase_fair() {
+    use crate::share_lock::ShareGuard;
+
+    let rwlock = TicketLock::rwlock(0);
+
+    let a = rwlock.read();
+    let b = ShareGuard::clone(&a);
+    assert!(rwlock.try_write().is_none());
+    drop(a);
+    assert!(rwlock.try_write().is_none());
+    drop(b);
+    assert!(rwlock.try_write().is_some());
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for i in 0..1000 {
+                    if i % 4 == 0 {
+                        *rwlock.write() += 1;
+                    } else {
+                        let _x = *rwlock.read();
+                    }
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(*rwlock.read(), 1000);
+}
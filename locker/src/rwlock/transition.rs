@@ -0,0 +1,250 @@
+//! Transitions between the kinds of guards of a rwlock
+//!
+//! A guard of a rwlock is always in one of the states of [`GuardKind`], and a guard can
+//! move between states without releasing the lock in between. These are the legal
+//! transitions, which are checked by [`GuardKind::can_transition_to`] and
+//! [`GuardKind::can_try_transition_to`]:
+//!
+//! | from \ to      | `Shared`      | `Upgradable`  | `Exclusive`       |
+//! |----------------|---------------|---------------|-------------------|
+//! | `Shared`       | no-op         | illegal       | try upgrade only  |
+//! | `Upgradable`   | downgrade     | no-op         | upgrade, blocks   |
+//! | `Exclusive`    | downgrade     | downgrade     | no-op             |
+//!
+//! A shared guard can't become upgradable, because there may be other shared guards,
+//! and at most one upgradable guard can exist at a time. For the same reason, a shared
+//! guard may only try to upgrade: if two shared guards waited for each other to be
+//! released while upgrading, they would deadlock. An upgradable guard can wait for the
+//! shared guards to be released, so that is the only kind of transition that blocks.
+//! Downgrading never blocks.
+//!
+//! None of the locks in this crate support upgradable guards yet, so [`RawRwLockGuard`]
+//! reports [`TransitionError::Unsupported`] for transitions into the `Upgradable` state.
+//!
+//! ```
+//! use locker::rwlock::transition::{GuardKind, RawRwLockGuard};
+//! use locker::rwlock::spin::SpinLock;
+//!
+//! let lock = SpinLock::new();
+//!
+//! let guard = RawRwLockGuard::shared(&lock);
+//! let guard = guard.try_transition_to(GuardKind::Exclusive).unwrap();
+//! assert_eq!(guard.kind(), GuardKind::Exclusive);
+//!
+//! let guard = guard.transition_to(GuardKind::Shared).unwrap();
+//! let _other = RawRwLockGuard::shared(&lock);
+//!
+//! // can't upgrade while there is another shared guard
+//! let guard = guard.try_transition_to(GuardKind::Exclusive).unwrap_err().into_guard();
+//! assert_eq!(guard.kind(), GuardKind::Shared);
+//! ```
+
+use crate::exclusive_lock::{RawExclusiveGuard, RawExclusiveLockDowngrade};
+use crate::share_lock::{RawShareGuard, RawShareLockUpgrade};
+use crate::{Inhabitted, RawLockInfo};
+
+/// The kind of access that a guard of a rwlock has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GuardKind {
+    /// A *shr lock*, which may be held by many guards at once
+    Shared,
+    /// A *shr lock* which can be upgraded without releasing it, at most one guard
+    /// may be upgradable at a time
+    Upgradable,
+    /// A *exc lock*, which is held by exactly one guard
+    Exclusive,
+}
+
+impl GuardKind {
+    /// Check if a guard of this kind may be turned into a guard of kind `to`,
+    /// without releasing the lock in between, waiting if that blocks
+    ///
+    /// See the [module docs](self) for the full table of transitions
+    pub const fn can_transition_to(self, to: GuardKind) -> bool {
+        !matches!(
+            (self, to),
+            (GuardKind::Shared, GuardKind::Upgradable) | (GuardKind::Shared, GuardKind::Exclusive)
+        )
+    }
+
+    /// Check if a guard of this kind may try to turn into a guard of kind `to`,
+    /// without releasing the lock in between, and without blocking
+    ///
+    /// See the [module docs](self) for the full table of transitions
+    pub const fn can_try_transition_to(self, to: GuardKind) -> bool {
+        !matches!((self, to), (GuardKind::Shared, GuardKind::Upgradable))
+    }
+
+    /// Check if turning a guard of this kind into a guard of kind `to` may block
+    pub const fn transition_blocks(self, to: GuardKind) -> bool {
+        matches!((self, to), (GuardKind::Upgradable, GuardKind::Exclusive))
+    }
+}
+
+/// The error returned when a guard transition fails, which holds the unchanged guard
+#[derive(Debug)]
+pub enum TransitionError<G> {
+    /// The transition isn't allowed, see [`GuardKind::can_transition_to`] and
+    /// [`GuardKind::can_try_transition_to`]
+    Illegal(G),
+    /// The transition is allowed, but the lock doesn't support the target kind of guard
+    Unsupported(G),
+    /// The transition would have blocked
+    WouldBlock(G),
+}
+
+impl<G> TransitionError<G> {
+    /// Get back the guard, which is still in the state it was in before the transition
+    pub fn into_guard(self) -> G {
+        match self {
+            TransitionError::Illegal(guard)
+            | TransitionError::Unsupported(guard)
+            | TransitionError::WouldBlock(guard) => guard,
+        }
+    }
+}
+
+/// A raw guard of a rwlock, in any of the states that the lock supports
+///
+/// This allows writing code that is generic over the kind of guard it holds,
+/// see the [module docs](self) for details
+pub enum RawRwLockGuard<'a, L: RawLockInfo + RawShareLockUpgrade> {
+    /// A shared guard
+    Shared(RawShareGuard<'a, L>),
+    /// An exclusive guard
+    Exclusive(RawExclusiveGuard<'a, L>),
+}
+
+impl<'a, L: RawLockInfo + RawShareLockUpgrade + RawExclusiveLockDowngrade> RawRwLockGuard<'a, L>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+    L::ShareGuardTraits: Inhabitted,
+{
+    /// Acquire a shared guard, blocking until it is acquired
+    pub fn shared(lock: &'a L) -> Self {
+        RawRwLockGuard::Shared(RawShareGuard::new(lock))
+    }
+
+    /// Acquire an exclusive guard, blocking until it is acquired
+    pub fn exclusive(lock: &'a L) -> Self {
+        RawRwLockGuard::Exclusive(RawExclusiveGuard::new(lock))
+    }
+
+    /// The kind of access this guard has
+    pub fn kind(&self) -> GuardKind {
+        match self {
+            RawRwLockGuard::Shared(guard) => guard.kind(),
+            RawRwLockGuard::Exclusive(guard) => guard.kind(),
+        }
+    }
+
+    /// The lock that this guard holds
+    pub fn inner(&self) -> &L {
+        match self {
+            RawRwLockGuard::Shared(guard) => guard.inner(),
+            RawRwLockGuard::Exclusive(guard) => guard.inner(),
+        }
+    }
+
+    fn check(self, to: GuardKind, legal: bool) -> Result<Self, TransitionError<Self>> {
+        if !legal {
+            Err(TransitionError::Illegal(self))
+        } else if to == GuardKind::Upgradable {
+            Err(TransitionError::Unsupported(self))
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Turn this guard into a guard of kind `to`, blocking if the transition blocks
+    ///
+    /// Shared guards can't upgrade with this, see [`try_transition_to`](Self::try_transition_to)
+    pub fn transition_to(self, to: GuardKind) -> Result<Self, TransitionError<Self>> {
+        let legal = self.kind().can_transition_to(to);
+
+        Ok(match self.check(to, legal)? {
+            RawRwLockGuard::Exclusive(guard) if to == GuardKind::Shared => {
+                RawRwLockGuard::Shared(guard.into())
+            }
+            guard => guard,
+        })
+    }
+
+    /// Turn this guard into a guard of kind `to`, without blocking
+    pub fn try_transition_to(self, to: GuardKind) -> Result<Self, TransitionError<Self>> {
+        let legal = self.kind().can_try_transition_to(to);
+
+        Ok(match self.check(to, legal)? {
+            RawRwLockGuard::Shared(guard) if to == GuardKind::Exclusive => {
+                match guard.try_upgrade() {
+                    Ok(guard) => RawRwLockGuard::Exclusive(guard),
+                    Err(guard) => {
+                        return Err(TransitionError::WouldBlock(RawRwLockGuard::Shared(guard)))
+                    }
+                }
+            }
+            // downgrading never blocks
+            RawRwLockGuard::Exclusive(guard) if to == GuardKind::Shared => {
+                RawRwLockGuard::Shared(guard.into())
+            }
+            guard => guard,
+        })
+    }
+}
+
+impl<L: RawLockInfo + RawShareLockUpgrade + RawExclusiveLockDowngrade> core::fmt::Debug
+    for RawRwLockGuard<'_, L>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+    L::ShareGuardTraits: Inhabitted,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RawRwLockGuard")
+            .field("kind", &self.kind())
+            .finish()
+    }
+}
+
+#[test]
+fn transitions() {
+    use GuardKind::*;
+
+    let kinds = [Shared, Upgradable, Exclusive];
+    for from in kinds {
+        for to in kinds {
+            assert_eq!(
+                from.can_try_transition_to(to),
+                (from, to) != (Shared, Upgradable)
+            );
+            assert_eq!(from.can_transition_to(to), from != Shared || to == Shared);
+            assert_eq!(
+                from.transition_blocks(to),
+                (from, to) == (Upgradable, Exclusive)
+            );
+        }
+    }
+
+    let lock = crate::rwlock::spin::SpinLock::new();
+
+    let guard = RawRwLockGuard::exclusive(&lock);
+    let guard = match guard.transition_to(Upgradable) {
+        Err(TransitionError::Unsupported(guard)) => guard,
+        _ => panic!("upgradable guards aren't supported"),
+    };
+
+    let guard = guard.try_transition_to(Shared).unwrap();
+    let guard = match guard.transition_to(Upgradable) {
+        Err(TransitionError::Illegal(guard)) => guard,
+        _ => panic!("shared guards can't become upgradable"),
+    };
+    assert_eq!(guard.kind(), Shared);
+
+    // two shared guards upgrading at once would deadlock, so only trying is allowed
+    let guard = match guard.transition_to(Exclusive) {
+        Err(TransitionError::Illegal(guard)) => guard,
+        _ => panic!("shared guards can't block while upgrading"),
+    };
+
+    let guard = guard.try_transition_to(Exclusive).unwrap();
+    assert!(RawShareGuard::try_new(guard.inner()).is_none());
+}
@@ -0,0 +1,355 @@
+//! A rwlock that counts writes, for optimistic concurrency control
+//!
+//! [`Versioned`] wraps a raw rwlock and increments a version every time a write lock is
+//! released. A reader can remember the version that it read, do some work without holding
+//! the lock, and then [`validate`](RwLock::validate) that nothing was written in between
+//! before committing the result, instead of keeping a hand-rolled sequence counter next to
+//! a plain rwlock.
+//!
+//! ```
+//! use locker::rwlock::default::DefaultLock;
+//! use locker::rwlock::versioned::Versioned;
+//!
+//! let rwlock = Versioned::<DefaultLock>::rwlock(vec![1, 2, 3]);
+//!
+//! let (guard, version) = rwlock.read_versioned();
+//! let sum: i32 = guard.iter().sum();
+//! drop(guard);
+//!
+//! // the expensive part happens without holding the lock
+//! let average = sum / 3;
+//!
+//! let mut guard = rwlock.write();
+//! if rwlock.validate(version) {
+//!     guard.push(average);
+//! }
+//! drop(guard);
+//!
+//! assert_eq!(rwlock.version(), version + 1);
+//! assert!(!rwlock.validate(version));
+//! ```
+//!
+//! The version is only stable while a lock is held, so validating without holding a
+//! lock only tells if a write was completed up until that point.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::exclusive_lock::{
+    RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair, RawExclusiveLockTimed,
+};
+use crate::share_lock::{
+    RawShareLock, RawShareLockFair, RawShareLockTimed, RawShareLockUpgrade,
+    RawShareLockUpgradeTimed, ShareGuard,
+};
+use crate::{Init, RawLockInfo, RawTimedLock};
+
+/// a raw rwlock that counts writes
+pub type RawRwLock<L> = crate::rwlock::raw::RwLock<Versioned<L>>;
+
+/// a rwlock that counts writes
+pub type RwLock<L, T> = crate::rwlock::RwLock<Versioned<L>, T>;
+
+// set while the write lock is held, the version is stored in the remaining bits, so
+// releasing the write lock clears this bit and increments the version with a single add
+const WRITING: u64 = 1;
+
+/// Wraps a raw rwlock, and increments a version every time the write lock is released
+///
+/// Downgrading a write lock, and bumping a write lock, count as releasing it.
+#[derive(Debug, Default)]
+pub struct Versioned<L: ?Sized> {
+    state: AtomicU64,
+    lock: L,
+}
+
+impl<L> Versioned<L> {
+    /// Wrap `lock`, starting at version 0
+    #[inline]
+    pub const fn new(lock: L) -> Self {
+        Self {
+            state: AtomicU64::new(0),
+            lock,
+        }
+    }
+}
+
+impl<L: Init> Versioned<L> {
+    /// create a new versioned rwlock, starting at version 0
+    pub const fn rwlock<T>(value: T) -> RwLock<L, T> {
+        unsafe { RwLock::from_raw_parts(RawRwLock::from_raw(Self::new(L::INIT)), value) }
+    }
+}
+
+impl<L: ?Sized> Versioned<L> {
+    /// The underlying lock
+    #[inline]
+    pub const fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    /// The number of times the write lock was released
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.state.load(Ordering::Acquire) >> 1
+    }
+
+    /// Check that the write lock wasn't released since `version` was read
+    ///
+    /// A write that is still in progress isn't counted yet, so this should be called
+    /// while holding the lock, usually the write lock that commits the result.
+    #[inline]
+    pub fn validate(&self, version: u64) -> bool {
+        (self.state.load(Ordering::Acquire) & !WRITING) == version << 1
+    }
+
+    #[inline]
+    fn begin_write(&self) {
+        self.state.fetch_add(WRITING, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn end_write(&self) {
+        self.state.fetch_add(WRITING, Ordering::Release);
+    }
+}
+
+impl<L: crate::rwlock::RawRwLock, T: ?Sized> crate::rwlock::RwLock<Versioned<L>, T> {
+    /// The number of times the write lock was released, see [`Versioned::version`]
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.raw().inner().version()
+    }
+
+    /// Check that the write lock wasn't released since `version` was read,
+    /// see [`Versioned::validate`]
+    #[inline]
+    pub fn validate(&self, version: u64) -> bool {
+        self.raw().inner().validate(version)
+    }
+
+    /// Acquire a read lock, along with the version of the value that it reads
+    #[inline]
+    pub fn read_versioned(&self) -> (ShareGuard<'_, Versioned<L>, T>, u64)
+    where
+        L::ExclusiveGuardTraits: crate::Inhabitted,
+        L::ShareGuardTraits: crate::Inhabitted,
+    {
+        let guard = self.read();
+        (guard, self.version())
+    }
+}
+
+unsafe impl<L: crate::mutex::RawMutex> crate::mutex::RawMutex for Versioned<L> {}
+unsafe impl<L: crate::rwlock::RawRwLock> crate::rwlock::RawRwLock for Versioned<L> {}
+
+impl<L: Init> Init for Versioned<L> {
+    const INIT: Self = Self::new(Init::INIT);
+}
+
+unsafe impl<L: RawLockInfo + ?Sized> RawLockInfo for Versioned<L> {
+    type ExclusiveGuardTraits = <L as RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <L as RawLockInfo>::ShareGuardTraits;
+
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        self.lock.name()
+    }
+}
+
+impl<L: RawTimedLock + ?Sized> RawTimedLock for Versioned<L> {
+    type Instant = L::Instant;
+    type Duration = L::Duration;
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLock> RawExclusiveLock for Versioned<L> {
+    #[inline]
+    fn exc_lock(&self) {
+        self.lock.exc_lock();
+        self.begin_write();
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let locked = self.lock.exc_try_lock();
+
+        if locked {
+            self.begin_write();
+        }
+
+        locked
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.end_write();
+        self.lock.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        self.end_write();
+        let result = self.lock.exc_bump();
+        self.begin_write();
+        result
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLockFair for Versioned<L> {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        self.end_write();
+        self.lock.exc_unlock_fair()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        self.end_write();
+        let result = self.lock.exc_bump_fair();
+        self.begin_write();
+        result
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockTimed> RawExclusiveLockTimed for Versioned<L> {
+    #[inline]
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        let locked = self.lock.exc_try_lock_until(instant);
+
+        if locked {
+            self.begin_write();
+        }
+
+        locked
+    }
+
+    #[inline]
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        let locked = self.lock.exc_try_lock_for(duration);
+
+        if locked {
+            self.begin_write();
+        }
+
+        locked
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockDowngrade> RawExclusiveLockDowngrade for Versioned<L> {
+    #[inline]
+    unsafe fn downgrade(&self) {
+        self.end_write();
+        self.lock.downgrade()
+    }
+
+    #[inline]
+    unsafe fn try_downgrade(&self) -> bool {
+        // readers that get in after the downgrade must see the new version
+        self.end_write();
+        let downgraded = self.lock.try_downgrade();
+
+        if !downgraded {
+            self.begin_write();
+        }
+
+        downgraded
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLock> RawShareLock for Versioned<L> {
+    #[inline]
+    fn shr_lock(&self) {
+        self.lock.shr_lock()
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        self.lock.shr_try_lock()
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        self.lock.shr_split()
+    }
+
+    #[inline]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.lock.shr_try_split()
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        self.lock.shr_unlock()
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        self.lock.shr_bump()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockFair> RawShareLockFair for Versioned<L> {
+    #[inline]
+    unsafe fn shr_unlock_fair(&self) {
+        self.lock.shr_unlock_fair()
+    }
+
+    #[inline]
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        self.lock.shr_bump_fair()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockTimed> RawShareLockTimed for Versioned<L> {
+    #[inline]
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.lock.shr_try_lock_until(instant)
+    }
+
+    #[inline]
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.lock.shr_try_lock_for(duration)
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockUpgrade> RawShareLockUpgrade for Versioned<L> {
+    #[inline]
+    unsafe fn upgrade(&self) {
+        self.lock.upgrade();
+        self.begin_write();
+    }
+
+    #[inline]
+    unsafe fn try_upgrade(&self) -> bool {
+        let upgraded = self.lock.try_upgrade();
+
+        if upgraded {
+            self.begin_write();
+        }
+
+        upgraded
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockUpgradeTimed> RawShareLockUpgradeTimed for Versioned<L> {
+    #[inline]
+    unsafe fn try_upgrade_until(&self, instant: Self::Instant) -> bool {
+        let upgraded = self.lock.try_upgrade_until(instant);
+
+        if upgraded {
+            self.begin_write();
+        }
+
+        upgraded
+    }
+
+    #[inline]
+    unsafe fn try_upgrade_for(&self, duration: Self::Duration) -> bool {
+        let upgraded = self.lock.try_upgrade_for(duration);
+
+        if upgraded {
+            self.begin_write();
+        }
+
+        upgraded
+    }
+}
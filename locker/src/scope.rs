@@ -0,0 +1,85 @@
+//! Integration with [`std::thread::scope`]
+//!
+//! These helpers move a held lock into a thread spawned in a scope, which is
+//! useful for pipelines where ownership of a locked resource moves between stages.
+//! The lock is always acquired on the calling thread, so stages acquire the lock
+//! in the order they were started, and it is released with a fair unlock so that
+//! the next waiting stage gets the lock.
+
+use std::thread::{Scope, ScopedJoinHandle};
+
+use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveLockFair};
+use crate::mutex::{Mutex, RawMutex};
+use crate::RawLockInfo;
+
+/// Move a held guard into a new thread spawned in `scope`
+///
+/// The new thread runs `f` with the guard, then unlocks it using a fair
+/// unlocking protocol, handing the lock off to the next waiting thread.
+pub fn send_guard_to_scope<'scope, 'env, L, T, St, F, R>(
+    scope: &'scope Scope<'scope, 'env>,
+    mut guard: ExclusiveGuard<'env, L, T, St>,
+    f: F,
+) -> ScopedJoinHandle<'scope, R>
+where
+    L: RawExclusiveLockFair + RawLockInfo,
+    T: ?Sized,
+    ExclusiveGuard<'env, L, T, St>: Send + 'scope,
+    F: FnOnce(&mut ExclusiveGuard<'env, L, T, St>) -> R + Send + 'scope,
+    R: Send + 'scope,
+{
+    scope.spawn(move || {
+        let value = f(&mut guard);
+        ExclusiveGuard::unlock_fair(guard);
+        value
+    })
+}
+
+impl<L: RawMutex + RawExclusiveLockFair, T: ?Sized> Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// Acquires the mutex on the current thread, then hands the guard off
+    /// to a new thread spawned in `scope`
+    ///
+    /// See [`send_guard_to_scope`] for details
+    pub fn lock_scoped<'scope, 'env, F, R>(
+        &'env self,
+        scope: &'scope Scope<'scope, 'env>,
+        f: F,
+    ) -> ScopedJoinHandle<'scope, R>
+    where
+        ExclusiveGuard<'env, L, T>: Send,
+        F: FnOnce(&mut ExclusiveGuard<'env, L, T>) -> R + Send + 'scope,
+        R: Send + 'scope,
+    {
+        send_guard_to_scope(scope, self.lock(), f)
+    }
+}
+
+#[test]
+#[cfg(feature = "extra")]
+fn lock_scoped() {
+    use std::sync::Barrier;
+
+    let mutex = crate::mutex::default::DefaultLock::mutex(Vec::new());
+    let barrier = Barrier::new(2);
+
+    std::thread::scope(|s| {
+        let first = mutex.lock_scoped(s, |v| {
+            barrier.wait();
+            v.push(0);
+        });
+
+        // the lock is held as soon as `lock_scoped` returns
+        assert!(mutex.try_lock().is_none());
+        barrier.wait();
+
+        let second = mutex.lock_scoped(s, |v| v.push(1));
+
+        first.join().unwrap();
+        second.join().unwrap();
+    });
+
+    assert_eq!(*mutex.lock(), [0, 1]);
+}
@@ -5,8 +5,8 @@
 mod guard;
 mod raw;
 
-pub use guard::{MappedShareGuard, ShareGuard};
-pub use raw::{RawShareGuard, _RawShareGuard};
+pub use guard::{GuardedIter, MappedShareGuard, ShareGuard};
+pub use raw::{_RawShareGuard, RawShareGuard};
 
 #[cfg(doc)]
 use crate::RawLockInfo;
@@ -36,11 +36,26 @@ use crate::RawLockInfo;
 ///
 /// All of these rules are enforced in a safe way through [`RawShareGuard`].
 ///
+/// Trying to take a *shr lock* of an exclusive-only lock is rejected at compile time,
+/// with an error that points to a rwlock-capable lock instead
+///
+/// ```compile_fail,E0277
+/// use locker::share_lock::RawShareGuard;
+///
+/// let lock = locker::mutex::default::DefaultLock::new();
+/// let _guard = RawShareGuard::new(&lock);
+/// ```
+///
 /// # Safety
 ///
 /// * `shr_unlock` must be called `n` times before `exc_lock`,
 /// `exc_try_lock` can succeed (provided that `RawExclusiveLock` is implemented),
 /// where `n` is the number of times `shr_lock` and `shr_split` are called combined
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not support *shr locks*",
+    label = "this lock is exclusive-only",
+    note = "use a rwlock-capable lock, like `rwlock::default::DefaultLock`, for shared access"
+)]
 pub unsafe trait RawShareLock {
     /// acquire a *shr locks*
     ///
@@ -58,6 +73,36 @@ pub unsafe trait RawShareLock {
     /// returns true on success
     fn shr_try_lock(&self) -> bool;
 
+    /// acquire a *shr lock*, with a hint about how contended the lock is expected to be
+    ///
+    /// blocks until lock is acquired, see [`RawShareLock::shr_try_lock_hint`]
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if the lock is cannot be acquired
+    #[inline]
+    fn shr_lock_hint(&self, contention: Contention) {
+        if !self.shr_try_lock_hint(contention) {
+            self.shr_lock();
+        }
+    }
+
+    /// attempts to acquire a *shr lock*, with a hint about how contended the lock is expected to be
+    ///
+    /// With [`Contention::Low`], a lock may assume that it is unlocked, and try to acquire it
+    /// with a single compare-and-swap instead of loading its state first. This is faster when
+    /// the guess is right, and costs an extra atomic operation when it is wrong.
+    /// By default the hint is ignored.
+    ///
+    /// This function is non-blocking and may not panic
+    ///
+    /// returns true on success
+    #[inline]
+    fn shr_try_lock_hint(&self, contention: Contention) -> bool {
+        let _ = contention;
+        self.shr_try_lock()
+    }
+
     /// Re-acquire the lock without checking if it was already acquired.
     /// This is equivilent to just calling `shr_lock`, but can be more efficient
     /// in most cases.
@@ -68,8 +113,30 @@ pub unsafe trait RawShareLock {
     ///
     /// * the caller must own a *shr lock*
     /// * the lock must not have been moved since it was locked
+    ///
+    /// # Panic
+    ///
+    /// This function should panic if the number of *shr lock*s would overflow,
+    /// see [`OverflowPolicy::Panic`]
     unsafe fn shr_split(&self);
 
+    /// Re-acquire the lock without checking if it was already acquired,
+    /// unless the number of *shr lock*s would overflow.
+    ///
+    /// acquires a *shr lock* and returns true on success
+    ///
+    /// This is the checked version of [`RawShareLock::shr_split`],
+    /// which is used to implement each [`OverflowPolicy`]
+    ///
+    /// # Safety
+    ///
+    /// * the caller must own a *shr lock*
+    /// * the lock must not have been moved since it was locked
+    unsafe fn shr_try_split(&self) -> bool {
+        self.shr_split();
+        true
+    }
+
     /// Unlock a single shared lock
     ///
     /// This releases a *shr lock*
@@ -84,14 +151,70 @@ pub unsafe trait RawShareLock {
     ///
     /// This method is functionally equivalent to calling `shr_unlock` followed by `shr_lock`,
     /// however it can be much more efficient in the case where there are no waiting threads.
+    /// Returns whether the lock was actually released, see [`BumpResult`](crate::BumpResult).
     ///
     /// # Safety
     ///
     /// * the caller must own a *shr lock*
     /// * the lock must not have been moved since it was locked
-    unsafe fn shr_bump(&self) {
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
         self.shr_unlock();
         self.shr_lock();
+        crate::BumpResult::YIELDED
+    }
+}
+
+/// How much contention a lock is expected to see when it is acquired
+///
+/// See [`RawShareLock::shr_try_lock_hint`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Contention {
+    /// The lock is probably unlocked
+    Low,
+    /// The lock is probably held by other threads
+    High,
+}
+
+/// What to do when creating another *shr lock* would overflow the lock's reader count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Panic, this is what all of the locks in this crate do by default
+    Panic,
+    /// Block until enough *shr lock*s are released to create a new one
+    ///
+    /// There is no way to be notified when a *shr lock* is released, so this spins,
+    /// and yields the thread between rounds of spinning if the `std` feature is enabled.
+    /// This will never return if the current thread holds all of the *shr lock*s
+    Block,
+}
+
+impl OverflowPolicy {
+    /// Split a *shr lock* with `try_split`, and follow this policy if it fails
+    #[inline]
+    #[track_caller]
+    pub fn split(self, mut try_split: impl FnMut() -> bool) {
+        if !try_split() {
+            self.split_slow(&mut try_split)
+        }
+    }
+
+    #[cold]
+    #[track_caller]
+    fn split_slow(self, try_split: &mut dyn FnMut() -> bool) {
+        match self {
+            Self::Panic => panic!("tried to create too many shared locks"),
+            Self::Block => {
+                let mut spin = crate::spin_wait::SpinWait::new();
+
+                while !try_split() {
+                    if !spin.spin() {
+                        #[cfg(feature = "std")]
+                        std::thread::yield_now();
+                        spin.reset();
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -117,6 +240,31 @@ pub unsafe trait RawShareLockTimed: RawShareLock + crate::RawTimedLock {
     ///
     /// returns true on success
     fn shr_try_lock_for(&self, duration: Self::Duration) -> bool;
+
+    /// Temporarily yields the lock to a waiting thread if there is one, but only
+    /// waits up to `duration` to get the lock back.
+    ///
+    /// This is useful for cooperative yielding in latency-sensitive loops. If the lock
+    /// could not be re-acquired within `duration` (for example, because the lock was
+    /// handed over to a slow thread), then this blocks until the lock is re-acquired, and
+    /// returns false. The caller should use this as a signal to stop yielding.
+    ///
+    /// returns true if the lock was re-acquired within `duration`
+    ///
+    /// # Safety
+    ///
+    /// * the caller must own a *shr lock*
+    /// * the lock must not have been moved since it was locked
+    unsafe fn shr_bump_for(&self, duration: Self::Duration) -> bool {
+        self.shr_unlock();
+
+        if self.shr_try_lock_for(duration) {
+            true
+        } else {
+            self.shr_lock();
+            false
+        }
+    }
 }
 
 /// Additional methods for locks which support fair unlocking.
@@ -146,14 +294,16 @@ pub unsafe trait RawShareLockFair: RawShareLock {
     ///
     /// This method is functionally equivalent to calling `shr_unlock_fair` followed by `shr_lock`,
     /// however it can be much more efficient in the case where there are no waiting threads.
+    /// Returns whether the lock was actually released, see [`BumpResult`](crate::BumpResult).
     ///
     /// # Safety
     ///
     /// * the caller must own a *shr lock*
     /// * the lock must not have been moved since it was locked
-    unsafe fn shr_bump_fair(&self) {
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
         self.shr_unlock_fair();
         self.shr_lock();
+        crate::BumpResult::YIELDED
     }
 }
 
@@ -243,15 +393,29 @@ macro_rules! trait_impls {
                 L::shr_try_lock(self)
             }
 
+            #[inline]
+            fn shr_lock_hint(&self, contention: Contention) {
+                L::shr_lock_hint(self, contention)
+            }
+
+            #[inline]
+            fn shr_try_lock_hint(&self, contention: Contention) -> bool {
+                L::shr_try_lock_hint(self, contention)
+            }
+
             unsafe fn shr_split(&self) {
                 L::shr_split(self)
             }
 
+            unsafe fn shr_try_split(&self) -> bool {
+                L::shr_try_split(self)
+            }
+
             unsafe fn shr_unlock(&self) {
                 L::shr_unlock(self)
             }
 
-            unsafe fn shr_bump(&self) {
+            unsafe fn shr_bump(&self) -> crate::BumpResult {
                 L::shr_bump(self)
             }
         }
@@ -264,6 +428,10 @@ macro_rules! trait_impls {
             fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
                 L::shr_try_lock_for(self, duration)
             }
+
+            unsafe fn shr_bump_for(&self, duration: Self::Duration) -> bool {
+                L::shr_bump_for(self, duration)
+            }
         }
 
         unsafe impl<$L: ?Sized + RawShareLockFair> RawShareLockFair for $type {
@@ -271,7 +439,7 @@ macro_rules! trait_impls {
                 L::shr_unlock_fair(self)
             }
 
-            unsafe fn shr_bump_fair(&self) {
+            unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
                 L::shr_bump_fair(self)
             }
         }
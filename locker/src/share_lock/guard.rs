@@ -1,8 +1,10 @@
 use super::{RawShareGuard, RawShareLock, RawShareLockFair};
 use crate::RawLockInfo;
+use core::fmt;
 use core::marker::PhantomData;
 use core::ops::Deref;
 
+use crate::guard::Origin;
 pub use crate::guard::{Mapped, Pure, TryMapError};
 
 /// An RAII exclusive guard guard returned by `ShareGuard::map`,
@@ -18,6 +20,7 @@ pub type MappedShareGuard<'a, L, T> = ShareGuard<'a, L, T, Mapped>;
 pub struct ShareGuard<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St = Pure> {
     raw: RawShareGuard<'a, L>,
     value: *const T,
+    origin: Origin,
     _repr: PhantomData<(&'a T, St)>,
 }
 
@@ -42,10 +45,21 @@ impl<L: RawShareLockFair + RawLockInfo, T: ?Sized, St> ShareGuard<'_, L, T, St>
     }
 }
 
+impl<L: super::RawShareLockTimed + RawLockInfo, T: ?Sized> ShareGuard<'_, L, T> {
+    /// Temporarily yields the lock to another thread if there is one,
+    /// but only waits up to `duration` to get the lock back.
+    ///
+    /// Returns false if the lock could not be re-acquired in time, the lock is held either way.
+    /// [read more](super::RawShareLockTimed#method.shr_bump_for)
+    pub fn bump_for(g: &mut Self, duration: L::Duration) -> bool {
+        g.raw.bump_for(duration)
+    }
+}
+
 impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized> ShareGuard<'a, L, T> {
     /// Temporarily yields the lock to another thread if there is one.
     /// [read more](RawShareLock#method.shr_bump)
-    pub fn bump(g: &mut Self) {
+    pub fn bump(g: &mut Self) -> crate::BumpResult {
         g.raw.bump()
     }
 
@@ -60,8 +74,8 @@ impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized> ShareGuard<'a, L, T> {
 impl<'a, L: RawShareLockFair + RawLockInfo, T: ?Sized> ShareGuard<'a, L, T> {
     /// Temporarily yields the lock to a waiting thread if there is one.
     /// [read more](RawShareLockFair#method.shr_bump_fair)
-    pub fn bump_fair(g: &mut Self) {
-        g.raw.bump_fair();
+    pub fn bump_fair(g: &mut Self) -> crate::BumpResult {
+        g.raw.bump_fair()
     }
 
     /// Temporarily unlocks the lock to execute the given function.
@@ -89,13 +103,14 @@ impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St> ShareGuard<'a, L, T, St>
                 Self {
                     raw,
                     value,
+                    origin: Origin::NONE,
                     _repr: PhantomData,
                 }
             }
 
             /// The inner `RawShareGuard`
-            pub const fn raw(&self) -> &RawShareGuard<'a, L> {
-                &self.raw
+            pub const fn raw(g: &Self) -> &RawShareGuard<'a, L> {
+                &g.raw
             }
 
             /// The inner `RawShareGuard`
@@ -104,8 +119,8 @@ impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St> ShareGuard<'a, L, T, St>
             ///
             /// * You must not unlock this lock temporarily if this is a mapped lock
             /// * You must not overwrite the raw guard with another raw guard
-            pub const unsafe fn raw_mut(&mut self) -> &mut RawShareGuard<'a, L> {
-                &mut self.raw
+            pub const unsafe fn raw_mut(g: &mut Self) -> &mut RawShareGuard<'a, L> {
+                &mut g.raw
             }
         } else {
             /// Create a new guard from the given raw guard and pointer
@@ -120,6 +135,7 @@ impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St> ShareGuard<'a, L, T, St>
                 Self {
                     raw,
                     value,
+                    origin: Origin::NONE,
                     _repr: PhantomData,
                 }
             }
@@ -141,6 +157,13 @@ impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St> ShareGuard<'a, L, T, St>
         }
     }
 
+    /// Remember the typed lock this guard was created from
+    #[inline]
+    pub(crate) fn with_origin(mut g: Self, origin: Origin) -> Self {
+        g.origin = origin;
+        g
+    }
+
     /// Decomposes the `ShareGuard` into it's raw parts
     ///
     /// Returns the [`RawShareGuard`] and a pointer to the guarded value.
@@ -158,18 +181,50 @@ impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St> ShareGuard<'a, L, T, St>
         (g.raw, g.value)
     }
 
+    /// Convert this guard into a `MappedShareGuard` of the same data
+    ///
+    /// This gives up the ability to temporarily unlock the lock, and is how generic code can
+    /// treat a guard in any [`GuardState`](crate::GuardState) as a `MappedShareGuard`.
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::into_mapped(...)`.
+    #[inline]
+    pub fn into_mapped(g: Self) -> MappedShareGuard<'a, L, T> {
+        unsafe { ShareGuard::from_raw_parts(g.raw, g.value) }
+    }
+
     /// Make a new `MappedExclusiveGuard` for a component of the locked data.
     ///
     /// This operation cannot fail as the `ExclusiveGuard` passed in already locked the data.
     ///
     /// This is an associated function that needs to be used as `ExclusiveGuard::map(...)`.
     /// A method would interfere with methods of the same name on the contents of the locked data.
-    pub fn map<F, U: ?Sized>(g: Self, f: impl FnOnce(&T) -> &U) -> ShareGuard<'a, L, U, Mapped> {
+    pub fn map<U: ?Sized>(g: Self, f: impl FnOnce(&T) -> &U) -> ShareGuard<'a, L, U, Mapped> {
         let value = f(unsafe { &*g.value });
 
         unsafe { ShareGuard::from_raw_parts(g.raw, value) }
     }
 
+    /// Coerces the guarded pointer, this is how [`unsize_guard`](crate::unsize_guard) is implemented
+    ///
+    /// # Safety
+    ///
+    /// `f` must return the pointer it was given, only changed by an unsizing coercion
+    #[doc(hidden)]
+    #[inline]
+    pub unsafe fn __locker_unsize<U: ?Sized>(
+        self,
+        f: impl FnOnce(*const T) -> *const U,
+    ) -> ShareGuard<'a, L, U, St> {
+        let g = self;
+
+        ShareGuard {
+            raw: g.raw,
+            value: f(g.value),
+            origin: Origin::NONE,
+            _repr: PhantomData,
+        }
+    }
+
     /// Attempts to make a new `MappedExclusiveGuard` for a component of the locked data.
     /// The original guard is return if the closure returns `Err` as well as the error.
     ///
@@ -207,6 +262,39 @@ impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St> ShareGuard<'a, L, T, St>
         })
     }
 
+    /// Make `N` new `MappedShareGuard`s for components of the locked data.
+    ///
+    /// This is like `split_map`, but for any number of components of the same type.
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::split_into(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn split_into<U: ?Sized, const N: usize>(
+        g: Self,
+        f: impl FnOnce(&T) -> [&U; N],
+    ) -> [ShareGuard<'a, L, U, Mapped>; N] {
+        let values = f(unsafe { &*g.value });
+        let raw = g.raw;
+
+        values.map(|value| unsafe { ShareGuard::from_raw_parts(raw.clone(), value) })
+    }
+
+    /// Attempts to make `N` new `MappedShareGuard`s for components of the locked data.
+    ///
+    /// This is like `split_into`, but `f` can return `None` for components that are missing,
+    /// which don't get a guard. The lock is held until all of the returned guards are dropped.
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::try_map_split_many(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn try_map_split_many<U: ?Sized, const N: usize>(
+        g: Self,
+        f: impl FnOnce(&T) -> [Option<&U>; N],
+    ) -> [Option<ShareGuard<'a, L, U, Mapped>>; N] {
+        let values = f(unsafe { &*g.value });
+        let raw = g.raw;
+
+        values.map(|value| Some(unsafe { ShareGuard::from_raw_parts(raw.clone(), value?) }))
+    }
+
     /// Attempts to make two new `MappedExclusiveGuard`s for a component of the locked data.
     /// The original guard is return if the closure returns `Err` as well as the error.
     ///
@@ -246,10 +334,14 @@ where
     ///
     /// This function may panic if the lock is impossible to acquire
     pub fn upgrade(g: Self) -> crate::exclusive_lock::ExclusiveGuard<'a, L, T> {
-        unsafe {
+        let origin = g.origin;
+
+        let g = unsafe {
             let (raw, ptr) = ShareGuard::into_raw_parts(g);
             crate::exclusive_lock::ExclusiveGuard::from_raw_parts(raw.upgrade(), ptr as *mut T)
-        }
+        };
+
+        crate::exclusive_lock::ExclusiveGuard::with_origin(g, origin)
     }
 
     /// Attempts to atomically upgrades a read lock lock into a exclusive write lock,
@@ -257,20 +349,28 @@ where
     ///
     /// returns a exclusive guard if successful, otherwise returns the current guard
     pub fn try_upgrade(g: Self) -> Result<crate::exclusive_lock::ExclusiveGuard<'a, L, T>, Self> {
+        let origin = g.origin;
+
         unsafe {
             let (raw, ptr) = ShareGuard::into_raw_parts(g);
 
             match raw.try_upgrade() {
-                Ok(raw) => Ok(crate::exclusive_lock::ExclusiveGuard::from_raw_parts(
-                    raw,
-                    ptr as *mut T,
+                Ok(raw) => Ok(crate::exclusive_lock::ExclusiveGuard::with_origin(
+                    crate::exclusive_lock::ExclusiveGuard::from_raw_parts(raw, ptr as *mut T),
+                    origin,
                 )),
-                Err(raw) => Err(Self::from_raw_parts(raw, ptr)),
+                Err(raw) => Err(Self::with_origin(Self::from_raw_parts(raw, ptr), origin)),
             }
         }
     }
 }
 
+#[cfg(feature = "nightly")]
+impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized + core::marker::Unsize<U>, U: ?Sized, St>
+    core::ops::CoerceUnsized<ShareGuard<'a, L, U, St>> for ShareGuard<'a, L, T, St>
+{
+}
+
 impl<L: RawShareLock + RawLockInfo, T: ?Sized, St> Deref for ShareGuard<'_, L, T, St> {
     type Target = T;
 
@@ -281,6 +381,155 @@ impl<L: RawShareLock + RawLockInfo, T: ?Sized, St> Deref for ShareGuard<'_, L, T
 
 impl<L: RawShareLock + RawLockInfo, T: ?Sized, St> Clone for ShareGuard<'_, L, T, St> {
     fn clone(&self) -> Self {
-        unsafe { Self::from_raw_parts(self.raw.clone(), &*self.value) }
+        let g = unsafe { Self::from_raw_parts(self.raw.clone(), &*self.value) };
+        Self::with_origin(g, self.origin)
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + fmt::Debug, St> fmt::Debug
+    for ShareGuard<'_, L, T, St>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + fmt::Display, St> fmt::Display
+    for ShareGuard<'_, L, T, St>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+/// Compares the protected value with `U`, this only reads through the guard,
+/// so it never locks anything
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + PartialEq<U>, U: ?Sized, St> PartialEq<U>
+    for ShareGuard<'_, L, T, St>
+{
+    fn eq(&self, other: &U) -> bool {
+        T::eq(self, other)
+    }
+}
+
+/// Compares the protected value with `U`, this only reads through the guard,
+/// so it never locks anything
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + PartialOrd<U>, U: ?Sized, St> PartialOrd<U>
+    for ShareGuard<'_, L, T, St>
+{
+    fn partial_cmp(&self, other: &U) -> Option<core::cmp::Ordering> {
+        T::partial_cmp(self, other)
+    }
+}
+
+#[cfg(feature = "guard-origin")]
+impl<'a, L: RawShareLock + RawLockInfo, T> ShareGuard<'a, L, T> {
+    /// The [`RwLock`](crate::rwlock::RwLock) this guard was created from
+    ///
+    /// This allows functions that only take a guard to lock the same `RwLock` again, for
+    /// example to upgrade another reader. The origin is kept across `bump_for`, clones,
+    /// downgrades and upgrades, but not by mapping, so this returns `None` if the guard
+    /// wasn't created by a `RwLock` (i.e. with `from_raw_parts` or by a reentrant mutex).
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::rwlock(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    #[inline]
+    pub fn rwlock(g: &Self) -> Option<&'a crate::rwlock::RwLock<L, T>> {
+        // SAFETY: only `RwLock<L, T>` sets the origin of a pure guard over a `T`,
+        // and it is reset whenever the guard is mapped
+        unsafe { g.origin.get() }
+    }
+}
+
+impl<'a, L: RawShareLock + RawLockInfo, C: ?Sized, St> ShareGuard<'a, L, C, St> {
+    /// Create an iterator that yields a `MappedShareGuard` for each element of the locked
+    /// container (i.e. `[T]`, `[T; N]`, `Vec<T>`, ...)
+    ///
+    /// The lock will be held until all of the yielded guards and the iterator are dropped.
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::iter_guarded(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn iter_guarded<T>(g: Self) -> GuardedIter<'a, L, T>
+    where
+        C: AsRef<[T]>,
+    {
+        let (raw, value) = ShareGuard::into_raw_parts(g);
+
+        GuardedIter {
+            raw,
+            iter: unsafe { (*value).as_ref().iter() },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, L, K, V, S, St> ShareGuard<'a, L, std::collections::HashMap<K, V, S>, St>
+where
+    L: RawShareLock + RawLockInfo,
+    K: Eq + core::hash::Hash,
+    S: core::hash::BuildHasher,
+{
+    /// Look up each of the `keys` in the locked map, and make a `MappedShareGuard`
+    /// for each value that was found
+    ///
+    /// This only locks the map once, every returned guard shares that lock, and the lock
+    /// is held until all of them are dropped. The guards are returned in the same order
+    /// as the `keys`, with `None` for keys that aren't in the map.
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::project_keys(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn project_keys<Q>(g: Self, keys: &[Q]) -> std::vec::Vec<Option<MappedShareGuard<'a, L, V>>>
+    where
+        K: core::borrow::Borrow<Q>,
+        Q: Eq + core::hash::Hash,
+    {
+        let (raw, map) = ShareGuard::into_raw_parts(g);
+        let map = unsafe { &*map };
+
+        keys.iter()
+            .map(|key| {
+                let value = map.get(key)?;
+                Some(unsafe { ShareGuard::from_raw_parts(raw.clone(), value) })
+            })
+            .collect()
     }
 }
+
+/// An iterator that yields a `MappedShareGuard` for each element of a locked slice
+///
+/// created by [`ShareGuard::iter_guarded`]
+pub struct GuardedIter<'a, L: RawShareLock + RawLockInfo, T> {
+    raw: RawShareGuard<'a, L>,
+    iter: core::slice::Iter<'a, T>,
+}
+
+impl<'a, L: RawShareLock + RawLockInfo, T> GuardedIter<'a, L, T> {
+    /// The remaining elements of the locked slice
+    pub fn as_slice(&self) -> &[T] {
+        self.iter.as_slice()
+    }
+}
+
+impl<'a, L: RawShareLock + RawLockInfo, T> Iterator for GuardedIter<'a, L, T> {
+    type Item = MappedShareGuard<'a, L, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.iter.next()?;
+        Some(unsafe { ShareGuard::from_raw_parts(self.raw.clone(), value) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T> DoubleEndedIterator for GuardedIter<'_, L, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let value = self.iter.next_back()?;
+        Some(unsafe { ShareGuard::from_raw_parts(self.raw.clone(), value) })
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T> ExactSizeIterator for GuardedIter<'_, L, T> {}
+
+impl<L: RawShareLock + RawLockInfo, T> core::iter::FusedIterator for GuardedIter<'_, L, T> {}
@@ -69,13 +69,22 @@ where
     }
 }
 
+impl<L: super::RawShareLockTimed + RawLockInfo> RawShareGuard<'_, L> {
+    /// Temporarily yields the lock to another thread if there is one,
+    /// but only waits up to `duration` to get the lock back.
+    ///
+    /// Returns false if the lock could not be re-acquired in time, the lock is held either way.
+    /// [read more](super::RawShareLockTimed#method.shr_bump_for)
+    pub fn bump_for(&mut self, duration: L::Duration) -> bool {
+        unsafe { self.lock.shr_bump_for(duration) }
+    }
+}
+
 impl<'a, L: RawShareLock + RawLockInfo> RawShareGuard<'a, L> {
     /// Temporarily yields the lock to another thread if there is one.
     /// [read more](RawShareLock#method.shr_bump)
-    pub fn bump(&mut self) {
-        unsafe {
-            self.lock.shr_bump();
-        }
+    pub fn bump(&mut self) -> crate::BumpResult {
+        unsafe { self.lock.shr_bump() }
     }
 
     /// Temporarily unlocks the lock to execute the given function.
@@ -89,6 +98,12 @@ impl<'a, L: RawShareLock + RawLockInfo> RawShareGuard<'a, L> {
         f()
     }
 
+    /// The kind of access this guard has, which is always [`GuardKind::Shared`](crate::rwlock::transition::GuardKind::Shared)
+    #[inline]
+    pub fn kind(&self) -> crate::rwlock::transition::GuardKind {
+        crate::rwlock::transition::GuardKind::Shared
+    }
+
     /// The inner lock
     pub fn inner(&self) -> &L {
         self.lock
@@ -112,10 +127,8 @@ impl<L: RawShareLockFair + RawLockInfo> RawShareGuard<'_, L> {
 
     /// Temporarily yields the lock to a waiting thread if there is one.
     /// [read more](RawShareLockFair#method.shr_bump_fair)
-    pub fn bump_fair(&mut self) {
-        unsafe {
-            self.lock.shr_bump_fair();
-        }
+    pub fn bump_fair(&mut self) -> crate::BumpResult {
+        unsafe { self.lock.shr_bump_fair() }
     }
 
     /// Temporarily unlocks the lock to execute the given function.
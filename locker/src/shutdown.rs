@@ -0,0 +1,142 @@
+//! A one-time signal that many threads or tasks can wait on
+//!
+//! [`ShutdownSignal`] is triggered at most once, and the triggering side can attach a payload
+//! (like an exit code, or the reason for shutting down). Every waiter sees the same payload,
+//! whether it waits by blocking the current thread, or by awaiting [`ShutdownSignal::wait_async`].
+//!
+//! It is a [`OnceCell`](crate::once::OnceCell) under the hood, and waiters are
+//! [subscriptions](crate::once::Subscription) to that cell, so waiting never triggers the signal.
+//!
+//! ```
+//! use locker::shutdown::ShutdownSignal;
+//!
+//! static SHUTDOWN: ShutdownSignal<i32> = locker::Init::INIT;
+//!
+//! std::thread::scope(|s| {
+//!     let worker = s.spawn(|| *SHUTDOWN.wait());
+//!
+//!     assert!(SHUTDOWN.trigger(0));
+//!     assert!(!SHUTDOWN.trigger(1));
+//!
+//!     assert_eq!(worker.join().unwrap(), 0);
+//! });
+//! ```
+
+use crate::once::{default::RawLock, Finish, OnceCell, Subscription};
+use std::time::{Duration, Instant};
+
+/// A one-time signal with a payload, see the [module docs](self) for details
+pub struct ShutdownSignal<T = (), L: Finish = RawLock> {
+    cell: OnceCell<L, T>,
+}
+
+crate::impl_init! {
+    [T, L: Finish + crate::Init] ShutdownSignal<T, L> = Self { cell: crate::Init::INIT }
+}
+
+impl<T, L: Finish + crate::Init> ShutdownSignal<T, L> {
+    /// Create a new signal which hasn't been triggered yet
+    #[inline]
+    pub fn new() -> Self {
+        crate::Init::INIT
+    }
+}
+
+impl<T, L: Finish> ShutdownSignal<T, L> {
+    /// Trigger the signal with the given payload, and wake up everyone waiting on it
+    ///
+    /// Returns true if this call triggered the signal. If the signal was already
+    /// triggered, then the `payload` is dropped, and waiters keep seeing the first payload.
+    pub fn trigger(&self, payload: T) -> bool {
+        let mut payload = Some(payload);
+        self.cell.get_or_init(|| payload.take().unwrap());
+        payload.is_none()
+    }
+
+    /// Check if the signal was triggered, without blocking
+    #[inline]
+    pub fn is_triggered(&self) -> bool {
+        self.cell.get().is_some()
+    }
+
+    /// The payload the signal was triggered with, or `None` if it wasn't triggered yet
+    #[inline]
+    pub fn payload(&self) -> Option<&T> {
+        self.cell.get()
+    }
+
+    /// Block the current thread until the signal is triggered
+    pub fn wait(&self) -> &T {
+        self.cell.subscribe().wait()
+    }
+
+    /// Block the current thread until the signal is triggered, or the timeout expires
+    ///
+    /// Returns `None` if the timeout expired before the signal was triggered
+    pub fn wait_for(&self, duration: Duration) -> Option<&T> {
+        self.cell.subscribe().wait_for(duration)
+    }
+
+    /// Block the current thread until the signal is triggered, or the deadline is reached
+    ///
+    /// Returns `None` if the deadline passed before the signal was triggered
+    pub fn wait_until(&self, deadline: Instant) -> Option<&T> {
+        self.cell.subscribe().wait_until(Some(deadline))
+    }
+
+    /// A future that resolves to the payload once the signal is triggered
+    ///
+    /// Dropping the future before it resolves stops waiting, without affecting
+    /// other waiters.
+    #[inline]
+    pub fn wait_async(&self) -> Subscription<'_, L, T> {
+        self.cell.subscribe()
+    }
+}
+
+#[test]
+fn wait_async() {
+    use crate::test_waker::Count;
+    use core::future::Future;
+    use core::task::{Context, Poll, Waker};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let signal = ShutdownSignal::<&str>::new();
+    assert_eq!(signal.wait_for(Duration::from_millis(10)), None);
+
+    let count = Arc::new(Count(AtomicUsize::new(0)));
+    let waker = Waker::from(count.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let mut futures = [signal.wait_async(), signal.wait_async()];
+    for future in &mut futures {
+        assert!(core::pin::Pin::new(future).poll(&mut cx).is_pending());
+    }
+
+    // a dropped future stops waiting, so it isn't woken, and the others still are
+    let mut cancelled = signal.wait_async();
+    assert!(core::pin::Pin::new(&mut cancelled).poll(&mut cx).is_pending());
+    drop(cancelled);
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| assert_eq!(*signal.wait(), "done"));
+        s.spawn(|_| {
+            std::thread::sleep(Duration::from_millis(10));
+            assert!(signal.trigger("done"));
+        });
+    })
+    .unwrap();
+
+    assert_eq!(count.0.load(Ordering::SeqCst), 2);
+    for future in &mut futures {
+        assert_eq!(
+            core::pin::Pin::new(future).poll(&mut cx),
+            Poll::Ready(&"done")
+        );
+    }
+
+    assert!(signal.is_triggered());
+    assert!(!signal.trigger("again"));
+    assert_eq!(signal.payload(), Some(&"done"));
+}
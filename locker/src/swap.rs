@@ -0,0 +1,108 @@
+//! A read-mostly atomically swappable `Arc`
+//!
+//! [`LockSwap`] is built on top of the default rwlock, and is meant for
+//! patterns like config reloading, where the value is read very often but
+//! only rarely replaced.
+
+use std::sync::Arc;
+
+use crate::rwlock::default::{DefaultLock, RwLock};
+use crate::share_lock::ShareGuard;
+
+/// A guard to the currently loaded value of a [`LockSwap`]
+pub type Guard<'a, T> = ShareGuard<'a, DefaultLock, Arc<T>>;
+
+/// A read-mostly container for an `Arc<T>` that can be swapped out
+pub struct LockSwap<T: ?Sized> {
+    inner: RwLock<Arc<T>>,
+}
+
+impl<T: Default> Default for LockSwap<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Arc::default())
+    }
+}
+
+impl<T: ?Sized> From<Arc<T>> for LockSwap<T> {
+    #[inline]
+    fn from(value: Arc<T>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: ?Sized> LockSwap<T> {
+    /// Create a new `LockSwap` with the given initial value
+    #[inline]
+    pub fn new(value: Arc<T>) -> Self {
+        Self {
+            inner: RwLock::new(value),
+        }
+    }
+
+    /// Consume the `LockSwap`, returning the current value
+    #[inline]
+    pub fn into_inner(self) -> Arc<T> {
+        self.inner.into_inner()
+    }
+
+    /// Get a shared guard to the current value
+    ///
+    /// While the guard is alive, `store` and `rcu` will block, so
+    /// prefer `load_full` if you need to hold on to the value for a long time.
+    #[inline]
+    pub fn load(&self) -> Guard<'_, T> {
+        self.inner.read()
+    }
+
+    /// Get a clone of the current value
+    #[inline]
+    pub fn load_full(&self) -> Arc<T> {
+        Arc::clone(&self.inner.read())
+    }
+
+    /// Replace the current value, returning the old value
+    #[inline]
+    pub fn swap(&self, value: Arc<T>) -> Arc<T> {
+        core::mem::replace(&mut *self.inner.write(), value)
+    }
+
+    /// Replace the current value
+    ///
+    /// The old value is dropped after the lock is released.
+    #[inline]
+    pub fn store(&self, value: Arc<T>) {
+        drop(self.swap(value));
+    }
+
+    /// Read-copy-update the current value
+    ///
+    /// The update function is given the current value, and the value
+    /// it returns will replace the current value. The write lock is held
+    /// while `update` runs, so `load` and `load_full` block until it returns,
+    /// and `rcu` waits for all outstanding `load` guards to be dropped first.
+    /// Only values cloned out with `load_full` stay usable throughout.
+    /// Returns the old value.
+    pub fn rcu<F>(&self, mut update: F) -> Arc<T>
+    where
+        T: Sized,
+        F: FnMut(&T) -> T,
+    {
+        let mut guard = self.inner.write();
+        let new = Arc::new(update(&guard));
+        core::mem::replace(&mut *guard, new)
+    }
+}
+
+#[test]
+fn lock_swap() {
+    let swap = LockSwap::new(Arc::new(0));
+
+    assert_eq!(**swap.load(), 0);
+    swap.store(Arc::new(1));
+    assert_eq!(*swap.load_full(), 1);
+
+    let old = swap.rcu(|x| x + 1);
+    assert_eq!(*old, 1);
+    assert_eq!(**swap.load(), 2);
+}
@@ -0,0 +1,62 @@
+//! `tracing` instrumentation of the slow paths of locks
+//!
+//! With the `tracing` feature flag, a `lock_wait` span is entered for as long as a thread
+//! waits on a lock or a condvar, which carries the kind of wait and the address of the lock.
+//! When the wait ends, an event with the time spent waiting is emitted inside of that span.
+//! Unlocks that wake up parked threads emit an `unlock_slow` event with the fairness decision.
+//!
+//! Without the feature flag, all of this compiles to nothing.
+
+/// Traces a wait from its creation until it is dropped
+#[must_use]
+pub(crate) struct SlowPath {
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+    #[cfg(feature = "tracing")]
+    start: std::time::Instant,
+}
+
+impl SlowPath {
+    /// Start tracing a wait of the given kind, like `exc_lock`, on `lock`
+    #[inline]
+    pub(crate) fn enter<T: ?Sized>(kind: &'static str, lock: &T) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            let lock = lock as *const T as *const () as usize;
+
+            Self {
+                span: tracing::trace_span!("lock_wait", kind, lock).entered(),
+                start: std::time::Instant::now(),
+            }
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = (kind, lock);
+            Self {}
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for SlowPath {
+    fn drop(&mut self) {
+        let waited_ns = self.start.elapsed().as_nanos() as u64;
+        tracing::trace!(parent: &self.span, waited_ns, "lock wait ended");
+    }
+}
+
+/// Trace an unlock that woke up `unparked` threads, and whether the lock was handed
+/// over to one of them directly (`fair`)
+#[inline]
+#[cfg(all(feature = "extra", any(feature = "std", feature = "parking_lot_core")))]
+pub(crate) fn unlock_slow<T: ?Sized>(lock: &T, unparked: usize, fair: bool) {
+    #[cfg(feature = "tracing")]
+    {
+        let lock = lock as *const T as *const () as usize;
+        tracing::trace!(lock, unparked, fair, "unlock_slow");
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    let _ = (lock, unparked, fair);
+}
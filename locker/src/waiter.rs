@@ -1,9 +1,16 @@
-use parking_lot_core::{self, SpinWait, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+use crate::parking::{DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+use crate::spin_wait::SpinWait;
 
 use std::mem::MaybeUninit;
 
 use std::time::{Duration, Instant};
 
+pub mod key;
+pub mod strategy;
+
+pub use key::ParkKey;
+pub use strategy::{BusyPoll, ParkImmediately, Spin, SpinThenPark, WaitStrategy};
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Waiter<T: ?Sized = MaybeUninit<u8>> {
     _private: (),
@@ -66,7 +73,7 @@ unsafe fn wait(key: usize, timeout: Option<Instant>) -> bool {
     let before_sleep = || {};
     let timed_out = |_key, _was_last| {};
 
-    parking_lot_core::park(
+    crate::parking::park(
         key,
         validate,
         before_sleep,
@@ -129,17 +136,47 @@ impl<T: ?Sized> Waiter<T> {
         value.ok_or(Timeout)
     }
 
+    /// Park until notified, or until `timeout` is reached, but only if `should_park` returns true
+    ///
+    /// Unlike [`wait_while`](Self::wait_while), `should_park` is checked while the parking queue
+    /// is locked, so a notification that races with the check can't be lost.
+    ///
+    /// Returns false if the timeout was reached
+    #[inline]
+    pub fn park_if<F: FnMut(&T) -> bool>(
+        &self,
+        timeout: Option<Instant>,
+        mut should_park: F,
+    ) -> bool {
+        let validate = || should_park(&self.inner);
+        let before_sleep = || {};
+        let timed_out = |_key, _was_last| {};
+
+        let result = unsafe {
+            crate::parking::park(
+                self.key(),
+                validate,
+                before_sleep,
+                timed_out,
+                DEFAULT_PARK_TOKEN,
+                timeout,
+            )
+        };
+
+        result != crate::parking::ParkResult::TimedOut
+    }
+
     #[inline]
     pub fn notify_one(&self) -> bool {
         let key = self.key();
         let callback = |_result| DEFAULT_UNPARK_TOKEN;
 
-        unsafe { parking_lot_core::unpark_one(key, callback).unparked_threads > 0 }
+        unsafe { crate::parking::unpark_one(key, callback).unparked_threads > 0 }
     }
 
     #[inline]
     pub fn notify_all(&self) -> usize {
-        unsafe { parking_lot_core::unpark_all(self.key(), DEFAULT_UNPARK_TOKEN) }
+        unsafe { crate::parking::unpark_all(self.key(), DEFAULT_UNPARK_TOKEN) }
     }
 
     #[inline(always)]
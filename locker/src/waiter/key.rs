@@ -0,0 +1,169 @@
+//! The keys that threads park on
+//!
+//! `parking_lot_core` queues parked threads by a key, which the adaptive locks derive
+//! from their own address. So a lock must not move while threads are parked on it,
+//! otherwise an unlock from the new address won't find them. Safe code can't move a lock
+//! that is in use, but a lock that is moved between uses (e.g. inside of a `Box` that is
+//! reallocated) still ends up using different keys over its lifetime.
+//!
+//! With the `stable-keys` feature flag, [`ParkKey`] instead allocates a small token on the
+//! heap the first time a thread parks, and uses the address of the token as the key, so the
+//! key doesn't change when the lock moves. In debug builds it also checks that the lock wasn't
+//! moved while threads were parked on it.
+
+#[cfg(feature = "stable-keys")]
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// The key that threads park on while waiting for a lock
+///
+/// Without the `stable-keys` feature flag this is zero-sized, and the key is the address
+/// of the lock. Each key covers two addresses, `key` and `key + 1`, so that locks can
+/// keep separate queues.
+pub struct ParkKey {
+    // points to a `u16`, so that `key + 1` is also owned by this lock
+    #[cfg(feature = "stable-keys")]
+    token: AtomicPtr<u16>,
+    // the address of the lock the last time a thread parked on it
+    #[cfg(all(feature = "stable-keys", debug_assertions))]
+    parked_at: core::sync::atomic::AtomicUsize,
+}
+
+impl Default for ParkKey {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParkKey {
+    /// Create a new key
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(feature = "stable-keys")]
+            token: AtomicPtr::new(core::ptr::null_mut()),
+            #[cfg(all(feature = "stable-keys", debug_assertions))]
+            parked_at: core::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    /// The key of `lock`, which parks and unparks threads that are waiting for it
+    ///
+    /// `self` must be stored inside of `lock`
+    #[inline]
+    pub fn key<T: ?Sized>(&self, lock: &T) -> usize {
+        self.get(lock)
+    }
+
+    /// Record where `lock` is while a thread parks on it
+    ///
+    /// This must be called from the `validate` callback of `crate::parking::park`, so that
+    /// it is synchronized with [`check_unparked`](Self::check_unparked) by the parking queue
+    #[inline]
+    #[cfg_attr(
+        not(all(feature = "stable-keys", debug_assertions)),
+        allow(unused_variables)
+    )]
+    pub fn record_parked<T: ?Sized>(&self, lock: &T) {
+        #[cfg(all(feature = "stable-keys", debug_assertions))]
+        self.parked_at.store(addr(lock), Ordering::Relaxed);
+    }
+
+    /// Check that `lock` didn't move while the `unparked` threads were parked on it
+    ///
+    /// This must be called after `crate::parking::unpark_*` returns, not in its callback
+    ///
+    /// # Panic
+    ///
+    /// In debug builds with the `stable-keys` feature flag, this panics if `lock`
+    /// was moved while threads were parked on it
+    #[inline]
+    #[cfg_attr(
+        not(all(feature = "stable-keys", debug_assertions)),
+        allow(unused_variables)
+    )]
+    pub fn check_unparked<T: ?Sized>(&self, lock: &T, unparked: usize) {
+        #[cfg(all(feature = "stable-keys", debug_assertions))]
+        assert!(
+            unparked == 0 || self.parked_at.load(Ordering::Relaxed) == addr(lock),
+            "a lock was moved while threads were parked on it"
+        );
+    }
+
+    #[cfg(not(feature = "stable-keys"))]
+    #[inline]
+    fn get<T: ?Sized>(&self, lock: &T) -> usize {
+        addr(lock)
+    }
+
+    #[cfg(feature = "stable-keys")]
+    #[inline]
+    fn get<T: ?Sized>(&self, _: &T) -> usize {
+        let token = self.token.load(Ordering::Acquire);
+
+        if token.is_null() {
+            self.alloc_token()
+        } else {
+            token as usize
+        }
+    }
+
+    #[cfg(feature = "stable-keys")]
+    #[cold]
+    fn alloc_token(&self) -> usize {
+        let token = std::boxed::Box::into_raw(std::boxed::Box::new(0_u16));
+
+        match self.token.compare_exchange(
+            core::ptr::null_mut(),
+            token,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => token as usize,
+            Err(current) => {
+                // another thread allocated the token first
+                drop(unsafe { std::boxed::Box::from_raw(token) });
+                current as usize
+            }
+        }
+    }
+}
+
+#[cfg(feature = "stable-keys")]
+impl Drop for ParkKey {
+    fn drop(&mut self) {
+        let token = *self.token.get_mut();
+
+        if !token.is_null() {
+            drop(unsafe { std::boxed::Box::from_raw(token) });
+        }
+    }
+}
+
+#[inline]
+fn addr<T: ?Sized>(lock: &T) -> usize {
+    lock as *const T as *const () as usize
+}
+
+#[test]
+fn park_key() {
+    let lock = ParkKey::new();
+    let key = lock.key(&lock);
+    lock.record_parked(&lock);
+    lock.check_unparked(&lock, 1);
+    assert_eq!(lock.key(&lock), key);
+
+    #[cfg(feature = "stable-keys")]
+    {
+        // the key stays the same after the lock moves
+        let moved = std::boxed::Box::new(lock);
+        assert_eq!(moved.key(&*moved), key);
+        moved.check_unparked(&*moved, 0);
+
+        #[cfg(debug_assertions)]
+        {
+            let result = std::panic::catch_unwind(|| moved.check_unparked(&*moved, 1));
+            assert!(result.is_err());
+        }
+    }
+}
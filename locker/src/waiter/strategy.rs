@@ -0,0 +1,156 @@
+//! Strategies for waiting on a contended lock
+//!
+//! The adaptive locks take a [`WaitStrategy`] as a type parameter, which decides
+//! how long a thread waits on a contended lock before it is parked. The default,
+//! [`SpinThenPark`], spins for a short while and then parks, which is a good trade-off
+//! between latency and CPU usage for most workloads.
+
+use crate::spin_wait::SpinWait;
+use std::time::{Duration, Instant};
+
+/// Decides how a thread waits on a contended lock before parking
+pub trait WaitStrategy {
+    /// The state of a single wait on a lock
+    type Spinner;
+
+    /// Start waiting on a lock, `timeout` is the deadline of the lock operation if there is one
+    fn start(timeout: Option<Instant>) -> Self::Spinner;
+
+    /// Wait a bit before retrying the lock
+    ///
+    /// Returns false if the thread should be parked instead
+    fn spin(spinner: &mut Self::Spinner) -> bool;
+
+    /// Called after the thread was unparked, before it retries the lock
+    fn reset(spinner: &mut Self::Spinner);
+}
+
+/// Spin a few times, yielding to the OS between later spins, and then park the thread
+///
+/// This is the default strategy of the adaptive locks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SpinThenPark;
+
+impl WaitStrategy for SpinThenPark {
+    type Spinner = SpinWait;
+
+    #[inline]
+    fn start(_: Option<Instant>) -> Self::Spinner {
+        SpinWait::new()
+    }
+
+    #[inline]
+    fn spin(spinner: &mut Self::Spinner) -> bool {
+        spinner.spin()
+    }
+
+    #[inline]
+    fn reset(spinner: &mut Self::Spinner) {
+        spinner.reset()
+    }
+}
+
+/// Never spin, park the thread as soon as the lock is contended
+///
+/// This minimizes the CPU time spent waiting, at the cost of latency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ParkImmediately;
+
+impl WaitStrategy for ParkImmediately {
+    type Spinner = ();
+
+    #[inline]
+    fn start(_: Option<Instant>) -> Self::Spinner {}
+
+    #[inline]
+    fn spin(_: &mut Self::Spinner) -> bool {
+        false
+    }
+
+    #[inline]
+    fn reset(_: &mut Self::Spinner) {}
+}
+
+/// Spin until the lock is acquired or the timeout is reached, instead of parking the thread
+///
+/// The locks only spin while there is no queue of parked threads, so a thread still parks
+/// if others are already parked on the lock. With this strategy that only happens after a
+/// timed lock attempt runs out of time: the thread marks the lock as having parked threads
+/// on its way to time out, and threads that see the mark before it is cleared park too.
+///
+/// This has the lowest latency, but burns CPU time for as long as the lock is contended,
+/// so it should only be used if critical sections are very short and there are fewer
+/// threads than cores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Spin;
+
+impl WaitStrategy for Spin {
+    type Spinner = Option<Instant>;
+
+    #[inline]
+    fn start(timeout: Option<Instant>) -> Self::Spinner {
+        timeout
+    }
+
+    #[inline]
+    fn spin(timeout: &mut Self::Spinner) -> bool {
+        core::hint::spin_loop();
+        timeout.is_none_or(|timeout| Instant::now() < timeout)
+    }
+
+    #[inline]
+    fn reset(_: &mut Self::Spinner) {}
+}
+
+/// Busy poll the lock for up to `MICROS` microseconds, then park the thread
+///
+/// Unlike [`SpinThenPark`], the thread never yields to the OS while polling,
+/// so a latency-critical thread will see the lock as soon as it is released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BusyPoll<const MICROS: u64>;
+
+/// The state of a [`BusyPoll`] wait
+#[derive(Debug, Clone, Copy)]
+pub struct BusyPollSpinner {
+    deadline: Option<Instant>,
+    timeout: Option<Instant>,
+}
+
+impl<const MICROS: u64> BusyPoll<MICROS> {
+    #[inline]
+    fn deadline(timeout: Option<Instant>) -> Option<Instant> {
+        // if `MICROS` is too large to represent, poll until the timeout
+        match (
+            Instant::now().checked_add(Duration::from_micros(MICROS)),
+            timeout,
+        ) {
+            (Some(deadline), Some(timeout)) => Some(deadline.min(timeout)),
+            (deadline, timeout) => deadline.or(timeout),
+        }
+    }
+}
+
+impl<const MICROS: u64> WaitStrategy for BusyPoll<MICROS> {
+    type Spinner = BusyPollSpinner;
+
+    #[inline]
+    fn start(timeout: Option<Instant>) -> Self::Spinner {
+        BusyPollSpinner {
+            deadline: Self::deadline(timeout),
+            timeout,
+        }
+    }
+
+    #[inline]
+    fn spin(spinner: &mut Self::Spinner) -> bool {
+        core::hint::spin_loop();
+        spinner
+            .deadline
+            .is_none_or(|deadline| Instant::now() < deadline)
+    }
+
+    #[inline]
+    fn reset(spinner: &mut Self::Spinner) {
+        spinner.deadline = Self::deadline(spinner.timeout);
+    }
+}
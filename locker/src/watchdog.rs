@@ -0,0 +1,447 @@
+//! A watchdog that reports locks that are held for too long
+//!
+//! This is enabled by the `watchdog` feature flag. Locks wrapped in [`Watched`]
+//! record when and where they were acquired, and once the watchdog is [started](start)
+//! a background thread periodically checks for locks that have been held longer
+//! than the configured threshold, and reports them to a callback.
+//!
+//! Until the watchdog is started, `Watched` locks only pay for a single atomic load.
+
+use core::panic::Location;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::collections::BTreeMap;
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+use crate::exclusive_lock::{
+    RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair, RawExclusiveLockTimed,
+};
+use crate::mutex::spin::{Mutex, SpinLock};
+use crate::mutex::RawMutex;
+use crate::rwlock::RawRwLock;
+use crate::share_lock::{RawShareLock, RawShareLockFair, RawShareLockTimed};
+use crate::{Init, RawLockInfo, RawTimedLock};
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static REGISTRY: Mutex<BTreeMap<usize, Held>> = SpinLock::mutex(BTreeMap::new());
+
+/// The kind of access a reported lock was held with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// the lock was held by a *exc lock*
+    Exclusive,
+    /// the lock was held by one or more *shr lock*s
+    Shared,
+}
+
+/// A lock that has been held for longer than the threshold
+#[derive(Debug, Clone, Copy)]
+pub struct Report {
+    /// The address of the lock
+    pub addr: usize,
+    /// The [name](RawLockInfo::name) of the lock, if it has one
+    pub name: Option<&'static str>,
+    /// The kind of access the lock is held with
+    pub access: Access,
+    /// How long the lock has been held for
+    pub held_for: Duration,
+    /// The location where the lock was acquired
+    ///
+    /// For shared access, this is where the first *shr lock* was acquired
+    pub location: &'static Location<'static>,
+    /// The thread that acquired the lock
+    pub thread: ThreadId,
+}
+
+/// The configuration for the watchdog
+pub struct Config {
+    threshold: Duration,
+    interval: Duration,
+    callback: Box<dyn Fn(&Report) + Send + Sync>,
+}
+
+impl Config {
+    /// Report locks that have been held for longer than `threshold`
+    ///
+    /// By default, locks are checked every `threshold / 2`, and reports are
+    /// logged to stderr.
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            interval: threshold / 2,
+            callback: Box::new(|report| {
+                let name = report.name.unwrap_or("<unnamed>");
+                eprintln!(
+                    "lock `{}` at {:#x} has been held ({:?}) for {:?} by {:?}, acquired at {}",
+                    name,
+                    report.addr,
+                    report.access,
+                    report.held_for,
+                    report.thread,
+                    report.location
+                )
+            }),
+        }
+    }
+
+    /// How often the watchdog checks for long held locks
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Called once for every acquisition that is held longer than the threshold
+    pub fn callback(mut self, callback: impl Fn(&Report) + Send + Sync + 'static) -> Self {
+        self.callback = Box::new(callback);
+        self
+    }
+}
+
+/// Start the watchdog thread
+///
+/// Only locks acquired after the watchdog is started will be reported.
+/// Returns false if the watchdog was already started, in which case `config` is ignored.
+pub fn start(config: Config) -> bool {
+    if STARTED.swap(true, Ordering::AcqRel) {
+        return false;
+    }
+
+    std::thread::Builder::new()
+        .name("locker-watchdog".into())
+        .spawn(move || loop {
+            std::thread::sleep(config.interval);
+
+            let now = Instant::now();
+            let reports = REGISTRY
+                .lock()
+                .iter_mut()
+                .filter(|(_, held)| !held.reported && now - held.since >= config.threshold)
+                .map(|(&addr, held)| {
+                    held.reported = true;
+
+                    Report {
+                        addr,
+                        name: held.name,
+                        access: held.access,
+                        held_for: now - held.since,
+                        location: held.location,
+                        thread: held.thread,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            // the callback is run without holding the registry, so
+            // that it can use watched locks
+            reports.iter().for_each(&config.callback);
+        })
+        .expect("could not spawn the watchdog thread");
+
+    true
+}
+
+struct Held {
+    access: Access,
+    name: Option<&'static str>,
+    since: Instant,
+    location: &'static Location<'static>,
+    thread: ThreadId,
+    shared: usize,
+    reported: bool,
+}
+
+impl Held {
+    #[track_caller]
+    fn new(access: Access, name: Option<&'static str>) -> Self {
+        Self {
+            access,
+            name,
+            since: Instant::now(),
+            location: Location::caller(),
+            thread: std::thread::current().id(),
+            shared: 0,
+            reported: false,
+        }
+    }
+}
+
+#[inline]
+fn is_started() -> bool {
+    STARTED.load(Ordering::Relaxed)
+}
+
+#[cold]
+#[track_caller]
+fn acquire_exc(addr: usize, name: Option<&'static str>) {
+    REGISTRY
+        .lock()
+        .insert(addr, Held::new(Access::Exclusive, name));
+}
+
+#[cold]
+#[track_caller]
+fn acquire_shr(addr: usize, name: Option<&'static str>) {
+    REGISTRY
+        .lock()
+        .entry(addr)
+        .or_insert_with(|| Held::new(Access::Shared, name))
+        .shared += 1;
+}
+
+#[cold]
+fn release_exc(addr: usize) {
+    REGISTRY.lock().remove(&addr);
+}
+
+#[cold]
+fn release_shr(addr: usize) {
+    let mut registry = REGISTRY.lock();
+
+    if let Some(held) = registry.get_mut(&addr) {
+        held.shared = held.shared.saturating_sub(1);
+
+        if held.shared == 0 {
+            registry.remove(&addr);
+        }
+    }
+}
+
+#[cold]
+fn downgrade(addr: usize) {
+    if let Some(held) = REGISTRY.lock().get_mut(&addr) {
+        held.access = Access::Shared;
+        held.shared = 1;
+    }
+}
+
+/// Wraps a lock and records when and where it was acquired, so
+/// that the [watchdog](self) can report it if it is held for too long
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Watched<L: ?Sized>(pub L);
+
+impl<L: ?Sized + RawLockInfo> Watched<L> {
+    #[inline]
+    fn addr(&self) -> usize {
+        self as *const Self as *const () as usize
+    }
+
+    #[inline]
+    #[track_caller]
+    fn acquired_exc(&self, locked: bool) -> bool {
+        if locked && is_started() {
+            acquire_exc(self.addr(), self.0.name());
+        }
+
+        locked
+    }
+
+    #[inline]
+    #[track_caller]
+    fn acquired_shr(&self, locked: bool) -> bool {
+        if locked && is_started() {
+            acquire_shr(self.addr(), self.0.name());
+        }
+
+        locked
+    }
+
+    #[inline]
+    fn released_exc(&self) {
+        if is_started() {
+            release_exc(self.addr());
+        }
+    }
+
+    #[inline]
+    fn released_shr(&self) {
+        if is_started() {
+            release_shr(self.addr());
+        }
+    }
+}
+
+unsafe impl<L: RawMutex> RawMutex for Watched<L> {}
+unsafe impl<L: RawRwLock> RawRwLock for Watched<L> {}
+
+impl<L: Init> Init for Watched<L> {
+    const INIT: Self = Self(Init::INIT);
+}
+
+unsafe impl<L: RawLockInfo + ?Sized> RawLockInfo for Watched<L> {
+    type ExclusiveGuardTraits = <L as RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <L as RawLockInfo>::ShareGuardTraits;
+
+    #[inline]
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
+}
+
+impl<L: RawTimedLock + ?Sized> RawTimedLock for Watched<L> {
+    type Instant = L::Instant;
+    type Duration = L::Duration;
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLock + RawLockInfo> RawExclusiveLock for Watched<L> {
+    #[track_caller]
+    fn exc_lock(&self) {
+        self.0.exc_lock();
+        self.acquired_exc(true);
+    }
+
+    #[track_caller]
+    fn exc_try_lock(&self) -> bool {
+        self.acquired_exc(self.0.exc_try_lock())
+    }
+
+    unsafe fn exc_unlock(&self) {
+        self.released_exc();
+        self.0.exc_unlock()
+    }
+
+    unsafe fn exc_bump(&self) -> crate::BumpResult {
+        self.0.exc_bump()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockFair + RawLockInfo> RawExclusiveLockFair for Watched<L> {
+    unsafe fn exc_unlock_fair(&self) {
+        self.released_exc();
+        self.0.exc_unlock_fair()
+    }
+
+    unsafe fn exc_bump_fair(&self) -> crate::BumpResult {
+        self.0.exc_bump_fair()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockTimed> RawExclusiveLockTimed for Watched<L> {
+    #[track_caller]
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.acquired_exc(self.0.exc_try_lock_until(instant))
+    }
+
+    #[track_caller]
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.acquired_exc(self.0.exc_try_lock_for(duration))
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockDowngrade + RawLockInfo> RawExclusiveLockDowngrade
+    for Watched<L>
+{
+    unsafe fn downgrade(&self) {
+        if is_started() {
+            downgrade(self.addr());
+        }
+
+        self.0.downgrade()
+    }
+
+    unsafe fn try_downgrade(&self) -> bool {
+        let downgraded = self.0.try_downgrade();
+
+        if downgraded && is_started() {
+            downgrade(self.addr());
+        }
+
+        downgraded
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLock + RawLockInfo> RawShareLock for Watched<L> {
+    #[track_caller]
+    fn shr_lock(&self) {
+        self.0.shr_lock();
+        self.acquired_shr(true);
+    }
+
+    #[track_caller]
+    fn shr_try_lock(&self) -> bool {
+        self.acquired_shr(self.0.shr_try_lock())
+    }
+
+    #[track_caller]
+    unsafe fn shr_split(&self) {
+        self.0.shr_split();
+        self.acquired_shr(true);
+    }
+
+    #[track_caller]
+    unsafe fn shr_try_split(&self) -> bool {
+        self.acquired_shr(self.0.shr_try_split())
+    }
+
+    unsafe fn shr_unlock(&self) {
+        self.released_shr();
+        self.0.shr_unlock()
+    }
+
+    unsafe fn shr_bump(&self) -> crate::BumpResult {
+        self.0.shr_bump()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockFair + RawLockInfo> RawShareLockFair for Watched<L> {
+    unsafe fn shr_unlock_fair(&self) {
+        self.released_shr();
+        self.0.shr_unlock_fair()
+    }
+
+    unsafe fn shr_bump_fair(&self) -> crate::BumpResult {
+        self.0.shr_bump_fair()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockTimed> RawShareLockTimed for Watched<L> {
+    #[track_caller]
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.acquired_shr(self.0.shr_try_lock_until(instant))
+    }
+
+    #[track_caller]
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.acquired_shr(self.0.shr_try_lock_for(duration))
+    }
+}
+
+#[test]
+fn watchdog() {
+    use std::sync::Arc;
+
+    type WatchedMutex<T> = crate::mutex::Mutex<Watched<crate::mutex::default::DefaultLock>, T>;
+
+    let reports = Arc::new(SpinLock::mutex(Vec::new()));
+
+    let config = Config::new(Duration::from_millis(20))
+        .interval(Duration::from_millis(5))
+        .callback({
+            let reports = reports.clone();
+            move |report| reports.lock().push(*report)
+        });
+
+    assert!(start(config));
+    assert!(!start(Config::new(Duration::from_secs(1))));
+
+    let mutex = WatchedMutex::new(0);
+    let addr = mutex.raw().inner() as *const _ as usize;
+
+    let short = mutex.lock();
+    drop(short);
+
+    let line = line!() + 1;
+    let guard = mutex.lock();
+    std::thread::sleep(Duration::from_millis(100));
+    drop(guard);
+
+    let reports = reports.lock();
+    let report = reports.iter().find(|report| report.addr == addr).unwrap();
+    assert_eq!(
+        reports.iter().filter(|report| report.addr == addr).count(),
+        1
+    );
+    assert_eq!(report.access, Access::Exclusive);
+    assert_eq!(report.thread, std::thread::current().id());
+    assert_eq!(report.location.file(), file!());
+    assert_eq!(report.location.line(), line);
+    assert!(report.held_for >= Duration::from_millis(20));
+}
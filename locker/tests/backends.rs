@@ -0,0 +1,334 @@
+//! A scenario matrix that is run against every backend that supports it
+//!
+//! Each scenario is a generic function over the raw lock traits that it needs, and
+//! checks the mutual exclusion invariants of the lock while threads race through the
+//! slow paths: fair handoffs, bumping with waiters, timeouts that race unlocking, and
+//! downgrading with parked readers and writers.
+
+#![cfg(feature = "extra")]
+
+#[cfg(feature = "std")]
+use locker::exclusive_lock::RawExclusiveLockTimed;
+use locker::exclusive_lock::{RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair};
+use locker::share_lock::{RawShareLock, RawShareLockFair};
+use locker::Init;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+const THREADS: usize = 4;
+const ITERS: usize = 200;
+
+/// A lock, and counters that check that it is used correctly
+struct Checked<L> {
+    lock: L,
+    writers: AtomicUsize,
+    readers: AtomicUsize,
+}
+
+impl<L: Init> Checked<L> {
+    fn new() -> Self {
+        Self {
+            lock: L::INIT,
+            writers: AtomicUsize::new(0),
+            readers: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<L> Checked<L> {
+    fn exclusive(&self) {
+        assert_eq!(self.writers.fetch_add(1, Ordering::SeqCst), 0);
+        assert_eq!(self.readers.load(Ordering::SeqCst), 0);
+        std::hint::spin_loop();
+        self.writers.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn shared(&self) {
+        self.readers.fetch_add(1, Ordering::SeqCst);
+        assert_eq!(self.writers.load(Ordering::SeqCst), 0);
+        std::hint::spin_loop();
+        self.readers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<L: RawExclusiveLock> Checked<L> {
+    /// After a scenario, the lock must be unlocked
+    fn assert_unlocked(&self) {
+        assert!(self.lock.exc_try_lock(), "the lock was left locked");
+        unsafe { self.lock.exc_unlock() }
+    }
+}
+
+/// Run `f(checked, thread, iteration)` on every thread, for every iteration
+fn run<L: Sync>(checked: &Checked<L>, f: impl Fn(&Checked<L>, usize, usize) + Sync) {
+    crossbeam_utils::thread::scope(|s| {
+        for thread in 0..THREADS {
+            let f = &f;
+            s.spawn(move |_| {
+                for iteration in 0..ITERS {
+                    f(checked, thread, iteration)
+                }
+            });
+        }
+    })
+    .unwrap();
+}
+
+/// Threads hand the lock off with a mix of fair and normal unlocks
+fn fair_handoff<L: RawExclusiveLockFair + Init + Sync>() {
+    let checked = Checked::<L>::new();
+
+    run(&checked, |checked, thread, _| {
+        checked.lock.exc_lock();
+        checked.exclusive();
+
+        unsafe {
+            if thread % 2 == 0 {
+                checked.lock.exc_unlock_fair()
+            } else {
+                checked.lock.exc_unlock()
+            }
+        }
+    });
+
+    checked.assert_unlocked();
+}
+
+/// Threads bump the lock while other threads are waiting for it
+fn bump_with_waiters<L: RawExclusiveLockFair + Init + Sync>() {
+    let checked = Checked::<L>::new();
+
+    run(&checked, |checked, _, _| {
+        checked.lock.exc_lock();
+        checked.exclusive();
+
+        unsafe {
+            checked.lock.exc_bump();
+            checked.exclusive();
+            checked.lock.exc_bump_fair();
+            checked.exclusive();
+            checked.lock.exc_unlock();
+        }
+    });
+
+    checked.assert_unlocked();
+}
+
+/// Timed lock attempts expire while the lock is being unlocked
+#[cfg(feature = "std")]
+fn timed_expiry_races_unlock<L: RawExclusiveLockTimed<Duration = Duration> + Init + Sync>() {
+    let checked = Checked::<L>::new();
+    let acquired = AtomicUsize::new(0);
+    let timeout = Duration::from_micros(50);
+
+    run(&checked, |checked, thread, _| {
+        if thread == 0 {
+            // hold the lock for about as long as the other threads are willing to wait
+            checked.lock.exc_lock();
+            checked.exclusive();
+            std::thread::sleep(timeout);
+            unsafe { checked.lock.exc_unlock() }
+            return;
+        }
+
+        let start = std::time::Instant::now();
+
+        if checked.lock.exc_try_lock_for(timeout) {
+            checked.exclusive();
+            acquired.fetch_add(1, Ordering::Relaxed);
+            unsafe { checked.lock.exc_unlock() }
+        } else {
+            // an attempt may only give up once its time is up
+            assert!(
+                start.elapsed() >= timeout,
+                "gave up after {:?}",
+                start.elapsed()
+            );
+        }
+    });
+
+    checked.assert_unlocked();
+
+    // the lock is free for most of the time, so waiters that are woken
+    // up by the unlocks must be able to take it
+    assert_ne!(
+        acquired.into_inner(),
+        0,
+        "no timed attempt acquired the lock"
+    );
+}
+
+/// Readers release the lock (fairly or not) while writers are parked on it
+fn shared_with_parked_writers<L: RawShareLockFair + RawExclusiveLockFair + Init + Sync>() {
+    let checked = Checked::<L>::new();
+
+    run(&checked, |checked, thread, iteration| {
+        // each thread takes turns being the writer
+        if (thread + iteration) % THREADS == 0 {
+            checked.lock.exc_lock();
+            checked.exclusive();
+            unsafe { checked.lock.exc_unlock_fair() }
+        } else {
+            checked.lock.shr_lock();
+            checked.shared();
+
+            // hold on to the lock for long enough that a writer has to park
+            if iteration % 16 == 0 {
+                std::thread::sleep(Duration::from_micros(200));
+            }
+
+            unsafe {
+                checked.lock.shr_bump();
+                checked.shared();
+
+                if thread % 2 == 0 {
+                    checked.lock.shr_unlock_fair()
+                } else {
+                    checked.lock.shr_unlock()
+                }
+            }
+        }
+    });
+
+    checked.assert_unlocked();
+}
+
+/// Writers downgrade their lock while readers and writers are waiting
+fn downgrade_with_waiters<L: RawExclusiveLockDowngrade + RawShareLock + Init + Sync>() {
+    let checked = Checked::<L>::new();
+
+    run(&checked, |checked, thread, _| {
+        if thread % 2 == 0 {
+            checked.lock.exc_lock();
+            checked.exclusive();
+
+            unsafe {
+                checked.lock.downgrade();
+                checked.shared();
+                checked.lock.shr_unlock();
+            }
+        } else {
+            checked.lock.shr_lock();
+            checked.shared();
+            unsafe { checked.lock.shr_unlock() }
+        }
+    });
+
+    checked.assert_unlocked();
+}
+
+macro_rules! scenarios {
+    ($($(#[$meta:meta])* $name:ident: $lock:ty => [$($scenario:ident),* $(,)?];)*) => {$(
+        $(#[$meta])*
+        mod $name {
+            $(
+                #[test]
+                fn $scenario() {
+                    super::$scenario::<$lock>()
+                }
+            )*
+        }
+    )*};
+}
+
+// the default backends are only timed and fair with `parking_lot_core` (or `std`
+// for the default mutex), otherwise they are the spin locks that are already covered here
+scenarios! {
+    mutex_spin: locker::mutex::spin::SpinLock => [fair_handoff, bump_with_waiters];
+    mutex_ticket: locker::mutex::ticket::TicketLock => [fair_handoff, bump_with_waiters];
+    mutex_splittable_spin: locker::mutex::splittable_spin::SplitSpinLock => [
+        fair_handoff,
+        bump_with_waiters,
+    ];
+    #[cfg(feature = "std")]
+    mutex_word_lock: locker::mutex::word_lock::WordLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+    ];
+    #[cfg(feature = "std")]
+    mutex_default: locker::mutex::default::DefaultLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+    ];
+    #[cfg(feature = "parking_lot_core")]
+    mutex_tagged_default: locker::mutex::tagged_default::TaggedDefaultLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+    ];
+    #[cfg(feature = "parking_lot_core")]
+    mutex_splittable_default: locker::mutex::splittable_default::SplitDefaultLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+    ];
+    #[cfg(feature = "parking_lot_core")]
+    mutex_adaptive: locker::mutex::adaptive::AdaptiveLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+    ];
+    #[cfg(feature = "parking_lot_core")]
+    mutex_tagged: locker::mutex::tagged::TaggedLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+    ];
+    #[cfg(feature = "parking_lot_core")]
+    mutex_splittable: locker::mutex::splittable::SplitLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+    ];
+
+    rwlock_spin: locker::rwlock::spin::SpinLock => [
+        fair_handoff,
+        bump_with_waiters,
+        shared_with_parked_writers,
+        downgrade_with_waiters,
+    ];
+    rwlock_ticket: locker::rwlock::ticket::TicketLock => [
+        fair_handoff,
+        bump_with_waiters,
+        shared_with_parked_writers,
+    ];
+    rwlock_splittable_spin: locker::rwlock::splittable_spin::SplitSpinLock => [
+        fair_handoff,
+        bump_with_waiters,
+        shared_with_parked_writers,
+    ];
+    #[cfg(feature = "parking_lot_core")]
+    rwlock_default: locker::rwlock::default::DefaultLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+        shared_with_parked_writers,
+        downgrade_with_waiters,
+    ];
+    #[cfg(feature = "parking_lot_core")]
+    rwlock_splittable_default: locker::rwlock::splittable_default::SplitDefaultLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+        shared_with_parked_writers,
+    ];
+    #[cfg(feature = "parking_lot_core")]
+    rwlock_adaptive: locker::rwlock::adaptive::AdaptiveLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+        shared_with_parked_writers,
+        downgrade_with_waiters,
+    ];
+    #[cfg(feature = "parking_lot_core")]
+    rwlock_splittable: locker::rwlock::splittable::SplitLock => [
+        fair_handoff,
+        bump_with_waiters,
+        timed_expiry_races_unlock,
+        shared_with_parked_writers,
+    ];
+    #[cfg(feature = "parking_lot_core")]
+    rwlock_phase_fair: locker::rwlock::phase_fair::PhaseFairLock => [timed_expiry_races_unlock];
+}
@@ -0,0 +1,134 @@
+#![cfg(all(feature = "extra", feature = "std"))]
+
+use locker::mutex::spin::SpinLock;
+use locker::remutex::ceiling::{Ceiling, CeilingMutex, Priority};
+use locker::remutex::lock::ReLock;
+
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+thread_local! {
+    static CURRENT: Cell<u8> = const { Cell::new(0) };
+    static HELD: Cell<usize> = const { Cell::new(0) };
+}
+
+struct TaskPriority;
+
+impl Priority for TaskPriority {
+    type Level = u8;
+
+    fn current(&self) -> u8 {
+        CURRENT.with(Cell::get)
+    }
+
+    fn set(&self, level: u8) {
+        CURRENT.with(|current| current.set(level))
+    }
+
+    fn held(&self) -> usize {
+        HELD.with(Cell::get)
+    }
+
+    fn set_held(&self, held: usize) {
+        HELD.with(|current| current.set(held))
+    }
+}
+
+type Mutex<T> = CeilingMutex<ReLock<SpinLock>, TaskPriority, T>;
+
+#[test]
+fn raises_to_ceiling() {
+    static MUTEX: Mutex<u32> = Ceiling::mutex(TaskPriority, 3, 0);
+
+    TaskPriority.set(1);
+
+    let guard = MUTEX.lock();
+    assert_eq!(TaskPriority.current(), 3);
+
+    let nested = MUTEX.lock();
+    assert_eq!(*guard + *nested, 0);
+    drop(guard);
+    assert_eq!(TaskPriority.current(), 3);
+    drop(nested);
+    assert_eq!(TaskPriority.current(), 1);
+
+    // the ceiling is never lowered
+    TaskPriority.set(3);
+    drop(MUTEX.lock());
+    assert_eq!(TaskPriority.current(), 3);
+}
+
+#[test]
+fn failed_lock_restores_priority() {
+    let mutex: Mutex<u32> = Ceiling::mutex(TaskPriority, 2, 0);
+
+    std::thread::scope(|s| {
+        let _guard = mutex.lock();
+
+        s.spawn(|| {
+            TaskPriority.set(1);
+            assert!(mutex.try_lock().is_none());
+            assert_eq!(TaskPriority.current(), 1);
+        })
+        .join()
+        .unwrap();
+    });
+
+    assert_eq!(TaskPriority.current(), 0);
+}
+
+#[test]
+fn above_ceiling() {
+    let mutex: Mutex<()> = Ceiling::mutex(TaskPriority, 2, ());
+
+    TaskPriority.set(5);
+    assert!(catch_unwind(AssertUnwindSafe(|| mutex.lock())).is_err());
+    assert_eq!(TaskPriority.current(), 5);
+
+    // the lock wasn't acquired
+    TaskPriority.set(0);
+    drop(mutex.lock());
+    assert_eq!(TaskPriority.current(), 0);
+}
+
+#[test]
+fn nested() {
+    let low: Mutex<()> = Ceiling::mutex(TaskPriority, 2, ());
+    let high: Mutex<()> = Ceiling::mutex(TaskPriority, 4, ());
+
+    TaskPriority.set(1);
+
+    // a lower ceiling doesn't lower the priority
+    let high_guard = high.lock();
+    let low_guard = low.lock();
+    assert_eq!(TaskPriority.current(), 4);
+    drop(low_guard);
+    assert_eq!(TaskPriority.current(), 4);
+    drop(high_guard);
+    assert_eq!(TaskPriority.current(), 1);
+
+    let low_guard = low.lock();
+    let high_guard = high.lock();
+    assert_eq!(TaskPriority.current(), 4);
+    drop(high_guard);
+    assert_eq!(TaskPriority.current(), 2);
+    drop(low_guard);
+    assert_eq!((TaskPriority.current(), TaskPriority.held()), (1, 0));
+}
+
+#[test]
+fn out_of_order() {
+    let low: Mutex<()> = Ceiling::mutex(TaskPriority, 2, ());
+    let high: Mutex<()> = Ceiling::mutex(TaskPriority, 4, ());
+
+    TaskPriority.set(1);
+
+    let low_guard = low.lock();
+    let high_guard = high.lock();
+
+    assert!(catch_unwind(AssertUnwindSafe(|| drop(low_guard))).is_err());
+    // the priority wasn't lowered while the high lock is still held
+    assert_eq!(TaskPriority.current(), 4);
+    drop(high_guard);
+    assert_eq!(TaskPriority.current(), 2);
+}
@@ -0,0 +1,48 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn convert_rwlock() {
+    use locker::rwlock::{adaptive::AdaptiveLock, phase_fair::PhaseFairLock, spin::SpinLock};
+
+    let rwlock = SpinLock::rwlock(10);
+    let rwlock = rwlock.convert::<AdaptiveLock>();
+    *rwlock.write() += 1;
+
+    let rwlock = rwlock.convert::<PhaseFairLock>();
+    let read = rwlock.read();
+    assert!(rwlock.try_write().is_none());
+    assert_eq!(*read, 11);
+    drop(read);
+
+    let rwlock = rwlock.convert::<SpinLock>();
+    assert_eq!(*rwlock.try_write().unwrap(), 11);
+}
+
+#[test]
+#[cfg(feature = "extra")]
+fn convert_init() {
+    use locker::rwlock::{local::LocalLock, ticket::TicketLock};
+
+    let rwlock = TicketLock::rwlock(vec![1]);
+    let rwlock = rwlock.convert_init::<LocalLock>();
+    rwlock.write().push(2);
+    assert_eq!(*rwlock.read(), [1, 2]);
+}
+
+#[test]
+#[cfg(feature = "extra")]
+fn map_lock() {
+    use locker::rwlock::{default::DefaultLock, raw::RwLock, spin::SpinLock};
+    use locker::Init;
+
+    let raw = RwLock::<SpinLock>::INIT;
+    let raw: RwLock<DefaultLock> = unsafe { raw.map_lock(|_| DefaultLock::INIT) };
+
+    let guard = raw.write();
+    assert!(raw.try_read().is_none());
+    drop(guard);
+    assert!(raw.try_read().is_some());
+
+    let raw = locker::mutex::raw::Mutex::<SpinLock>::INIT.convert::<DefaultLock>();
+    let _guard = raw.lock();
+    assert!(raw.try_lock().is_none());
+}
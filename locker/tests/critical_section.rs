@@ -0,0 +1,49 @@
+#![cfg(all(feature = "critical-section", feature = "std"))]
+
+use locker::mutex::critical_section::{CriticalSectionLock, Mutex, SpinCriticalSection};
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+critical_section::set_impl!(SpinCriticalSection);
+
+#[test]
+fn nested_critical_sections() {
+    let outer = CriticalSectionLock::mutex(0);
+    let inner = CriticalSectionLock::mutex(0);
+
+    critical_section::with(|_| {
+        let mut outer = outer.lock();
+        let mut inner = inner.lock();
+        *outer += 1;
+        *inner += 1;
+        drop(inner);
+        drop(outer);
+    });
+
+    let guard = outer.lock();
+    assert!(outer.try_lock().is_none());
+
+    let payload = catch_unwind(AssertUnwindSafe(|| outer.lock())).unwrap_err();
+    let message = payload.downcast_ref::<&str>().unwrap();
+    assert!(message.contains("would deadlock"), "{}", message);
+
+    assert_eq!(*guard, 1);
+    assert_eq!(*inner.lock(), 1);
+}
+
+#[test]
+fn threads() {
+    static COUNTER: Mutex<u32> = CriticalSectionLock::mutex(0);
+
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..1000 {
+                    *COUNTER.lock() += 1;
+                }
+            });
+        }
+    });
+
+    assert_eq!(*COUNTER.lock(), 4000);
+}
@@ -54,3 +54,102 @@ pub fn condvar() {
     }
     println!("done");
 }
+
+#[test]
+pub fn condvar_cell() {
+    use locker::condvar::CondvarCell;
+    use std::time::Duration;
+
+    let cell = CondvarCell::<DefaultLock, u32>::new(0);
+
+    let (guard, result) = cell.wait_until_for(Duration::from_millis(1), |&x| x == 1);
+    assert!(result.timed_out());
+    assert_eq!(*guard, 0);
+    drop(guard);
+
+    crossbeam_utils::thread::scope(|s| {
+        let waiters = (1..=4)
+            .map(|i| {
+                let cell = &cell;
+                s.spawn(move |_| *cell.wait_until(|&x| x >= i))
+            })
+            .collect::<Vec<_>>();
+
+        for _ in 0..4 {
+            cell.modify_and_notify(|x| *x += 1);
+        }
+
+        for waiter in waiters {
+            assert!(waiter.join().unwrap() >= 1);
+        }
+    })
+    .unwrap();
+
+    assert_eq!(cell.into_inner(), 4);
+}
+
+fn mutex_generic<L: locker::mutex::RawMutex + locker::condvar::Parkable + Init + Sync>()
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+{
+    use locker::exclusive_lock::ExclusiveGuard;
+
+    let mutex = locker::mutex::Mutex::<L, Vec<u32>>::new(vec![0]);
+    let cv = Condvar::new();
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            for i in 1..=100 {
+                // pushing may reallocate, which would invalidate a projection
+                // that was kept across the wait
+                mutex.lock().push(i);
+                cv.notify_all();
+            }
+        });
+
+        let mut last = ExclusiveGuard::map(mutex.lock(), |v| v.last_mut().unwrap());
+
+        while *last != 100 {
+            last = cv.wait_mapped(&mutex, last, |v| v.last_mut().unwrap());
+        }
+
+        *last += 1;
+        drop(last);
+
+        let mut guard = mutex.lock();
+        while guard.len() != 101 {
+            cv.wait(&mut guard);
+        }
+
+        // the guard is locked again after waiting
+        guard.push(0);
+    })
+    .unwrap();
+
+    let values = mutex.into_inner();
+    assert_eq!(values.len(), 102);
+    assert_eq!(values[100], 101);
+}
+
+#[test]
+pub fn mutex_generic_over_parkable() {
+    mutex_generic::<DefaultLock>();
+    mutex_generic::<locker::mutex::word_lock::WordLock>();
+
+    // the default lock is already adaptive, so use a strategy that parks right away
+    #[cfg(feature = "parking_lot_core")]
+    mutex_generic::<locker::mutex::adaptive::AdaptiveLock<locker::waiter::ParkImmediately>>();
+}
+
+#[test]
+#[should_panic = "different mutex"]
+pub fn wait_mapped_with_other_mutex() {
+    use locker::exclusive_lock::ExclusiveGuard;
+
+    let a = DefaultLock::mutex(0);
+    let b = DefaultLock::mutex(0);
+    let cv = Condvar::new();
+
+    let guard = ExclusiveGuard::into_mapped(a.lock());
+    let _ = cv.wait_mapped(&b, guard, |x| x);
+}
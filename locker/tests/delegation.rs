@@ -0,0 +1,136 @@
+#![cfg(all(feature = "extra", any(feature = "std", feature = "alloc")))]
+
+use locker::exclusive_lock::{RawExclusiveLock, RawExclusiveLockDowngrade};
+use locker::mutex::RawMutex;
+use locker::rwlock::{raw, spin::SpinLock, RawRwLock, RwLock};
+use locker::share_lock::{RawShareLock, RawShareLockUpgrade};
+
+use std::{rc::Rc, sync::Arc};
+
+fn check_rwlock<L>(lock: L)
+where
+    L: RawRwLock + RawExclusiveLockDowngrade + RawShareLockUpgrade,
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+    L::ShareGuardTraits: locker::marker::Inhabitted,
+{
+    let rwlock = RwLock::from_raw_parts(unsafe { raw::RwLock::from_raw(lock) }, 0);
+
+    *rwlock.write() += 1;
+
+    let a = rwlock.read();
+    let b = rwlock.read();
+    assert!(rwlock.try_write().is_none());
+    assert_eq!(*a + *b, 2);
+    drop((a, b));
+
+    let guard = rwlock.write();
+    assert!(rwlock.try_read().is_none());
+    let guard = locker::exclusive_lock::ExclusiveGuard::downgrade(guard);
+    assert!(rwlock.try_read().is_some());
+    let guard = locker::share_lock::ShareGuard::upgrade(guard);
+    assert!(rwlock.try_read().is_none());
+    drop(guard);
+
+    assert!(rwlock.try_write().is_some());
+}
+
+fn check_mutex<L: RawMutex>(lock: L)
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+{
+    let mutex = locker::mutex::Mutex::from_raw_parts(
+        unsafe { locker::mutex::raw::Mutex::from_raw(lock) },
+        0,
+    );
+
+    let guard = mutex.lock();
+    assert!(mutex.try_lock().is_none());
+    drop(guard);
+    assert!(mutex.try_lock().is_some());
+}
+
+fn assert_raw<L: RawExclusiveLock + RawShareLock>(_: &L) {}
+
+#[test]
+fn rwlock_delegation() {
+    let lock = SpinLock::new();
+    check_rwlock(&lock);
+
+    let mut lock = SpinLock::new();
+    check_rwlock(&mut lock);
+
+    check_rwlock(Box::new(SpinLock::new()));
+    check_rwlock(Rc::new(SpinLock::new()));
+    check_rwlock(Arc::new(SpinLock::new()));
+    check_rwlock(&&Arc::new(SpinLock::new()));
+}
+
+#[test]
+fn mutex_delegation() {
+    let lock = SpinLock::new();
+    check_mutex(&lock);
+
+    let mut lock = SpinLock::new();
+    check_mutex(&mut lock);
+
+    check_mutex(Box::new(SpinLock::new()));
+    check_mutex(Rc::new(SpinLock::new()));
+    check_mutex(Arc::new(SpinLock::new()));
+}
+
+#[test]
+fn shared_lock_through_references() {
+    let lock = Arc::new(SpinLock::new());
+    assert_raw(&lock);
+
+    let a = RwLock::from_raw_parts(unsafe { raw::RwLock::from_raw(Arc::clone(&lock)) }, ());
+    let b = RwLock::from_raw_parts(unsafe { raw::RwLock::from_raw(&*lock) }, ());
+
+    let guard = a.read();
+    assert!(b.try_read().is_some());
+    assert!(b.try_write().is_none());
+    drop(guard);
+    assert!(b.try_write().is_some());
+}
+
+#[cfg(feature = "parking_lot_core")]
+fn check_timed_rwlock<L>(lock: L)
+where
+    L: locker::share_lock::RawShareLockUpgradeTimed<Duration = std::time::Duration>
+        + locker::share_lock::RawShareLockFair
+        + locker::exclusive_lock::RawExclusiveLockTimed,
+{
+    let timeout = std::time::Duration::from_millis(1);
+
+    assert!(lock.shr_try_lock_for(timeout));
+    assert!(lock.shr_try_lock_for(timeout));
+    assert!(!lock.exc_try_lock_for(timeout));
+
+    unsafe {
+        assert!(!lock.try_upgrade_for(timeout));
+        lock.shr_unlock_fair();
+        assert!(lock.try_upgrade_for(timeout));
+        assert!(!lock.shr_try_lock_for(timeout));
+        assert!(lock.exc_bump_for(timeout));
+        lock.exc_unlock();
+    }
+
+    assert!(lock.exc_try_lock_for(timeout));
+    unsafe { lock.exc_unlock() }
+}
+
+#[test]
+#[cfg(feature = "parking_lot_core")]
+fn timed_rwlock_delegation() {
+    use locker::rwlock::adaptive::AdaptiveLock;
+
+    let lock = AdaptiveLock::new();
+    check_timed_rwlock(&lock);
+
+    let mut lock = AdaptiveLock::new();
+    check_timed_rwlock(&mut lock);
+
+    check_timed_rwlock(Box::new(AdaptiveLock::new()));
+    check_timed_rwlock(Rc::new(AdaptiveLock::new()));
+    check_timed_rwlock(Arc::new(AdaptiveLock::new()));
+}
@@ -0,0 +1,28 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "std"))]
+fn try_downgrade() {
+    use locker::exclusive_lock::ExclusiveGuard;
+    use locker::rwlock::local::LocalLock;
+
+    // local guards are not `Send`, but can still be downgraded
+    let rwlock = LocalLock::rwlock(0);
+
+    let mut guard = rwlock.write();
+    *guard += 1;
+
+    let guard = match ExclusiveGuard::try_downgrade(guard) {
+        Ok(guard) => guard,
+        Err(_) => panic!("could not downgrade a local lock"),
+    };
+
+    assert_eq!(*guard, 1);
+    assert!(rwlock.try_read().is_some());
+    assert!(rwlock.try_write().is_none());
+    drop(guard);
+
+    let raw = rwlock.raw().write();
+    let raw = raw.try_downgrade_to_raw_share().ok().unwrap();
+    assert!(rwlock.try_write().is_none());
+    drop(raw);
+    assert!(rwlock.try_write().is_some());
+}
@@ -0,0 +1,40 @@
+#[test]
+#[cfg(feature = "extra")]
+fn guard_from_mut() {
+    use locker::mutex::spin::SpinLock;
+
+    let mut mutex = SpinLock::mutex(0);
+    *mutex.guard_from_mut() += 1;
+    assert_eq!(*mutex.try_lock().unwrap(), 1);
+
+    let guard = mutex.guard_from_mut();
+    drop(guard);
+    assert!(mutex.try_lock().is_some());
+}
+
+#[test]
+#[cfg(feature = "extra")]
+fn write_from_mut() {
+    use locker::rwlock::{local::LocalLock, spin::SpinLock};
+
+    let mut rwlock = SpinLock::rwlock(0);
+    *rwlock.write_from_mut() += 1;
+    assert_eq!(*rwlock.try_read().unwrap(), 1);
+
+    let mut rwlock = LocalLock::rwlock(0);
+    let mut guard = rwlock.write_from_mut();
+    *guard += 1;
+    drop(guard);
+    assert_eq!(*rwlock.try_write().unwrap(), 1);
+}
+
+#[test]
+#[cfg(feature = "extra")]
+#[should_panic = "tried to lock a leaked exc lock"]
+fn guard_from_mut_leaked() {
+    use locker::mutex::spin::SpinLock;
+
+    let mut mutex = SpinLock::mutex(0);
+    core::mem::forget(mutex.lock());
+    let _guard = mutex.guard_from_mut();
+}
@@ -0,0 +1,43 @@
+#![cfg(all(
+    feature = "guard-origin",
+    feature = "extra",
+    feature = "parking_lot_core"
+))]
+
+use locker::exclusive_lock::ExclusiveGuard;
+use locker::rwlock::adaptive::{AdaptiveLock, RwLock};
+use locker::share_lock::ShareGuard;
+
+// takes another read lock from a guard, without a reference to the rwlock
+fn read_again<'a>(guard: &ShareGuard<'a, AdaptiveLock, u32>) -> ShareGuard<'a, AdaptiveLock, u32> {
+    ShareGuard::rwlock(guard).unwrap().read()
+}
+
+#[test]
+fn origin() {
+    let rwlock: RwLock<u32> = AdaptiveLock::rwlock(0);
+
+    let mut guard = rwlock.write();
+    assert!(core::ptr::eq(
+        ExclusiveGuard::rwlock(&guard).unwrap(),
+        &rwlock
+    ));
+    *guard += 1;
+
+    let guard = ExclusiveGuard::downgrade(guard);
+    let other = read_again(&guard);
+    assert_eq!(*other, 1);
+    drop(other);
+
+    let clone = guard.clone();
+    assert!(ShareGuard::rwlock(&clone).is_some());
+    drop(clone);
+
+    let guard = ShareGuard::upgrade(guard);
+    assert!(ExclusiveGuard::rwlock(&guard).is_some());
+
+    // mapped guards forget where they came from
+    let (raw, value) = ExclusiveGuard::into_raw_parts(ExclusiveGuard::into_mapped(guard));
+    let guard = unsafe { ExclusiveGuard::from_raw_parts(raw, value) };
+    assert!(ExclusiveGuard::rwlock(&guard).is_none());
+}
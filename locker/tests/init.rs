@@ -0,0 +1,53 @@
+#[test]
+#[cfg(feature = "extra")]
+fn raw_locks() {
+    use locker::{mutex, rwlock, Init};
+
+    fn assert_init<L: Init + Default>() {
+        let _ = L::INIT;
+        let _ = L::default();
+    }
+
+    assert_init::<mutex::spin::SpinLock>();
+    assert_init::<mutex::local::LocalLock>();
+    assert_init::<mutex::ticket::TicketLock>();
+    assert_init::<rwlock::spin::SpinLock>();
+    assert_init::<rwlock::local::LocalLock>();
+    assert_init::<rwlock::ticket::TicketLock>();
+    assert_init::<mutex::raw::Mutex<mutex::spin::SpinLock>>();
+    assert_init::<rwlock::raw::RwLock<rwlock::spin::SpinLock>>();
+
+    #[cfg(feature = "parking_lot_core")]
+    assert_init::<mutex::tagged::TaggedLock>();
+
+    assert_init::<mutex::tagged_spin::TaggedSpinLock>();
+    assert_init::<mutex::splittable_spin::SplitSpinLock>();
+    assert_init::<rwlock::splittable_spin::SplitSpinLock>();
+
+    #[cfg(feature = "parking_lot_core")]
+    assert_init::<rwlock::phase_fair::PhaseFairLock>();
+
+    #[cfg(feature = "adaptive")]
+    {
+        assert_init::<mutex::adaptive::AdaptiveLock>();
+        assert_init::<rwlock::adaptive::AdaptiveLock>();
+        assert_init::<mutex::default::DefaultLock>();
+        assert_init::<rwlock::default::DefaultLock>();
+    }
+}
+
+#[test]
+#[cfg(feature = "extra")]
+fn tuples_and_arrays() {
+    use locker::exclusive_lock::RawExclusiveLock;
+    use locker::mutex::spin::SpinLock;
+    use locker::Init;
+
+    let (a, b): (SpinLock, SpinLock) = Init::INIT;
+    let locks: [SpinLock; 4] = Init::INIT;
+
+    a.exc_lock();
+    assert!(!a.exc_try_lock());
+    assert!(b.exc_try_lock());
+    assert!(locks.iter().all(|lock| lock.exc_try_lock()));
+}
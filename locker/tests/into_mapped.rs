@@ -0,0 +1,34 @@
+#[test]
+#[cfg(feature = "extra")]
+fn into_mapped() {
+    use locker::exclusive_lock::{ExclusiveGuard, MappedExclusiveGuard};
+    use locker::rwlock::spin::SpinLock;
+    use locker::share_lock::ShareGuard;
+    use locker::{GuardState, Mapped, Pure};
+
+    fn increment<St: GuardState>(g: ExclusiveGuard<'_, SpinLock, u32, St>) -> u32 {
+        let mut g = ExclusiveGuard::into_mapped(g);
+        *g += 1;
+        *g
+    }
+
+    assert!(Pure::can_unlock_temporarily());
+    assert!(!Mapped::can_unlock_temporarily());
+
+    let lock = SpinLock::rwlock((0_u32, 10_u32));
+
+    assert_eq!(
+        increment(ExclusiveGuard::map(lock.write(), |x| &mut x.0)),
+        1
+    );
+
+    let guard: MappedExclusiveGuard<_, _> = ExclusiveGuard::into_mapped(lock.write());
+    assert_eq!(increment(ExclusiveGuard::map(guard, |x| &mut x.1)), 11);
+
+    let guard = ShareGuard::into_mapped(lock.read());
+    assert_eq!(*guard, (1, 11));
+    assert_eq!(
+        core::mem::size_of_val(&guard),
+        core::mem::size_of_val(&lock.read())
+    );
+}
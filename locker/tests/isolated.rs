@@ -0,0 +1,26 @@
+#![cfg(feature = "test-util")]
+
+use locker::mutex::global::{GlobalLock, Mutex};
+
+#[test]
+fn isolated_set() {
+    // neighbouring global mutexes 61 bytes apart share a lock in the global set
+    let mutexes: Vec<Mutex<u8>> = (0..62).map(GlobalLock::mutex).collect();
+    let (a, b) = (&mutexes[0], &mutexes[61]);
+    assert!(GlobalLock::will_mutex_contend(a, b));
+
+    let _a = a.lock();
+    assert!(b.try_lock().is_none());
+
+    unsafe {
+        GlobalLock::with_isolated_set(|| {
+            let _b = b.try_lock().unwrap();
+
+            // nested sets are isolated from each other too
+            GlobalLock::with_isolated_set(|| assert_eq!(*b.try_lock().unwrap(), 61));
+            assert!(b.try_lock().is_none());
+        });
+    }
+
+    assert!(b.try_lock().is_none());
+}
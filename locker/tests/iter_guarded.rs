@@ -0,0 +1,17 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "std"))]
+fn iter_guarded() {
+    use locker::rwlock::default::DefaultLock;
+    use locker::share_lock::{MappedShareGuard, ShareGuard};
+
+    let rwlock = DefaultLock::rwlock(vec![1, 2, 3]);
+
+    let guards: Vec<MappedShareGuard<'_, _, i32>> =
+        ShareGuard::iter_guarded(rwlock.read()).collect();
+
+    assert!(rwlock.try_write().is_none());
+    assert_eq!(guards.iter().map(|g| **g).collect::<Vec<_>>(), [1, 2, 3]);
+
+    drop(guards);
+    assert!(rwlock.try_write().is_some());
+}
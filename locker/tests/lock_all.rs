@@ -0,0 +1,60 @@
+#[test]
+#[cfg(feature = "parking_lot_core")]
+fn lock_all() {
+    use locker::lock_all::{lock_all, try_lock_all, Read, Write};
+    use locker::rwlock::adaptive::AdaptiveLock;
+
+    let a = AdaptiveLock::mutex(0);
+    let b = AdaptiveLock::mutex(0);
+    let c = AdaptiveLock::rwlock(0);
+
+    // the locks are listed in opposite orders, but acquired in the same order
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            for _ in 0..1000 {
+                let (mut a, mut b, c) = lock_all((&a, &b, Read(&c)));
+                *a += 1;
+                *b += *c + 1;
+            }
+        });
+
+        s.spawn(|_| {
+            for _ in 0..1000 {
+                let (mut c, mut b, mut a) = lock_all((Write(&c), &b, &a));
+                *a += 1;
+                *b += 1;
+                *c += 0;
+            }
+        });
+
+        for _ in 0..1000 {
+            let (mut b, mut a) = b.lock_2(&a);
+            *a += 1;
+            *b += 1;
+        }
+    })
+    .unwrap();
+
+    assert_eq!((*a.lock(), *b.lock()), (3000, 3000));
+
+    let d = AdaptiveLock::rwlock(1);
+    let (mut write_c, write_d) = c.write_2(&d);
+    *write_c += 1;
+    assert_eq!(*write_d, 1);
+    drop(write_d);
+
+    // all or nothing, `a` is released again because `c` is still locked
+    assert!(try_lock_all((&a, Read(&c))).is_none());
+    assert!(a.try_lock().is_some());
+    drop(write_c);
+
+    let (a, c, d) = try_lock_all((&a, Read(&c), Read(&d))).unwrap();
+    assert_eq!((*a, *c, *d), (3000, 1, 1));
+}
+
+#[test]
+#[should_panic(expected = "tried to acquire the same lock more than once")]
+fn lock_all_twice() {
+    let mutex = locker::mutex::spin::SpinLock::mutex(0);
+    let _guards = locker::lock_all::lock_all((&mutex, &mutex));
+}
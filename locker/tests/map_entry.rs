@@ -0,0 +1,38 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn read_key_or_insert_with() {
+    use locker::rwlock::adaptive::AdaptiveLock;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let map = AdaptiveLock::rwlock(HashMap::new());
+    let inserts = AtomicUsize::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|_| {
+                let value = map.read_key_or_insert_with(1, || {
+                    inserts.fetch_add(1, Ordering::Relaxed);
+                    String::from("one")
+                });
+
+                assert_eq!(*value, "one");
+                // the value is returned with a read lock
+                assert!(map.try_write().is_none());
+
+                // another thread may be waiting for the write lock, so reading again
+                // while the read lock is held could deadlock
+                drop(value);
+                assert!(map.read_key(&1).is_some());
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(inserts.load(Ordering::Relaxed), 1);
+
+    map.write_entry(2).push_str("two");
+    assert_eq!(*map.read_key(&2).unwrap(), "two");
+    assert!(map.read_key(&3).is_none());
+    assert_eq!(map.read().len(), 2);
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "test-util")]
+
+use locker::exclusive_lock::ExclusiveGuard;
+use locker::mock::{Call, MockLock, Outcome};
+use locker::share_lock::ShareGuard;
+use std::time::Duration;
+
+#[test]
+fn scripted_rwlock() {
+    let rwlock = MockLock::rwlock(0);
+    let lock = rwlock.raw().inner();
+    let timeout = Duration::from_millis(10);
+
+    // without a script, the lock follows the locks that are held
+    let a = rwlock.read();
+    let b = rwlock.read();
+    assert_eq!(lock.shr_count(), 2);
+    assert!(rwlock.try_write().is_none());
+
+    let b = ShareGuard::try_upgrade(b).unwrap_err();
+    drop(a);
+    let mut b = ShareGuard::try_upgrade(b).unwrap();
+    *b += 1;
+    assert!(lock.is_exc_locked());
+
+    // if the lock isn't re-acquired in time, it's re-acquired by blocking
+    lock.script([Outcome::Timeout, Outcome::Succeed, Outcome::Fail]);
+    assert!(!ExclusiveGuard::bump_for(&mut b, timeout));
+    assert!(lock.is_exc_locked());
+    drop(b);
+    assert!(rwlock.try_read_for(timeout).is_none());
+    assert_eq!(lock.remaining(), 0);
+
+    let b = ExclusiveGuard::downgrade(rwlock.write());
+    assert_eq!(*b, 1);
+    drop(b);
+
+    assert_eq!(
+        lock.take_calls(),
+        [
+            Call::ShrLock,
+            Call::ShrLock,
+            Call::ExcTryLock,
+            Call::TryUpgrade,
+            Call::ShrUnlock,
+            Call::TryUpgrade,
+            Call::ExcBumpFor(timeout),
+            Call::ExcUnlock,
+            Call::ShrTryLockFor(timeout),
+            Call::ExcLock,
+            Call::Downgrade,
+            Call::ShrUnlock,
+        ]
+    );
+    assert!(lock.calls().is_empty());
+}
+
+#[test]
+#[should_panic = "`ExcLock` would block forever on a mock lock"]
+fn would_block() {
+    let mutex = MockLock::mutex(());
+    let _guard = mutex.lock();
+    let _ = mutex.lock();
+}
+
+#[test]
+#[should_panic = "`ExcTryLock` can't time out, but the script says it does"]
+fn untimed_timeout() {
+    let mutex = MockLock::mutex(());
+    mutex.raw().inner().script([Outcome::Timeout]);
+    let _ = mutex.try_lock();
+}
+
+#[test]
+#[should_panic = "`ExcTryLock` can't succeed while conflicting locks are held, but the script says it does"]
+fn conflicting_succeed() {
+    let mutex = MockLock::mutex(());
+    let _guard = mutex.lock();
+    mutex.raw().inner().script([Outcome::Succeed]);
+    let _ = mutex.try_lock();
+}
@@ -0,0 +1,43 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "std"))]
+fn project_keys() {
+    use locker::rwlock::default::DefaultLock;
+    use locker::share_lock::ShareGuard;
+    use std::collections::HashMap;
+
+    let mut map = HashMap::new();
+    map.insert("a", 1);
+    map.insert("b", 2);
+    map.insert("c", 3);
+
+    let rwlock = DefaultLock::rwlock(map);
+
+    let guards = ShareGuard::project_keys(rwlock.read(), &["c", "missing", "a"]);
+    let values: Vec<_> = guards
+        .iter()
+        .map(|guard| guard.as_deref().copied())
+        .collect();
+    assert_eq!(values, [Some(3), None, Some(1)]);
+    assert!(rwlock.try_write().is_none());
+
+    drop(guards);
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+#[cfg(all(feature = "extra", feature = "std"))]
+fn try_map_split_many() {
+    use locker::rwlock::default::DefaultLock;
+    use locker::share_lock::ShareGuard;
+
+    let rwlock = DefaultLock::rwlock(vec![1, 2, 3]);
+
+    let [a, b, c] =
+        ShareGuard::try_map_split_many(rwlock.read(), |x| [x.get(2), x.get(5), x.first()]);
+    assert!(b.is_none());
+    assert_eq!((*a.unwrap(), *c.as_deref().unwrap()), (3, 1));
+    assert!(rwlock.try_write().is_none());
+
+    drop(c);
+    assert!(rwlock.try_write().is_some());
+}
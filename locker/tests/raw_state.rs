@@ -0,0 +1,62 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn decode_rwlock_states() {
+    use locker::exclusive_lock::RawExclusiveLock;
+    use locker::raw_state::{DecodedState, RawStateBits};
+    use locker::rwlock::{adaptive::AdaptiveLock, splittable::SplitLock};
+    use locker::share_lock::RawShareLock;
+
+    fn check<L: RawStateBits + RawExclusiveLock + RawShareLock + locker::Init>() {
+        let lock = L::INIT;
+        assert_eq!(lock.decoded_state(), DecodedState::default());
+
+        lock.shr_lock();
+        lock.shr_lock();
+        let state = lock.decoded_state();
+        assert_eq!((state.exc, state.readers), (0, 2));
+
+        unsafe {
+            lock.shr_unlock();
+            lock.shr_unlock();
+        }
+
+        lock.exc_lock();
+        let bits = lock.raw_state_bits();
+        let state = L::decode_state(bits);
+        assert_eq!((state.exc, state.readers), (1, 0));
+        assert!(!state.parked && !state.exc_parked);
+        unsafe { lock.exc_unlock() }
+    }
+
+    check::<AdaptiveLock>();
+    check::<SplitLock>();
+    check::<locker::rwlock::spin::SpinLock>();
+}
+
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn decode_parked_mutex() {
+    use locker::exclusive_lock::RawExclusiveLock;
+    use locker::mutex::adaptive::AdaptiveLock;
+    use locker::raw_state::RawStateBits;
+
+    let lock = AdaptiveLock::new();
+    lock.exc_lock();
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            lock.exc_lock();
+            unsafe { lock.exc_unlock() }
+        });
+
+        while !lock.decoded_state().parked {
+            std::thread::yield_now();
+        }
+
+        assert_eq!(lock.decoded_state().exc, 1);
+        unsafe { lock.exc_unlock() }
+    })
+    .unwrap();
+
+    assert!(!lock.decoded_state().parked);
+}
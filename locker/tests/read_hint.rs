@@ -0,0 +1,38 @@
+#![cfg(feature = "extra")]
+
+use locker::rwlock::{RawRwLock, RwLock};
+use locker::share_lock::Contention;
+
+fn check<L: RawRwLock + locker::Init>()
+where
+    L::ExclusiveGuardTraits: locker::marker::Inhabitted,
+    L::ShareGuardTraits: locker::marker::Inhabitted,
+{
+    let rwlock = RwLock::<L, _>::new(0);
+
+    let a = rwlock.read_uncontended_hint();
+    // the guess is wrong, so the lock falls back to the normal strategy
+    let b = rwlock.read_hint(Contention::Low);
+    let c = rwlock.read_hint(Contention::High);
+    assert_eq!(*a + *b + *c, 0);
+    assert!(rwlock.try_write().is_none());
+    drop((a, b, c));
+
+    let guard = rwlock.write();
+    assert!(rwlock.try_read_hint(Contention::Low).is_none());
+    assert!(rwlock.try_read_hint(Contention::High).is_none());
+    drop(guard);
+
+    assert!(rwlock.try_read_hint(Contention::Low).is_some());
+    assert!(rwlock.try_write().is_some());
+}
+
+#[test]
+fn read_hint() {
+    check::<locker::rwlock::spin::SpinLock>();
+    check::<locker::rwlock::default::DefaultLock>();
+    check::<locker::rwlock::ticket::TicketLock>();
+
+    #[cfg(feature = "parking_lot_core")]
+    check::<locker::rwlock::adaptive::AdaptiveLock>();
+}
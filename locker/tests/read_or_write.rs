@@ -0,0 +1,61 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn read_or_write() {
+    use locker::rwlock::{adaptive::AdaptiveLock, Preference};
+    use std::time::Duration;
+
+    let rwlock = AdaptiveLock::rwlock(0);
+
+    let mut guard = rwlock.read_or_write(Preference::Write);
+    assert!(guard.is_write());
+    *guard.as_mut().unwrap() += 1;
+
+    assert!(rwlock.try_read_or_write(Preference::Read).is_none());
+    assert!(rwlock
+        .try_read_or_write_for(Preference::Read, Duration::from_millis(1))
+        .is_none());
+    drop(guard);
+
+    let read = rwlock.read();
+    let guard = rwlock.read_or_write(Preference::Write);
+    assert!(guard.is_read());
+    assert_eq!(*guard, 1);
+
+    drop(read);
+    drop(guard);
+
+    let guard = rwlock.read_or_write(Preference::Read);
+    assert!(guard.is_read());
+}
+
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn read_or_write_first_available() {
+    use locker::exclusive_lock::ExclusiveGuard;
+    use locker::rwlock::{adaptive::AdaptiveLock, Preference};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let rwlock = AdaptiveLock::rwlock(0);
+    let (tx, rx) = mpsc::channel();
+
+    let write = rwlock.write();
+
+    crossbeam_utils::thread::scope(|s| {
+        let rwlock = &rwlock;
+        s.spawn(move |_| {
+            // write access only becomes available once the main thread releases its read
+            // lock, which it only does after this thread got access, so this must read
+            let guard = rwlock
+                .try_read_or_write_for(Preference::Write, Duration::from_secs(10))
+                .unwrap();
+            assert!(guard.is_read());
+            tx.send(()).unwrap();
+        });
+
+        let read = ExclusiveGuard::downgrade(write);
+        rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        drop(read);
+    })
+    .unwrap();
+}
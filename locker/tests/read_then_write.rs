@@ -0,0 +1,78 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn read_then_maybe_write() {
+    use locker::rwlock::adaptive::AdaptiveLock;
+
+    let rwlock = AdaptiveLock::rwlock(0);
+    let below = |limit| move |value: &u32| if *value < limit { Some(*value) } else { None };
+
+    // nothing to do, so no write lock is taken
+    let read = rwlock.read();
+    assert_eq!(rwlock.read_then_maybe_write(below(0), |_, _| ()), None);
+    drop(read);
+
+    assert_eq!(
+        rwlock.read_then_maybe_write(below(1), |value, old| {
+            *value = old + 1;
+            old
+        }),
+        Some(0)
+    );
+    assert_eq!(*rwlock.read(), 1);
+
+    // the check is done again once the write lock is held, and can change its mind
+    let mut checks = 0;
+    let result = rwlock.read_then_maybe_write(
+        |value| {
+            checks += 1;
+            if checks == 1 {
+                // the first check is done with the read lock
+                assert!(rwlock.try_write().is_none());
+                assert!(rwlock.try_read().is_some());
+                Some(*value)
+            } else {
+                None
+            }
+        },
+        |_, _| unreachable!(),
+    );
+    assert_eq!(result, None);
+    assert_eq!(checks, 2);
+}
+
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn read_then_maybe_upgrade() {
+    use locker::rwlock::adaptive::AdaptiveLock;
+
+    let rwlock = AdaptiveLock::rwlock(0);
+    let mut checks = 0;
+
+    let result = rwlock.read_then_maybe_upgrade(
+        |value| {
+            checks += 1;
+            Some(*value + 1)
+        },
+        |value, new| *value = new,
+    );
+    assert_eq!(result, Some(()));
+    assert_eq!(checks, 1);
+    assert_eq!(*rwlock.read(), 1);
+
+    // another reader blocks the upgrade, so the value is checked again under a write lock
+    crossbeam_utils::thread::scope(|s| {
+        let read = rwlock.read();
+
+        let handle = s.spawn(|_| {
+            rwlock.read_then_maybe_upgrade(|value| Some(*value * 10), |value, new| *value = new)
+        });
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        drop(read);
+
+        assert_eq!(handle.join().unwrap(), Some(()));
+    })
+    .unwrap();
+
+    assert_eq!(*rwlock.read(), 10);
+}
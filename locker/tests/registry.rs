@@ -0,0 +1,108 @@
+#![cfg(feature = "registry")]
+
+use locker::mutex::default::DefaultLock;
+use locker::registry::{self, Access, Held, Registered};
+use locker::rwlock::RwLock;
+use locker::share_lock::ShareGuard;
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+type RegisteredMutex<T> = locker::mutex::Mutex<Registered<DefaultLock>, T>;
+type RegisteredRwLock<T> = RwLock<Registered<locker::rwlock::default::DefaultLock>, T>;
+
+fn addr<L>(lock: &L) -> usize {
+    lock as *const L as usize
+}
+
+// the registry is process-wide, so everything is checked in a single test
+#[test]
+fn freeze() {
+    let mutex = RegisteredMutex::new(0);
+    let rwlock = RegisteredRwLock::new(0);
+    let mutex_addr = addr(mutex.raw().inner());
+    let rwlock_addr = addr(rwlock.raw().inner());
+
+    // locks are only registered once they are acquired
+    assert!(registry::report_held().is_empty());
+
+    *mutex.lock() += 1;
+    assert!(registry::report_held().is_empty());
+
+    // a lock that moved is tracked at its new address
+    let mut slot = RegisteredMutex::new(0);
+    *slot.lock() += 1;
+    let moved = Box::new(std::mem::replace(&mut slot, RegisteredMutex::new(0)));
+
+    let moved_guard = moved.lock();
+    let slot_guard = slot.lock();
+    let mut held = registry::report_held();
+    held.sort_by_key(|held| held.addr);
+    let mut expected = [addr(moved.raw().inner()), addr(slot.raw().inner())].map(|addr| Held {
+        addr,
+        name: None,
+        access: Access::Exclusive,
+        guards: 1,
+    });
+    expected.sort_by_key(|held| held.addr);
+    assert_eq!(held, expected);
+
+    drop((moved_guard, slot_guard));
+    assert!(registry::report_held().is_empty());
+
+    let read = rwlock.read();
+    let read_2 = ShareGuard::clone(&read);
+    let write = mutex.lock();
+
+    let mut held = registry::report_held();
+    held.sort_by_key(|held| held.addr);
+    let mut expected = vec![
+        Held {
+            addr: mutex_addr,
+            name: None,
+            access: Access::Exclusive,
+            guards: 1,
+        },
+        Held {
+            addr: rwlock_addr,
+            name: None,
+            access: Access::Shared,
+            guards: 2,
+        },
+    ];
+    expected.sort_by_key(|held| held.addr);
+    assert_eq!(held, expected);
+
+    registry::freeze();
+    assert!(registry::is_frozen());
+
+    // guards acquired before the freeze can still be used and released
+    drop(read_2);
+    assert!(rwlock.try_read().is_none());
+    assert!(mutex.try_lock().is_none());
+
+    let payload = catch_unwind(AssertUnwindSafe(|| rwlock.read())).unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(
+        message.contains("after the lock registry was frozen"),
+        "{}",
+        message
+    );
+
+    drop(write);
+    assert_eq!(
+        registry::report_held(),
+        [Held {
+            addr: rwlock_addr,
+            name: None,
+            access: Access::Shared,
+            guards: 1,
+        }]
+    );
+
+    drop(read);
+    assert!(registry::report_held().is_empty());
+
+    // unregistered locks aren't affected
+    let plain = DefaultLock::mutex(0);
+    *plain.lock() += 1;
+}
@@ -0,0 +1,46 @@
+#[test]
+#[cfg(feature = "extra")]
+fn replace_set_update() {
+    use locker::exclusive_lock::ExclusiveGuard;
+    use locker::mutex::spin::SpinLock;
+
+    #[derive(Debug, PartialEq)]
+    enum State {
+        Idle,
+        Running(u32),
+        Done(u32),
+    }
+
+    let mutex = SpinLock::mutex(State::Idle);
+    let mut guard = mutex.lock();
+
+    assert_eq!(
+        ExclusiveGuard::replace(&mut guard, State::Running(1)),
+        State::Idle
+    );
+
+    ExclusiveGuard::replace_with(&mut guard, |state| match state {
+        State::Running(n) => State::Done(n + 1),
+        state => state,
+    });
+    assert_eq!(*guard, State::Done(2));
+
+    ExclusiveGuard::set(&mut guard, State::Idle);
+    assert_eq!(*guard, State::Idle);
+    drop(guard);
+
+    let mutex = SpinLock::mutex(vec![1, 2]);
+    let mut guard = mutex.lock();
+    ExclusiveGuard::update(&mut guard, |mut list| {
+        list.push(3);
+        list
+    });
+    assert_eq!(*guard, [1, 2, 3]);
+
+    // a panic leaves the default value behind
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        ExclusiveGuard::update(&mut guard, |_| panic!())
+    }));
+    assert!(result.is_err());
+    assert!(guard.is_empty());
+}
@@ -0,0 +1,29 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "std"))]
+fn split_into() {
+    use locker::exclusive_lock::ExclusiveGuard;
+    use locker::mutex::splittable_default::SplitDefaultLock;
+    use locker::rwlock::default::DefaultLock;
+    use locker::share_lock::ShareGuard;
+
+    let rwlock = DefaultLock::rwlock([1, 2, 3, 4]);
+
+    let [a, b, c] = ShareGuard::split_into(rwlock.read(), |x| [&x[0], &x[1], &x[3]]);
+    assert_eq!((*a, *b, *c), (1, 2, 4));
+    assert!(rwlock.try_write().is_none());
+
+    drop((a, b));
+    assert!(rwlock.try_write().is_none());
+    drop(c);
+    assert!(rwlock.try_write().is_some());
+
+    let mutex = SplitDefaultLock::mutex([1, 2, 3]);
+
+    let [mut a, mut b, mut c] = ExclusiveGuard::split_into(mutex.lock(), |[a, b, c]| [a, b, c]);
+    std::mem::swap(&mut *a, &mut *c);
+    *b *= 10;
+    assert!(mutex.try_lock().is_none());
+    drop((a, b, c));
+
+    assert_eq!(*mutex.lock(), [3, 20, 1]);
+}
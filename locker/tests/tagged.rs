@@ -0,0 +1,98 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "parking_lot_core"))]
+fn wait_for_tag() {
+    use locker::mutex::tagged::TaggedLock;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    const READY: u8 = 1;
+    const DONE: u8 = 2;
+
+    let mutex = TaggedLock::mutex(0_u32);
+    let tag = mutex.raw().inner();
+
+    assert_eq!(
+        tag.wait_for_tag(|tag| tag == READY, Some(Duration::from_millis(10))),
+        Err(0)
+    );
+
+    crossbeam_utils::thread::scope(|s| {
+        let guard = mutex.lock();
+
+        // threads waiting for the lock and threads waiting for a tag share the lock's address
+        let locker = s.spawn(|_| *mutex.lock() += 1);
+        let waiters: Vec<_> = (0..4)
+            .map(|_| s.spawn(|_| tag.wait_for_tag(|tag| tag == DONE, None)))
+            .collect();
+
+        std::thread::sleep(Duration::from_millis(20));
+        tag.update_tag_and_notify(Ordering::Release, Ordering::Relaxed, |_| Some(READY))
+            .unwrap();
+        drop(guard);
+        locker.join().unwrap();
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(*mutex.lock(), 1);
+        tag.update_tag_and_notify(Ordering::Release, Ordering::Relaxed, |_| Some(DONE))
+            .unwrap();
+
+        for waiter in waiters {
+            assert_eq!(waiter.join().unwrap(), Ok(DONE));
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "extra")]
+fn tag_fields() {
+    use locker::mutex::tag_field::{AtomicTag, Bits};
+    use locker::mutex::tagged_spin::TaggedSpinLock;
+    use std::sync::atomic::Ordering;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[repr(u8)]
+    enum State {
+        Empty = 1,
+        Full = 3,
+    }
+
+    locker::impl_tag_value! { State { Empty, Full } }
+
+    type Count = Bits<0, 3>;
+    type StateField = Bits<3, 2, State>;
+    type Flag = Bits<6, 1, bool>;
+
+    let lock = TaggedSpinLock::with_tag(0b0001_1000);
+    assert_eq!(lock.tag_field::<StateField>(Ordering::Relaxed), State::Full);
+
+    assert_eq!(
+        lock.set_field::<StateField>(State::Empty, Ordering::Relaxed),
+        State::Full
+    );
+    assert!(!lock.set_field::<Flag>(true, Ordering::Relaxed));
+    for _ in 0..5 {
+        lock.update_field::<Count>(Ordering::Relaxed, Ordering::Relaxed, |x| x.checked_add(1))
+            .unwrap();
+    }
+
+    // the fields don't overwrite each other
+    assert_eq!(lock.tag(Ordering::Relaxed), 0b0100_1101);
+    assert_eq!(lock.tag_field::<Count>(Ordering::Relaxed), 5);
+
+    // the lock bit is outside of the tag, so locking doesn't change any field
+    let mutex = locker::mutex::Mutex::from_raw_parts(
+        unsafe { locker::mutex::raw::Mutex::from_raw(lock) },
+        (),
+    );
+    let _guard = mutex.lock();
+    let lock = mutex.raw().inner();
+    assert_eq!(
+        lock.tag_field::<StateField>(Ordering::Relaxed),
+        State::Empty
+    );
+    assert!(lock.tag_field::<Flag>(Ordering::Relaxed));
+
+    let result = std::panic::catch_unwind(|| lock.set_field::<Count>(8, Ordering::Relaxed));
+    assert!(result.is_err());
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "tracing")]
+
+use locker::condvar::Condvar;
+use locker::mutex::default::DefaultLock;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Records the `kind` of every `lock_wait` span, and the message of every event
+#[derive(Default)]
+struct Recorder {
+    next_id: AtomicU64,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+struct Fields(Vec<String>);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push(format!("{}={}", field.name(), value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0.push(format!("{:?}", value));
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = Fields(Vec::new());
+        span.record(&mut fields);
+        let mut log = self.log.lock().unwrap();
+        log.push(format!("{} {}", span.metadata().name(), fields.0.join(" ")));
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields(Vec::new());
+        event.record(&mut fields);
+        self.log.lock().unwrap().push(fields.0.join(" "));
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn record(f: impl FnOnce()) -> Vec<String> {
+    let recorder = Recorder::default();
+    let log = recorder.log.clone();
+    tracing::subscriber::with_default(recorder, f);
+    let log = log.lock().unwrap();
+    log.clone()
+}
+
+#[test]
+fn uncontended_is_silent() {
+    let mutex = DefaultLock::mutex(0);
+
+    let log = record(|| {
+        *mutex.lock() += 1;
+    });
+
+    assert!(log.is_empty(), "{:?}", log);
+}
+
+#[test]
+fn condvar_wait() {
+    let mutex = DefaultLock::mutex(());
+    let cv = Condvar::new();
+
+    let log = record(|| {
+        let mut guard = mutex.lock();
+        assert!(cv
+            .wait_for(&mut guard, Duration::from_millis(1))
+            .timed_out());
+    });
+
+    assert_eq!(log, ["lock_wait kind=condvar_wait", "lock wait ended"]);
+}
+
+#[test]
+fn contended_lock() {
+    let mutex = DefaultLock::mutex(0);
+
+    let log = crossbeam_utils::thread::scope(|s| {
+        let mut guard = mutex.lock();
+
+        let waiter = s.spawn(|_| record(|| *mutex.lock() += 1));
+
+        std::thread::sleep(Duration::from_millis(50));
+        *guard += 1;
+        drop(guard);
+
+        waiter.join().unwrap()
+    })
+    .unwrap();
+
+    assert_eq!(*mutex.lock(), 2);
+    assert_eq!(log, ["lock_wait kind=exc_lock", "lock wait ended"]);
+}
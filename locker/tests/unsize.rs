@@ -0,0 +1,25 @@
+#[test]
+#[cfg(all(feature = "extra", feature = "std"))]
+fn unsize_guard() {
+    use locker::exclusive_lock::ExclusiveGuard;
+    use locker::rwlock::default::DefaultLock;
+    use locker::share_lock::ShareGuard;
+    use std::fmt::Debug;
+
+    let rwlock = DefaultLock::rwlock([1, 2, 3]);
+
+    let mut slice: ShareGuard<'_, DefaultLock, [i32]> =
+        locker::unsize_guard!(rwlock.read() => [i32]);
+    assert_eq!(slice.len(), 3);
+
+    // the coerced guard is still a pure guard
+    ShareGuard::bump(&mut slice);
+    assert_eq!(*slice, [1, 2, 3]);
+    drop(slice);
+
+    let mut debug: ExclusiveGuard<'_, DefaultLock, dyn Debug> =
+        locker::unsize_guard!(rwlock.write() => dyn Debug);
+    ExclusiveGuard::unlocked(&mut debug, || assert!(rwlock.try_read().is_some()));
+    assert_eq!(format!("{:?}", &*debug), "[1, 2, 3]");
+    assert!(rwlock.try_read().is_none());
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "extra")]
+
+use locker::exclusive_lock::ExclusiveGuard;
+use locker::rwlock::default::DefaultLock;
+use locker::rwlock::versioned::Versioned;
+
+#[test]
+fn versioned() {
+    let rwlock = Versioned::<DefaultLock>::rwlock(0);
+    assert_eq!(rwlock.version(), 0);
+
+    // reading doesn't change the version
+    let (guard, version) = rwlock.read_versioned();
+    assert_eq!((*guard, version), (0, 0));
+    drop(guard);
+    assert!(rwlock.validate(version));
+
+    // the version changes once the write lock is released
+    let mut guard = rwlock.write();
+    *guard += 1;
+    assert!(rwlock.validate(version));
+    drop(guard);
+    assert_eq!(rwlock.version(), 1);
+    assert!(!rwlock.validate(version));
+
+    // downgrading and bumping count as releasing the write lock
+    let mut guard = rwlock.write();
+    ExclusiveGuard::bump(&mut guard);
+    assert_eq!(rwlock.version(), 2);
+
+    let guard = ExclusiveGuard::downgrade(guard);
+    assert_eq!(rwlock.version(), 3);
+    assert!(rwlock.try_read().is_some());
+    drop(guard);
+    assert_eq!(rwlock.version(), 3);
+}
+
+#[test]
+fn optimistic_increment() {
+    let rwlock = Versioned::<DefaultLock>::rwlock(0_u32);
+
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..100 {
+                    loop {
+                        let (guard, version) = rwlock.read_versioned();
+                        let next = *guard + 1;
+                        drop(guard);
+
+                        let mut guard = rwlock.write();
+                        if rwlock.validate(version) {
+                            *guard = next;
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    assert_eq!(*rwlock.read(), 400);
+    assert!(rwlock.version() >= 400);
+}
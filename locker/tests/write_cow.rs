@@ -0,0 +1,58 @@
+#![cfg(feature = "extra")]
+
+use locker::rwlock::cow::CowWriteGuard;
+use locker::rwlock::default::DefaultLock;
+
+#[test]
+fn write_cow() {
+    let rwlock = DefaultLock::rwlock(vec![1, 2, 3]);
+
+    // reading doesn't copy, and dropping the guard doesn't write
+    let guard = rwlock.write_cow();
+    assert_eq!(*guard, [1, 2, 3]);
+    assert!(!CowWriteGuard::is_copied(&guard));
+    assert!(rwlock.try_read().is_some());
+    assert_eq!(CowWriteGuard::commit(guard), Ok(None));
+
+    let mut guard = rwlock.write_cow();
+    guard.push(4);
+    assert!(CowWriteGuard::is_copied(&guard));
+    assert_eq!(*CowWriteGuard::original(&guard), [1, 2, 3]);
+
+    // only read access is held until the guard is dropped
+    assert!(rwlock.try_write().is_none());
+    assert_eq!(*rwlock.read(), [1, 2, 3]);
+    drop(guard);
+    assert_eq!(*rwlock.read(), [1, 2, 3, 4]);
+
+    let mut guard = rwlock.write_cow();
+    guard.clear();
+    CowWriteGuard::discard(guard);
+    assert_eq!(*rwlock.read(), [1, 2, 3, 4]);
+}
+
+#[test]
+fn concurrent_write_cow() {
+    let rwlock = DefaultLock::rwlock(0);
+
+    // neither guard can upgrade while the other holds its read lock, so the
+    // first commit hands its copy back instead of waiting for the other guard
+    let mut a = rwlock.write_cow();
+    let mut b = rwlock.write_cow();
+    *a += 1;
+    *b += 2;
+
+    assert_eq!(CowWriteGuard::commit(a), Err(1));
+    assert_eq!(CowWriteGuard::commit(b), Ok(Some(0)));
+    assert_eq!(*rwlock.read(), 2);
+
+    // a plain reader also prevents the upgrade, and dropping the guard
+    // throws the copy away
+    let mut a = rwlock.write_cow();
+    *a += 1;
+    let reader = rwlock.read();
+    drop(a);
+    assert_eq!(*reader, 2);
+    drop(reader);
+    assert_eq!(*rwlock.read(), 2);
+}
@@ -0,0 +1,255 @@
+//! Per-core values, for targets without `std`
+//!
+//! On bare metal there are no threads to key values by, but there is a fixed number
+//! of cores, and usually a cheap way to ask which core is running. [`CoreLocal`] and
+//! [`CoreLocalKey`] have the same shape as `ThreadLocal` and `LocalKey`, but they look
+//! up values by the [`CoreId`] of the running core, and store them in an array with
+//! room for `N` cores, so they never allocate.
+//!
+//! ```
+//! use std::cell::Cell;
+//! use thread_local::core_local::{CoreId, CoreLocalKey};
+//!
+//! struct Cpu;
+//!
+//! // there is only a single core on this target, and no interrupt handler
+//! // accesses a `CoreLocal`
+//! unsafe impl CoreId for Cpu {
+//!     fn current() -> usize {
+//!         0
+//!     }
+//! }
+//!
+//! static TICKS: CoreLocalKey<Cell<u32>, Cpu, 1> = CoreLocalKey::new(|| Cell::new(0));
+//!
+//! TICKS.with(|ticks| ticks.set(ticks.get() + 1));
+//! assert_eq!(TICKS.get(), 1);
+//! ```
+
+use core::marker::PhantomData;
+use locker::once::spin::{OnceCell, RawLock};
+use locker::once::TryInitError;
+
+/// A source of the id of the running core
+///
+/// # Safety
+///
+/// `current` must return different ids in any two contexts that may access the same
+/// [`CoreLocal`] at the same time. This includes interrupt handlers, which may run on
+/// the same core as the code they preempt.
+pub unsafe trait CoreId {
+    /// The id of the running core
+    ///
+    /// `CoreLocal`s with room for `N` cores panic if this is `N` or more
+    fn current() -> usize;
+}
+
+/// A value for each core, with room for `N` cores, keyed by `C`
+pub struct CoreLocal<T, C, const N: usize> {
+    slots: [OnceCell<T>; N],
+    core_id: PhantomData<fn() -> C>,
+}
+
+// only the core that owns a value accesses it through a shared reference
+unsafe impl<T: Send, C, const N: usize> Sync for CoreLocal<T, C, N> {}
+
+impl<T, C, const N: usize> Default for CoreLocal<T, C, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, C, const N: usize> locker::Init for CoreLocal<T, C, N> {
+    const INIT: Self = Self::new();
+}
+
+impl<T, C, const N: usize> CoreLocal<T, C, N> {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: OnceCell<T> = RawLock::once_cell();
+
+    /// Create a new `CoreLocal` without a value for any core
+    pub const fn new() -> Self {
+        Self {
+            slots: [Self::EMPTY; N],
+            core_id: PhantomData,
+        }
+    }
+
+    /// The number of cores that this can hold a value for
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            inner: self.slots.iter_mut(),
+        }
+    }
+}
+
+impl<T, C: CoreId, const N: usize> CoreLocal<T, C, N> {
+    #[inline]
+    fn slot(&self) -> &OnceCell<T> {
+        let core_id = C::current();
+
+        match self.slots.get(core_id) {
+            Some(slot) => slot,
+            None => panic!(
+                "core {} is out of range, there is only room for {} cores",
+                core_id, N
+            ),
+        }
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.slot().get()
+    }
+
+    pub fn get_or_insert_with<F: FnOnce() -> T>(&self, value: F) -> &T {
+        self.slot().get_or_init(value)
+    }
+
+    pub fn get_or_try_insert_with<F: FnOnce() -> Result<T, E>, E>(
+        &self,
+        value: F,
+    ) -> Result<&T, E> {
+        match self.slot().get_or_try_init(value) {
+            Ok(value) => Ok(value),
+            Err(TryInitError::Init(err)) => Err(err),
+            Err(TryInitError::Poisoned) => {
+                panic!("the value of this core was poisoned by a panic while it was created")
+            }
+        }
+    }
+
+    pub fn get_or_insert(&self, value: T) -> &T {
+        self.get_or_insert_with(move || value)
+    }
+}
+
+pub struct IterMut<'a, T> {
+    inner: core::slice::IterMut<'a, OnceCell<T>>,
+}
+
+pub struct IntoIter<T, const N: usize> {
+    inner: core::array::IntoIter<OnceCell<T>, N>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.find_map(OnceCell::get_mut)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.find_map(|mut slot| slot.reset())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+impl<'a, T, C, const N: usize> IntoIterator for &'a mut CoreLocal<T, C, N> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, C, const N: usize> IntoIterator for CoreLocal<T, C, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: IntoIterator::into_iter(self.slots),
+        }
+    }
+}
+
+/// A per-core static, the `no_std` counterpart of `LocalKey`
+///
+/// Each core sees its own value, which is created by `init` the first time the core
+/// accesses it. Unlike `LocalKey`, this can be created without a macro.
+pub struct CoreLocalKey<T, C, const N: usize, F = fn() -> T> {
+    inner: CoreLocal<T, C, N>,
+    init: F,
+}
+
+impl<T, C, const N: usize, F> CoreLocalKey<T, C, N, F> {
+    /// Create a new key, which creates the value of each core with `init`
+    pub const fn new(init: F) -> Self {
+        Self {
+            inner: CoreLocal::new(),
+            init,
+        }
+    }
+}
+
+impl<T, C: CoreId, const N: usize, F: Fn() -> T> CoreLocalKey<T, C, N, F> {
+    /// Acquire a reference to this core's value, creating it if this
+    /// core hasn't accessed it yet
+    #[inline]
+    pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+        f(self)
+    }
+}
+
+impl<T, C: CoreId, const N: usize, F: Fn() -> T> core::ops::Deref for CoreLocalKey<T, C, N, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner.get_or_insert_with(&self.init)
+    }
+}
+
+#[test]
+fn per_core() {
+    use std::cell::Cell;
+
+    std::thread_local! {
+        static CORE: Cell<usize> = const { Cell::new(0) };
+    }
+
+    struct Fake;
+
+    unsafe impl CoreId for Fake {
+        fn current() -> usize {
+            CORE.with(Cell::get)
+        }
+    }
+
+    let mut local = CoreLocal::<usize, Fake, 2>::new();
+
+    std::thread::scope(|s| {
+        for core in 0..2 {
+            let local = &local;
+            s.spawn(move || {
+                CORE.with(|c| c.set(core));
+                assert_eq!(*local.get_or_insert(core * 10), core * 10);
+                assert_eq!(local.get(), Some(&(core * 10)));
+            });
+        }
+    });
+
+    assert_eq!(local.iter_mut().count(), 2);
+    assert_eq!(local.get_or_try_insert_with(|| Err(())), Ok(&0));
+
+    let out_of_range = std::thread::spawn(move || {
+        CORE.with(|c| c.set(2));
+        local.get().is_none()
+    });
+    assert!(out_of_range.join().is_err());
+}
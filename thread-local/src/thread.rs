@@ -0,0 +1,579 @@
+use locker::cell::SharedCell;
+use locker::Init;
+use std::alloc::Layout;
+use std::collections::HashMap;
+use std::ptr::NonNull;
+use std::thread::ThreadId;
+
+type Lock = locker::rwlock::default::DefaultLock;
+type RwLock = locker::rwlock::raw::RwLock<Lock>;
+
+#[doc(hidden)]
+pub use std::boxed::Box;
+
+/// Declare thread-local statics
+///
+/// This accepts the same syntax as [`std::thread_local!`], so code can switch
+/// between the two by changing the import.
+///
+/// * `static NAME: T = expr;` creates the value of each thread by evaluating `expr`,
+///   and stores it inline, so accessing it doesn't allocate for each thread
+/// * `static NAME: T = const { expr };` evaluates `expr` at compile time, each thread
+///   still creates its value lazily on first access, but only by copying out that constant
+/// * `#[raw] static NAME: T = expr;` evaluates `expr` into a `Box<T>`, so `T` may be unsized
+///
+/// Attributes, including `#[cfg]`, are applied to each item separately, and the
+/// trailing semicolon of the last item is optional.
+#[macro_export]
+macro_rules! thread_local {
+    () => {};
+    (#[raw] $(#[$meta:meta])* $v:vis static $name:ident: $type:ty = $expr:expr $(; $($rest:tt)*)?) => {
+        $(#[$meta])*
+        $v static $name: $crate::LocalKey<$type, fn() -> $crate::Box<$type>> = unsafe { $crate::LocalKey::new(move || $expr) };
+
+        $($crate::thread_local! { $($rest)* })?
+    };
+    ($(#[$meta:meta])* $v:vis static $name:ident: $type:ty = const { $expr:expr } $(; $($rest:tt)*)?) => {
+        $(#[$meta])*
+        $v static $name: $crate::LocalKey<$type> = unsafe {
+            $crate::LocalKey::new($crate::Inline(|| {
+                #[allow(clippy::declare_interior_mutable_const)]
+                const INIT: $type = $expr;
+                INIT
+            }))
+        };
+
+        $($crate::thread_local! { $($rest)* })?
+    };
+    ($(#[$meta:meta])* $v:vis static $name:ident: $type:ty = $expr:expr $(; $($rest:tt)*)?) => {
+        $(#[$meta])*
+        $v static $name: $crate::LocalKey<$type> = unsafe { $crate::LocalKey::new($crate::Inline(move || $expr)) };
+
+        $($crate::thread_local! { $($rest)* })?
+    };
+}
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    #[raw]
+    pub static FOO: [u32] = vec![0; COUNT.fetch_add(1, Ordering::Relaxed)].into_boxed_slice();
+}
+
+pub fn get() -> &'static [u32] {
+    &*FOO
+}
+
+/// A thread-local static, declared by [`thread_local!`]
+///
+/// Each thread sees its own value, which is created the first time the thread accesses it.
+/// Values live until the key is dropped, so for statics they live as long as the process.
+///
+/// With an [`Inline`] initializer (the default) values are stored inline, otherwise the
+/// initializer returns a `Box<T>`, which allows unsized values.
+pub struct LocalKey<T: ?Sized, F = Inline<fn() -> T>> {
+    inner: ThreadLocal<T>,
+    init: F,
+}
+
+/// An initializer of a [`LocalKey`] which creates values that are stored inline
+#[doc(hidden)]
+pub struct Inline<F>(pub F);
+
+unsafe impl<T: ?Sized, F: Send> Send for LocalKey<T, F> {}
+unsafe impl<T: ?Sized, F: Sync> Sync for LocalKey<T, F> {}
+
+impl<T: ?Sized, F> LocalKey<T, F> {
+    #[doc(hidden)]
+    pub const unsafe fn new(init: F) -> Self {
+        Self {
+            inner: ThreadLocal::new(),
+            init,
+        }
+    }
+}
+
+impl<T: ?Sized, F> LocalKey<T, F>
+where
+    Self: std::ops::Deref<Target = T>,
+{
+    /// Acquire a reference to this thread's value, creating it if this
+    /// thread hasn't accessed it yet
+    #[inline]
+    pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+        f(self)
+    }
+
+    /// Acquire a reference to this thread's value, creating it if this
+    /// thread hasn't accessed it yet
+    ///
+    /// Values are never destroyed before the key, so this never fails,
+    /// it exists for parity with [`std::thread::LocalKey::try_with`]
+    #[inline]
+    pub fn try_with<R>(&'static self, f: impl FnOnce(&T) -> R) -> Result<R, AccessError> {
+        Ok(f(self))
+    }
+}
+
+/// The error returned by [`LocalKey::try_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessError {
+    _priv: (),
+}
+
+impl std::fmt::Display for AccessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("already destroyed")
+    }
+}
+
+impl std::error::Error for AccessError {}
+
+impl<T: ?Sized, F: Fn() -> Box<T>> std::ops::Deref for LocalKey<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner.get_or_insert_boxed_with(&self.init)
+    }
+}
+
+impl<T, F: Fn() -> T> std::ops::Deref for LocalKey<T, Inline<F>> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner.get_or_insert_with(&self.init.0)
+    }
+}
+
+pub struct ThreadLocal<T: ?Sized> {
+    lock: RwLock,
+    // created on the first insert, so that `new` can be `const`
+    inner: SharedCell<Option<Inner<T>>>,
+}
+
+struct Inner<T: ?Sized> {
+    map: HashMap<ThreadId, Slot<T>>,
+    // declared after `map`, so that the values are dropped before their chunks
+    chunks: Chunks,
+}
+
+impl<T: ?Sized> Default for Inner<T> {
+    fn default() -> Self {
+        Self {
+            map: HashMap::new(),
+            chunks: Chunks::default(),
+        }
+    }
+}
+
+/// A value owned by a `ThreadLocal`
+///
+/// The value is either a `Box` that was turned into a raw pointer, or it is stored inline
+/// in one of the `Chunks`. Either way, moving the slot around while the map grows doesn't
+/// invalidate the references that other threads hold to their values
+struct Slot<T: ?Sized> {
+    value: NonNull<T>,
+    // moves an inline value into a box, this is `None` if the value is already boxed
+    inline: Option<unsafe fn(NonNull<T>) -> Box<T>>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for Slot<T> {}
+unsafe impl<T: ?Sized + Sync> Sync for Slot<T> {}
+
+impl<T: ?Sized> Slot<T> {
+    fn boxed(value: Box<T>) -> Self {
+        Self {
+            value: NonNull::from(Box::leak(value)),
+            inline: None,
+        }
+    }
+
+    fn into_box(self) -> Box<T> {
+        let slot = std::mem::ManuallyDrop::new(self);
+
+        match slot.inline {
+            None => unsafe { Box::from_raw(slot.value.as_ptr()) },
+            Some(into_box) => unsafe { into_box(slot.value) },
+        }
+    }
+}
+
+impl<T> Slot<T> {
+    fn inline(value: T, chunks: &mut Chunks) -> Self {
+        unsafe fn into_box<T>(value: NonNull<T>) -> Box<T> {
+            Box::new(value.as_ptr().read())
+        }
+
+        let ptr = chunks.alloc::<T>();
+        unsafe { ptr.as_ptr().write(value) }
+
+        Self {
+            value: ptr,
+            inline: Some(into_box::<T>),
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for Slot<T> {
+    fn drop(&mut self) {
+        match self.inline {
+            None => drop(unsafe { Box::from_raw(self.value.as_ptr()) }),
+            Some(_) => unsafe { self.value.as_ptr().drop_in_place() },
+        }
+    }
+}
+
+/// The storage of inline values
+///
+/// Values are never removed one at a time, so this only hands out slots at the end of
+/// the last chunk, and each chunk is twice as big as the one before it. Chunks are never
+/// moved, so references to their values stay valid until the chunks are dropped.
+///
+/// Each value is padded to its own cache line, because the values of different threads
+/// are next to each other, and they shouldn't contend on the same cache line.
+#[derive(Default)]
+struct Chunks {
+    chunks: Vec<Chunk>,
+    // the number of unused slots at the end of the last chunk
+    free: usize,
+}
+
+struct Chunk {
+    ptr: NonNull<u8>,
+    layout: Layout,
+    capacity: usize,
+}
+
+// the slot of an inline value, 128 bytes because some platforms prefetch pairs of cache lines
+#[repr(C, align(128))]
+struct Padded<T>(T);
+
+// a chunk only owns memory, the values in it are owned by `Slot`s
+unsafe impl Send for Chunk {}
+unsafe impl Sync for Chunk {}
+
+impl Chunks {
+    const FIRST_CAPACITY: usize = 4;
+
+    /// Reserve an uninitialized slot for a `T`, all values in these chunks must be `T`s
+    fn alloc<T>(&mut self) -> NonNull<T> {
+        if std::mem::size_of::<T>() == 0 {
+            return NonNull::dangling();
+        }
+
+        if self.free == 0 {
+            let capacity = self
+                .chunks
+                .last()
+                .map_or(Self::FIRST_CAPACITY, |chunk| chunk.capacity * 2);
+            let layout =
+                Layout::array::<Padded<T>>(capacity).expect("too many thread local values");
+            let ptr = NonNull::new(unsafe { std::alloc::alloc(layout) })
+                .unwrap_or_else(|| std::alloc::handle_alloc_error(layout));
+
+            self.chunks.push(Chunk {
+                ptr,
+                layout,
+                capacity,
+            });
+            self.free = capacity;
+        }
+
+        let chunk = self.chunks.last().unwrap();
+        let index = chunk.capacity - self.free;
+        self.free -= 1;
+
+        // `Padded` is `repr(C)`, so the value is at the start of the slot
+        unsafe { NonNull::new_unchecked(chunk.ptr.as_ptr().cast::<Padded<T>>().add(index).cast()) }
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        unsafe { std::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+unsafe impl<T: Send> Sync for ThreadLocal<T> {}
+
+impl<T: ?Sized> Default for ThreadLocal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> Init for ThreadLocal<T> {
+    const INIT: Self = Self::new();
+}
+
+impl<T: ?Sized> ThreadLocal<T> {
+    pub const fn new() -> Self {
+        Self {
+            lock: Init::INIT,
+            inner: SharedCell::new(None),
+        }
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        let thread_id = std::thread::current().id();
+        let _lock = self.lock.read();
+        self.get_locked(&thread_id)
+    }
+
+    /// Like [`get_or_insert_with`](ThreadLocal::get_or_insert_with), but the value is
+    /// boxed, so `T` may be unsized
+    pub fn get_or_insert_boxed_with<F: FnOnce() -> V, V: Into<Box<T>>>(&self, value: F) -> &T {
+        match self.get_or_try_insert_boxed_with(|| Ok::<_, std::convert::Infallible>(value())) {
+            Ok(x) => x,
+            Err(x) => match x {},
+        }
+    }
+
+    /// Like [`get_or_try_insert_with`](ThreadLocal::get_or_try_insert_with), but the
+    /// value is boxed, so `T` may be unsized
+    pub fn get_or_try_insert_boxed_with<F: FnOnce() -> Result<V, E>, E, V: Into<Box<T>>>(
+        &self,
+        value: F,
+    ) -> Result<&T, E> {
+        let thread_id = std::thread::current().id();
+        let _lock = self.lock.read();
+
+        if let Some(item) = self.get_locked(&thread_id) {
+            return Ok(item);
+        }
+
+        self.try_insert(_lock, thread_id, value, |value, _| {
+            Slot::boxed(value.into())
+        })
+    }
+
+    /// Get the value of the given thread
+    ///
+    /// The caller must hold the lock
+    #[inline]
+    fn get_locked(&self, thread_id: &ThreadId) -> Option<&T> {
+        let slot = unsafe {
+            self.inner
+                .with(|inner| Some(inner.as_ref()?.map.get(thread_id)?.value))?
+        };
+        Some(unsafe { &*slot.as_ptr() })
+    }
+
+    #[cold]
+    fn try_insert<V, E>(
+        &self,
+        _lock: locker::share_lock::RawShareGuard<Lock>,
+        thread_id: ThreadId,
+        value: impl FnOnce() -> Result<V, E>,
+        into_slot: impl FnOnce(V, &mut Chunks) -> Slot<T>,
+    ) -> Result<&T, E> {
+        let _lock = _lock.upgrade();
+
+        let value = value()?;
+
+        unsafe {
+            self.inner.with_mut(|inner| {
+                let inner = inner.get_or_insert_with(Inner::default);
+                let slot = into_slot(value, &mut inner.chunks);
+                let item = slot.value;
+                inner.map.insert(thread_id, slot);
+
+                Ok(&*item.as_ptr())
+            })
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            inner: self
+                .inner
+                .get_mut()
+                .get_or_insert_with(Inner::default)
+                .map
+                .iter_mut(),
+        }
+    }
+}
+
+impl<T> ThreadLocal<T> {
+    /// Get the value of the current thread, creating it with `value` if this
+    /// thread doesn't have one yet
+    ///
+    /// The value is stored inline, so this doesn't allocate for each thread.
+    pub fn get_or_insert_with<F: FnOnce() -> T>(&self, value: F) -> &T {
+        match self.get_or_try_insert_with(|| Ok::<_, std::convert::Infallible>(value())) {
+            Ok(x) => x,
+            Err(x) => match x {},
+        }
+    }
+
+    /// Get the value of the current thread, trying to create it with `value` if this
+    /// thread doesn't have one yet
+    ///
+    /// The value is stored inline, so this doesn't allocate for each thread.
+    pub fn get_or_try_insert_with<F: FnOnce() -> Result<T, E>, E>(
+        &self,
+        value: F,
+    ) -> Result<&T, E> {
+        let thread_id = std::thread::current().id();
+        let _lock = self.lock.read();
+
+        if let Some(item) = self.get_locked(&thread_id) {
+            return Ok(item);
+        }
+
+        self.try_insert(_lock, thread_id, value, Slot::inline)
+    }
+
+    pub fn get_or_insert(&self, value: T) -> &T {
+        self.get_or_insert_with(move || value)
+    }
+}
+
+pub struct IterMut<'a, T: ?Sized> {
+    inner: std::collections::hash_map::IterMut<'a, ThreadId, Slot<T>>,
+}
+
+pub struct IntoIter<T: ?Sized> {
+    inner: std::collections::hash_map::IntoIter<ThreadId, Slot<T>>,
+    // declared after `inner`, so that the remaining values are dropped before their chunks
+    _chunks: Chunks,
+}
+
+impl<'a, T: ?Sized> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, item) = self.inner.next()?;
+        Some(unsafe { &mut *item.value.as_ptr() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T: ?Sized> Iterator for IntoIter<T> {
+    type Item = Box<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, item) = self.inner.next()?;
+        Some(item.into_box())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T: ?Sized> IntoIterator for &'a mut ThreadLocal<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<'a, T: ?Sized> IntoIterator for ThreadLocal<T> {
+    type Item = Box<T>;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let inner = self.inner.into_inner().unwrap_or_default();
+
+        IntoIter {
+            inner: inner.map.into_iter(),
+            _chunks: inner.chunks,
+        }
+    }
+}
+
+#[test]
+fn macro_forms() {
+    use std::cell::Cell;
+
+    thread_local! {
+        static CONST: Cell<u32> = const { Cell::new(1) };
+        #[cfg(any())]
+        static MISSING: u32 = missing();
+        #[raw]
+        static RAW: str = Box::from("raw");
+        pub(crate) static LAST: Vec<u32> = vec![COUNT.load(Ordering::Relaxed) as u32]
+    }
+
+    CONST.with(|value| value.set(value.get() + 1));
+    assert_eq!(CONST.try_with(Cell::get), Ok(2));
+
+    std::thread::spawn(|| assert_eq!(CONST.with(Cell::get), 1))
+        .join()
+        .unwrap();
+
+    assert_eq!(RAW.with(str::len), 3);
+    assert_eq!(LAST.with(Vec::len), 1);
+}
+
+#[test]
+fn inline_values() {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    struct Counted(usize, Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.1.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let local = ThreadLocal::<Counted>::new();
+    let first = local.get_or_insert(Counted(0, dropped.clone()));
+
+    // enough threads to fill a few chunks, which must not move the earlier values
+    std::thread::scope(|s| {
+        for i in 1..20 {
+            let (local, dropped) = (&local, &dropped);
+            s.spawn(move || {
+                let value = local.get_or_insert_with(|| Counted(i, dropped.clone()));
+                assert_eq!(value.0, i);
+                assert_eq!(local.get().unwrap().0, i);
+            });
+        }
+    });
+
+    assert_eq!(first.0, 0);
+    assert_eq!(local.get_or_try_insert_with(|| Err(())).unwrap().0, 0);
+
+    let mut values = local.into_iter();
+    let kept = values.next().unwrap();
+    drop(values.next());
+    assert_eq!(dropped.load(Ordering::Relaxed), 1);
+
+    // the values that weren't yielded are dropped with the iterator
+    drop(values);
+    assert_eq!(dropped.load(Ordering::Relaxed), 19);
+    drop(kept);
+    assert_eq!(dropped.load(Ordering::Relaxed), 20);
+
+    let mut units = ThreadLocal::<()>::new();
+    units.get_or_insert(());
+    assert_eq!(units.iter_mut().count(), 1);
+}
+
+#[test]
+fn padded_values() {
+    let local = ThreadLocal::<u8>::new();
+    let first = local.get_or_insert(0) as *const u8 as usize;
+    let second = std::thread::scope(|s| {
+        s.spawn(|| local.get_or_insert(1) as *const u8 as usize)
+            .join()
+            .unwrap()
+    });
+
+    // the values of different threads don't share a cache line
+    assert_eq!(first % 128, 0);
+    assert!(first.abs_diff(second) >= 128);
+}